@@ -172,6 +172,27 @@ impl ActivityIndicator {
     }
 
     fn content_to_render(&mut self, cx: &mut ViewContext<Self>) -> Content {
+        // Show the progress of loading large files.
+        let project = self.project.read(cx);
+        let mut large_file_loads = project.large_file_load_progress();
+        if let Some((project_path, progress)) = large_file_loads.next() {
+            let mut message = format!(
+                "Loading {} ({}%)",
+                project_path.path.display(),
+                (progress * 100.) as usize
+            );
+            let additional_load_count = large_file_loads.count();
+            if additional_load_count > 0 {
+                write!(&mut message, " + {} more", additional_load_count).unwrap();
+            }
+            message.push('…');
+            return Content {
+                icon: None,
+                message,
+                on_click: None,
+            };
+        }
+
         // Show any language server has pending activity.
         let mut pending_work = self.pending_language_server_work(cx);
         if let Some(PendingWork {
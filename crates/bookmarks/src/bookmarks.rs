@@ -0,0 +1,340 @@
+//! The project-wide bookmarks picker, and the modal to label the bookmark of the current line.
+//!
+//! Bookmarks themselves live in the editor, see [`editor::Bookmark`].
+use std::sync::Arc;
+
+use editor::{project_bookmarks, scroll::Autoscroll, Editor, ProjectBookmark};
+use fuzzy::{match_strings, StringMatch, StringMatchCandidate};
+use gpui::{
+    actions, div, AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, Render,
+    Subscription, Task, View, ViewContext, VisualContext, WeakView,
+};
+use picker::{Picker, PickerDelegate};
+use text::{Bias, Point};
+use theme::ActiveTheme;
+use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
+use util::ResultExt;
+use workspace::{ModalView, Workspace};
+
+actions!(bookmarks, [Toggle, LabelBookmark]);
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(BookmarksView::register).detach();
+    cx.observe_new_views(BookmarkLabel::register).detach();
+}
+
+pub struct BookmarksView {
+    picker: View<Picker<BookmarksViewDelegate>>,
+}
+
+impl BookmarksView {
+    fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+        workspace.register_action(|workspace, _: &Toggle, cx| {
+            let weak_workspace = cx.view().downgrade();
+            workspace.toggle_modal(cx, move |cx| BookmarksView::new(weak_workspace, cx));
+        });
+    }
+
+    fn new(workspace: WeakView<Workspace>, cx: &mut ViewContext<Self>) -> Self {
+        let delegate = BookmarksViewDelegate {
+            bookmarks_view: cx.view().downgrade(),
+            workspace: workspace.clone(),
+            bookmarks: Vec::new(),
+            candidates: Vec::new(),
+            matches: Vec::new(),
+            selected_index: 0,
+        };
+        let picker = cx.new_view(|cx| Picker::uniform_list(delegate, cx));
+
+        if let Some(workspace) = workspace.upgrade() {
+            let load = project_bookmarks(workspace.read(cx).project(), cx);
+            let picker = picker.downgrade();
+            cx.spawn(|_, mut cx| async move {
+                let bookmarks = load.await.log_err()?;
+                picker
+                    .update(&mut cx, |picker, cx| {
+                        picker.delegate.set_bookmarks(bookmarks);
+                        picker.refresh(cx);
+                    })
+                    .ok()
+            })
+            .detach();
+        }
+
+        Self { picker }
+    }
+}
+
+impl Render for BookmarksView {
+    fn render(&mut self, _cx: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex().w(rems(34.)).child(self.picker.clone())
+    }
+}
+
+impl FocusableView for BookmarksView {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<DismissEvent> for BookmarksView {}
+impl ModalView for BookmarksView {}
+
+pub struct BookmarksViewDelegate {
+    bookmarks_view: WeakView<BookmarksView>,
+    workspace: WeakView<Workspace>,
+    bookmarks: Vec<ProjectBookmark>,
+    candidates: Vec<StringMatchCandidate>,
+    matches: Vec<StringMatch>,
+    selected_index: usize,
+}
+
+impl BookmarksViewDelegate {
+    fn set_bookmarks(&mut self, bookmarks: Vec<ProjectBookmark>) {
+        self.candidates = bookmarks
+            .iter()
+            .enumerate()
+            .map(|(id, bookmark)| {
+                let location = format!(
+                    "{}:{}",
+                    bookmark.project_path.path.to_string_lossy(),
+                    bookmark.row + 1
+                );
+                let text = match &bookmark.label {
+                    Some(label) => format!("{label} {location}"),
+                    None => location,
+                };
+                StringMatchCandidate::new(id, text)
+            })
+            .collect();
+        self.bookmarks = bookmarks;
+    }
+}
+
+impl PickerDelegate for BookmarksViewDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self, _cx: &mut WindowContext) -> Arc<str> {
+        "Search bookmarks...".into()
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(&mut self, query: String, cx: &mut ViewContext<Picker<Self>>) -> Task<()> {
+        let background = cx.background_executor().clone();
+        let candidates = self.candidates.clone();
+        cx.spawn(|this, mut cx| async move {
+            let matches = if query.is_empty() {
+                candidates
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, candidate)| StringMatch {
+                        candidate_id: index,
+                        string: candidate.string,
+                        positions: Vec::new(),
+                        score: 0.0,
+                    })
+                    .collect()
+            } else {
+                match_strings(
+                    &candidates,
+                    &query,
+                    false,
+                    100,
+                    &Default::default(),
+                    background,
+                )
+                .await
+            };
+
+            this.update(&mut cx, |this, cx| {
+                let delegate = &mut this.delegate;
+                delegate.matches = matches;
+                delegate.selected_index = delegate
+                    .selected_index
+                    .min(delegate.matches.len().saturating_sub(1));
+                cx.notify();
+            })
+            .log_err();
+        })
+    }
+
+    fn confirm(&mut self, _: bool, cx: &mut ViewContext<Picker<Self>>) {
+        let Some(bookmark) = self
+            .matches
+            .get(self.selected_index)
+            .and_then(|mat| self.bookmarks.get(mat.candidate_id))
+        else {
+            return;
+        };
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+
+        let row = bookmark.row;
+        let open_task = workspace.update(cx, |workspace, cx| {
+            workspace.open_path(bookmark.project_path.clone(), None, true, cx)
+        });
+        cx.spawn(|_, mut cx| async move {
+            let item = open_task.await.log_err()?;
+            let editor = item.downcast::<Editor>()?;
+            editor
+                .update(&mut cx, |editor, cx| {
+                    let snapshot = editor.buffer().read(cx).snapshot(cx);
+                    let point = snapshot.clip_point(Point::new(row, 0), Bias::Left);
+                    editor.change_selections(Some(Autoscroll::center()), cx, |s| {
+                        s.select_ranges([point..point])
+                    });
+                })
+                .log_err()
+        })
+        .detach();
+        self.dismissed(cx);
+    }
+
+    fn dismissed(&mut self, cx: &mut ViewContext<Picker<Self>>) {
+        self.bookmarks_view
+            .update(cx, |_, cx| cx.emit(DismissEvent))
+            .log_err();
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _: &mut ViewContext<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let mat = &self.matches[ix];
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .selected(selected)
+                .start_slot(Icon::new(IconName::Bookmark).color(Color::Accent))
+                .child(HighlightedLabel::new(
+                    mat.string.clone(),
+                    mat.positions.clone(),
+                )),
+        )
+    }
+}
+
+/// A modal to label the bookmark of the line of the cursor, which bookmarks the line if needed.
+pub struct BookmarkLabel {
+    label_editor: View<Editor>,
+    active_editor: View<Editor>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl ModalView for BookmarkLabel {}
+
+impl FocusableView for BookmarkLabel {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.label_editor.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<DismissEvent> for BookmarkLabel {}
+
+impl BookmarkLabel {
+    fn register(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+        let handle = cx.view().downgrade();
+        editor.register_action(move |_: &LabelBookmark, cx| {
+            let Some(editor) = handle.upgrade() else {
+                return;
+            };
+            let Some(workspace) = editor.read(cx).workspace() else {
+                return;
+            };
+            workspace.update(cx, |workspace, cx| {
+                workspace.toggle_modal(cx, move |cx| BookmarkLabel::new(editor, cx));
+            })
+        });
+    }
+
+    fn new(active_editor: View<Editor>, cx: &mut ViewContext<Self>) -> Self {
+        let label = active_editor.read(cx).bookmark_label_at_cursor(cx);
+        let label_editor = cx.new_view(|cx| {
+            let mut editor = Editor::single_line(cx);
+            editor.set_placeholder_text("Bookmark label", cx);
+            if let Some(label) = label {
+                editor.set_text(label, cx);
+                editor.select_all(&editor::actions::SelectAll, cx);
+            }
+            editor
+        });
+        let label_editor_change = cx.subscribe(&label_editor, Self::on_label_editor_event);
+
+        Self {
+            label_editor,
+            active_editor,
+            _subscriptions: vec![label_editor_change],
+        }
+    }
+
+    fn on_label_editor_event(
+        &mut self,
+        _: View<Editor>,
+        event: &editor::EditorEvent,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if let editor::EditorEvent::Blurred = event {
+            cx.emit(DismissEvent);
+        }
+    }
+
+    fn cancel(&mut self, _: &menu::Cancel, cx: &mut ViewContext<Self>) {
+        cx.emit(DismissEvent);
+    }
+
+    fn confirm(&mut self, _: &menu::Confirm, cx: &mut ViewContext<Self>) {
+        let label = self.label_editor.read(cx).text(cx).trim().to_string();
+        self.active_editor.update(cx, |editor, cx| {
+            editor.set_bookmark_label(Some(label), cx);
+            editor.focus(cx);
+        });
+        cx.emit(DismissEvent);
+    }
+}
+
+impl Render for BookmarkLabel {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        div()
+            .elevation_2(cx)
+            .key_context("BookmarkLabel")
+            .on_action(cx.listener(Self::cancel))
+            .on_action(cx.listener(Self::confirm))
+            .w_96()
+            .child(
+                v_flex()
+                    .px_1()
+                    .pt_0p5()
+                    .gap_px()
+                    .child(
+                        v_flex()
+                            .py_0p5()
+                            .px_1()
+                            .child(div().px_1().py_0p5().child(self.label_editor.clone())),
+                    )
+                    .child(
+                        div()
+                            .h_px()
+                            .w_full()
+                            .bg(cx.theme().colors().element_background),
+                    )
+                    .child(h_flex().justify_between().px_2().py_1().child(
+                        Label::new("Label the bookmark of the current line").color(Color::Muted),
+                    )),
+            )
+    }
+}
@@ -479,6 +479,45 @@ impl Server {
             .add_request_handler(user_handler(
                 forward_read_only_project_request::<proto::InlayHints>,
             ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GetSemanticTokens>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GetCodeLens>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GetDocumentLinks>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::ResolveDocumentLink>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::PrepareCallHierarchy>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GetIncomingCalls>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GetOutgoingCalls>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::PrepareTypeHierarchy>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GetSupertypes>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GetSubtypes>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GetFoldingRanges>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GetSelectionRanges>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GetLinkedEditingRanges>,
+            ))
             .add_request_handler(user_handler(
                 forward_read_only_project_request::<proto::OpenBufferByPath>,
             ))
@@ -539,9 +578,37 @@ impl Server {
             .add_request_handler(user_handler(
                 forward_mutating_project_request::<proto::MultiLspQuery>,
             ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GitChangedFiles>,
+            ))
+            .add_request_handler(user_handler(
+                forward_mutating_project_request::<proto::GitStage>,
+            ))
+            .add_request_handler(user_handler(
+                forward_mutating_project_request::<proto::GitUnstage>,
+            ))
+            .add_request_handler(user_handler(
+                forward_mutating_project_request::<proto::GitSetIndexText>,
+            ))
+            .add_request_handler(user_handler(
+                forward_mutating_project_request::<proto::GitCommit>,
+            ))
+            .add_request_handler(user_handler(
+                forward_mutating_project_request::<proto::GitRemoteOperation>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GitRevisionText>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GitFileHistory>,
+            ))
             .add_message_handler(create_buffer_for_peer)
             .add_request_handler(update_buffer)
             .add_message_handler(broadcast_project_message_from_host::<proto::RefreshInlayHints>)
+            .add_message_handler(
+                broadcast_project_message_from_host::<proto::RefreshSemanticTokens>,
+            )
+            .add_message_handler(broadcast_project_message_from_host::<proto::RefreshCodeLens>)
             .add_message_handler(broadcast_project_message_from_host::<proto::UpdateBufferFile>)
             .add_message_handler(broadcast_project_message_from_host::<proto::BufferReloaded>)
             .add_message_handler(broadcast_project_message_from_host::<proto::BufferSaved>)
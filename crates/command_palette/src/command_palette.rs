@@ -180,35 +180,37 @@ impl CommandPaletteDelegate {
     ) {
         self.updating_matches.take();
 
-        let mut intercept_result = CommandPaletteInterceptor::try_global(cx)
-            .and_then(|interceptor| interceptor.intercept(&query, cx));
+        let mut intercept_results = CommandPaletteInterceptor::try_global(cx)
+            .map(|interceptor| interceptor.intercept(&query, cx))
+            .unwrap_or_default();
 
         if parse_zed_link(&query, cx).is_some() {
-            intercept_result = Some(CommandInterceptResult {
+            intercept_results = vec![CommandInterceptResult {
                 action: OpenZedUrl { url: query.clone() }.boxed_clone(),
                 string: query.clone(),
                 positions: vec![],
-            })
+            }];
         }
 
-        if let Some(CommandInterceptResult {
-            action,
-            string,
-            positions,
-        }) = intercept_result
-        {
+        for (ix, intercept_result) in intercept_results.into_iter().enumerate() {
+            let CommandInterceptResult {
+                action,
+                string,
+                positions,
+            } = intercept_result;
             if let Some(idx) = matches
                 .iter()
+                .skip(ix)
                 .position(|m| commands[m.candidate_id].action.type_id() == action.type_id())
             {
-                matches.remove(idx);
+                matches.remove(ix + idx);
             }
             commands.push(Command {
                 name: string.clone(),
                 action,
             });
             matches.insert(
-                0,
+                ix,
                 StringMatch {
                     candidate_id: commands.len() - 1,
                     string,
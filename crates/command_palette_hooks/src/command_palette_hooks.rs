@@ -93,7 +93,7 @@ pub struct CommandInterceptResult {
 /// An interceptor for the command palette.
 #[derive(Default)]
 pub struct CommandPaletteInterceptor(
-    Option<Box<dyn Fn(&str, &AppContext) -> Option<CommandInterceptResult>>>,
+    Option<Box<dyn Fn(&str, &AppContext) -> Vec<CommandInterceptResult>>>,
 );
 
 #[derive(Default)]
@@ -117,9 +117,11 @@ impl CommandPaletteInterceptor {
     }
 
     /// Intercepts the given query from the command palette.
-    pub fn intercept(&self, query: &str, cx: &AppContext) -> Option<CommandInterceptResult> {
+    ///
+    /// The results are listed in order above the matching commands of the palette.
+    pub fn intercept(&self, query: &str, cx: &AppContext) -> Vec<CommandInterceptResult> {
         let Some(handler) = self.0.as_ref() else {
-            return None;
+            return Vec::new();
         };
 
         (handler)(query, cx)
@@ -135,7 +137,7 @@ impl CommandPaletteInterceptor {
     /// This will override the previous interceptor, if it exists.
     pub fn set(
         &mut self,
-        handler: Box<dyn Fn(&str, &AppContext) -> Option<CommandInterceptResult>>,
+        handler: Box<dyn Fn(&str, &AppContext) -> Vec<CommandInterceptResult>>,
     ) {
         self.0 = Some(handler);
     }
@@ -1255,7 +1255,7 @@ mod tests {
             self.abs_path.clone()
         }
 
-        fn load(&self, _: &AppContext) -> Task<Result<String>> {
+        fn load(&self, _: &AppContext) -> Task<Result<language::LoadedFile>> {
             unimplemented!()
         }
 
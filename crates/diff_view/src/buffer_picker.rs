@@ -0,0 +1,188 @@
+use std::sync::Arc;
+
+use fuzzy::{match_strings, StringMatch, StringMatchCandidate};
+use gpui::{
+    AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, Model, ParentElement,
+    Render, SharedString, Styled, Task, View, ViewContext, VisualContext, WeakView,
+};
+use language::Buffer;
+use picker::{Picker, PickerDelegate};
+use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
+use util::ResultExt;
+use workspace::{ModalView, Workspace};
+
+use crate::{buffer_title, DiffView};
+
+/// Picks the open buffer to compare the active one with.
+pub(crate) struct BufferPicker {
+    picker: View<Picker<BufferPickerDelegate>>,
+}
+
+impl BufferPicker {
+    pub(crate) fn new(
+        workspace: WeakView<Workspace>,
+        buffer: Model<Buffer>,
+        other_buffers: Vec<Model<Buffer>>,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let mut candidates = Vec::new();
+        let mut buffers: Vec<Model<Buffer>> = Vec::new();
+        for other_buffer in other_buffers {
+            if buffers.contains(&other_buffer) {
+                continue;
+            }
+            let label = other_buffer
+                .read(cx)
+                .file()
+                .map(|file| file.full_path(cx).to_string_lossy().into_owned())
+                .unwrap_or_else(|| buffer_title(&other_buffer, cx).to_string());
+            candidates.push(StringMatchCandidate::new(buffers.len(), label));
+            buffers.push(other_buffer);
+        }
+        let delegate = BufferPickerDelegate {
+            buffer_picker: cx.view().downgrade(),
+            workspace,
+            buffer,
+            buffers,
+            candidates,
+            matches: Vec::new(),
+            selected_index: 0,
+        };
+        let picker = cx.new_view(|cx| Picker::uniform_list(delegate, cx));
+        Self { picker }
+    }
+}
+
+impl Render for BufferPicker {
+    fn render(&mut self, _cx: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex().w(rems(34.)).child(self.picker.clone())
+    }
+}
+
+impl FocusableView for BufferPicker {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<DismissEvent> for BufferPicker {}
+impl ModalView for BufferPicker {}
+
+pub(crate) struct BufferPickerDelegate {
+    buffer_picker: WeakView<BufferPicker>,
+    workspace: WeakView<Workspace>,
+    buffer: Model<Buffer>,
+    buffers: Vec<Model<Buffer>>,
+    candidates: Vec<StringMatchCandidate>,
+    matches: Vec<StringMatch>,
+    selected_index: usize,
+}
+
+impl PickerDelegate for BufferPickerDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self, _cx: &mut WindowContext) -> Arc<str> {
+        "Compare with an open file...".into()
+    }
+
+    fn no_matches_text(&self, _cx: &mut WindowContext) -> SharedString {
+        "No other open files".into()
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn confirm(&mut self, _: bool, cx: &mut ViewContext<Picker<Self>>) {
+        if let Some(mat) = self.matches.get(self.selected_index) {
+            // The picked file is the old side, like the base of a change.
+            let old_buffer = self.buffers[mat.candidate_id].clone();
+            let new_buffer = self.buffer.clone();
+            self.workspace
+                .update(cx, |workspace, cx| {
+                    let view = DiffView::new(
+                        old_buffer.clone(),
+                        buffer_title(&old_buffer, cx),
+                        new_buffer.clone(),
+                        buffer_title(&new_buffer, cx),
+                        workspace.project().clone(),
+                        cx,
+                    );
+                    workspace.add_item_to_active_pane(Box::new(view), None, cx);
+                })
+                .log_err();
+        }
+        self.dismissed(cx);
+    }
+
+    fn dismissed(&mut self, cx: &mut ViewContext<Picker<Self>>) {
+        self.buffer_picker
+            .update(cx, |_, cx| cx.emit(DismissEvent))
+            .log_err();
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(&mut self, query: String, cx: &mut ViewContext<Picker<Self>>) -> Task<()> {
+        let background = cx.background_executor().clone();
+        let candidates = self.candidates.clone();
+        cx.spawn(|this, mut cx| async move {
+            let matches = if query.is_empty() {
+                candidates
+                    .into_iter()
+                    .map(|candidate| StringMatch {
+                        candidate_id: candidate.id,
+                        string: candidate.string,
+                        positions: Vec::new(),
+                        score: 0.0,
+                    })
+                    .collect()
+            } else {
+                match_strings(
+                    &candidates,
+                    &query,
+                    false,
+                    100,
+                    &Default::default(),
+                    background,
+                )
+                .await
+            };
+
+            this.update(&mut cx, |this, cx| {
+                let delegate = &mut this.delegate;
+                delegate.matches = matches;
+                delegate.selected_index = delegate
+                    .selected_index
+                    .min(delegate.matches.len().saturating_sub(1));
+                cx.notify();
+            })
+            .log_err();
+        })
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _cx: &mut ViewContext<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let mat = &self.matches[ix];
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .selected(selected)
+                .child(HighlightedLabel::new(
+                    mat.string.clone(),
+                    mat.positions.clone(),
+                )),
+        )
+    }
+}
@@ -0,0 +1,756 @@
+mod buffer_picker;
+mod revision_prompt;
+
+use std::{
+    any::{Any, TypeId},
+    ops::Range,
+    time::Duration,
+};
+
+use anyhow::Result;
+use buffer_picker::BufferPicker;
+use collections::HashSet;
+use editor::{
+    display_map::{BlockDisposition, BlockId, BlockProperties, BlockStyle},
+    scroll::Autoscroll,
+    Anchor, Editor, EditorEvent, MultiBufferSnapshot, ToPoint,
+};
+use git::diff::{line_changes, LineChange};
+use gpui::{
+    actions, div, AnyElement, AnyView, AppContext, EventEmitter, FocusHandle, FocusableView, Hsla,
+    InteractiveElement, IntoElement, Model, ParentElement, Render, SharedString, Styled,
+    Subscription, Task, View, ViewContext, VisualContext, WindowContext,
+};
+use language::{language_settings::SoftWrap, Buffer, Point};
+use project::{Project, ProjectPath};
+use revision_prompt::RevisionPrompt;
+use ui::prelude::*;
+use util::ResultExt;
+use workspace::{
+    item::{Item, ItemEvent, ItemHandle as _, ItemNavHistory, TabContentParams},
+    notifications::NotifyTaskExt,
+    Workspace,
+};
+
+actions!(
+    diff_view,
+    [
+        CompareWithHead,
+        CompareWithRevision,
+        CompareWithClipboard,
+        CompareWithBuffer,
+        GoToNextChange,
+        GoToPreviousChange,
+    ]
+);
+
+/// Edits come in bursts while typing, the texts are compared again once they settle.
+const DIFF_DEBOUNCE: Duration = Duration::from_millis(50);
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(|workspace: &mut Workspace, _| {
+        workspace
+            .register_action(|workspace, _: &CompareWithHead, cx| {
+                if let Some(buffer) = active_buffer(workspace, cx) {
+                    compare_with_revision(workspace, buffer, "HEAD".to_string(), cx);
+                }
+            })
+            .register_action(|workspace, _: &CompareWithRevision, cx| {
+                if let Some(buffer) = active_buffer(workspace, cx) {
+                    let workspace_handle = cx.view().downgrade();
+                    workspace
+                        .toggle_modal(cx, |cx| RevisionPrompt::new(workspace_handle, buffer, cx));
+                }
+            })
+            .register_action(|workspace, _: &CompareWithClipboard, cx| {
+                let Some(buffer) = active_buffer(workspace, cx) else {
+                    return;
+                };
+                let Some(clipboard_text) = cx.read_from_clipboard().map(|item| item.text().clone())
+                else {
+                    return;
+                };
+                let old_buffer = text_buffer_like(clipboard_text, &buffer, cx);
+                let title = buffer_title(&buffer, cx);
+                let view = DiffView::new(
+                    old_buffer,
+                    "Clipboard".into(),
+                    buffer,
+                    title,
+                    workspace.project().clone(),
+                    cx,
+                );
+                workspace.add_item_to_active_pane(Box::new(view), None, cx);
+            })
+            .register_action(|workspace, _: &CompareWithBuffer, cx| {
+                if let Some(buffer) = active_buffer(workspace, cx) {
+                    let workspace_handle = cx.view().downgrade();
+                    let buffers = workspace
+                        .items_of_type::<Editor>(cx)
+                        .filter_map(|editor| editor.read(cx).buffer().read(cx).as_singleton())
+                        .filter(|other_buffer| other_buffer != &buffer)
+                        .collect::<Vec<_>>();
+                    workspace.toggle_modal(cx, |cx| {
+                        BufferPicker::new(workspace_handle, buffer, buffers, cx)
+                    });
+                }
+            });
+    })
+    .detach();
+}
+
+/// Returns the buffer of the active editor, when it edits a single one.
+fn active_buffer(workspace: &Workspace, cx: &AppContext) -> Option<Model<Buffer>> {
+    workspace
+        .active_item(cx)?
+        .act_as::<Editor>(cx)?
+        .read(cx)
+        .buffer()
+        .read(cx)
+        .as_singleton()
+}
+
+/// Opens the diff between the buffer's file at a revision of its repository and the buffer.
+pub fn compare_with_revision(
+    workspace: &mut Workspace,
+    buffer: Model<Buffer>,
+    revision: String,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let project = workspace.project().clone();
+    let revision_text = project
+        .read(cx)
+        .git_revision_text(&buffer, revision.clone(), cx);
+    cx.spawn(|workspace, mut cx| async move {
+        // The file did not exist at the revision, all of its lines were added since.
+        let revision_text = revision_text.await?.unwrap_or_default();
+        workspace.update(&mut cx, |workspace, cx| {
+            let old_buffer = text_buffer_like(revision_text, &buffer, cx);
+            let title = buffer_title(&buffer, cx);
+            let view = DiffView::new(
+                old_buffer,
+                format!("{title} ({revision})").into(),
+                buffer,
+                title,
+                project,
+                cx,
+            );
+            workspace.add_item_to_active_pane(Box::new(view), None, cx);
+        })
+    })
+    .detach_and_notify_err(cx);
+}
+
+/// Creates a buffer with the text, highlighted with the language of another buffer.
+pub fn text_buffer_like(
+    text: String,
+    buffer: &Model<Buffer>,
+    cx: &mut AppContext,
+) -> Model<Buffer> {
+    let language = buffer.read(cx).language().cloned();
+    cx.new_model(|cx| {
+        let buffer = Buffer::local(text, cx);
+        match language {
+            Some(language) => buffer.with_language(language, cx),
+            None => buffer,
+        }
+    })
+}
+
+/// Returns the name of the buffer's file.
+pub fn buffer_title(buffer: &Model<Buffer>, cx: &AppContext) -> SharedString {
+    buffer
+        .read(cx)
+        .file()
+        .map(|file| file.file_name(cx).to_string_lossy().into_owned().into())
+        .unwrap_or_else(|| "untitled".into())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Side {
+    Old,
+    New,
+}
+
+/// One of the two compared buffers, with its editor.
+struct DiffSide {
+    editor: View<Editor>,
+    buffer: Model<Buffer>,
+    title: SharedString,
+    /// The blocks, which take the place of the lines the other side has more of.
+    padding_blocks: HashSet<BlockId>,
+}
+
+/// The changes between two buffers, shown side by side with their unchanged lines aligned.
+pub struct DiffView {
+    project: Model<Project>,
+    old: DiffSide,
+    new: DiffSide,
+    /// The changed rows, in both editors. The buffers' own diff hunks are against the git index and the
+    /// buffers are shared with the other editors, so the changes between the two sides are kept here.
+    changes: Vec<(Range<Anchor>, Range<Anchor>)>,
+    update_diff_task: Task<()>,
+    _subscriptions: Vec<Subscription>,
+}
+
+enum DiffViewRowHighlight {}
+
+impl DiffView {
+    pub fn new(
+        old_buffer: Model<Buffer>,
+        old_title: SharedString,
+        new_buffer: Model<Buffer>,
+        new_title: SharedString,
+        project: Model<Project>,
+        cx: &mut WindowContext,
+    ) -> View<Self> {
+        cx.new_view(|cx| {
+            let old_editor = Self::create_editor(&old_buffer, &project, cx);
+            let new_editor = Self::create_editor(&new_buffer, &project, cx);
+            let subscriptions = vec![
+                cx.subscribe(&old_buffer, Self::on_buffer_event),
+                cx.subscribe(&new_buffer, Self::on_buffer_event),
+                cx.subscribe(&old_editor, |this, _, event: &EditorEvent, cx| {
+                    this.on_editor_event(Side::Old, event, cx)
+                }),
+                cx.subscribe(&new_editor, |this, _, event: &EditorEvent, cx| {
+                    this.on_editor_event(Side::New, event, cx)
+                }),
+            ];
+            let mut this = Self {
+                project,
+                old: DiffSide {
+                    editor: old_editor,
+                    buffer: old_buffer,
+                    title: old_title,
+                    padding_blocks: HashSet::default(),
+                },
+                new: DiffSide {
+                    editor: new_editor,
+                    buffer: new_buffer,
+                    title: new_title,
+                    padding_blocks: HashSet::default(),
+                },
+                changes: Vec::new(),
+                update_diff_task: Task::ready(()),
+                _subscriptions: subscriptions,
+            };
+            this.schedule_diff_update(Duration::ZERO, cx);
+            this
+        })
+    }
+
+    fn create_editor(
+        buffer: &Model<Buffer>,
+        project: &Model<Project>,
+        cx: &mut ViewContext<Self>,
+    ) -> View<Editor> {
+        // Only the buffers of the project are saved, the others are texts to compare with.
+        let is_project_buffer = buffer.read(cx).file().is_some();
+        cx.new_view(|cx| {
+            let mut editor = Editor::for_buffer(buffer.clone(), Some(project.clone()), cx);
+            // Wrapped lines would take a different amount of rows on each side.
+            editor.set_soft_wrap_mode(SoftWrap::None, cx);
+            editor.set_read_only(!is_project_buffer);
+            editor
+        })
+    }
+
+    fn side(&self, side: Side) -> &DiffSide {
+        match side {
+            Side::Old => &self.old,
+            Side::New => &self.new,
+        }
+    }
+
+    fn on_buffer_event(
+        &mut self,
+        _: Model<Buffer>,
+        event: &language::Event,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if let language::Event::Edited | language::Event::Reloaded = event {
+            self.schedule_diff_update(DIFF_DEBOUNCE, cx);
+        }
+    }
+
+    fn on_editor_event(&mut self, side: Side, event: &EditorEvent, cx: &mut ViewContext<Self>) {
+        if let EditorEvent::ScrollPositionChanged { .. } = event {
+            self.sync_scroll_position(side, cx);
+        }
+        if side == Side::New {
+            cx.emit(event.clone());
+        }
+    }
+
+    /// Scrolls the other editor to the same position, the padding keeps the unchanged lines aligned.
+    fn sync_scroll_position(&mut self, from: Side, cx: &mut ViewContext<Self>) {
+        let (source, target) = match from {
+            Side::Old => (&self.old.editor, &self.new.editor),
+            Side::New => (&self.new.editor, &self.old.editor),
+        };
+        let scroll_position = source.update(cx, |editor, cx| editor.scroll_position(cx));
+        target.update(cx, |editor, cx| {
+            if editor.scroll_position(cx) != scroll_position {
+                editor.set_scroll_position(scroll_position, cx);
+            }
+        });
+    }
+
+    fn schedule_diff_update(&mut self, delay: Duration, cx: &mut ViewContext<Self>) {
+        let old_snapshot = self.old.buffer.read(cx).snapshot();
+        let new_snapshot = self.new.buffer.read(cx).snapshot();
+        self.update_diff_task = cx.spawn(|this, mut cx| async move {
+            if !delay.is_zero() {
+                cx.background_executor().timer(delay).await;
+            }
+            let changes = cx
+                .background_executor()
+                .spawn(async move { line_changes(&old_snapshot.text(), &new_snapshot.text()) })
+                .await;
+            this.update(&mut cx, |this, cx| this.apply_changes(changes, cx))
+                .log_err();
+        });
+    }
+
+    fn apply_changes(&mut self, changes: Vec<LineChange>, cx: &mut ViewContext<Self>) {
+        let old_snapshot = self.old.buffer_snapshot(cx);
+        let new_snapshot = self.new.buffer_snapshot(cx);
+        self.changes = changes
+            .iter()
+            .map(|change| {
+                (
+                    rows_to_anchors(&old_snapshot, &change.old_rows),
+                    rows_to_anchors(&new_snapshot, &change.new_rows),
+                )
+            })
+            .collect();
+
+        let mut old_paddings = Vec::new();
+        let mut new_paddings = Vec::new();
+        for change in &changes {
+            let line_delta = change.line_delta();
+            if line_delta > 0 {
+                old_paddings.push((change.old_rows.end, line_delta as u32));
+            } else if line_delta < 0 {
+                new_paddings.push((change.new_rows.end, line_delta.unsigned_abs() as u32));
+            }
+        }
+
+        let padding_color = cx.theme().colors().editor_subheader_background;
+        let mut deleted_color = cx.theme().status().git().deleted;
+        deleted_color.fade_out(0.7);
+        let mut created_color = cx.theme().status().git().created;
+        created_color.fade_out(0.7);
+        let old_rows = changes
+            .iter()
+            .map(|change| change.old_rows.clone())
+            .collect::<Vec<_>>();
+        let new_rows = changes
+            .iter()
+            .map(|change| change.new_rows.clone())
+            .collect::<Vec<_>>();
+        self.old
+            .update_editor(&old_rows, deleted_color, &old_paddings, padding_color, cx);
+        self.new
+            .update_editor(&new_rows, created_color, &new_paddings, padding_color, cx);
+        self.sync_scroll_position(Side::New, cx);
+        cx.notify();
+    }
+
+    /// The side, which editor is focused, the new one by default.
+    fn focused_side(&self, cx: &WindowContext) -> Side {
+        if self.old.editor.read(cx).is_focused(cx) {
+            Side::Old
+        } else {
+            Side::New
+        }
+    }
+
+    fn go_to_next_change(&mut self, _: &GoToNextChange, cx: &mut ViewContext<Self>) {
+        self.go_to_change(true, cx);
+    }
+
+    fn go_to_previous_change(&mut self, _: &GoToPreviousChange, cx: &mut ViewContext<Self>) {
+        self.go_to_change(false, cx);
+    }
+
+    fn go_to_change(&mut self, forward: bool, cx: &mut ViewContext<Self>) {
+        let side = self.focused_side(cx);
+        let editor = self.side(side).editor.clone();
+        let snapshot = self.side(side).buffer_snapshot(cx);
+        let cursor_row = editor
+            .update(cx, |editor, cx| editor.selections.newest::<Point>(cx))
+            .head()
+            .row;
+        let change_start_row = |(old, new): &(Range<Anchor>, Range<Anchor>)| {
+            let range = match side {
+                Side::Old => old,
+                Side::New => new,
+            };
+            range.start.to_point(&snapshot).row
+        };
+        // Like the editor's hunk navigation, wraps around at the first and the last change.
+        let change = if forward {
+            self.changes
+                .iter()
+                .find(|change| change_start_row(*change) > cursor_row)
+                .or_else(|| self.changes.first())
+        } else {
+            self.changes
+                .iter()
+                .rev()
+                .find(|change| change_start_row(*change) < cursor_row)
+                .or_else(|| self.changes.last())
+        };
+        let Some((old_range, new_range)) = change.cloned() else {
+            return;
+        };
+
+        for (editor, position, is_focused) in [
+            (&self.old.editor, old_range.start, side == Side::Old),
+            (&self.new.editor, new_range.start, side == Side::New),
+        ] {
+            editor.update(cx, |editor, cx| {
+                let autoscroll = is_focused.then(Autoscroll::center);
+                editor.change_selections(autoscroll, cx, |selections| {
+                    selections.select_anchor_ranges([position..position]);
+                });
+            });
+        }
+    }
+}
+
+impl DiffSide {
+    fn buffer_snapshot(&self, cx: &AppContext) -> MultiBufferSnapshot {
+        self.editor.read(cx).buffer().read(cx).snapshot(cx)
+    }
+
+    /// Highlights the changed rows and replaces the padding blocks.
+    fn update_editor(
+        &mut self,
+        changed_rows: &[Range<u32>],
+        change_color: Hsla,
+        paddings: &[(u32, u32)],
+        padding_color: Hsla,
+        cx: &mut WindowContext,
+    ) {
+        let old_blocks = std::mem::take(&mut self.padding_blocks);
+        self.padding_blocks = self.editor.update(cx, |editor, cx| {
+            let snapshot = editor.buffer().read(cx).snapshot(cx);
+            editor.clear_row_highlights::<DiffViewRowHighlight>();
+            for rows in changed_rows.iter().filter(|rows| !rows.is_empty()) {
+                let range = rows_to_anchors(&snapshot, &(rows.start..rows.end - 1));
+                editor.highlight_rows::<DiffViewRowHighlight>(
+                    range.start..=range.end,
+                    Some(change_color),
+                    cx,
+                );
+            }
+
+            editor.remove_blocks(old_blocks, None, cx);
+            let max_row = snapshot.max_point().row;
+            let blocks = paddings.iter().flat_map(|&(row, lines)| {
+                // A padding at the end of the text goes below its last line.
+                let (position, disposition) = if row > max_row {
+                    (
+                        snapshot.anchor_after(snapshot.max_point()),
+                        BlockDisposition::Below,
+                    )
+                } else {
+                    (
+                        snapshot.anchor_before(Point::new(row, 0)),
+                        BlockDisposition::Above,
+                    )
+                };
+                // Blocks are at most `u8::MAX` lines high.
+                let heights = std::iter::repeat(u8::MAX)
+                    .take((lines / u8::MAX as u32) as usize)
+                    .chain(Some((lines % u8::MAX as u32) as u8).filter(|height| *height > 0));
+                heights.map(move |height| BlockProperties {
+                    position,
+                    height,
+                    style: BlockStyle::Flex,
+                    render: Box::new(move |_| {
+                        div().size_full().bg(padding_color).into_any_element()
+                    }),
+                    disposition,
+                })
+            });
+            editor.insert_blocks(blocks, None, cx).into_iter().collect()
+        });
+    }
+
+    fn render(&self, cx: &mut ViewContext<DiffView>) -> impl IntoElement {
+        v_flex()
+            .flex_1()
+            .h_full()
+            .min_w_0()
+            .child(
+                h_flex()
+                    .px_2()
+                    .h_8()
+                    .border_b_1()
+                    .border_color(cx.theme().colors().border)
+                    .child(Label::new(self.title.clone()).single_line()),
+            )
+            .child(div().flex_1().min_h_0().child(self.editor.clone()))
+    }
+}
+
+/// Converts rows to an anchor range, from the start of the first row to the start of the last one.
+fn rows_to_anchors(snapshot: &MultiBufferSnapshot, rows: &Range<u32>) -> Range<Anchor> {
+    let start = snapshot.clip_point(Point::new(rows.start, 0), editor::Bias::Left);
+    let end = snapshot.clip_point(Point::new(rows.end, 0), editor::Bias::Left);
+    snapshot.anchor_before(start)..snapshot.anchor_before(end)
+}
+
+impl EventEmitter<EditorEvent> for DiffView {}
+
+impl FocusableView for DiffView {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.new.editor.focus_handle(cx)
+    }
+}
+
+impl Render for DiffView {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        h_flex()
+            .key_context("DiffView")
+            .on_action(cx.listener(Self::go_to_next_change))
+            .on_action(cx.listener(Self::go_to_previous_change))
+            .size_full()
+            .bg(cx.theme().colors().editor_background)
+            .child(self.old.render(cx))
+            .child(div().w_px().h_full().bg(cx.theme().colors().border))
+            .child(self.new.render(cx))
+    }
+}
+
+impl Item for DiffView {
+    type Event = EditorEvent;
+
+    fn to_item_events(event: &EditorEvent, f: impl FnMut(ItemEvent)) {
+        Editor::to_item_events(event, f)
+    }
+
+    fn deactivated(&mut self, cx: &mut ViewContext<Self>) {
+        self.new
+            .editor
+            .update(cx, |editor, cx| editor.deactivated(cx));
+    }
+
+    fn navigate(&mut self, data: Box<dyn Any>, cx: &mut ViewContext<Self>) -> bool {
+        self.new
+            .editor
+            .update(cx, |editor, cx| editor.navigate(data, cx))
+    }
+
+    fn tab_tooltip_text(&self, _: &AppContext) -> Option<SharedString> {
+        Some(format!("{} ↔ {}", self.old.title, self.new.title).into())
+    }
+
+    fn tab_content(&self, params: TabContentParams, _: &WindowContext) -> AnyElement {
+        Label::new(format!("{} ↔ {}", self.old.title, self.new.title))
+            .single_line()
+            .color(if params.selected {
+                Color::Default
+            } else {
+                Color::Muted
+            })
+            .into_any_element()
+    }
+
+    fn telemetry_event_text(&self) -> Option<&'static str> {
+        Some("diff view")
+    }
+
+    fn for_each_project_item(
+        &self,
+        cx: &AppContext,
+        f: &mut dyn FnMut(gpui::EntityId, &dyn project::Item),
+    ) {
+        self.new.editor.for_each_project_item(cx, f)
+    }
+
+    fn is_singleton(&self, _: &AppContext) -> bool {
+        false
+    }
+
+    fn set_nav_history(&mut self, nav_history: ItemNavHistory, cx: &mut ViewContext<Self>) {
+        self.new.editor.update(cx, |editor, _| {
+            editor.set_nav_history(Some(nav_history));
+        });
+    }
+
+    fn clone_on_split(
+        &self,
+        _workspace_id: workspace::WorkspaceId,
+        cx: &mut ViewContext<Self>,
+    ) -> Option<View<Self>>
+    where
+        Self: Sized,
+    {
+        Some(DiffView::new(
+            self.old.buffer.clone(),
+            self.old.title.clone(),
+            self.new.buffer.clone(),
+            self.new.title.clone(),
+            self.project.clone(),
+            cx,
+        ))
+    }
+
+    fn is_dirty(&self, cx: &AppContext) -> bool {
+        self.new.buffer.read(cx).is_dirty()
+    }
+
+    fn has_conflict(&self, cx: &AppContext) -> bool {
+        self.new.buffer.read(cx).has_conflict()
+    }
+
+    fn can_save(&self, cx: &AppContext) -> bool {
+        self.new.buffer.read(cx).file().is_some()
+    }
+
+    fn save(
+        &mut self,
+        format: bool,
+        project: Model<Project>,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Result<()>> {
+        self.new
+            .editor
+            .update(cx, |editor, cx| editor.save(format, project, cx))
+    }
+
+    fn save_as(
+        &mut self,
+        _: Model<Project>,
+        _: ProjectPath,
+        _: &mut ViewContext<Self>,
+    ) -> Task<Result<()>> {
+        unreachable!()
+    }
+
+    fn reload(&mut self, project: Model<Project>, cx: &mut ViewContext<Self>) -> Task<Result<()>> {
+        self.new
+            .editor
+            .update(cx, |editor, cx| editor.reload(project, cx))
+    }
+
+    fn act_as_type<'a>(
+        &'a self,
+        type_id: TypeId,
+        self_handle: &'a View<Self>,
+        _: &'a AppContext,
+    ) -> Option<AnyView> {
+        if type_id == TypeId::of::<Self>() {
+            Some(self_handle.to_any())
+        } else if type_id == TypeId::of::<Editor>() {
+            Some(self.new.editor.to_any())
+        } else {
+            None
+        }
+    }
+
+    fn added_to_workspace(&mut self, workspace: &mut Workspace, cx: &mut ViewContext<Self>) {
+        for editor in [&self.old.editor, &self.new.editor] {
+            editor.update(cx, |editor, cx| editor.added_to_workspace(workspace, cx));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use editor::display_map::{DisplayRow, ToDisplayPoint};
+    use gpui::TestAppContext;
+    use project::FakeFs;
+    use serde_json::json;
+    use workspace::AppState;
+
+    #[gpui::test]
+    async fn test_diff_view_aligns_unchanged_lines(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree("/dir", json!({ "a.txt": "one\nthree\nfour\nfive\n" }))
+            .await;
+        let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
+        let buffer = project
+            .update(cx, |project, cx| {
+                project.open_local_buffer("/dir/a.txt", cx)
+            })
+            .await
+            .unwrap();
+
+        let view = workspace.update(cx, |workspace, cx| {
+            let old_buffer = text_buffer_like("one\ntwo\nthree\nfive\n".to_string(), &buffer, cx);
+            let view = DiffView::new(
+                old_buffer,
+                "Old".into(),
+                buffer.clone(),
+                "New".into(),
+                project.clone(),
+                cx,
+            );
+            workspace.add_item_to_active_pane(Box::new(view.clone()), None, cx);
+            view
+        });
+        cx.run_until_parked();
+
+        let display_row = |editor: &View<Editor>, row: u32, cx: &mut gpui::VisualTestContext| {
+            editor.update(cx, |editor, cx| {
+                let snapshot = editor.snapshot(cx);
+                snapshot
+                    .buffer_snapshot
+                    .anchor_before(Point::new(row, 0))
+                    .to_display_point(&snapshot)
+                    .row()
+            })
+        };
+        let (old_editor, new_editor) = view.update(cx, |view, _| {
+            (view.old.editor.clone(), view.new.editor.clone())
+        });
+        // "two" was removed and "four" added, so "five" is on the fifth display row of both sides.
+        assert_eq!(display_row(&old_editor, 3, cx), DisplayRow(4));
+        assert_eq!(display_row(&new_editor, 3, cx), DisplayRow(4));
+        assert_eq!(view.update(cx, |view, _| view.changes.len()), 2);
+
+        // Editing the new buffer compares the texts again.
+        buffer.update(cx, |buffer, cx| buffer.edit([(0..0, "zero\n")], None, cx));
+        cx.executor().advance_clock(DIFF_DEBOUNCE);
+        cx.run_until_parked();
+        assert_eq!(view.update(cx, |view, _| view.changes.len()), 3);
+        assert_eq!(display_row(&old_editor, 3, cx), DisplayRow(5));
+        assert_eq!(display_row(&new_editor, 4, cx), DisplayRow(5));
+
+        // "zero" was added, "two" removed before "three" and "four" added, the navigation wraps around.
+        let cursor_row = |cx: &mut gpui::VisualTestContext| {
+            new_editor.update(cx, |editor, cx| {
+                editor.selections.newest::<Point>(cx).head().row
+            })
+        };
+        for expected_row in [2, 3, 0] {
+            view.update(cx, |view, cx| view.go_to_next_change(&GoToNextChange, cx));
+            assert_eq!(cursor_row(cx), expected_row);
+        }
+        view.update(cx, |view, cx| {
+            view.go_to_previous_change(&GoToPreviousChange, cx)
+        });
+        assert_eq!(cursor_row(cx), 3);
+    }
+
+    fn init_test(cx: &mut TestAppContext) -> std::sync::Arc<AppState> {
+        cx.update(|cx| {
+            let state = AppState::test(cx);
+            language::init(cx);
+            editor::init(cx);
+            workspace::init_settings(cx);
+            Project::init_settings(cx);
+            crate::init(cx);
+            state
+        })
+    }
+}
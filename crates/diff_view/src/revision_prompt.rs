@@ -0,0 +1,104 @@
+use editor::Editor;
+use gpui::{
+    AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, Model, Render,
+    Subscription, View, ViewContext, VisualContext, WeakView,
+};
+use language::Buffer;
+use ui::prelude::*;
+use workspace::{ModalView, Workspace};
+
+use crate::compare_with_revision;
+
+/// Asks for the revision to compare the buffer's file with.
+pub(crate) struct RevisionPrompt {
+    workspace: WeakView<Workspace>,
+    buffer: Model<Buffer>,
+    revision_editor: View<Editor>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl ModalView for RevisionPrompt {}
+
+impl EventEmitter<DismissEvent> for RevisionPrompt {}
+
+impl FocusableView for RevisionPrompt {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.revision_editor.focus_handle(cx)
+    }
+}
+
+impl RevisionPrompt {
+    pub(crate) fn new(
+        workspace: WeakView<Workspace>,
+        buffer: Model<Buffer>,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let revision_editor = cx.new_view(|cx| {
+            let mut editor = Editor::single_line(cx);
+            editor.set_placeholder_text("HEAD~1", cx);
+            editor
+        });
+        let revision_editor_subscription =
+            cx.subscribe(&revision_editor, |_, _, event: &editor::EditorEvent, cx| {
+                if let editor::EditorEvent::Blurred = event {
+                    cx.emit(DismissEvent);
+                }
+            });
+        Self {
+            workspace,
+            buffer,
+            revision_editor,
+            _subscriptions: vec![revision_editor_subscription],
+        }
+    }
+
+    fn cancel(&mut self, _: &menu::Cancel, cx: &mut ViewContext<Self>) {
+        cx.emit(DismissEvent);
+    }
+
+    fn confirm(&mut self, _: &menu::Confirm, cx: &mut ViewContext<Self>) {
+        let revision = self.revision_editor.read(cx).text(cx).trim().to_string();
+        if revision.is_empty() {
+            return;
+        }
+        let buffer = self.buffer.clone();
+        self.workspace
+            .update(cx, |workspace, cx| {
+                compare_with_revision(workspace, buffer, revision, cx);
+            })
+            .ok();
+        cx.emit(DismissEvent);
+    }
+}
+
+impl Render for RevisionPrompt {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        div()
+            .elevation_2(cx)
+            .key_context("RevisionPrompt")
+            .on_action(cx.listener(Self::cancel))
+            .on_action(cx.listener(Self::confirm))
+            .w_96()
+            .child(
+                v_flex()
+                    .px_1()
+                    .pt_0p5()
+                    .gap_px()
+                    .child(
+                        v_flex()
+                            .py_0p5()
+                            .px_1()
+                            .child(div().px_1().py_0p5().child(self.revision_editor.clone())),
+                    )
+                    .child(
+                        div()
+                            .h_px()
+                            .w_full()
+                            .bg(cx.theme().colors().element_background),
+                    )
+                    .child(h_flex().px_2().py_1().child(
+                        Label::new("Compare with a branch, a tag or a commit").color(Color::Muted),
+                    )),
+            )
+    }
+}
@@ -191,6 +191,8 @@ gpui::actions!(
         GoToHunk,
         GoToImplementation,
         GoToImplementationSplit,
+        GoToNextBookmark,
+        GoToPrevBookmark,
         GoToPrevDiagnostic,
         GoToPrevHunk,
         GoToTypeDefinition,
@@ -265,13 +267,16 @@ gpui::actions!(
         SortLinesCaseInsensitive,
         SortLinesCaseSensitive,
         SplitSelectionIntoLines,
+        StageSelectedHunks,
         Tab,
         TabPrev,
+        ToggleBookmark,
         ToggleGitBlame,
         ToggleGitBlameInline,
         ToggleHunkDiff,
         ToggleInlayHints,
         ToggleLineNumbers,
+        ToggleReadOnly,
         ToggleSoftWrap,
         Transpose,
         Undo,
@@ -279,5 +284,6 @@ gpui::actions!(
         UnfoldLines,
         UniqueLinesCaseInsensitive,
         UniqueLinesCaseSensitive,
+        UnstageSelectedHunks,
     ]
 );
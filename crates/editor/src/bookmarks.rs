@@ -0,0 +1,354 @@
+//! Line bookmarks, which can be toggled on any line of a file and optionally labeled.
+//!
+//! The bookmarks of local files are kept in the project's [`BookmarkStore`] as anchors of their buffers, so they follow
+//! the edits, and all the editors of a file read from and write to the store, rendering them in the gutter.
+//! Their rows are persisted in the editor database, keyed by the path of their worktree, whenever they change and
+//! after the edits of the buffer, to get restored whenever the file is opened again.
+use std::{path::PathBuf, time::Duration};
+
+use anyhow::Result;
+use gpui::{AppContext, Model, Task};
+use language::{Buffer, Point, ToPoint as _};
+use project::{Bookmark, BookmarkStore, BookmarkStoreEvent, Project, ProjectPath};
+use ui::{prelude::*, Tooltip};
+use util::ResultExt;
+
+use crate::{
+    display_map::DisplayRow, persistence::DB, scroll::Autoscroll, Direction, Editor, EditorMode,
+    GoToNextBookmark, GoToPrevBookmark, ToggleBookmark,
+};
+
+const BOOKMARKS_SAVE_DEBOUNCE: Duration = Duration::from_secs(1);
+
+/// A bookmark of a project file, as persisted in the database.
+#[derive(Clone, Debug)]
+pub struct ProjectBookmark {
+    pub project_path: ProjectPath,
+    pub row: u32,
+    pub label: Option<String>,
+}
+
+/// The local file of an editor, whose bookmarks are kept in the project's store.
+struct BookmarksFile {
+    store: Model<BookmarkStore>,
+    project_path: ProjectPath,
+    buffer: Model<Buffer>,
+    /// The path of the file's worktree, under which the bookmarks are persisted.
+    worktree_path: PathBuf,
+}
+
+/// Returns the persisted bookmarks of all local visible worktrees of the project.
+pub fn project_bookmarks(
+    project: &Model<Project>,
+    cx: &AppContext,
+) -> Task<Result<Vec<ProjectBookmark>>> {
+    let worktrees = project
+        .read(cx)
+        .visible_worktrees(cx)
+        .filter(|worktree| worktree.read(cx).is_local())
+        .map(|worktree| {
+            let worktree = worktree.read(cx);
+            (worktree.id(), worktree.abs_path().to_path_buf())
+        })
+        .collect::<Vec<_>>();
+    cx.background_executor().spawn(async move {
+        let mut bookmarks = Vec::new();
+        for (worktree_id, worktree_path) in worktrees {
+            for (path, row, label) in DB.get_worktree_bookmarks(worktree_path)? {
+                bookmarks.push(ProjectBookmark {
+                    project_path: ProjectPath {
+                        worktree_id,
+                        path: path.into(),
+                    },
+                    row,
+                    label,
+                });
+            }
+        }
+        Ok(bookmarks)
+    })
+}
+
+impl Editor {
+    /// Returns the sorted, deduplicated rows of the file's bookmarks, along with their labels.
+    pub fn bookmark_rows(&self, cx: &AppContext) -> Vec<(u32, Option<String>)> {
+        let Some(file) = self.bookmarks_file(cx) else {
+            return Vec::new();
+        };
+        let Some(bookmarks) = file
+            .store
+            .read(cx)
+            .bookmarks(&file.project_path, &file.buffer)
+        else {
+            return Vec::new();
+        };
+        let buffer = file.buffer.read(cx);
+        let mut rows = bookmarks
+            .iter()
+            .map(|bookmark| (bookmark.anchor.to_point(buffer).row, bookmark.label.clone()))
+            .collect::<Vec<_>>();
+        // Keep the labeled bookmark when edits moved several of them onto the same row.
+        rows.sort_by(|(row_a, label_a), (row_b, label_b)| {
+            row_a
+                .cmp(row_b)
+                .then(label_b.is_some().cmp(&label_a.is_some()))
+        });
+        rows.dedup_by_key(|(row, _)| *row);
+        rows
+    }
+
+    pub(crate) fn has_bookmarks(&self, cx: &AppContext) -> bool {
+        self.bookmarks_file(cx).map_or(false, |file| {
+            file.store
+                .read(cx)
+                .bookmarks(&file.project_path, &file.buffer)
+                .map_or(false, |bookmarks| !bookmarks.is_empty())
+        })
+    }
+
+    pub fn toggle_bookmark(&mut self, _: &ToggleBookmark, cx: &mut ViewContext<Self>) {
+        let mut rows = self
+            .selections
+            .all::<Point>(cx)
+            .into_iter()
+            .map(|selection| selection.head().row)
+            .collect::<Vec<_>>();
+        rows.dedup();
+        self.update_bookmarks(cx, |bookmarks, buffer| {
+            for row in rows {
+                let len = bookmarks.len();
+                bookmarks.retain(|bookmark| bookmark.anchor.to_point(buffer).row != row);
+                if bookmarks.len() == len {
+                    bookmarks.push(Bookmark {
+                        anchor: buffer.anchor_after(Point::new(row, 0)),
+                        label: None,
+                    });
+                }
+            }
+        });
+    }
+
+    /// Returns the label of the bookmark on the line of the newest cursor, if any.
+    pub fn bookmark_label_at_cursor(&self, cx: &AppContext) -> Option<String> {
+        let row = self.selections.newest::<Point>(cx).head().row;
+        self.bookmark_rows(cx)
+            .into_iter()
+            .find(|(bookmark_row, _)| *bookmark_row == row)
+            .and_then(|(_, label)| label)
+    }
+
+    /// Labels the bookmark on the line of the newest cursor, bookmarking the line first if needed.
+    pub fn set_bookmark_label(&mut self, label: Option<String>, cx: &mut ViewContext<Self>) {
+        let row = self.selections.newest::<Point>(cx).head().row;
+        self.update_bookmarks(cx, |bookmarks, buffer| {
+            bookmarks.retain(|bookmark| bookmark.anchor.to_point(buffer).row != row);
+            bookmarks.push(Bookmark {
+                anchor: buffer.anchor_after(Point::new(row, 0)),
+                label: label.filter(|label| !label.is_empty()),
+            });
+        });
+    }
+
+    pub fn go_to_next_bookmark(&mut self, _: &GoToNextBookmark, cx: &mut ViewContext<Self>) {
+        self.go_to_bookmark(Direction::Next, cx);
+    }
+
+    pub fn go_to_prev_bookmark(&mut self, _: &GoToPrevBookmark, cx: &mut ViewContext<Self>) {
+        self.go_to_bookmark(Direction::Prev, cx);
+    }
+
+    fn go_to_bookmark(&mut self, direction: Direction, cx: &mut ViewContext<Self>) {
+        let rows = self
+            .bookmark_rows(cx)
+            .into_iter()
+            .map(|(row, _)| row)
+            .collect::<Vec<_>>();
+        let cursor_row = self.selections.newest::<Point>(cx).head().row;
+        let target_row = match direction {
+            Direction::Next => rows
+                .iter()
+                .find(|row| **row > cursor_row)
+                .or_else(|| rows.first()),
+            Direction::Prev => rows
+                .iter()
+                .rev()
+                .find(|row| **row < cursor_row)
+                .or_else(|| rows.last()),
+        };
+        if let Some(row) = target_row {
+            let point = Point::new(*row, 0);
+            self.change_selections(Some(Autoscroll::center()), cx, |s| {
+                s.select_ranges([point..point])
+            });
+        }
+    }
+
+    pub(crate) fn render_bookmark_indicator(
+        &self,
+        display_row: DisplayRow,
+        row: u32,
+        label: Option<String>,
+        cx: &mut ViewContext<Self>,
+    ) -> IconButton {
+        IconButton::new(
+            ("bookmark_indicator", display_row.0 as usize),
+            IconName::Bookmark,
+        )
+        .icon_size(IconSize::XSmall)
+        .size(ui::ButtonSize::None)
+        .icon_color(Color::Accent)
+        .when_some(label, |button, label| {
+            button.tooltip(move |cx| Tooltip::text(label.clone(), cx))
+        })
+        .on_click(cx.listener(move |editor, _e, cx| {
+            editor.update_bookmarks(cx, |bookmarks, buffer| {
+                bookmarks.retain(|bookmark| bookmark.anchor.to_point(buffer).row != row);
+            });
+        }))
+    }
+
+    fn bookmarks_file(&self, cx: &AppContext) -> Option<BookmarksFile> {
+        if self.mode != EditorMode::Full {
+            return None;
+        }
+        let store = self.project.as_ref()?.read(cx).bookmark_store().clone();
+        let buffer = self.buffer.read(cx).as_singleton()?;
+        let file = project::File::from_dyn(buffer.read(cx).file())?;
+        if !file.is_local {
+            return None;
+        }
+        let worktree = file.worktree.read(cx);
+        Some(BookmarksFile {
+            store,
+            project_path: ProjectPath {
+                worktree_id: worktree.id(),
+                path: file.path.clone(),
+            },
+            worktree_path: worktree.abs_path().to_path_buf(),
+            buffer,
+        })
+    }
+
+    /// Subscribes to the bookmarks of the file in the project's store, loading them from the database
+    /// for the first editor of the file.
+    pub(crate) fn load_bookmarks(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(file) = self.bookmarks_file(cx) else {
+            return;
+        };
+
+        self._subscriptions
+            .push(cx.subscribe(&file.store, |editor, _, event, cx| {
+                let BookmarkStoreEvent::BookmarksChanged(path) = event;
+                let is_edited_file = editor
+                    .bookmarks_file(cx)
+                    .map_or(false, |file| file.project_path == *path);
+                if is_edited_file {
+                    cx.notify();
+                }
+            }));
+        if file
+            .store
+            .read(cx)
+            .bookmarks(&file.project_path, &file.buffer)
+            .is_some()
+        {
+            return;
+        }
+
+        let BookmarksFile {
+            store,
+            project_path,
+            buffer,
+            worktree_path,
+        } = file;
+        let path = project_path.path.to_path_buf();
+        let load = cx
+            .background_executor()
+            .spawn(async move { DB.get_bookmarks(worktree_path, path) });
+        cx.spawn(|_, mut cx| async move {
+            let rows = load.await.log_err()?;
+            store
+                .update(&mut cx, |store, cx| {
+                    // Another editor of the file may have loaded them meanwhile.
+                    if store.bookmarks(&project_path, &buffer).is_some() {
+                        return;
+                    }
+                    let snapshot = buffer.read(cx).snapshot();
+                    let max_row = snapshot.max_point().row;
+                    let bookmarks = rows
+                        .into_iter()
+                        .filter(|(row, _)| *row <= max_row)
+                        .map(|(row, label)| Bookmark {
+                            anchor: snapshot.anchor_after(Point::new(row, 0)),
+                            label,
+                        })
+                        .collect();
+                    store.set_bookmarks(project_path, &buffer, bookmarks, cx);
+                })
+                .ok()
+        })
+        .detach();
+    }
+
+    /// Changes the bookmarks of the file in the project's store, and persists them.
+    fn update_bookmarks(
+        &mut self,
+        cx: &mut ViewContext<Self>,
+        update: impl FnOnce(&mut Vec<Bookmark>, &Buffer),
+    ) {
+        let Some(file) = self.bookmarks_file(cx) else {
+            return;
+        };
+        let mut bookmarks = file
+            .store
+            .read(cx)
+            .bookmarks(&file.project_path, &file.buffer)
+            .map(<[Bookmark]>::to_vec)
+            .unwrap_or_default();
+        update(&mut bookmarks, file.buffer.read(cx));
+        file.store.update(cx, |store, cx| {
+            store.set_bookmarks(file.project_path, &file.buffer, bookmarks, cx)
+        });
+        self.save_bookmarks(cx);
+    }
+
+    /// Persists the rows of the file's bookmarks once the edits of the buffer settle,
+    /// so that they are restored on the lines they moved to.
+    pub(crate) fn schedule_bookmarks_save(&mut self, cx: &mut ViewContext<Self>) {
+        if !self.has_bookmarks(cx) {
+            return;
+        }
+        self.save_bookmarks_task = Some(cx.spawn(|editor, mut cx| async move {
+            cx.background_executor()
+                .timer(BOOKMARKS_SAVE_DEBOUNCE)
+                .await;
+            editor
+                .update(&mut cx, |editor, cx| editor.save_bookmarks(cx))
+                .ok();
+        }));
+    }
+
+    /// Persists the rows of the file's bookmarks, once they were loaded from the database.
+    pub(crate) fn save_bookmarks(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(file) = self.bookmarks_file(cx) else {
+            return;
+        };
+        if file
+            .store
+            .read(cx)
+            .bookmarks(&file.project_path, &file.buffer)
+            .is_none()
+        {
+            return;
+        }
+
+        let rows = self.bookmark_rows(cx);
+        let worktree_path = file.worktree_path;
+        let path = file.project_path.path.to_path_buf();
+        cx.background_executor()
+            .spawn(async move {
+                DB.save_bookmarks(worktree_path, path, rows).await.log_err();
+            })
+            .detach();
+    }
+}
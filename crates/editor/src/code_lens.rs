@@ -0,0 +1,279 @@
+//! Stores code lenses received from LSP <a href="https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_codeLens">textDocument/codeLens</a> requests
+//! and renders them as blocks above the lines they refer to.
+//!
+//! Lenses are cached per buffer by an [`LspBufferCache`], so that only edited buffers are re-queried.
+//! Clicking a lens executes its command on the language server that produced it.
+use std::time::Duration;
+
+use collections::{HashMap, HashSet};
+use gpui::{AppContext, Model, ModelContext, Task, ViewContext, WeakView};
+use language::{language_settings::language_settings, Buffer, Point, ToPoint as _};
+use project::{CodeLens, Project};
+use ui::prelude::*;
+use workspace::notifications::DetachAndPromptErr;
+
+use crate::{
+    display_map::{
+        BlockContext, BlockDisposition, BlockId, BlockProperties, BlockStyle, RenderBlock,
+    },
+    lsp_buffer_cache::{LspBufferCache, LspBufferQuery, LspRefreshReason},
+    Anchor, Editor,
+};
+
+#[derive(Default)]
+pub(super) struct CodeLensCache {
+    lenses: LspBufferCache<Vec<CodeLens>>,
+    blocks: Vec<CodeLensBlock>,
+}
+
+/// A block, rendering the lenses of a single line.
+struct CodeLensBlock {
+    id: BlockId,
+    position: Anchor,
+    lenses: Vec<CodeLens>,
+}
+
+struct CodeLensQuery;
+
+impl LspBufferQuery for CodeLensQuery {
+    type Result = Vec<CodeLens>;
+
+    const DEBOUNCE: Duration = Duration::from_millis(250);
+
+    fn cache(editor: &Editor) -> &LspBufferCache<Vec<CodeLens>> {
+        &editor.code_lens_cache.lenses
+    }
+
+    fn cache_mut(editor: &mut Editor) -> &mut LspBufferCache<Vec<CodeLens>> {
+        &mut editor.code_lens_cache.lenses
+    }
+
+    fn enabled(buffer: &Model<Buffer>, cx: &AppContext) -> bool {
+        let buffer = buffer.read(cx);
+        language_settings(buffer.language(), buffer.file(), cx).code_lens
+    }
+
+    fn query(
+        project: &mut Project,
+        buffer: Model<Buffer>,
+        _: Option<&Vec<CodeLens>>,
+        cx: &mut ModelContext<Project>,
+    ) -> Task<anyhow::Result<Vec<CodeLens>>> {
+        project.code_lens(buffer, cx)
+    }
+
+    fn results_changed(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+        editor.update_code_lens_blocks(cx);
+    }
+}
+
+impl Editor {
+    pub(super) fn refresh_code_lens(
+        &mut self,
+        reason: LspRefreshReason,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.refresh_lsp_buffer_cache::<CodeLensQuery>(reason, cx);
+    }
+
+    /// Updates the code lens blocks to match the cached lenses.
+    /// Lenses starting on the same line share a single block.
+    ///
+    /// Blocks are matched with the existing ones by their anchors, and only the changed ones are replaced,
+    /// so that refreshing the lenses does not make the unchanged blocks flicker.
+    pub(super) fn update_code_lens_blocks(&mut self, cx: &mut ViewContext<Self>) {
+        let multi_buffer = self.buffer.read(cx);
+        let multi_buffer_snapshot = multi_buffer.snapshot(cx);
+        let editor = cx.view().downgrade();
+        let mut old_blocks = std::mem::take(&mut self.code_lens_cache.blocks)
+            .into_iter()
+            .map(Some)
+            .collect::<Vec<_>>();
+        let mut blocks = Vec::new();
+        let mut blocks_to_insert = Vec::new();
+        let mut blocks_to_replace = HashMap::default();
+        for (excerpt_id, buffer_snapshot, excerpt_range) in multi_buffer_snapshot.excerpts() {
+            let buffer_id = buffer_snapshot.remote_id();
+            let Some(lenses) = self.code_lens_cache.lenses.get(&buffer_id) else {
+                continue;
+            };
+            let Some(buffer) = multi_buffer.buffer(buffer_id) else {
+                continue;
+            };
+
+            let excerpt_rows = excerpt_range.context.start.to_point(buffer_snapshot).row
+                ..=excerpt_range.context.end.to_point(buffer_snapshot).row;
+            let mut lenses_by_row = Vec::<(u32, Vec<CodeLens>)>::new();
+            for lens in lenses {
+                if lens.lsp_lens.command.is_none() {
+                    continue;
+                }
+                let row = lens.range.start.to_point(buffer_snapshot).row;
+                if !excerpt_rows.contains(&row) {
+                    continue;
+                }
+                match lenses_by_row
+                    .iter_mut()
+                    .find(|(lens_row, _)| *lens_row == row)
+                {
+                    Some((_, row_lenses)) => row_lenses.push(lens.clone()),
+                    None => lenses_by_row.push((row, vec![lens.clone()])),
+                }
+            }
+
+            for (row, lenses) in lenses_by_row {
+                let indent = buffer_snapshot.indent_size_for_line(row).len;
+                let Some(position) = multi_buffer_snapshot.anchor_in_excerpt(
+                    excerpt_id,
+                    buffer_snapshot.anchor_before(Point::new(row, indent)),
+                ) else {
+                    continue;
+                };
+
+                let old_block = old_blocks.iter_mut().find(|old_block| {
+                    old_block.as_ref().map_or(false, |old_block| {
+                        old_block
+                            .position
+                            .cmp(&position, &multi_buffer_snapshot)
+                            .is_eq()
+                    })
+                });
+                match old_block.and_then(Option::take) {
+                    Some(old_block) if same_commands(&old_block.lenses, &lenses) => {
+                        blocks.push(old_block);
+                    }
+                    Some(old_block) => {
+                        blocks_to_replace.insert(
+                            old_block.id,
+                            render_code_lens_block(editor.clone(), buffer.clone(), lenses.clone()),
+                        );
+                        blocks.push(CodeLensBlock {
+                            id: old_block.id,
+                            position: old_block.position,
+                            lenses,
+                        });
+                    }
+                    None => blocks_to_insert.push((
+                        BlockProperties {
+                            position,
+                            height: 1,
+                            style: BlockStyle::Sticky,
+                            render: render_code_lens_block(
+                                editor.clone(),
+                                buffer.clone(),
+                                lenses.clone(),
+                            ),
+                            disposition: BlockDisposition::Above,
+                        },
+                        lenses,
+                    )),
+                }
+            }
+        }
+
+        let blocks_to_remove = old_blocks
+            .into_iter()
+            .flatten()
+            .map(|old_block| old_block.id)
+            .collect::<HashSet<_>>();
+        if !blocks_to_remove.is_empty() {
+            self.remove_blocks(blocks_to_remove, None, cx);
+        }
+        if !blocks_to_replace.is_empty() {
+            self.replace_blocks(blocks_to_replace, None, cx);
+        }
+        if !blocks_to_insert.is_empty() {
+            let (properties, lenses): (Vec<_>, Vec<_>) = blocks_to_insert.into_iter().unzip();
+            let positions = properties
+                .iter()
+                .map(|properties| properties.position)
+                .collect::<Vec<_>>();
+            let ids = self.insert_blocks(properties, None, cx);
+            blocks.extend(ids.into_iter().zip(positions).zip(lenses).map(
+                |((id, position), lenses)| CodeLensBlock {
+                    id,
+                    position,
+                    lenses,
+                },
+            ));
+        }
+        self.code_lens_cache.blocks = blocks;
+    }
+
+    fn execute_code_lens(
+        &mut self,
+        buffer: Model<Buffer>,
+        lens: CodeLens,
+        cx: &mut ViewContext<Self>,
+    ) -> Option<Task<anyhow::Result<()>>> {
+        let workspace = self.workspace()?;
+        let project = self.project.clone()?;
+        let title = lens
+            .lsp_lens
+            .command
+            .as_ref()
+            .map(|command| command.title.clone())
+            .unwrap_or_default();
+        let execute_lens = project.update(cx, |project, cx| {
+            project.execute_code_lens(buffer, lens, cx)
+        });
+        let workspace = workspace.downgrade();
+        Some(cx.spawn(|editor, cx| async move {
+            let project_transaction = execute_lens.await?;
+            Self::open_project_transaction(&editor, workspace, project_transaction, title, cx).await
+        }))
+    }
+}
+
+/// Whether the blocks of the lenses would look and act the same.
+fn same_commands(old_lenses: &[CodeLens], new_lenses: &[CodeLens]) -> bool {
+    old_lenses.len() == new_lenses.len()
+        && old_lenses
+            .iter()
+            .zip(new_lenses)
+            .all(|(old_lens, new_lens)| {
+                old_lens.server_id == new_lens.server_id
+                    && old_lens.lsp_lens.command == new_lens.lsp_lens.command
+            })
+}
+
+fn render_code_lens_block(
+    editor: WeakView<Editor>,
+    buffer: Model<Buffer>,
+    lenses: Vec<CodeLens>,
+) -> RenderBlock {
+    Box::new(move |cx: &mut BlockContext| {
+        h_flex()
+            .id(("code-lens-block", cx.block_id))
+            .pl(cx.anchor_x)
+            .gap_1()
+            .children(lenses.iter().enumerate().filter_map(|(ix, lens)| {
+                let title = lens.lsp_lens.command.as_ref()?.title.clone();
+                let editor = editor.clone();
+                let buffer = buffer.clone();
+                let lens = lens.clone();
+                Some(
+                    Button::new(("code-lens", ix), title)
+                        .style(ButtonStyle::Transparent)
+                        .label_size(LabelSize::XSmall)
+                        .color(Color::Muted)
+                        .on_click(move |_, cx| {
+                            let task = editor
+                                .update(cx, |editor, cx| {
+                                    editor.execute_code_lens(buffer.clone(), lens.clone(), cx)
+                                })
+                                .ok()
+                                .flatten();
+                            if let Some(task) = task {
+                                task.detach_and_prompt_err(
+                                    "Failed to run code lens",
+                                    cx,
+                                    |_, _| None,
+                                );
+                            }
+                        }),
+                )
+            }))
+            .into_any_element()
+    })
+}
@@ -28,7 +28,10 @@ use crate::{EditorStyle, RowExt};
 pub use block_map::{BlockMap, BlockPoint};
 use collections::{HashMap, HashSet};
 use fold_map::FoldMap;
-use gpui::{Font, HighlightStyle, Hsla, LineLayout, Model, ModelContext, Pixels, UnderlineStyle};
+use gpui::{
+    Font, HighlightStyle, Hsla, LineLayout, Model, ModelContext, Pixels, SharedString,
+    UnderlineStyle,
+};
 use inlay_map::InlayMap;
 use language::{
     language_settings::language_settings, OffsetUtf16, Point, Subscription as BufferSubscription,
@@ -42,6 +45,7 @@ use serde::Deserialize;
 use std::{any::TypeId, borrow::Cow, fmt::Debug, num::NonZeroU32, ops::Range, sync::Arc};
 use sum_tree::{Bias, TreeMap};
 use tab_map::TabMap;
+use text::BufferId;
 
 use wrap_map::WrapMap;
 
@@ -69,6 +73,16 @@ pub trait ToDisplayPoint {
 
 type TextHighlights = TreeMap<Option<TypeId>, Arc<(HighlightStyle, Vec<Range<Anchor>>)>>;
 type InlayHighlights = TreeMap<TypeId, TreeMap<InlayId, (HighlightStyle, InlayHighlight)>>;
+type SemanticTokenHighlights = Arc<[(Range<Anchor>, HighlightStyle)]>;
+type FoldingRanges = Arc<HashMap<BufferId, Arc<[FoldingRange]>>>;
+
+/// A fold region provided by a language server, spanning whole buffer lines.
+#[derive(Clone, Debug)]
+pub struct FoldingRange {
+    pub range: Range<language::Anchor>,
+    /// The text to display instead of the folded lines, an ellipsis when absent.
+    pub placeholder: Option<SharedString>,
+}
 
 /// Decides how text in a [`MultiBuffer`] should be displayed in a buffer, handling inlay hints,
 /// folding, hard tabs, soft wrapping, custom blocks (like diagnostics), and highlighting.
@@ -92,6 +106,11 @@ pub struct DisplayMap {
     text_highlights: TextHighlights,
     /// Regions of inlays that should be highlighted.
     inlay_highlights: InlayHighlights,
+    /// Styles of the language server's semantic tokens, layered under the other text highlights.
+    semantic_token_highlights: SemanticTokenHighlights,
+    /// Fold regions of the buffers whose language servers provide them, sorted by their start.
+    /// Folds of other buffers are derived from indentation.
+    folding_ranges: FoldingRanges,
     pub clip_at_line_ends: bool,
 }
 
@@ -124,6 +143,8 @@ impl DisplayMap {
             block_map,
             text_highlights: Default::default(),
             inlay_highlights: Default::default(),
+            semantic_token_highlights: Arc::new([]),
+            folding_ranges: Default::default(),
             clip_at_line_ends: false,
         }
     }
@@ -149,15 +170,22 @@ impl DisplayMap {
             block_snapshot,
             text_highlights: self.text_highlights.clone(),
             inlay_highlights: self.inlay_highlights.clone(),
+            semantic_token_highlights: self.semantic_token_highlights.clone(),
+            folding_ranges: self.folding_ranges.clone(),
             clip_at_line_ends: self.clip_at_line_ends,
         }
     }
 
     pub fn set_state(&mut self, other: &DisplaySnapshot, cx: &mut ModelContext<Self>) {
-        self.fold(
+        self.fold_with_placeholders(
             other
                 .folds_in_range(0..other.buffer_snapshot.len())
-                .map(|fold| fold.range.to_offset(&other.buffer_snapshot)),
+                .map(|fold| {
+                    (
+                        fold.range.to_offset(&other.buffer_snapshot),
+                        fold.placeholder.clone(),
+                    )
+                }),
             cx,
         );
     }
@@ -166,6 +194,15 @@ impl DisplayMap {
         &mut self,
         ranges: impl IntoIterator<Item = Range<T>>,
         cx: &mut ModelContext<Self>,
+    ) {
+        self.fold_with_placeholders(ranges.into_iter().map(|range| (range, None)), cx)
+    }
+
+    /// Folds the given ranges, displaying each of them as its placeholder text, if any, instead of an ellipsis.
+    pub fn fold_with_placeholders<T: ToOffset>(
+        &mut self,
+        ranges: impl IntoIterator<Item = (Range<T>, Option<SharedString>)>,
+        cx: &mut ModelContext<Self>,
     ) {
         let snapshot = self.buffer.read(cx).snapshot(cx);
         let edits = self.buffer_subscription.consume().into_inner();
@@ -177,7 +214,7 @@ impl DisplayMap {
             .wrap_map
             .update(cx, |map, cx| map.sync(snapshot, edits, cx));
         self.block_map.read(snapshot, edits);
-        let (snapshot, edits) = fold_map.fold(ranges);
+        let (snapshot, edits) = fold_map.fold_with_placeholders(ranges);
         let (snapshot, edits) = self.tab_map.sync(snapshot, edits, tab_size);
         let (snapshot, edits) = self
             .wrap_map
@@ -274,6 +311,19 @@ impl DisplayMap {
         }
     }
 
+    /// Replaces all semantic token highlights; `highlights` must be sorted and non-overlapping.
+    pub(crate) fn set_semantic_token_highlights(
+        &mut self,
+        highlights: Vec<(Range<Anchor>, HighlightStyle)>,
+    ) {
+        self.semantic_token_highlights = highlights.into();
+    }
+
+    /// Replaces the language server fold regions; each buffer's ranges must be sorted by their start.
+    pub(crate) fn set_folding_ranges(&mut self, ranges: HashMap<BufferId, Arc<[FoldingRange]>>) {
+        self.folding_ranges = Arc::new(ranges);
+    }
+
     pub fn text_highlights(&self, type_id: TypeId) -> Option<(HighlightStyle, &[Range<Anchor>])> {
         let highlights = self.text_highlights.get(&Some(type_id))?;
         Some((highlights.0, &highlights.1))
@@ -349,6 +399,7 @@ impl DisplayMap {
 pub(crate) struct Highlights<'a> {
     pub text_highlights: Option<&'a TextHighlights>,
     pub inlay_highlights: Option<&'a InlayHighlights>,
+    pub semantic_token_highlights: Option<&'a SemanticTokenHighlights>,
     pub styles: HighlightStyles,
 }
 
@@ -374,6 +425,8 @@ pub struct DisplaySnapshot {
     block_snapshot: block_map::BlockSnapshot,
     text_highlights: TextHighlights,
     inlay_highlights: InlayHighlights,
+    semantic_token_highlights: SemanticTokenHighlights,
+    folding_ranges: FoldingRanges,
     clip_at_line_ends: bool,
 }
 
@@ -554,6 +607,7 @@ impl DisplaySnapshot {
             Highlights {
                 text_highlights: Some(&self.text_highlights),
                 inlay_highlights: Some(&self.inlay_highlights),
+                semantic_token_highlights: Some(&self.semantic_token_highlights),
                 styles: highlight_styles,
             },
         )
@@ -844,6 +898,10 @@ impl DisplaySnapshot {
     }
 
     pub fn is_foldable(&self, buffer_row: MultiBufferRow) -> bool {
+        if let Some(lsp_range) = self.lsp_foldable_range(buffer_row) {
+            return lsp_range.is_some();
+        }
+
         let max_row = self.buffer_snapshot.max_buffer_row();
         if buffer_row >= max_row {
             return false;
@@ -867,7 +925,15 @@ impl DisplaySnapshot {
         false
     }
 
-    pub fn foldable_range(&self, buffer_row: MultiBufferRow) -> Option<Range<Point>> {
+    /// Returns the range to fold for the given row, along with the text to display in its place.
+    pub fn foldable_range(
+        &self,
+        buffer_row: MultiBufferRow,
+    ) -> Option<(Range<Point>, Option<SharedString>)> {
+        if let Some(lsp_range) = self.lsp_foldable_range(buffer_row) {
+            return lsp_range.filter(|_| !self.is_line_folded(buffer_row));
+        }
+
         let start = MultiBufferPoint::new(buffer_row.0, self.buffer_snapshot.line_len(buffer_row));
         if self.is_foldable(MultiBufferRow(start.row))
             && !self.is_line_folded(MultiBufferRow(start.row))
@@ -888,12 +954,50 @@ impl DisplaySnapshot {
                 }
             }
             let end = end.unwrap_or(max_point);
-            Some(start..end)
+            Some((start..end, None))
         } else {
             None
         }
     }
 
+    /// Returns the outermost language server fold region starting at the given row, if it spans multiple lines.
+    /// Returns `None` when no fold regions are known for the row's buffer, so that folds are derived from indentation.
+    fn lsp_foldable_range(
+        &self,
+        buffer_row: MultiBufferRow,
+    ) -> Option<Option<(Range<Point>, Option<SharedString>)>> {
+        let (buffer, line) = self.buffer_snapshot.buffer_line_for_row(buffer_row)?;
+        let folding_ranges = self.folding_ranges.get(&buffer.remote_id())?;
+
+        let row = line.start.row;
+        let row_of = |anchor: &language::Anchor| buffer.summary_for_anchor::<Point>(anchor).row;
+        let start_ix = folding_ranges
+            .partition_point(|folding_range| row_of(&folding_range.range.start) < row);
+        let Some((end_row, placeholder)) = folding_ranges[start_ix..]
+            .iter()
+            .take_while(|folding_range| row_of(&folding_range.range.start) == row)
+            .map(|folding_range| (row_of(&folding_range.range.end), &folding_range.placeholder))
+            .filter(|(end_row, _)| *end_row > row)
+            .max_by_key(|(end_row, _)| *end_row)
+        else {
+            return Some(None);
+        };
+
+        // Fold regions extending past the end of the excerpt cannot be folded.
+        let end = MultiBufferRow(buffer_row.0 + end_row - row);
+        if end > self.buffer_snapshot.max_buffer_row() {
+            return Some(None);
+        }
+        let (end_buffer, end_line) = self.buffer_snapshot.buffer_line_for_row(end)?;
+        if end_buffer.remote_id() != buffer.remote_id() || end_line.start.row != end_row {
+            return Some(None);
+        }
+
+        let start = Point::new(buffer_row.0, self.buffer_snapshot.line_len(buffer_row));
+        let end = Point::new(end.0, self.buffer_snapshot.line_len(end));
+        Some(Some((start..end, placeholder.clone())))
+    }
+
     #[cfg(any(test, feature = "test-support"))]
     pub fn text_highlight_ranges<Tag: ?Sized + 'static>(
         &self,
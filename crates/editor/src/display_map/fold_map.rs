@@ -2,7 +2,7 @@ use super::{
     inlay_map::{InlayBufferRows, InlayChunks, InlayEdit, InlayOffset, InlayPoint, InlaySnapshot},
     Highlights,
 };
-use gpui::{ElementId, HighlightStyle, Hsla};
+use gpui::{ElementId, HighlightStyle, Hsla, SharedString};
 use language::{Chunk, Edit, Point, TextSummary};
 use multi_buffer::{Anchor, AnchorRangeExt, MultiBufferRow, MultiBufferSnapshot, ToOffset};
 use std::{
@@ -13,6 +13,8 @@ use std::{
 use sum_tree::{Bias, Cursor, FilterCursor, SumTree};
 use util::post_inc;
 
+const DEFAULT_PLACEHOLDER: &str = "⋯";
+
 #[derive(Copy, Clone, Debug, Default, Eq, Ord, PartialOrd, PartialEq)]
 pub struct FoldPoint(pub Point);
 
@@ -76,11 +78,19 @@ impl<'a> FoldMapWriter<'a> {
     pub(crate) fn fold<T: ToOffset>(
         &mut self,
         ranges: impl IntoIterator<Item = Range<T>>,
+    ) -> (FoldSnapshot, Vec<FoldEdit>) {
+        self.fold_with_placeholders(ranges.into_iter().map(|range| (range, None)))
+    }
+
+    /// Folds the given ranges, displaying each of them as its placeholder text, if any, instead of an ellipsis.
+    pub(crate) fn fold_with_placeholders<T: ToOffset>(
+        &mut self,
+        ranges: impl IntoIterator<Item = (Range<T>, Option<SharedString>)>,
     ) -> (FoldSnapshot, Vec<FoldEdit>) {
         let mut edits = Vec::new();
         let mut folds = Vec::new();
         let snapshot = self.0.snapshot.inlay_snapshot.clone();
-        for range in ranges.into_iter() {
+        for (range, placeholder) in ranges.into_iter() {
             let buffer = &snapshot.buffer;
             let range = range.start.to_offset(&buffer)..range.end.to_offset(&buffer);
 
@@ -99,6 +109,7 @@ impl<'a> FoldMapWriter<'a> {
             folds.push(Fold {
                 id: FoldId(post_inc(&mut self.0.next_fold_id.0)),
                 range: fold_range,
+                placeholder,
             });
 
             let inlay_range =
@@ -327,8 +338,11 @@ impl FoldMap {
                         let item = folds_cursor.item().map(|f| {
                             let buffer_start = f.range.start.to_offset(&inlay_snapshot.buffer);
                             let buffer_end = f.range.end.to_offset(&inlay_snapshot.buffer);
-                            inlay_snapshot.to_inlay_offset(buffer_start)
-                                ..inlay_snapshot.to_inlay_offset(buffer_end)
+                            (
+                                inlay_snapshot.to_inlay_offset(buffer_start)
+                                    ..inlay_snapshot.to_inlay_offset(buffer_end),
+                                f.placeholder.clone(),
+                            )
                         });
                         folds_cursor.next(&inlay_snapshot.buffer);
                         item
@@ -336,19 +350,24 @@ impl FoldMap {
                 })
                 .peekable();
 
-                while folds.peek().map_or(false, |fold| fold.start < edit.new.end) {
-                    let mut fold = folds.next().unwrap();
+                while folds
+                    .peek()
+                    .map_or(false, |(fold, _)| fold.start < edit.new.end)
+                {
+                    let (mut fold, mut placeholder) = folds.next().unwrap();
                     let sum = new_transforms.summary();
 
                     assert!(fold.start.0 >= sum.input.len);
 
                     while folds
                         .peek()
-                        .map_or(false, |next_fold| next_fold.start <= fold.end)
+                        .map_or(false, |(next_fold, _)| next_fold.start <= fold.end)
                     {
-                        let next_fold = folds.next().unwrap();
+                        let (next_fold, _) = folds.next().unwrap();
                         if next_fold.end > fold.end {
                             fold.end = next_fold.end;
+                            // The placeholder only describes the text of a single fold.
+                            placeholder = None;
                         }
                     }
 
@@ -368,11 +387,12 @@ impl FoldMap {
                     }
 
                     if fold.end > fold.start {
-                        let output_text = "⋯";
+                        let output_text =
+                            placeholder.unwrap_or_else(|| SharedString::from(DEFAULT_PLACEHOLDER));
                         new_transforms.push(
                             Transform {
                                 summary: TransformSummary {
-                                    output: TextSummary::from(output_text),
+                                    output: TextSummary::from(output_text.as_ref()),
                                     input: inlay_snapshot
                                         .text_summary_for_range(fold.start..fold.end),
                                 },
@@ -503,7 +523,7 @@ impl FoldSnapshot {
         if let Some(transform) = cursor.item() {
             let start_in_transform = range.start.0 - cursor.start().0 .0;
             let end_in_transform = cmp::min(range.end, cursor.end(&()).0).0 - cursor.start().0 .0;
-            if let Some(output_text) = transform.output_text {
+            if let Some(output_text) = &transform.output_text {
                 summary = TextSummary::from(
                     &output_text
                         [start_in_transform.column as usize..end_in_transform.column as usize],
@@ -528,7 +548,7 @@ impl FoldSnapshot {
                 .output;
             if let Some(transform) = cursor.item() {
                 let end_in_transform = range.end.0 - cursor.start().0 .0;
-                if let Some(output_text) = transform.output_text {
+                if let Some(output_text) = &transform.output_text {
                     summary += TextSummary::from(&output_text[..end_in_transform.column as usize]);
                 } else {
                     let inlay_start = self.inlay_snapshot.to_offset(cursor.start().1);
@@ -808,7 +828,7 @@ fn consolidate_fold_edits(edits: &mut Vec<FoldEdit>) {
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 struct Transform {
     summary: TransformSummary,
-    output_text: Option<&'static str>,
+    output_text: Option<SharedString>,
 }
 
 impl Transform {
@@ -853,6 +873,8 @@ impl Into<ElementId> for FoldId {
 pub struct Fold {
     pub id: FoldId,
     pub range: FoldRange,
+    /// The text displayed in place of the folded range, an ellipsis when absent.
+    pub placeholder: Option<SharedString>,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -1013,7 +1035,7 @@ impl<'a> Iterator for FoldChunks<'a> {
 
         // If we're in a fold, then return the fold's display text and
         // advance the transform and buffer cursors to the end of the fold.
-        if let Some(output_text) = transform.output_text {
+        if let Some(output_text) = &transform.output_text {
             self.inlay_chunk.take();
             self.inlay_offset += InlayOffset(transform.summary.input.len);
             self.inlay_chunks.seek(self.inlay_offset);
@@ -1026,7 +1048,7 @@ impl<'a> Iterator for FoldChunks<'a> {
 
             self.output_offset += output_text.len();
             return Some(Chunk {
-                text: output_text,
+                text: output_text.as_ref(),
                 highlight_style: self.ellipses_color.map(|color| HighlightStyle {
                     color: Some(color),
                     ..Default::default()
@@ -1041,6 +1041,13 @@ impl InlaySnapshot {
                 cursor.seek(&range.start, Bias::Right, &());
             }
         }
+        if let Some(semantic_token_highlights) = highlights.semantic_token_highlights {
+            self.apply_semantic_token_highlights(
+                &range,
+                semantic_token_highlights,
+                &mut highlight_endpoints,
+            );
+        }
         highlight_endpoints.sort();
         let buffer_range = self.to_buffer_offset(range.start)..self.to_buffer_offset(range.end);
         let buffer_chunks = self.buffer.chunks(buffer_range, language_aware);
@@ -1119,6 +1126,69 @@ impl InlaySnapshot {
         }
     }
 
+    /// Semantic token endpoints carry no tag, so they sort below every other text highlight
+    /// and the remaining highlights are applied on top of them.
+    fn apply_semantic_token_highlights(
+        &self,
+        range: &Range<InlayOffset>,
+        semantic_token_highlights: &[(Range<Anchor>, HighlightStyle)],
+        highlight_endpoints: &mut Vec<HighlightEndpoint>,
+    ) {
+        if semantic_token_highlights.is_empty() {
+            return;
+        }
+
+        let range_start = self.buffer.anchor_after(self.to_buffer_offset(range.start));
+        let range_end = self.buffer.anchor_before(self.to_buffer_offset(range.end));
+        let start_ix = match semantic_token_highlights.binary_search_by(|(probe, _)| {
+            if probe.end.cmp(&range_start, &self.buffer).is_gt() {
+                cmp::Ordering::Greater
+            } else {
+                cmp::Ordering::Less
+            }
+        }) {
+            Ok(i) | Err(i) => i,
+        };
+
+        let mut previous_end = None;
+        for (token_range, style) in &semantic_token_highlights[start_ix..] {
+            if token_range.start.cmp(&range_end, &self.buffer).is_ge() {
+                break;
+            }
+
+            let start = self.to_inlay_offset(token_range.start.to_offset(&self.buffer));
+            let end = self.to_inlay_offset(token_range.end.to_offset(&self.buffer));
+            // Starts sort before ends at the same offset, so an adjacent token's end would
+            // clear the style of the token starting right after it: hand the style over instead.
+            if let Some(previous_end) = previous_end.take() {
+                if previous_end != start {
+                    highlight_endpoints.push(HighlightEndpoint {
+                        offset: previous_end,
+                        is_start: false,
+                        tag: None,
+                        style: HighlightStyle::default(),
+                    });
+                }
+            }
+            highlight_endpoints.push(HighlightEndpoint {
+                offset: start,
+                is_start: true,
+                tag: None,
+                style: *style,
+            });
+            previous_end = Some(end);
+        }
+
+        if let Some(previous_end) = previous_end {
+            highlight_endpoints.push(HighlightEndpoint {
+                offset: previous_end,
+                is_start: false,
+                tag: None,
+                style: HighlightStyle::default(),
+            });
+        }
+    }
+
     #[cfg(test)]
     pub fn text(&self) -> String {
         self.chunks(Default::default()..self.len(), false, Highlights::default())
@@ -0,0 +1,106 @@
+//! Stores links received from LSP <a href="https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_documentLink">textDocument/documentLink</a> requests,
+//! such as dependency names or import paths, so that they can be cmd-clicked like URLs.
+//!
+//! Links are queried lazily when hovered with the secondary modifier held, and cached per buffer with the buffer version they were computed for.
+//! Links without a target get resolved with <a href="https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#documentLink_resolve">documentLink/resolve</a> once hovered.
+use std::{ops::Range, sync::Arc};
+
+use clock::Global;
+use collections::HashMap;
+use gpui::{Model, Task, ViewContext};
+use language::Buffer;
+use project::DocumentLink;
+use text::{BufferId, ToOffset};
+use util::ResultExt;
+
+use crate::{hover_links::HoverLink, Editor};
+
+#[derive(Default)]
+pub(super) struct DocumentLinksCache {
+    buffers: HashMap<BufferId, CachedBufferDocumentLinks>,
+}
+
+struct CachedBufferDocumentLinks {
+    buffer_version: Global,
+    links: Arc<[DocumentLink]>,
+}
+
+impl Editor {
+    /// Returns the range and the target of the document link at the given position of the buffer, if any.
+    pub(crate) fn document_link_at(
+        &mut self,
+        buffer: &Model<Buffer>,
+        position: text::Anchor,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Option<(Range<text::Anchor>, HoverLink)>> {
+        let Some(project) = self.project.clone() else {
+            return Task::ready(None);
+        };
+
+        let buffers = self.buffer.read(cx).all_buffers();
+        self.document_links_cache
+            .buffers
+            .retain(|buffer_id, _| buffers.iter().any(|b| b.read(cx).remote_id() == *buffer_id));
+
+        let buffer_id = buffer.read(cx).remote_id();
+        let buffer_version = buffer.read(cx).version();
+        let cached_links = self
+            .document_links_cache
+            .buffers
+            .get(&buffer_id)
+            .filter(|cached| cached.buffer_version == buffer_version)
+            .map(|cached| cached.links.clone());
+        let links_query = match cached_links {
+            Some(links) => Task::ready(Some(links)),
+            None => {
+                let query = project.update(cx, |project, cx| project.document_links(buffer, cx));
+                cx.spawn(|editor, mut cx| async move {
+                    let links: Arc<[DocumentLink]> = query.await.log_err()?.into();
+                    editor
+                        .update(&mut cx, |editor, _| {
+                            editor.document_links_cache.buffers.insert(
+                                buffer_id,
+                                CachedBufferDocumentLinks {
+                                    buffer_version,
+                                    links: links.clone(),
+                                },
+                            );
+                        })
+                        .ok()?;
+                    Some(links)
+                })
+            }
+        };
+
+        let buffer = buffer.clone();
+        cx.spawn(|_, mut cx| async move {
+            let links = links_query.await?;
+            let link = buffer
+                .update(&mut cx, |buffer, _| {
+                    let offset = position.to_offset(buffer);
+                    links
+                        .iter()
+                        .find(|link| {
+                            link.range.start.to_offset(buffer) <= offset
+                                && offset <= link.range.end.to_offset(buffer)
+                        })
+                        .cloned()
+                })
+                .ok()??;
+            let link = project
+                .update(&mut cx, |project, cx| {
+                    project.resolve_document_link(buffer, link, cx)
+                })
+                .ok()?
+                .await
+                .log_err()?;
+            let target = link.lsp_link.target?;
+            let hover_link = if target.scheme() == "file" {
+                HoverLink::File(target.to_file_path().ok()?)
+            } else {
+                HoverLink::Url(target.to_string())
+            };
+            Some((link.range, hover_link))
+        })
+    }
+}
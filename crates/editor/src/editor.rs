@@ -8,6 +8,12 @@
 //! * [`display_map`] - chunks up text in the editor into the logical blocks, establishes coordinates and mapping between each of them.
 //!   Contains all metadata related to text transformations (folds, fake inlay text insertions, soft wraps, tab markup, etc.).
 //! * [`inlay_hint_cache`] - is a storage of inlay hints out of LSP requests, responsible for querying LSP and updating `display_map`'s state accordingly.
+//! * [`semantic_tokens`] - same as above, for LSP semantic tokens that get layered over the syntax highlighting.
+//! * [`code_lens`] - same as above, for LSP code lenses that get rendered as blocks above the lines they refer to.
+//! * [`folding_ranges`] - same as above, for LSP fold regions that replace the indentation-based folding.
+//! * [`lsp_buffer_cache`] - the per-buffer caching and debounced refreshing shared by the three caches above.
+//! * [`document_links`] - stores LSP document links, which get cmd-clickable along with go-to-definition links.
+//! * [`bookmarks`] - line bookmarks with optional labels, rendered in the gutter and persisted per worktree.
 //!
 //! All other submodules and structs are mostly concerned with holding editor data about the way it displays current buffer region(s).
 //!
@@ -15,13 +21,19 @@
 pub mod actions;
 mod blame_entry_tooltip;
 mod blink_manager;
+mod bookmarks;
+mod code_lens;
 pub mod display_map;
 mod editor_settings;
 mod element;
+mod folding_ranges;
 mod hunk_diff;
 mod inlay_hint_cache;
+mod linked_editing_ranges;
+mod lsp_buffer_cache;
 
 mod debounced_delay;
+mod document_links;
 mod git;
 mod highlight_matching_bracket;
 mod hover_links;
@@ -34,6 +46,8 @@ mod persistence;
 mod rust_analyzer_ext;
 pub mod scroll;
 mod selections_collection;
+mod semantic_tokens;
+mod snippets;
 pub mod tasks;
 
 #[cfg(test)]
@@ -46,18 +60,22 @@ pub(crate) use actions::*;
 use aho_corasick::AhoCorasick;
 use anyhow::{anyhow, Context as _, Result};
 use blink_manager::BlinkManager;
+pub use bookmarks::{project_bookmarks, ProjectBookmark};
 use client::{Collaborator, ParticipantIndex};
 use clock::ReplicaId;
+use code_lens::CodeLensCache;
 use collections::{hash_map, BTreeMap, Bound, HashMap, HashSet, VecDeque};
 use convert_case::{Case, Casing};
 use debounced_delay::DebouncedDelay;
 pub use display_map::DisplayPoint;
 use display_map::*;
+use document_links::DocumentLinksCache;
 pub use editor_settings::EditorSettings;
 use element::LineWithInvisibles;
 pub use element::{
     CursorLayout, EditorElement, HighlightedRange, HighlightedRangeLine, PointForPosition,
 };
+use folding_ranges::FoldingRangesCache;
 use futures::FutureExt;
 use fuzzy::{StringMatch, StringMatchCandidate};
 use git::blame::GitBlame;
@@ -87,6 +105,8 @@ use language::{
     Point, Selection, SelectionGoal, TransactionId,
 };
 use language::{BufferRow, Runnable, RunnableRange};
+use linked_editing_ranges::LinkedEditingRanges;
+use lsp_buffer_cache::LspRefreshReason;
 use task::{ResolvedTask, TaskTemplate, TaskVariables};
 
 use hover_links::{HoverLink, HoveredLinkState, InlayHighlight};
@@ -109,10 +129,12 @@ use rand::prelude::*;
 use rpc::{proto::*, ErrorExt};
 use scroll::{Autoscroll, OngoingScroll, ScrollAnchor, ScrollManager, ScrollbarAutoHide};
 use selections_collection::{resolve_multiple, MutableSelectionsCollection, SelectionsCollection};
+use semantic_tokens::SemanticTokensCache;
 use serde::{Deserialize, Serialize};
 use settings::{Settings, SettingsStore};
 use smallvec::SmallVec;
 use snippet::Snippet;
+use snippets::{user_snippet_completions, SnippetTransform, NON_LSP_SERVER_ID};
 use std::ops::Not as _;
 use std::{
     any::TypeId,
@@ -433,6 +455,9 @@ pub struct Editor {
     autoclose_regions: Vec<AutocloseRegion>,
     snippet_stack: InvalidationStack<SnippetState>,
     select_larger_syntax_node_stack: Vec<Box<[Selection<usize>]>>,
+    selection_ranges_task: Option<Task<()>>,
+    linked_editing_ranges: Option<LinkedEditingRanges>,
+    linked_editing_ranges_task: Option<Task<()>>,
     ime_transaction: Option<TransactionId>,
     active_diagnostics: Option<ActiveDiagnosticGroup>,
     soft_wrap_mode_override: Option<language_settings::SoftWrap>,
@@ -481,6 +506,10 @@ pub struct Editor {
     active_inline_completion: Option<Inlay>,
     show_inline_completions: bool,
     inlay_hint_cache: InlayHintCache,
+    semantic_tokens_cache: SemanticTokensCache,
+    code_lens_cache: CodeLensCache,
+    document_links_cache: DocumentLinksCache,
+    folding_ranges_cache: FoldingRangesCache,
     expanded_hunks: ExpandedHunks,
     next_inlay_id: usize,
     _subscriptions: Vec<Subscription>,
@@ -507,6 +536,7 @@ pub struct Editor {
     expect_bounds_change: Option<Bounds<Pixels>>,
     tasks: HashMap<(BufferId, BufferRow), (usize, RunnableTasks)>,
     tasks_update_task: Option<Task<()>>,
+    save_bookmarks_task: Option<Task<()>>,
 }
 
 #[derive(Clone)]
@@ -514,6 +544,7 @@ pub struct EditorSnapshot {
     pub mode: EditorMode,
     show_gutter: bool,
     render_git_blame_gutter: bool,
+    show_bookmarks: bool,
     pub display_snapshot: DisplaySnapshot,
     pub placeholder_text: Option<Arc<str>>,
     is_focused: bool,
@@ -689,6 +720,10 @@ struct AutocloseRegion {
 #[derive(Debug)]
 struct SnippetState {
     ranges: Vec<Vec<Range<Anchor>>>,
+    /// The values to choose from for each tabstop, shown when moving to it.
+    choices: Vec<Vec<String>>,
+    /// The transformations of each tabstop's text, updated when moving out of it.
+    transforms: Vec<Vec<SnippetTransform>>,
     active_index: usize,
 }
 
@@ -1565,6 +1600,14 @@ impl Editor {
         let soft_wrap_mode_override =
             (mode == EditorMode::SingleLine).then(|| language_settings::SoftWrap::PreferLine);
 
+        // Large files are opened read-only, editing them has to be enabled explicitly.
+        let read_only = mode == EditorMode::Full
+            && buffer
+                .read(cx)
+                .as_singleton()
+                .and_then(|buffer| buffer.read(cx).file())
+                .map_or(false, |file| file.is_large());
+
         let mut project_subscriptions = Vec::new();
         if mode == EditorMode::Full {
             if let Some(project) = project.as_ref() {
@@ -1573,11 +1616,22 @@ impl Editor {
                         cx.emit(EditorEvent::TitleChanged);
                     }));
                 }
-                project_subscriptions.push(cx.subscribe(project, |editor, _, event, cx| {
-                    if let project::Event::RefreshInlayHints = event {
-                        editor.refresh_inlay_hints(InlayHintRefreshReason::RefreshRequested, cx);
-                    };
-                }));
+                project_subscriptions.push(cx.subscribe(
+                    project,
+                    |editor, _, event, cx| match event {
+                        project::Event::RefreshInlayHints => {
+                            editor
+                                .refresh_inlay_hints(InlayHintRefreshReason::RefreshRequested, cx);
+                        }
+                        project::Event::RefreshSemanticTokens => {
+                            editor.refresh_semantic_tokens(LspRefreshReason::RefreshRequested, cx);
+                        }
+                        project::Event::RefreshCodeLens => {
+                            editor.refresh_code_lens(LspRefreshReason::RefreshRequested, cx);
+                        }
+                        _ => {}
+                    },
+                ));
             }
         }
 
@@ -1605,6 +1659,9 @@ impl Editor {
             autoclose_regions: Default::default(),
             snippet_stack: Default::default(),
             select_larger_syntax_node_stack: Vec::new(),
+            selection_ranges_task: None,
+            linked_editing_ranges: None,
+            linked_editing_ranges_task: None,
             ime_transaction: Default::default(),
             active_diagnostics: None,
             soft_wrap_mode_override,
@@ -1642,7 +1699,7 @@ impl Editor {
             keymap_context_layers: Default::default(),
             input_enabled: true,
             use_modal_editing: mode == EditorMode::Full,
-            read_only: false,
+            read_only,
             use_autoclose: true,
             auto_replace_emoji_shortcode: false,
             leader_peer_id: None,
@@ -1652,6 +1709,10 @@ impl Editor {
             inline_completion_provider: None,
             active_inline_completion: None,
             inlay_hint_cache: InlayHintCache::new(inlay_hint_settings),
+            semantic_tokens_cache: SemanticTokensCache::default(),
+            code_lens_cache: CodeLensCache::default(),
+            document_links_cache: DocumentLinksCache::default(),
+            folding_ranges_cache: FoldingRangesCache::default(),
             expanded_hunks: ExpandedHunks::default(),
             gutter_hovered: false,
             pixel_position_of_newest_cursor: None,
@@ -1692,9 +1753,13 @@ impl Editor {
                 }),
             ],
             tasks_update_task: None,
+            save_bookmarks_task: None,
         };
         this.tasks_update_task = Some(this.refresh_runnables(cx));
         this._subscriptions.extend(project_subscriptions);
+        this.refresh_semantic_tokens(LspRefreshReason::NewBuffersShown, cx);
+        this.refresh_code_lens(LspRefreshReason::NewBuffersShown, cx);
+        this.refresh_folding_ranges(LspRefreshReason::NewBuffersShown, cx);
 
         this.end_selection(cx);
         this.scroll_manager.show_scrollbar(cx);
@@ -1707,6 +1772,8 @@ impl Editor {
                 this.git_blame_inline_enabled = true;
                 this.start_git_blame_inline(false, cx);
             }
+
+            this.load_bookmarks(cx);
         }
 
         this.report_editor_event("open", None, cx);
@@ -1845,6 +1912,7 @@ impl Editor {
             mode: self.mode,
             show_gutter: self.show_gutter,
             render_git_blame_gutter: self.render_git_blame_gutter(cx),
+            show_bookmarks: self.has_bookmarks(cx),
             display_snapshot: self.display_map.update(cx, |map, cx| map.snapshot(cx)),
             scroll_anchor: self.scroll_manager.anchor(),
             ongoing_scroll: self.scroll_manager.ongoing_scroll(),
@@ -2063,6 +2131,7 @@ impl Editor {
         self.snippet_stack
             .invalidate(&self.selections.disjoint_anchors(), buffer);
         self.take_rename(false, cx);
+        self.refresh_linked_editing_ranges(cx);
 
         let new_cursor_position = self.selections.newest_anchor().head();
 
@@ -2775,10 +2844,14 @@ impl Editor {
         }
 
         drop(snapshot);
+        let linked_edits = self.linked_edits(&edits, cx);
         self.transact(cx, |this, cx| {
             this.buffer.update(cx, |buffer, cx| {
                 buffer.edit(edits, this.autoindent_mode.clone(), cx);
             });
+            if let Some(linked_edits) = linked_edits {
+                linked_edits.apply(cx);
+            }
 
             let new_anchor_selections = new_selections.iter().map(|e| &e.0);
             let new_selection_deltas = new_selections.iter().map(|e| e.1);
@@ -3166,6 +3239,11 @@ impl Editor {
         let text: Arc<str> = text.into();
         self.transact(cx, |this, cx| {
             let old_selections = this.selections.all_adjusted(cx);
+            let edits = old_selections
+                .iter()
+                .map(|s| (s.start..s.end, text.clone()))
+                .collect::<Vec<_>>();
+            let linked_edits = this.linked_edits(&edits, cx);
             let selection_anchors = this.buffer.update(cx, |buffer, cx| {
                 let anchors = {
                     let snapshot = buffer.read(cx);
@@ -3177,15 +3255,12 @@ impl Editor {
                         })
                         .collect::<Vec<_>>()
                 };
-                buffer.edit(
-                    old_selections
-                        .iter()
-                        .map(|s| (s.start..s.end, text.clone())),
-                    autoindent_mode,
-                    cx,
-                );
+                buffer.edit(edits, autoindent_mode, cx);
                 anchors
             });
+            if let Some(linked_edits) = linked_edits {
+                linked_edits.apply(cx);
+            }
 
             this.change_selections(Some(Autoscroll::fit()), cx, |s| {
                 s.select_anchors(selection_anchors);
@@ -3723,7 +3798,13 @@ impl Editor {
         let snippet;
         let text;
         if completion.is_snippet() {
-            snippet = Some(Snippet::parse(&completion.new_text).log_err()?);
+            let variables = self.snippet_variables(cx);
+            snippet = Some(
+                Snippet::parse_with_variables(&completion.new_text, &|name| {
+                    variables.get(name).cloned()
+                })
+                .log_err()?,
+            );
             text = snippet.as_ref().unwrap().text.clone();
         } else {
             snippet = None;
@@ -3796,9 +3877,16 @@ impl Editor {
         self.transact(cx, |this, cx| {
             if let Some(mut snippet) = snippet {
                 snippet.text = text.to_string();
-                for tabstop in snippet.tabstops.iter_mut().flatten() {
-                    tabstop.start -= common_prefix_len as isize;
-                    tabstop.end -= common_prefix_len as isize;
+                for tabstop in &mut snippet.tabstops {
+                    let transformed_ranges = tabstop.transformed_ranges.iter_mut();
+                    for range in tabstop
+                        .ranges
+                        .iter_mut()
+                        .chain(transformed_ranges.map(|(range, _)| range))
+                    {
+                        range.start -= common_prefix_len as isize;
+                        range.end -= common_prefix_len as isize;
+                    }
                 }
 
                 this.insert_snippet(&ranges, snippet, cx).log_err();
@@ -3815,6 +3903,12 @@ impl Editor {
             this.refresh_inline_completion(true, cx);
         });
 
+        // The user's snippets and the choices of a tabstop have no language server to ask for
+        // additional edits.
+        if completion.server_id == NON_LSP_SERVER_ID {
+            return Some(Task::ready(Ok(())));
+        }
+
         let provider = self.completion_provider.as_ref()?;
         let apply_edits = provider.apply_additional_edits_for_completion(
             buffer_handle,
@@ -3909,6 +4003,14 @@ impl Editor {
                                 .flatten()
                         },
                     );
+                    let inventory = this
+                        .project
+                        .as_ref()
+                        .map(|project| project.read(cx).task_inventory().clone());
+                    let worktree_id = buffer
+                        .read(cx)
+                        .file()
+                        .map(|file| WorktreeId::from_usize(file.worktree_id()));
                     let tasks = tasks.zip(task_context).map(|(tasks, mut task_context)| {
                         // Fill in the environmental variables from the tree-sitter captures
                         let mut additional_task_variables = TaskVariables::default();
@@ -3928,9 +4030,22 @@ impl Editor {
                                 .templates
                                 .iter()
                                 .filter_map(|(kind, template)| {
-                                    template
-                                        .resolve_task(&kind.to_id_base(), &task_context)
-                                        .map(|task| (kind.clone(), task))
+                                    let task = match &inventory {
+                                        Some(inventory) => inventory.read(cx).resolve_task(
+                                            kind,
+                                            template,
+                                            buffer.read(cx).language_at(Point::new(
+                                                buffer_row,
+                                                tasks.1.column,
+                                            )),
+                                            worktree_id,
+                                            &task_context,
+                                        ),
+                                        None => {
+                                            template.resolve_task(&kind.to_id_base(), &task_context)
+                                        }
+                                    };
+                                    task.map(|task| (kind.clone(), task))
                                 })
                                 .collect(),
                             position: snapshot
@@ -4639,6 +4754,8 @@ impl Editor {
         struct Tabstop<T> {
             is_end_tabstop: bool,
             ranges: Vec<Range<T>>,
+            choices: Vec<String>,
+            transforms: Vec<SnippetTransform>,
         }
 
         let tabstops = self.buffer.update(cx, |buffer, cx| {
@@ -4654,14 +4771,23 @@ impl Editor {
 
             let snapshot = &*buffer.read(cx);
             let snippet = &snippet;
+            let insertion_starts = insertion_ranges
+                .iter()
+                .scan(0_isize, |delta, insertion_range| {
+                    let insertion_start = insertion_range.start as isize + *delta;
+                    *delta += snippet.text.len() as isize - insertion_range.len() as isize;
+                    Some(insertion_start)
+                })
+                .collect::<Vec<_>>();
             snippet
                 .tabstops
                 .iter()
                 .map(|tabstop| {
-                    let is_end_tabstop = tabstop.first().map_or(false, |tabstop| {
+                    let is_end_tabstop = tabstop.ranges.first().map_or(false, |tabstop| {
                         tabstop.is_empty() && tabstop.start == snippet.text.len() as isize
                     });
                     let mut tabstop_ranges = tabstop
+                        .ranges
                         .iter()
                         .flat_map(|tabstop_range| {
                             let mut delta = 0_isize;
@@ -4681,9 +4807,34 @@ impl Editor {
                         .collect::<Vec<_>>();
                     tabstop_ranges.sort_unstable_by(|a, b| a.start.cmp(&b.start, snapshot));
 
+                    // Transformed ranges exclude the text typed at their boundaries, which
+                    // belongs to the tabstop next to them.
+                    let mut transforms = Vec::new();
+                    if let Some(source_range) = tabstop.ranges.first() {
+                        for insertion_start in &insertion_starts {
+                            let source = snapshot
+                                .anchor_before((insertion_start + source_range.start) as usize)
+                                ..snapshot
+                                    .anchor_after((insertion_start + source_range.end) as usize);
+                            for (range, transform) in &tabstop.transformed_ranges {
+                                let range = snapshot
+                                    .anchor_after((insertion_start + range.start) as usize)
+                                    ..snapshot
+                                        .anchor_before((insertion_start + range.end) as usize);
+                                transforms.push(SnippetTransform::new(
+                                    range,
+                                    source.clone(),
+                                    transform.clone(),
+                                ));
+                            }
+                        }
+                    }
+
                     Tabstop {
                         is_end_tabstop,
                         ranges: tabstop_ranges,
+                        choices: tabstop.choices.clone(),
+                        transforms,
                     }
                 })
                 .collect::<Vec<_>>()
@@ -4694,16 +4845,26 @@ impl Editor {
                 s.select_ranges(tabstop.ranges.iter().cloned());
             });
 
+            if !tabstop.choices.is_empty() {
+                self.show_snippet_choices(&tabstop.choices, cx);
+            }
+
             // If we're already at the last tabstop and it's at the end of the snippet,
             // we're done, we don't need to keep the state around.
             if !tabstop.is_end_tabstop {
-                let ranges = tabstops
-                    .into_iter()
-                    .map(|tabstop| tabstop.ranges)
-                    .collect::<Vec<_>>();
+                let mut ranges = Vec::new();
+                let mut choices = Vec::new();
+                let mut transforms = Vec::new();
+                for tabstop in tabstops {
+                    ranges.push(tabstop.ranges);
+                    choices.push(tabstop.choices);
+                    transforms.push(tabstop.transforms);
+                }
                 self.snippet_stack.push(SnippetState {
                     active_index: 0,
                     ranges,
+                    choices,
+                    transforms,
                 });
             }
 
@@ -4756,6 +4917,7 @@ impl Editor {
 
     pub fn move_to_snippet_tabstop(&mut self, bias: Bias, cx: &mut ViewContext<Self>) -> bool {
         if let Some(mut snippet) = self.snippet_stack.pop() {
+            let previous_index = snippet.active_index;
             match bias {
                 Bias::Left => {
                     if snippet.active_index > 0 {
@@ -4774,10 +4936,20 @@ impl Editor {
                     }
                 }
             }
+            if let Some(transforms) = snippet.transforms.get_mut(previous_index) {
+                self.apply_snippet_transforms(transforms, cx);
+            }
             if let Some(current_ranges) = snippet.ranges.get(snippet.active_index) {
                 self.change_selections(Some(Autoscroll::fit()), cx, |s| {
                     s.select_anchor_ranges(current_ranges.iter().cloned())
                 });
+                if let Some(choices) = snippet
+                    .choices
+                    .get(snippet.active_index)
+                    .filter(|choices| !choices.is_empty())
+                {
+                    self.show_snippet_choices(choices, cx);
+                }
                 // If snippet state is not at the last tabstop, push it back on the stack
                 if snippet.active_index + 1 < snippet.ranges.len() {
                     self.snippet_stack.push(snippet);
@@ -7706,26 +7878,70 @@ impl Editor {
         &mut self,
         _: &SelectLargerSyntaxNode,
         cx: &mut ViewContext<Self>,
+    ) {
+        let old_selections = self.selections.all::<usize>(cx).into_boxed_slice();
+        let Some(selection_ranges) = self.lsp_selection_ranges(&old_selections, cx) else {
+            self.select_larger_node(old_selections, &[], cx);
+            return;
+        };
+
+        self.selection_ranges_task = Some(cx.spawn(|editor, mut cx| async move {
+            let selection_ranges = selection_ranges.await;
+            editor
+                .update(&mut cx, |editor, cx| {
+                    // Drop the response if the selections changed while the language servers were queried.
+                    if *editor.selections.all::<usize>(cx) == *old_selections {
+                        editor.select_larger_node(old_selections, &selection_ranges, cx);
+                    }
+                })
+                .ok();
+        }));
+    }
+
+    /// Expands each selection to the smallest enclosing range provided by the language server,
+    /// or to the enclosing syntax node when there is none.
+    fn select_larger_node(
+        &mut self,
+        old_selections: Box<[Selection<usize>]>,
+        selection_ranges: &[Vec<Range<Anchor>>],
+        cx: &mut ViewContext<Self>,
     ) {
         let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
         let buffer = self.buffer.read(cx).snapshot(cx);
-        let old_selections = self.selections.all::<usize>(cx).into_boxed_slice();
 
         let mut stack = mem::take(&mut self.select_larger_syntax_node_stack);
         let mut selected_larger_node = false;
         let new_selections = old_selections
             .iter()
-            .map(|selection| {
+            .enumerate()
+            .map(|(ix, selection)| {
                 let old_range = selection.start..selection.end;
+                let lsp_range = selection_ranges.get(ix).and_then(|ranges| {
+                    ranges
+                        .iter()
+                        .map(|range| range.to_offset(&buffer))
+                        .find(|range| {
+                            range.start <= old_range.start
+                                && range.end >= old_range.end
+                                && *range != old_range
+                                && !display_map.intersects_fold(range.start)
+                                && !display_map.intersects_fold(range.end)
+                        })
+                });
+
                 let mut new_range = old_range.clone();
-                while let Some(containing_range) =
-                    buffer.range_for_syntax_ancestor(new_range.clone())
-                {
-                    new_range = containing_range;
-                    if !display_map.intersects_fold(new_range.start)
-                        && !display_map.intersects_fold(new_range.end)
+                if let Some(lsp_range) = lsp_range {
+                    new_range = lsp_range;
+                } else {
+                    while let Some(containing_range) =
+                        buffer.range_for_syntax_ancestor(new_range.clone())
                     {
-                        break;
+                        new_range = containing_range;
+                        if !display_map.intersects_fold(new_range.start)
+                            && !display_map.intersects_fold(new_range.end)
+                        {
+                            break;
+                        }
                     }
                 }
 
@@ -7749,6 +7965,76 @@ impl Editor {
         self.select_larger_syntax_node_stack = stack;
     }
 
+    /// Queries the language servers providing selection ranges for the heads of the given selections.
+    /// Returns, for each selection, the ranges containing its head from the innermost to the outermost one.
+    fn lsp_selection_ranges(
+        &self,
+        selections: &[Selection<usize>],
+        cx: &mut ViewContext<Self>,
+    ) -> Option<Task<Vec<Vec<Range<Anchor>>>>> {
+        let project = self.project.clone()?;
+        let multi_buffer = self.buffer.read(cx);
+        let snapshot = multi_buffer.snapshot(cx);
+
+        let mut positions_per_buffer =
+            HashMap::<BufferId, (Model<Buffer>, Vec<(usize, ExcerptId, text::Anchor)>)>::default();
+        for (ix, selection) in selections.iter().enumerate() {
+            let head = snapshot.anchor_before(selection.head());
+            let Some(buffer) = head
+                .buffer_id
+                .and_then(|buffer_id| multi_buffer.buffer(buffer_id))
+            else {
+                continue;
+            };
+            if !supports_selection_ranges(project.read(cx), &buffer, cx) {
+                continue;
+            }
+            positions_per_buffer
+                .entry(buffer.read(cx).remote_id())
+                .or_insert_with(|| (buffer, Vec::new()))
+                .1
+                .push((ix, head.excerpt_id, head.text_anchor));
+        }
+        if positions_per_buffer.is_empty() {
+            return None;
+        }
+
+        let queries = positions_per_buffer
+            .into_values()
+            .map(|(buffer, positions)| {
+                let query = project.update(cx, |project, cx| {
+                    project.selection_ranges(
+                        &buffer,
+                        positions.iter().map(|(_, _, position)| *position),
+                        cx,
+                    )
+                });
+                async move { (positions, query.await) }
+            })
+            .collect::<Vec<_>>();
+        let selection_count = selections.len();
+        Some(cx.spawn(|_, _| async move {
+            let mut selection_ranges = vec![Vec::new(); selection_count];
+            for (positions, ranges_per_position) in futures::future::join_all(queries).await {
+                let Some(ranges_per_position) = ranges_per_position.log_err() else {
+                    continue;
+                };
+                for ((ix, excerpt_id, _), ranges) in positions.into_iter().zip(ranges_per_position)
+                {
+                    selection_ranges[ix] = ranges
+                        .into_iter()
+                        .filter_map(|range| {
+                            let start = snapshot.anchor_in_excerpt(excerpt_id, range.start)?;
+                            let end = snapshot.anchor_in_excerpt(excerpt_id, range.end)?;
+                            Some(start..end)
+                        })
+                        .collect();
+                }
+            }
+            selection_ranges
+        }))
+    }
+
     pub fn select_smaller_syntax_node(
         &mut self,
         _: &SelectSmallerSyntaxNode,
@@ -8335,6 +8621,17 @@ impl Editor {
                     cx.open_url(&url);
                     Task::ready(Ok(None))
                 }
+                HoverLink::File(path) => {
+                    let Some(workspace) = self.workspace() else {
+                        return Task::ready(Ok(false));
+                    };
+                    let open_task = workspace
+                        .update(cx, |workspace, cx| workspace.open_abs_path(path, true, cx));
+                    return cx.spawn(|_, _| async move {
+                        open_task.await?;
+                        Ok(true)
+                    });
+                }
             };
             cx.spawn(|editor, mut cx| async move {
                 let target = target_task.await.context("target resolution task")?;
@@ -8407,7 +8704,7 @@ impl Editor {
                                     )
                                 }),
                                 HoverLink::InlayHint(_, _) => None,
-                                HoverLink::Url(_) => None,
+                                HoverLink::Url(_) | HoverLink::File(_) => None,
                             })
                             .unwrap_or(tab_kind.to_string());
                         let location_tasks = definitions
@@ -8417,7 +8714,7 @@ impl Editor {
                                 HoverLink::InlayHint(lsp_location, server_id) => {
                                     editor.compute_target_location(lsp_location, server_id, cx)
                                 }
-                                HoverLink::Url(_) => Task::ready(Ok(None)),
+                                HoverLink::Url(_) | HoverLink::File(_) => Task::ready(Ok(None)),
                             })
                             .collect::<Vec<_>>();
                         (title, location_tasks, editor.workspace().clone())
@@ -9183,9 +9480,9 @@ impl Editor {
             for row in (0..=range.end.row).rev() {
                 let fold_range = display_map.foldable_range(MultiBufferRow(row));
 
-                if let Some(fold_range) = fold_range {
+                if let Some((fold_range, placeholder)) = fold_range {
                     if fold_range.end.row >= buffer_start_row {
-                        fold_ranges.push(fold_range);
+                        fold_ranges.push((fold_range, placeholder));
                         if row <= range.start.row {
                             break;
                         }
@@ -9194,21 +9491,21 @@ impl Editor {
             }
         }
 
-        self.fold_ranges(fold_ranges, true, cx);
+        self.fold_ranges_with_placeholders(fold_ranges, true, cx);
     }
 
     pub fn fold_at(&mut self, fold_at: &FoldAt, cx: &mut ViewContext<Self>) {
         let buffer_row = fold_at.buffer_row;
         let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
 
-        if let Some(fold_range) = display_map.foldable_range(buffer_row) {
+        if let Some((fold_range, placeholder)) = display_map.foldable_range(buffer_row) {
             let autoscroll = self
                 .selections
                 .all::<Point>(cx)
                 .iter()
                 .any(|selection| fold_range.overlaps(&selection.range()));
 
-            self.fold_ranges(std::iter::once(fold_range), autoscroll, cx);
+            self.fold_ranges_with_placeholders([(fold_range, placeholder)], autoscroll, cx);
         }
     }
 
@@ -9275,20 +9572,35 @@ impl Editor {
         ranges: impl IntoIterator<Item = Range<T>>,
         auto_scroll: bool,
         cx: &mut ViewContext<Self>,
+    ) {
+        self.fold_ranges_with_placeholders(
+            ranges.into_iter().map(|range| (range, None)),
+            auto_scroll,
+            cx,
+        )
+    }
+
+    /// Folds the given ranges, displaying each of them as its placeholder text, if any, instead of an ellipsis.
+    pub fn fold_ranges_with_placeholders<T: ToOffset + Clone>(
+        &mut self,
+        ranges: impl IntoIterator<Item = (Range<T>, Option<SharedString>)>,
+        auto_scroll: bool,
+        cx: &mut ViewContext<Self>,
     ) {
         let mut fold_ranges = Vec::new();
         let mut buffers_affected = HashMap::default();
         let multi_buffer = self.buffer().read(cx);
-        for range in ranges {
+        for (range, placeholder) in ranges {
             if let Some((_, buffer, _)) = multi_buffer.excerpt_containing(range.start.clone(), cx) {
                 buffers_affected.insert(buffer.read(cx).remote_id(), buffer);
             };
-            fold_ranges.push(range);
+            fold_ranges.push((range, placeholder));
         }
 
         let mut ranges = fold_ranges.into_iter().peekable();
         if ranges.peek().is_some() {
-            self.display_map.update(cx, |map, cx| map.fold(ranges, cx));
+            self.display_map
+                .update(cx, |map, cx| map.fold_with_placeholders(ranges, cx));
 
             if auto_scroll {
                 self.request_autoscroll(Autoscroll::fit(), cx);
@@ -9528,6 +9840,11 @@ impl Editor {
         EditorSettings::override_global(editor_settings, cx);
     }
 
+    pub fn toggle_read_only(&mut self, _: &ToggleReadOnly, cx: &mut ViewContext<Self>) {
+        self.read_only = !self.read_only;
+        cx.notify();
+    }
+
     pub fn set_show_gutter(&mut self, show_gutter: bool, cx: &mut ViewContext<Self>) {
         self.show_gutter = show_gutter;
         cx.notify();
@@ -10181,6 +10498,7 @@ impl Editor {
                 if self.has_active_inline_completion(cx) {
                     self.update_visible_inline_completion(cx);
                 }
+                self.schedule_bookmarks_save(cx);
                 cx.emit(EditorEvent::BufferEdited);
                 cx.emit(SearchEvent::MatchesInvalidated);
 
@@ -10211,6 +10529,9 @@ impl Editor {
                             );
                         }
                     }
+                    self.refresh_semantic_tokens(LspRefreshReason::BufferEdited, cx);
+                    self.refresh_code_lens(LspRefreshReason::BufferEdited, cx);
+                    self.refresh_folding_ranges(LspRefreshReason::BufferEdited, cx);
                 }
 
                 let Some(project) = &self.project else { return };
@@ -10229,9 +10550,17 @@ impl Editor {
                     excerpts: excerpts.clone(),
                 });
                 self.refresh_inlay_hints(InlayHintRefreshReason::NewLinesShown, cx);
+                self.refresh_semantic_tokens(LspRefreshReason::NewBuffersShown, cx);
+                self.refresh_code_lens(LspRefreshReason::NewBuffersShown, cx);
+                self.refresh_folding_ranges(LspRefreshReason::NewBuffersShown, cx);
             }
             multi_buffer::Event::ExcerptsRemoved { ids } => {
                 self.refresh_inlay_hints(InlayHintRefreshReason::ExcerptsRemoved(ids.clone()), cx);
+                self.refresh_semantic_tokens(LspRefreshReason::NewBuffersShown, cx);
+                self.update_semantic_token_highlights(cx);
+                self.refresh_code_lens(LspRefreshReason::NewBuffersShown, cx);
+                self.update_code_lens_blocks(cx);
+                self.refresh_folding_ranges(LspRefreshReason::NewBuffersShown, cx);
                 cx.emit(EditorEvent::ExcerptsRemoved { ids: ids.clone() })
             }
             multi_buffer::Event::Reparsed => {
@@ -10240,11 +10569,19 @@ impl Editor {
                 cx.emit(EditorEvent::Reparsed);
             }
             multi_buffer::Event::LanguageChanged => {
+                self.refresh_semantic_tokens(LspRefreshReason::NewBuffersShown, cx);
+                self.refresh_code_lens(LspRefreshReason::NewBuffersShown, cx);
+                self.refresh_folding_ranges(LspRefreshReason::NewBuffersShown, cx);
                 cx.emit(EditorEvent::Reparsed);
                 cx.notify();
             }
             multi_buffer::Event::DirtyChanged => cx.emit(EditorEvent::DirtyChanged),
-            multi_buffer::Event::Saved => cx.emit(EditorEvent::Saved),
+            multi_buffer::Event::Saved => {
+                if self.has_bookmarks(cx) {
+                    self.save_bookmarks(cx);
+                }
+                cx.emit(EditorEvent::Saved);
+            }
             multi_buffer::Event::FileHandleChanged | multi_buffer::Event::Reloaded => {
                 cx.emit(EditorEvent::TitleChanged)
             }
@@ -10281,6 +10618,9 @@ impl Editor {
             )),
             cx,
         );
+        self.refresh_semantic_tokens(LspRefreshReason::NewBuffersShown, cx);
+        self.update_semantic_token_highlights(cx);
+        self.refresh_code_lens(LspRefreshReason::NewBuffersShown, cx);
         let editor_settings = EditorSettings::get_global(cx);
         self.scroll_manager.vertical_scroll_margin = editor_settings.vertical_scroll_margin;
         self.show_breadcrumbs = editor_settings.toolbar.breadcrumbs;
@@ -10820,8 +11160,14 @@ impl CompletionProvider for Model<Project> {
         buffer_position: text::Anchor,
         cx: &mut ViewContext<Editor>,
     ) -> Task<Result<Vec<Completion>>> {
-        self.update(cx, |project, cx| {
+        let project_completions = self.update(cx, |project, cx| {
             project.completions(&buffer, buffer_position, cx)
+        });
+        let snippet_completions = user_snippet_completions(buffer, buffer_position, cx);
+        cx.background_executor().spawn(async move {
+            let mut completions = project_completions.await?;
+            completions.extend(snippet_completions);
+            Ok(completions)
         })
     }
 
@@ -10850,6 +11196,20 @@ impl CompletionProvider for Model<Project> {
     }
 }
 
+fn supports_selection_ranges(project: &Project, buffer: &Model<Buffer>, cx: &AppContext) -> bool {
+    // The language servers of remote projects are only known to the host.
+    project.is_remote()
+        || project
+            .language_servers_for_buffer(buffer.read(cx), cx)
+            .any(
+                |(_, server)| match server.capabilities().selection_range_provider {
+                    Some(lsp::SelectionRangeProviderCapability::Simple(enabled)) => enabled,
+                    Some(_) => true,
+                    None => false,
+                },
+            )
+}
+
 fn inlay_hint_settings(
     location: Anchor,
     snapshot: &MultiBufferSnapshot,
@@ -10972,7 +11332,7 @@ impl EditorSnapshot {
             .then_some(em_width * GIT_BLAME_GUTTER_WIDTH_CHARS);
 
         let mut left_padding = git_blame_entries_width.unwrap_or(Pixels::ZERO);
-        left_padding += if gutter_settings.code_actions {
+        left_padding += if gutter_settings.code_actions || self.show_bookmarks {
             em_width * 3.0
         } else if show_git_gutter && gutter_lines_enabled {
             em_width * 2.0
@@ -4564,6 +4564,237 @@ async fn test_select_larger_smaller_syntax_node(cx: &mut gpui::TestAppContext) {
     );
 }
 
+#[gpui::test]
+async fn test_select_larger_node_via_lsp(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorLspTestContext::new_rust(
+        lsp::ServerCapabilities {
+            selection_range_provider: Some(lsp::SelectionRangeProviderCapability::Simple(true)),
+            ..Default::default()
+        },
+        cx,
+    )
+    .await;
+
+    cx.set_state(indoc! {"
+        fn main() {
+            let x = oneˇ + two;
+        }
+    "});
+    cx.lsp
+        .handle_request::<lsp::request::SelectionRangeRequest, _, _>(|params, _| async move {
+            assert_eq!(params.positions, [lsp::Position::new(1, 15)]);
+            Ok(Some(vec![lsp::SelectionRange {
+                range: lsp::Range::new(lsp::Position::new(1, 12), lsp::Position::new(1, 15)),
+                parent: Some(Box::new(lsp::SelectionRange {
+                    range: lsp::Range::new(lsp::Position::new(1, 4), lsp::Position::new(1, 22)),
+                    parent: None,
+                })),
+            }]))
+        });
+
+    cx.update_editor(|editor, cx| editor.select_larger_syntax_node(&SelectLargerSyntaxNode, cx));
+    cx.run_until_parked();
+    cx.assert_editor_state(indoc! {"
+        fn main() {
+            let x = «oneˇ» + two;
+        }
+    "});
+
+    cx.update_editor(|editor, cx| editor.select_larger_syntax_node(&SelectLargerSyntaxNode, cx));
+    cx.run_until_parked();
+    cx.assert_editor_state(indoc! {"
+        fn main() {
+            «let x = one + two;ˇ»
+        }
+    "});
+
+    cx.update_editor(|editor, cx| editor.select_smaller_syntax_node(&SelectSmallerSyntaxNode, cx));
+    cx.assert_editor_state(indoc! {"
+        fn main() {
+            let x = «oneˇ» + two;
+        }
+    "});
+}
+
+#[gpui::test]
+async fn test_fold_via_lsp_folding_ranges(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorLspTestContext::new_rust(
+        lsp::ServerCapabilities {
+            folding_range_provider: Some(lsp::FoldingRangeProviderCapability::Simple(true)),
+            ..Default::default()
+        },
+        cx,
+    )
+    .await;
+
+    cx.lsp
+        .handle_request::<lsp::request::FoldingRangeRequest, _, _>(|_, _| async move {
+            Ok(Some(vec![
+                lsp::FoldingRange {
+                    start_line: 0,
+                    start_character: None,
+                    end_line: 1,
+                    end_character: None,
+                    kind: Some(lsp::FoldingRangeKind::Imports),
+                    collapsed_text: Some(" ...".to_string()),
+                },
+                lsp::FoldingRange {
+                    start_line: 3,
+                    start_character: None,
+                    end_line: 4,
+                    end_character: None,
+                    kind: None,
+                    collapsed_text: None,
+                },
+            ]))
+        });
+    cx.set_state(indoc! {"
+        use a;
+        use b;
+
+        fn main() {
+            let ˇx = 1;
+        }
+    "});
+    cx.executor().advance_clock(Duration::from_secs(1));
+    cx.run_until_parked();
+
+    cx.update_editor(|editor, cx| {
+        let snapshot = editor.snapshot(cx);
+        // The imports are not indented, yet the language server made them foldable.
+        assert!(snapshot.is_foldable(MultiBufferRow(0)));
+        assert!(!snapshot.is_foldable(MultiBufferRow(1)));
+        assert!(snapshot.is_foldable(MultiBufferRow(3)));
+
+        editor.fold(&Fold, cx);
+        assert_eq!(
+            editor.display_text(cx),
+            "use a;\nuse b;\n\nfn main() {⋯\n}\n"
+        );
+
+        editor.fold_at(
+            &FoldAt {
+                buffer_row: MultiBufferRow(0),
+            },
+            cx,
+        );
+        assert_eq!(editor.display_text(cx), "use a; ...\n\nfn main() {⋯\n}\n");
+    });
+}
+
+#[gpui::test]
+async fn test_linked_edits_via_lsp(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorLspTestContext::new_rust(
+        lsp::ServerCapabilities {
+            linked_editing_range_provider: Some(lsp::LinkedEditingRangeServerCapabilities::Simple(
+                true,
+            )),
+            ..Default::default()
+        },
+        cx,
+    )
+    .await;
+
+    cx.lsp
+        .handle_request::<lsp::request::LinkedEditingRange, _, _>(|params, _| async move {
+            assert_eq!(
+                params.text_document_position_params.position,
+                lsp::Position::new(1, 9)
+            );
+            Ok(Some(lsp::LinkedEditingRanges {
+                ranges: vec![
+                    lsp::Range::new(lsp::Position::new(1, 8), lsp::Position::new(1, 11)),
+                    lsp::Range::new(lsp::Position::new(1, 14), lsp::Position::new(1, 17)),
+                ],
+                word_pattern: None,
+            }))
+        });
+    cx.set_state(indoc! {"
+        fn main() {
+            let aˇbc = abc;
+        }
+    "});
+    cx.run_until_parked();
+
+    cx.update_editor(|editor, cx| editor.handle_input("x", cx));
+    cx.assert_editor_state(indoc! {"
+        fn main() {
+            let axˇbc = axbc;
+        }
+    "});
+
+    cx.update_editor(|editor, cx| editor.backspace(&Backspace, cx));
+    cx.update_editor(|editor, cx| editor.delete(&Delete, cx));
+    cx.assert_editor_state(indoc! {"
+        fn main() {
+            let aˇc = ac;
+        }
+    "});
+
+    // Both ranges get edited as part of the same transaction.
+    cx.update_editor(|editor, cx| editor.undo(&Undo, cx));
+    cx.assert_editor_state(indoc! {"
+        fn main() {
+            let aˇbc = abc;
+        }
+    "});
+}
+
+#[gpui::test]
+async fn test_linked_edits_via_tree_sitter(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+    let html_language = Arc::new(
+        Language::new(
+            LanguageConfig {
+                name: "HTML".into(),
+                ..Default::default()
+            },
+            Some(tree_sitter_html::language()),
+        )
+        .with_linked_editing_query(
+            r#"
+            (element
+                (start_tag (tag_name) @linked)
+                (end_tag (tag_name) @linked))
+            "#,
+        )
+        .unwrap(),
+    );
+    cx.language_registry().add(html_language.clone());
+    cx.update_buffer(|buffer, cx| buffer.set_language(Some(html_language), cx));
+
+    cx.set_state("<diˇv><p></p></div>");
+    cx.run_until_parked();
+    cx.update_editor(|editor, cx| editor.handle_input("a", cx));
+    cx.assert_editor_state("<diaˇv><p></p></diav>");
+
+    // Whitespace ends the tag name.
+    cx.update_editor(|editor, cx| editor.handle_input(" ", cx));
+    cx.run_until_parked();
+    cx.update_editor(|editor, cx| editor.handle_input("x", cx));
+    cx.assert_editor_state("<dia xˇv><p></p></diav>");
+
+    cx.update(|cx| {
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<AllLanguageSettings>(cx, |settings| {
+                settings.defaults.linked_edits = Some(false);
+            });
+        });
+    });
+    cx.set_state("<ˇp></p>");
+    cx.run_until_parked();
+    cx.update_editor(|editor, cx| editor.handle_input("a", cx));
+    cx.assert_editor_state("<aˇp></p>");
+}
+
 #[gpui::test]
 async fn test_autoindent_selections(cx: &mut gpui::TestAppContext) {
     init_test(cx, |_| {});
@@ -5761,6 +5992,86 @@ async fn test_snippets(cx: &mut gpui::TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_snippet_choices_and_transforms(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let (text, insertion_ranges) = marked_text_ranges(
+        indoc! {"
+            let ˇ;
+            let ˇ;
+        "},
+        false,
+    );
+
+    let buffer = cx.update(|cx| MultiBuffer::build_simple(&text, cx));
+    let (editor, cx) = cx.add_window_view(|cx| build_editor(buffer, cx));
+
+    _ = editor.update(cx, |editor, cx| {
+        let snippet = Snippet::parse("${1|x,y|}: ${2:u8} = ${1/(.*)/${1:/upcase}/}$0").unwrap();
+        editor
+            .insert_snippet(&insertion_ranges, snippet, cx)
+            .unwrap();
+
+        fn assert(editor: &mut Editor, cx: &mut ViewContext<Editor>, marked_text: &str) {
+            let (expected_text, selection_ranges) = marked_text_ranges(marked_text, false);
+            assert_eq!(editor.text(cx), expected_text);
+            assert_eq!(editor.selections.ranges::<usize>(cx), selection_ranges);
+        }
+
+        assert(
+            editor,
+            cx,
+            indoc! {"
+                let «x»: u8 = X;
+                let «x»: u8 = X;
+            "},
+        );
+
+        // The choices of the tabstop are shown in their order.
+        if let Some(ContextMenu::Completions(menu)) = editor.context_menu.read().as_ref() {
+            let choices = menu
+                .matches
+                .iter()
+                .map(|mat| mat.string.as_str())
+                .collect::<Vec<_>>();
+            assert_eq!(choices, ["x", "y"]);
+        } else {
+            panic!("expected the choices of the tabstop");
+        }
+
+        editor
+            .confirm_completion(&ConfirmCompletion { item_ix: Some(1) }, cx)
+            .unwrap()
+            .detach();
+        assert_eq!(editor.text(cx), "let y: u8 = X;\nlet y: u8 = X;\n");
+
+        // The transformation of the tabstop's text is updated when moving out of it.
+        assert!(editor.move_to_next_snippet_tabstop(cx));
+        assert!(!editor.context_menu_visible());
+        assert(
+            editor,
+            cx,
+            indoc! {"
+                let y: «u8» = Y;
+                let y: «u8» = Y;
+            "},
+        );
+
+        assert!(editor.move_to_prev_snippet_tabstop(cx));
+        editor.insert("ab", cx);
+        assert!(editor.move_to_next_snippet_tabstop(cx));
+        assert(
+            editor,
+            cx,
+            indoc! {"
+                let ab: «u8» = AB;
+                let ab: «u8» = AB;
+            "},
+        );
+    });
+}
+
 #[gpui::test]
 async fn test_document_format_during_save(cx: &mut gpui::TestAppContext) {
     init_test(cx, |_| {});
@@ -8132,6 +8443,113 @@ async fn test_move_to_enclosing_bracket(cx: &mut gpui::TestAppContext) {
     );
 }
 
+#[gpui::test]
+async fn test_bookmarks(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+    cx.set_state(indoc! {"
+        oˇne
+        two
+        thˇree
+        four
+        five
+    "});
+    cx.update_editor(|editor, cx| editor.toggle_bookmark(&ToggleBookmark, cx));
+    cx.update_editor(|editor, cx| {
+        assert_eq!(editor.bookmark_rows(cx), vec![(0, None), (2, None)]);
+    });
+
+    // Bookmarks follow the edits of their lines.
+    cx.update_editor(|editor, cx| {
+        editor.change_selections(None, cx, |s| s.select_ranges([0..0]));
+    });
+    cx.simulate_keystroke("enter");
+    // Their new rows get persisted once the edits settle.
+    cx.update_editor(|editor, _| assert!(editor.save_bookmarks_task.is_some()));
+    cx.update_editor(|editor, cx| {
+        editor.go_to_next_bookmark(&GoToNextBookmark, cx);
+    });
+    cx.assert_editor_state(indoc! {"
+
+        one
+        two
+        ˇthree
+        four
+        five
+    "});
+    cx.update_editor(|editor, cx| {
+        editor.go_to_next_bookmark(&GoToNextBookmark, cx);
+    });
+    cx.assert_editor_state(indoc! {"
+
+        ˇone
+        two
+        three
+        four
+        five
+    "});
+    cx.update_editor(|editor, cx| {
+        editor.go_to_prev_bookmark(&GoToPrevBookmark, cx);
+        editor.set_bookmark_label(Some("three".into()), cx);
+    });
+    cx.assert_editor_state(indoc! {"
+
+        one
+        two
+        ˇthree
+        four
+        five
+    "});
+    cx.update_editor(|editor, cx| {
+        assert_eq!(
+            editor.bookmark_rows(cx),
+            vec![(1, None), (3, Some("three".into()))]
+        );
+        assert_eq!(editor.bookmark_label_at_cursor(cx), Some("three".into()));
+
+        editor.toggle_bookmark(&ToggleBookmark, cx);
+        assert_eq!(editor.bookmark_rows(cx), vec![(1, None)]);
+    });
+}
+
+#[gpui::test]
+async fn test_bookmarks_shared_between_editors(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+    cx.set_state(indoc! {"
+        oˇne
+        two
+        three
+    "});
+    cx.run_until_parked();
+    let other_editor = cx.update_editor(|editor, cx| {
+        let buffer = editor.buffer().read(cx).as_singleton().unwrap();
+        let project = editor.project.clone();
+        cx.new_view(|cx| Editor::for_buffer(buffer, project, cx))
+    });
+    cx.run_until_parked();
+
+    cx.update_editor(|editor, cx| editor.toggle_bookmark(&ToggleBookmark, cx));
+    cx.run_until_parked();
+    other_editor.update(&mut cx.cx, |editor, cx| {
+        assert_eq!(editor.bookmark_rows(cx), vec![(0, None)]);
+
+        editor.change_selections(None, cx, |s| {
+            s.select_ranges([Point::new(2, 0)..Point::new(2, 0)])
+        });
+        editor.set_bookmark_label(Some("three".into()), cx);
+    });
+    cx.run_until_parked();
+    cx.update_editor(|editor, cx| {
+        assert_eq!(
+            editor.bookmark_rows(cx),
+            vec![(0, None), (2, Some("three".into()))]
+        );
+    });
+}
+
 #[gpui::test]
 async fn test_on_type_formatting_not_triggered(cx: &mut gpui::TestAppContext) {
     init_test(cx, |_| {});
@@ -281,6 +281,9 @@ impl EditorElement {
         register_action(view, cx, Editor::go_to_prev_diagnostic);
         register_action(view, cx, Editor::go_to_hunk);
         register_action(view, cx, Editor::go_to_prev_hunk);
+        register_action(view, cx, Editor::toggle_bookmark);
+        register_action(view, cx, Editor::go_to_next_bookmark);
+        register_action(view, cx, Editor::go_to_prev_bookmark);
         register_action(view, cx, |editor, a, cx| {
             editor.go_to_definition(a, cx).detach_and_log_err(cx);
         });
@@ -315,6 +318,7 @@ impl EditorElement {
         register_action(view, cx, Editor::open_excerpts_in_split);
         register_action(view, cx, Editor::toggle_soft_wrap);
         register_action(view, cx, Editor::toggle_line_numbers);
+        register_action(view, cx, Editor::toggle_read_only);
         register_action(view, cx, Editor::toggle_inlay_hints);
         register_action(view, cx, hover_popover::hover);
         register_action(view, cx, Editor::reveal_in_finder);
@@ -383,6 +387,8 @@ impl EditorElement {
         register_action(view, cx, Editor::unique_lines_case_sensitive);
         register_action(view, cx, Editor::accept_partial_inline_completion);
         register_action(view, cx, Editor::revert_selected_hunks);
+        register_action(view, cx, Editor::stage_selected_hunks);
+        register_action(view, cx, Editor::unstage_selected_hunks);
         register_action(view, cx, Editor::open_active_item_in_terminal)
     }
 
@@ -1454,6 +1460,65 @@ impl EditorElement {
         })
     }
 
+    #[allow(clippy::too_many_arguments)]
+    fn layout_bookmark_indicators(
+        &self,
+        line_height: Pixels,
+        rows: Range<DisplayRow>,
+        code_actions_indicator_row: Option<DisplayRow>,
+        scroll_pixel_position: gpui::Point<Pixels>,
+        gutter_dimensions: &GutterDimensions,
+        gutter_hitbox: &Hitbox,
+        snapshot: &EditorSnapshot,
+        cx: &mut WindowContext,
+    ) -> Vec<AnyElement> {
+        self.editor.update(cx, |editor, cx| {
+            let indicator_rows = editor
+                .bookmark_rows(cx)
+                .into_iter()
+                .filter_map(|(row, label)| {
+                    let multibuffer_row = MultiBufferRow(row);
+                    if snapshot.is_line_folded(multibuffer_row) {
+                        return None;
+                    }
+                    let display_row = Point::new(row, 0).to_display_point(snapshot).row();
+                    if !rows.contains(&display_row)
+                        || Some(display_row) == code_actions_indicator_row
+                    {
+                        return None;
+                    }
+                    // Run indicators take precedence over bookmarks on the same line.
+                    let (buffer, range) = snapshot
+                        .buffer_snapshot
+                        .buffer_line_for_row(multibuffer_row)?;
+                    if editor
+                        .tasks
+                        .contains_key(&(buffer.remote_id(), range.start.row))
+                    {
+                        return None;
+                    }
+                    Some((display_row, row, label))
+                })
+                .collect_vec();
+
+            indicator_rows
+                .into_iter()
+                .map(|(display_row, row, label)| {
+                    let button = editor.render_bookmark_indicator(display_row, row, label, cx);
+                    prepaint_gutter_button(
+                        button,
+                        display_row,
+                        line_height,
+                        gutter_dimensions,
+                        scroll_pixel_position,
+                        gutter_hitbox,
+                        cx,
+                    )
+                })
+                .collect_vec()
+        })
+    }
+
     fn layout_code_actions_indicator(
         &self,
         line_height: Pixels,
@@ -2428,6 +2493,10 @@ impl EditorElement {
                 }
             });
 
+            for bookmark_indicator in layout.bookmark_indicators.iter_mut() {
+                bookmark_indicator.paint(cx);
+            }
+
             for test_indicators in layout.test_indicators.iter_mut() {
                 test_indicators.paint(cx);
             }
@@ -4116,6 +4185,20 @@ impl Element for EditorElement {
                     cx,
                 );
 
+                let bookmark_indicators = self.layout_bookmark_indicators(
+                    line_height,
+                    start_row..end_row,
+                    code_actions_indicator
+                        .as_ref()
+                        .and(newest_selection_head)
+                        .map(|head| head.row()),
+                    scroll_pixel_position,
+                    &gutter_dimensions,
+                    &gutter_hitbox,
+                    &snapshot,
+                    cx,
+                );
+
                 if !context_menu_visible && !cx.has_active_drag() {
                     self.layout_hover_popovers(
                         &snapshot,
@@ -4216,6 +4299,7 @@ impl Element for EditorElement {
                     selections,
                     mouse_context_menu,
                     test_indicators,
+                    bookmark_indicators,
                     code_actions_indicator,
                     fold_indicators,
                     tab_invisible,
@@ -4333,6 +4417,7 @@ pub struct EditorLayout {
     selections: Vec<(PlayerColor, Vec<SelectionLayout>)>,
     code_actions_indicator: Option<AnyElement>,
     test_indicators: Vec<AnyElement>,
+    bookmark_indicators: Vec<AnyElement>,
     fold_indicators: Vec<Option<AnyElement>>,
     mouse_context_menu: Option<AnyElement>,
     tab_invisible: ShapedLine,
@@ -0,0 +1,89 @@
+//! Stores fold regions received from LSP <a href="https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_foldingRange">textDocument/foldingRange</a> requests
+//! and hands them to the `display_map`, which uses them instead of the indentation to decide what lines can be folded.
+//!
+//! Fold regions are cached per buffer by an [`LspBufferCache`], so that only edited buffers are re-queried.
+//! Buffers whose language servers do not provide any fold regions keep being folded based on their indentation.
+use std::{sync::Arc, time::Duration};
+
+use gpui::{AppContext, Model, ModelContext, Task, ViewContext};
+use language::Buffer;
+use project::{FoldingRange, Project};
+
+use crate::{
+    display_map,
+    lsp_buffer_cache::{LspBufferCache, LspBufferQuery, LspRefreshReason},
+    Editor,
+};
+
+pub(super) type FoldingRangesCache = LspBufferCache<Arc<[display_map::FoldingRange]>>;
+
+struct FoldingRangesQuery;
+
+impl LspBufferQuery for FoldingRangesQuery {
+    type Result = Arc<[display_map::FoldingRange]>;
+
+    const DEBOUNCE: Duration = Duration::from_millis(250);
+
+    fn cache(editor: &Editor) -> &FoldingRangesCache {
+        &editor.folding_ranges_cache
+    }
+
+    fn cache_mut(editor: &mut Editor) -> &mut FoldingRangesCache {
+        &mut editor.folding_ranges_cache
+    }
+
+    fn enabled(_: &Model<Buffer>, _: &AppContext) -> bool {
+        true
+    }
+
+    fn query(
+        project: &mut Project,
+        buffer: Model<Buffer>,
+        _: Option<&Self::Result>,
+        cx: &mut ModelContext<Project>,
+    ) -> Task<anyhow::Result<Self::Result>> {
+        let query = project.folding_ranges(&buffer, cx);
+        cx.background_executor().spawn(async move {
+            Ok(query
+                .await?
+                .into_iter()
+                .map(display_folding_range)
+                .collect())
+        })
+    }
+
+    fn results_changed(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+        editor.update_folding_ranges(cx);
+    }
+}
+
+impl Editor {
+    pub(super) fn refresh_folding_ranges(
+        &mut self,
+        reason: LspRefreshReason,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.refresh_lsp_buffer_cache::<FoldingRangesQuery>(reason, cx);
+    }
+
+    fn update_folding_ranges(&mut self, cx: &mut ViewContext<Self>) {
+        // Servers without fold regions for a buffer respond with none at all, keep folding those by indentation.
+        let folding_ranges = self
+            .folding_ranges_cache
+            .iter()
+            .filter(|(_, folding_ranges)| !folding_ranges.is_empty())
+            .map(|(buffer_id, folding_ranges)| (*buffer_id, folding_ranges.clone()))
+            .collect();
+        self.display_map.update(cx, |display_map, _| {
+            display_map.set_folding_ranges(folding_ranges)
+        });
+        cx.notify();
+    }
+}
+
+fn display_folding_range(folding_range: FoldingRange) -> display_map::FoldingRange {
+    display_map::FoldingRange {
+        range: folding_range.range,
+        placeholder: folding_range.collapsed_text.map(Into::into),
+    }
+}
@@ -11,7 +11,7 @@ use project::{
     HoverBlock, HoverBlockKind, InlayHintLabelPartTooltip, InlayHintTooltip, LocationLink,
     ResolveState,
 };
-use std::ops::Range;
+use std::{ops::Range, path::PathBuf};
 use theme::ActiveTheme as _;
 use util::{maybe, ResultExt, TryFutureExt};
 
@@ -58,6 +58,7 @@ impl RangeInEditor {
 #[derive(Debug, Clone)]
 pub enum HoverLink {
     Url(String),
+    File(PathBuf),
     Text(LocationLink),
     InlayHint(lsp::Location, LanguageServerId),
 }
@@ -468,7 +469,7 @@ pub fn show_link_definition(
         || hovered_link_state
             .links
             .first()
-            .is_some_and(|d| matches!(d, HoverLink::Url(_)));
+            .is_some_and(|d| matches!(d, HoverLink::Url(_) | HoverLink::File(_)));
 
     if same_kind {
         if is_cached && (&hovered_link_state.last_trigger_point == &trigger_point)
@@ -503,6 +504,18 @@ pub fn show_link_definition(
                             (range, vec![HoverLink::Url(url)])
                         })
                         .ok()
+                    } else if let Some((link_range, link)) = this
+                        .update(&mut cx, |editor, cx| {
+                            editor.document_link_at(&buffer, buffer_position, cx)
+                        })?
+                        .await
+                    {
+                        let range = maybe!({
+                            let start = snapshot.anchor_in_excerpt(excerpt_id, link_range.start)?;
+                            let end = snapshot.anchor_in_excerpt(excerpt_id, link_range.end)?;
+                            Some(RangeInEditor::Text(start..end))
+                        });
+                        Some((range, vec![link]))
                     } else if let Some(project) = project {
                         // query the LSP for definition info
                         project
@@ -1247,4 +1260,95 @@ mod tests {
         cx.simulate_click(screen_coord, Modifiers::secondary_key());
         assert_eq!(cx.opened_url(), Some("https://zed.dev/releases".into()));
     }
+
+    #[gpui::test]
+    async fn test_document_links(cx: &mut gpui::TestAppContext) {
+        init_test(cx, |_| {});
+        let mut cx = EditorLspTestContext::new_rust(
+            lsp::ServerCapabilities {
+                document_link_provider: Some(lsp::DocumentLinkOptions {
+                    resolve_provider: Some(true),
+                    work_done_progress_options: Default::default(),
+                }),
+                ..Default::default()
+            },
+            cx,
+        )
+        .await;
+        let fs = cx.update_workspace(|workspace, cx| workspace.project().read(cx).fs().clone());
+        fs.as_fake()
+            .insert_file("/root/dir/other.rs", "".into())
+            .await;
+
+        cx.set_state(indoc! {"
+            mod other;
+            use serdeˇ::Serialize;
+        "});
+        let file_link_range = cx.lsp_range(indoc! {"
+            mod «other»;
+            use serde::Serialize;
+        "});
+        let url_link_range = cx.lsp_range(indoc! {"
+            mod other;
+            use «serde»::Serialize;
+        "});
+        let mut requests = cx.handle_request::<lsp::request::DocumentLinkRequest, _, _>(
+            move |_, _, _| async move {
+                Ok(Some(vec![
+                    lsp::DocumentLink {
+                        range: file_link_range,
+                        target: Some(lsp::Url::from_file_path("/root/dir/other.rs").unwrap()),
+                        tooltip: None,
+                        data: None,
+                    },
+                    lsp::DocumentLink {
+                        range: url_link_range,
+                        target: None,
+                        tooltip: None,
+                        data: None,
+                    },
+                ]))
+            },
+        );
+        cx.handle_request::<lsp::request::DocumentLinkResolve, _, _>(|_, mut link, _| async move {
+            link.target = Some(lsp::Url::parse("https://docs.rs/serde").unwrap());
+            Ok(link)
+        });
+
+        // Links without a target get resolved when hovered.
+        let screen_coord = cx.pixel_position(indoc! {"
+            mod other;
+            use serˇde::Serialize;
+        "});
+        cx.simulate_mouse_move(screen_coord, None, Modifiers::secondary_key());
+        requests.next().await;
+        cx.run_until_parked();
+        cx.assert_editor_text_highlights::<HoveredLinkState>(indoc! {"
+            mod other;
+            use «serdeˇ»::Serialize;
+        "});
+        cx.simulate_click(screen_coord, Modifiers::secondary_key());
+        assert_eq!(cx.opened_url(), Some("https://docs.rs/serde".into()));
+
+        // Links to files open them in the workspace.
+        let screen_coord = cx.pixel_position(indoc! {"
+            mod otˇher;
+            use serde::Serialize;
+        "});
+        cx.simulate_mouse_move(screen_coord, None, Modifiers::secondary_key());
+        cx.run_until_parked();
+        cx.assert_editor_text_highlights::<HoveredLinkState>(indoc! {"
+            mod «otherˇ»;
+            use serde::Serialize;
+        "});
+        cx.simulate_click(screen_coord, Modifiers::secondary_key());
+        cx.run_until_parked();
+        cx.update_workspace(|workspace, cx| {
+            let active_item = workspace.active_item(cx).unwrap();
+            assert_eq!(
+                active_item.project_path(cx).unwrap().path.as_ref(),
+                std::path::Path::new("dir/other.rs")
+            );
+        });
+    }
 }
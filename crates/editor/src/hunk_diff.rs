@@ -4,13 +4,13 @@ use std::{
 };
 
 use collections::{hash_map, HashMap, HashSet};
-use git::diff::{DiffHunk, DiffHunkStatus};
+use git::diff::{BufferDiff, DiffHunk, DiffHunkStatus, LineChange};
 use gpui::{AppContext, Hsla, Model, Task, View};
-use language::Buffer;
+use language::{Buffer, LineEnding};
 use multi_buffer::{
     Anchor, ExcerptRange, MultiBuffer, MultiBufferRow, MultiBufferSnapshot, ToPoint,
 };
-use text::{BufferId, Point};
+use text::{BufferId, OffsetRangeExt as _, Point, Rope};
 use ui::{
     div, ActiveTheme, Context as _, IntoElement, ParentElement, Styled, ViewContext, VisualContext,
 };
@@ -20,7 +20,7 @@ use crate::{
     git::{diff_hunk_to_display, DisplayDiffHunk},
     hunk_status, hunks_for_selections, BlockDisposition, BlockId, BlockProperties, BlockStyle,
     DiffRowHighlight, Editor, EditorSnapshot, ExpandAllHunkDiffs, RangeToAnchorExt,
-    RevertSelectedHunks, ToDisplayPoint, ToggleHunkDiff,
+    RevertSelectedHunks, StageSelectedHunks, ToDisplayPoint, ToggleHunkDiff, UnstageSelectedHunks,
 };
 
 #[derive(Debug, Clone)]
@@ -105,6 +105,97 @@ impl Editor {
         self.toggle_hunks_expanded(hunks.collect(), cx);
     }
 
+    /// Stages the changes of the selected hunks, replacing their diff base ranges in the index with the buffer text.
+    pub fn stage_selected_hunks(&mut self, _: &StageSelectedHunks, cx: &mut ViewContext<Self>) {
+        let Some(project) = self.project.clone() else {
+            return;
+        };
+        let multi_buffer_snapshot = self.buffer().read(cx).snapshot(cx);
+        let selections = self.selections.disjoint_anchors();
+        let mut hunks_by_buffer = HashMap::<BufferId, Vec<DiffHunk<MultiBufferRow>>>::default();
+        for hunk in hunks_for_selections(&multi_buffer_snapshot, &selections) {
+            hunks_by_buffer
+                .entry(hunk.buffer_id)
+                .or_default()
+                .push(hunk);
+        }
+
+        for (buffer_id, hunks) in hunks_by_buffer {
+            let Some(buffer) = self.buffer().read(cx).buffer(buffer_id) else {
+                continue;
+            };
+            let Some(index_text) = index_text_with_hunks_staged(buffer.read(cx), hunks) else {
+                continue;
+            };
+            project
+                .update(cx, |project, cx| {
+                    project.git_set_index_text(&buffer, Some(index_text), cx)
+                })
+                .detach_and_log_err(cx);
+        }
+    }
+
+    /// Unstages the staged changes of the selected lines, replacing their index ranges with the HEAD text.
+    pub fn unstage_selected_hunks(&mut self, _: &UnstageSelectedHunks, cx: &mut ViewContext<Self>) {
+        let Some(project) = self.project.clone() else {
+            return;
+        };
+        let mut index_rows_by_buffer =
+            HashMap::<BufferId, (Model<Buffer>, Vec<RangeInclusive<u32>>)>::default();
+        for selection in self.selections.disjoint_anchors().iter() {
+            let buffer_ranges = self
+                .buffer()
+                .read(cx)
+                .range_to_buffer_ranges(selection.start..selection.end, cx);
+            for (buffer, range, _) in buffer_ranges {
+                let snapshot = buffer.read(cx).snapshot();
+                let Some(diff_base) = buffer.read(cx).diff_base() else {
+                    continue;
+                };
+                let rows = snapshot.offset_to_point(range.start).row
+                    ..=snapshot.offset_to_point(range.end).row;
+                index_rows_by_buffer
+                    .entry(snapshot.remote_id())
+                    .or_insert_with(|| (buffer.clone(), Vec::new()))
+                    .1
+                    .push(diff_base_rows(&snapshot, diff_base, rows));
+            }
+        }
+
+        for (buffer, index_rows) in index_rows_by_buffer.into_values() {
+            let Some(index_text) = buffer.read(cx).diff_base().cloned() else {
+                continue;
+            };
+            let line_ending = buffer.read(cx).line_ending();
+            let head_text = project.update(cx, |project, cx| {
+                project.git_revision_text(&buffer, "HEAD".to_string(), cx)
+            });
+            let project = project.clone();
+            cx.spawn(|_, mut cx| async move {
+                let index_text = match head_text.await? {
+                    Some(head_text) => Some(
+                        cx.background_executor()
+                            .spawn(index_text_with_rows_unstaged(
+                                index_text,
+                                head_text,
+                                index_rows,
+                                line_ending,
+                            ))
+                            .await,
+                    ),
+                    // Nothing is committed for the file yet, so it is removed from the index.
+                    None => None,
+                };
+                project
+                    .update(&mut cx, |project, cx| {
+                        project.git_set_index_text(&buffer, index_text, cx)
+                    })?
+                    .await
+            })
+            .detach_and_log_err(cx);
+        }
+    }
+
     fn toggle_hunks_expanded(
         &mut self,
         hunks_to_toggle: Vec<DiffHunk<MultiBufferRow>>,
@@ -515,6 +606,102 @@ impl Editor {
     }
 }
 
+/// Returns the buffer's diff base, with the hunks' ranges replaced with their current buffer text.
+fn index_text_with_hunks_staged<T>(buffer: &Buffer, mut hunks: Vec<DiffHunk<T>>) -> Option<String> {
+    let diff_base = buffer.diff_base()?;
+    let snapshot = buffer.snapshot();
+    hunks.sort_by_key(|hunk| hunk.diff_base_byte_range.start);
+    hunks.dedup_by_key(|hunk| hunk.diff_base_byte_range.start);
+
+    let mut index_text = String::new();
+    let mut diff_base_offset = 0;
+    for hunk in hunks {
+        let diff_base_range = hunk.diff_base_byte_range;
+        if diff_base_range.start < diff_base_offset || diff_base_range.end > diff_base.len() {
+            debug_panic!("overlapping or out of bounds hunks {diff_base_range:?}");
+            return None;
+        }
+        index_text.extend(diff_base.chunks_in_range(diff_base_offset..diff_base_range.start));
+        index_text.extend(snapshot.text_for_range(hunk.buffer_range));
+        diff_base_offset = diff_base_range.end;
+    }
+    index_text.extend(diff_base.chunks_in_range(diff_base_offset..diff_base.len()));
+    Some(with_line_ending(index_text, buffer.line_ending()))
+}
+
+/// Returns the index text, with the staged changes on the index rows replaced with their HEAD text.
+async fn index_text_with_rows_unstaged(
+    index_text: Rope,
+    mut head_text: String,
+    index_rows: Vec<RangeInclusive<u32>>,
+    line_ending: LineEnding,
+) -> String {
+    LineEnding::normalize(&mut head_text);
+    let head_text = Rope::from(head_text.as_str());
+    let index =
+        text::Buffer::new_normalized(0, BufferId::new(1).unwrap(), LineEnding::Unix, index_text)
+            .snapshot();
+    let mut diff = BufferDiff::new();
+    diff.update(&head_text, &index).await;
+
+    let mut new_index_text = String::new();
+    let mut index_offset = 0;
+    for hunk in diff.hunks_intersecting_range(text::Anchor::MIN..text::Anchor::MAX, &index) {
+        let hunk_rows = &hunk.associated_range;
+        let selected = index_rows.iter().any(|rows| {
+            if hunk_rows.is_empty() {
+                // Removed lines are between two rows, both of them select the removal.
+                (*rows.start()..=*rows.end() + 1).contains(&hunk_rows.start)
+            } else {
+                hunk_rows.start <= *rows.end() && hunk_rows.end > *rows.start()
+            }
+        });
+        if !selected {
+            continue;
+        }
+        let index_range = hunk.buffer_range.to_offset(&index);
+        new_index_text.extend(index.text_for_range(index_offset..index_range.start));
+        new_index_text.extend(head_text.chunks_in_range(hunk.diff_base_byte_range));
+        index_offset = index_range.end;
+    }
+    new_index_text.extend(index.text_for_range(index_offset..index.len()));
+    with_line_ending(new_index_text, line_ending)
+}
+
+/// Maps the buffer rows to the rows of its diff base, changed rows map to the whole diff base range they replace.
+fn diff_base_rows(
+    buffer: &language::BufferSnapshot,
+    diff_base: &Rope,
+    rows: RangeInclusive<u32>,
+) -> RangeInclusive<u32> {
+    let changes = buffer
+        .git_diff_hunks_intersecting_range(text::Anchor::MIN..text::Anchor::MAX)
+        .map(|hunk| {
+            let base_start = diff_base.offset_to_point(hunk.diff_base_byte_range.start);
+            let base_end = diff_base.offset_to_point(hunk.diff_base_byte_range.end);
+            let base_end_row = base_end.row + (base_end.column > 0) as u32;
+            LineChange {
+                old_rows: base_start.row..base_end_row,
+                new_rows: hunk.associated_range,
+            }
+        })
+        .collect::<Vec<_>>();
+    let start = LineChange::old_rows_for(&changes, *rows.start()).start;
+    let end = LineChange::old_rows_for(&changes, *rows.end())
+        .end
+        .saturating_sub(1);
+    start..=end.max(start)
+}
+
+/// Converts the normalized text back to the line ending of its file.
+fn with_line_ending(text: String, line_ending: LineEnding) -> String {
+    if line_ending == LineEnding::Windows {
+        text.replace('\n', "\r\n")
+    } else {
+        text
+    }
+}
+
 fn create_diff_base_buffer(buffer: &Model<Buffer>, cx: &mut AppContext) -> Option<Model<Buffer>> {
     buffer
         .update(cx, |buffer, _| {
@@ -650,3 +837,140 @@ fn to_inclusive_row_range(
     let new_range = point_range.to_anchors(&snapshot.buffer_snapshot);
     new_range.start..=new_range.end
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor_tests::init_test;
+    use gpui::{Context as _, TestAppContext};
+
+    fn buffer_with_diff_base(
+        text: &str,
+        diff_base: &str,
+        cx: &mut TestAppContext,
+    ) -> Model<Buffer> {
+        let buffer = cx.new_model(|cx| {
+            let mut buffer = Buffer::local(text, cx);
+            buffer.set_diff_base(Some(diff_base.to_string()), cx);
+            buffer
+        });
+        cx.executor().run_until_parked();
+        buffer
+    }
+
+    fn buffer_hunks(buffer: &Buffer) -> Vec<DiffHunk<u32>> {
+        buffer
+            .snapshot()
+            .git_diff_hunks_intersecting_range(text::Anchor::MIN..text::Anchor::MAX)
+            .collect()
+    }
+
+    #[gpui::test]
+    async fn test_index_text_with_hunks_staged(cx: &mut TestAppContext) {
+        init_test(cx, |_| {});
+
+        let buffer = buffer_with_diff_base(
+            "one\nTWO\nthree\nfour\nfive\nsix\n",
+            "one\ntwo\nthree\nfive\nsix\n",
+            cx,
+        );
+        buffer.read_with(cx, |buffer, _| {
+            let hunks = buffer_hunks(buffer);
+            assert_eq!(hunks.len(), 2);
+            assert_eq!(
+                index_text_with_hunks_staged(buffer, vec![hunks[0].clone()]).as_deref(),
+                Some("one\nTWO\nthree\nfive\nsix\n")
+            );
+            // The added lines are inserted at their position in the index.
+            assert_eq!(
+                index_text_with_hunks_staged(buffer, vec![hunks[1].clone()]).as_deref(),
+                Some("one\ntwo\nthree\nfour\nfive\nsix\n")
+            );
+            assert_eq!(
+                index_text_with_hunks_staged(buffer, hunks.into_iter().rev().collect()).as_deref(),
+                Some("one\nTWO\nthree\nfour\nfive\nsix\n")
+            );
+        });
+
+        // The index keeps the line endings of the file.
+        let buffer =
+            buffer_with_diff_base("one\r\nTWO\r\nthree\r\n", "one\r\ntwo\r\nthree\r\n", cx);
+        buffer.read_with(cx, |buffer, _| {
+            assert_eq!(
+                index_text_with_hunks_staged(buffer, buffer_hunks(buffer)).as_deref(),
+                Some("one\r\nTWO\r\nthree\r\n")
+            );
+        });
+    }
+
+    #[gpui::test]
+    async fn test_index_text_with_rows_unstaged() {
+        let head_text = "one\ntwo\nthree\nfive\nsix\n";
+        let index_text = Rope::from("one\nTWO\nthree\nfour\nfive\nsix\n");
+        let unstage = |index_rows: Vec<RangeInclusive<u32>>| {
+            index_text_with_rows_unstaged(
+                index_text.clone(),
+                head_text.to_string(),
+                index_rows,
+                LineEnding::Unix,
+            )
+        };
+
+        assert_eq!(
+            unstage(vec![1..=1]).await,
+            "one\ntwo\nthree\nfour\nfive\nsix\n"
+        );
+        assert_eq!(unstage(vec![3..=3]).await, "one\nTWO\nthree\nfive\nsix\n");
+        assert_eq!(unstage(vec![0..=0, 5..=5]).await, index_text.to_string());
+        assert_eq!(unstage(vec![0..=5]).await, head_text);
+
+        // Removed lines are unstaged from the rows around them.
+        let unstage_removal = |index_rows: Vec<RangeInclusive<u32>>| {
+            index_text_with_rows_unstaged(
+                Rope::from("one\nthree\n"),
+                "one\ntwo\nthree\n".to_string(),
+                index_rows,
+                LineEnding::Unix,
+            )
+        };
+        assert_eq!(unstage_removal(vec![0..=0]).await, "one\ntwo\nthree\n");
+        assert_eq!(unstage_removal(vec![1..=1]).await, "one\ntwo\nthree\n");
+
+        // The HEAD text is normalized, and the result converted back to the file's line endings.
+        let index_text = index_text_with_rows_unstaged(
+            Rope::from("one\nTWO\nthree\n"),
+            "one\r\ntwo\r\nthree\r\n".to_string(),
+            vec![1..=1],
+            LineEnding::Windows,
+        )
+        .await;
+        assert_eq!(index_text, "one\r\ntwo\r\nthree\r\n");
+    }
+
+    #[gpui::test]
+    async fn test_diff_base_rows(cx: &mut TestAppContext) {
+        init_test(cx, |_| {});
+
+        let buffer = buffer_with_diff_base(
+            "one\nTWO\nthree\nfour\nfive\nsix\n",
+            "one\ntwo\nthree\nfive\nsix\n",
+            cx,
+        );
+        buffer.read_with(cx, |buffer, _| {
+            let snapshot = buffer.snapshot();
+            let diff_base = buffer.diff_base().unwrap();
+            assert_eq!(diff_base_rows(&snapshot, diff_base, 0..=0), 0..=0);
+            assert_eq!(diff_base_rows(&snapshot, diff_base, 1..=2), 1..=2);
+            assert_eq!(diff_base_rows(&snapshot, diff_base, 4..=5), 3..=4);
+            // Added lines have no rows in the diff base, the row they are inserted before is used.
+            assert_eq!(diff_base_rows(&snapshot, diff_base, 3..=3), 3..=3);
+        });
+
+        let buffer = buffer_with_diff_base("one\nthree\n", "one\ntwo\nthree\n", cx);
+        buffer.read_with(cx, |buffer, _| {
+            let snapshot = buffer.snapshot();
+            let diff_base = buffer.diff_base().unwrap();
+            assert_eq!(diff_base_rows(&snapshot, diff_base, 1..=1), 2..=2);
+        });
+    }
+}
@@ -1060,6 +1060,21 @@ impl SearchableItem for Editor {
     ) {
         let text = self.buffer.read(cx);
         let text = text.snapshot(cx);
+        if query.is_structural() || query.fancy_regex() {
+            // The replacements of structural matches and of fancy regexes' lookaround depend on
+            // the surroundings of the match, so the match is searched again in its buffer.
+            let range = identifier.to_offset(&text);
+            let replacement = text.excerpt_containing(range.clone()).and_then(|excerpt| {
+                query.replacement_for_match(excerpt.buffer(), excerpt.map_range_to_buffer(range))
+            });
+            if let Some(replacement) = replacement {
+                self.transact(cx, |this, cx| {
+                    this.edit([(identifier.clone(), Arc::from(replacement))], cx);
+                });
+            }
+            return;
+        }
+
         let text = text.text_for_range(identifier.clone()).collect::<Vec<_>>();
         let text: Cow<_> = if text.len() == 1 {
             text.first().cloned().unwrap().into()
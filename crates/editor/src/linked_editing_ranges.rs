@@ -0,0 +1,222 @@
+//! Tracks the ranges linked to the one under the newest cursor, such as the names of an opening and a closing tag,
+//! and mirrors the edits made within one of them into the others.
+//!
+//! The ranges come from LSP <a href="https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_linkedEditingRange">textDocument/linkedEditingRange</a> requests,
+//! or from the language's `linked_editing` tree-sitter query if no language server provides any.
+//! Edits are only mirrored while all linked ranges have the same text.
+use std::{ops::Range, sync::Arc};
+
+use gpui::{AppContext, Model, ViewContext};
+use language::{Buffer, BufferSnapshot};
+use multi_buffer::ToOffset;
+use project::Project;
+use text::{Anchor, ToOffset as _};
+use util::ResultExt;
+
+use crate::{Editor, EditorMode};
+
+pub(super) struct LinkedEditingRanges {
+    buffer: Model<Buffer>,
+    ranges: Vec<Range<Anchor>>,
+}
+
+/// Edits to apply to a buffer to keep its linked ranges in sync.
+pub(super) struct LinkedEdits {
+    buffer: Model<Buffer>,
+    edits: Vec<(Range<Anchor>, Arc<str>)>,
+}
+
+impl LinkedEditingRanges {
+    fn offset_ranges(&self, snapshot: &BufferSnapshot) -> Vec<Range<usize>> {
+        self.ranges
+            .iter()
+            .map(|range| range.start.to_offset(snapshot)..range.end.to_offset(snapshot))
+            .collect()
+    }
+
+    fn contains(&self, range: &Range<usize>, snapshot: &BufferSnapshot) -> bool {
+        self.offset_ranges(snapshot)
+            .iter()
+            .any(|linked_range| linked_range.start <= range.start && range.end <= linked_range.end)
+    }
+
+    fn have_same_text(&self, snapshot: &BufferSnapshot) -> bool {
+        let mut texts = self
+            .offset_ranges(snapshot)
+            .into_iter()
+            .map(|range| snapshot.text_for_range(range).collect::<String>());
+        let first_text = texts.next().unwrap_or_default();
+        texts.all(|text| text == first_text)
+    }
+}
+
+impl LinkedEdits {
+    pub(super) fn apply(self, cx: &mut AppContext) {
+        self.buffer
+            .update(cx, |buffer, cx| buffer.edit(self.edits, None, cx));
+    }
+}
+
+impl Editor {
+    pub(super) fn refresh_linked_editing_ranges(&mut self, cx: &mut ViewContext<Self>) {
+        if self.mode != EditorMode::Full {
+            return;
+        }
+
+        let selection = self.selections.newest_anchor().clone();
+        let multi_buffer = self.buffer.read(cx);
+        let Some((buffer, start)) = multi_buffer.text_anchor_for_position(selection.start, cx)
+        else {
+            self.clear_linked_editing_ranges();
+            return;
+        };
+        let Some((end_buffer, end)) = multi_buffer.text_anchor_for_position(selection.end, cx)
+        else {
+            self.clear_linked_editing_ranges();
+            return;
+        };
+        let snapshot = buffer.read(cx).snapshot();
+        if end_buffer != buffer || !snapshot.settings_at(start, cx).linked_edits {
+            self.clear_linked_editing_ranges();
+            return;
+        }
+
+        let selection_range = start.to_offset(&snapshot)..end.to_offset(&snapshot);
+        if let Some(linked_editing_ranges) = &self.linked_editing_ranges {
+            if linked_editing_ranges.buffer == buffer
+                && linked_editing_ranges.contains(&selection_range, &snapshot)
+                && linked_editing_ranges.have_same_text(&snapshot)
+            {
+                return;
+            }
+        }
+        self.clear_linked_editing_ranges();
+
+        let lsp_query = self
+            .project
+            .clone()
+            .filter(|project| supports_linked_editing_ranges(project.read(cx), &buffer, cx))
+            .map(|project| {
+                project.update(cx, |project, cx| {
+                    project.linked_editing_ranges(&buffer, start, cx)
+                })
+            });
+        self.linked_editing_ranges_task = Some(cx.spawn(|editor, mut cx| async move {
+            let lsp_ranges = match lsp_query {
+                Some(lsp_query) => lsp_query.await.log_err().unwrap_or_default(),
+                None => Vec::new(),
+            };
+            editor
+                .update(&mut cx, |editor, cx| {
+                    let snapshot = buffer.read(cx).snapshot();
+                    let ranges = if lsp_ranges.is_empty() {
+                        snapshot
+                            .linked_editing_ranges(start)
+                            .into_iter()
+                            .map(|range| {
+                                snapshot.anchor_before(range.start)
+                                    ..snapshot.anchor_after(range.end)
+                            })
+                            .collect()
+                    } else {
+                        lsp_ranges
+                    };
+                    let linked_editing_ranges = LinkedEditingRanges { buffer, ranges };
+                    let selection_range = start.to_offset(&snapshot)..end.to_offset(&snapshot);
+                    if linked_editing_ranges.ranges.len() > 1
+                        && linked_editing_ranges.contains(&selection_range, &snapshot)
+                    {
+                        editor.linked_editing_ranges = Some(linked_editing_ranges);
+                    }
+                })
+                .ok();
+        }));
+    }
+
+    fn clear_linked_editing_ranges(&mut self) {
+        self.linked_editing_ranges = None;
+        self.linked_editing_ranges_task = None;
+    }
+
+    /// Returns the edits mirroring the given ones into the linked ranges they are not made in.
+    /// Has to be called before the given edits are applied.
+    pub(super) fn linked_edits<T: ToOffset + Clone>(
+        &self,
+        edits: &[(Range<T>, Arc<str>)],
+        cx: &AppContext,
+    ) -> Option<LinkedEdits> {
+        let linked_editing_ranges = self.linked_editing_ranges.as_ref()?;
+        let snapshot = linked_editing_ranges.buffer.read(cx).snapshot();
+        if !linked_editing_ranges.have_same_text(&snapshot) {
+            return None;
+        }
+
+        let multi_buffer = self.buffer.read(cx);
+        let linked_ranges = linked_editing_ranges.offset_ranges(&snapshot);
+        let mut edits_in_linked_ranges = Vec::new();
+        for (range, text) in edits {
+            // Whitespace ends a tag name, there is nothing to mirror past it.
+            if text.chars().any(char::is_whitespace) {
+                return None;
+            }
+            let (start_buffer, start) =
+                multi_buffer.text_anchor_for_position(range.start.clone(), cx)?;
+            let (end_buffer, end) = multi_buffer.text_anchor_for_position(range.end.clone(), cx)?;
+            if start_buffer != linked_editing_ranges.buffer
+                || end_buffer != linked_editing_ranges.buffer
+            {
+                continue;
+            }
+            let range = start.to_offset(&snapshot)..end.to_offset(&snapshot);
+            if let Some(ix) = linked_ranges.iter().position(|linked_range| {
+                linked_range.start <= range.start && range.end <= linked_range.end
+            }) {
+                edits_in_linked_ranges.push((ix, range, text.clone()));
+            }
+        }
+
+        let mut linked_edits = Vec::new();
+        for (ix, range, text) in &edits_in_linked_ranges {
+            let relative_start = range.start - linked_ranges[*ix].start;
+            let relative_end = range.end - linked_ranges[*ix].start;
+            for (other_ix, other_range) in linked_ranges.iter().enumerate() {
+                // Ranges edited directly, e.g. with a cursor in each of them, must not be edited twice.
+                if edits_in_linked_ranges
+                    .iter()
+                    .any(|(edited_ix, _, _)| *edited_ix == other_ix)
+                {
+                    continue;
+                }
+                let start = other_range.start + relative_start;
+                let end = other_range.start + relative_end;
+                linked_edits.push((
+                    snapshot.anchor_before(start)..snapshot.anchor_after(end),
+                    text.clone(),
+                ));
+            }
+        }
+
+        (!linked_edits.is_empty()).then(|| LinkedEdits {
+            buffer: linked_editing_ranges.buffer.clone(),
+            edits: linked_edits,
+        })
+    }
+}
+
+fn supports_linked_editing_ranges(
+    project: &Project,
+    buffer: &Model<Buffer>,
+    cx: &AppContext,
+) -> bool {
+    // The language servers of remote projects are only known to the host.
+    project.is_remote()
+        || project
+            .language_servers_for_buffer(buffer.read(cx), cx)
+            .any(
+                |(_, server)| match server.capabilities().linked_editing_range_provider {
+                    Some(lsp::LinkedEditingRangeServerCapabilities::Simple(enabled)) => enabled,
+                    Some(_) => true,
+                    None => false,
+                },
+            )
+}
@@ -0,0 +1,181 @@
+//! The per-buffer caching and refreshing shared by the editor's LSP-backed caches,
+//! such as [`crate::semantic_tokens`], [`crate::code_lens`] and [`crate::folding_ranges`].
+//!
+//! Each cache keeps one query result per buffer, with the buffer version it was computed for, so that only edited buffers are re-queried.
+//! Results of buffers that are not shown by the editor anymore get dropped on the next refresh.
+use std::time::Duration;
+
+use clock::Global;
+use collections::HashMap;
+use futures::future;
+use gpui::{AppContext, Model, ModelContext, Task, ViewContext};
+use language::Buffer;
+use project::Project;
+use text::BufferId;
+use util::ResultExt;
+
+use crate::{Editor, EditorMode};
+
+/// Why an LSP-backed cache is being refreshed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum LspRefreshReason {
+    /// New buffers or excerpts got shown, or the settings changed: only buffers without up-to-date results are queried.
+    NewBuffersShown,
+    /// Some buffer got edited: its results are re-queried after a debounce.
+    BufferEdited,
+    /// The language server asked for all results to be re-queried, e.g. with a `workspace/semanticTokens/refresh` request.
+    RefreshRequested,
+}
+
+pub(super) struct LspBufferCache<T> {
+    buffers: HashMap<BufferId, CachedBufferResult<T>>,
+    refresh_task: Option<Task<()>>,
+}
+
+struct CachedBufferResult<T> {
+    buffer_version: Global,
+    result: T,
+}
+
+impl<T> Default for LspBufferCache<T> {
+    fn default() -> Self {
+        Self {
+            buffers: HashMap::default(),
+            refresh_task: None,
+        }
+    }
+}
+
+impl<T> LspBufferCache<T> {
+    pub fn get(&self, buffer_id: &BufferId) -> Option<&T> {
+        self.buffers.get(buffer_id).map(|cached| &cached.result)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&BufferId, &T)> {
+        self.buffers
+            .iter()
+            .map(|(buffer_id, cached)| (buffer_id, &cached.result))
+    }
+}
+
+/// An LSP query whose results get cached per buffer in an [`LspBufferCache`] of the editor.
+pub(super) trait LspBufferQuery: 'static {
+    type Result: 'static;
+
+    /// How long to wait after an edit before re-querying the edited buffers.
+    const DEBOUNCE: Duration;
+
+    fn cache(editor: &Editor) -> &LspBufferCache<Self::Result>;
+
+    fn cache_mut(editor: &mut Editor) -> &mut LspBufferCache<Self::Result>;
+
+    /// Whether the buffer should be queried at all, e.g. according to the language settings.
+    fn enabled(buffer: &Model<Buffer>, cx: &AppContext) -> bool;
+
+    /// Queries the buffer, given the buffer's previous result, if any.
+    fn query(
+        project: &mut Project,
+        buffer: Model<Buffer>,
+        previous: Option<&Self::Result>,
+        cx: &mut ModelContext<Project>,
+    ) -> Task<anyhow::Result<Self::Result>>;
+
+    /// Applies the cached results to the editor, after they changed.
+    fn results_changed(editor: &mut Editor, cx: &mut ViewContext<Editor>);
+}
+
+impl Editor {
+    pub(super) fn refresh_lsp_buffer_cache<Q: LspBufferQuery>(
+        &mut self,
+        reason: LspRefreshReason,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if self.mode != EditorMode::Full {
+            return;
+        }
+        let Some(project) = self.project.clone() else {
+            return;
+        };
+
+        let buffers = self
+            .buffer
+            .read(cx)
+            .all_buffers()
+            .into_iter()
+            .filter(|buffer| Q::enabled(buffer, cx))
+            .collect::<Vec<_>>();
+        let cache = Q::cache_mut(self);
+        let cache_len = cache.buffers.len();
+        cache.buffers.retain(|buffer_id, _| {
+            buffers
+                .iter()
+                .any(|buffer| buffer.read(cx).remote_id() == *buffer_id)
+        });
+        if cache_len != cache.buffers.len() {
+            Q::results_changed(self, cx);
+        }
+        if buffers.is_empty() {
+            Q::cache_mut(self).refresh_task = None;
+            return;
+        }
+
+        let debounce = (reason == LspRefreshReason::BufferEdited).then_some(Q::DEBOUNCE);
+        let invalidate = reason == LspRefreshReason::RefreshRequested;
+        let refresh_task = cx.spawn(|editor, mut cx| async move {
+            if let Some(debounce) = debounce {
+                cx.background_executor().timer(debounce).await;
+            }
+
+            let Some(queries) = editor
+                .update(&mut cx, |editor, cx| {
+                    buffers
+                        .into_iter()
+                        .filter_map(|buffer| {
+                            let buffer_id = buffer.read(cx).remote_id();
+                            let buffer_version = buffer.read(cx).version();
+                            let cached = Q::cache(editor).buffers.get(&buffer_id);
+                            if !invalidate
+                                && cached
+                                    .map_or(false, |cached| cached.buffer_version == buffer_version)
+                            {
+                                return None;
+                            }
+
+                            let query = project.update(cx, |project, cx| {
+                                Q::query(project, buffer, cached.map(|cached| &cached.result), cx)
+                            });
+                            Some(async move { (buffer_id, buffer_version, query.await) })
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .ok()
+            else {
+                return;
+            };
+            if queries.is_empty() {
+                return;
+            }
+
+            let results = future::join_all(queries).await;
+            editor
+                .update(&mut cx, |editor, cx| {
+                    let cache = Q::cache_mut(editor);
+                    for (buffer_id, buffer_version, result) in results {
+                        let Some(result) = result.log_err() else {
+                            continue;
+                        };
+                        cache.buffers.insert(
+                            buffer_id,
+                            CachedBufferResult {
+                                buffer_version,
+                                result,
+                            },
+                        );
+                    }
+                    Q::results_changed(editor, cx);
+                })
+                .ok();
+        });
+        Q::cache_mut(self).refresh_task = Some(refresh_task);
+    }
+}
@@ -1,5 +1,6 @@
 use std::path::PathBuf;
 
+use anyhow::Result;
 use db::sqlez_macros::sql;
 use db::{define_connection, query};
 
@@ -15,6 +16,13 @@ define_connection!(
     //   scroll_vertical_offset: f32,
     //   scroll_horizontal_offset: f32,
     // )
+    //
+    // bookmarks(
+    //   worktree_path: PathBuf,
+    //   path: PathBuf,
+    //   row: u32,
+    //   label: Option<String>,
+    // )
     pub static ref DB: EditorDb<WorkspaceDb> =
         &[sql! (
             CREATE TABLE editors(
@@ -31,6 +39,15 @@ define_connection!(
             ALTER TABLE editors ADD COLUMN scroll_top_row INTEGER NOT NULL DEFAULT 0;
             ALTER TABLE editors ADD COLUMN scroll_horizontal_offset REAL NOT NULL DEFAULT 0;
             ALTER TABLE editors ADD COLUMN scroll_vertical_offset REAL NOT NULL DEFAULT 0;
+        ),
+        sql! (
+            CREATE TABLE bookmarks(
+                worktree_path BLOB NOT NULL,
+                path BLOB NOT NULL,
+                row INTEGER NOT NULL,
+                label TEXT,
+                PRIMARY KEY(worktree_path, path, row)
+            ) STRICT;
         )];
 );
 
@@ -80,4 +97,44 @@ impl EditorDb {
             WHERE item_id = ?1 AND workspace_id = ?2
         }
     }
+
+    query! {
+        pub fn get_bookmarks(worktree_path: PathBuf, path: PathBuf) -> Result<Vec<(u32, Option<String>)>> {
+            SELECT row, label FROM bookmarks
+            WHERE worktree_path = ? AND path = ?
+            ORDER BY row
+        }
+    }
+
+    query! {
+        pub fn get_worktree_bookmarks(worktree_path: PathBuf) -> Result<Vec<(PathBuf, u32, Option<String>)>> {
+            SELECT path, row, label FROM bookmarks
+            WHERE worktree_path = ?
+            ORDER BY path, row
+        }
+    }
+
+    /// Replaces the bookmarks of a file with the given rows and labels.
+    pub async fn save_bookmarks(
+        &self,
+        worktree_path: PathBuf,
+        path: PathBuf,
+        bookmarks: Vec<(u32, Option<String>)>,
+    ) -> Result<()> {
+        self.write(move |conn| {
+            conn.with_savepoint("save_bookmarks", || {
+                conn.exec_bound(sql!(
+                    DELETE FROM bookmarks WHERE worktree_path = ? AND path = ?
+                ))?((worktree_path.as_path(), path.as_path()))?;
+                for (row, label) in bookmarks {
+                    conn.exec_bound(sql!(
+                        INSERT INTO bookmarks(worktree_path, path, row, label)
+                        VALUES (?, ?, ?, ?)
+                    ))?((worktree_path.as_path(), path.as_path(), row, label))?;
+                }
+                Ok(())
+            })
+        })
+        .await
+    }
 }
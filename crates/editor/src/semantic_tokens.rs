@@ -0,0 +1,178 @@
+//! Stores semantic tokens received from LSP <a href="https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_semanticTokens">textDocument/semanticTokens</a> requests
+//! and turns them into the `display_map`'s semantic token highlights, layered over the tree-sitter syntax highlighting.
+//!
+//! Tokens are cached per buffer by an [`LspBufferCache`], and the previous result is kept around to request deltas from servers that support them.
+//! The theme styles of the tokens are resolved once per legend and theme, and looked up by the tokens' legend indices.
+use std::{sync::Arc, time::Duration};
+
+use collections::HashMap;
+use gpui::{AppContext, HighlightStyle, Model, ModelContext, Task, ViewContext};
+use language::{language_settings::language_settings, Buffer};
+use project::{Project, SemanticTokens, SemanticTokensLegend};
+use theme::{ActiveTheme as _, SyntaxTheme};
+
+use crate::{
+    lsp_buffer_cache::{LspBufferCache, LspBufferQuery, LspRefreshReason},
+    Editor,
+};
+
+#[derive(Default)]
+pub(super) struct SemanticTokensCache {
+    tokens: LspBufferCache<SemanticTokens>,
+    legend_styles: LegendStyles,
+}
+
+/// The theme styles of the legends' token types and modifiers, keyed by their indices.
+#[derive(Default)]
+struct LegendStyles {
+    theme: Option<Arc<SyntaxTheme>>,
+    legends: Vec<(
+        Arc<SemanticTokensLegend>,
+        HashMap<(u32, u32), Option<HighlightStyle>>,
+    )>,
+}
+
+impl LegendStyles {
+    /// Returns the styles resolved so far for the legend, dropping all of them if the theme changed.
+    fn styles(
+        &mut self,
+        legend: &Arc<SemanticTokensLegend>,
+        theme: &Arc<SyntaxTheme>,
+    ) -> &mut HashMap<(u32, u32), Option<HighlightStyle>> {
+        if !self
+            .theme
+            .as_ref()
+            .map_or(false, |styles_theme| Arc::ptr_eq(styles_theme, theme))
+        {
+            self.theme = Some(theme.clone());
+            self.legends.clear();
+        }
+
+        let ix = match self.legends.iter().position(|(known_legend, _)| {
+            Arc::ptr_eq(known_legend, legend) || known_legend == legend
+        }) {
+            Some(ix) => ix,
+            None => {
+                self.legends.push((legend.clone(), HashMap::default()));
+                self.legends.len() - 1
+            }
+        };
+        &mut self.legends[ix].1
+    }
+}
+
+fn resolve_style(
+    theme: &SyntaxTheme,
+    legend: &SemanticTokensLegend,
+    token_type: u32,
+    token_modifiers: u32,
+) -> Option<HighlightStyle> {
+    let token_type = legend.token_type(token_type)?;
+    let token_modifiers = legend.token_modifiers(token_modifiers).collect::<Vec<_>>();
+    theme.semantic_token_style(token_type, &token_modifiers)
+}
+
+struct SemanticTokensQuery;
+
+impl LspBufferQuery for SemanticTokensQuery {
+    type Result = SemanticTokens;
+
+    const DEBOUNCE: Duration = Duration::from_millis(150);
+
+    fn cache(editor: &Editor) -> &LspBufferCache<SemanticTokens> {
+        &editor.semantic_tokens_cache.tokens
+    }
+
+    fn cache_mut(editor: &mut Editor) -> &mut LspBufferCache<SemanticTokens> {
+        &mut editor.semantic_tokens_cache.tokens
+    }
+
+    fn enabled(buffer: &Model<Buffer>, cx: &AppContext) -> bool {
+        let buffer = buffer.read(cx);
+        language_settings(buffer.language(), buffer.file(), cx).semantic_tokens
+    }
+
+    fn query(
+        project: &mut Project,
+        buffer: Model<Buffer>,
+        previous: Option<&SemanticTokens>,
+        cx: &mut ModelContext<Project>,
+    ) -> Task<anyhow::Result<SemanticTokens>> {
+        project.semantic_tokens(buffer, previous, cx)
+    }
+
+    fn results_changed(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+        editor.update_semantic_token_highlights(cx);
+    }
+}
+
+impl Editor {
+    pub(super) fn refresh_semantic_tokens(
+        &mut self,
+        reason: LspRefreshReason,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.refresh_lsp_buffer_cache::<SemanticTokensQuery>(reason, cx);
+    }
+
+    /// Re-derives the highlights from the cached tokens, e.g. after the theme changed.
+    pub(super) fn update_semantic_token_highlights(&mut self, cx: &mut ViewContext<Self>) {
+        let syntax_theme = cx.theme().syntax().clone();
+        let multi_buffer_snapshot = self.buffer.read(cx).snapshot(cx);
+        let mut highlights = Vec::new();
+        let SemanticTokensCache {
+            tokens,
+            legend_styles,
+        } = &mut self.semantic_tokens_cache;
+        for (excerpt_id, buffer_snapshot, excerpt_range) in multi_buffer_snapshot.excerpts() {
+            let Some(cached) = tokens.get(&buffer_snapshot.remote_id()) else {
+                continue;
+            };
+            let legend = &cached.legend;
+            let styles = legend_styles.styles(legend, &syntax_theme);
+
+            let tokens = &cached.tokens;
+            let start_ix = tokens.partition_point(|token| {
+                token
+                    .range
+                    .end
+                    .cmp(&excerpt_range.context.start, buffer_snapshot)
+                    .is_le()
+            });
+            for token in &tokens[start_ix..] {
+                if token
+                    .range
+                    .start
+                    .cmp(&excerpt_range.context.end, buffer_snapshot)
+                    .is_ge()
+                {
+                    break;
+                }
+
+                let Some(style) = *styles
+                    .entry((token.token_type, token.token_modifiers))
+                    .or_insert_with(|| {
+                        resolve_style(
+                            &syntax_theme,
+                            legend,
+                            token.token_type,
+                            token.token_modifiers,
+                        )
+                    })
+                else {
+                    continue;
+                };
+                let start = multi_buffer_snapshot.anchor_in_excerpt(excerpt_id, token.range.start);
+                let end = multi_buffer_snapshot.anchor_in_excerpt(excerpt_id, token.range.end);
+                if let Some((start, end)) = start.zip(end) {
+                    highlights.push((start..end, style));
+                }
+            }
+        }
+
+        self.display_map.update(cx, |display_map, _| {
+            display_map.set_semantic_token_highlights(highlights)
+        });
+        cx.notify();
+    }
+}
@@ -0,0 +1,314 @@
+//! Support for the parts of snippets that go beyond inserting their text: the values of their
+//! variables, the choices of their tabstops and the transformations of their tabstops' text,
+//! as well as offering the user's snippets in the completions.
+use std::{ops::Range, sync::Arc};
+
+use collections::HashMap;
+use fuzzy::{StringMatch, StringMatchCandidate};
+use gpui::{AppContext, Model, UniformListScrollHandle, ViewContext};
+use language::{Buffer, CharKind, CodeLabel, Documentation, Point};
+use lsp::LanguageServerId;
+use multi_buffer::{Anchor, MultiBufferRow, ToOffset};
+use parking_lot::{Mutex, RwLock};
+use project::Completion;
+use rand::Rng;
+use snippet_provider::SnippetRegistry;
+use text::ToOffset as _;
+use util::post_inc;
+
+use crate::{debounced_delay::DebouncedDelay, CompletionsMenu, ContextMenu, Editor};
+
+/// The server id of the completions, which do not come from a language server, like the user's
+/// snippets and the choices of a snippet's tabstop.
+pub(crate) const NON_LSP_SERVER_ID: LanguageServerId = LanguageServerId(usize::MAX);
+
+/// A range of an inserted snippet, showing a transformation of a tabstop's text.
+#[derive(Debug)]
+pub(crate) struct SnippetTransform {
+    range: Range<Anchor>,
+    /// The tabstop's range in the same insertion of the snippet.
+    source: Range<Anchor>,
+    transform: snippet::Transform,
+}
+
+impl SnippetTransform {
+    pub(crate) fn new(
+        range: Range<Anchor>,
+        source: Range<Anchor>,
+        transform: snippet::Transform,
+    ) -> Self {
+        Self {
+            range,
+            source,
+            transform,
+        }
+    }
+}
+
+impl Editor {
+    /// Returns the values of the snippet variables, like `$TM_FILENAME`, for the newest selection.
+    pub(crate) fn snippet_variables(
+        &self,
+        cx: &mut ViewContext<Self>,
+    ) -> HashMap<&'static str, String> {
+        let mut variables = HashMap::default();
+        let snapshot = self.buffer.read(cx).snapshot(cx);
+        let selection = self.selections.newest::<Point>(cx);
+        let head = selection.head();
+
+        variables.insert(
+            "TM_SELECTED_TEXT",
+            snapshot.text_for_range(selection.range()).collect(),
+        );
+        let line_len = snapshot.line_len(MultiBufferRow(head.row));
+        variables.insert(
+            "TM_CURRENT_LINE",
+            snapshot
+                .text_for_range(Point::new(head.row, 0)..Point::new(head.row, line_len))
+                .collect(),
+        );
+        let (word_range, kind) = snapshot.surrounding_word(head);
+        if kind == Some(CharKind::Word) {
+            variables.insert(
+                "TM_CURRENT_WORD",
+                snapshot.text_for_range(word_range).collect(),
+            );
+        }
+        variables.insert("TM_LINE_INDEX", head.row.to_string());
+        variables.insert("TM_LINE_NUMBER", (head.row + 1).to_string());
+
+        if let Some(file) = snapshot.file_at(head) {
+            let full_path = file.full_path(cx);
+            let abs_path = file
+                .as_local()
+                .map_or(full_path.clone(), |file| file.abs_path(cx));
+            let file_name = file.file_name(cx).to_string_lossy().into_owned();
+            let file_name_base = file_name
+                .split_once('.')
+                .map_or(file_name.as_str(), |(base, _)| base)
+                .to_string();
+            variables.insert("TM_FILENAME", file_name);
+            variables.insert("TM_FILENAME_BASE", file_name_base);
+            if let Some(directory) = abs_path.parent() {
+                variables.insert("TM_DIRECTORY", directory.to_string_lossy().into_owned());
+            }
+            variables.insert("TM_FILEPATH", abs_path.to_string_lossy().into_owned());
+            variables.insert(
+                "RELATIVE_FILEPATH",
+                file.path().to_string_lossy().into_owned(),
+            );
+            if let Some(worktree_name) = full_path.components().next() {
+                variables.insert(
+                    "WORKSPACE_NAME",
+                    worktree_name.as_os_str().to_string_lossy().into_owned(),
+                );
+            }
+        }
+
+        if let Some(scope) = snapshot.language_scope_at(head) {
+            if let Some(prefix) = scope.line_comment_prefixes().first() {
+                variables.insert("LINE_COMMENT", prefix.trim_end().to_string());
+            }
+            if let Some((start, end)) = scope.block_comment_delimiters() {
+                variables.insert("BLOCK_COMMENT_START", start.trim_end().to_string());
+                variables.insert("BLOCK_COMMENT_END", end.trim_start().to_string());
+            }
+        }
+
+        if let Some(item) = cx.read_from_clipboard() {
+            variables.insert("CLIPBOARD", item.text().clone());
+        }
+
+        let now = time::OffsetDateTime::now_utc().to_offset(cx.local_timezone());
+        let month_name = now.month().to_string();
+        let day_name = now.weekday().to_string();
+        variables.insert("CURRENT_YEAR", now.year().to_string());
+        variables.insert("CURRENT_YEAR_SHORT", format!("{:02}", now.year() % 100));
+        variables.insert("CURRENT_MONTH", format!("{:02}", u8::from(now.month())));
+        variables.insert("CURRENT_MONTH_NAME_SHORT", month_name[..3].to_string());
+        variables.insert("CURRENT_MONTH_NAME", month_name);
+        variables.insert("CURRENT_DATE", format!("{:02}", now.day()));
+        variables.insert("CURRENT_DAY_NAME_SHORT", day_name[..3].to_string());
+        variables.insert("CURRENT_DAY_NAME", day_name);
+        variables.insert("CURRENT_HOUR", format!("{:02}", now.hour()));
+        variables.insert("CURRENT_MINUTE", format!("{:02}", now.minute()));
+        variables.insert("CURRENT_SECOND", format!("{:02}", now.second()));
+        variables.insert("CURRENT_SECONDS_UNIX", now.unix_timestamp().to_string());
+        let offset = now.offset();
+        variables.insert(
+            "CURRENT_TIMEZONE_OFFSET",
+            format!(
+                "{}{:02}:{:02}",
+                if offset.is_negative() { '-' } else { '+' },
+                offset.whole_hours().abs(),
+                offset.minutes_past_hour().abs()
+            ),
+        );
+
+        let mut rng = rand::thread_rng();
+        variables.insert("RANDOM", format!("{:06}", rng.gen_range(0..1_000_000)));
+        variables.insert("RANDOM_HEX", format!("{:06x}", rng.gen_range(0..0x1000000)));
+        variables.insert("UUID", uuid::Uuid::new_v4().to_string());
+
+        variables
+    }
+
+    /// Replaces the transformed ranges with the transformation of their tabstop's current text.
+    pub(crate) fn apply_snippet_transforms(
+        &mut self,
+        transforms: &mut [SnippetTransform],
+        cx: &mut ViewContext<Self>,
+    ) {
+        if transforms.is_empty() {
+            return;
+        }
+
+        let snapshot = self.buffer.read(cx).snapshot(cx);
+        let mut edits = transforms
+            .iter()
+            .enumerate()
+            .map(|(ix, transform)| {
+                let source_text = snapshot
+                    .text_for_range(transform.source.clone())
+                    .collect::<String>();
+                let start = transform.range.start.to_offset(&snapshot);
+                let end = transform.range.end.to_offset(&snapshot).max(start);
+                (start..end, transform.transform.apply(&source_text), ix)
+            })
+            .collect::<Vec<_>>();
+        edits.sort_unstable_by_key(|(range, _, _)| range.start);
+
+        self.transact(cx, |this, cx| {
+            this.buffer.update(cx, |buffer, cx| {
+                buffer.edit(
+                    edits
+                        .iter()
+                        .map(|(range, text, _)| (range.clone(), text.clone())),
+                    None,
+                    cx,
+                );
+            });
+        });
+
+        // The edits collapse the transformed ranges, so that they are recreated around the new text.
+        let snapshot = self.buffer.read(cx).snapshot(cx);
+        let mut delta = 0_isize;
+        for (range, text, ix) in edits {
+            let start = (range.start as isize + delta) as usize;
+            transforms[ix].range =
+                snapshot.anchor_after(start)..snapshot.anchor_before(start + text.len());
+            delta += text.len() as isize - range.len() as isize;
+        }
+    }
+
+    /// Shows the choices of the tabstop at the newest selection in the completions menu.
+    pub(crate) fn show_snippet_choices(&mut self, choices: &[String], cx: &mut ViewContext<Self>) {
+        let selection = self.selections.newest_anchor().clone();
+        let buffer = self.buffer.read(cx);
+        let Some((buffer_handle, start)) = buffer.text_anchor_for_position(selection.start, cx)
+        else {
+            return;
+        };
+        let Some((_, end)) = buffer.text_anchor_for_position(selection.end, cx) else {
+            return;
+        };
+
+        let completions = choices
+            .iter()
+            .map(|choice| Completion {
+                old_range: start..end,
+                new_text: choice.clone(),
+                label: CodeLabel::plain(choice.clone(), None),
+                server_id: NON_LSP_SERVER_ID,
+                documentation: Some(Documentation::Undocumented),
+                lsp_completion: lsp::CompletionItem {
+                    label: choice.clone(),
+                    ..Default::default()
+                },
+            })
+            .collect::<Vec<_>>();
+        let match_candidates = choices
+            .iter()
+            .enumerate()
+            .map(|(id, choice)| StringMatchCandidate::new(id, choice.clone()))
+            .collect();
+        // The choices are shown in their order, rather than filtered by the text before the cursor.
+        let matches = choices
+            .iter()
+            .enumerate()
+            .map(|(candidate_id, choice)| StringMatch {
+                candidate_id,
+                score: Default::default(),
+                positions: Default::default(),
+                string: choice.clone(),
+            })
+            .collect();
+
+        let menu = CompletionsMenu {
+            id: post_inc(&mut self.next_completion_id),
+            initial_position: selection.start,
+            buffer: buffer_handle,
+            completions: Arc::new(RwLock::new(completions.into())),
+            match_candidates,
+            matches,
+            selected_item: 0,
+            scroll_handle: UniformListScrollHandle::new(),
+            selected_completion_documentation_resolve_debounce: Arc::new(Mutex::new(
+                DebouncedDelay::new(),
+            )),
+        };
+        self.completion_tasks.clear();
+        *self.context_menu.write() = Some(ContextMenu::Completions(menu));
+        self.discard_inline_completion(cx);
+        cx.notify();
+    }
+}
+
+/// Returns the user's snippets for the language at the position as completions, when the
+/// position follows a word.
+pub(crate) fn user_snippet_completions(
+    buffer: &Model<Buffer>,
+    buffer_position: text::Anchor,
+    cx: &AppContext,
+) -> Vec<Completion> {
+    let Some(registry) = SnippetRegistry::try_global(cx) else {
+        return Vec::new();
+    };
+    let snapshot = buffer.read(cx).snapshot();
+    let position = buffer_position.to_offset(&snapshot);
+    let (word_range, kind) = snapshot.surrounding_word(position);
+    if kind != Some(CharKind::Word) || word_range.start >= position {
+        return Vec::new();
+    }
+
+    let language_name = snapshot
+        .language_at(position)
+        .map(|language| language.name());
+    let old_range = snapshot.anchor_before(word_range.start)..buffer_position;
+    registry
+        .snippets_for_language(language_name.as_deref())
+        .into_iter()
+        .flat_map(|snippet| {
+            let old_range = old_range.clone();
+            snippet.prefixes.clone().into_iter().map(move |prefix| {
+                let description = snippet
+                    .description
+                    .clone()
+                    .unwrap_or_else(|| snippet.name.clone());
+                Completion {
+                    old_range: old_range.clone(),
+                    new_text: snippet.body.clone(),
+                    label: CodeLabel::plain(prefix.clone(), None),
+                    server_id: NON_LSP_SERVER_ID,
+                    documentation: Some(Documentation::SingleLine(description)),
+                    lsp_completion: lsp::CompletionItem {
+                        label: prefix,
+                        kind: Some(lsp::CompletionItemKind::SNIPPET),
+                        insert_text_format: Some(lsp::InsertTextFormat::SNIPPET),
+                        ..Default::default()
+                    },
+                }
+            })
+        })
+        .collect()
+}
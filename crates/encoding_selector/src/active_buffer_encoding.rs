@@ -0,0 +1,73 @@
+use editor::Editor;
+use gpui::{div, IntoElement, ParentElement, Render, Subscription, View, ViewContext, WeakView};
+use language::Encoding;
+use ui::{Button, ButtonCommon, Clickable, FluentBuilder, LabelSize, Tooltip};
+use workspace::{item::ItemHandle, StatusItemView, Workspace};
+
+use crate::EncodingSelector;
+
+pub struct ActiveBufferEncoding {
+    active_encoding: Option<Encoding>,
+    workspace: WeakView<Workspace>,
+    _observe_active_editor: Option<Subscription>,
+}
+
+impl ActiveBufferEncoding {
+    pub fn new(workspace: &Workspace) -> Self {
+        Self {
+            active_encoding: None,
+            workspace: workspace.weak_handle(),
+            _observe_active_editor: None,
+        }
+    }
+
+    fn update_encoding(&mut self, editor: View<Editor>, cx: &mut ViewContext<Self>) {
+        self.active_encoding = None;
+
+        let editor = editor.read(cx);
+        if let Some((_, buffer, _)) = editor.active_excerpt(cx) {
+            if let Some(file) = buffer.read(cx).file() {
+                self.active_encoding = Some(file.encoding());
+            }
+        }
+
+        cx.notify();
+    }
+}
+
+impl Render for ActiveBufferEncoding {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        div().when_some(self.active_encoding, |el, active_encoding| {
+            el.child(
+                Button::new("change-encoding", active_encoding.to_string())
+                    .label_size(LabelSize::Small)
+                    .on_click(cx.listener(|this, _, cx| {
+                        if let Some(workspace) = this.workspace.upgrade() {
+                            workspace.update(cx, |workspace, cx| {
+                                EncodingSelector::toggle(workspace, cx)
+                            });
+                        }
+                    }))
+                    .tooltip(|cx| Tooltip::text("Reopen with Encoding", cx)),
+            )
+        })
+    }
+}
+
+impl StatusItemView for ActiveBufferEncoding {
+    fn set_active_pane_item(
+        &mut self,
+        active_pane_item: Option<&dyn ItemHandle>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if let Some(editor) = active_pane_item.and_then(|item| item.act_as::<Editor>(cx)) {
+            self._observe_active_editor = Some(cx.observe(&editor, Self::update_encoding));
+            self.update_encoding(editor, cx);
+        } else {
+            self.active_encoding = None;
+            self._observe_active_editor = None;
+        }
+
+        cx.notify();
+    }
+}
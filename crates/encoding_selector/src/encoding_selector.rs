@@ -0,0 +1,231 @@
+mod active_buffer_encoding;
+
+pub use active_buffer_encoding::ActiveBufferEncoding;
+use editor::Editor;
+use fuzzy::{match_strings, StringMatch, StringMatchCandidate};
+use gpui::{
+    actions, AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, Model,
+    ParentElement, PromptLevel, Render, Styled, View, ViewContext, VisualContext, WeakView,
+};
+use language::{Buffer, Encoding};
+use picker::{Picker, PickerDelegate};
+use project::Project;
+use std::sync::Arc;
+use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
+use util::ResultExt;
+use workspace::{notifications::DetachAndPromptErr, ModalView, Workspace};
+
+actions!(encoding_selector, [ReopenWithEncoding]);
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(EncodingSelector::register).detach();
+}
+
+pub struct EncodingSelector {
+    picker: View<Picker<EncodingSelectorDelegate>>,
+}
+
+impl EncodingSelector {
+    fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+        workspace.register_action(move |workspace, _: &ReopenWithEncoding, cx| {
+            Self::toggle(workspace, cx);
+        });
+    }
+
+    fn toggle(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) -> Option<()> {
+        let (_, buffer, _) = workspace
+            .active_item(cx)?
+            .act_as::<Editor>(cx)?
+            .read(cx)
+            .active_excerpt(cx)?;
+        buffer.read(cx).file()?.as_local()?;
+        let project = workspace.project().clone();
+
+        workspace.toggle_modal(cx, move |cx| EncodingSelector::new(buffer, project, cx));
+        Some(())
+    }
+
+    fn new(buffer: Model<Buffer>, project: Model<Project>, cx: &mut ViewContext<Self>) -> Self {
+        let delegate = EncodingSelectorDelegate::new(cx.view().downgrade(), buffer, project);
+
+        let picker = cx.new_view(|cx| Picker::uniform_list(delegate, cx));
+        Self { picker }
+    }
+}
+
+impl Render for EncodingSelector {
+    fn render(&mut self, _cx: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex().w(rems(34.)).child(self.picker.clone())
+    }
+}
+
+impl FocusableView for EncodingSelector {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<DismissEvent> for EncodingSelector {}
+impl ModalView for EncodingSelector {}
+
+pub struct EncodingSelectorDelegate {
+    encoding_selector: WeakView<EncodingSelector>,
+    buffer: Model<Buffer>,
+    project: Model<Project>,
+    encodings: Vec<Encoding>,
+    candidates: Vec<StringMatchCandidate>,
+    matches: Vec<StringMatch>,
+    selected_index: usize,
+}
+
+impl EncodingSelectorDelegate {
+    fn new(
+        encoding_selector: WeakView<EncodingSelector>,
+        buffer: Model<Buffer>,
+        project: Model<Project>,
+    ) -> Self {
+        let encodings = Encoding::all();
+        let candidates = encodings
+            .iter()
+            .enumerate()
+            .map(|(candidate_id, encoding)| {
+                StringMatchCandidate::new(candidate_id, encoding.to_string())
+            })
+            .collect::<Vec<_>>();
+
+        Self {
+            encoding_selector,
+            buffer,
+            project,
+            encodings,
+            candidates,
+            matches: vec![],
+            selected_index: 0,
+        }
+    }
+}
+
+impl PickerDelegate for EncodingSelectorDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self, _cx: &mut WindowContext) -> Arc<str> {
+        "Reopen with encoding...".into()
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn confirm(&mut self, _: bool, cx: &mut ViewContext<Picker<Self>>) {
+        if let Some(mat) = self.matches.get(self.selected_index) {
+            let encoding = self.encodings[mat.candidate_id];
+            let project = self.project.clone();
+            let buffer = self.buffer.clone();
+            let discard_changes = if buffer.read(cx).is_dirty() {
+                Some(cx.prompt(
+                    PromptLevel::Warning,
+                    "Reopening the file discards its unsaved changes",
+                    None,
+                    &["Reopen", "Cancel"],
+                ))
+            } else {
+                None
+            };
+            cx.spawn(|_, mut cx| async move {
+                if let Some(discard_changes) = discard_changes {
+                    if discard_changes.await != Ok(0) {
+                        return Ok(());
+                    }
+                }
+                project
+                    .update(&mut cx, |project, cx| {
+                        project.reopen_buffer_with_encoding(buffer, encoding, cx)
+                    })?
+                    .await
+            })
+            .detach_and_prompt_err("Failed to reopen the file", cx, |error, _| {
+                Some(format!("{error:#}"))
+            });
+        }
+        self.dismissed(cx);
+    }
+
+    fn dismissed(&mut self, cx: &mut ViewContext<Picker<Self>>) {
+        self.encoding_selector
+            .update(cx, |_, cx| cx.emit(DismissEvent))
+            .log_err();
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(
+        &mut self,
+        query: String,
+        cx: &mut ViewContext<Picker<Self>>,
+    ) -> gpui::Task<()> {
+        let background = cx.background_executor().clone();
+        let candidates = self.candidates.clone();
+        cx.spawn(|this, mut cx| async move {
+            let matches = if query.is_empty() {
+                candidates
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, candidate)| StringMatch {
+                        candidate_id: index,
+                        string: candidate.string,
+                        positions: Vec::new(),
+                        score: 0.0,
+                    })
+                    .collect()
+            } else {
+                match_strings(
+                    &candidates,
+                    &query,
+                    false,
+                    100,
+                    &Default::default(),
+                    background,
+                )
+                .await
+            };
+
+            this.update(&mut cx, |this, cx| {
+                let delegate = &mut this.delegate;
+                delegate.matches = matches;
+                delegate.selected_index = delegate
+                    .selected_index
+                    .min(delegate.matches.len().saturating_sub(1));
+                cx.notify();
+            })
+            .log_err();
+        })
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        cx: &mut ViewContext<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let mat = &self.matches[ix];
+        let buffer_encoding = self.buffer.read(cx).file().map(|file| file.encoding());
+        let mut label = mat.string.clone();
+        if buffer_encoding.map_or(false, |encoding| encoding.to_string() == mat.string) {
+            label.push_str(" (current)");
+        }
+
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .selected(selected)
+                .child(HighlightedLabel::new(label, mat.positions.clone())),
+        )
+    }
+}
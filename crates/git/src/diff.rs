@@ -1,6 +1,6 @@
 use rope::Rope;
 use std::{iter, ops::Range};
-use sum_tree::SumTree;
+use sum_tree::{Bias, SumTree};
 use text::{Anchor, BufferId, BufferSnapshot, OffsetRangeExt, Point};
 
 pub use git2 as libgit;
@@ -178,7 +178,13 @@ impl BufferDiff {
         if let Some(patch) = patch {
             let mut divergence = 0;
             for hunk_index in 0..patch.num_hunks() {
-                let hunk = Self::process_patch_hunk(&patch, hunk_index, buffer, &mut divergence);
+                let hunk = Self::process_patch_hunk(
+                    &patch,
+                    hunk_index,
+                    diff_base,
+                    buffer,
+                    &mut divergence,
+                );
                 tree.push(hunk, buffer);
             }
         }
@@ -219,6 +225,7 @@ impl BufferDiff {
     fn process_patch_hunk(
         patch: &GitPatch<'_>,
         hunk_index: usize,
+        diff_base: &Rope,
         buffer: &text::BufferSnapshot,
         buffer_row_divergence: &mut i64,
     ) -> DiffHunk<Anchor> {
@@ -270,8 +277,13 @@ impl BufferDiff {
             row..row
         });
 
-        //unwrap_or addition without deletion
-        let diff_base_byte_range = diff_base_byte_range.unwrap_or(0..0);
+        //unwrap_or addition without deletion, which is inserted before the hunk's old start row
+        let diff_base_byte_range = diff_base_byte_range.unwrap_or_else(|| {
+            let (hunk, _) = patch.hunk(hunk_index).unwrap();
+            let start = Point::new(hunk.old_start(), 0);
+            let offset = diff_base.point_to_offset(diff_base.clip_point(start, Bias::Left));
+            offset..offset
+        });
 
         let start = Point::new(buffer_row_range.start, 0);
         let end = Point::new(buffer_row_range.end, 0);
@@ -285,6 +297,79 @@ impl BufferDiff {
     }
 }
 
+/// A range of lines, that differs between the old and the new texts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LineChange {
+    /// The rows of the old text, empty when lines were only added.
+    pub old_rows: Range<u32>,
+    /// The rows of the new text, empty when lines were only removed.
+    pub new_rows: Range<u32>,
+}
+
+impl LineChange {
+    /// How many more lines the new text has than the old one in this change.
+    pub fn line_delta(&self) -> i64 {
+        self.new_rows.len() as i64 - self.old_rows.len() as i64
+    }
+
+    /// Maps a row of the new text to the rows of the old text, given all of the changes between them, in order.
+    /// A changed row maps to the whole range of the old rows it replaced, an unchanged one to its single old row.
+    pub fn old_rows_for(changes: &[LineChange], new_row: u32) -> Range<u32> {
+        let mut divergence = 0_i64;
+        for change in changes {
+            if new_row < change.new_rows.start {
+                break;
+            }
+            // The added rows map to the empty range, where they were added.
+            if new_row < change.new_rows.end {
+                return change.old_rows.clone();
+            }
+            divergence = change.old_rows.end as i64 - change.new_rows.end as i64;
+        }
+        let old_row = (new_row as i64 + divergence).max(0) as u32;
+        old_row..old_row + 1
+    }
+}
+
+/// Returns the changed lines between the texts, in order.
+pub fn line_changes(old_text: &str, new_text: &str) -> Vec<LineChange> {
+    let mut options = GitOptions::new();
+    options.context_lines(0);
+    let patch = match GitPatch::from_buffers(
+        old_text.as_bytes(),
+        None,
+        new_text.as_bytes(),
+        None,
+        Some(&mut options),
+    ) {
+        Ok(patch) => patch,
+        Err(error) => {
+            log::error!("failed to diff the texts: {error}");
+            return Vec::new();
+        }
+    };
+
+    (0..patch.num_hunks())
+        .filter_map(|hunk_ix| {
+            let (hunk, _) = patch.hunk(hunk_ix).ok()?;
+            Some(LineChange {
+                old_rows: hunk_rows(hunk.old_start(), hunk.old_lines()),
+                new_rows: hunk_rows(hunk.new_start(), hunk.new_lines()),
+            })
+        })
+        .collect()
+}
+
+/// Git numbers lines from 1, and an empty range of lines starts after the line it is numbered with.
+fn hunk_rows(start: u32, lines: u32) -> Range<u32> {
+    let start = if lines == 0 {
+        start
+    } else {
+        start.saturating_sub(1)
+    };
+    start..start + lines
+}
+
 /// Range (crossing new lines), old, new
 #[cfg(any(test, feature = "test-support"))]
 #[track_caller]
@@ -421,4 +506,50 @@ mod tests {
             ],
         );
     }
+
+    fn change(old_rows: Range<u32>, new_rows: Range<u32>) -> LineChange {
+        LineChange { old_rows, new_rows }
+    }
+
+    #[test]
+    fn test_line_changes() {
+        assert_eq!(line_changes("a\nb\nc\n", "a\nb\nc\n"), Vec::new());
+        assert_eq!(
+            line_changes("a\nb\nc\n", "a\nB\nc\n"),
+            vec![change(1..2, 1..2)]
+        );
+        assert_eq!(
+            line_changes("a\nb\nc\n", "a\nb\nx\ny\nc\n"),
+            vec![change(2..2, 2..4)]
+        );
+        assert_eq!(
+            line_changes("a\nb\nc\nd\n", "a\nd\n"),
+            vec![change(1..3, 1..1)]
+        );
+        assert_eq!(line_changes("", "a\nb\n"), vec![change(0..0, 0..2)]);
+        assert_eq!(
+            line_changes("x\na\nb\nc\n", "a\nb\nc\ny\nz\n"),
+            vec![change(0..1, 0..0), change(4..4, 3..5)]
+        );
+    }
+
+    #[test]
+    fn test_line_delta() {
+        assert_eq!(change(1..3, 1..1).line_delta(), -2);
+        assert_eq!(change(2..2, 2..4).line_delta(), 2);
+        assert_eq!(change(1..2, 1..2).line_delta(), 0);
+    }
+
+    #[test]
+    fn test_old_rows_for() {
+        // "b" was changed into "B" and "C", "d" removed and "y" added after "e".
+        let changes = line_changes("a\nb\nc\nd\ne\n", "a\nB\nC\nc\ne\ny\n");
+        let old_rows = |row| LineChange::old_rows_for(&changes, row);
+        assert_eq!(old_rows(0), 0..1);
+        assert_eq!(old_rows(1), 1..2);
+        assert_eq!(old_rows(2), 1..2);
+        assert_eq!(old_rows(3), 2..3);
+        assert_eq!(old_rows(4), 4..5);
+        assert_eq!(old_rows(5), 5..5);
+    }
 }
@@ -0,0 +1,207 @@
+use crate::Oid;
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::path::Path;
+use std::process::Command;
+use time::macros::format_description;
+use time::{OffsetDateTime, UtcOffset};
+
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+
+/// The commits that changed a file, or some of its lines, latest first.
+#[derive(Debug, Clone, Default)]
+pub struct FileHistory {
+    pub entries: Vec<HistoryEntry>,
+    pub remote_url: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub sha: Oid,
+    pub author: String,
+    pub author_mail: String,
+    pub author_time: i64,
+    pub author_tz: String,
+    pub message: String,
+}
+
+impl HistoryEntry {
+    /// Returns the first line of the commit message.
+    pub fn summary(&self) -> &str {
+        self.message.lines().next().unwrap_or_default()
+    }
+
+    pub fn author_offset_date_time(&self) -> Result<OffsetDateTime> {
+        let format = format_description!("[offset_hour][offset_minute]");
+        let offset = UtcOffset::parse(&self.author_tz, &format)?;
+        let date_time_utc = OffsetDateTime::from_unix_timestamp(self.author_time)?;
+        Ok(date_time_utc.to_offset(offset))
+    }
+}
+
+impl FileHistory {
+    /// Returns the history of the file at `path`, relative to the working directory.
+    ///
+    /// With `rows`, only the commits that changed these rows of the file at HEAD are returned,
+    /// following the rows back through the history as they moved.
+    pub fn for_path(
+        git_binary: &Path,
+        working_directory: &Path,
+        path: &Path,
+        rows: Option<Range<u32>>,
+        remote_url: Option<String>,
+    ) -> Result<Self> {
+        let output = run_git_log(git_binary, working_directory, path, rows)?;
+        let entries = parse_git_log(&output)?;
+        Ok(Self {
+            entries,
+            remote_url,
+        })
+    }
+}
+
+const GIT_LOG_NO_COMMITS_ERROR: &'static str = "does not have any commits yet";
+const GIT_LOG_NO_PATH: &'static str = "There is no path";
+
+// Every entry starts with a record separator and its fields are separated by NUL bytes,
+// the message being the last one, as it spans multiple lines.
+const GIT_LOG_FORMAT: &'static str = "--format=%x1e%H%x00%an%x00%ae%x00%at%x00%ai%x00%B";
+
+fn run_git_log(
+    git_binary: &Path,
+    working_directory: &Path,
+    path: &Path,
+    rows: Option<Range<u32>>,
+) -> Result<String> {
+    let mut command = Command::new(git_binary);
+
+    command
+        .current_dir(working_directory)
+        .arg("log")
+        .arg("--no-patch")
+        .arg(GIT_LOG_FORMAT);
+
+    match rows {
+        Some(rows) => {
+            // `git log -L` takes 1-based inclusive line numbers.
+            let start_line = rows.start + 1;
+            let end_line = rows.end.max(start_line);
+            command.arg(format!(
+                "-L{start_line},{end_line}:{}",
+                path.to_string_lossy()
+            ));
+        }
+        None => {
+            command.arg("--").arg(path.as_os_str());
+        }
+    }
+
+    #[cfg(windows)]
+    command.creation_flags(windows::Win32::System::Threading::CREATE_NO_WINDOW.0);
+
+    let output = command
+        .output()
+        .map_err(|e| anyhow!("Failed to start git log process: {}", e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let trimmed = stderr.trim();
+        if trimmed.contains(GIT_LOG_NO_COMMITS_ERROR) || trimmed.contains(GIT_LOG_NO_PATH) {
+            return Ok(String::new());
+        }
+        return Err(anyhow!("git log process failed: {}", stderr));
+    }
+
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+// parse_git_log parses the output of `git log` with `GIT_LOG_FORMAT`, which looks like this,
+// with `<RS>` being the record separator and `<NUL>` a NUL byte:
+//
+//    <RS>6ad46b5257ba16d12c5ca9f0d4900320959df7f4<NUL>Joe Schmoe<NUL>joe.schmoe@example.com<NUL>1709741400<NUL>2024-03-06 17:10:00 +0100<NUL>Joe's cool commit
+//
+//    With a longer description.
+//
+// The author's time zone is the last part of the ISO-like author date.
+fn parse_git_log(output: &str) -> Result<Vec<HistoryEntry>> {
+    output
+        .split_terminator('\x1e')
+        .filter(|record| !record.trim().is_empty())
+        .map(|record| {
+            let mut fields = record.splitn(6, '\0');
+            let mut next_field = |name: &str| {
+                fields
+                    .next()
+                    .with_context(|| format!("failed to parse {name} of git log entry"))
+            };
+
+            let sha = next_field("sha")?.parse::<Oid>()?;
+            let author = next_field("author")?.to_string();
+            let author_mail = next_field("author mail")?.to_string();
+            let author_time = next_field("author time")?
+                .parse::<i64>()
+                .context("failed to parse author time")?;
+            let author_tz = next_field("author date")?
+                .rsplit(' ')
+                .next()
+                .unwrap_or_default()
+                .to_string();
+            let message = next_field("message")?.trim().to_string();
+
+            Ok(HistoryEntry {
+                sha,
+                author,
+                author_mail,
+                author_time,
+                author_tz,
+                message,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_git_log() {
+        let output = concat!(
+            "\x1ea060a80a19491ef24816656f02efd6a3abd33540\0Joe Schmoe\0joe.schmoe@example.com\0",
+            "1709741400\02024-03-06 17:10:00 +0100\0Change the second line\n\nWith a description.\n\n",
+            "\x1e59a39959619e22f0d8a0a82c36fe15e24cca14ee\0Jane Doe\0jane@example.com\0",
+            "1709740000\02024-03-06 10:46:40 -0500\0Initial commit\n\n",
+        );
+        let entries = parse_git_log(output).unwrap();
+
+        assert_eq!(
+            entries,
+            vec![
+                HistoryEntry {
+                    sha: "a060a80a19491ef24816656f02efd6a3abd33540".parse().unwrap(),
+                    author: "Joe Schmoe".into(),
+                    author_mail: "joe.schmoe@example.com".into(),
+                    author_time: 1709741400,
+                    author_tz: "+0100".into(),
+                    message: "Change the second line\n\nWith a description.".into(),
+                },
+                HistoryEntry {
+                    sha: "59a39959619e22f0d8a0a82c36fe15e24cca14ee".parse().unwrap(),
+                    author: "Jane Doe".into(),
+                    author_mail: "jane@example.com".into(),
+                    author_time: 1709740000,
+                    author_tz: "-0500".into(),
+                    message: "Initial commit".into(),
+                },
+            ]
+        );
+        assert_eq!(entries[0].summary(), "Change the second line");
+        assert_eq!(
+            entries[1].author_offset_date_time().unwrap().offset(),
+            UtcOffset::from_hms(-5, 0, 0).unwrap()
+        );
+        assert!(parse_git_log("").unwrap().is_empty());
+    }
+}
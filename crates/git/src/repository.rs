@@ -1,4 +1,5 @@
 use crate::blame::Blame;
+use crate::history::FileHistory;
 use crate::GitHostingProviderRegistry;
 use anyhow::{Context, Result};
 use collections::HashMap;
@@ -8,13 +9,18 @@ use rope::Rope;
 use serde::{Deserialize, Serialize};
 use std::{
     cmp::Ordering,
+    ops::Range,
     path::{Component, Path, PathBuf},
+    process::Command,
     sync::Arc,
     time::SystemTime,
 };
 use sum_tree::{MapSeekTarget, TreeMap};
 use util::{paths::PathExt, ResultExt};
 
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+
 pub use git2::Repository as LibGitRepository;
 
 #[derive(Clone, Debug, Hash, PartialEq)]
@@ -32,6 +38,9 @@ pub trait GitRepository: Send {
     /// Note that for symlink entries, this will return the contents of the symlink, not the target.
     fn load_index_text(&self, relative_file_path: &Path) -> Option<String>;
 
+    /// Loads the contents of the file at a revision, like `HEAD` or a commit sha, `None` if the file did not exist in it.
+    fn load_revision_text(&self, path: &RepoPath, revision: &str) -> Result<Option<String>>;
+
     /// Returns the URL of the remote with the given name.
     fn remote_url(&self, name: &str) -> Option<String>;
     fn branch_name(&self) -> Option<String>;
@@ -62,8 +71,35 @@ pub trait GitRepository: Send {
     fn create_branch(&self, _: &str) -> Result<()>;
 
     fn blame(&self, path: &Path, content: Rope) -> Result<crate::blame::Blame>;
+
+    /// Returns the commits that changed the file, or only the given rows of its HEAD version.
+    fn file_history(&self, path: &Path, rows: Option<Range<u32>>) -> Result<FileHistory>;
+
+    /// Returns the changed files in the repository, with their staged and unstaged changes.
+    fn changed_files(&self) -> Result<Vec<GitStatusEntry>>;
+
+    /// Adds the current contents of the files to the index, removing the deleted ones from it.
+    fn stage_paths(&self, paths: &[RepoPath]) -> Result<()>;
+
+    /// Resets the index entries of the files to their HEAD versions.
+    fn unstage_paths(&self, paths: &[RepoPath]) -> Result<()>;
+
+    /// Replaces the index contents of the file, removing it from the index if `content` is `None`.
+    fn set_index_text(&self, path: &RepoPath, content: Option<String>) -> Result<()>;
+
+    /// Commits the staged changes. When amending, an empty message keeps the message of the amended commit.
+    fn commit(&self, message: &str, amend: bool) -> Result<()>;
+
+    /// The remote operations only prepare the git invocation, the returned job talks to the remote
+    /// and is meant to run after the repository lock is released.
+    fn push(&self) -> Result<GitJob>;
+    fn pull(&self) -> Result<GitJob>;
+    fn fetch(&self) -> Result<GitJob>;
 }
 
+/// Work on a repository that does not need access to it, like running the git CLI.
+pub type GitJob = Box<dyn FnOnce() -> Result<()> + Send>;
+
 impl std::fmt::Debug for dyn GitRepository {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("dyn GitRepository<...>").finish()
@@ -121,6 +157,22 @@ impl GitRepository for RealGitRepository {
         None
     }
 
+    fn load_revision_text(&self, path: &RepoPath, revision: &str) -> Result<Option<String>> {
+        check_path_to_repo_path_errors(path)?;
+        let commit = self
+            .repository
+            .revparse_single(revision)
+            .with_context(|| format!("unknown revision {revision}"))?
+            .peel_to_commit()?;
+        let entry = match commit.tree()?.get_path(path) {
+            Ok(entry) => entry,
+            Err(error) if error.code() == git2::ErrorCode::NotFound => return Ok(None),
+            Err(error) => return Err(error.into()),
+        };
+        let content = self.repository.find_blob(entry.id())?.content().to_owned();
+        Ok(Some(String::from_utf8(content)?))
+    }
+
     fn remote_url(&self, name: &str) -> Option<String> {
         let remote = self.repository.find_remote(name).ok()?;
         remote.url().map(|url| url.to_string())
@@ -259,6 +311,211 @@ impl GitRepository for RealGitRepository {
             self.hosting_provider_registry.clone(),
         )
     }
+
+    fn file_history(&self, path: &Path, rows: Option<Range<u32>>) -> Result<FileHistory> {
+        let working_directory = self
+            .repository
+            .workdir()
+            .with_context(|| format!("failed to get git working directory for file {:?}", path))?;
+
+        const REMOTE_NAME: &str = "origin";
+        let remote_url = self.remote_url(REMOTE_NAME);
+
+        FileHistory::for_path(
+            &self.git_binary_path,
+            working_directory,
+            path,
+            rows,
+            remote_url,
+        )
+    }
+
+    fn changed_files(&self) -> Result<Vec<GitStatusEntry>> {
+        let mut options = git2::StatusOptions::new();
+        options.include_untracked(true);
+        options.recurse_untracked_dirs(true);
+        options.include_ignored(false);
+
+        let statuses = self.repository.statuses(Some(&mut options))?;
+        let mut entries = statuses
+            .iter()
+            .filter_map(|status| {
+                let path = RepoPath(PathBuf::try_from_bytes(status.path_bytes()).log_err()?);
+                let status = status.status();
+                let entry = if status.contains(git2::Status::CONFLICTED) {
+                    GitStatusEntry {
+                        path,
+                        staged: None,
+                        unstaged: Some(GitChangeKind::Conflict),
+                    }
+                } else {
+                    GitStatusEntry {
+                        path,
+                        staged: read_index_change(status),
+                        unstaged: read_worktree_change(status),
+                    }
+                };
+                (entry.staged.is_some() || entry.unstaged.is_some()).then_some(entry)
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(entries)
+    }
+
+    fn stage_paths(&self, paths: &[RepoPath]) -> Result<()> {
+        let working_directory = self
+            .repository
+            .workdir()
+            .context("failed to get git working directory")?;
+        let mut index = self.repository.index()?;
+        for path in paths {
+            check_path_to_repo_path_errors(path)?;
+            if working_directory.join(path).symlink_metadata().is_ok() {
+                index.add_path(path)?;
+            } else {
+                index.remove_path(path)?;
+            }
+        }
+        index.write()?;
+        Ok(())
+    }
+
+    fn unstage_paths(&self, paths: &[RepoPath]) -> Result<()> {
+        let head = self
+            .repository
+            .head()
+            .and_then(|head| head.peel_to_commit());
+        match head {
+            Ok(head) => {
+                self.repository.reset_default(
+                    Some(head.as_object()),
+                    paths.iter().map(|path| path.0.as_path()),
+                )?;
+            }
+            // Nothing is committed yet, so the files are just removed from the index.
+            Err(_) => {
+                let mut index = self.repository.index()?;
+                for path in paths {
+                    check_path_to_repo_path_errors(path)?;
+                    index.remove_path(path)?;
+                }
+                index.write()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn set_index_text(&self, path: &RepoPath, content: Option<String>) -> Result<()> {
+        check_path_to_repo_path_errors(path)?;
+        let mut index = self.repository.index()?;
+        match content {
+            Some(content) => {
+                const STAGE_NORMAL: i32 = 0;
+                const REGULAR_FILE_MODE: u32 = 0o100644;
+                let entry = match index.get_path(path, STAGE_NORMAL) {
+                    Some(entry) => entry,
+                    None => git2::IndexEntry {
+                        ctime: git2::IndexTime::new(0, 0),
+                        mtime: git2::IndexTime::new(0, 0),
+                        dev: 0,
+                        ino: 0,
+                        mode: REGULAR_FILE_MODE,
+                        uid: 0,
+                        gid: 0,
+                        file_size: 0,
+                        id: git2::Oid::zero(),
+                        flags: 0,
+                        flags_extended: 0,
+                        path: path.to_string_lossy().into_owned().into_bytes(),
+                    },
+                };
+                index.add_frombuffer(&entry, content.as_bytes())?;
+            }
+            None => index.remove_path(path)?,
+        }
+        index.write()?;
+        Ok(())
+    }
+
+    fn commit(&self, message: &str, amend: bool) -> Result<()> {
+        anyhow::ensure!(
+            amend || !message.trim().is_empty(),
+            "the commit message is empty"
+        );
+        let mut args = vec!["commit", "--quiet"];
+        if amend {
+            args.push("--amend");
+            if message.trim().is_empty() {
+                args.push("--no-edit");
+            }
+        }
+        if !message.trim().is_empty() {
+            args.extend(["--message", message]);
+        }
+        self.run_git(&args)
+    }
+
+    fn push(&self) -> Result<GitJob> {
+        let has_upstream = self
+            .branch_name()
+            .and_then(|name| self.repository.find_branch(&name, BranchType::Local).ok())
+            .map_or(false, |branch| branch.upstream().is_ok());
+        if has_upstream {
+            self.git_job(&["push"])
+        } else {
+            self.git_job(&["push", "--set-upstream", "origin", "HEAD"])
+        }
+    }
+
+    fn pull(&self) -> Result<GitJob> {
+        self.git_job(&["pull"])
+    }
+
+    fn fetch(&self) -> Result<GitJob> {
+        self.git_job(&["fetch"])
+    }
+}
+
+impl RealGitRepository {
+    /// Runs the git CLI in the working directory, so that the user's hooks, credential helpers and signing
+    /// configuration are respected.
+    fn run_git(&self, args: &[&str]) -> Result<()> {
+        self.git_job(args)?()
+    }
+
+    /// Prepares running the git CLI in the working directory, without borrowing the repository.
+    fn git_job(&self, args: &[&str]) -> Result<GitJob> {
+        let working_directory = self
+            .repository
+            .workdir()
+            .context("failed to get git working directory")?
+            .to_path_buf();
+        let git_binary_path = self.git_binary_path.clone();
+        let args = args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        Ok(Box::new(move || {
+            let mut command = Command::new(&git_binary_path);
+            command
+                .current_dir(working_directory)
+                .args(&args)
+                // There is no terminal to ask for the credentials in, nor an editor to wait for.
+                .env("GIT_TERMINAL_PROMPT", "0")
+                .env("GIT_EDITOR", "true");
+
+            #[cfg(windows)]
+            command.creation_flags(windows::Win32::System::Threading::CREATE_NO_WINDOW.0);
+
+            let output = command
+                .output()
+                .with_context(|| format!("failed to run git {}", args[0]))?;
+            anyhow::ensure!(
+                output.status.success(),
+                "git {} failed: {}",
+                args[0],
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+            Ok(())
+        }))
+    }
 }
 
 fn matches_index(repo: &LibGitRepository, path: &RepoPath, mtime: SystemTime) -> bool {
@@ -276,6 +533,34 @@ fn matches_index(repo: &LibGitRepository, path: &RepoPath, mtime: SystemTime) ->
     false
 }
 
+fn read_index_change(status: git2::Status) -> Option<GitChangeKind> {
+    if status.contains(git2::Status::INDEX_NEW) {
+        Some(GitChangeKind::Added)
+    } else if status.contains(git2::Status::INDEX_DELETED) {
+        Some(GitChangeKind::Deleted)
+    } else if status.intersects(
+        git2::Status::INDEX_MODIFIED | git2::Status::INDEX_RENAMED | git2::Status::INDEX_TYPECHANGE,
+    ) {
+        Some(GitChangeKind::Modified)
+    } else {
+        None
+    }
+}
+
+fn read_worktree_change(status: git2::Status) -> Option<GitChangeKind> {
+    if status.contains(git2::Status::WT_NEW) {
+        Some(GitChangeKind::Added)
+    } else if status.contains(git2::Status::WT_DELETED) {
+        Some(GitChangeKind::Deleted)
+    } else if status.intersects(
+        git2::Status::WT_MODIFIED | git2::Status::WT_RENAMED | git2::Status::WT_TYPECHANGE,
+    ) {
+        Some(GitChangeKind::Modified)
+    } else {
+        None
+    }
+}
+
 fn read_status(status: git2::Status) -> Option<GitFileStatus> {
     if status.contains(git2::Status::CONFLICTED) {
         Some(GitFileStatus::Conflict)
@@ -301,9 +586,20 @@ pub struct FakeGitRepository {
 #[derive(Debug, Clone, Default)]
 pub struct FakeGitRepositoryState {
     pub index_contents: HashMap<PathBuf, String>,
+    /// The contents of the files at each revision, by revision.
+    pub revision_contents: HashMap<String, HashMap<PathBuf, String>>,
     pub blames: HashMap<PathBuf, Blame>,
+    pub file_histories: HashMap<PathBuf, FileHistory>,
     pub worktree_statuses: HashMap<RepoPath, GitFileStatus>,
     pub branch_name: Option<String>,
+    /// The changed files, as reported by `changed_files`, which staging and committing update.
+    pub changed_files: Vec<GitStatusEntry>,
+    /// The messages of the commits, made in the repository, latest last.
+    pub commit_messages: Vec<String>,
+    /// How many commits were not pushed yet.
+    pub unpushed_commits: usize,
+    /// The remote operations, run in the repository, latest last.
+    pub remote_operations: Vec<&'static str>,
 }
 
 impl FakeGitRepository {
@@ -320,6 +616,15 @@ impl GitRepository for FakeGitRepository {
         state.index_contents.get(path).cloned()
     }
 
+    fn load_revision_text(&self, path: &RepoPath, revision: &str) -> Result<Option<String>> {
+        let state = self.state.lock();
+        let contents = state
+            .revision_contents
+            .get(revision)
+            .with_context(|| format!("unknown revision {revision}"))?;
+        Ok(contents.get(&path.0).cloned())
+    }
+
     fn remote_url(&self, _name: &str) -> Option<String> {
         None
     }
@@ -377,6 +682,109 @@ impl GitRepository for FakeGitRepository {
             .with_context(|| format!("failed to get blame for {:?}", path))
             .cloned()
     }
+
+    fn file_history(&self, path: &Path, _rows: Option<Range<u32>>) -> Result<FileHistory> {
+        let state = self.state.lock();
+        state
+            .file_histories
+            .get(path)
+            .with_context(|| format!("failed to get history for {:?}", path))
+            .cloned()
+    }
+
+    fn changed_files(&self) -> Result<Vec<GitStatusEntry>> {
+        let state = self.state.lock();
+        Ok(state.changed_files.clone())
+    }
+
+    fn stage_paths(&self, paths: &[RepoPath]) -> Result<()> {
+        let mut state = self.state.lock();
+        for entry in &mut state.changed_files {
+            if paths.contains(&entry.path) {
+                if let Some(change) = entry.unstaged.take() {
+                    entry.staged = Some(change);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn unstage_paths(&self, paths: &[RepoPath]) -> Result<()> {
+        let mut state = self.state.lock();
+        for entry in &mut state.changed_files {
+            if paths.contains(&entry.path) {
+                if let Some(change) = entry.staged.take() {
+                    entry.unstaged = Some(change);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn set_index_text(&self, path: &RepoPath, content: Option<String>) -> Result<()> {
+        let mut state = self.state.lock();
+        match content {
+            Some(content) => state.index_contents.insert(path.0.clone(), content),
+            None => state.index_contents.remove(&path.0),
+        };
+        Ok(())
+    }
+
+    fn commit(&self, message: &str, amend: bool) -> Result<()> {
+        let mut state = self.state.lock();
+        if amend {
+            let last_message = state
+                .commit_messages
+                .pop()
+                .context("there is no commit to amend")?;
+            let message = if message.trim().is_empty() {
+                last_message
+            } else {
+                message.to_string()
+            };
+            state.commit_messages.push(message);
+        } else {
+            anyhow::ensure!(!message.trim().is_empty(), "the commit message is empty");
+            let has_staged_changes = state
+                .changed_files
+                .iter()
+                .any(|entry| entry.staged.is_some());
+            anyhow::ensure!(has_staged_changes, "there are no staged changes to commit");
+            state.commit_messages.push(message.to_string());
+            state.unpushed_commits += 1;
+        }
+        state.changed_files.retain_mut(|entry| {
+            entry.staged = None;
+            entry.unstaged.is_some()
+        });
+        Ok(())
+    }
+
+    fn push(&self) -> Result<GitJob> {
+        let state = self.state.clone();
+        Ok(Box::new(move || {
+            let mut state = state.lock();
+            state.unpushed_commits = 0;
+            state.remote_operations.push("push");
+            Ok(())
+        }))
+    }
+
+    fn pull(&self) -> Result<GitJob> {
+        let state = self.state.clone();
+        Ok(Box::new(move || {
+            state.lock().remote_operations.push("pull");
+            Ok(())
+        }))
+    }
+
+    fn fetch(&self) -> Result<GitJob> {
+        let state = self.state.clone();
+        Ok(Box::new(move || {
+            state.lock().remote_operations.push("fetch");
+            Ok(())
+        }))
+    }
 }
 
 fn check_path_to_repo_path_errors(relative_file_path: &Path) -> Result<()> {
@@ -440,6 +848,23 @@ impl GitFileStatus {
     }
 }
 
+/// How a file was changed, in the index or in the working directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GitChangeKind {
+    Added,
+    Modified,
+    Deleted,
+    Conflict,
+}
+
+/// A changed file in the repository, with its changes staged in the index and made in the working directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitStatusEntry {
+    pub path: RepoPath,
+    pub staged: Option<GitChangeKind>,
+    pub unstaged: Option<GitChangeKind>,
+}
+
 #[derive(Clone, Debug, Ord, Hash, PartialOrd, Eq, PartialEq)]
 pub struct RepoPath(pub PathBuf);
 
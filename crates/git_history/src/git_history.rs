@@ -0,0 +1,700 @@
+use std::ops::Range;
+
+use anyhow::{Context as _, Result};
+use diff_view::{buffer_title, text_buffer_like, DiffView};
+use editor::Editor;
+use git::{
+    diff::{line_changes, LineChange},
+    history::{FileHistory, HistoryEntry},
+    parse_git_remote_url, BuildCommitPermalinkParams, GitHostingProviderRegistry,
+};
+use gpui::{
+    actions, uniform_list, AnyElement, AppContext, EventEmitter, FocusHandle, FocusableView,
+    InteractiveElement, IntoElement, Model, ParentElement, Render, SharedString, Styled, Task,
+    UniformListScrollHandle, View, ViewContext, VisualContext, WeakView, WindowContext,
+};
+use language::{Buffer, Point};
+use project::Project;
+use ui::{prelude::*, ListItem, Tooltip};
+use url::Url;
+use util::ResultExt;
+use workspace::{
+    item::{Item, ItemEvent, TabContentParams},
+    notifications::NotifyTaskExt,
+    Workspace,
+};
+
+actions!(
+    git_history,
+    [
+        ShowFileHistory,
+        ShowLineHistory,
+        CompareWithPrevious,
+        CompareWithCurrent,
+        OpenPermalink,
+    ]
+);
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(|workspace: &mut Workspace, _| {
+        workspace
+            .register_action(|workspace, _: &ShowFileHistory, cx| {
+                show_history(workspace, false, cx);
+            })
+            .register_action(|workspace, _: &ShowLineHistory, cx| {
+                show_history(workspace, true, cx);
+            });
+    })
+    .detach();
+}
+
+/// Opens the history of the active editor's file, or only of its selected lines.
+fn show_history(workspace: &mut Workspace, for_selection: bool, cx: &mut ViewContext<Workspace>) {
+    let Some(editor) = workspace
+        .active_item(cx)
+        .and_then(|item| item.act_as::<Editor>(cx))
+    else {
+        return;
+    };
+    let Some(buffer) = editor.read(cx).buffer().read(cx).as_singleton() else {
+        return;
+    };
+    let rows = for_selection.then(|| {
+        let selection = editor.update(cx, |editor, cx| editor.selections.newest::<Point>(cx));
+        // A selection ending at the start of a line does not include it.
+        let end_row = if selection.end.column == 0 && selection.end.row > selection.start.row {
+            selection.end.row
+        } else {
+            selection.end.row + 1
+        };
+        selection.start.row..end_row
+    });
+    let view = GitHistoryView::new(
+        workspace.weak_handle(),
+        workspace.project().clone(),
+        buffer,
+        rows,
+        cx,
+    );
+    workspace.add_item_to_active_pane(Box::new(view), None, cx);
+}
+
+/// The commits that changed a file, or some of its lines, with the file at the selected one.
+pub struct GitHistoryView {
+    workspace: WeakView<Workspace>,
+    project: Model<Project>,
+    buffer: Model<Buffer>,
+    /// The rows of the buffer, whose history is shown, the whole file's when `None`.
+    rows: Option<Range<u32>>,
+    title: SharedString,
+    entries: Vec<HistoryEntry>,
+    /// The links to the commits on the hosting provider of the repository's remote.
+    permalinks: Vec<Option<Url>>,
+    hosting_provider_name: Option<String>,
+    selected_index: Option<usize>,
+    revision_editor: Option<View<Editor>>,
+    error: Option<SharedString>,
+    is_loading: bool,
+    focus_handle: FocusHandle,
+    scroll_handle: UniformListScrollHandle,
+    _load_history_task: Task<()>,
+    load_revision_task: Task<()>,
+}
+
+impl GitHistoryView {
+    pub fn new(
+        workspace: WeakView<Workspace>,
+        project: Model<Project>,
+        buffer: Model<Buffer>,
+        rows: Option<Range<u32>>,
+        cx: &mut WindowContext,
+    ) -> View<Self> {
+        cx.new_view(|cx| {
+            let file_name = buffer_title(&buffer, cx);
+            let title = match &rows {
+                Some(rows) if rows.len() > 1 => {
+                    format!("{file_name}:{}-{}", rows.start + 1, rows.end)
+                }
+                Some(rows) => format!("{file_name}:{}", rows.start + 1),
+                None => file_name.to_string(),
+            };
+            let history = Self::load_history(&project, &buffer, rows.clone(), cx);
+            let load_history_task = cx.spawn(|this, mut cx| async move {
+                let history = history.await;
+                this.update(&mut cx, |this, cx| {
+                    this.is_loading = false;
+                    match history {
+                        Ok(history) => this.set_history(history, cx),
+                        Err(error) => this.error = Some(format!("{error:#}").into()),
+                    }
+                    cx.notify();
+                })
+                .log_err();
+            });
+
+            Self {
+                workspace,
+                project,
+                buffer,
+                rows,
+                title: title.into(),
+                entries: Vec::new(),
+                permalinks: Vec::new(),
+                hosting_provider_name: None,
+                selected_index: None,
+                revision_editor: None,
+                error: None,
+                is_loading: true,
+                focus_handle: cx.focus_handle(),
+                scroll_handle: UniformListScrollHandle::new(),
+                _load_history_task: load_history_task,
+                load_revision_task: Task::ready(()),
+            }
+        })
+    }
+
+    /// Loads the history of the file, or of the buffer rows, which are mapped to the rows of the HEAD version
+    /// `git log -L` works with.
+    fn load_history(
+        project: &Model<Project>,
+        buffer: &Model<Buffer>,
+        rows: Option<Range<u32>>,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Result<FileHistory>> {
+        let Some(rows) = rows else {
+            return project.read(cx).git_file_history(buffer, None, cx);
+        };
+        let head_text = project
+            .read(cx)
+            .git_revision_text(buffer, "HEAD".to_string(), cx);
+        let buffer_text = buffer.read(cx).text();
+        let project = project.clone();
+        let buffer = buffer.clone();
+        cx.spawn(|_, mut cx| async move {
+            let head_text = head_text.await?.context("the file is not committed yet")?;
+            let head_rows = cx
+                .background_executor()
+                .spawn(async move { head_rows(&head_text, &buffer_text, rows) })
+                .await
+                .context("the selected lines are not committed yet")?;
+            project
+                .update(&mut cx, |project, cx| {
+                    project.git_file_history(&buffer, Some(head_rows), cx)
+                })?
+                .await
+        })
+    }
+
+    fn set_history(&mut self, history: FileHistory, cx: &mut ViewContext<Self>) {
+        let provider_registry = GitHostingProviderRegistry::default_global(cx);
+        let parsed_remote_url = history
+            .remote_url
+            .as_deref()
+            .and_then(|remote_url| parse_git_remote_url(provider_registry, remote_url));
+        self.hosting_provider_name = parsed_remote_url
+            .as_ref()
+            .map(|(provider, _)| provider.name());
+        self.permalinks = history
+            .entries
+            .iter()
+            .map(|entry| {
+                let (provider, remote) = parsed_remote_url.as_ref()?;
+                Some(provider.build_commit_permalink(
+                    remote,
+                    BuildCommitPermalinkParams {
+                        sha: entry.sha.to_string().as_str(),
+                    },
+                ))
+            })
+            .collect();
+        self.entries = history.entries;
+        if !self.entries.is_empty() {
+            self.select_entry(0, cx);
+        }
+    }
+
+    /// Shows the file at the entry's commit.
+    fn select_entry(&mut self, ix: usize, cx: &mut ViewContext<Self>) {
+        let Some(entry) = self.entries.get(ix) else {
+            return;
+        };
+        self.selected_index = Some(ix);
+        self.scroll_handle.scroll_to_item(ix);
+        let revision_text =
+            self.project
+                .read(cx)
+                .git_revision_text(&self.buffer, entry.sha.to_string(), cx);
+        self.load_revision_task = cx.spawn(|this, mut cx| async move {
+            let revision_text = revision_text.await;
+            this.update(&mut cx, |this, cx| {
+                match revision_text {
+                    Ok(revision_text) => {
+                        // The file does not exist at the commit, which deleted it.
+                        let buffer =
+                            text_buffer_like(revision_text.unwrap_or_default(), &this.buffer, cx);
+                        let project = this.project.clone();
+                        this.revision_editor = Some(cx.new_view(|cx| {
+                            let mut editor = Editor::for_buffer(buffer, Some(project), cx);
+                            editor.set_read_only(true);
+                            editor
+                        }));
+                    }
+                    Err(error) => this.error = Some(format!("{error:#}").into()),
+                }
+                cx.notify();
+            })
+            .log_err();
+        });
+        cx.notify();
+    }
+
+    fn select_next(&mut self, _: &menu::SelectNext, cx: &mut ViewContext<Self>) {
+        let ix = self.selected_index.map_or(0, |ix| ix + 1);
+        if ix < self.entries.len() {
+            self.select_entry(ix, cx);
+        }
+    }
+
+    fn select_prev(&mut self, _: &menu::SelectPrev, cx: &mut ViewContext<Self>) {
+        if let Some(ix) = self.selected_index.and_then(|ix| ix.checked_sub(1)) {
+            self.select_entry(ix, cx);
+        }
+    }
+
+    fn select_first(&mut self, _: &menu::SelectFirst, cx: &mut ViewContext<Self>) {
+        self.select_entry(0, cx);
+    }
+
+    fn select_last(&mut self, _: &menu::SelectLast, cx: &mut ViewContext<Self>) {
+        if let Some(ix) = self.entries.len().checked_sub(1) {
+            self.select_entry(ix, cx);
+        }
+    }
+
+    fn confirm(&mut self, _: &menu::Confirm, cx: &mut ViewContext<Self>) {
+        self.compare_with_previous(&CompareWithPrevious, cx);
+    }
+
+    fn selected_entry(&self) -> Option<&HistoryEntry> {
+        self.entries.get(self.selected_index?)
+    }
+
+    /// Opens the changes, that the selected commit made to the file.
+    fn compare_with_previous(&mut self, _: &CompareWithPrevious, cx: &mut ViewContext<Self>) {
+        let Some(entry) = self.selected_entry() else {
+            return;
+        };
+        let sha = entry.sha.to_string();
+        let short_sha = entry.sha.display_short();
+        let project = self.project.read(cx);
+        let old_text = project.git_revision_text(&self.buffer, format!("{sha}^"), cx);
+        let new_text = project.git_revision_text(&self.buffer, sha, cx);
+        let buffer = self.buffer.clone();
+        let project = self.project.clone();
+        let workspace = self.workspace.clone();
+        cx.spawn(|_, mut cx| async move {
+            // The root commit has no parent, all of its lines were added.
+            let old_text = old_text.await.ok().flatten().unwrap_or_default();
+            let new_text = new_text.await?.unwrap_or_default();
+            workspace.update(&mut cx, |workspace, cx| {
+                let title = buffer_title(&buffer, cx);
+                let old_buffer = text_buffer_like(old_text, &buffer, cx);
+                let new_buffer = text_buffer_like(new_text, &buffer, cx);
+                let view = DiffView::new(
+                    old_buffer,
+                    format!("{title} ({short_sha}^)").into(),
+                    new_buffer,
+                    format!("{title} ({short_sha})").into(),
+                    project,
+                    cx,
+                );
+                workspace.add_item_to_active_pane(Box::new(view), None, cx);
+            })
+        })
+        .detach_and_notify_err(cx);
+    }
+
+    /// Opens the changes to the file since the selected commit.
+    fn compare_with_current(&mut self, _: &CompareWithCurrent, cx: &mut ViewContext<Self>) {
+        let Some(entry) = self.selected_entry() else {
+            return;
+        };
+        let revision = entry.sha.to_string();
+        let buffer = self.buffer.clone();
+        self.workspace
+            .update(cx, |workspace, cx| {
+                diff_view::compare_with_revision(workspace, buffer, revision, cx);
+            })
+            .log_err();
+    }
+
+    fn open_permalink(&mut self, _: &OpenPermalink, cx: &mut ViewContext<Self>) {
+        let permalink = self
+            .selected_index
+            .and_then(|ix| self.permalinks.get(ix)?.as_ref());
+        if let Some(permalink) = permalink {
+            cx.open_url(permalink.as_str());
+        }
+    }
+
+    fn render_entry(&self, ix: usize, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let entry = &self.entries[ix];
+        let timestamp = match entry.author_offset_date_time() {
+            Ok(timestamp) => time_format::format_localized_timestamp(
+                timestamp,
+                time::OffsetDateTime::now_utc(),
+                cx.local_timezone(),
+                time_format::TimestampFormat::Relative,
+            ),
+            Err(_) => "Error parsing date".to_string(),
+        };
+        ListItem::new(ix)
+            .selected(self.selected_index == Some(ix))
+            .child(
+                v_flex()
+                    .py_0p5()
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .child(Label::new(entry.sha.display_short()).color(Color::Muted))
+                            .child(Label::new(entry.summary().to_string()).single_line()),
+                    )
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .child(
+                                Label::new(entry.author.clone())
+                                    .size(LabelSize::Small)
+                                    .color(Color::Muted)
+                                    .single_line(),
+                            )
+                            .child(
+                                Label::new(timestamp)
+                                    .size(LabelSize::Small)
+                                    .color(Color::Muted),
+                            ),
+                    ),
+            )
+            .on_click(cx.listener(move |this, _, cx| this.select_entry(ix, cx)))
+    }
+
+    fn render_revision(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let Some((ix, entry)) = self
+            .selected_index
+            .and_then(|ix| Some((ix, self.entries.get(ix)?)))
+        else {
+            return div().flex_1().into_any_element();
+        };
+        let permalink_button = self.permalinks[ix].as_ref().map(|_| {
+            let label = match &self.hosting_provider_name {
+                Some(name) => format!("Open on {name}"),
+                None => "Open Permalink".to_string(),
+            };
+            Button::new("open-permalink", label)
+                .icon(IconName::ExternalLink)
+                .icon_size(IconSize::Small)
+                .icon_position(IconPosition::End)
+                .on_click(cx.listener(|this, _, cx| this.open_permalink(&OpenPermalink, cx)))
+        });
+        v_flex()
+            .flex_1()
+            .min_w_0()
+            .h_full()
+            .child(
+                h_flex()
+                    .px_2()
+                    .h_8()
+                    .gap_1()
+                    .border_b_1()
+                    .border_color(cx.theme().colors().border)
+                    .child(
+                        Label::new(format!(
+                            "{} ({})",
+                            buffer_title(&self.buffer, cx),
+                            entry.sha.display_short()
+                        ))
+                        .single_line(),
+                    )
+                    .child(div().flex_1())
+                    .child(
+                        Button::new("compare-with-previous", "Compare with Previous")
+                            .tooltip(|cx| {
+                                Tooltip::for_action(
+                                    "Changes Made by the Commit",
+                                    &CompareWithPrevious,
+                                    cx,
+                                )
+                            })
+                            .on_click(cx.listener(|this, _, cx| {
+                                this.compare_with_previous(&CompareWithPrevious, cx)
+                            })),
+                    )
+                    .child(
+                        Button::new("compare-with-current", "Compare with Current")
+                            .tooltip(|cx| {
+                                Tooltip::for_action(
+                                    "Changes Since the Commit",
+                                    &CompareWithCurrent,
+                                    cx,
+                                )
+                            })
+                            .on_click(cx.listener(|this, _, cx| {
+                                this.compare_with_current(&CompareWithCurrent, cx)
+                            })),
+                    )
+                    .children(permalink_button),
+            )
+            .children(
+                self.revision_editor
+                    .clone()
+                    .map(|editor| div().flex_1().min_h_0().child(editor)),
+            )
+            .into_any_element()
+    }
+}
+
+impl EventEmitter<ItemEvent> for GitHistoryView {}
+
+impl FocusableView for GitHistoryView {
+    fn focus_handle(&self, _: &AppContext) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Render for GitHistoryView {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let content = if let Some(error) = self.error.clone() {
+            div()
+                .p_4()
+                .child(Label::new(error).color(Color::Error))
+                .into_any_element()
+        } else if self.is_loading {
+            div()
+                .p_4()
+                .child(Label::new("Loading history…").color(Color::Muted))
+                .into_any_element()
+        } else if self.entries.is_empty() {
+            div()
+                .p_4()
+                .child(Label::new("No commits changed the file").color(Color::Muted))
+                .into_any_element()
+        } else {
+            h_flex()
+                .size_full()
+                .child(
+                    uniform_list(
+                        cx.view().clone(),
+                        "git-history-entries",
+                        self.entries.len(),
+                        |this, range, cx| range.map(|ix| this.render_entry(ix, cx)).collect(),
+                    )
+                    .w_2_5()
+                    .h_full()
+                    .track_scroll(self.scroll_handle.clone()),
+                )
+                .child(div().w_px().h_full().bg(cx.theme().colors().border))
+                .child(self.render_revision(cx))
+                .into_any_element()
+        };
+
+        v_flex()
+            .key_context("GitHistoryView")
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(Self::select_next))
+            .on_action(cx.listener(Self::select_prev))
+            .on_action(cx.listener(Self::select_first))
+            .on_action(cx.listener(Self::select_last))
+            .on_action(cx.listener(Self::confirm))
+            .on_action(cx.listener(Self::compare_with_previous))
+            .on_action(cx.listener(Self::compare_with_current))
+            .on_action(cx.listener(Self::open_permalink))
+            .size_full()
+            .bg(cx.theme().colors().editor_background)
+            .child(content)
+    }
+}
+
+impl Item for GitHistoryView {
+    type Event = ItemEvent;
+
+    fn tab_content(&self, params: TabContentParams, _: &WindowContext) -> AnyElement {
+        Label::new(format!("History of {}", self.title))
+            .single_line()
+            .color(if params.selected {
+                Color::Default
+            } else {
+                Color::Muted
+            })
+            .into_any_element()
+    }
+
+    fn telemetry_event_text(&self) -> Option<&'static str> {
+        Some("git history")
+    }
+
+    fn show_toolbar(&self) -> bool {
+        false
+    }
+
+    fn clone_on_split(
+        &self,
+        _workspace_id: workspace::WorkspaceId,
+        cx: &mut ViewContext<Self>,
+    ) -> Option<View<Self>> {
+        Some(GitHistoryView::new(
+            self.workspace.clone(),
+            self.project.clone(),
+            self.buffer.clone(),
+            self.rows.clone(),
+            cx,
+        ))
+    }
+
+    fn to_item_events(event: &Self::Event, mut f: impl FnMut(ItemEvent)) {
+        f(*event)
+    }
+}
+
+/// Maps the buffer rows to the rows of the HEAD text, `None` when the lines were added after it.
+fn head_rows(head_text: &str, buffer_text: &str, rows: Range<u32>) -> Option<Range<u32>> {
+    let head_text = head_text.replace("\r\n", "\n");
+    let changes = line_changes(&head_text, buffer_text);
+    let start = LineChange::old_rows_for(&changes, rows.start).start;
+    let end = LineChange::old_rows_for(&changes, rows.end.saturating_sub(1).max(rows.start))
+        .end
+        .min(head_text.lines().count() as u32);
+    (start < end).then_some(start..end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use editor::scroll::Autoscroll;
+    use gpui::TestAppContext;
+    use project::FakeFs;
+    use serde_json::json;
+    use std::path::{Path, PathBuf};
+    use workspace::AppState;
+
+    #[gpui::test]
+    async fn test_line_history(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree("/dir", json!({ ".git": {}, "a.txt": "one\ntwo\nthree\n" }))
+            .await;
+        let entries = vec![
+            history_entry("a060a80a19491ef24816656f02efd6a3abd33540", "Change two"),
+            history_entry("59a39959619e22f0d8a0a82c36fe15e24cca14ee", "Add a.txt"),
+        ];
+        fs.with_git_state(Path::new("/dir/.git"), true, |state| {
+            state.file_histories.insert(
+                PathBuf::from("a.txt"),
+                FileHistory {
+                    entries: entries.clone(),
+                    remote_url: None,
+                },
+            );
+            for (sha, text) in [
+                ("HEAD", "one\ntwo\nthree\n"),
+                ("a060a80a19491ef24816656f02efd6a3abd33540", "one\ntwo\n"),
+                ("59a39959619e22f0d8a0a82c36fe15e24cca14ee", "one\n2\n"),
+            ] {
+                state.revision_contents.insert(
+                    sha.to_string(),
+                    [(PathBuf::from("a.txt"), text.to_string())]
+                        .into_iter()
+                        .collect(),
+                );
+            }
+        });
+        let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
+        let editor = workspace
+            .update(cx, |workspace, cx| {
+                workspace.open_abs_path(PathBuf::from("/dir/a.txt"), true, cx)
+            })
+            .await
+            .unwrap()
+            .downcast::<Editor>()
+            .unwrap();
+        editor.update(cx, |editor, cx| {
+            editor.change_selections(Some(Autoscroll::fit()), cx, |selections| {
+                selections.select_ranges([Point::new(1, 0)..Point::new(2, 0)])
+            });
+        });
+
+        cx.dispatch_action(ShowLineHistory);
+        cx.run_until_parked();
+        let view = workspace.update(cx, |workspace, cx| {
+            workspace
+                .active_item(cx)
+                .and_then(|item| item.downcast::<GitHistoryView>())
+                .unwrap()
+        });
+        view.update(cx, |view, cx| {
+            assert_eq!(view.rows, Some(1..2));
+            assert_eq!(view.title.as_ref(), "a.txt:2");
+            assert_eq!(view.entries, entries);
+            assert_eq!(view.selected_index, Some(0));
+            let revision_editor = view.revision_editor.clone().unwrap();
+            assert_eq!(revision_editor.read(cx).text(cx), "one\ntwo\n");
+            assert!(revision_editor.read(cx).read_only(cx));
+        });
+
+        view.update(cx, |view, cx| view.select_next(&menu::SelectNext, cx));
+        cx.run_until_parked();
+        view.update(cx, |view, cx| {
+            assert_eq!(view.selected_index, Some(1));
+            let revision_editor = view.revision_editor.clone().unwrap();
+            assert_eq!(revision_editor.read(cx).text(cx), "one\n2\n");
+        });
+    }
+
+    #[test]
+    fn test_head_rows() {
+        let head_text = "one\ntwo\nthree\nfour\n";
+        assert_eq!(head_rows(head_text, head_text, 1..3), Some(1..3));
+        // "zero" was added and "three" changed.
+        let buffer_text = "zero\none\ntwo\nTHREE\nfour\n";
+        assert_eq!(head_rows(head_text, buffer_text, 1..2), Some(0..1));
+        assert_eq!(head_rows(head_text, buffer_text, 3..4), Some(2..3));
+        assert_eq!(head_rows(head_text, buffer_text, 0..3), Some(0..2));
+        assert_eq!(head_rows(head_text, buffer_text, 0..1), None);
+        // "two" was removed.
+        let buffer_text = "one\nthree\nfour\n";
+        assert_eq!(head_rows(head_text, buffer_text, 1..3), Some(2..4));
+        // The lines appended to the file, or past its end, are not in the HEAD text.
+        let buffer_text = "one\ntwo\nthree\nfour\nfive\nsix";
+        assert_eq!(head_rows(head_text, buffer_text, 4..6), None);
+        assert_eq!(head_rows(head_text, buffer_text, 3..6), Some(3..4));
+        assert_eq!(
+            head_rows(head_text, "one\ntwo\nthree\nfour\n", 3..10),
+            Some(3..4)
+        );
+        // The HEAD text may have Windows line endings, the buffer text never has.
+        assert_eq!(head_rows("one\r\ntwo\r\n", "one\nTWO\n", 0..2), Some(0..2));
+    }
+
+    fn history_entry(sha: &str, message: &str) -> HistoryEntry {
+        HistoryEntry {
+            sha: sha.parse().unwrap(),
+            author: "Joe Schmoe".to_string(),
+            author_mail: "joe.schmoe@example.com".to_string(),
+            author_time: 1709741400,
+            author_tz: "+0100".to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    fn init_test(cx: &mut TestAppContext) -> std::sync::Arc<AppState> {
+        cx.update(|cx| {
+            let state = AppState::test(cx);
+            language::init(cx);
+            editor::init(cx);
+            workspace::init_settings(cx);
+            Project::init_settings(cx);
+            crate::init(cx);
+            state
+        })
+    }
+}
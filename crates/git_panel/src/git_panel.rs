@@ -0,0 +1,810 @@
+mod git_panel_settings;
+
+use std::{sync::Arc, time::Duration};
+
+use anyhow::{anyhow, Result};
+use db::kvp::KEY_VALUE_STORE;
+use editor::{Editor, EditorElement, EditorStyle};
+use git::repository::{GitChangeKind, GitStatusEntry, RepoPath};
+use git_panel_settings::{GitPanelDockPosition, GitPanelSettings};
+use gpui::{
+    actions, Action, AppContext, AsyncWindowContext, EventEmitter, FocusHandle, FocusableView,
+    FontStyle, FontWeight, InteractiveElement, KeyContext, Model, ParentElement, Pixels, Render,
+    Styled, Subscription, Task, TextStyle, View, ViewContext, VisualContext as _, WeakView,
+    WhiteSpace, WindowContext,
+};
+use project::{Fs, GitRemoteOperation, Project, ProjectPath};
+use serde::{Deserialize, Serialize};
+use settings::Settings;
+use theme::ThemeSettings;
+use ui::{prelude::*, v_flex, Label, ListHeader, ListItem, TextSize, Tooltip};
+use util::{ResultExt, TryFutureExt};
+use workspace::{
+    dock::{DockPosition, Panel, PanelEvent},
+    Workspace,
+};
+
+const GIT_PANEL_KEY: &str = "GitPanel";
+
+/// Worktree events come in bursts while files are saved, the status is queried again once they settle.
+const REFRESH_DEBOUNCE: Duration = Duration::from_millis(100);
+
+actions!(
+    git_panel,
+    [
+        ToggleFocus,
+        Commit,
+        AmendCommit,
+        StageAll,
+        UnstageAll,
+        Push,
+        Pull,
+        Fetch,
+    ]
+);
+
+pub fn init_settings(cx: &mut AppContext) {
+    GitPanelSettings::register(cx);
+}
+
+pub fn init(cx: &mut AppContext) {
+    init_settings(cx);
+
+    cx.observe_new_views(|workspace: &mut Workspace, _| {
+        workspace.register_action(|workspace, _: &ToggleFocus, cx| {
+            workspace.toggle_panel_focus::<GitPanel>(cx);
+        });
+    })
+    .detach();
+}
+
+/// A repository of the project, with the files changed in its index and its working directory.
+struct Repository {
+    work_directory: ProjectPath,
+    branch: Option<Arc<str>>,
+    entries: Vec<GitStatusEntry>,
+}
+
+pub struct GitPanel {
+    project: Model<Project>,
+    workspace: WeakView<Workspace>,
+    fs: Arc<dyn Fs>,
+    focus_handle: FocusHandle,
+    commit_editor: View<Editor>,
+    repositories: Vec<Repository>,
+    /// The repository that gets committed and synchronized with its remote.
+    selected_repository: Option<ProjectPath>,
+    refresh_task: Task<()>,
+    /// A commit or a remote operation in progress, with its description.
+    pending_operation: Option<(&'static str, Task<()>)>,
+    width: Option<Pixels>,
+    pending_serialization: Task<Option<()>>,
+    _subscriptions: Vec<Subscription>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SerializedGitPanel {
+    width: Option<Pixels>,
+}
+
+impl GitPanel {
+    fn new(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) -> View<Self> {
+        let project = workspace.project().clone();
+        let fs = workspace.app_state().fs.clone();
+        let workspace = workspace.weak_handle();
+        cx.new_view(|cx| {
+            let commit_editor = cx.new_view(|cx| {
+                let mut editor = Editor::auto_height(10, cx);
+                editor.set_placeholder_text("Commit message", cx);
+                editor
+            });
+            let project_subscription = cx.subscribe(&project, |panel, _, event, cx| match event {
+                project::Event::WorktreeAdded
+                | project::Event::WorktreeRemoved(_)
+                | project::Event::WorktreeUpdatedEntries(..)
+                | project::Event::WorktreeUpdatedGitRepositories => {
+                    panel.schedule_refresh(cx);
+                }
+                _ => {}
+            });
+            let mut panel = Self {
+                project,
+                workspace,
+                fs,
+                focus_handle: cx.focus_handle(),
+                commit_editor,
+                repositories: Vec::new(),
+                selected_repository: None,
+                refresh_task: Task::ready(()),
+                pending_operation: None,
+                width: None,
+                pending_serialization: Task::ready(None),
+                _subscriptions: vec![project_subscription],
+            };
+            panel.schedule_refresh(cx);
+            panel
+        })
+    }
+
+    pub async fn load(
+        workspace: WeakView<Workspace>,
+        mut cx: AsyncWindowContext,
+    ) -> Result<View<Self>> {
+        let serialized_panel = cx
+            .background_executor()
+            .spawn(async move { KEY_VALUE_STORE.read_kvp(GIT_PANEL_KEY) })
+            .await
+            .map_err(|e| anyhow!("Failed to load git panel: {}", e))
+            .log_err()
+            .flatten()
+            .map(|panel| serde_json::from_str::<SerializedGitPanel>(&panel))
+            .transpose()
+            .log_err()
+            .flatten();
+
+        workspace.update(&mut cx, |workspace, cx| {
+            let panel = GitPanel::new(workspace, cx);
+            if let Some(serialized_panel) = serialized_panel {
+                panel.update(cx, |panel, cx| {
+                    panel.width = serialized_panel.width.map(|px| px.round());
+                    cx.notify();
+                });
+            }
+            panel
+        })
+    }
+
+    fn serialize(&mut self, cx: &mut ViewContext<Self>) {
+        let width = self.width;
+        self.pending_serialization = cx.background_executor().spawn(
+            async move {
+                KEY_VALUE_STORE
+                    .write_kvp(
+                        GIT_PANEL_KEY.into(),
+                        serde_json::to_string(&SerializedGitPanel { width })?,
+                    )
+                    .await?;
+                anyhow::Ok(())
+            }
+            .log_err(),
+        );
+    }
+
+    fn schedule_refresh(&mut self, cx: &mut ViewContext<Self>) {
+        self.refresh_task = cx.spawn(|panel, mut cx| async move {
+            cx.background_executor().timer(REFRESH_DEBOUNCE).await;
+            let Some(queries) = panel
+                .update(&mut cx, |panel, cx| {
+                    let project = panel.project.read(cx);
+                    project
+                        .git_repositories(cx)
+                        .into_iter()
+                        .map(|(work_directory, branch)| {
+                            let changed_files =
+                                project.git_changed_files(work_directory.clone(), cx);
+                            (work_directory, branch, changed_files)
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .ok()
+            else {
+                return;
+            };
+
+            let mut repositories = Vec::with_capacity(queries.len());
+            for (work_directory, branch, changed_files) in queries {
+                let entries = changed_files.await.log_err().unwrap_or_default();
+                repositories.push(Repository {
+                    work_directory,
+                    branch,
+                    entries,
+                });
+            }
+
+            panel
+                .update(&mut cx, |panel, cx| {
+                    let is_selection_valid =
+                        panel
+                            .selected_repository
+                            .as_ref()
+                            .map_or(false, |selected_repository| {
+                                repositories.iter().any(|repository| {
+                                    &repository.work_directory == selected_repository
+                                })
+                            });
+                    if !is_selection_valid {
+                        panel.selected_repository = repositories
+                            .first()
+                            .map(|repository| repository.work_directory.clone());
+                    }
+                    panel.repositories = repositories;
+                    cx.notify();
+                })
+                .ok();
+        });
+    }
+
+    fn selected_repository(&self) -> Option<&Repository> {
+        let selected_repository = self.selected_repository.as_ref()?;
+        self.repositories
+            .iter()
+            .find(|repository| &repository.work_directory == selected_repository)
+    }
+
+    fn select_repository(&mut self, work_directory: ProjectPath, cx: &mut ViewContext<Self>) {
+        self.selected_repository = Some(work_directory);
+        cx.notify();
+    }
+
+    /// Runs a git operation, showing its error in the workspace and refreshing the changes once done.
+    ///
+    /// Operations with a description block the commit and the remote operations until they are finished.
+    fn spawn_operation(
+        &mut self,
+        description: Option<&'static str>,
+        operation: Task<Result<()>>,
+        on_success: impl FnOnce(&mut Self, &mut ViewContext<Self>) + 'static,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let task = cx.spawn(|panel, mut cx| async move {
+            let result = operation.await;
+            panel
+                .update(&mut cx, |panel, cx| {
+                    if description.is_some() {
+                        panel.pending_operation = None;
+                    }
+                    match result {
+                        Ok(()) => on_success(panel, cx),
+                        Err(error) => {
+                            panel
+                                .workspace
+                                .update(cx, |workspace, cx| workspace.show_error(&error, cx))
+                                .ok();
+                        }
+                    }
+                    panel.schedule_refresh(cx);
+                    cx.notify();
+                })
+                .ok();
+        });
+        match description {
+            Some(description) => self.pending_operation = Some((description, task)),
+            None => task.detach(),
+        }
+        cx.notify();
+    }
+
+    fn stage(
+        &mut self,
+        work_directory: ProjectPath,
+        paths: Vec<RepoPath>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if paths.is_empty() {
+            return;
+        }
+        let operation = self.project.read(cx).git_stage(work_directory, paths, cx);
+        self.spawn_operation(None, operation, |_, _| {}, cx);
+    }
+
+    fn unstage(
+        &mut self,
+        work_directory: ProjectPath,
+        paths: Vec<RepoPath>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if paths.is_empty() {
+            return;
+        }
+        let operation = self.project.read(cx).git_unstage(work_directory, paths, cx);
+        self.spawn_operation(None, operation, |_, _| {}, cx);
+    }
+
+    fn stage_all(&mut self, _: &StageAll, cx: &mut ViewContext<Self>) {
+        let Some(repository) = self.selected_repository() else {
+            return;
+        };
+        let work_directory = repository.work_directory.clone();
+        let paths = repository
+            .entries
+            .iter()
+            .filter(|entry| entry.unstaged.is_some())
+            .map(|entry| entry.path.clone())
+            .collect();
+        self.stage(work_directory, paths, cx);
+    }
+
+    fn unstage_all(&mut self, _: &UnstageAll, cx: &mut ViewContext<Self>) {
+        let Some(repository) = self.selected_repository() else {
+            return;
+        };
+        let work_directory = repository.work_directory.clone();
+        let paths = repository
+            .entries
+            .iter()
+            .filter(|entry| entry.staged.is_some())
+            .map(|entry| entry.path.clone())
+            .collect();
+        self.unstage(work_directory, paths, cx);
+    }
+
+    fn can_commit(&self, amend: bool, cx: &AppContext) -> bool {
+        let Some(repository) = self.selected_repository() else {
+            return false;
+        };
+        if self.pending_operation.is_some() {
+            return false;
+        }
+        // Amending without a message keeps the message of the amended commit.
+        amend
+            || (repository
+                .entries
+                .iter()
+                .any(|entry| entry.staged.is_some())
+                && !self.commit_editor.read(cx).text(cx).trim().is_empty())
+    }
+
+    fn commit(&mut self, _: &Commit, cx: &mut ViewContext<Self>) {
+        self.commit_changes(false, cx);
+    }
+
+    fn amend_commit(&mut self, _: &AmendCommit, cx: &mut ViewContext<Self>) {
+        self.commit_changes(true, cx);
+    }
+
+    fn commit_changes(&mut self, amend: bool, cx: &mut ViewContext<Self>) {
+        if !self.can_commit(amend, cx) {
+            return;
+        }
+        let Some(work_directory) = self.selected_repository.clone() else {
+            return;
+        };
+        let message = self.commit_editor.read(cx).text(cx).trim().to_string();
+        let operation = self
+            .project
+            .read(cx)
+            .git_commit(work_directory, message, amend, cx);
+        let description = if amend {
+            "Amending…"
+        } else {
+            "Committing…"
+        };
+        self.spawn_operation(
+            Some(description),
+            operation,
+            |panel, cx| {
+                panel
+                    .commit_editor
+                    .update(cx, |editor, cx| editor.set_text("", cx));
+            },
+            cx,
+        );
+    }
+
+    fn push(&mut self, _: &Push, cx: &mut ViewContext<Self>) {
+        self.remote_operation(GitRemoteOperation::Push, cx);
+    }
+
+    fn pull(&mut self, _: &Pull, cx: &mut ViewContext<Self>) {
+        self.remote_operation(GitRemoteOperation::Pull, cx);
+    }
+
+    fn fetch(&mut self, _: &Fetch, cx: &mut ViewContext<Self>) {
+        self.remote_operation(GitRemoteOperation::Fetch, cx);
+    }
+
+    fn remote_operation(&mut self, operation: GitRemoteOperation, cx: &mut ViewContext<Self>) {
+        if self.pending_operation.is_some() {
+            return;
+        }
+        let Some(work_directory) = self.selected_repository.clone() else {
+            return;
+        };
+        let description = match operation {
+            GitRemoteOperation::Push => "Pushing…",
+            GitRemoteOperation::Pull => "Pulling…",
+            GitRemoteOperation::Fetch => "Fetching…",
+        };
+        let operation = self
+            .project
+            .read(cx)
+            .git_remote_operation(work_directory, operation, cx);
+        self.spawn_operation(Some(description), operation, |_, _| {}, cx);
+    }
+
+    fn open_entry(
+        &mut self,
+        work_directory: &ProjectPath,
+        repo_path: &RepoPath,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let project_path = ProjectPath {
+            worktree_id: work_directory.worktree_id,
+            path: work_directory.path.join(&repo_path.0).into(),
+        };
+        self.workspace
+            .update(cx, |workspace, cx| {
+                workspace
+                    .open_path(project_path, None, true, cx)
+                    .detach_and_log_err(cx);
+            })
+            .ok();
+    }
+
+    fn dispatch_context(&self) -> KeyContext {
+        let mut dispatch_context = KeyContext::new_with_defaults();
+        dispatch_context.add("GitPanel");
+        dispatch_context
+    }
+
+    fn render_repository(
+        &self,
+        ix: usize,
+        repository: &Repository,
+        cx: &mut ViewContext<Self>,
+    ) -> impl IntoElement {
+        let is_selected = self.selected_repository.as_ref() == Some(&repository.work_directory);
+        let name = match repository.work_directory.path.file_name() {
+            Some(name) => name.to_string_lossy().into_owned(),
+            None => self
+                .project
+                .read(cx)
+                .worktree_for_id(repository.work_directory.worktree_id, cx)
+                .map(|worktree| worktree.read(cx).root_name().to_string())
+                .unwrap_or_default(),
+        };
+        let label = match &repository.branch {
+            Some(branch) => format!("{name} ({branch})"),
+            None => name,
+        };
+        let staged = repository
+            .entries
+            .iter()
+            .filter_map(|entry| Some((entry, entry.staged?)))
+            .collect::<Vec<_>>();
+        let unstaged = repository
+            .entries
+            .iter()
+            .filter_map(|entry| Some((entry, entry.unstaged?)))
+            .collect::<Vec<_>>();
+        let work_directory = repository.work_directory.clone();
+
+        v_flex()
+            .id(("repository", ix))
+            .when(self.repositories.len() > 1, |this| {
+                this.child(
+                    ListItem::new("repository-header")
+                        .selected(is_selected)
+                        .child(
+                            h_flex()
+                                .gap_2()
+                                .child(Icon::new(IconName::FileGit).color(Color::Muted))
+                                .child(Label::new(label.clone()).single_line()),
+                        )
+                        .on_click(cx.listener(move |panel, _, cx| {
+                            panel.select_repository(work_directory.clone(), cx);
+                        })),
+                )
+            })
+            .when(self.repositories.len() == 1, |this| {
+                this.child(
+                    h_flex()
+                        .px_2()
+                        .gap_2()
+                        .child(Icon::new(IconName::FileGit).color(Color::Muted))
+                        .child(Label::new(label).single_line()),
+                )
+            })
+            .child(
+                ListHeader::new(format!("Staged Changes ({})", staged.len())).end_slot(
+                    (!staged.is_empty()).then(|| {
+                        let work_directory = repository.work_directory.clone();
+                        let paths = staged
+                            .iter()
+                            .map(|(entry, _)| entry.path.clone())
+                            .collect::<Vec<_>>();
+                        IconButton::new("unstage-all", IconName::Dash)
+                            .icon_size(IconSize::Small)
+                            .tooltip(|cx| Tooltip::text("Unstage All", cx))
+                            .on_click(cx.listener(move |panel, _, cx| {
+                                panel.unstage(work_directory.clone(), paths.clone(), cx);
+                            }))
+                    }),
+                ),
+            )
+            .children(staged.iter().enumerate().map(|(entry_ix, (entry, kind))| {
+                self.render_entry(
+                    ("staged", entry_ix),
+                    &repository.work_directory,
+                    entry,
+                    *kind,
+                    true,
+                    cx,
+                )
+            }))
+            .child(
+                ListHeader::new(format!("Changes ({})", unstaged.len())).end_slot(
+                    (!unstaged.is_empty()).then(|| {
+                        let work_directory = repository.work_directory.clone();
+                        let paths = unstaged
+                            .iter()
+                            .map(|(entry, _)| entry.path.clone())
+                            .collect::<Vec<_>>();
+                        IconButton::new("stage-all", IconName::Plus)
+                            .icon_size(IconSize::Small)
+                            .tooltip(|cx| Tooltip::text("Stage All", cx))
+                            .on_click(cx.listener(move |panel, _, cx| {
+                                panel.stage(work_directory.clone(), paths.clone(), cx);
+                            }))
+                    }),
+                ),
+            )
+            .children(
+                unstaged
+                    .iter()
+                    .enumerate()
+                    .map(|(entry_ix, (entry, kind))| {
+                        self.render_entry(
+                            ("unstaged", entry_ix),
+                            &repository.work_directory,
+                            entry,
+                            *kind,
+                            false,
+                            cx,
+                        )
+                    }),
+            )
+    }
+
+    fn render_entry(
+        &self,
+        id: (&'static str, usize),
+        work_directory: &ProjectPath,
+        entry: &GitStatusEntry,
+        kind: GitChangeKind,
+        is_staged: bool,
+        cx: &mut ViewContext<Self>,
+    ) -> impl IntoElement {
+        let (status, color) = match kind {
+            GitChangeKind::Added => ("A", Color::Created),
+            GitChangeKind::Modified => ("M", Color::Modified),
+            GitChangeKind::Deleted => ("D", Color::Deleted),
+            GitChangeKind::Conflict => ("!", Color::Conflict),
+        };
+        let file_name = entry
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let directory = entry
+            .path
+            .parent()
+            .map(|parent| parent.to_string_lossy().into_owned())
+            .filter(|parent| !parent.is_empty());
+
+        let toggle_staged = {
+            let work_directory = work_directory.clone();
+            let path = entry.path.clone();
+            if is_staged {
+                IconButton::new("unstage", IconName::Dash)
+                    .icon_size(IconSize::Small)
+                    .tooltip(|cx| Tooltip::text("Unstage", cx))
+                    .on_click(cx.listener(move |panel, _, cx| {
+                        panel.unstage(work_directory.clone(), vec![path.clone()], cx);
+                    }))
+            } else {
+                IconButton::new("stage", IconName::Plus)
+                    .icon_size(IconSize::Small)
+                    .tooltip(|cx| Tooltip::text("Stage", cx))
+                    .on_click(cx.listener(move |panel, _, cx| {
+                        panel.stage(work_directory.clone(), vec![path.clone()], cx);
+                    }))
+            }
+        };
+
+        let work_directory = work_directory.clone();
+        let path = entry.path.clone();
+        ListItem::new(id)
+            .child(
+                h_flex()
+                    .h_6()
+                    .gap_2()
+                    .child(Label::new(status).color(color))
+                    .child(Label::new(file_name).color(color).single_line())
+                    .children(directory.map(|directory| {
+                        Label::new(directory)
+                            .size(LabelSize::Small)
+                            .color(Color::Muted)
+                            .single_line()
+                    })),
+            )
+            .end_slot(toggle_staged)
+            .when(kind != GitChangeKind::Deleted, |this| {
+                this.on_click(cx.listener(move |panel, _, cx| {
+                    panel.open_entry(&work_directory, &path, cx);
+                }))
+            })
+    }
+
+    fn render_commit_editor(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let settings = ThemeSettings::get_global(cx);
+        let text_style = TextStyle {
+            color: cx.theme().colors().text,
+            font_family: settings.ui_font.family.clone(),
+            font_features: settings.ui_font.features.clone(),
+            font_size: TextSize::Small.rems(cx).into(),
+            font_weight: FontWeight::NORMAL,
+            font_style: FontStyle::Normal,
+            line_height: relative(1.3),
+            background_color: None,
+            underline: None,
+            strikethrough: None,
+            white_space: WhiteSpace::Normal,
+        };
+        div()
+            .p_2()
+            .rounded_md()
+            .border_1()
+            .border_color(cx.theme().colors().border)
+            .bg(cx.theme().colors().editor_background)
+            .child(EditorElement::new(
+                &self.commit_editor,
+                EditorStyle {
+                    background: cx.theme().colors().editor_background,
+                    local_player: cx.theme().players().local(),
+                    text: text_style,
+                    ..Default::default()
+                },
+            ))
+    }
+
+    fn render_actions(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let is_busy = self.pending_operation.is_some();
+        let remote_button =
+            |id: &'static str, icon: IconName, tooltip: &'static str, action: Box<dyn Action>| {
+                IconButton::new(id, icon)
+                    .icon_size(IconSize::Small)
+                    .disabled(is_busy)
+                    .tooltip(move |cx| Tooltip::for_action(tooltip, &*action, cx))
+            };
+        h_flex()
+            .gap_1()
+            .child(
+                Button::new("commit", "Commit")
+                    .style(ButtonStyle::Filled)
+                    .disabled(!self.can_commit(false, cx))
+                    .on_click(cx.listener(|panel, _, cx| panel.commit(&Commit, cx))),
+            )
+            .child(
+                Button::new("amend", "Amend")
+                    .disabled(!self.can_commit(true, cx))
+                    .on_click(cx.listener(|panel, _, cx| panel.amend_commit(&AmendCommit, cx))),
+            )
+            .child(div().flex_1())
+            .child(
+                remote_button("fetch", IconName::Update, "Fetch", Box::new(Fetch))
+                    .on_click(cx.listener(|panel, _, cx| panel.fetch(&Fetch, cx))),
+            )
+            .child(
+                remote_button("pull", IconName::ArrowDown, "Pull", Box::new(Pull))
+                    .on_click(cx.listener(|panel, _, cx| panel.pull(&Pull, cx))),
+            )
+            .child(
+                remote_button("push", IconName::ArrowUp, "Push", Box::new(Push))
+                    .on_click(cx.listener(|panel, _, cx| panel.push(&Push, cx))),
+            )
+    }
+}
+
+impl Render for GitPanel {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        if self.repositories.is_empty() {
+            return v_flex()
+                .id("empty-git-panel")
+                .size_full()
+                .p_4()
+                .track_focus(&self.focus_handle)
+                .child(Label::new("No git repositories in the project").color(Color::Muted));
+        }
+
+        v_flex()
+            .id("git-panel")
+            .size_full()
+            .key_context(self.dispatch_context())
+            .on_action(cx.listener(Self::commit))
+            .on_action(cx.listener(Self::amend_commit))
+            .on_action(cx.listener(Self::stage_all))
+            .on_action(cx.listener(Self::unstage_all))
+            .on_action(cx.listener(Self::push))
+            .on_action(cx.listener(Self::pull))
+            .on_action(cx.listener(Self::fetch))
+            .track_focus(&self.focus_handle)
+            .child(
+                v_flex()
+                    .p_2()
+                    .gap_2()
+                    .border_b_1()
+                    .border_color(cx.theme().colors().border)
+                    .child(self.render_commit_editor(cx))
+                    .child(self.render_actions(cx))
+                    .children(self.pending_operation.as_ref().map(|(description, _)| {
+                        Label::new(*description)
+                            .size(LabelSize::Small)
+                            .color(Color::Muted)
+                    })),
+            )
+            .child(
+                v_flex()
+                    .id("repositories")
+                    .flex_1()
+                    .overflow_y_scroll()
+                    .children(
+                        self.repositories
+                            .iter()
+                            .enumerate()
+                            .map(|(ix, repository)| self.render_repository(ix, repository, cx))
+                            .collect::<Vec<_>>(),
+                    ),
+            )
+    }
+}
+
+impl FocusableView for GitPanel {
+    fn focus_handle(&self, _cx: &AppContext) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl EventEmitter<PanelEvent> for GitPanel {}
+
+impl Panel for GitPanel {
+    fn position(&self, cx: &WindowContext) -> DockPosition {
+        match GitPanelSettings::get_global(cx).dock {
+            GitPanelDockPosition::Left => DockPosition::Left,
+            GitPanelDockPosition::Right => DockPosition::Right,
+        }
+    }
+
+    fn position_is_valid(&self, position: DockPosition) -> bool {
+        matches!(position, DockPosition::Left | DockPosition::Right)
+    }
+
+    fn set_position(&mut self, position: DockPosition, cx: &mut ViewContext<Self>) {
+        settings::update_settings_file::<GitPanelSettings>(self.fs.clone(), cx, move |settings| {
+            let dock = match position {
+                DockPosition::Left | DockPosition::Bottom => GitPanelDockPosition::Left,
+                DockPosition::Right => GitPanelDockPosition::Right,
+            };
+            settings.dock = Some(dock);
+        });
+    }
+
+    fn size(&self, cx: &WindowContext) -> Pixels {
+        self.width
+            .unwrap_or_else(|| GitPanelSettings::get_global(cx).default_width)
+    }
+
+    fn set_size(&mut self, size: Option<Pixels>, cx: &mut ViewContext<Self>) {
+        self.width = size;
+        self.serialize(cx);
+        cx.notify();
+    }
+
+    fn icon(&self, cx: &WindowContext) -> Option<IconName> {
+        GitPanelSettings::get_global(cx)
+            .button
+            .then(|| IconName::FileGit)
+    }
+
+    fn icon_tooltip(&self, _cx: &WindowContext) -> Option<&'static str> {
+        Some("Git Panel")
+    }
+
+    fn toggle_action(&self) -> Box<dyn Action> {
+        Box::new(ToggleFocus)
+    }
+
+    fn persistent_name() -> &'static str {
+        "Git Panel"
+    }
+}
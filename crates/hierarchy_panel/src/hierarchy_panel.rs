@@ -0,0 +1,676 @@
+mod hierarchy_panel_settings;
+
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use db::kvp::KEY_VALUE_STORE;
+use editor::{scroll::Autoscroll, Editor};
+use gpui::{
+    actions, div, px, uniform_list, Action, AppContext, AsyncWindowContext, ClickEvent,
+    EventEmitter, FocusHandle, FocusableView, InteractiveElement, KeyContext, Model, ParentElement,
+    Pixels, Render, Stateful, Styled, Task, UniformListScrollHandle, View, ViewContext,
+    VisualContext as _, WeakView, WindowContext,
+};
+use hierarchy_panel_settings::{HierarchyPanelDockPosition, HierarchyPanelSettings};
+use language::{Anchor, Buffer, OffsetRangeExt as _};
+use menu::{Confirm, SelectFirst, SelectLast, SelectNext, SelectPrev};
+use project::{Fs, HierarchyItem, Project};
+use serde::{Deserialize, Serialize};
+use settings::Settings;
+use ui::{prelude::*, v_flex, Label, ListItem};
+use util::{ResultExt, TryFutureExt};
+use workspace::{
+    dock::{DockPosition, Panel, PanelEvent},
+    Workspace,
+};
+
+const HIERARCHY_PANEL_KEY: &str = "HierarchyPanel";
+
+actions!(
+    hierarchy_panel,
+    [
+        ToggleFocus,
+        ShowIncomingCalls,
+        ShowOutgoingCalls,
+        ShowSupertypes,
+        ShowSubtypes,
+        ExpandSelectedEntry,
+        CollapseSelectedEntry,
+        Open,
+    ]
+);
+
+pub fn init_settings(cx: &mut AppContext) {
+    HierarchyPanelSettings::register(cx);
+}
+
+pub fn init(cx: &mut AppContext) {
+    init_settings(cx);
+
+    cx.observe_new_views(|workspace: &mut Workspace, _| {
+        workspace
+            .register_action(|workspace, _: &ToggleFocus, cx| {
+                workspace.toggle_panel_focus::<HierarchyPanel>(cx);
+            })
+            .register_action(|workspace, _: &ShowIncomingCalls, cx| {
+                show_hierarchy(workspace, HierarchyKind::IncomingCalls, cx);
+            })
+            .register_action(|workspace, _: &ShowOutgoingCalls, cx| {
+                show_hierarchy(workspace, HierarchyKind::OutgoingCalls, cx);
+            })
+            .register_action(|workspace, _: &ShowSupertypes, cx| {
+                show_hierarchy(workspace, HierarchyKind::Supertypes, cx);
+            })
+            .register_action(|workspace, _: &ShowSubtypes, cx| {
+                show_hierarchy(workspace, HierarchyKind::Subtypes, cx);
+            });
+    })
+    .detach();
+}
+
+/// Shows the hierarchy of the symbol under the newest cursor of the active editor.
+fn show_hierarchy(workspace: &mut Workspace, kind: HierarchyKind, cx: &mut ViewContext<Workspace>) {
+    let Some(editor) = workspace
+        .active_item(cx)
+        .and_then(|item| item.act_as::<Editor>(cx))
+    else {
+        return;
+    };
+    let Some((buffer, position)) = editor.update(cx, |editor, cx| {
+        let head = editor.selections.newest_anchor().head();
+        editor.buffer().read(cx).text_anchor_for_position(head, cx)
+    }) else {
+        return;
+    };
+    if let Some(panel) = workspace.focus_panel::<HierarchyPanel>(cx) {
+        panel.update(cx, |panel, cx| panel.show(kind, buffer, position, cx));
+    }
+}
+
+/// Which relation between symbols the panel shows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HierarchyKind {
+    IncomingCalls,
+    OutgoingCalls,
+    Supertypes,
+    Subtypes,
+}
+
+impl HierarchyKind {
+    fn title(&self) -> &'static str {
+        match self {
+            Self::IncomingCalls => "Incoming Calls",
+            Self::OutgoingCalls => "Outgoing Calls",
+            Self::Supertypes => "Supertypes",
+            Self::Subtypes => "Subtypes",
+        }
+    }
+
+    fn is_call_hierarchy(&self) -> bool {
+        matches!(self, Self::IncomingCalls | Self::OutgoingCalls)
+    }
+}
+
+pub struct HierarchyPanel {
+    project: Model<Project>,
+    workspace: WeakView<Workspace>,
+    fs: Arc<dyn Fs>,
+    focus_handle: FocusHandle,
+    scroll_handle: UniformListScrollHandle,
+    hierarchy: Option<Hierarchy>,
+    /// Indices of the nodes that are visible, in display order.
+    visible_entries: Vec<usize>,
+    selected_entry: Option<usize>,
+    prepare_task: Option<Task<()>>,
+    width: Option<Pixels>,
+    pending_serialization: Task<Option<()>>,
+}
+
+struct Hierarchy {
+    kind: HierarchyKind,
+    /// The buffer the hierarchy was prepared in, which determines the language server
+    /// queried to expand its items.
+    buffer: Model<Buffer>,
+    roots: Vec<usize>,
+    nodes: Vec<HierarchyNode>,
+}
+
+struct HierarchyNode {
+    item: HierarchyItem,
+    parent: Option<usize>,
+    depth: usize,
+    expanded: bool,
+    children: NodeChildren,
+}
+
+/// Children are only queried once their parent is expanded.
+enum NodeChildren {
+    NotLoaded,
+    Loading(Task<()>),
+    Loaded(Vec<usize>),
+}
+
+impl Hierarchy {
+    fn push_node(&mut self, item: HierarchyItem, parent: Option<usize>) -> usize {
+        let depth = parent.map_or(0, |parent| self.nodes[parent].depth + 1);
+        self.nodes.push(HierarchyNode {
+            item,
+            parent,
+            depth,
+            expanded: false,
+            children: NodeChildren::NotLoaded,
+        });
+        self.nodes.len() - 1
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SerializedHierarchyPanel {
+    width: Option<Pixels>,
+}
+
+impl HierarchyPanel {
+    fn new(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) -> View<Self> {
+        let project = workspace.project().clone();
+        let fs = workspace.app_state().fs.clone();
+        let workspace = workspace.weak_handle();
+        cx.new_view(|cx| Self {
+            project,
+            workspace,
+            fs,
+            focus_handle: cx.focus_handle(),
+            scroll_handle: UniformListScrollHandle::new(),
+            hierarchy: None,
+            visible_entries: Vec::new(),
+            selected_entry: None,
+            prepare_task: None,
+            width: None,
+            pending_serialization: Task::ready(None),
+        })
+    }
+
+    pub async fn load(
+        workspace: WeakView<Workspace>,
+        mut cx: AsyncWindowContext,
+    ) -> Result<View<Self>> {
+        let serialized_panel = cx
+            .background_executor()
+            .spawn(async move { KEY_VALUE_STORE.read_kvp(HIERARCHY_PANEL_KEY) })
+            .await
+            .map_err(|e| anyhow!("Failed to load hierarchy panel: {}", e))
+            .log_err()
+            .flatten()
+            .map(|panel| serde_json::from_str::<SerializedHierarchyPanel>(&panel))
+            .transpose()
+            .log_err()
+            .flatten();
+
+        workspace.update(&mut cx, |workspace, cx| {
+            let panel = HierarchyPanel::new(workspace, cx);
+            if let Some(serialized_panel) = serialized_panel {
+                panel.update(cx, |panel, cx| {
+                    panel.width = serialized_panel.width.map(|px| px.round());
+                    cx.notify();
+                });
+            }
+            panel
+        })
+    }
+
+    fn serialize(&mut self, cx: &mut ViewContext<Self>) {
+        let width = self.width;
+        self.pending_serialization = cx.background_executor().spawn(
+            async move {
+                KEY_VALUE_STORE
+                    .write_kvp(
+                        HIERARCHY_PANEL_KEY.into(),
+                        serde_json::to_string(&SerializedHierarchyPanel { width })?,
+                    )
+                    .await?;
+                anyhow::Ok(())
+            }
+            .log_err(),
+        );
+    }
+
+    /// Replaces the displayed hierarchy with the one of the symbol at `position`.
+    pub fn show(
+        &mut self,
+        kind: HierarchyKind,
+        buffer: Model<Buffer>,
+        position: Anchor,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let prepare = self.project.update(cx, |project, cx| {
+            if kind.is_call_hierarchy() {
+                project.prepare_call_hierarchy(&buffer, position, cx)
+            } else {
+                project.prepare_type_hierarchy(&buffer, position, cx)
+            }
+        });
+        self.prepare_task = Some(cx.spawn(|panel, mut cx| async move {
+            let Some(items) = prepare.await.log_err() else {
+                return;
+            };
+            panel
+                .update(&mut cx, |panel, cx| {
+                    let mut hierarchy = Hierarchy {
+                        kind,
+                        buffer,
+                        roots: Vec::new(),
+                        nodes: Vec::new(),
+                    };
+                    for item in items {
+                        let root = hierarchy.push_node(item, None);
+                        hierarchy.roots.push(root);
+                    }
+                    let roots = hierarchy.roots.clone();
+                    panel.hierarchy = Some(hierarchy);
+                    panel.selected_entry = roots.first().copied();
+                    panel.update_visible_entries(cx);
+                    // The roots are the symbol under the cursor, expand them right away
+                    // as they are not interesting on their own.
+                    for root in roots {
+                        panel.expand_entry(root, cx);
+                    }
+                })
+                .ok();
+        }));
+    }
+
+    fn expand_entry(&mut self, ix: usize, cx: &mut ViewContext<Self>) {
+        let Some(hierarchy) = self.hierarchy.as_mut() else {
+            return;
+        };
+        let Some(node) = hierarchy.nodes.get_mut(ix) else {
+            return;
+        };
+        node.expanded = true;
+        if !matches!(node.children, NodeChildren::NotLoaded) {
+            self.update_visible_entries(cx);
+            return;
+        }
+
+        let kind = hierarchy.kind;
+        let buffer = hierarchy.buffer.clone();
+        let item = node.item.clone();
+        let query = self.project.update(cx, |project, cx| match kind {
+            HierarchyKind::IncomingCalls => project.incoming_calls(&buffer, &item, cx),
+            HierarchyKind::OutgoingCalls => project.outgoing_calls(&buffer, &item, cx),
+            HierarchyKind::Supertypes => project.supertypes(&buffer, &item, cx),
+            HierarchyKind::Subtypes => project.subtypes(&buffer, &item, cx),
+        });
+        // The task is owned by the node, so it gets dropped along with a replaced hierarchy.
+        let load_children = cx.spawn(|panel, mut cx| async move {
+            let items = query.await.log_err().unwrap_or_default();
+            panel
+                .update(&mut cx, |panel, cx| {
+                    let Some(hierarchy) = panel.hierarchy.as_mut() else {
+                        return;
+                    };
+                    let children = items
+                        .into_iter()
+                        .map(|item| hierarchy.push_node(item, Some(ix)))
+                        .collect();
+                    hierarchy.nodes[ix].children = NodeChildren::Loaded(children);
+                    panel.update_visible_entries(cx);
+                })
+                .ok();
+        });
+        if let Some(node) = self
+            .hierarchy
+            .as_mut()
+            .and_then(|hierarchy| hierarchy.nodes.get_mut(ix))
+        {
+            node.children = NodeChildren::Loading(load_children);
+        }
+        cx.notify();
+    }
+
+    fn collapse_entry(&mut self, ix: usize, cx: &mut ViewContext<Self>) {
+        if let Some(node) = self
+            .hierarchy
+            .as_mut()
+            .and_then(|hierarchy| hierarchy.nodes.get_mut(ix))
+        {
+            node.expanded = false;
+            self.update_visible_entries(cx);
+        }
+    }
+
+    fn toggle_expanded(&mut self, ix: usize, cx: &mut ViewContext<Self>) {
+        let is_expanded = self
+            .hierarchy
+            .as_ref()
+            .and_then(|hierarchy| hierarchy.nodes.get(ix))
+            .map_or(false, |node| node.expanded);
+        if is_expanded {
+            self.collapse_entry(ix, cx);
+        } else {
+            self.expand_entry(ix, cx);
+        }
+    }
+
+    fn update_visible_entries(&mut self, cx: &mut ViewContext<Self>) {
+        fn push_visible(hierarchy: &Hierarchy, ix: usize, visible_entries: &mut Vec<usize>) {
+            visible_entries.push(ix);
+            let node = &hierarchy.nodes[ix];
+            if let (true, NodeChildren::Loaded(children)) = (node.expanded, &node.children) {
+                for child in children {
+                    push_visible(hierarchy, *child, visible_entries);
+                }
+            }
+        }
+
+        self.visible_entries.clear();
+        if let Some(hierarchy) = &self.hierarchy {
+            for root in &hierarchy.roots {
+                push_visible(hierarchy, *root, &mut self.visible_entries);
+            }
+        }
+        // Keep the selection visible when collapsing one of its ancestors.
+        while let Some(selected_entry) = self.selected_entry {
+            if self.visible_entries.contains(&selected_entry) {
+                break;
+            }
+            self.selected_entry = self
+                .hierarchy
+                .as_ref()
+                .and_then(|hierarchy| hierarchy.nodes[selected_entry].parent);
+        }
+        cx.notify();
+    }
+
+    fn select_entry(&mut self, ix: usize, cx: &mut ViewContext<Self>) {
+        self.selected_entry = Some(ix);
+        if let Some(visible_ix) = self.visible_entries.iter().position(|entry| *entry == ix) {
+            self.scroll_handle.scroll_to_item(visible_ix);
+        }
+        cx.notify();
+    }
+
+    fn selected_visible_ix(&self) -> Option<usize> {
+        let selected_entry = self.selected_entry?;
+        self.visible_entries
+            .iter()
+            .position(|entry| *entry == selected_entry)
+    }
+
+    fn select_next(&mut self, _: &SelectNext, cx: &mut ViewContext<Self>) {
+        let next_ix = self.selected_visible_ix().map_or(0, |ix| ix + 1);
+        if let Some(entry) = self.visible_entries.get(next_ix).copied() {
+            self.select_entry(entry, cx);
+        }
+    }
+
+    fn select_prev(&mut self, _: &SelectPrev, cx: &mut ViewContext<Self>) {
+        let prev_ix = match self.selected_visible_ix() {
+            Some(ix) => ix.saturating_sub(1),
+            None => self.visible_entries.len().saturating_sub(1),
+        };
+        if let Some(entry) = self.visible_entries.get(prev_ix).copied() {
+            self.select_entry(entry, cx);
+        }
+    }
+
+    fn select_first(&mut self, _: &SelectFirst, cx: &mut ViewContext<Self>) {
+        if let Some(entry) = self.visible_entries.first().copied() {
+            self.select_entry(entry, cx);
+        }
+    }
+
+    fn select_last(&mut self, _: &SelectLast, cx: &mut ViewContext<Self>) {
+        if let Some(entry) = self.visible_entries.last().copied() {
+            self.select_entry(entry, cx);
+        }
+    }
+
+    fn expand_selected_entry(&mut self, _: &ExpandSelectedEntry, cx: &mut ViewContext<Self>) {
+        let Some(selected_entry) = self.selected_entry else {
+            return;
+        };
+        let Some(node) = self
+            .hierarchy
+            .as_ref()
+            .and_then(|hierarchy| hierarchy.nodes.get(selected_entry))
+        else {
+            return;
+        };
+        match (&node.children, node.expanded) {
+            (NodeChildren::Loaded(children), true) => {
+                if let Some(first_child) = children.first().copied() {
+                    self.select_entry(first_child, cx);
+                }
+            }
+            _ => self.expand_entry(selected_entry, cx),
+        }
+    }
+
+    fn collapse_selected_entry(&mut self, _: &CollapseSelectedEntry, cx: &mut ViewContext<Self>) {
+        let Some(selected_entry) = self.selected_entry else {
+            return;
+        };
+        let Some(node) = self
+            .hierarchy
+            .as_ref()
+            .and_then(|hierarchy| hierarchy.nodes.get(selected_entry))
+        else {
+            return;
+        };
+        if node.expanded {
+            self.collapse_entry(selected_entry, cx);
+        } else if let Some(parent) = node.parent {
+            self.select_entry(parent, cx);
+        }
+    }
+
+    fn open(&mut self, _: &Open, cx: &mut ViewContext<Self>) {
+        if let Some(selected_entry) = self.selected_entry {
+            self.open_entry(selected_entry, false, cx);
+        }
+    }
+
+    fn confirm(&mut self, _: &Confirm, cx: &mut ViewContext<Self>) {
+        if let Some(selected_entry) = self.selected_entry {
+            self.open_entry(selected_entry, true, cx);
+        }
+    }
+
+    fn open_entry(&mut self, ix: usize, focus_opened_item: bool, cx: &mut ViewContext<Self>) {
+        let Some(location) = self
+            .hierarchy
+            .as_ref()
+            .and_then(|hierarchy| hierarchy.nodes.get(ix))
+            .map(|node| node.item.location.clone())
+        else {
+            return;
+        };
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+
+        let editor = workspace.update(cx, |workspace, cx| {
+            let pane = workspace.active_pane().clone();
+            workspace.open_project_item::<Editor>(pane, location.buffer.clone(), cx)
+        });
+        let range = location.range.to_offset(location.buffer.read(cx));
+        editor.update(cx, |editor, cx| {
+            editor.change_selections(Some(Autoscroll::center()), cx, |s| {
+                s.select_ranges([range]);
+            });
+        });
+        if focus_opened_item {
+            cx.focus_view(&editor);
+        }
+    }
+
+    fn dispatch_context(&self) -> KeyContext {
+        let mut dispatch_context = KeyContext::new_with_defaults();
+        dispatch_context.add("HierarchyPanel");
+        dispatch_context.add("menu");
+        dispatch_context
+    }
+
+    fn render_entry(
+        &self,
+        ix: usize,
+        node: &HierarchyNode,
+        cx: &mut ViewContext<Self>,
+    ) -> Stateful<Div> {
+        let settings = HierarchyPanelSettings::get_global(cx);
+        let toggle = match &node.children {
+            NodeChildren::Loaded(children) if children.is_empty() => None,
+            _ => Some(node.expanded),
+        };
+        let is_loading = matches!(node.children, NodeChildren::Loading(_));
+        div().id(ix).child(
+            ListItem::new(ix)
+                .indent_level(node.depth)
+                .indent_step_size(px(settings.indent_size))
+                .selected(self.selected_entry == Some(ix))
+                .toggle(toggle)
+                .on_toggle(cx.listener(move |this, _, cx| this.toggle_expanded(ix, cx)))
+                .child(
+                    h_flex()
+                        .h_6()
+                        .gap_2()
+                        .child(Label::new(node.item.name.clone()).single_line())
+                        .children(node.item.detail.clone().map(|detail| {
+                            Label::new(detail)
+                                .size(LabelSize::Small)
+                                .color(Color::Muted)
+                                .single_line()
+                        })),
+                )
+                .end_slot(is_loading.then(|| {
+                    Label::new("Loading…")
+                        .size(LabelSize::Small)
+                        .color(Color::Muted)
+                }))
+                .on_click(cx.listener(move |this, event: &ClickEvent, cx| {
+                    this.select_entry(ix, cx);
+                    this.open_entry(ix, event.up.click_count > 1, cx);
+                })),
+        )
+    }
+}
+
+impl Render for HierarchyPanel {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let Some(hierarchy) = self.hierarchy.as_ref() else {
+            return v_flex()
+                .id("empty-hierarchy-panel")
+                .size_full()
+                .p_4()
+                .track_focus(&self.focus_handle)
+                .child(
+                    Label::new("Show the calls or the types related to a symbol from the editor")
+                        .color(Color::Muted),
+                );
+        };
+
+        v_flex()
+            .id("hierarchy-panel")
+            .size_full()
+            .key_context(self.dispatch_context())
+            .on_action(cx.listener(Self::select_next))
+            .on_action(cx.listener(Self::select_prev))
+            .on_action(cx.listener(Self::select_first))
+            .on_action(cx.listener(Self::select_last))
+            .on_action(cx.listener(Self::expand_selected_entry))
+            .on_action(cx.listener(Self::collapse_selected_entry))
+            .on_action(cx.listener(Self::open))
+            .on_action(cx.listener(Self::confirm))
+            .track_focus(&self.focus_handle)
+            .child(
+                h_flex()
+                    .px_2()
+                    .h_8()
+                    .border_b_1()
+                    .border_color(cx.theme().colors().border)
+                    .child(Label::new(hierarchy.kind.title())),
+            )
+            .child(
+                uniform_list(
+                    cx.view().clone(),
+                    "entries",
+                    self.visible_entries.len(),
+                    |this, range, cx| {
+                        let Some(hierarchy) = this.hierarchy.as_ref() else {
+                            return Vec::new();
+                        };
+                        this.visible_entries[range]
+                            .iter()
+                            .map(|ix| this.render_entry(*ix, &hierarchy.nodes[*ix], cx))
+                            .collect()
+                    },
+                )
+                .size_full()
+                .track_scroll(self.scroll_handle.clone()),
+            )
+    }
+}
+
+impl FocusableView for HierarchyPanel {
+    fn focus_handle(&self, _cx: &AppContext) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl EventEmitter<PanelEvent> for HierarchyPanel {}
+
+impl Panel for HierarchyPanel {
+    fn position(&self, cx: &WindowContext) -> DockPosition {
+        match HierarchyPanelSettings::get_global(cx).dock {
+            HierarchyPanelDockPosition::Left => DockPosition::Left,
+            HierarchyPanelDockPosition::Right => DockPosition::Right,
+        }
+    }
+
+    fn position_is_valid(&self, position: DockPosition) -> bool {
+        matches!(position, DockPosition::Left | DockPosition::Right)
+    }
+
+    fn set_position(&mut self, position: DockPosition, cx: &mut ViewContext<Self>) {
+        settings::update_settings_file::<HierarchyPanelSettings>(
+            self.fs.clone(),
+            cx,
+            move |settings| {
+                let dock = match position {
+                    DockPosition::Left | DockPosition::Bottom => HierarchyPanelDockPosition::Left,
+                    DockPosition::Right => HierarchyPanelDockPosition::Right,
+                };
+                settings.dock = Some(dock);
+            },
+        );
+    }
+
+    fn size(&self, cx: &WindowContext) -> Pixels {
+        self.width
+            .unwrap_or_else(|| HierarchyPanelSettings::get_global(cx).default_width)
+    }
+
+    fn set_size(&mut self, size: Option<Pixels>, cx: &mut ViewContext<Self>) {
+        self.width = size;
+        self.serialize(cx);
+        cx.notify();
+    }
+
+    fn icon(&self, cx: &WindowContext) -> Option<IconName> {
+        HierarchyPanelSettings::get_global(cx)
+            .button
+            .then(|| IconName::Code)
+    }
+
+    fn icon_tooltip(&self, _cx: &WindowContext) -> Option<&'static str> {
+        Some("Hierarchy Panel")
+    }
+
+    fn toggle_action(&self) -> Box<dyn Action> {
+        Box::new(ToggleFocus)
+    }
+
+    fn persistent_name() -> &'static str {
+        "Hierarchy Panel"
+    }
+}
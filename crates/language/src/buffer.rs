@@ -368,6 +368,17 @@ pub trait File: Send + Sync {
 
     /// Return whether Zed considers this to be a private file.
     fn is_private(&self) -> bool;
+
+    /// Returns the encoding of the file on disk, which its contents are decoded from and saved with.
+    fn encoding(&self) -> Encoding {
+        Encoding::default()
+    }
+
+    /// Returns whether the file exceeds the large file threshold, in which case its buffer
+    /// is neither parsed, nor diffed, nor registered with language servers.
+    fn is_large(&self) -> bool {
+        false
+    }
 }
 
 /// The file associated with a buffer, in the case where the file is on the local disk.
@@ -375,8 +386,8 @@ pub trait LocalFile: File {
     /// Returns the absolute path of this file.
     fn abs_path(&self, cx: &AppContext) -> PathBuf;
 
-    /// Loads the file's contents from disk.
-    fn load(&self, cx: &AppContext) -> Task<Result<String>>;
+    /// Loads the file's contents from disk, re-evaluating whether it exceeds the large file threshold.
+    fn load(&self, cx: &AppContext) -> Task<Result<LoadedFile>>;
 
     /// Called when the buffer is reloaded from disk.
     fn buffer_reloaded(
@@ -478,6 +489,22 @@ pub struct Chunk<'a> {
     pub is_tab: bool,
 }
 
+/// The contents of a local file, loaded from disk to reload its buffer.
+pub struct LoadedFile {
+    /// The file, with its [`File::is_large`] state matching its current size.
+    pub file: Arc<dyn File>,
+    pub contents: LoadedContents,
+}
+
+/// The text of a file reloaded from disk.
+pub enum LoadedContents {
+    /// The text of a file under the large file threshold, which is diffed against the buffer's text.
+    Text(String),
+    /// The text of a file over the large file threshold, decoded chunk by chunk.
+    /// It replaces the buffer's text as a whole, as large files are not diffed.
+    Large { text: Rope, line_ending: LineEnding },
+}
+
 /// A set of edits to a given version of a buffer, computed asynchronously.
 pub struct Diff {
     pub(crate) base_version: clock::Global,
@@ -795,7 +822,7 @@ impl Buffer {
         let (tx, rx) = futures::channel::oneshot::channel();
         let prev_version = self.text.version();
         self.reload_task = Some(cx.spawn(|this, mut cx| async move {
-            let Some((new_mtime, new_text)) = this.update(&mut cx, |this, cx| {
+            let Some((new_mtime, loaded)) = this.update(&mut cx, |this, cx| {
                 let file = this.file.as_ref()?.as_local()?;
                 Some((file.mtime(), file.load(cx)))
             })?
@@ -803,7 +830,36 @@ impl Buffer {
                 return Ok(());
             };
 
-            let new_text = new_text.await?;
+            let LoadedFile { file, contents } = loaded.await?;
+            this.update(&mut cx, |this, cx| {
+                if this
+                    .file
+                    .as_ref()
+                    .map_or(false, |old_file| old_file.is_large() != file.is_large())
+                {
+                    this.file_updated(file, cx);
+                }
+            })?;
+
+            let new_text = match contents {
+                LoadedContents::Text(new_text) => new_text,
+                LoadedContents::Large { text, line_ending } => {
+                    return this.update(&mut cx, |this, cx| {
+                        if this.version() == prev_version {
+                            this.finalize_last_transaction();
+                            this.edit([(0..this.len(), text.to_string())], None, cx);
+                            tx.send(this.finalize_last_transaction().cloned()).ok();
+                            this.has_conflict = false;
+                            this.did_reload(this.version(), line_ending, new_mtime, cx);
+                        } else {
+                            this.has_conflict = true;
+                            this.did_reload(prev_version, this.line_ending(), this.saved_mtime, cx);
+                        }
+
+                        this.reload_task.take();
+                    });
+                }
+            };
             let diff = this
                 .update(&mut cx, |this, cx| this.diff(new_text.clone(), cx))?
                 .await;
@@ -863,7 +919,10 @@ impl Buffer {
         let mut file_changed = false;
 
         if let Some(old_file) = self.file.as_ref() {
-            if new_file.path() != old_file.path() {
+            if new_file.path() != old_file.path()
+                || new_file.encoding() != old_file.encoding()
+                || new_file.is_large() != old_file.is_large()
+            {
                 file_changed = true;
             }
 
@@ -904,6 +963,10 @@ impl Buffer {
     /// Sets the text that will be used to compute a Git diff
     /// against the buffer text.
     pub fn set_diff_base(&mut self, diff_base: Option<String>, cx: &mut ModelContext<Self>) {
+        // Large files are not diffed against the index.
+        if self.file.as_ref().map_or(false, |file| file.is_large()) {
+            return;
+        }
         self.diff_base = diff_base
             .map(|mut raw_diff_base| {
                 LineEnding::normalize(&mut raw_diff_base);
@@ -2993,6 +3056,46 @@ impl BufferSnapshot {
         })
     }
 
+    /// Returns the ranges that should be edited together with the one containing the given position,
+    /// e.g. the names of an opening and a closing tag, according to the linked editing query.
+    /// Returns no ranges if the position is not within any of them.
+    pub fn linked_editing_ranges<T: ToOffset>(&self, position: T) -> Vec<Range<usize>> {
+        let offset = position.to_offset(self);
+        let mut syntax_matches = self.syntax.matches(offset..offset, self, |grammar| {
+            grammar
+                .linked_editing_config
+                .as_ref()
+                .map(|config| &config.query)
+        });
+
+        let configs = syntax_matches
+            .grammars()
+            .iter()
+            .map(|grammar| grammar.linked_editing_config.as_ref())
+            .collect::<Vec<_>>();
+
+        while let Some(mat) = syntax_matches.peek() {
+            if let Some(config) = configs[mat.grammar_index] {
+                let ranges = mat
+                    .captures
+                    .iter()
+                    .filter(|capture| capture.index == config.linked_capture_ix)
+                    .map(|capture| capture.node.byte_range())
+                    .collect::<Vec<_>>();
+                if ranges.len() > 1
+                    && ranges
+                        .iter()
+                        .any(|range| range.start <= offset && offset <= range.end)
+                {
+                    return ranges;
+                }
+            }
+            syntax_matches.advance();
+        }
+
+        Vec::new()
+    }
+
     pub fn runnable_ranges(
         &self,
         range: Range<Anchor>,
@@ -993,6 +993,47 @@ fn test_enclosing_bracket_ranges_where_brackets_are_not_outermost_children(cx: &
     );
 }
 
+#[gpui::test]
+fn test_linked_editing_ranges(cx: &mut AppContext) {
+    let language = Language::new(
+        LanguageConfig {
+            name: "TSX".into(),
+            ..Default::default()
+        },
+        Some(tree_sitter_typescript::language_tsx()),
+    )
+    .with_linked_editing_query(
+        r#"
+        (jsx_element
+            open_tag: (jsx_opening_element name: (_) @linked)
+            close_tag: (jsx_closing_element name: (_) @linked))
+        "#,
+    )
+    .unwrap();
+
+    let text = "let a = <div><span>b</span></div>;";
+    let buffer = cx.new_model(|cx| Buffer::local(text, cx).with_language(Arc::new(language), cx));
+    let snapshot = buffer.update(cx, |buffer, _| buffer.snapshot());
+
+    let range_of = |needle: &str, nth: usize| {
+        let start = text.match_indices(needle).nth(nth).unwrap().0;
+        start..start + needle.len()
+    };
+    assert_eq!(
+        snapshot.linked_editing_ranges(text.find("span").unwrap() + 2),
+        [range_of("span", 0), range_of("span", 1)]
+    );
+    // The ends of the tag names are still part of them.
+    assert_eq!(
+        snapshot.linked_editing_ranges(range_of("div", 1).end),
+        [range_of("div", 0), range_of("div", 1)]
+    );
+    assert_eq!(
+        snapshot.linked_editing_ranges(text.find('b').unwrap()),
+        Vec::<Range<usize>>::new()
+    );
+}
+
 #[gpui::test]
 fn test_range_for_syntax_ancestor(cx: &mut AppContext) {
     cx.new_model(|cx| {
@@ -1031,6 +1072,73 @@ fn test_range_for_syntax_ancestor(cx: &mut AppContext) {
     }
 }
 
+#[gpui::test]
+fn test_structural_search(cx: &mut AppContext) {
+    cx.new_model(|cx| {
+        let text = r#"
+            fn main() {
+                foo(1, bar(2));
+                foo(baz, baz);
+                // foo(in_a_comment)
+                let x = foo();
+            }
+        "#
+        .unindent();
+        let buffer = Buffer::local(text, cx).with_language(Arc::new(rust_lang()), cx);
+        let snapshot = buffer.snapshot();
+
+        let matched_text = |pattern: &str, range: Range<usize>| {
+            StructuralPattern::new(pattern)
+                .unwrap()
+                .matches(&snapshot, range)
+                .into_iter()
+                .map(|mat| snapshot.text_for_range(mat.range).collect::<String>())
+                .collect::<Vec<_>>()
+        };
+        let full_range = 0..snapshot.len();
+
+        assert_eq!(
+            matched_text("foo($A, $B)", full_range.clone()),
+            ["foo(1, bar(2))", "foo(baz, baz)"]
+        );
+        assert_eq!(
+            matched_text("foo($A, $A)", full_range.clone()),
+            ["foo(baz, baz)"]
+        );
+        assert_eq!(
+            matched_text("foo($$$ARGS)", full_range.clone()),
+            ["foo(1, bar(2))", "foo(baz, baz)", "foo()"]
+        );
+        assert_eq!(matched_text("bar($_)", full_range.clone()), ["bar(2)"]);
+        assert_eq!(
+            matched_text(
+                r#"(call_expression function: (identifier) @name (#eq? @name "bar")) @match"#,
+                full_range.clone()
+            ),
+            ["bar(2)"]
+        );
+
+        let second_line =
+            snapshot.point_to_offset(Point::new(2, 0))..snapshot.point_to_offset(Point::new(3, 0));
+        assert_eq!(matched_text("foo($$$ARGS)", second_line), ["foo(baz, baz)"]);
+
+        let pattern = StructuralPattern::new("foo($A, $B)").unwrap();
+        let mat = &pattern.matches(&snapshot, full_range)[0];
+        assert_eq!(
+            mat.replacement("foo($B, $A) + ${A} + $$ + $C"),
+            "foo(bar(2), 1) + 1 + $ + $C"
+        );
+
+        assert!(StructuralPattern::new("$A $B").is_err());
+        assert!(StructuralPattern::new("(identifier) @name")
+            .unwrap()
+            .is_query());
+        assert!(!StructuralPattern::new("(a + $B)").unwrap().is_query());
+
+        buffer
+    });
+}
+
 #[gpui::test]
 fn test_autoindent_with_soft_tabs(cx: &mut AppContext) {
     init_settings(cx, |_| {});
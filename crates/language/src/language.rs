@@ -13,6 +13,7 @@ mod language_registry;
 pub mod language_settings;
 mod outline;
 pub mod proto;
+mod structural_search;
 mod syntax_map;
 mod task_context;
 
@@ -73,8 +74,9 @@ pub use language_registry::{
 };
 pub use lsp::LanguageServerId;
 pub use outline::{Outline, OutlineItem};
+pub use structural_search::{StructuralMatch, StructuralPattern};
 pub use syntax_map::{OwnedSyntaxLayer, SyntaxLayer};
-pub use text::LineEnding;
+pub use text::{Encoding, LineEnding};
 pub use tree_sitter::{Node, Parser, Tree, TreeCursor};
 
 /// Initializes the `language` crate.
@@ -847,6 +849,7 @@ pub struct Grammar {
     pub(crate) brackets_config: Option<BracketConfig>,
     pub(crate) redactions_config: Option<RedactionConfig>,
     pub(crate) runnable_config: Option<RunnableConfig>,
+    pub(crate) linked_editing_config: Option<LinkedEditingConfig>,
     pub(crate) indents_config: Option<IndentConfig>,
     pub outline_config: Option<OutlineConfig>,
     pub embedding_config: Option<EmbeddingConfig>,
@@ -901,6 +904,12 @@ struct RunnableConfig {
     pub run_capture_ix: u32,
 }
 
+struct LinkedEditingConfig {
+    pub query: Query,
+    /// index of the capture that corresponds to @linked
+    pub linked_capture_ix: u32,
+}
+
 struct OverrideConfig {
     query: Query,
     values: HashMap<u32, (String, LanguageConfigOverride)>,
@@ -943,6 +952,7 @@ impl Language {
                     override_config: None,
                     redactions_config: None,
                     runnable_config: None,
+                    linked_editing_config: None,
                     error_query: Query::new(&ts_language, "(ERROR) @error").unwrap(),
                     ts_language,
                     highlight_map: Default::default(),
@@ -1003,6 +1013,11 @@ impl Language {
                 .with_runnable_query(query.as_ref())
                 .context("Error loading tests query")?;
         }
+        if let Some(query) = queries.linked_editing {
+            self = self
+                .with_linked_editing_query(query.as_ref())
+                .context("Error loading linked editing query")?;
+        }
         Ok(self)
     }
 
@@ -1301,6 +1316,25 @@ impl Language {
         Ok(self)
     }
 
+    pub fn with_linked_editing_query(mut self, source: &str) -> anyhow::Result<Self> {
+        let grammar = self
+            .grammar_mut()
+            .ok_or_else(|| anyhow!("cannot mutate grammar"))?;
+
+        let query = Query::new(&grammar.ts_language, source)?;
+        let mut linked_capture_ix = None;
+        get_capture_indices(&query, &mut [("linked", &mut linked_capture_ix)]);
+
+        if let Some(linked_capture_ix) = linked_capture_ix {
+            grammar.linked_editing_config = Some(LinkedEditingConfig {
+                query,
+                linked_capture_ix,
+            });
+        }
+
+        Ok(self)
+    }
+
     fn grammar_mut(&mut self) -> Option<&mut Grammar> {
         Arc::get_mut(self.grammar.as_mut()?)
     }
@@ -110,10 +110,17 @@ pub struct LanguageSettings {
     pub extend_comment_on_newline: bool,
     /// Inlay hint related settings.
     pub inlay_hints: InlayHintSettings,
+    /// Whether to color code using semantic tokens from language servers,
+    /// on top of the tree-sitter syntax highlighting.
+    pub semantic_tokens: bool,
+    /// Whether to show code lenses from language servers above the items they refer to.
+    pub code_lens: bool,
     /// Whether to automatically close brackets.
     pub use_autoclose: bool,
     // Controls how the editor handles the autoclosed characters.
     pub always_treat_brackets_as_autoclosed: bool,
+    /// Whether to edit linked ranges, such as the names of matching tags, together.
+    pub linked_edits: bool,
     /// Which code actions to run on save
     pub code_actions_on_format: HashMap<String, bool>,
 }
@@ -305,6 +312,18 @@ pub struct LanguageSettingsContent {
     /// Inlay hint related settings.
     #[serde(default)]
     pub inlay_hints: Option<InlayHintSettings>,
+    /// Whether to color code using semantic tokens from language servers,
+    /// on top of the tree-sitter syntax highlighting.
+    ///
+    /// Default: false
+    #[serde(default)]
+    pub semantic_tokens: Option<bool>,
+    /// Whether to show code lenses from language servers, such as reference counts
+    /// or "Run test" actions, above the items they refer to.
+    ///
+    /// Default: false
+    #[serde(default)]
+    pub code_lens: Option<bool>,
     /// Whether to automatically type closing characters for you. For example,
     /// when you type (, Zed will automatically add a closing ) at the correct position.
     ///
@@ -318,6 +337,11 @@ pub struct LanguageSettingsContent {
     ///
     /// Default: false
     pub always_treat_brackets_as_autoclosed: Option<bool>,
+    /// Whether to edit linked ranges together, such as the names of an opening
+    /// and its closing tag: renaming one renames the other one as you type.
+    ///
+    /// Default: true
+    pub linked_edits: Option<bool>,
     /// Which code actions to run on save after the formatter.
     /// These are not run if formatting is off.
     ///
@@ -712,6 +736,7 @@ fn merge_settings(settings: &mut LanguageSettings, src: &LanguageSettingsContent
         &mut settings.always_treat_brackets_as_autoclosed,
         src.always_treat_brackets_as_autoclosed,
     );
+    merge(&mut settings.linked_edits, src.linked_edits);
     merge(&mut settings.show_wrap_guides, src.show_wrap_guides);
     merge(&mut settings.wrap_guides, src.wrap_guides.clone());
     merge(
@@ -749,6 +774,8 @@ fn merge_settings(settings: &mut LanguageSettings, src: &LanguageSettingsContent
         src.extend_comment_on_newline,
     );
     merge(&mut settings.inlay_hints, src.inlay_hints);
+    merge(&mut settings.semantic_tokens, src.semantic_tokens);
+    merge(&mut settings.code_lens, src.code_lens);
 }
 
 #[cfg(test)]
@@ -0,0 +1,528 @@
+//! Structural search, which matches the syntax trees of buffers rather than their text.
+//!
+//! A pattern is either a Tree-sitter query, like `(call_expression function: (identifier) @name)`,
+//! or a code template with metavariables, like `foo($A, $$$REST)`. The template is parsed with the
+//! grammar of each language it is searched in, and matches every node with the same structure.
+use crate::{
+    syntax_map::{QueryCursorHandle, TextProvider},
+    with_parser, BufferSnapshot, Grammar, GrammarId, Language,
+};
+use anyhow::{anyhow, Result};
+use collections::HashMap;
+use parking_lot::Mutex;
+use std::{cmp::Reverse, fmt, ops::Range, sync::Arc};
+use tree_sitter::{Node, Query, Tree};
+
+/// The prefix of the identifiers, which replace the metavariables of a template before it is
+/// parsed, followed by the index of the metavariable.
+const PLACEHOLDER_PREFIX: &str = "zed_metavariable_";
+
+/// The name of the capture, which is the range of a query's match. Without it, a match spans all
+/// of its captures.
+const MATCH_CAPTURE_NAME: &str = "match";
+
+/// A structural search pattern, compiled lazily for the grammar of each language it is used with.
+pub struct StructuralPattern {
+    source: String,
+    kind: PatternKind,
+    compiled: Mutex<HashMap<GrammarId, Option<Arc<CompiledPattern>>>>,
+}
+
+/// A match of a [`StructuralPattern`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StructuralMatch {
+    pub range: Range<usize>,
+    /// The text of the query's captures, or of the template's metavariables, by their name.
+    pub captures: HashMap<String, String>,
+}
+
+enum PatternKind {
+    Query,
+    Template(Template),
+}
+
+enum CompiledPattern {
+    Query(Query),
+    Template(Tree),
+}
+
+struct Template {
+    /// The template, with its metavariables replaced by placeholder identifiers.
+    text: String,
+    metavariables: Vec<Metavariable>,
+    /// The words of the template outside of its metavariables, which every match contains.
+    words: Vec<String>,
+}
+
+struct Metavariable {
+    /// The name of the metavariable, `_` for one that matches anything without being captured.
+    name: String,
+    /// Whether the metavariable, written as `$$$NAME`, matches any number of sibling nodes.
+    multiple: bool,
+}
+
+impl StructuralPattern {
+    /// Creates a pattern, which is a query, if it starts with a parenthesis or a bracket and
+    /// contains a capture, and a code template otherwise.
+    pub fn new(source: &str) -> Result<Self> {
+        let source = source.trim();
+        if source.is_empty() {
+            return Err(anyhow!("structural search pattern is empty"));
+        }
+
+        let kind = if is_query(source) {
+            PatternKind::Query
+        } else {
+            let template = Template::parse(source);
+            let has_code = template
+                .text
+                .split(|c: char| c.is_whitespace())
+                .any(|token| !token.is_empty() && !is_placeholder(token));
+            if !has_code {
+                return Err(anyhow!(
+                    "structural search template contains nothing but metavariables"
+                ));
+            }
+            PatternKind::Template(template)
+        };
+
+        Ok(Self {
+            source: source.to_string(),
+            kind,
+            compiled: Default::default(),
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    pub fn is_query(&self) -> bool {
+        matches!(self.kind, PatternKind::Query)
+    }
+
+    /// Returns whether the given text may contain a match, as it contains every word of the
+    /// template outside of its metavariables.
+    pub fn may_match(&self, text: &str) -> bool {
+        match &self.kind {
+            PatternKind::Query => true,
+            PatternKind::Template(template) => template
+                .words
+                .iter()
+                .all(|word| text.contains(word.as_str())),
+        }
+    }
+
+    /// Returns the non-overlapping matches within the given range of the buffer, in all of its
+    /// syntax layers, ordered by their position.
+    pub fn matches(&self, buffer: &BufferSnapshot, range: Range<usize>) -> Vec<StructuralMatch> {
+        // A buffer, which was just opened, may not have been parsed yet.
+        let unparsed_tree;
+        let mut roots = buffer
+            .syntax_layers()
+            .map(|layer| (layer.language, layer.node()))
+            .collect::<Vec<_>>();
+        if roots.is_empty() {
+            if let Some((language, grammar)) = buffer
+                .language()
+                .and_then(|language| Some((language, language.grammar()?)))
+            {
+                unparsed_tree = grammar.parse_text(buffer.as_rope(), None);
+                roots.push((language, unparsed_tree.root_node()));
+            }
+        }
+
+        let mut matches = Vec::new();
+        for (language, root) in roots {
+            let Some(compiled) = self.compiled_for(language) else {
+                continue;
+            };
+            match (&*compiled, &self.kind) {
+                (CompiledPattern::Query(query), _) => {
+                    query_matches(query, root, buffer, &range, &mut matches)
+                }
+                (CompiledPattern::Template(tree), PatternKind::Template(template)) => {
+                    let matcher = TemplateMatcher { template, buffer };
+                    matcher.matches(tree, root, &range, &mut matches)
+                }
+                (CompiledPattern::Template(_), PatternKind::Query) => {}
+            }
+        }
+
+        matches.sort_by_key(|mat| (mat.range.start, Reverse(mat.range.end)));
+        let mut end = 0;
+        matches.retain(|mat| {
+            if mat.range.start < end {
+                return false;
+            }
+            end = mat.range.end;
+            true
+        });
+        matches
+    }
+
+    fn compiled_for(&self, language: &Language) -> Option<Arc<CompiledPattern>> {
+        let grammar = language.grammar()?;
+        self.compiled
+            .lock()
+            .entry(grammar.id())
+            .or_insert_with(|| self.compile(grammar).map(Arc::new))
+            .clone()
+    }
+
+    /// Compiles the pattern for the grammar, returning `None`, when it is not valid in its language.
+    fn compile(&self, grammar: &Grammar) -> Option<CompiledPattern> {
+        match &self.kind {
+            PatternKind::Query => Query::new(&grammar.ts_language, &self.source)
+                .ok()
+                .map(CompiledPattern::Query),
+            PatternKind::Template(template) => {
+                let tree = with_parser(|parser| {
+                    parser.set_language(&grammar.ts_language).ok()?;
+                    parser.parse(&template.text, None)
+                })?;
+                if contains_error(tree.root_node()) {
+                    return None;
+                }
+                Some(CompiledPattern::Template(tree))
+            }
+        }
+    }
+}
+
+impl fmt::Debug for StructuralPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StructuralPattern")
+            .field("source", &self.source)
+            .field("is_query", &self.is_query())
+            .finish()
+    }
+}
+
+impl StructuralMatch {
+    /// Returns the replacement text for the match, in which `$NAME` and `${NAME}` are replaced
+    /// with the text of the capture or metavariable with that name, and `$$` with a `$`.
+    pub fn replacement(&self, replacement: &str) -> String {
+        let mut result = String::with_capacity(replacement.len());
+        let mut rest = replacement;
+        while let Some(ix) = rest.find('$') {
+            result.push_str(&rest[..ix]);
+            rest = &rest[ix + 1..];
+
+            if let Some((name, tail)) = rest
+                .strip_prefix('{')
+                .and_then(|braced| braced.split_once('}'))
+            {
+                if let Some(text) = self.captures.get(name) {
+                    result.push_str(text);
+                    rest = tail;
+                    continue;
+                }
+            }
+
+            // Also accept the `$$$NAME` of the metavariables, which match multiple nodes.
+            let name_start = rest.len() - rest.trim_start_matches('$').len();
+            let name_len = rest[name_start..]
+                .find(|c: char| !is_name_char(c))
+                .unwrap_or(rest.len() - name_start);
+            let name = &rest[name_start..name_start + name_len];
+            if let Some(text) = self.captures.get(name).filter(|_| !name.is_empty()) {
+                result.push_str(text);
+                rest = &rest[name_start + name_len..];
+            } else {
+                result.push('$');
+                rest = rest.strip_prefix('$').unwrap_or(rest);
+            }
+        }
+        result.push_str(rest);
+        result
+    }
+}
+
+impl Template {
+    fn parse(source: &str) -> Self {
+        let mut text = String::with_capacity(source.len());
+        let mut metavariables = Vec::<Metavariable>::new();
+        let mut words = Vec::new();
+        let mut word = String::new();
+        let mut rest = source;
+
+        while let Some(c) = rest.chars().next() {
+            if c == '$' {
+                let multiple = rest.starts_with("$$$");
+                let name_start = if multiple { 3 } else { 1 };
+                let name_len = rest[name_start..]
+                    .find(|c: char| !is_name_char(c))
+                    .unwrap_or(rest.len() - name_start);
+                let name = &rest[name_start..name_start + name_len];
+                if name.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+                    words.extend(Some(std::mem::take(&mut word)).filter(|w| !w.is_empty()));
+                    let ix = metavariables.len();
+                    metavariables.push(Metavariable {
+                        name: name.to_string(),
+                        multiple,
+                    });
+                    text.push_str(PLACEHOLDER_PREFIX);
+                    text.push_str(&ix.to_string());
+                    rest = &rest[name_start + name_len..];
+                    continue;
+                }
+            }
+
+            if is_name_char(c) {
+                word.push(c);
+            } else {
+                words.extend(Some(std::mem::take(&mut word)).filter(|w| !w.is_empty()));
+            }
+            text.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+        words.extend(Some(word).filter(|w| !w.is_empty()));
+
+        Self {
+            text,
+            metavariables,
+            words,
+        }
+    }
+}
+
+struct TemplateMatcher<'a> {
+    template: &'a Template,
+    buffer: &'a BufferSnapshot,
+}
+
+type Bindings = HashMap<String, String>;
+
+impl<'a> TemplateMatcher<'a> {
+    fn matches(
+        &self,
+        tree: &Tree,
+        root: Node,
+        range: &Range<usize>,
+        matches: &mut Vec<StructuralMatch>,
+    ) {
+        let template_root = template_root(tree.root_node());
+        let mut cursor = root.walk();
+        loop {
+            let node = cursor.node();
+            let node_range = node.byte_range();
+            let mut descend = node_range.start < range.end && node_range.end > range.start;
+            if descend
+                && node.kind_id() == template_root.kind_id()
+                && range.start <= node_range.start
+                && node_range.end <= range.end
+            {
+                let mut bindings = Bindings::default();
+                if self.match_node(template_root, node, &mut bindings) {
+                    matches.push(StructuralMatch {
+                        range: node_range,
+                        captures: bindings,
+                    });
+                    // Matches within a match would overlap it.
+                    descend = false;
+                }
+            }
+
+            if descend && cursor.goto_first_child() {
+                continue;
+            }
+            while !cursor.goto_next_sibling() {
+                if !cursor.goto_parent() {
+                    return;
+                }
+            }
+        }
+    }
+
+    fn match_node(&self, template_node: Node, node: Node, bindings: &mut Bindings) -> bool {
+        if let Some(metavariable) = self.metavariable(template_node) {
+            return self.bind(metavariable, node.byte_range(), bindings);
+        }
+        if template_node.kind_id() != node.kind_id() {
+            return false;
+        }
+
+        let template_children = significant_children(template_node);
+        let children = significant_children(node);
+        if template_children.is_empty() {
+            return children.is_empty()
+                && self.template_text(template_node) == self.buffer_text(node.byte_range());
+        }
+        self.match_children(&template_children, &children, bindings)
+    }
+
+    fn match_children(
+        &self,
+        template_children: &[Node],
+        children: &[Node],
+        bindings: &mut Bindings,
+    ) -> bool {
+        let Some((template_child, template_rest)) = template_children.split_first() else {
+            return children.is_empty();
+        };
+
+        match self.metavariable(*template_child) {
+            Some(metavariable) if metavariable.multiple => {
+                for count in 0..=children.len() {
+                    let range = match &children[..count] {
+                        [] => 0..0,
+                        [first, .., last] => first.start_byte()..last.end_byte(),
+                        [only] => only.byte_range(),
+                    };
+                    let saved = bindings.clone();
+                    if self.bind(metavariable, range, bindings)
+                        && self.match_children(template_rest, &children[count..], bindings)
+                    {
+                        return true;
+                    }
+                    *bindings = saved;
+                }
+                false
+            }
+            _ => {
+                let Some((child, rest)) = children.split_first() else {
+                    return false;
+                };
+                let saved = bindings.clone();
+                if self.match_node(*template_child, *child, bindings)
+                    && self.match_children(template_rest, rest, bindings)
+                {
+                    return true;
+                }
+                *bindings = saved;
+                false
+            }
+        }
+    }
+
+    /// Binds the metavariable to the text of the range, returning false, if it was already bound
+    /// to a different text.
+    fn bind(
+        &self,
+        metavariable: &Metavariable,
+        range: Range<usize>,
+        bindings: &mut Bindings,
+    ) -> bool {
+        if metavariable.name == "_" {
+            return true;
+        }
+        let text = self.buffer_text(range);
+        match bindings.get(&metavariable.name) {
+            Some(bound_text) => *bound_text == text,
+            None => {
+                bindings.insert(metavariable.name.clone(), text);
+                true
+            }
+        }
+    }
+
+    fn metavariable(&self, template_node: Node) -> Option<&'a Metavariable> {
+        let ix = self
+            .template_text(template_node)
+            .strip_prefix(PLACEHOLDER_PREFIX)?
+            .parse::<usize>()
+            .ok()?;
+        self.template.metavariables.get(ix)
+    }
+
+    fn template_text(&self, template_node: Node) -> &'a str {
+        &self.template.text[template_node.byte_range()]
+    }
+
+    fn buffer_text(&self, range: Range<usize>) -> String {
+        self.buffer.text_for_range(range).collect()
+    }
+}
+
+fn query_matches(
+    query: &Query,
+    root: Node,
+    buffer: &BufferSnapshot,
+    range: &Range<usize>,
+    matches: &mut Vec<StructuralMatch>,
+) {
+    let match_capture_ix = query.capture_index_for_name(MATCH_CAPTURE_NAME);
+    let mut cursor = QueryCursorHandle::new();
+    cursor.set_byte_range(range.clone());
+    for mat in cursor.matches(query, root, TextProvider(buffer.as_rope())) {
+        let mut captures = HashMap::default();
+        let mut match_range: Option<Range<usize>> = None;
+        for capture in mat.captures {
+            let capture_range = capture.node.byte_range();
+            if match_capture_ix.map_or(true, |ix| ix == capture.index) {
+                match_range = Some(match match_range {
+                    Some(match_range) => {
+                        match_range.start.min(capture_range.start)
+                            ..match_range.end.max(capture_range.end)
+                    }
+                    None => capture_range.clone(),
+                });
+            }
+            captures.insert(
+                query.capture_names()[capture.index as usize].to_string(),
+                buffer.text_for_range(capture_range).collect(),
+            );
+        }
+
+        if let Some(match_range) = match_range.filter(|match_range| {
+            !match_range.is_empty()
+                && range.start <= match_range.start
+                && match_range.end <= range.end
+        }) {
+            matches.push(StructuralMatch {
+                range: match_range,
+                captures,
+            });
+        }
+    }
+}
+
+/// Returns the node of the parsed template, which matches the nodes in the buffers: the innermost
+/// node spanning the whole template.
+fn template_root(root: Node) -> Node {
+    let mut node = root;
+    loop {
+        match significant_children(node).as_slice() {
+            [child] if node == root || child.byte_range() == node.byte_range() => node = *child,
+            _ => return node,
+        }
+    }
+}
+
+/// Returns the children of the node, which are neither extras, like comments, nor missing nodes,
+/// which the parser inserted to recover from an incomplete template, like a missing semicolon.
+fn significant_children(node: Node) -> Vec<Node> {
+    let mut cursor = node.walk();
+    node.children(&mut cursor)
+        .filter(|child| !child.is_extra() && !child.is_missing())
+        .collect()
+}
+
+fn contains_error(node: Node) -> bool {
+    if node.is_error() {
+        return true;
+    }
+    if !node.has_error() {
+        return false;
+    }
+    let mut cursor = node.walk();
+    let has_error_child = node.children(&mut cursor).any(contains_error);
+    has_error_child
+}
+
+fn is_query(source: &str) -> bool {
+    source.starts_with(['(', '['])
+        && source
+            .match_indices('@')
+            .any(|(ix, _)| source[ix + 1..].starts_with(is_name_char))
+}
+
+fn is_placeholder(text: &str) -> bool {
+    text.strip_prefix(PLACEHOLDER_PREFIX)
+        .map_or(false, |ix| ix.parse::<usize>().is_ok())
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
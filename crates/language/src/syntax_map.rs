@@ -206,9 +206,9 @@ struct ChangedRegion {
 #[derive(Default)]
 struct ChangeRegionSet(Vec<ChangedRegion>);
 
-struct TextProvider<'a>(&'a Rope);
+pub(crate) struct TextProvider<'a>(pub(crate) &'a Rope);
 
-struct ByteChunks<'a>(text::Chunks<'a>);
+pub(crate) struct ByteChunks<'a>(text::Chunks<'a>);
 
 pub(crate) struct QueryCursorHandle(Option<QueryCursor>);
 
@@ -764,6 +764,9 @@ impl SearchableItem for LspLogView {
             case: true,
             word: true,
             regex: true,
+            multiline: true,
+            fancy_regex: true,
+            structural: false,
             // LSP log is read-only.
             replacement: false,
         }
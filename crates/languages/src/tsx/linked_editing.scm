@@ -0,0 +1,5 @@
+(jsx_element
+  open_tag: (jsx_opening_element
+    name: (_) @linked)
+  close_tag: (jsx_closing_element
+    name: (_) @linked))
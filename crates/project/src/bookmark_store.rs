@@ -0,0 +1,63 @@
+//! Project-wide storage of the line bookmarks, shared by all the editors of a file.
+
+use collections::HashMap;
+use gpui::{EventEmitter, Model, ModelContext, WeakModel};
+use language::Buffer;
+use text::Anchor;
+
+use crate::ProjectPath;
+
+/// A bookmarked line of a file, anchored in its buffer to follow the edits.
+#[derive(Clone, Debug)]
+pub struct Bookmark {
+    pub anchor: Anchor,
+    pub label: Option<String>,
+}
+
+pub enum BookmarkStoreEvent {
+    BookmarksChanged(ProjectPath),
+}
+
+/// The bookmarks of the project's opened files, by their paths.
+#[derive(Default)]
+pub struct BookmarkStore {
+    files: HashMap<ProjectPath, FileBookmarks>,
+}
+
+struct FileBookmarks {
+    /// The buffer the anchors belong to, the file's bookmarks are loaded again for a new one.
+    buffer: WeakModel<Buffer>,
+    bookmarks: Vec<Bookmark>,
+}
+
+impl EventEmitter<BookmarkStoreEvent> for BookmarkStore {}
+
+impl BookmarkStore {
+    /// Returns the bookmarks of the file, `None` when they were not loaded for its buffer yet.
+    pub fn bookmarks(&self, path: &ProjectPath, buffer: &Model<Buffer>) -> Option<&[Bookmark]> {
+        let file = self.files.get(path)?;
+        if file.buffer.upgrade().as_ref() != Some(buffer) {
+            return None;
+        }
+        Some(&file.bookmarks)
+    }
+
+    /// Replaces the bookmarks of the file, anchored in the buffer.
+    pub fn set_bookmarks(
+        &mut self,
+        path: ProjectPath,
+        buffer: &Model<Buffer>,
+        bookmarks: Vec<Bookmark>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        self.files.insert(
+            path.clone(),
+            FileBookmarks {
+                buffer: buffer.downgrade(),
+                bookmarks,
+            },
+        );
+        cx.emit(BookmarkStoreEvent::BookmarksChanged(path));
+        cx.notify();
+    }
+}
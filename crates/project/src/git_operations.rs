@@ -0,0 +1,591 @@
+use crate::{Item as _, NoRepositoryError, Project, ProjectPath};
+use anyhow::{anyhow, Context as _, Result};
+use client::{proto, Client, TypedEnvelope};
+use git::{
+    history::{FileHistory, HistoryEntry},
+    repository::{GitChangeKind, GitRepository, GitStatusEntry, RepoPath},
+};
+use gpui::{AppContext, AsyncAppContext, Model, Task};
+use language::Buffer;
+use parking_lot::Mutex;
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use text::BufferId;
+use worktree::WorktreeId;
+
+/// A git operation, that synchronizes the repository with its remote.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GitRemoteOperation {
+    Push,
+    Pull,
+    Fetch,
+}
+
+impl Project {
+    /// Returns the work directories of the git repositories in the visible worktrees, with their current branches.
+    pub fn git_repositories(&self, cx: &AppContext) -> Vec<(ProjectPath, Option<Arc<str>>)> {
+        self.visible_worktrees(cx)
+            .flat_map(|worktree| {
+                let worktree = worktree.read(cx);
+                let worktree_id = worktree.id();
+                worktree
+                    .repositories()
+                    .map(|(work_directory, repository)| {
+                        let work_directory = ProjectPath {
+                            worktree_id,
+                            path: work_directory.clone(),
+                        };
+                        (work_directory, repository.branch())
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    pub fn git_changed_files(
+        &self,
+        work_directory: ProjectPath,
+        cx: &AppContext,
+    ) -> Task<Result<Vec<GitStatusEntry>>> {
+        if self.is_local() {
+            return self
+                .spawn_git_operation(&work_directory, cx, |repository| repository.changed_files());
+        }
+        let request = self.remote_id().map(|project_id| proto::GitChangedFiles {
+            project_id,
+            worktree_id: work_directory.worktree_id.to_proto(),
+            work_directory: work_directory.path.to_string_lossy().into_owned(),
+        });
+        let client = self.client.clone();
+        cx.background_executor().spawn(async move {
+            let request = request.context("project is not shared")?;
+            let response = client.request(request).await?;
+            Ok(response
+                .entries
+                .into_iter()
+                .map(deserialize_status_entry)
+                .collect())
+        })
+    }
+
+    /// Stages the current contents of the files in the repository.
+    pub fn git_stage(
+        &self,
+        work_directory: ProjectPath,
+        paths: Vec<RepoPath>,
+        cx: &AppContext,
+    ) -> Task<Result<()>> {
+        if self.is_local() {
+            return self.spawn_git_operation(&work_directory, cx, move |repository| {
+                repository.stage_paths(&paths)
+            });
+        }
+        let request = self.remote_id().map(|project_id| proto::GitStage {
+            project_id,
+            worktree_id: work_directory.worktree_id.to_proto(),
+            work_directory: work_directory.path.to_string_lossy().into_owned(),
+            repo_paths: serialize_repo_paths(&paths),
+        });
+        self.send_git_request(request, cx)
+    }
+
+    /// Unstages the changes of the files in the repository.
+    pub fn git_unstage(
+        &self,
+        work_directory: ProjectPath,
+        paths: Vec<RepoPath>,
+        cx: &AppContext,
+    ) -> Task<Result<()>> {
+        if self.is_local() {
+            return self.spawn_git_operation(&work_directory, cx, move |repository| {
+                repository.unstage_paths(&paths)
+            });
+        }
+        let request = self.remote_id().map(|project_id| proto::GitUnstage {
+            project_id,
+            worktree_id: work_directory.worktree_id.to_proto(),
+            work_directory: work_directory.path.to_string_lossy().into_owned(),
+            repo_paths: serialize_repo_paths(&paths),
+        });
+        self.send_git_request(request, cx)
+    }
+
+    /// Replaces the index contents of the buffer's file, used to stage its changes partially.
+    pub fn git_set_index_text(
+        &self,
+        buffer: &Model<Buffer>,
+        text: Option<String>,
+        cx: &AppContext,
+    ) -> Task<Result<()>> {
+        if self.is_local() {
+            let repository_and_path = self.local_git_repository_for_buffer(buffer, cx);
+            return cx.background_executor().spawn(async move {
+                let (repository, repo_path) = repository_and_path?;
+                let repository = repository.lock();
+                repository.set_index_text(&repo_path, text)
+            });
+        }
+        let request = self.remote_id().map(|project_id| proto::GitSetIndexText {
+            project_id,
+            buffer_id: buffer.read(cx).remote_id().into(),
+            text,
+        });
+        self.send_git_request(request, cx)
+    }
+
+    /// Commits the staged changes, an empty message keeps the message of the amended commit.
+    pub fn git_commit(
+        &self,
+        work_directory: ProjectPath,
+        message: String,
+        amend: bool,
+        cx: &AppContext,
+    ) -> Task<Result<()>> {
+        if self.is_local() {
+            return self.spawn_git_operation(&work_directory, cx, move |repository| {
+                repository.commit(&message, amend)
+            });
+        }
+        let request = self.remote_id().map(|project_id| proto::GitCommit {
+            project_id,
+            worktree_id: work_directory.worktree_id.to_proto(),
+            work_directory: work_directory.path.to_string_lossy().into_owned(),
+            message,
+            amend,
+        });
+        self.send_git_request(request, cx)
+    }
+
+    pub fn git_remote_operation(
+        &self,
+        work_directory: ProjectPath,
+        operation: GitRemoteOperation,
+        cx: &AppContext,
+    ) -> Task<Result<()>> {
+        if self.is_local() {
+            let repository = self.local_git_repository(&work_directory, cx);
+            return cx.background_executor().spawn(async move {
+                let job = {
+                    let repository = repository?;
+                    let repository = repository.lock();
+                    match operation {
+                        GitRemoteOperation::Push => repository.push(),
+                        GitRemoteOperation::Pull => repository.pull(),
+                        GitRemoteOperation::Fetch => repository.fetch(),
+                    }?
+                };
+                // Waiting for the remote must not block the other operations on the repository.
+                job()
+            });
+        }
+        let kind = match operation {
+            GitRemoteOperation::Push => proto::git_remote_operation::Kind::Push,
+            GitRemoteOperation::Pull => proto::git_remote_operation::Kind::Pull,
+            GitRemoteOperation::Fetch => proto::git_remote_operation::Kind::Fetch,
+        };
+        let request = self
+            .remote_id()
+            .map(|project_id| proto::GitRemoteOperation {
+                project_id,
+                worktree_id: work_directory.worktree_id.to_proto(),
+                work_directory: work_directory.path.to_string_lossy().into_owned(),
+                kind: kind.into(),
+            });
+        self.send_git_request(request, cx)
+    }
+
+    /// Loads the contents of the buffer's file at a revision, like `HEAD` or a commit sha,
+    /// `None` if the file did not exist in it.
+    pub fn git_revision_text(
+        &self,
+        buffer: &Model<Buffer>,
+        revision: String,
+        cx: &AppContext,
+    ) -> Task<Result<Option<String>>> {
+        if self.is_local() {
+            let repository_and_path = self.local_git_repository_for_buffer(buffer, cx);
+            return cx.background_executor().spawn(async move {
+                let (repository, repo_path) = repository_and_path?;
+                let repository = repository.lock();
+                repository.load_revision_text(&repo_path, &revision)
+            });
+        }
+        let request = self.remote_id().map(|project_id| proto::GitRevisionText {
+            project_id,
+            buffer_id: buffer.read(cx).remote_id().into(),
+            revision,
+        });
+        let client = self.client.clone();
+        cx.background_executor().spawn(async move {
+            let request = request.context("project is not shared")?;
+            let response = client.request(request).await?;
+            Ok(response.text)
+        })
+    }
+
+    /// Returns the commits that changed the buffer's file, or only the given rows of its HEAD version.
+    pub fn git_file_history(
+        &self,
+        buffer: &Model<Buffer>,
+        rows: Option<Range<u32>>,
+        cx: &AppContext,
+    ) -> Task<Result<FileHistory>> {
+        if self.is_local() {
+            let repository_and_path = self.local_git_repository_for_buffer(buffer, cx);
+            return cx.background_executor().spawn(async move {
+                let (repository, repo_path) = repository_and_path?;
+                let repository = repository.lock();
+                repository
+                    .file_history(&repo_path, rows)
+                    .with_context(|| format!("Failed to get the history of {repo_path:?}"))
+            });
+        }
+        let request = self.remote_id().map(|project_id| proto::GitFileHistory {
+            project_id,
+            buffer_id: buffer.read(cx).remote_id().into(),
+            start_row: rows.as_ref().map(|rows| rows.start),
+            end_row: rows.as_ref().map(|rows| rows.end),
+        });
+        let client = self.client.clone();
+        cx.background_executor().spawn(async move {
+            let request = request.context("project is not shared")?;
+            let response = client.request(request).await?;
+            Ok(deserialize_file_history(response))
+        })
+    }
+
+    fn local_git_repository(
+        &self,
+        work_directory: &ProjectPath,
+        cx: &AppContext,
+    ) -> Result<Arc<Mutex<dyn GitRepository>>> {
+        let worktree = self
+            .worktree_for_id(work_directory.worktree_id, cx)
+            .context("worktree not found")?;
+        let worktree = worktree
+            .read(cx)
+            .as_local()
+            .context("worktree is not local")?;
+        let repository = worktree
+            .repository_for_work_directory(&work_directory.path)
+            .ok_or(NoRepositoryError {})?;
+        let repository = worktree
+            .get_local_repo(&repository)
+            .ok_or(NoRepositoryError {})?;
+        Ok(repository.repo().clone())
+    }
+
+    /// Returns the local repository containing the buffer's file, with the file's path in it.
+    fn local_git_repository_for_buffer(
+        &self,
+        buffer: &Model<Buffer>,
+        cx: &AppContext,
+    ) -> Result<(Arc<Mutex<dyn GitRepository>>, RepoPath)> {
+        let project_path = buffer
+            .read(cx)
+            .project_path(cx)
+            .context("buffer has no file")?;
+        let (work_directory, _) = self
+            .worktree_for_id(project_path.worktree_id, cx)
+            .context("worktree not found")?
+            .read(cx)
+            .repository_and_work_directory_for_path(&project_path.path)
+            .ok_or(NoRepositoryError {})?;
+        let repo_path = RepoPath::from(
+            project_path
+                .path
+                .strip_prefix(&work_directory)?
+                .to_path_buf(),
+        );
+        let work_directory = ProjectPath {
+            worktree_id: project_path.worktree_id,
+            path: work_directory.as_ref().into(),
+        };
+        Ok((self.local_git_repository(&work_directory, cx)?, repo_path))
+    }
+
+    /// Runs the operation on the local repository in the background, the git CLI ones may take a while.
+    fn spawn_git_operation<R: Send + 'static>(
+        &self,
+        work_directory: &ProjectPath,
+        cx: &AppContext,
+        operation: impl FnOnce(&dyn GitRepository) -> Result<R> + Send + 'static,
+    ) -> Task<Result<R>> {
+        let repository = self.local_git_repository(work_directory, cx);
+        cx.background_executor().spawn(async move {
+            let repository = repository?;
+            let repository = repository.lock();
+            operation(&*repository)
+        })
+    }
+
+    fn send_git_request<T: proto::RequestMessage<Response = proto::Ack>>(
+        &self,
+        request: Option<T>,
+        cx: &AppContext,
+    ) -> Task<Result<()>> {
+        let client = self.client.clone();
+        cx.background_executor().spawn(async move {
+            let request = request.context("project is not shared")?;
+            client.request(request).await?;
+            Ok(())
+        })
+    }
+
+    pub(crate) async fn handle_git_changed_files(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::GitChangedFiles>,
+        _: Arc<Client>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::GitChangedFilesResponse> {
+        let work_directory = work_directory_from_proto(
+            envelope.payload.worktree_id,
+            &envelope.payload.work_directory,
+        );
+        let entries = this
+            .update(&mut cx, |this, cx| {
+                this.git_changed_files(work_directory, cx)
+            })?
+            .await?;
+        Ok(proto::GitChangedFilesResponse {
+            entries: entries.into_iter().map(serialize_status_entry).collect(),
+        })
+    }
+
+    pub(crate) async fn handle_git_stage(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::GitStage>,
+        _: Arc<Client>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::Ack> {
+        let work_directory = work_directory_from_proto(
+            envelope.payload.worktree_id,
+            &envelope.payload.work_directory,
+        );
+        let paths = deserialize_repo_paths(envelope.payload.repo_paths);
+        this.update(&mut cx, |this, cx| {
+            this.git_stage(work_directory, paths, cx)
+        })?
+        .await?;
+        Ok(proto::Ack {})
+    }
+
+    pub(crate) async fn handle_git_unstage(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::GitUnstage>,
+        _: Arc<Client>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::Ack> {
+        let work_directory = work_directory_from_proto(
+            envelope.payload.worktree_id,
+            &envelope.payload.work_directory,
+        );
+        let paths = deserialize_repo_paths(envelope.payload.repo_paths);
+        this.update(&mut cx, |this, cx| {
+            this.git_unstage(work_directory, paths, cx)
+        })?
+        .await?;
+        Ok(proto::Ack {})
+    }
+
+    pub(crate) async fn handle_git_set_index_text(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::GitSetIndexText>,
+        _: Arc<Client>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::Ack> {
+        let buffer_id = BufferId::new(envelope.payload.buffer_id)?;
+        this.update(&mut cx, |this, cx| {
+            let buffer = this
+                .opened_buffers
+                .get(&buffer_id)
+                .and_then(|buffer| buffer.upgrade())
+                .ok_or_else(|| anyhow!("unknown buffer id {}", buffer_id))?;
+            anyhow::Ok(this.git_set_index_text(&buffer, envelope.payload.text, cx))
+        })??
+        .await?;
+        Ok(proto::Ack {})
+    }
+
+    pub(crate) async fn handle_git_revision_text(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::GitRevisionText>,
+        _: Arc<Client>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::GitRevisionTextResponse> {
+        let buffer_id = BufferId::new(envelope.payload.buffer_id)?;
+        let text = this
+            .update(&mut cx, |this, cx| {
+                let buffer = this
+                    .opened_buffers
+                    .get(&buffer_id)
+                    .and_then(|buffer| buffer.upgrade())
+                    .ok_or_else(|| anyhow!("unknown buffer id {}", buffer_id))?;
+                anyhow::Ok(this.git_revision_text(&buffer, envelope.payload.revision, cx))
+            })??
+            .await?;
+        Ok(proto::GitRevisionTextResponse { text })
+    }
+
+    pub(crate) async fn handle_git_file_history(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::GitFileHistory>,
+        _: Arc<Client>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::GitFileHistoryResponse> {
+        let buffer_id = BufferId::new(envelope.payload.buffer_id)?;
+        let rows = envelope
+            .payload
+            .start_row
+            .zip(envelope.payload.end_row)
+            .map(|(start, end)| start..end);
+        let history = this
+            .update(&mut cx, |this, cx| {
+                let buffer = this
+                    .opened_buffers
+                    .get(&buffer_id)
+                    .and_then(|buffer| buffer.upgrade())
+                    .ok_or_else(|| anyhow!("unknown buffer id {}", buffer_id))?;
+                anyhow::Ok(this.git_file_history(&buffer, rows, cx))
+            })??
+            .await?;
+        Ok(serialize_file_history(history))
+    }
+
+    pub(crate) async fn handle_git_commit(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::GitCommit>,
+        _: Arc<Client>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::Ack> {
+        let work_directory = work_directory_from_proto(
+            envelope.payload.worktree_id,
+            &envelope.payload.work_directory,
+        );
+        let proto::GitCommit { message, amend, .. } = envelope.payload;
+        this.update(&mut cx, |this, cx| {
+            this.git_commit(work_directory, message, amend, cx)
+        })?
+        .await?;
+        Ok(proto::Ack {})
+    }
+
+    pub(crate) async fn handle_git_remote_operation(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::GitRemoteOperation>,
+        _: Arc<Client>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::Ack> {
+        let work_directory = work_directory_from_proto(
+            envelope.payload.worktree_id,
+            &envelope.payload.work_directory,
+        );
+        let operation = match envelope.payload.kind() {
+            proto::git_remote_operation::Kind::Push => GitRemoteOperation::Push,
+            proto::git_remote_operation::Kind::Pull => GitRemoteOperation::Pull,
+            proto::git_remote_operation::Kind::Fetch => GitRemoteOperation::Fetch,
+        };
+        this.update(&mut cx, |this, cx| {
+            this.git_remote_operation(work_directory, operation, cx)
+        })?
+        .await?;
+        Ok(proto::Ack {})
+    }
+}
+
+fn work_directory_from_proto(worktree_id: u64, path: &str) -> ProjectPath {
+    ProjectPath {
+        worktree_id: WorktreeId::from_proto(worktree_id),
+        path: Path::new(path).into(),
+    }
+}
+
+fn serialize_repo_paths(paths: &[RepoPath]) -> Vec<String> {
+    paths
+        .iter()
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect()
+}
+
+fn deserialize_repo_paths(paths: Vec<String>) -> Vec<RepoPath> {
+    paths
+        .into_iter()
+        .map(|path| RepoPath::new(PathBuf::from(path)))
+        .collect()
+}
+
+fn serialize_change_kind(kind: GitChangeKind) -> i32 {
+    let kind = match kind {
+        GitChangeKind::Added => proto::git_status_entry::ChangeKind::Added,
+        GitChangeKind::Modified => proto::git_status_entry::ChangeKind::Modified,
+        GitChangeKind::Deleted => proto::git_status_entry::ChangeKind::Deleted,
+        GitChangeKind::Conflict => proto::git_status_entry::ChangeKind::Conflict,
+    };
+    kind.into()
+}
+
+fn deserialize_change_kind(kind: i32) -> Option<GitChangeKind> {
+    Some(match proto::git_status_entry::ChangeKind::from_i32(kind)? {
+        proto::git_status_entry::ChangeKind::Added => GitChangeKind::Added,
+        proto::git_status_entry::ChangeKind::Modified => GitChangeKind::Modified,
+        proto::git_status_entry::ChangeKind::Deleted => GitChangeKind::Deleted,
+        proto::git_status_entry::ChangeKind::Conflict => GitChangeKind::Conflict,
+    })
+}
+
+fn serialize_status_entry(entry: GitStatusEntry) -> proto::GitStatusEntry {
+    proto::GitStatusEntry {
+        repo_path: entry.path.to_string_lossy().into_owned(),
+        staged: entry.staged.map(serialize_change_kind),
+        unstaged: entry.unstaged.map(serialize_change_kind),
+    }
+}
+
+fn deserialize_status_entry(entry: proto::GitStatusEntry) -> GitStatusEntry {
+    GitStatusEntry {
+        path: RepoPath::new(PathBuf::from(entry.repo_path)),
+        staged: entry.staged.and_then(deserialize_change_kind),
+        unstaged: entry.unstaged.and_then(deserialize_change_kind),
+    }
+}
+
+fn serialize_file_history(history: FileHistory) -> proto::GitFileHistoryResponse {
+    proto::GitFileHistoryResponse {
+        entries: history
+            .entries
+            .into_iter()
+            .map(|entry| proto::GitHistoryEntry {
+                sha: entry.sha.as_bytes().into(),
+                author: entry.author,
+                author_mail: entry.author_mail,
+                author_time: entry.author_time,
+                author_tz: entry.author_tz,
+                message: entry.message,
+            })
+            .collect(),
+        remote_url: history.remote_url,
+    }
+}
+
+fn deserialize_file_history(response: proto::GitFileHistoryResponse) -> FileHistory {
+    FileHistory {
+        entries: response
+            .entries
+            .into_iter()
+            .filter_map(|entry| {
+                Some(HistoryEntry {
+                    sha: git::Oid::from_bytes(&entry.sha).ok()?,
+                    author: entry.author,
+                    author_mail: entry.author_mail,
+                    author_time: entry.author_time,
+                    author_tz: entry.author_tz,
+                    message: entry.message,
+                })
+            })
+            .collect(),
+        remote_url: response.remote_url,
+    }
+}
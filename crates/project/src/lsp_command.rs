@@ -1,7 +1,9 @@
 use crate::{
-    CodeAction, CoreCompletion, DocumentHighlight, Hover, HoverBlock, HoverBlockKind, InlayHint,
-    InlayHintLabel, InlayHintLabelPart, InlayHintLabelPartTooltip, InlayHintTooltip, Location,
-    LocationLink, MarkupContent, Project, ProjectTransaction, ResolveState,
+    CodeAction, CodeLens, CoreCompletion, DocumentHighlight, DocumentLink, FoldingRange,
+    HierarchyItem, Hover, HoverBlock, HoverBlockKind, InlayHint, InlayHintLabel,
+    InlayHintLabelPart, InlayHintLabelPartTooltip, InlayHintTooltip, Location, LocationLink,
+    LspHierarchyItem, MarkupContent, Project, ProjectTransaction, ResolveState, SemanticToken,
+    SemanticTokens, SemanticTokensLegend,
 };
 use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
@@ -13,14 +15,15 @@ use language::{
     point_from_lsp, point_to_lsp,
     proto::{deserialize_anchor, deserialize_version, serialize_anchor, serialize_version},
     range_from_lsp, range_to_lsp, Anchor, Bias, Buffer, BufferSnapshot, CachedLspAdapter, CharKind,
-    OffsetRangeExt, PointUtf16, ToOffset, ToPointUtf16, Transaction, Unclipped,
+    OffsetRangeExt, Point, PointUtf16, ToOffset, ToPointUtf16, Transaction, Unclipped,
 };
 use lsp::{
     CompletionListItemDefaultsEditRange, DocumentHighlightKind, LanguageServer, LanguageServerId,
     OneOf, ServerCapabilities,
 };
-use std::{cmp::Reverse, ops::Range, path::Path, sync::Arc};
+use std::{cmp::Reverse, mem, ops::Range, path::Path, sync::Arc};
 use text::{BufferId, LineEnding};
+use util::ResultExt as _;
 
 pub fn lsp_formatting_options(tab_size: u32) -> lsp::FormattingOptions {
     lsp::FormattingOptions {
@@ -146,6 +149,51 @@ pub(crate) struct InlayHints {
     pub range: Range<Anchor>,
 }
 
+pub(crate) struct GetSemanticTokensFull;
+
+pub(crate) struct GetSemanticTokensDelta {
+    pub previous_result_id: String,
+    pub previous_lsp_tokens: Vec<lsp::SemanticToken>,
+}
+
+pub(crate) struct GetCodeLens;
+
+pub(crate) struct GetDocumentLinks;
+
+pub(crate) struct PrepareCallHierarchy {
+    pub position: PointUtf16,
+}
+
+pub(crate) struct GetIncomingCalls {
+    pub item: lsp::CallHierarchyItem,
+}
+
+pub(crate) struct GetOutgoingCalls {
+    pub item: lsp::CallHierarchyItem,
+}
+
+pub(crate) struct PrepareTypeHierarchy {
+    pub position: PointUtf16,
+}
+
+pub(crate) struct GetSupertypes {
+    pub item: lsp::TypeHierarchyItem,
+}
+
+pub(crate) struct GetSubtypes {
+    pub item: lsp::TypeHierarchyItem,
+}
+
+pub(crate) struct GetFoldingRanges;
+
+pub(crate) struct GetSelectionRanges {
+    pub positions: Vec<PointUtf16>,
+}
+
+pub(crate) struct GetLinkedEditingRanges {
+    pub position: PointUtf16,
+}
+
 pub(crate) struct FormattingOptions {
     tab_size: u32,
 }
@@ -2532,3 +2580,1851 @@ impl LspCommand for InlayHints {
         BufferId::new(message.buffer_id)
     }
 }
+
+impl GetSemanticTokensFull {
+    fn legend(server_capabilities: &lsp::ServerCapabilities) -> Option<&lsp::SemanticTokensLegend> {
+        match server_capabilities.semantic_tokens_provider.as_ref()? {
+            lsp::SemanticTokensServerCapabilities::SemanticTokensOptions(options) => {
+                Some(&options.legend)
+            }
+            lsp::SemanticTokensServerCapabilities::SemanticTokensRegistrationOptions(options) => {
+                Some(&options.semantic_tokens_options.legend)
+            }
+        }
+    }
+
+    fn full_options(
+        server_capabilities: &lsp::ServerCapabilities,
+    ) -> Option<&lsp::SemanticTokensFullOptions> {
+        match server_capabilities.semantic_tokens_provider.as_ref()? {
+            lsp::SemanticTokensServerCapabilities::SemanticTokensOptions(options) => {
+                options.full.as_ref()
+            }
+            lsp::SemanticTokensServerCapabilities::SemanticTokensRegistrationOptions(options) => {
+                options.semantic_tokens_options.full.as_ref()
+            }
+        }
+    }
+
+    /// Converts the relative LSP encoding into anchored tokens, which keep indexing into the legend.
+    fn lsp_to_project_tokens(
+        lsp_tokens: &[lsp::SemanticToken],
+        legend: &SemanticTokensLegend,
+        buffer: &Buffer,
+    ) -> Vec<SemanticToken> {
+        let mut tokens = Vec::with_capacity(lsp_tokens.len());
+        let mut line = 0;
+        let mut start_column = 0;
+        for lsp_token in lsp_tokens {
+            if lsp_token.delta_line == 0 {
+                start_column += lsp_token.delta_start;
+            } else {
+                line += lsp_token.delta_line;
+                start_column = lsp_token.delta_start;
+            }
+
+            if legend.token_type(lsp_token.token_type).is_none() {
+                continue;
+            }
+
+            let start =
+                buffer.clip_point_utf16(Unclipped(PointUtf16::new(line, start_column)), Bias::Left);
+            let end = buffer.clip_point_utf16(
+                Unclipped(PointUtf16::new(line, start_column + lsp_token.length)),
+                Bias::Left,
+            );
+            if start == end {
+                continue;
+            }
+
+            tokens.push(SemanticToken {
+                range: buffer.anchor_after(start)..buffer.anchor_before(end),
+                token_type: lsp_token.token_type,
+                token_modifiers: lsp_token.token_modifiers_bitset,
+            });
+        }
+        tokens
+    }
+
+    fn project_to_proto_token(token: SemanticToken) -> proto::SemanticToken {
+        proto::SemanticToken {
+            start: Some(serialize_anchor(&token.range.start)),
+            end: Some(serialize_anchor(&token.range.end)),
+            token_type: token.token_type,
+            token_modifiers: token.token_modifiers,
+        }
+    }
+
+    fn proto_to_project_token(token: proto::SemanticToken) -> Result<SemanticToken> {
+        let start = token
+            .start
+            .and_then(deserialize_anchor)
+            .context("invalid semantic token start")?;
+        let end = token
+            .end
+            .and_then(deserialize_anchor)
+            .context("invalid semantic token end")?;
+        Ok(SemanticToken {
+            range: start..end,
+            token_type: token.token_type,
+            token_modifiers: token.token_modifiers,
+        })
+    }
+
+    async fn project_tokens(
+        result_id: Option<String>,
+        lsp_tokens: Vec<lsp::SemanticToken>,
+        project: Model<Project>,
+        buffer: Model<Buffer>,
+        server_id: LanguageServerId,
+        mut cx: AsyncAppContext,
+    ) -> Result<SemanticTokens> {
+        let (_, language_server) =
+            language_server_for_buffer(&project, &buffer, server_id, &mut cx)?;
+        let legend = Self::legend(language_server.capabilities())
+            .map(|legend| SemanticTokensLegend {
+                token_types: legend
+                    .token_types
+                    .iter()
+                    .map(|token_type| Arc::from(token_type.as_str()))
+                    .collect(),
+                token_modifiers: legend
+                    .token_modifiers
+                    .iter()
+                    .map(|modifier| Arc::from(modifier.as_str()))
+                    .collect(),
+            })
+            .context("language server did not provide a semantic tokens legend")?;
+        let tokens = buffer.update(&mut cx, |buffer, _| {
+            Self::lsp_to_project_tokens(&lsp_tokens, &legend, buffer)
+        })?;
+        Ok(SemanticTokens {
+            result_id,
+            lsp_tokens,
+            legend: Arc::new(legend),
+            tokens,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl LspCommand for GetSemanticTokensFull {
+    type Response = SemanticTokens;
+    type LspRequest = lsp::request::SemanticTokensFullRequest;
+    type ProtoRequest = proto::GetSemanticTokens;
+
+    fn check_capabilities(&self, server_capabilities: &lsp::ServerCapabilities) -> bool {
+        match Self::full_options(server_capabilities) {
+            Some(lsp::SemanticTokensFullOptions::Bool(enabled)) => *enabled,
+            Some(lsp::SemanticTokensFullOptions::Delta { .. }) => true,
+            None => false,
+        }
+    }
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::SemanticTokensParams {
+        lsp::SemanticTokensParams {
+            text_document: lsp::TextDocumentIdentifier {
+                uri: lsp::Url::from_file_path(path).unwrap(),
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        message: Option<lsp::SemanticTokensResult>,
+        project: Model<Project>,
+        buffer: Model<Buffer>,
+        server_id: LanguageServerId,
+        cx: AsyncAppContext,
+    ) -> Result<SemanticTokens> {
+        let (result_id, lsp_tokens) = match message {
+            Some(lsp::SemanticTokensResult::Tokens(tokens)) => (tokens.result_id, tokens.data),
+            Some(lsp::SemanticTokensResult::Partial(partial)) => (None, partial.data),
+            None => return Ok(SemanticTokens::default()),
+        };
+        Self::project_tokens(result_id, lsp_tokens, project, buffer, server_id, cx).await
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetSemanticTokens {
+        proto::GetSemanticTokens {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            version: serialize_version(&buffer.version()),
+        }
+    }
+
+    async fn from_proto(
+        message: proto::GetSemanticTokens,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Self> {
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        Ok(Self)
+    }
+
+    fn response_to_proto(
+        response: SemanticTokens,
+        _: &mut Project,
+        _: PeerId,
+        buffer_version: &clock::Global,
+        _: &mut AppContext,
+    ) -> proto::GetSemanticTokensResponse {
+        proto::GetSemanticTokensResponse {
+            tokens: response
+                .tokens
+                .into_iter()
+                .map(Self::project_to_proto_token)
+                .collect(),
+            version: serialize_version(buffer_version),
+            token_types: response
+                .legend
+                .token_types
+                .iter()
+                .map(|token_type| token_type.to_string())
+                .collect(),
+            token_modifiers: response
+                .legend
+                .token_modifiers
+                .iter()
+                .map(|modifier| modifier.to_string())
+                .collect(),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::GetSemanticTokensResponse,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<SemanticTokens> {
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        let tokens = message
+            .tokens
+            .into_iter()
+            .map(Self::proto_to_project_token)
+            .collect::<Result<_>>()?;
+        Ok(SemanticTokens {
+            result_id: None,
+            lsp_tokens: Vec::new(),
+            legend: Arc::new(SemanticTokensLegend {
+                token_types: message.token_types.into_iter().map(Arc::from).collect(),
+                token_modifiers: message.token_modifiers.into_iter().map(Arc::from).collect(),
+            }),
+            tokens,
+        })
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetSemanticTokens) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+impl GetSemanticTokensDelta {
+    pub fn supports_delta(server_capabilities: &lsp::ServerCapabilities) -> bool {
+        matches!(
+            GetSemanticTokensFull::full_options(server_capabilities),
+            Some(lsp::SemanticTokensFullOptions::Delta { delta: Some(true) })
+        )
+    }
+
+    /// Applies delta edits, which index into the flat `u32` encoding of the tokens.
+    fn apply_edits(
+        lsp_tokens: Vec<lsp::SemanticToken>,
+        mut edits: Vec<lsp::SemanticTokensEdit>,
+    ) -> Vec<lsp::SemanticToken> {
+        fn flatten(token: lsp::SemanticToken) -> [u32; 5] {
+            [
+                token.delta_line,
+                token.delta_start,
+                token.length,
+                token.token_type,
+                token.token_modifiers_bitset,
+            ]
+        }
+
+        let mut data = lsp_tokens.into_iter().flat_map(flatten).collect::<Vec<_>>();
+        // Every edit refers to the original array, so apply them back to front.
+        edits.sort_by_key(|edit| Reverse(edit.start));
+        for edit in edits {
+            let start = (edit.start as usize).min(data.len());
+            let end = (start + edit.delete_count as usize).min(data.len());
+            data.splice(
+                start..end,
+                edit.data.unwrap_or_default().into_iter().flat_map(flatten),
+            );
+        }
+
+        data.chunks_exact(5)
+            .map(|chunk| lsp::SemanticToken {
+                delta_line: chunk[0],
+                delta_start: chunk[1],
+                length: chunk[2],
+                token_type: chunk[3],
+                token_modifiers_bitset: chunk[4],
+            })
+            .collect()
+    }
+}
+
+/// Deltas are only requested from local language servers, as the previous result lives
+/// on the host; remote projects always proxy [`GetSemanticTokensFull`] instead.
+#[async_trait(?Send)]
+impl LspCommand for GetSemanticTokensDelta {
+    type Response = SemanticTokens;
+    type LspRequest = lsp::request::SemanticTokensFullDeltaRequest;
+    type ProtoRequest = proto::GetSemanticTokens;
+
+    fn check_capabilities(&self, server_capabilities: &lsp::ServerCapabilities) -> bool {
+        Self::supports_delta(server_capabilities)
+    }
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::SemanticTokensDeltaParams {
+        lsp::SemanticTokensDeltaParams {
+            text_document: lsp::TextDocumentIdentifier {
+                uri: lsp::Url::from_file_path(path).unwrap(),
+            },
+            previous_result_id: self.previous_result_id.clone(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        message: Option<lsp::SemanticTokensFullDeltaResult>,
+        project: Model<Project>,
+        buffer: Model<Buffer>,
+        server_id: LanguageServerId,
+        cx: AsyncAppContext,
+    ) -> Result<SemanticTokens> {
+        let (result_id, lsp_tokens) = match message {
+            Some(lsp::SemanticTokensFullDeltaResult::Tokens(tokens)) => {
+                (tokens.result_id, tokens.data)
+            }
+            Some(lsp::SemanticTokensFullDeltaResult::TokensDelta(delta)) => (
+                delta.result_id,
+                Self::apply_edits(self.previous_lsp_tokens, delta.edits),
+            ),
+            Some(lsp::SemanticTokensFullDeltaResult::PartialTokensDelta { edits }) => {
+                (None, Self::apply_edits(self.previous_lsp_tokens, edits))
+            }
+            None => return Ok(SemanticTokens::default()),
+        };
+        GetSemanticTokensFull::project_tokens(result_id, lsp_tokens, project, buffer, server_id, cx)
+            .await
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetSemanticTokens {
+        GetSemanticTokensFull.to_proto(project_id, buffer)
+    }
+
+    async fn from_proto(
+        _: proto::GetSemanticTokens,
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: AsyncAppContext,
+    ) -> Result<Self> {
+        Err(anyhow!("semantic token deltas are not proxied to the host"))
+    }
+
+    fn response_to_proto(
+        response: SemanticTokens,
+        project: &mut Project,
+        peer_id: PeerId,
+        buffer_version: &clock::Global,
+        cx: &mut AppContext,
+    ) -> proto::GetSemanticTokensResponse {
+        GetSemanticTokensFull::response_to_proto(response, project, peer_id, buffer_version, cx)
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::GetSemanticTokensResponse,
+        project: Model<Project>,
+        buffer: Model<Buffer>,
+        cx: AsyncAppContext,
+    ) -> Result<SemanticTokens> {
+        GetSemanticTokensFull
+            .response_from_proto(message, project, buffer, cx)
+            .await
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetSemanticTokens) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+impl GetCodeLens {
+    pub fn can_resolve_lenses(capabilities: &ServerCapabilities) -> bool {
+        capabilities
+            .code_lens_provider
+            .as_ref()
+            .and_then(|options| options.resolve_provider)
+            .unwrap_or(false)
+    }
+
+    fn project_to_proto_lens(lens: &CodeLens) -> proto::CodeLens {
+        proto::CodeLens {
+            server_id: lens.server_id.0 as u64,
+            start: Some(serialize_anchor(&lens.range.start)),
+            end: Some(serialize_anchor(&lens.range.end)),
+            lsp_lens: serde_json::to_vec(&lens.lsp_lens).unwrap(),
+        }
+    }
+
+    fn proto_to_project_lens(lens: proto::CodeLens) -> Result<CodeLens> {
+        let start = lens
+            .start
+            .and_then(deserialize_anchor)
+            .context("invalid code lens start")?;
+        let end = lens
+            .end
+            .and_then(deserialize_anchor)
+            .context("invalid code lens end")?;
+        Ok(CodeLens {
+            server_id: LanguageServerId(lens.server_id as usize),
+            range: start..end,
+            lsp_lens: serde_json::from_slice(&lens.lsp_lens)?,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl LspCommand for GetCodeLens {
+    type Response = Vec<CodeLens>;
+    type LspRequest = lsp::request::CodeLensRequest;
+    type ProtoRequest = proto::GetCodeLens;
+
+    fn check_capabilities(&self, capabilities: &ServerCapabilities) -> bool {
+        capabilities.code_lens_provider.is_some()
+    }
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::CodeLensParams {
+        lsp::CodeLensParams {
+            text_document: lsp::TextDocumentIdentifier {
+                uri: lsp::Url::from_file_path(path).unwrap(),
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        message: Option<Vec<lsp::CodeLens>>,
+        project: Model<Project>,
+        buffer: Model<Buffer>,
+        server_id: LanguageServerId,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<CodeLens>> {
+        let lsp_lenses = message.unwrap_or_default();
+        if lsp_lenses.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Lenses are only rendered once they have a command to show, so resolve them right away
+        // instead of making every viewer (including guests) round-trip for each of them.
+        let (_, language_server) =
+            language_server_for_buffer(&project, &buffer, server_id, &mut cx)?;
+        let lsp_lenses = if Self::can_resolve_lenses(&language_server.capabilities()) {
+            future::join_all(lsp_lenses.into_iter().map(|lsp_lens| {
+                let language_server = language_server.clone();
+                async move {
+                    if lsp_lens.command.is_some() {
+                        return lsp_lens;
+                    }
+                    language_server
+                        .request::<lsp::request::CodeLensResolve>(lsp_lens.clone())
+                        .await
+                        .context("resolving a code lens")
+                        .log_err()
+                        .unwrap_or(lsp_lens)
+                }
+            }))
+            .await
+        } else {
+            lsp_lenses
+        };
+
+        buffer.update(&mut cx, |buffer, _| {
+            lsp_lenses
+                .into_iter()
+                .map(|lsp_lens| {
+                    let range = range_from_lsp(lsp_lens.range);
+                    let start = buffer.clip_point_utf16(range.start, Bias::Left);
+                    let end = buffer.clip_point_utf16(range.end, Bias::Left);
+                    CodeLens {
+                        server_id,
+                        range: buffer.anchor_after(start)..buffer.anchor_before(end),
+                        lsp_lens,
+                    }
+                })
+                .collect()
+        })
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetCodeLens {
+        proto::GetCodeLens {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            version: serialize_version(&buffer.version()),
+        }
+    }
+
+    async fn from_proto(
+        message: proto::GetCodeLens,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Self> {
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        Ok(Self)
+    }
+
+    fn response_to_proto(
+        lenses: Vec<CodeLens>,
+        _: &mut Project,
+        _: PeerId,
+        buffer_version: &clock::Global,
+        _: &mut AppContext,
+    ) -> proto::GetCodeLensResponse {
+        proto::GetCodeLensResponse {
+            lenses: lenses.iter().map(Self::project_to_proto_lens).collect(),
+            version: serialize_version(buffer_version),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::GetCodeLensResponse,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<CodeLens>> {
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        message
+            .lenses
+            .into_iter()
+            .map(Self::proto_to_project_lens)
+            .collect()
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetCodeLens) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+impl GetDocumentLinks {
+    pub fn can_resolve_links(capabilities: &ServerCapabilities) -> bool {
+        capabilities
+            .document_link_provider
+            .as_ref()
+            .and_then(|options| options.resolve_provider)
+            .unwrap_or(false)
+    }
+
+    pub fn project_to_proto_link(link: &DocumentLink) -> proto::DocumentLink {
+        proto::DocumentLink {
+            server_id: link.server_id.0 as u64,
+            start: Some(serialize_anchor(&link.range.start)),
+            end: Some(serialize_anchor(&link.range.end)),
+            lsp_link: serde_json::to_vec(&link.lsp_link).unwrap(),
+        }
+    }
+
+    pub fn proto_to_project_link(link: proto::DocumentLink) -> Result<DocumentLink> {
+        let start = link
+            .start
+            .and_then(deserialize_anchor)
+            .context("invalid document link start")?;
+        let end = link
+            .end
+            .and_then(deserialize_anchor)
+            .context("invalid document link end")?;
+        Ok(DocumentLink {
+            server_id: LanguageServerId(link.server_id as usize),
+            range: start..end,
+            lsp_link: serde_json::from_slice(&link.lsp_link)?,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl LspCommand for GetDocumentLinks {
+    type Response = Vec<DocumentLink>;
+    type LspRequest = lsp::request::DocumentLinkRequest;
+    type ProtoRequest = proto::GetDocumentLinks;
+
+    fn check_capabilities(&self, capabilities: &ServerCapabilities) -> bool {
+        capabilities.document_link_provider.is_some()
+    }
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::DocumentLinkParams {
+        lsp::DocumentLinkParams {
+            text_document: lsp::TextDocumentIdentifier {
+                uri: lsp::Url::from_file_path(path).unwrap(),
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        message: Option<Vec<lsp::DocumentLink>>,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        server_id: LanguageServerId,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<DocumentLink>> {
+        // Links without a target are only resolved once they are hovered.
+        buffer.update(&mut cx, |buffer, _| {
+            message
+                .unwrap_or_default()
+                .into_iter()
+                .map(|lsp_link| {
+                    let range = range_from_lsp(lsp_link.range);
+                    let start = buffer.clip_point_utf16(range.start, Bias::Left);
+                    let end = buffer.clip_point_utf16(range.end, Bias::Left);
+                    DocumentLink {
+                        server_id,
+                        range: buffer.anchor_before(start)..buffer.anchor_after(end),
+                        lsp_link,
+                    }
+                })
+                .collect()
+        })
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetDocumentLinks {
+        proto::GetDocumentLinks {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            version: serialize_version(&buffer.version()),
+        }
+    }
+
+    async fn from_proto(
+        message: proto::GetDocumentLinks,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Self> {
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        Ok(Self)
+    }
+
+    fn response_to_proto(
+        links: Vec<DocumentLink>,
+        _: &mut Project,
+        _: PeerId,
+        buffer_version: &clock::Global,
+        _: &mut AppContext,
+    ) -> proto::GetDocumentLinksResponse {
+        proto::GetDocumentLinksResponse {
+            links: links.iter().map(Self::project_to_proto_link).collect(),
+            version: serialize_version(buffer_version),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::GetDocumentLinksResponse,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<DocumentLink>> {
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        message
+            .links
+            .into_iter()
+            .map(Self::proto_to_project_link)
+            .collect()
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetDocumentLinks) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+/// Opens the buffers that the hierarchy items are defined in, so that they can be navigated to.
+async fn hierarchy_items_from_lsp(
+    lsp_items: Vec<LspHierarchyItem>,
+    project: Model<Project>,
+    buffer: Model<Buffer>,
+    server_id: LanguageServerId,
+    mut cx: AsyncAppContext,
+) -> Result<Vec<HierarchyItem>> {
+    let (lsp_adapter, language_server) =
+        language_server_for_buffer(&project, &buffer, server_id, &mut cx)?;
+    let mut items = Vec::with_capacity(lsp_items.len());
+    for lsp_item in lsp_items {
+        let (uri, selection_range) = match &lsp_item {
+            LspHierarchyItem::Call(item) => (item.uri.clone(), item.selection_range),
+            LspHierarchyItem::Type(item) => (item.uri.clone(), item.selection_range),
+        };
+        let target_buffer = project
+            .update(&mut cx, |project, cx| {
+                project.open_local_buffer_via_lsp(
+                    uri,
+                    language_server.server_id(),
+                    lsp_adapter.name.clone(),
+                    cx,
+                )
+            })?
+            .await?;
+        let range = target_buffer.update(&mut cx, |target_buffer, _| {
+            let start =
+                target_buffer.clip_point_utf16(point_from_lsp(selection_range.start), Bias::Left);
+            let end =
+                target_buffer.clip_point_utf16(point_from_lsp(selection_range.end), Bias::Left);
+            target_buffer.anchor_after(start)..target_buffer.anchor_before(end)
+        })?;
+        let (name, kind, detail) = match &lsp_item {
+            LspHierarchyItem::Call(item) => (item.name.clone(), item.kind, item.detail.clone()),
+            LspHierarchyItem::Type(item) => (item.name.clone(), item.kind, item.detail.clone()),
+        };
+        items.push(HierarchyItem {
+            name,
+            kind,
+            detail,
+            location: Location {
+                buffer: target_buffer,
+                range,
+            },
+            lsp_item,
+        });
+    }
+    Ok(items)
+}
+
+fn hierarchy_items_to_proto(
+    items: Vec<HierarchyItem>,
+    project: &mut Project,
+    peer_id: PeerId,
+    cx: &mut AppContext,
+) -> proto::GetHierarchyItemsResponse {
+    let items = items
+        .into_iter()
+        .map(|item| {
+            let buffer_id = project.create_buffer_for_peer(&item.location.buffer, peer_id, cx);
+            proto::HierarchyItem {
+                name: item.name,
+                kind: unsafe { mem::transmute(item.kind) },
+                detail: item.detail,
+                location: Some(proto::Location {
+                    buffer_id: buffer_id.into(),
+                    start: Some(serialize_anchor(&item.location.range.start)),
+                    end: Some(serialize_anchor(&item.location.range.end)),
+                }),
+                lsp_item: match &item.lsp_item {
+                    LspHierarchyItem::Call(item) => serde_json::to_vec(item),
+                    LspHierarchyItem::Type(item) => serde_json::to_vec(item),
+                }
+                .unwrap(),
+            }
+        })
+        .collect();
+    proto::GetHierarchyItemsResponse { items }
+}
+
+async fn hierarchy_items_from_proto(
+    message: proto::GetHierarchyItemsResponse,
+    deserialize_lsp_item: fn(&[u8]) -> Result<LspHierarchyItem>,
+    project: Model<Project>,
+    mut cx: AsyncAppContext,
+) -> Result<Vec<HierarchyItem>> {
+    let mut items = Vec::with_capacity(message.items.len());
+    for item in message.items {
+        let location = item.location.context("missing hierarchy item location")?;
+        let buffer_id = BufferId::new(location.buffer_id)?;
+        let target_buffer = project
+            .update(&mut cx, |project, cx| {
+                project.wait_for_remote_buffer(buffer_id, cx)
+            })?
+            .await?;
+        let start = location
+            .start
+            .and_then(deserialize_anchor)
+            .ok_or_else(|| anyhow!("missing target start"))?;
+        let end = location
+            .end
+            .and_then(deserialize_anchor)
+            .ok_or_else(|| anyhow!("missing target end"))?;
+        target_buffer
+            .update(&mut cx, |buffer, _| buffer.wait_for_anchors([start, end]))?
+            .await?;
+        items.push(HierarchyItem {
+            name: item.name,
+            kind: unsafe { mem::transmute(item.kind) },
+            detail: item.detail,
+            location: Location {
+                buffer: target_buffer,
+                range: start..end,
+            },
+            lsp_item: deserialize_lsp_item(&item.lsp_item)?,
+        });
+    }
+    Ok(items)
+}
+
+impl GetIncomingCalls {
+    pub fn supports_call_hierarchy(capabilities: &ServerCapabilities) -> bool {
+        match &capabilities.call_hierarchy_provider {
+            Some(lsp::CallHierarchyServerCapability::Simple(enabled)) => *enabled,
+            Some(lsp::CallHierarchyServerCapability::Options(_)) => true,
+            None => false,
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl LspCommand for PrepareCallHierarchy {
+    type Response = Vec<HierarchyItem>;
+    type LspRequest = lsp::request::CallHierarchyPrepare;
+    type ProtoRequest = proto::PrepareCallHierarchy;
+
+    fn check_capabilities(&self, capabilities: &ServerCapabilities) -> bool {
+        GetIncomingCalls::supports_call_hierarchy(capabilities)
+    }
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::CallHierarchyPrepareParams {
+        lsp::CallHierarchyPrepareParams {
+            text_document_position_params: lsp::TextDocumentPositionParams {
+                text_document: lsp::TextDocumentIdentifier {
+                    uri: lsp::Url::from_file_path(path).unwrap(),
+                },
+                position: point_to_lsp(self.position),
+            },
+            work_done_progress_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        items: Option<Vec<lsp::CallHierarchyItem>>,
+        project: Model<Project>,
+        buffer: Model<Buffer>,
+        server_id: LanguageServerId,
+        cx: AsyncAppContext,
+    ) -> Result<Vec<HierarchyItem>> {
+        let items = items
+            .unwrap_or_default()
+            .into_iter()
+            .map(LspHierarchyItem::Call)
+            .collect();
+        hierarchy_items_from_lsp(items, project, buffer, server_id, cx).await
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::PrepareCallHierarchy {
+        proto::PrepareCallHierarchy {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            position: Some(serialize_anchor(&buffer.anchor_before(self.position))),
+            version: serialize_version(&buffer.version()),
+        }
+    }
+
+    async fn from_proto(
+        message: proto::PrepareCallHierarchy,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Self> {
+        let position = message
+            .position
+            .and_then(deserialize_anchor)
+            .ok_or_else(|| anyhow!("invalid position"))?;
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        Ok(Self {
+            position: buffer.update(&mut cx, |buffer, _| position.to_point_utf16(buffer))?,
+        })
+    }
+
+    fn response_to_proto(
+        items: Vec<HierarchyItem>,
+        project: &mut Project,
+        peer_id: PeerId,
+        _: &clock::Global,
+        cx: &mut AppContext,
+    ) -> proto::GetHierarchyItemsResponse {
+        hierarchy_items_to_proto(items, project, peer_id, cx)
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::GetHierarchyItemsResponse,
+        project: Model<Project>,
+        _: Model<Buffer>,
+        cx: AsyncAppContext,
+    ) -> Result<Vec<HierarchyItem>> {
+        hierarchy_items_from_proto(message, LspHierarchyItem::call_from_slice, project, cx).await
+    }
+
+    fn buffer_id_from_proto(message: &proto::PrepareCallHierarchy) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+#[async_trait(?Send)]
+impl LspCommand for GetIncomingCalls {
+    type Response = Vec<HierarchyItem>;
+    type LspRequest = lsp::request::CallHierarchyIncomingCalls;
+    type ProtoRequest = proto::GetIncomingCalls;
+
+    fn check_capabilities(&self, capabilities: &ServerCapabilities) -> bool {
+        Self::supports_call_hierarchy(capabilities)
+    }
+
+    fn to_lsp(
+        &self,
+        _: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::CallHierarchyIncomingCallsParams {
+        lsp::CallHierarchyIncomingCallsParams {
+            item: self.item.clone(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        calls: Option<Vec<lsp::CallHierarchyIncomingCall>>,
+        project: Model<Project>,
+        buffer: Model<Buffer>,
+        server_id: LanguageServerId,
+        cx: AsyncAppContext,
+    ) -> Result<Vec<HierarchyItem>> {
+        let items = calls
+            .unwrap_or_default()
+            .into_iter()
+            .map(|call| LspHierarchyItem::Call(call.from))
+            .collect();
+        hierarchy_items_from_lsp(items, project, buffer, server_id, cx).await
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetIncomingCalls {
+        proto::GetIncomingCalls {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            lsp_item: serde_json::to_vec(&self.item).unwrap(),
+        }
+    }
+
+    async fn from_proto(
+        message: proto::GetIncomingCalls,
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: AsyncAppContext,
+    ) -> Result<Self> {
+        Ok(Self {
+            item: serde_json::from_slice(&message.lsp_item)?,
+        })
+    }
+
+    fn response_to_proto(
+        items: Vec<HierarchyItem>,
+        project: &mut Project,
+        peer_id: PeerId,
+        _: &clock::Global,
+        cx: &mut AppContext,
+    ) -> proto::GetHierarchyItemsResponse {
+        hierarchy_items_to_proto(items, project, peer_id, cx)
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::GetHierarchyItemsResponse,
+        project: Model<Project>,
+        _: Model<Buffer>,
+        cx: AsyncAppContext,
+    ) -> Result<Vec<HierarchyItem>> {
+        hierarchy_items_from_proto(message, LspHierarchyItem::call_from_slice, project, cx).await
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetIncomingCalls) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+#[async_trait(?Send)]
+impl LspCommand for GetOutgoingCalls {
+    type Response = Vec<HierarchyItem>;
+    type LspRequest = lsp::request::CallHierarchyOutgoingCalls;
+    type ProtoRequest = proto::GetOutgoingCalls;
+
+    fn check_capabilities(&self, capabilities: &ServerCapabilities) -> bool {
+        GetIncomingCalls::supports_call_hierarchy(capabilities)
+    }
+
+    fn to_lsp(
+        &self,
+        _: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::CallHierarchyOutgoingCallsParams {
+        lsp::CallHierarchyOutgoingCallsParams {
+            item: self.item.clone(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        calls: Option<Vec<lsp::CallHierarchyOutgoingCall>>,
+        project: Model<Project>,
+        buffer: Model<Buffer>,
+        server_id: LanguageServerId,
+        cx: AsyncAppContext,
+    ) -> Result<Vec<HierarchyItem>> {
+        let items = calls
+            .unwrap_or_default()
+            .into_iter()
+            .map(|call| LspHierarchyItem::Call(call.to))
+            .collect();
+        hierarchy_items_from_lsp(items, project, buffer, server_id, cx).await
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetOutgoingCalls {
+        proto::GetOutgoingCalls {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            lsp_item: serde_json::to_vec(&self.item).unwrap(),
+        }
+    }
+
+    async fn from_proto(
+        message: proto::GetOutgoingCalls,
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: AsyncAppContext,
+    ) -> Result<Self> {
+        Ok(Self {
+            item: serde_json::from_slice(&message.lsp_item)?,
+        })
+    }
+
+    fn response_to_proto(
+        items: Vec<HierarchyItem>,
+        project: &mut Project,
+        peer_id: PeerId,
+        _: &clock::Global,
+        cx: &mut AppContext,
+    ) -> proto::GetHierarchyItemsResponse {
+        hierarchy_items_to_proto(items, project, peer_id, cx)
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::GetHierarchyItemsResponse,
+        project: Model<Project>,
+        _: Model<Buffer>,
+        cx: AsyncAppContext,
+    ) -> Result<Vec<HierarchyItem>> {
+        hierarchy_items_from_proto(message, LspHierarchyItem::call_from_slice, project, cx).await
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetOutgoingCalls) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+#[async_trait(?Send)]
+impl LspCommand for PrepareTypeHierarchy {
+    type Response = Vec<HierarchyItem>;
+    type LspRequest = lsp::request::TypeHierarchyPrepare;
+    type ProtoRequest = proto::PrepareTypeHierarchy;
+
+    fn check_capabilities(&self, capabilities: &ServerCapabilities) -> bool {
+        capabilities.type_hierarchy_provider.is_some()
+    }
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::TypeHierarchyPrepareParams {
+        lsp::TypeHierarchyPrepareParams {
+            text_document_position_params: lsp::TextDocumentPositionParams {
+                text_document: lsp::TextDocumentIdentifier {
+                    uri: lsp::Url::from_file_path(path).unwrap(),
+                },
+                position: point_to_lsp(self.position),
+            },
+            work_done_progress_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        items: Option<Vec<lsp::TypeHierarchyItem>>,
+        project: Model<Project>,
+        buffer: Model<Buffer>,
+        server_id: LanguageServerId,
+        cx: AsyncAppContext,
+    ) -> Result<Vec<HierarchyItem>> {
+        let items = items
+            .unwrap_or_default()
+            .into_iter()
+            .map(LspHierarchyItem::Type)
+            .collect();
+        hierarchy_items_from_lsp(items, project, buffer, server_id, cx).await
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::PrepareTypeHierarchy {
+        proto::PrepareTypeHierarchy {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            position: Some(serialize_anchor(&buffer.anchor_before(self.position))),
+            version: serialize_version(&buffer.version()),
+        }
+    }
+
+    async fn from_proto(
+        message: proto::PrepareTypeHierarchy,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Self> {
+        let position = message
+            .position
+            .and_then(deserialize_anchor)
+            .ok_or_else(|| anyhow!("invalid position"))?;
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        Ok(Self {
+            position: buffer.update(&mut cx, |buffer, _| position.to_point_utf16(buffer))?,
+        })
+    }
+
+    fn response_to_proto(
+        items: Vec<HierarchyItem>,
+        project: &mut Project,
+        peer_id: PeerId,
+        _: &clock::Global,
+        cx: &mut AppContext,
+    ) -> proto::GetHierarchyItemsResponse {
+        hierarchy_items_to_proto(items, project, peer_id, cx)
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::GetHierarchyItemsResponse,
+        project: Model<Project>,
+        _: Model<Buffer>,
+        cx: AsyncAppContext,
+    ) -> Result<Vec<HierarchyItem>> {
+        hierarchy_items_from_proto(message, LspHierarchyItem::type_from_slice, project, cx).await
+    }
+
+    fn buffer_id_from_proto(message: &proto::PrepareTypeHierarchy) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+#[async_trait(?Send)]
+impl LspCommand for GetSupertypes {
+    type Response = Vec<HierarchyItem>;
+    type LspRequest = lsp::request::TypeHierarchySupertypes;
+    type ProtoRequest = proto::GetSupertypes;
+
+    fn check_capabilities(&self, capabilities: &ServerCapabilities) -> bool {
+        capabilities.type_hierarchy_provider.is_some()
+    }
+
+    fn to_lsp(
+        &self,
+        _: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::TypeHierarchySupertypesParams {
+        lsp::TypeHierarchySupertypesParams {
+            item: self.item.clone(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        supertypes: Option<Vec<lsp::TypeHierarchyItem>>,
+        project: Model<Project>,
+        buffer: Model<Buffer>,
+        server_id: LanguageServerId,
+        cx: AsyncAppContext,
+    ) -> Result<Vec<HierarchyItem>> {
+        let items = supertypes
+            .unwrap_or_default()
+            .into_iter()
+            .map(LspHierarchyItem::Type)
+            .collect();
+        hierarchy_items_from_lsp(items, project, buffer, server_id, cx).await
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetSupertypes {
+        proto::GetSupertypes {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            lsp_item: serde_json::to_vec(&self.item).unwrap(),
+        }
+    }
+
+    async fn from_proto(
+        message: proto::GetSupertypes,
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: AsyncAppContext,
+    ) -> Result<Self> {
+        Ok(Self {
+            item: serde_json::from_slice(&message.lsp_item)?,
+        })
+    }
+
+    fn response_to_proto(
+        items: Vec<HierarchyItem>,
+        project: &mut Project,
+        peer_id: PeerId,
+        _: &clock::Global,
+        cx: &mut AppContext,
+    ) -> proto::GetHierarchyItemsResponse {
+        hierarchy_items_to_proto(items, project, peer_id, cx)
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::GetHierarchyItemsResponse,
+        project: Model<Project>,
+        _: Model<Buffer>,
+        cx: AsyncAppContext,
+    ) -> Result<Vec<HierarchyItem>> {
+        hierarchy_items_from_proto(message, LspHierarchyItem::type_from_slice, project, cx).await
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetSupertypes) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+#[async_trait(?Send)]
+impl LspCommand for GetSubtypes {
+    type Response = Vec<HierarchyItem>;
+    type LspRequest = lsp::request::TypeHierarchySubtypes;
+    type ProtoRequest = proto::GetSubtypes;
+
+    fn check_capabilities(&self, capabilities: &ServerCapabilities) -> bool {
+        capabilities.type_hierarchy_provider.is_some()
+    }
+
+    fn to_lsp(
+        &self,
+        _: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::TypeHierarchySubtypesParams {
+        lsp::TypeHierarchySubtypesParams {
+            item: self.item.clone(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        subtypes: Option<Vec<lsp::TypeHierarchyItem>>,
+        project: Model<Project>,
+        buffer: Model<Buffer>,
+        server_id: LanguageServerId,
+        cx: AsyncAppContext,
+    ) -> Result<Vec<HierarchyItem>> {
+        let items = subtypes
+            .unwrap_or_default()
+            .into_iter()
+            .map(LspHierarchyItem::Type)
+            .collect();
+        hierarchy_items_from_lsp(items, project, buffer, server_id, cx).await
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetSubtypes {
+        proto::GetSubtypes {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            lsp_item: serde_json::to_vec(&self.item).unwrap(),
+        }
+    }
+
+    async fn from_proto(
+        message: proto::GetSubtypes,
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: AsyncAppContext,
+    ) -> Result<Self> {
+        Ok(Self {
+            item: serde_json::from_slice(&message.lsp_item)?,
+        })
+    }
+
+    fn response_to_proto(
+        items: Vec<HierarchyItem>,
+        project: &mut Project,
+        peer_id: PeerId,
+        _: &clock::Global,
+        cx: &mut AppContext,
+    ) -> proto::GetHierarchyItemsResponse {
+        hierarchy_items_to_proto(items, project, peer_id, cx)
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::GetHierarchyItemsResponse,
+        project: Model<Project>,
+        _: Model<Buffer>,
+        cx: AsyncAppContext,
+    ) -> Result<Vec<HierarchyItem>> {
+        hierarchy_items_from_proto(message, LspHierarchyItem::type_from_slice, project, cx).await
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetSubtypes) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+impl GetFoldingRanges {
+    fn kind_to_proto(kind: &lsp::FoldingRangeKind) -> String {
+        match kind {
+            lsp::FoldingRangeKind::Comment => "comment",
+            lsp::FoldingRangeKind::Imports => "imports",
+            lsp::FoldingRangeKind::Region => "region",
+        }
+        .to_string()
+    }
+
+    fn kind_from_proto(kind: &str) -> Option<lsp::FoldingRangeKind> {
+        match kind {
+            "comment" => Some(lsp::FoldingRangeKind::Comment),
+            "imports" => Some(lsp::FoldingRangeKind::Imports),
+            "region" => Some(lsp::FoldingRangeKind::Region),
+            _ => None,
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl LspCommand for GetFoldingRanges {
+    type Response = Vec<FoldingRange>;
+    type LspRequest = lsp::request::FoldingRangeRequest;
+    type ProtoRequest = proto::GetFoldingRanges;
+
+    fn check_capabilities(&self, capabilities: &ServerCapabilities) -> bool {
+        match &capabilities.folding_range_provider {
+            Some(lsp::FoldingRangeProviderCapability::Simple(enabled)) => *enabled,
+            Some(_) => true,
+            None => false,
+        }
+    }
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::FoldingRangeParams {
+        lsp::FoldingRangeParams {
+            text_document: lsp::TextDocumentIdentifier {
+                uri: lsp::Url::from_file_path(path).unwrap(),
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        message: Option<Vec<lsp::FoldingRange>>,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        _: LanguageServerId,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<FoldingRange>> {
+        buffer.update(&mut cx, |buffer, _| {
+            let max_point = buffer.max_point();
+            let mut folding_ranges = message
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|lsp_range| {
+                    lsp_range.start_line < lsp_range.end_line
+                        && lsp_range.start_line <= max_point.row
+                })
+                .map(|lsp_range| {
+                    // Only whole lines are folded, as requested with the `lineFoldingOnly` capability.
+                    let start = Point::new(lsp_range.start_line, 0);
+                    let end_row = lsp_range.end_line.min(max_point.row);
+                    let end = Point::new(end_row, buffer.line_len(end_row));
+                    FoldingRange {
+                        range: buffer.anchor_after(start)..buffer.anchor_before(end),
+                        kind: lsp_range.kind,
+                        collapsed_text: lsp_range.collapsed_text,
+                    }
+                })
+                .collect::<Vec<_>>();
+            folding_ranges.sort_by(|a, b| a.range.start.cmp(&b.range.start, buffer));
+            folding_ranges
+        })
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetFoldingRanges {
+        proto::GetFoldingRanges {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            version: serialize_version(&buffer.version()),
+        }
+    }
+
+    async fn from_proto(
+        message: proto::GetFoldingRanges,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Self> {
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        Ok(Self)
+    }
+
+    fn response_to_proto(
+        folding_ranges: Vec<FoldingRange>,
+        _: &mut Project,
+        _: PeerId,
+        buffer_version: &clock::Global,
+        _: &mut AppContext,
+    ) -> proto::GetFoldingRangesResponse {
+        proto::GetFoldingRangesResponse {
+            ranges: folding_ranges
+                .into_iter()
+                .map(|folding_range| proto::FoldingRange {
+                    start: Some(serialize_anchor(&folding_range.range.start)),
+                    end: Some(serialize_anchor(&folding_range.range.end)),
+                    kind: folding_range.kind.as_ref().map(Self::kind_to_proto),
+                    collapsed_text: folding_range.collapsed_text,
+                })
+                .collect(),
+            version: serialize_version(buffer_version),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::GetFoldingRangesResponse,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<FoldingRange>> {
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        message
+            .ranges
+            .into_iter()
+            .map(|folding_range| {
+                let start = folding_range
+                    .start
+                    .and_then(deserialize_anchor)
+                    .context("invalid folding range start")?;
+                let end = folding_range
+                    .end
+                    .and_then(deserialize_anchor)
+                    .context("invalid folding range end")?;
+                Ok(FoldingRange {
+                    range: start..end,
+                    kind: folding_range
+                        .kind
+                        .as_deref()
+                        .and_then(Self::kind_from_proto),
+                    collapsed_text: folding_range.collapsed_text,
+                })
+            })
+            .collect()
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetFoldingRanges) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+#[async_trait(?Send)]
+impl LspCommand for GetSelectionRanges {
+    type Response = Vec<Vec<Range<Anchor>>>;
+    type LspRequest = lsp::request::SelectionRangeRequest;
+    type ProtoRequest = proto::GetSelectionRanges;
+
+    fn check_capabilities(&self, capabilities: &ServerCapabilities) -> bool {
+        match &capabilities.selection_range_provider {
+            Some(lsp::SelectionRangeProviderCapability::Simple(enabled)) => *enabled,
+            Some(_) => true,
+            None => false,
+        }
+    }
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::SelectionRangeParams {
+        lsp::SelectionRangeParams {
+            text_document: lsp::TextDocumentIdentifier {
+                uri: lsp::Url::from_file_path(path).unwrap(),
+            },
+            positions: self.positions.iter().copied().map(point_to_lsp).collect(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        message: Option<Vec<lsp::SelectionRange>>,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        _: LanguageServerId,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<Vec<Range<Anchor>>>> {
+        let mut selection_ranges = message.unwrap_or_default();
+        // Servers respond with a selection range per requested position.
+        selection_ranges.truncate(self.positions.len());
+        buffer.update(&mut cx, |buffer, _| {
+            let mut ranges_per_position = selection_ranges
+                .into_iter()
+                .map(|selection_range| {
+                    let mut ranges = Vec::new();
+                    let mut selection_range = Some(Box::new(selection_range));
+                    while let Some(range) = selection_range {
+                        let range_start =
+                            buffer.clip_point_utf16(point_from_lsp(range.range.start), Bias::Left);
+                        let range_end =
+                            buffer.clip_point_utf16(point_from_lsp(range.range.end), Bias::Left);
+                        ranges.push(
+                            buffer.anchor_before(range_start)..buffer.anchor_after(range_end),
+                        );
+                        selection_range = range.parent;
+                    }
+                    ranges
+                })
+                .collect::<Vec<_>>();
+            ranges_per_position.resize(self.positions.len(), Vec::new());
+            ranges_per_position
+        })
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetSelectionRanges {
+        proto::GetSelectionRanges {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            positions: self
+                .positions
+                .iter()
+                .map(|position| serialize_anchor(&buffer.anchor_before(*position)))
+                .collect(),
+            version: serialize_version(&buffer.version()),
+        }
+    }
+
+    async fn from_proto(
+        message: proto::GetSelectionRanges,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Self> {
+        let positions = message
+            .positions
+            .into_iter()
+            .map(|position| deserialize_anchor(position).context("invalid position"))
+            .collect::<Result<Vec<_>>>()?;
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        Ok(Self {
+            positions: buffer.update(&mut cx, |buffer, _| {
+                positions
+                    .iter()
+                    .map(|position| position.to_point_utf16(buffer))
+                    .collect()
+            })?,
+        })
+    }
+
+    fn response_to_proto(
+        ranges_per_position: Vec<Vec<Range<Anchor>>>,
+        _: &mut Project,
+        _: PeerId,
+        buffer_version: &clock::Global,
+        _: &mut AppContext,
+    ) -> proto::GetSelectionRangesResponse {
+        proto::GetSelectionRangesResponse {
+            selections: ranges_per_position
+                .into_iter()
+                .map(|ranges| proto::SelectionRanges {
+                    starts: ranges
+                        .iter()
+                        .map(|range| serialize_anchor(&range.start))
+                        .collect(),
+                    ends: ranges
+                        .iter()
+                        .map(|range| serialize_anchor(&range.end))
+                        .collect(),
+                })
+                .collect(),
+            version: serialize_version(buffer_version),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::GetSelectionRangesResponse,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<Vec<Range<Anchor>>>> {
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        message
+            .selections
+            .into_iter()
+            .map(|selection| {
+                selection
+                    .starts
+                    .into_iter()
+                    .zip(selection.ends)
+                    .map(|(start, end)| {
+                        let start = deserialize_anchor(start).context("invalid range start")?;
+                        let end = deserialize_anchor(end).context("invalid range end")?;
+                        Ok(start..end)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetSelectionRanges) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+#[async_trait(?Send)]
+impl LspCommand for GetLinkedEditingRanges {
+    type Response = Vec<Range<Anchor>>;
+    type LspRequest = lsp::request::LinkedEditingRange;
+    type ProtoRequest = proto::GetLinkedEditingRanges;
+
+    fn check_capabilities(&self, capabilities: &ServerCapabilities) -> bool {
+        match &capabilities.linked_editing_range_provider {
+            Some(lsp::LinkedEditingRangeServerCapabilities::Simple(enabled)) => *enabled,
+            Some(_) => true,
+            None => false,
+        }
+    }
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::LinkedEditingRangeParams {
+        lsp::LinkedEditingRangeParams {
+            text_document_position_params: lsp::TextDocumentPositionParams {
+                text_document: lsp::TextDocumentIdentifier {
+                    uri: lsp::Url::from_file_path(path).unwrap(),
+                },
+                position: point_to_lsp(self.position),
+            },
+            work_done_progress_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        message: Option<lsp::LinkedEditingRanges>,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        _: LanguageServerId,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<Range<Anchor>>> {
+        let Some(linked_editing_ranges) = message else {
+            return Ok(Vec::new());
+        };
+        buffer.update(&mut cx, |buffer, _| {
+            linked_editing_ranges
+                .ranges
+                .into_iter()
+                .map(|range| {
+                    let start = buffer.clip_point_utf16(point_from_lsp(range.start), Bias::Left);
+                    let end = buffer.clip_point_utf16(point_from_lsp(range.end), Bias::Left);
+                    buffer.anchor_before(start)..buffer.anchor_after(end)
+                })
+                .collect()
+        })
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetLinkedEditingRanges {
+        proto::GetLinkedEditingRanges {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            position: Some(serialize_anchor(&buffer.anchor_before(self.position))),
+            version: serialize_version(&buffer.version()),
+        }
+    }
+
+    async fn from_proto(
+        message: proto::GetLinkedEditingRanges,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Self> {
+        let position = message
+            .position
+            .and_then(deserialize_anchor)
+            .context("invalid position")?;
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        Ok(Self {
+            position: buffer.update(&mut cx, |buffer, _| position.to_point_utf16(buffer))?,
+        })
+    }
+
+    fn response_to_proto(
+        ranges: Vec<Range<Anchor>>,
+        _: &mut Project,
+        _: PeerId,
+        buffer_version: &clock::Global,
+        _: &mut AppContext,
+    ) -> proto::GetLinkedEditingRangesResponse {
+        proto::GetLinkedEditingRangesResponse {
+            starts: ranges
+                .iter()
+                .map(|range| serialize_anchor(&range.start))
+                .collect(),
+            ends: ranges
+                .iter()
+                .map(|range| serialize_anchor(&range.end))
+                .collect(),
+            version: serialize_version(buffer_version),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::GetLinkedEditingRangesResponse,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<Range<Anchor>>> {
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        message
+            .starts
+            .into_iter()
+            .zip(message.ends)
+            .map(|(start, end)| {
+                let start = deserialize_anchor(start).context("invalid range start")?;
+                let end = deserialize_anchor(end).context("invalid range end")?;
+                Ok(start..end)
+            })
+            .collect()
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetLinkedEditingRanges) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
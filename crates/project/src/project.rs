@@ -1,5 +1,7 @@
+mod bookmark_store;
 pub mod connection_manager;
 pub mod debounced_delay;
+mod git_operations;
 pub mod lsp_command;
 pub mod lsp_ext_command;
 mod prettier_support;
@@ -98,7 +100,7 @@ use std::{
 };
 use task::static_source::{StaticSource, TrackedFile};
 use terminals::Terminals;
-use text::{Anchor, BufferId, LineEnding};
+use text::{Anchor, BufferId, Encoding, LineEnding};
 use util::{
     debug_panic, defer, maybe, merge_json_value_into, parse_env_output,
     paths::{
@@ -109,9 +111,11 @@ use util::{
 use worktree::{Snapshot, Traversal};
 
 pub use fs::*;
+pub use git_operations::GitRemoteOperation;
 pub use language::Location;
 #[cfg(any(test, feature = "test-support"))]
 pub use prettier::FORMAT_SUFFIX as TEST_PRETTIER_FORMAT_SUFFIX;
+pub use bookmark_store::{Bookmark, BookmarkStore, BookmarkStoreEvent};
 pub use task_inventory::{Inventory, TaskSourceKind};
 pub use worktree::{
     DiagnosticSummary, Entry, EntryKind, File, LocalWorktree, PathChange, ProjectEntryId,
@@ -164,6 +168,8 @@ pub struct Project {
     language_server_ids: HashMap<(WorktreeId, LanguageServerName), LanguageServerId>,
     language_server_statuses: BTreeMap<LanguageServerId, LanguageServerStatus>,
     last_formatting_failure: Option<String>,
+    /// Large files that are being loaded, along with the share of each file loaded so far.
+    large_file_load_progress: HashMap<ProjectPath, f32>,
     last_workspace_edits_by_language_server: HashMap<LanguageServerId, ProjectTransaction>,
     language_server_watched_paths: HashMap<LanguageServerId, HashMap<WorktreeId, GlobSet>>,
     client: Arc<client::Client>,
@@ -204,6 +210,7 @@ pub struct Project {
     prettiers_per_worktree: HashMap<WorktreeId, HashSet<Option<PathBuf>>>,
     prettier_instances: HashMap<PathBuf, PrettierInstance>,
     tasks: Model<Inventory>,
+    bookmark_store: Model<BookmarkStore>,
     hosted_project_id: Option<ProjectId>,
     dev_server_project_id: Option<client::DevServerProjectId>,
     search_history: SearchHistory,
@@ -331,6 +338,8 @@ pub enum Event {
     CollaboratorJoined(proto::PeerId),
     CollaboratorLeft(proto::PeerId),
     RefreshInlayHints,
+    RefreshSemanticTokens,
+    RefreshCodeLens,
     RevealInProjectPanel(ProjectEntryId),
 }
 
@@ -431,6 +440,30 @@ pub struct CodeAction {
     pub lsp_action: lsp::CodeAction,
 }
 
+/// A code lens provided by a language server.
+#[derive(Clone, Debug)]
+pub struct CodeLens {
+    /// The id of the language server that produced this code lens.
+    pub server_id: LanguageServerId,
+    /// The range of the buffer this code lens is displayed for.
+    pub range: Range<Anchor>,
+    /// The raw code lens provided by the language server.
+    /// Its command is only missing when the server failed to resolve it.
+    pub lsp_lens: lsp::CodeLens,
+}
+
+/// A link to a file or a URL provided by a language server for a range of a buffer.
+#[derive(Clone, Debug)]
+pub struct DocumentLink {
+    /// The id of the language server that produced this link.
+    pub server_id: LanguageServerId,
+    /// The range of the buffer this link is attached to.
+    pub range: Range<Anchor>,
+    /// The raw document link provided by the language server.
+    /// Its target is missing until the link gets resolved.
+    pub lsp_link: lsp::DocumentLink,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ResolveState {
     Resolved,
@@ -478,6 +511,96 @@ pub struct MarkupContent {
     pub value: String,
 }
 
+/// A single token from a language server's semantic highlighting response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SemanticToken {
+    pub range: Range<language::Anchor>,
+    /// The index of the token type in the [`SemanticTokensLegend`].
+    pub token_type: u32,
+    /// The token modifiers, as a bitset of indices in the [`SemanticTokensLegend`].
+    pub token_modifiers: u32,
+}
+
+/// The names of the token types and modifiers of a language server, which its semantic tokens refer to by index.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SemanticTokensLegend {
+    pub token_types: Vec<Arc<str>>,
+    pub token_modifiers: Vec<Arc<str>>,
+}
+
+impl SemanticTokensLegend {
+    /// Returns the name of the token type (e.g. `function`, `macro`) with the given index.
+    pub fn token_type(&self, token_type: u32) -> Option<&str> {
+        self.token_types
+            .get(token_type as usize)
+            .map(|token_type| token_type.as_ref())
+    }
+
+    /// Returns the names of the token modifiers (e.g. `readonly`) set in the given bitset.
+    pub fn token_modifiers(&self, token_modifiers: u32) -> impl Iterator<Item = &str> {
+        self.token_modifiers
+            .iter()
+            .take(32)
+            .enumerate()
+            .filter(move |(ix, _)| token_modifiers & (1 << ix) != 0)
+            .map(|(_, modifier)| modifier.as_ref())
+    }
+}
+
+/// The semantic tokens of a whole buffer, as returned by
+/// <a href="https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_semanticTokens">textDocument/semanticTokens</a>.
+#[derive(Debug, Clone, Default)]
+pub struct SemanticTokens {
+    /// Id of the result, used to request deltas from the server later.
+    /// Never set for tokens received from a remote host.
+    pub result_id: Option<String>,
+    /// Tokens in the server's encoded form, kept to apply the next delta on top.
+    pub lsp_tokens: Vec<lsp::SemanticToken>,
+    /// The legend that the token types and modifiers index into.
+    pub legend: Arc<SemanticTokensLegend>,
+    /// Tokens sorted by their position in the buffer.
+    pub tokens: Vec<SemanticToken>,
+}
+
+/// An item of a call or type hierarchy, as returned by a language server.
+#[derive(Debug, Clone)]
+pub struct HierarchyItem {
+    pub name: String,
+    pub kind: lsp::SymbolKind,
+    /// More detail for this item, e.g. the signature of a function.
+    pub detail: Option<String>,
+    /// The range that should be selected when navigating to this item, e.g. the name of a function.
+    pub location: Location,
+    /// The raw item, sent back to the language server to expand the hierarchy from this item.
+    pub lsp_item: LspHierarchyItem,
+}
+
+#[derive(Debug, Clone)]
+pub enum LspHierarchyItem {
+    Call(lsp::CallHierarchyItem),
+    Type(lsp::TypeHierarchyItem),
+}
+
+impl LspHierarchyItem {
+    fn call_from_slice(bytes: &[u8]) -> Result<Self> {
+        Ok(Self::Call(serde_json::from_slice(bytes)?))
+    }
+
+    fn type_from_slice(bytes: &[u8]) -> Result<Self> {
+        Ok(Self::Type(serde_json::from_slice(bytes)?))
+    }
+}
+
+/// A fold region provided by a language server.
+#[derive(Debug, Clone)]
+pub struct FoldingRange {
+    /// The lines to fold, from the start of the first one to the end of the last one.
+    pub range: Range<language::Anchor>,
+    pub kind: Option<lsp::FoldingRangeKind>,
+    /// The text to display instead of the folded lines.
+    pub collapsed_text: Option<String>,
+}
+
 #[derive(Debug, Clone)]
 pub struct LocationLink {
     pub origin: Option<Location>,
@@ -643,7 +766,10 @@ impl Project {
         client.add_model_request_handler(Self::handle_on_type_formatting);
         client.add_model_request_handler(Self::handle_inlay_hints);
         client.add_model_request_handler(Self::handle_resolve_inlay_hint);
+        client.add_model_request_handler(Self::handle_resolve_document_link);
         client.add_model_request_handler(Self::handle_refresh_inlay_hints);
+        client.add_model_request_handler(Self::handle_refresh_semantic_tokens);
+        client.add_model_request_handler(Self::handle_refresh_code_lens);
         client.add_model_request_handler(Self::handle_reload_buffers);
         client.add_model_request_handler(Self::handle_synchronize_buffers);
         client.add_model_request_handler(Self::handle_format_buffers);
@@ -656,6 +782,18 @@ impl Project {
         client.add_model_request_handler(Self::handle_lsp_command::<GetReferences>);
         client.add_model_request_handler(Self::handle_lsp_command::<PrepareRename>);
         client.add_model_request_handler(Self::handle_lsp_command::<PerformRename>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetSemanticTokensFull>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetCodeLens>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetDocumentLinks>);
+        client.add_model_request_handler(Self::handle_lsp_command::<PrepareCallHierarchy>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetIncomingCalls>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetOutgoingCalls>);
+        client.add_model_request_handler(Self::handle_lsp_command::<PrepareTypeHierarchy>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetSupertypes>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetSubtypes>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetFoldingRanges>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetSelectionRanges>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetLinkedEditingRanges>);
         client.add_model_request_handler(Self::handle_search_project);
         client.add_model_request_handler(Self::handle_get_project_symbols);
         client.add_model_request_handler(Self::handle_open_buffer_for_symbol);
@@ -666,6 +804,14 @@ impl Project {
         client.add_model_message_handler(Self::handle_update_diff_base);
         client.add_model_request_handler(Self::handle_lsp_command::<lsp_ext_command::ExpandMacro>);
         client.add_model_request_handler(Self::handle_blame_buffer);
+        client.add_model_request_handler(Self::handle_git_changed_files);
+        client.add_model_request_handler(Self::handle_git_stage);
+        client.add_model_request_handler(Self::handle_git_unstage);
+        client.add_model_request_handler(Self::handle_git_set_index_text);
+        client.add_model_request_handler(Self::handle_git_commit);
+        client.add_model_request_handler(Self::handle_git_remote_operation);
+        client.add_model_request_handler(Self::handle_git_revision_text);
+        client.add_model_request_handler(Self::handle_git_file_history);
         client.add_model_request_handler(Self::handle_multi_lsp_query);
     }
 
@@ -682,6 +828,7 @@ impl Project {
             cx.spawn(move |this, cx| Self::send_buffer_ordered_messages(this, rx, cx))
                 .detach();
             let tasks = Inventory::new(cx);
+            let bookmark_store = cx.new_model(|_| BookmarkStore::default());
 
             Self {
                 worktrees: Vec::new(),
@@ -720,6 +867,7 @@ impl Project {
                 language_server_ids: HashMap::default(),
                 language_server_statuses: Default::default(),
                 last_formatting_failure: None,
+                large_file_load_progress: HashMap::default(),
                 last_workspace_edits_by_language_server: Default::default(),
                 language_server_watched_paths: HashMap::default(),
                 buffers_being_formatted: Default::default(),
@@ -728,6 +876,7 @@ impl Project {
                 nonce: StdRng::from_entropy().gen(),
                 terminals: Terminals {
                     local_handles: Vec::new(),
+                    task_diagnostics: HashMap::default(),
                 },
                 current_lsp_settings: ProjectSettings::get_global(cx).lsp.clone(),
                 node: Some(node),
@@ -735,6 +884,7 @@ impl Project {
                 prettiers_per_worktree: HashMap::default(),
                 prettier_instances: HashMap::default(),
                 tasks,
+                bookmark_store,
                 hosted_project_id: None,
                 dev_server_project_id: None,
                 search_history: Self::new_search_history(),
@@ -802,6 +952,7 @@ impl Project {
         let this = cx.new_model(|cx| {
             let replica_id = response.payload.replica_id as ReplicaId;
             let tasks = Inventory::new(cx);
+            let bookmark_store = cx.new_model(|_| BookmarkStore::default());
             // BIG CAUTION NOTE: The order in which we initialize fields here matters and it should match what's done in Self::local.
             // Otherwise, you might run into issues where worktree id on remote is different than what's on local host.
             // That's because Worktree's identifier is entity id, which should probably be changed.
@@ -870,6 +1021,7 @@ impl Project {
                     })
                     .collect(),
                 last_formatting_failure: None,
+                large_file_load_progress: HashMap::default(),
                 last_workspace_edits_by_language_server: Default::default(),
                 language_server_watched_paths: HashMap::default(),
                 opened_buffers: Default::default(),
@@ -880,6 +1032,7 @@ impl Project {
                 nonce: StdRng::from_entropy().gen(),
                 terminals: Terminals {
                     local_handles: Vec::new(),
+                    task_diagnostics: HashMap::default(),
                 },
                 current_lsp_settings: ProjectSettings::get_global(cx).lsp.clone(),
                 node: None,
@@ -887,6 +1040,7 @@ impl Project {
                 prettiers_per_worktree: HashMap::default(),
                 prettier_instances: HashMap::default(),
                 tasks,
+                bookmark_store,
                 hosted_project_id: None,
                 dev_server_project_id: response
                     .payload
@@ -1263,6 +1417,10 @@ impl Project {
         &self.tasks
     }
 
+    pub fn bookmark_store(&self) -> &Model<BookmarkStore> {
+        &self.bookmark_store
+    }
+
     pub fn search_history(&self) -> &SearchHistory {
         &self.search_history
     }
@@ -2062,10 +2220,36 @@ impl Project {
         worktree: Model<Worktree>,
         cx: &mut ModelContext<Self>,
     ) -> Task<Result<Model<Buffer>>> {
+        let (progress_tx, mut progress_rx) = watch::channel();
         let load_buffer = worktree.update(cx, |worktree, cx| {
             let worktree = worktree.as_local_mut().unwrap();
-            worktree.load_buffer(&path, cx)
+            worktree.load_buffer(&path, progress_tx, cx)
         });
+        let project_path = ProjectPath {
+            worktree_id: worktree.read(cx).id(),
+            path: path.clone(),
+        };
+        cx.spawn(|this, mut cx| async move {
+            while let Some(progress) = progress_rx.next().await {
+                if let Some(progress) = progress {
+                    this.update(&mut cx, |this, cx| {
+                        this.large_file_load_progress
+                            .insert(project_path.clone(), progress);
+                        cx.notify();
+                    })?;
+                }
+            }
+            this.update(&mut cx, |this, cx| {
+                if this
+                    .large_file_load_progress
+                    .remove(&project_path)
+                    .is_some()
+                {
+                    cx.notify();
+                }
+            })
+        })
+        .detach_and_log_err(cx);
         fn is_not_found_error(error: &anyhow::Error) -> bool {
             error
                 .root_cause()
@@ -2365,7 +2549,7 @@ impl Project {
         let buffer_id = buffer.remote_id();
 
         if let Some(file) = File::from_dyn(buffer.file()) {
-            if !file.is_local() {
+            if !file.is_local() || file.is_large {
                 return;
             }
 
@@ -3031,6 +3215,9 @@ impl Project {
         let Some(file) = buffer.file() else {
             return;
         };
+        if file.is_large() {
+            return;
+        }
         let content = buffer.as_rope();
         let Some(new_language_result) = self
             .languages
@@ -3513,6 +3700,46 @@ impl Project {
             })
             .detach();
 
+        language_server
+            .on_request::<lsp::request::SemanticTokensRefresh, _, _>({
+                let this = this.clone();
+                move |(), mut cx| {
+                    let this = this.clone();
+                    async move {
+                        this.update(&mut cx, |project, cx| {
+                            cx.emit(Event::RefreshSemanticTokens);
+                            project.remote_id().map(|project_id| {
+                                project
+                                    .client
+                                    .send(proto::RefreshSemanticTokens { project_id })
+                            })
+                        })?
+                        .transpose()?;
+                        Ok(())
+                    }
+                }
+            })
+            .detach();
+
+        language_server
+            .on_request::<lsp::request::CodeLensRefresh, _, _>({
+                let this = this.clone();
+                move |(), mut cx| {
+                    let this = this.clone();
+                    async move {
+                        this.update(&mut cx, |project, cx| {
+                            cx.emit(Event::RefreshCodeLens);
+                            project.remote_id().map(|project_id| {
+                                project.client.send(proto::RefreshCodeLens { project_id })
+                            })
+                        })?
+                        .transpose()?;
+                        Ok(())
+                    }
+                }
+            })
+            .detach();
+
         language_server
             .on_request::<lsp::request::ShowMessageRequest, _, _>({
                 let this = this.clone();
@@ -4314,6 +4541,13 @@ impl Project {
         self.last_formatting_failure.as_deref()
     }
 
+    /// Returns the large files that are being loaded, along with the share of each file loaded so far.
+    pub fn large_file_load_progress(&self) -> impl Iterator<Item = (&ProjectPath, f32)> {
+        self.large_file_load_progress
+            .iter()
+            .map(|(path, progress)| (path, *progress))
+    }
+
     pub fn update_diagnostics(
         &mut self,
         language_server_id: LanguageServerId,
@@ -4611,6 +4845,52 @@ impl Project {
         })
     }
 
+    /// Reloads the given buffer from disk, decoding its file with the given encoding, which is then used to save it.
+    /// Any unsaved changes are discarded.
+    pub fn reopen_buffer_with_encoding(
+        &self,
+        buffer: Model<Buffer>,
+        encoding: Encoding,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<()>> {
+        let Some(file) = File::from_dyn(buffer.read(cx).file()).cloned() else {
+            return Task::ready(Err(anyhow!("buffer has no file")));
+        };
+        if !file.is_local() {
+            return Task::ready(Err(anyhow!("cannot change the encoding of a remote file")));
+        }
+
+        let abs_path = file.abs_path(cx);
+        let fs = self.fs.clone();
+        cx.spawn(move |this, mut cx| async move {
+            // Check that the file can be decoded before switching the buffer over to the new encoding.
+            let bytes = fs.load_bytes(&abs_path).await?;
+            cx.background_executor()
+                .spawn(async move { encoding.decode(bytes) })
+                .await
+                .with_context(|| format!("reopening {abs_path:?} with {encoding}"))?;
+
+            let new_file = File { encoding, ..file };
+            let reload = this.update(&mut cx, |this, cx| {
+                if let Some(project_id) = this.remote_id() {
+                    this.client
+                        .send(proto::UpdateBufferFile {
+                            project_id,
+                            buffer_id: buffer.read(cx).remote_id().into(),
+                            file: Some(new_file.to_proto()),
+                        })
+                        .log_err();
+                }
+                buffer.update(cx, |buffer, cx| {
+                    buffer.file_updated(Arc::new(new_file), cx);
+                    buffer.reload(cx)
+                })
+            })?;
+            reload.await?;
+            Ok(())
+        })
+    }
+
     pub fn format(
         &mut self,
         buffers: HashSet<Model<Buffer>>,
@@ -5125,6 +5405,169 @@ impl Project {
         self.references_impl(buffer, position, cx)
     }
 
+    /// Returns the items of the call hierarchy at the given position,
+    /// that can then be expanded with [`Self::incoming_calls`] and [`Self::outgoing_calls`].
+    pub fn prepare_call_hierarchy<T: ToPointUtf16>(
+        &self,
+        buffer: &Model<Buffer>,
+        position: T,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<HierarchyItem>>> {
+        let position = position.to_point_utf16(buffer.read(cx));
+        self.request_lsp(
+            buffer.clone(),
+            LanguageServerToQuery::Primary,
+            PrepareCallHierarchy { position },
+            cx,
+        )
+    }
+
+    /// Returns the items calling the given call hierarchy item.
+    /// `buffer` is the buffer the hierarchy was prepared in, which determines the language server to query.
+    pub fn incoming_calls(
+        &self,
+        buffer: &Model<Buffer>,
+        item: &HierarchyItem,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<HierarchyItem>>> {
+        let LspHierarchyItem::Call(item) = &item.lsp_item else {
+            return Task::ready(Err(anyhow!("not a call hierarchy item")));
+        };
+        self.request_lsp(
+            buffer.clone(),
+            LanguageServerToQuery::Primary,
+            GetIncomingCalls { item: item.clone() },
+            cx,
+        )
+    }
+
+    /// Returns the items called by the given call hierarchy item.
+    /// `buffer` is the buffer the hierarchy was prepared in, which determines the language server to query.
+    pub fn outgoing_calls(
+        &self,
+        buffer: &Model<Buffer>,
+        item: &HierarchyItem,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<HierarchyItem>>> {
+        let LspHierarchyItem::Call(item) = &item.lsp_item else {
+            return Task::ready(Err(anyhow!("not a call hierarchy item")));
+        };
+        self.request_lsp(
+            buffer.clone(),
+            LanguageServerToQuery::Primary,
+            GetOutgoingCalls { item: item.clone() },
+            cx,
+        )
+    }
+
+    /// Returns the items of the type hierarchy at the given position,
+    /// that can then be expanded with [`Self::supertypes`] and [`Self::subtypes`].
+    pub fn prepare_type_hierarchy<T: ToPointUtf16>(
+        &self,
+        buffer: &Model<Buffer>,
+        position: T,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<HierarchyItem>>> {
+        let position = position.to_point_utf16(buffer.read(cx));
+        self.request_lsp(
+            buffer.clone(),
+            LanguageServerToQuery::Primary,
+            PrepareTypeHierarchy { position },
+            cx,
+        )
+    }
+
+    /// Returns the supertypes of the given type hierarchy item.
+    /// `buffer` is the buffer the hierarchy was prepared in, which determines the language server to query.
+    pub fn supertypes(
+        &self,
+        buffer: &Model<Buffer>,
+        item: &HierarchyItem,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<HierarchyItem>>> {
+        let LspHierarchyItem::Type(item) = &item.lsp_item else {
+            return Task::ready(Err(anyhow!("not a type hierarchy item")));
+        };
+        self.request_lsp(
+            buffer.clone(),
+            LanguageServerToQuery::Primary,
+            GetSupertypes { item: item.clone() },
+            cx,
+        )
+    }
+
+    /// Returns the subtypes of the given type hierarchy item.
+    /// `buffer` is the buffer the hierarchy was prepared in, which determines the language server to query.
+    pub fn subtypes(
+        &self,
+        buffer: &Model<Buffer>,
+        item: &HierarchyItem,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<HierarchyItem>>> {
+        let LspHierarchyItem::Type(item) = &item.lsp_item else {
+            return Task::ready(Err(anyhow!("not a type hierarchy item")));
+        };
+        self.request_lsp(
+            buffer.clone(),
+            LanguageServerToQuery::Primary,
+            GetSubtypes { item: item.clone() },
+            cx,
+        )
+    }
+
+    /// Returns the fold regions of the buffer, sorted by their start.
+    pub fn folding_ranges(
+        &self,
+        buffer: &Model<Buffer>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<FoldingRange>>> {
+        self.request_lsp(
+            buffer.clone(),
+            LanguageServerToQuery::Primary,
+            GetFoldingRanges,
+            cx,
+        )
+    }
+
+    /// Returns, for each of the given positions, the ranges of the syntactic constructs containing it,
+    /// from the innermost to the outermost one.
+    /// The list is empty for the positions the language server has no selection ranges for.
+    pub fn selection_ranges<T: ToPointUtf16>(
+        &self,
+        buffer: &Model<Buffer>,
+        positions: impl IntoIterator<Item = T>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<Vec<Range<language::Anchor>>>>> {
+        let positions = positions
+            .into_iter()
+            .map(|position| position.to_point_utf16(buffer.read(cx)))
+            .collect();
+        self.request_lsp(
+            buffer.clone(),
+            LanguageServerToQuery::Primary,
+            GetSelectionRanges { positions },
+            cx,
+        )
+    }
+
+    /// Returns the ranges that have to be edited together with the one at the given position,
+    /// such as the names of the opening and closing tags of an element.
+    /// The list is empty if there are no such ranges at the position.
+    pub fn linked_editing_ranges<T: ToPointUtf16>(
+        &self,
+        buffer: &Model<Buffer>,
+        position: T,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<Range<language::Anchor>>>> {
+        let position = position.to_point_utf16(buffer.read(cx));
+        self.request_lsp(
+            buffer.clone(),
+            LanguageServerToQuery::Primary,
+            GetLinkedEditingRanges { position },
+            cx,
+        )
+    }
+
     fn document_highlights_impl(
         &self,
         buffer: &Model<Buffer>,
@@ -6543,6 +6986,153 @@ impl Project {
         }
     }
 
+    /// Fetches semantic tokens for the whole buffer from its primary language server.
+    ///
+    /// When `previous` holds a result the server can compute a delta against, only
+    /// the delta is requested and applied on top of it.
+    pub fn semantic_tokens(
+        &mut self,
+        buffer_handle: Model<Buffer>,
+        previous: Option<&SemanticTokens>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<SemanticTokens>> {
+        let buffer = buffer_handle.read(cx);
+        let previous = previous
+            .and_then(|previous| Some((previous.result_id.clone()?, previous.lsp_tokens.clone())));
+        if let Some((previous_result_id, previous_lsp_tokens)) = previous {
+            let supports_delta = self.is_local()
+                && self
+                    .primary_language_server_for_buffer(buffer, cx)
+                    .map_or(false, |(_, server)| {
+                        GetSemanticTokensDelta::supports_delta(server.capabilities())
+                    });
+            if supports_delta {
+                return self.request_lsp(
+                    buffer_handle,
+                    LanguageServerToQuery::Primary,
+                    GetSemanticTokensDelta {
+                        previous_result_id,
+                        previous_lsp_tokens,
+                    },
+                    cx,
+                );
+            }
+        }
+
+        self.request_lsp(
+            buffer_handle,
+            LanguageServerToQuery::Primary,
+            GetSemanticTokensFull,
+            cx,
+        )
+    }
+
+    /// Queries the code lenses of the buffer from its primary language server,
+    /// resolving the ones that come without a command.
+    pub fn code_lens(
+        &mut self,
+        buffer_handle: Model<Buffer>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<CodeLens>>> {
+        self.request_lsp(
+            buffer_handle,
+            LanguageServerToQuery::Primary,
+            GetCodeLens,
+            cx,
+        )
+    }
+
+    /// Runs the command of a code lens via `workspace/executeCommand`,
+    /// returning the edits the language server applied while running it.
+    pub fn execute_code_lens(
+        &self,
+        buffer_handle: Model<Buffer>,
+        lens: CodeLens,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<ProjectTransaction>> {
+        let Some(command) = lens.lsp_lens.command else {
+            return Task::ready(Err(anyhow!("code lens has no command to execute")));
+        };
+        // A command-only code action goes through the same execution path, locally and over rpc.
+        let action = CodeAction {
+            server_id: lens.server_id,
+            range: lens.range,
+            lsp_action: lsp::CodeAction {
+                title: command.title.clone(),
+                command: Some(command),
+                ..lsp::CodeAction::default()
+            },
+        };
+        self.apply_code_action(buffer_handle, action, true, cx)
+    }
+
+    /// Queries the links of the buffer from its primary language server.
+    /// Their targets might be missing until they are passed to [`Self::resolve_document_link`].
+    pub fn document_links(
+        &self,
+        buffer_handle: &Model<Buffer>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<DocumentLink>>> {
+        self.request_lsp(
+            buffer_handle.clone(),
+            LanguageServerToQuery::Primary,
+            GetDocumentLinks,
+            cx,
+        )
+    }
+
+    /// Asks the language server that produced the link for its target, if it is missing.
+    pub fn resolve_document_link(
+        &self,
+        buffer_handle: Model<Buffer>,
+        link: DocumentLink,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<DocumentLink>> {
+        if link.lsp_link.target.is_some() {
+            return Task::ready(Ok(link));
+        }
+
+        if self.is_local() {
+            let buffer = buffer_handle.read(cx);
+            let Some((_, lang_server)) =
+                self.language_server_for_buffer(buffer, link.server_id, cx)
+            else {
+                return Task::ready(Ok(link));
+            };
+            if !GetDocumentLinks::can_resolve_links(lang_server.capabilities()) {
+                return Task::ready(Ok(link));
+            }
+
+            let lang_server = lang_server.clone();
+            cx.spawn(move |_, _| async move {
+                let lsp_link = lang_server
+                    .request::<lsp::request::DocumentLinkResolve>(link.lsp_link.clone())
+                    .await
+                    .context("document link resolve LSP request")?;
+                Ok(DocumentLink { lsp_link, ..link })
+            })
+        } else if let Some(project_id) = self.remote_id() {
+            let client = self.client.clone();
+            let request = proto::ResolveDocumentLink {
+                project_id,
+                buffer_id: buffer_handle.read(cx).remote_id().into(),
+                link: Some(GetDocumentLinks::project_to_proto_link(&link)),
+            };
+            cx.spawn(move |_, _| async move {
+                let response = client
+                    .request(request)
+                    .await
+                    .context("document link resolve proto request")?;
+                match response.link {
+                    Some(resolved_link) => GetDocumentLinks::proto_to_project_link(resolved_link),
+                    None => Ok(link),
+                }
+            })
+        } else {
+            Task::ready(Err(anyhow!("project does not have a remote id")))
+        }
+    }
+
     #[allow(clippy::type_complexity)]
     pub fn search(
         &self,
@@ -7334,6 +7924,8 @@ impl Project {
                             worktree: worktree_handle.clone(),
                             is_deleted: false,
                             is_private: entry.is_private,
+                            encoding: old_file.encoding,
+                            is_large: old_file.is_large,
                         }
                     } else if let Some(entry) = snapshot.entry_for_path(old_file.path().as_ref()) {
                         File {
@@ -7344,6 +7936,8 @@ impl Project {
                             worktree: worktree_handle.clone(),
                             is_deleted: false,
                             is_private: entry.is_private,
+                            encoding: old_file.encoding,
+                            is_large: old_file.is_large,
                         }
                     } else {
                         File {
@@ -7354,6 +7948,8 @@ impl Project {
                             worktree: worktree_handle.clone(),
                             is_deleted: true,
                             is_private: old_file.is_private,
+                            encoding: old_file.encoding,
+                            is_large: old_file.is_large,
                         }
                     };
 
@@ -9189,6 +9785,35 @@ impl Project {
         })
     }
 
+    async fn handle_resolve_document_link(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::ResolveDocumentLink>,
+        _: Arc<Client>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::ResolveDocumentLinkResponse> {
+        let link = GetDocumentLinks::proto_to_project_link(
+            envelope
+                .payload
+                .link
+                .context("missing document link to resolve")?,
+        )?;
+        let buffer = this.update(&mut cx, |this, _| {
+            let buffer_id = BufferId::new(envelope.payload.buffer_id)?;
+            this.opened_buffers
+                .get(&buffer_id)
+                .and_then(|buffer| buffer.upgrade())
+                .ok_or_else(|| anyhow!("unknown buffer id {}", buffer_id))
+        })??;
+        let resolved_link = this
+            .update(&mut cx, |project, cx| {
+                project.resolve_document_link(buffer, link, cx)
+            })?
+            .await?;
+        Ok(proto::ResolveDocumentLinkResponse {
+            link: Some(GetDocumentLinks::project_to_proto_link(&resolved_link)),
+        })
+    }
+
     async fn try_resolve_code_action(
         lang_server: &LanguageServer,
         action: &mut CodeAction,
@@ -9294,6 +9919,30 @@ impl Project {
         Ok(proto::Ack {})
     }
 
+    async fn handle_refresh_semantic_tokens(
+        this: Model<Self>,
+        _: TypedEnvelope<proto::RefreshSemanticTokens>,
+        _: Arc<Client>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::Ack> {
+        this.update(&mut cx, |_, cx| {
+            cx.emit(Event::RefreshSemanticTokens);
+        })?;
+        Ok(proto::Ack {})
+    }
+
+    async fn handle_refresh_code_lens(
+        this: Model<Self>,
+        _: TypedEnvelope<proto::RefreshCodeLens>,
+        _: Arc<Client>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::Ack> {
+        this.update(&mut cx, |_, cx| {
+            cx.emit(Event::RefreshCodeLens);
+        })?;
+        Ok(proto::Ack {})
+    }
+
     async fn handle_lsp_command<T: LspCommand>(
         this: Model<Self>,
         envelope: TypedEnvelope<T::ProtoRequest>,
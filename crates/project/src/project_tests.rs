@@ -4,7 +4,7 @@ use futures::{future, StreamExt};
 use gpui::AppContext;
 use language::{
     language_settings::{AllLanguageSettings, LanguageSettingsContent},
-    tree_sitter_rust, tree_sitter_typescript, Diagnostic, FakeLspAdapter, LanguageConfig,
+    tree_sitter_rust, tree_sitter_typescript, Diagnostic, Encoding, FakeLspAdapter, LanguageConfig,
     LanguageMatcher, LineEnding, OffsetRangeExt, Point, ToPoint,
 };
 use lsp::Url;
@@ -2431,14 +2431,14 @@ async fn test_definition(cx: &mut gpui::TestAppContext) {
 }
 
 #[gpui::test]
-async fn test_completions_without_edit_ranges(cx: &mut gpui::TestAppContext) {
+async fn test_semantic_tokens(cx: &mut gpui::TestAppContext) {
     init_test(cx);
 
     let fs = FakeFs::new(cx.executor());
     fs.insert_tree(
         "/dir",
         json!({
-            "a.ts": "",
+            "a.rs": "fn main() { foo!(); }",
         }),
     )
     .await;
@@ -2446,15 +2446,26 @@ async fn test_completions_without_edit_ranges(cx: &mut gpui::TestAppContext) {
     let project = Project::test(fs, ["/dir".as_ref()], cx).await;
 
     let language_registry = project.read_with(cx, |project, _| project.languages().clone());
-    language_registry.add(typescript_lang());
-    let mut fake_language_servers = language_registry.register_fake_lsp_adapter(
-        "TypeScript",
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
         FakeLspAdapter {
             capabilities: lsp::ServerCapabilities {
-                completion_provider: Some(lsp::CompletionOptions {
-                    trigger_characters: Some(vec![":".to_string()]),
-                    ..Default::default()
-                }),
+                semantic_tokens_provider: Some(
+                    lsp::SemanticTokensServerCapabilities::SemanticTokensOptions(
+                        lsp::SemanticTokensOptions {
+                            legend: lsp::SemanticTokensLegend {
+                                token_types: vec![
+                                    lsp::SemanticTokenType::FUNCTION,
+                                    lsp::SemanticTokenType::MACRO,
+                                ],
+                                token_modifiers: vec![lsp::SemanticTokenModifier::DECLARATION],
+                            },
+                            full: Some(lsp::SemanticTokensFullOptions::Delta { delta: Some(true) }),
+                            ..Default::default()
+                        },
+                    ),
+                ),
                 ..Default::default()
             },
             ..Default::default()
@@ -2462,75 +2473,121 @@ async fn test_completions_without_edit_ranges(cx: &mut gpui::TestAppContext) {
     );
 
     let buffer = project
-        .update(cx, |p, cx| p.open_local_buffer("/dir/a.ts", cx))
+        .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
         .await
         .unwrap();
+    let fake_server = fake_servers.next().await.unwrap();
 
-    let fake_server = fake_language_servers.next().await.unwrap();
-
-    let text = "let a = b.fqn";
-    buffer.update(cx, |buffer, cx| buffer.set_text(text, cx));
-    let completions = project.update(cx, |project, cx| {
-        project.completions(&buffer, text.len(), cx)
-    });
-
-    fake_server
-        .handle_request::<lsp::request::Completion, _, _>(|_, _| async move {
-            Ok(Some(lsp::CompletionResponse::Array(vec![
-                lsp::CompletionItem {
-                    label: "fullyQualifiedName?".into(),
-                    insert_text: Some("fullyQualifiedName".into()),
-                    ..Default::default()
+    fake_server.handle_request::<lsp::request::SemanticTokensFullRequest, _, _>(
+        |params, _| async move {
+            assert_eq!(
+                params.text_document.uri,
+                lsp::Url::from_file_path("/dir/a.rs").unwrap()
+            );
+            Ok(Some(lsp::SemanticTokensResult::Tokens(
+                lsp::SemanticTokens {
+                    result_id: Some("1".to_string()),
+                    data: vec![
+                        lsp::SemanticToken {
+                            delta_line: 0,
+                            delta_start: 3,
+                            length: 4,
+                            token_type: 0,
+                            token_modifiers_bitset: 1,
+                        },
+                        lsp::SemanticToken {
+                            delta_line: 0,
+                            delta_start: 9,
+                            length: 3,
+                            token_type: 1,
+                            token_modifiers_bitset: 0,
+                        },
+                    ],
                 },
-            ])))
-        })
-        .next()
-        .await;
-    let completions = completions.await.unwrap();
-    let snapshot = buffer.update(cx, |buffer, _| buffer.snapshot());
-    assert_eq!(completions.len(), 1);
-    assert_eq!(completions[0].new_text, "fullyQualifiedName");
-    assert_eq!(
-        completions[0].old_range.to_offset(&snapshot),
-        text.len() - 3..text.len()
+            )))
+        },
     );
-
-    let text = "let a = \"atoms/cmp\"";
-    buffer.update(cx, |buffer, cx| buffer.set_text(text, cx));
-    let completions = project.update(cx, |project, cx| {
-        project.completions(&buffer, text.len() - 1, cx)
+    let tokens = project
+        .update(cx, |project, cx| {
+            project.semantic_tokens(buffer.clone(), None, cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(tokens.result_id.as_deref(), Some("1"));
+    buffer.update(cx, |buffer, _| {
+        assert_eq!(
+            tokens
+                .tokens
+                .iter()
+                .map(|token| (
+                    token.range.to_offset(buffer),
+                    tokens.legend.token_type(token.token_type).unwrap(),
+                    tokens
+                        .legend
+                        .token_modifiers(token.token_modifiers)
+                        .collect::<Vec<_>>()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (3..7, "function", vec!["declaration"]),
+                (12..15, "macro", vec![]),
+            ]
+        );
     });
 
-    fake_server
-        .handle_request::<lsp::request::Completion, _, _>(|_, _| async move {
-            Ok(Some(lsp::CompletionResponse::Array(vec![
-                lsp::CompletionItem {
-                    label: "component".into(),
-                    ..Default::default()
+    // Once a result id is known, only the delta is requested.
+    fake_server.handle_request::<lsp::request::SemanticTokensFullDeltaRequest, _, _>(
+        |params, _| async move {
+            assert_eq!(params.previous_result_id, "1");
+            Ok(Some(lsp::SemanticTokensFullDeltaResult::TokensDelta(
+                lsp::SemanticTokensDelta {
+                    result_id: Some("2".to_string()),
+                    edits: vec![lsp::SemanticTokensEdit {
+                        start: 5,
+                        delete_count: 5,
+                        data: Some(vec![lsp::SemanticToken {
+                            delta_line: 0,
+                            delta_start: 9,
+                            length: 3,
+                            token_type: 0,
+                            token_modifiers_bitset: 0,
+                        }]),
+                    }],
                 },
-            ])))
-        })
-        .next()
-        .await;
-    let completions = completions.await.unwrap();
-    let snapshot = buffer.update(cx, |buffer, _| buffer.snapshot());
-    assert_eq!(completions.len(), 1);
-    assert_eq!(completions[0].new_text, "component");
-    assert_eq!(
-        completions[0].old_range.to_offset(&snapshot),
-        text.len() - 4..text.len() - 1
+            )))
+        },
     );
+    let tokens = project
+        .update(cx, |project, cx| {
+            project.semantic_tokens(buffer.clone(), Some(&tokens), cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(tokens.result_id.as_deref(), Some("2"));
+    buffer.update(cx, |buffer, _| {
+        assert_eq!(
+            tokens
+                .tokens
+                .iter()
+                .map(|token| (
+                    token.range.to_offset(buffer),
+                    tokens.legend.token_type(token.token_type).unwrap()
+                ))
+                .collect::<Vec<_>>(),
+            vec![(3..7, "function"), (12..15, "function")]
+        );
+    });
 }
 
 #[gpui::test]
-async fn test_completions_with_carriage_returns(cx: &mut gpui::TestAppContext) {
+async fn test_code_lens(cx: &mut gpui::TestAppContext) {
     init_test(cx);
 
     let fs = FakeFs::new(cx.executor());
     fs.insert_tree(
         "/dir",
         json!({
-            "a.ts": "",
+            "a.rs": "fn main() {}\n\n#[test]\nfn test_a() {}",
         }),
     )
     .await;
@@ -2538,13 +2595,16 @@ async fn test_completions_with_carriage_returns(cx: &mut gpui::TestAppContext) {
     let project = Project::test(fs, ["/dir".as_ref()], cx).await;
 
     let language_registry = project.read_with(cx, |project, _| project.languages().clone());
-    language_registry.add(typescript_lang());
-    let mut fake_language_servers = language_registry.register_fake_lsp_adapter(
-        "TypeScript",
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
         FakeLspAdapter {
             capabilities: lsp::ServerCapabilities {
-                completion_provider: Some(lsp::CompletionOptions {
-                    trigger_characters: Some(vec![":".to_string()]),
+                code_lens_provider: Some(lsp::CodeLensOptions {
+                    resolve_provider: Some(true),
+                }),
+                execute_command_provider: Some(lsp::ExecuteCommandOptions {
+                    commands: vec!["runTest".to_string()],
                     ..Default::default()
                 }),
                 ..Default::default()
@@ -2554,44 +2614,189 @@ async fn test_completions_with_carriage_returns(cx: &mut gpui::TestAppContext) {
     );
 
     let buffer = project
-        .update(cx, |p, cx| p.open_local_buffer("/dir/a.ts", cx))
+        .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
         .await
         .unwrap();
+    let fake_server = fake_servers.next().await.unwrap();
 
-    let fake_server = fake_language_servers.next().await.unwrap();
+    fake_server.handle_request::<lsp::request::CodeLensRequest, _, _>(|params, _| async move {
+        assert_eq!(
+            params.text_document.uri,
+            lsp::Url::from_file_path("/dir/a.rs").unwrap()
+        );
+        Ok(Some(vec![
+            lsp::CodeLens {
+                range: lsp::Range::new(lsp::Position::new(0, 3), lsp::Position::new(0, 7)),
+                command: None,
+                data: Some(json!({ "references": "main" })),
+            },
+            lsp::CodeLens {
+                range: lsp::Range::new(lsp::Position::new(3, 3), lsp::Position::new(3, 9)),
+                command: Some(lsp::Command {
+                    title: "Run test".to_string(),
+                    command: "runTest".to_string(),
+                    arguments: Some(vec![json!("test_a")]),
+                }),
+                data: None,
+            },
+        ]))
+    });
+    // Only the lens without a command gets resolved.
+    fake_server.handle_request::<lsp::request::CodeLensResolve, _, _>(|lens, _| async move {
+        assert_eq!(lens.data, Some(json!({ "references": "main" })));
+        Ok(lsp::CodeLens {
+            command: Some(lsp::Command {
+                title: "0 references".to_string(),
+                command: "showReferences".to_string(),
+                arguments: None,
+            }),
+            ..lens
+        })
+    });
 
-    let text = "let a = b.fqn";
-    buffer.update(cx, |buffer, cx| buffer.set_text(text, cx));
-    let completions = project.update(cx, |project, cx| {
-        project.completions(&buffer, text.len(), cx)
+    let lenses = project
+        .update(cx, |project, cx| project.code_lens(buffer.clone(), cx))
+        .await
+        .unwrap();
+    buffer.update(cx, |buffer, _| {
+        assert_eq!(
+            lenses
+                .iter()
+                .map(|lens| (
+                    lens.range.to_offset(buffer),
+                    lens.lsp_lens.command.as_ref().unwrap().title.as_str()
+                ))
+                .collect::<Vec<_>>(),
+            vec![(3..7, "0 references"), (25..31, "Run test")]
+        );
     });
 
-    fake_server
-        .handle_request::<lsp::request::Completion, _, _>(|_, _| async move {
-            Ok(Some(lsp::CompletionResponse::Array(vec![
-                lsp::CompletionItem {
-                    label: "fullyQualifiedName?".into(),
-                    insert_text: Some("fully\rQualified\r\nName".into()),
-                    ..Default::default()
-                },
-            ])))
+    fake_server.handle_request::<lsp::request::ExecuteCommand, _, _>(|params, _| async move {
+        assert_eq!(params.command, "runTest");
+        assert_eq!(params.arguments, vec![json!("test_a")]);
+        Ok(None)
+    });
+    project
+        .update(cx, |project, cx| {
+            project.execute_code_lens(buffer.clone(), lenses[1].clone(), cx)
         })
-        .next()
+        .await
+        .unwrap();
+}
+
+#[gpui::test]
+async fn test_call_hierarchy(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "a.rs": "fn a() { b(); }",
+            "b.rs": "fn b() {}",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                call_hierarchy_provider: Some(lsp::CallHierarchyServerCapability::Simple(true)),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/b.rs", cx))
+        .await
+        .unwrap();
+    let fake_server = fake_servers.next().await.unwrap();
+
+    fn lsp_item(name: &str, path: &str, start: u32, end: u32) -> lsp::CallHierarchyItem {
+        lsp::CallHierarchyItem {
+            name: name.to_string(),
+            kind: lsp::SymbolKind::FUNCTION,
+            tags: None,
+            detail: Some(format!("fn {name}()")),
+            uri: lsp::Url::from_file_path(path).unwrap(),
+            range: lsp::Range::new(lsp::Position::new(0, 0), lsp::Position::new(0, end)),
+            selection_range: lsp::Range::new(
+                lsp::Position::new(0, start),
+                lsp::Position::new(0, start + 1),
+            ),
+            data: None,
+        }
+    }
+
+    fake_server.handle_request::<lsp::request::CallHierarchyPrepare, _, _>(
+        |params, _| async move {
+            assert_eq!(
+                params.text_document_position_params.position,
+                lsp::Position::new(0, 3)
+            );
+            Ok(Some(vec![lsp_item("b", "/dir/b.rs", 3, 9)]))
+        },
+    );
+    let items = project
+        .update(cx, |project, cx| {
+            project.prepare_call_hierarchy(&buffer, Point::new(0, 3), cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].name, "b");
+    assert_eq!(items[0].location.buffer, buffer);
+
+    // The callers are defined in another buffer, which gets opened to point at them.
+    fake_server.handle_request::<lsp::request::CallHierarchyIncomingCalls, _, _>(
+        |params, _| async move {
+            assert_eq!(params.item.name, "b");
+            Ok(Some(vec![lsp::CallHierarchyIncomingCall {
+                from: lsp_item("a", "/dir/a.rs", 3, 15),
+                from_ranges: vec![lsp::Range::new(
+                    lsp::Position::new(0, 9),
+                    lsp::Position::new(0, 10),
+                )],
+            }]))
+        },
+    );
+    let incoming_calls = project
+        .update(cx, |project, cx| {
+            project.incoming_calls(&buffer, &items[0], cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(incoming_calls.len(), 1);
+    let caller = &incoming_calls[0];
+    assert_eq!(caller.name, "a");
+    assert_eq!(caller.detail.as_deref(), Some("fn a()"));
+    caller.location.buffer.update(cx, |buffer, _| {
+        assert_eq!(buffer.file().unwrap().path().as_ref(), Path::new("a.rs"));
+        assert_eq!(caller.location.range.to_offset(buffer), 3..4);
+    });
+
+    let result = project
+        .update(cx, |project, cx| project.supertypes(&buffer, &items[0], cx))
         .await;
-    let completions = completions.await.unwrap();
-    assert_eq!(completions.len(), 1);
-    assert_eq!(completions[0].new_text, "fully\nQualified\nName");
+    assert!(result.is_err(), "call hierarchy items have no supertypes");
 }
 
-#[gpui::test(iterations = 10)]
-async fn test_apply_code_actions_with_commands(cx: &mut gpui::TestAppContext) {
+#[gpui::test]
+async fn test_folding_and_selection_ranges(cx: &mut gpui::TestAppContext) {
     init_test(cx);
 
     let fs = FakeFs::new(cx.executor());
     fs.insert_tree(
         "/dir",
         json!({
-            "a.ts": "a",
+            "a.rs": "use a;\nuse b;\n\nfn main() {\n    let x = 1;\n}\n",
         }),
     )
     .await;
@@ -2599,153 +2804,996 @@ async fn test_apply_code_actions_with_commands(cx: &mut gpui::TestAppContext) {
     let project = Project::test(fs, ["/dir".as_ref()], cx).await;
 
     let language_registry = project.read_with(cx, |project, _| project.languages().clone());
-    language_registry.add(typescript_lang());
-    let mut fake_language_servers = language_registry.register_fake_lsp_adapter(
-        "TypeScript",
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
         FakeLspAdapter {
             capabilities: lsp::ServerCapabilities {
-                code_action_provider: Some(lsp::CodeActionProviderCapability::Options(
-                    lsp::CodeActionOptions {
-                        resolve_provider: Some(true),
-                        ..lsp::CodeActionOptions::default()
-                    },
-                )),
-                ..lsp::ServerCapabilities::default()
+                folding_range_provider: Some(lsp::FoldingRangeProviderCapability::Simple(true)),
+                selection_range_provider: Some(lsp::SelectionRangeProviderCapability::Simple(true)),
+                ..Default::default()
             },
-            ..FakeLspAdapter::default()
+            ..Default::default()
+        },
+    );
+
+    let buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
+        .await
+        .unwrap();
+    let fake_server = fake_servers.next().await.unwrap();
+
+    fake_server.handle_request::<lsp::request::FoldingRangeRequest, _, _>(|_, _| async move {
+        Ok(Some(vec![
+            lsp::FoldingRange {
+                start_line: 3,
+                start_character: Some(11),
+                end_line: 4,
+                end_character: None,
+                kind: None,
+                collapsed_text: None,
+            },
+            lsp::FoldingRange {
+                start_line: 0,
+                start_character: None,
+                end_line: 1,
+                end_character: None,
+                kind: Some(lsp::FoldingRangeKind::Imports),
+                collapsed_text: Some("use ...".to_string()),
+            },
+            // Ranges spanning a single line cannot be folded.
+            lsp::FoldingRange {
+                start_line: 4,
+                start_character: None,
+                end_line: 4,
+                end_character: None,
+                kind: None,
+                collapsed_text: None,
+            },
+        ]))
+    });
+    let folding_ranges = project
+        .update(cx, |project, cx| project.folding_ranges(&buffer, cx))
+        .await
+        .unwrap();
+    buffer.update(cx, |buffer, _| {
+        assert_eq!(
+            folding_ranges
+                .iter()
+                .map(|folding_range| (
+                    folding_range.range.to_point(buffer),
+                    folding_range.kind.clone(),
+                    folding_range.collapsed_text.as_deref(),
+                ))
+                .collect::<Vec<_>>(),
+            [
+                (
+                    Point::new(0, 0)..Point::new(1, 6),
+                    Some(lsp::FoldingRangeKind::Imports),
+                    Some("use ..."),
+                ),
+                (Point::new(3, 0)..Point::new(4, 14), None, None),
+            ]
+        );
+    });
+
+    fake_server.handle_request::<lsp::request::SelectionRangeRequest, _, _>(
+        |params, _| async move {
+            assert_eq!(params.positions, [lsp::Position::new(4, 8)]);
+            Ok(Some(vec![lsp::SelectionRange {
+                range: lsp::Range::new(lsp::Position::new(4, 8), lsp::Position::new(4, 9)),
+                parent: Some(Box::new(lsp::SelectionRange {
+                    range: lsp::Range::new(lsp::Position::new(4, 4), lsp::Position::new(4, 14)),
+                    parent: None,
+                })),
+            }]))
         },
     );
+    let selection_ranges = project
+        .update(cx, |project, cx| {
+            project.selection_ranges(&buffer, [Point::new(4, 8)], cx)
+        })
+        .await
+        .unwrap();
+    buffer.update(cx, |buffer, _| {
+        assert_eq!(
+            selection_ranges
+                .iter()
+                .map(|ranges| ranges
+                    .iter()
+                    .map(|range| range.to_offset(buffer))
+                    .collect::<Vec<_>>())
+                .collect::<Vec<_>>(),
+            [vec![35..36, 31..41]]
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_document_links(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "a.rs": "mod b; // see https://zed.dev\n",
+            "b.rs": "",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                document_link_provider: Some(lsp::DocumentLinkOptions {
+                    resolve_provider: Some(true),
+                    work_done_progress_options: Default::default(),
+                }),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
+        .await
+        .unwrap();
+    let fake_server = fake_servers.next().await.unwrap();
+
+    fake_server.handle_request::<lsp::request::DocumentLinkRequest, _, _>(|_, _| async move {
+        Ok(Some(vec![
+            lsp::DocumentLink {
+                range: lsp::Range::new(lsp::Position::new(0, 4), lsp::Position::new(0, 5)),
+                target: None,
+                tooltip: None,
+                data: Some(json!("b")),
+            },
+            lsp::DocumentLink {
+                range: lsp::Range::new(lsp::Position::new(0, 14), lsp::Position::new(0, 29)),
+                target: Some(lsp::Url::parse("https://zed.dev").unwrap()),
+                tooltip: None,
+                data: None,
+            },
+        ]))
+    });
+    let links = project
+        .update(cx, |project, cx| project.document_links(&buffer, cx))
+        .await
+        .unwrap();
+    buffer.update(cx, |buffer, _| {
+        assert_eq!(
+            links
+                .iter()
+                .map(|link| (
+                    link.range.to_offset(buffer),
+                    link.lsp_link.target.as_ref().map(|target| target.as_str()),
+                ))
+                .collect::<Vec<_>>(),
+            [(4..5, None), (14..29, Some("https://zed.dev/"))]
+        );
+    });
+
+    fake_server.handle_request::<lsp::request::DocumentLinkResolve, _, _>(
+        |mut link, _| async move {
+            assert_eq!(link.data, Some(json!("b")));
+            link.target = Some(lsp::Url::from_file_path("/dir/b.rs").unwrap());
+            Ok(link)
+        },
+    );
+    let resolved_link = project
+        .update(cx, |project, cx| {
+            project.resolve_document_link(buffer.clone(), links[0].clone(), cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        resolved_link.lsp_link.target,
+        Some(lsp::Url::from_file_path("/dir/b.rs").unwrap())
+    );
+}
+
+#[gpui::test]
+async fn test_completions_without_edit_ranges(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "a.ts": "",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(typescript_lang());
+    let mut fake_language_servers = language_registry.register_fake_lsp_adapter(
+        "TypeScript",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                completion_provider: Some(lsp::CompletionOptions {
+                    trigger_characters: Some(vec![":".to_string()]),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let buffer = project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/a.ts", cx))
+        .await
+        .unwrap();
+
+    let fake_server = fake_language_servers.next().await.unwrap();
+
+    let text = "let a = b.fqn";
+    buffer.update(cx, |buffer, cx| buffer.set_text(text, cx));
+    let completions = project.update(cx, |project, cx| {
+        project.completions(&buffer, text.len(), cx)
+    });
+
+    fake_server
+        .handle_request::<lsp::request::Completion, _, _>(|_, _| async move {
+            Ok(Some(lsp::CompletionResponse::Array(vec![
+                lsp::CompletionItem {
+                    label: "fullyQualifiedName?".into(),
+                    insert_text: Some("fullyQualifiedName".into()),
+                    ..Default::default()
+                },
+            ])))
+        })
+        .next()
+        .await;
+    let completions = completions.await.unwrap();
+    let snapshot = buffer.update(cx, |buffer, _| buffer.snapshot());
+    assert_eq!(completions.len(), 1);
+    assert_eq!(completions[0].new_text, "fullyQualifiedName");
+    assert_eq!(
+        completions[0].old_range.to_offset(&snapshot),
+        text.len() - 3..text.len()
+    );
+
+    let text = "let a = \"atoms/cmp\"";
+    buffer.update(cx, |buffer, cx| buffer.set_text(text, cx));
+    let completions = project.update(cx, |project, cx| {
+        project.completions(&buffer, text.len() - 1, cx)
+    });
+
+    fake_server
+        .handle_request::<lsp::request::Completion, _, _>(|_, _| async move {
+            Ok(Some(lsp::CompletionResponse::Array(vec![
+                lsp::CompletionItem {
+                    label: "component".into(),
+                    ..Default::default()
+                },
+            ])))
+        })
+        .next()
+        .await;
+    let completions = completions.await.unwrap();
+    let snapshot = buffer.update(cx, |buffer, _| buffer.snapshot());
+    assert_eq!(completions.len(), 1);
+    assert_eq!(completions[0].new_text, "component");
+    assert_eq!(
+        completions[0].old_range.to_offset(&snapshot),
+        text.len() - 4..text.len() - 1
+    );
+}
+
+#[gpui::test]
+async fn test_completions_with_carriage_returns(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "a.ts": "",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(typescript_lang());
+    let mut fake_language_servers = language_registry.register_fake_lsp_adapter(
+        "TypeScript",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                completion_provider: Some(lsp::CompletionOptions {
+                    trigger_characters: Some(vec![":".to_string()]),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let buffer = project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/a.ts", cx))
+        .await
+        .unwrap();
+
+    let fake_server = fake_language_servers.next().await.unwrap();
+
+    let text = "let a = b.fqn";
+    buffer.update(cx, |buffer, cx| buffer.set_text(text, cx));
+    let completions = project.update(cx, |project, cx| {
+        project.completions(&buffer, text.len(), cx)
+    });
+
+    fake_server
+        .handle_request::<lsp::request::Completion, _, _>(|_, _| async move {
+            Ok(Some(lsp::CompletionResponse::Array(vec![
+                lsp::CompletionItem {
+                    label: "fullyQualifiedName?".into(),
+                    insert_text: Some("fully\rQualified\r\nName".into()),
+                    ..Default::default()
+                },
+            ])))
+        })
+        .next()
+        .await;
+    let completions = completions.await.unwrap();
+    assert_eq!(completions.len(), 1);
+    assert_eq!(completions[0].new_text, "fully\nQualified\nName");
+}
+
+#[gpui::test(iterations = 10)]
+async fn test_apply_code_actions_with_commands(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "a.ts": "a",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(typescript_lang());
+    let mut fake_language_servers = language_registry.register_fake_lsp_adapter(
+        "TypeScript",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                code_action_provider: Some(lsp::CodeActionProviderCapability::Options(
+                    lsp::CodeActionOptions {
+                        resolve_provider: Some(true),
+                        ..lsp::CodeActionOptions::default()
+                    },
+                )),
+                ..lsp::ServerCapabilities::default()
+            },
+            ..FakeLspAdapter::default()
+        },
+    );
+
+    let buffer = project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/a.ts", cx))
+        .await
+        .unwrap();
+
+    let fake_server = fake_language_servers.next().await.unwrap();
+
+    // Language server returns code actions that contain commands, and not edits.
+    let actions = project.update(cx, |project, cx| project.code_actions(&buffer, 0..0, cx));
+    fake_server
+        .handle_request::<lsp::request::CodeActionRequest, _, _>(|_, _| async move {
+            Ok(Some(vec![
+                lsp::CodeActionOrCommand::CodeAction(lsp::CodeAction {
+                    title: "The code action".into(),
+                    data: Some(serde_json::json!({
+                        "command": "_the/command",
+                    })),
+                    ..lsp::CodeAction::default()
+                }),
+                lsp::CodeActionOrCommand::CodeAction(lsp::CodeAction {
+                    title: "two".into(),
+                    ..lsp::CodeAction::default()
+                }),
+            ]))
+        })
+        .next()
+        .await;
+
+    let action = actions.await[0].clone();
+    let apply = project.update(cx, |project, cx| {
+        project.apply_code_action(buffer.clone(), action, true, cx)
+    });
+
+    // Resolving the code action does not populate its edits. In absence of
+    // edits, we must execute the given command.
+    fake_server.handle_request::<lsp::request::CodeActionResolveRequest, _, _>(
+        |mut action, _| async move {
+            if action.data.is_some() {
+                action.command = Some(lsp::Command {
+                    title: "The command".into(),
+                    command: "_the/command".into(),
+                    arguments: Some(vec![json!("the-argument")]),
+                });
+            }
+            Ok(action)
+        },
+    );
+
+    // While executing the command, the language server sends the editor
+    // a `workspaceEdit` request.
+    fake_server
+        .handle_request::<lsp::request::ExecuteCommand, _, _>({
+            let fake = fake_server.clone();
+            move |params, _| {
+                assert_eq!(params.command, "_the/command");
+                let fake = fake.clone();
+                async move {
+                    fake.server
+                        .request::<lsp::request::ApplyWorkspaceEdit>(
+                            lsp::ApplyWorkspaceEditParams {
+                                label: None,
+                                edit: lsp::WorkspaceEdit {
+                                    changes: Some(
+                                        [(
+                                            lsp::Url::from_file_path("/dir/a.ts").unwrap(),
+                                            vec![lsp::TextEdit {
+                                                range: lsp::Range::new(
+                                                    lsp::Position::new(0, 0),
+                                                    lsp::Position::new(0, 0),
+                                                ),
+                                                new_text: "X".into(),
+                                            }],
+                                        )]
+                                        .into_iter()
+                                        .collect(),
+                                    ),
+                                    ..Default::default()
+                                },
+                            },
+                        )
+                        .await
+                        .unwrap();
+                    Ok(Some(json!(null)))
+                }
+            }
+        })
+        .next()
+        .await;
+
+    // Applying the code action returns a project transaction containing the edits
+    // sent by the language server in its `workspaceEdit` request.
+    let transaction = apply.await.unwrap();
+    assert!(transaction.0.contains_key(&buffer));
+    buffer.update(cx, |buffer, cx| {
+        assert_eq!(buffer.text(), "Xa");
+        buffer.undo(cx);
+        assert_eq!(buffer.text(), "a");
+    });
+}
+
+#[gpui::test(iterations = 10)]
+async fn test_save_file(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "file1": "the old contents",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/file1", cx))
+        .await
+        .unwrap();
+    buffer.update(cx, |buffer, cx| {
+        assert_eq!(buffer.text(), "the old contents");
+        buffer.edit([(0..0, "a line of text.\n".repeat(10 * 1024))], None, cx);
+    });
+
+    project
+        .update(cx, |project, cx| project.save_buffer(buffer.clone(), cx))
+        .await
+        .unwrap();
+
+    let new_text = fs.load(Path::new("/dir/file1")).await.unwrap();
+    assert_eq!(new_text, buffer.update(cx, |buffer, _| buffer.text()));
+}
+
+#[gpui::test]
+async fn test_save_file_with_encoding(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({})).await;
+    fs.insert_file("/dir/legacy.c", b"// caf\xE9\n".to_vec())
+        .await;
+    fs.insert_file("/dir/modern.c", "// café\n".as_bytes().to_vec())
+        .await;
+    fs.insert_file("/dir/binary.o", b"\x7FELF\x02\x01\x01\x00\xE9".to_vec())
+        .await;
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/legacy.c", cx))
+        .await
+        .unwrap();
+    buffer.update(cx, |buffer, cx| {
+        assert_eq!(buffer.text(), "// café\n");
+        assert_eq!(
+            buffer.file().unwrap().encoding().to_string(),
+            "windows-1252"
+        );
+        buffer.edit([(buffer.len()..buffer.len(), "// naïve\n")], None, cx);
+    });
+    project
+        .update(cx, |project, cx| project.save_buffer(buffer.clone(), cx))
+        .await
+        .unwrap();
+    assert_eq!(
+        fs.load_bytes(Path::new("/dir/legacy.c")).await.unwrap(),
+        b"// caf\xE9\n// na\xEFve\n"
+    );
+
+    // Characters that the encoding cannot represent fail the save, leaving the file untouched.
+    buffer.update(cx, |buffer, cx| {
+        buffer.edit([(buffer.len()..buffer.len(), "// 🍐\n")], None, cx);
+    });
+    project
+        .update(cx, |project, cx| project.save_buffer(buffer.clone(), cx))
+        .await
+        .unwrap_err();
+    assert_eq!(
+        fs.load_bytes(Path::new("/dir/legacy.c")).await.unwrap(),
+        b"// caf\xE9\n// na\xEFve\n"
+    );
+
+    // Reopening with an encoding that cannot decode the file keeps the buffer as is.
+    project
+        .update(cx, |project, cx| {
+            project.reopen_buffer_with_encoding(buffer.clone(), Encoding::utf8(), cx)
+        })
+        .await
+        .unwrap_err();
+    buffer.update(cx, |buffer, _| {
+        assert_eq!(buffer.text(), "// café\n// naïve\n// 🍐\n");
+        assert_eq!(
+            buffer.file().unwrap().encoding().to_string(),
+            "windows-1252"
+        );
+    });
+
+    let buffer = project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/modern.c", cx))
+        .await
+        .unwrap();
+    buffer.update(cx, |buffer, _| {
+        assert_eq!(buffer.text(), "// café\n");
+        assert_eq!(buffer.file().unwrap().encoding(), Encoding::utf8());
+    });
+    let windows_1252 = Encoding::from_name("windows-1252").unwrap();
+    project
+        .update(cx, |project, cx| {
+            project.reopen_buffer_with_encoding(buffer.clone(), windows_1252, cx)
+        })
+        .await
+        .unwrap();
+    buffer.update(cx, |buffer, _| {
+        assert_eq!(buffer.text(), "// cafÃ©\n");
+        assert_eq!(buffer.file().unwrap().encoding(), windows_1252);
+        assert!(!buffer.is_dirty());
+    });
+
+    // Binary files are not opened as Windows-1252 text.
+    project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/binary.o", cx))
+        .await
+        .unwrap_err();
+}
+
+#[gpui::test]
+async fn test_open_large_file(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+    cx.update(|cx| {
+        cx.update_global(|settings: &mut SettingsStore, cx| {
+            settings.update_user_settings::<WorktreeSettings>(cx, |settings| {
+                settings.large_file_threshold_mb = Some(0);
+            });
+        });
+    });
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            ".git": {},
+            "main.rs": "fn main() {\r\n}\r\n",
+        }),
+    )
+    .await;
+    fs.set_index_for_repo(
+        Path::new("/dir/.git"),
+        &[(Path::new("main.rs"), "fn main() {}\n".into())],
+    );
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/main.rs", cx)
+        })
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+
+    buffer.update(cx, |buffer, _| {
+        assert!(buffer.file().unwrap().is_large());
+        assert_eq!(buffer.text(), "fn main() {\n}\n");
+        assert_eq!(buffer.line_ending(), LineEnding::Windows);
+        assert!(buffer.language().is_none());
+        assert!(buffer.diff_base().is_none());
+    });
+    project.update(cx, |project, _| {
+        assert_eq!(project.large_file_load_progress().count(), 0);
+    });
+}
+
+#[gpui::test]
+async fn test_open_large_file_with_invalid_utf8_after_first_chunk(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+    cx.update(|cx| {
+        cx.update_global(|settings: &mut SettingsStore, cx| {
+            settings.update_user_settings::<WorktreeSettings>(cx, |settings| {
+                settings.large_file_threshold_mb = Some(0);
+            });
+        });
+    });
+
+    // The first chunk is plain ASCII, so the file is detected as UTF-8 until the last chunk.
+    let mut contents = "a".repeat(5 * 1024 * 1024).into_bytes();
+    contents.extend_from_slice(b"\ncaf\xE9\n");
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({})).await;
+    fs.insert_file("/dir/large.txt", contents).await;
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/large.txt", cx)
+        })
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+
+    buffer.update(cx, |buffer, _| {
+        assert!(buffer.file().unwrap().is_large());
+        assert_eq!(buffer.len(), 5 * 1024 * 1024 + "\ncafé\n".len());
+        assert!(buffer.text().ends_with("a\ncafé\n"));
+        assert_eq!(buffer.file().unwrap().encoding(), Encoding::windows_1252());
+    });
+}
+
+#[gpui::test]
+async fn test_reload_file_grown_over_large_file_threshold(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+    cx.update(|cx| {
+        cx.update_global(|settings: &mut SettingsStore, cx| {
+            settings.update_user_settings::<WorktreeSettings>(cx, |settings| {
+                settings.large_file_threshold_mb = Some(1);
+            });
+        });
+    });
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ "log.txt": "one\r\n" }))
+        .await;
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/log.txt", cx)
+        })
+        .await
+        .unwrap();
+    buffer.update(cx, |buffer, _| {
+        assert!(!buffer.file().unwrap().is_large());
+    });
+
+    let new_contents = "two\n".repeat(512 * 1024);
+    fs.save(
+        "/dir/log.txt".as_ref(),
+        &new_contents.as_str().into(),
+        LineEnding::Windows,
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    cx.executor().run_until_parked();
+
+    buffer.update(cx, |buffer, _| {
+        assert!(buffer.file().unwrap().is_large());
+        assert_eq!(buffer.text(), new_contents);
+        assert_eq!(buffer.line_ending(), LineEnding::Windows);
+        assert!(!buffer.is_dirty());
+        assert!(!buffer.has_conflict());
+    });
+
+    fs.save(
+        "/dir/log.txt".as_ref(),
+        &"three\n".into(),
+        LineEnding::Unix,
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    cx.executor().run_until_parked();
+
+    buffer.update(cx, |buffer, _| {
+        assert!(!buffer.file().unwrap().is_large());
+        assert_eq!(buffer.text(), "three\n");
+    });
+}
+
+#[gpui::test]
+async fn test_git_stage_and_commit(cx: &mut gpui::TestAppContext) {
+    use git::repository::{GitChangeKind, GitStatusEntry, RepoPath};
+
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            ".git": {},
+            "a.txt": "a",
+            "b.txt": "b",
+        }),
+    )
+    .await;
+    let entry = |path: &str, staged, unstaged| GitStatusEntry {
+        path: RepoPath::new(PathBuf::from(path)),
+        staged,
+        unstaged,
+    };
+    fs.with_git_state(Path::new("/dir/.git"), true, |state| {
+        state.changed_files = vec![
+            entry("a.txt", None, Some(GitChangeKind::Modified)),
+            entry("b.txt", None, Some(GitChangeKind::Added)),
+        ];
+    });
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    cx.executor().run_until_parked();
+    let work_directory = project.read_with(cx, |project, cx| {
+        let repositories = project.git_repositories(cx);
+        assert_eq!(repositories.len(), 1);
+        repositories[0].0.clone()
+    });
+
+    project
+        .update(cx, |project, cx| {
+            project.git_stage(
+                work_directory.clone(),
+                vec![RepoPath::new(PathBuf::from("a.txt"))],
+                cx,
+            )
+        })
+        .await
+        .unwrap();
+    let changed_files = project
+        .update(cx, |project, cx| {
+            project.git_changed_files(work_directory.clone(), cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        changed_files,
+        vec![
+            entry("a.txt", Some(GitChangeKind::Modified), None),
+            entry("b.txt", None, Some(GitChangeKind::Added)),
+        ]
+    );
+
+    // A new commit needs a message.
+    let result = project
+        .update(cx, |project, cx| {
+            project.git_commit(work_directory.clone(), String::new(), false, cx)
+        })
+        .await;
+    assert!(result.is_err());
+    project
+        .update(cx, |project, cx| {
+            project.git_commit(work_directory.clone(), "Change a".to_string(), false, cx)
+        })
+        .await
+        .unwrap();
+    // Amending with an empty message keeps the message of the amended commit.
+    project
+        .update(cx, |project, cx| {
+            project.git_commit(work_directory.clone(), String::new(), true, cx)
+        })
+        .await
+        .unwrap();
+    project
+        .update(cx, |project, cx| {
+            project.git_remote_operation(work_directory.clone(), GitRemoteOperation::Push, cx)
+        })
+        .await
+        .unwrap();
+
+    let changed_files = project
+        .update(cx, |project, cx| {
+            project.git_changed_files(work_directory.clone(), cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        changed_files,
+        vec![entry("b.txt", None, Some(GitChangeKind::Added))]
+    );
+    fs.with_git_state(Path::new("/dir/.git"), false, |state| {
+        assert_eq!(state.commit_messages, vec!["Change a".to_string()]);
+        assert_eq!(state.unpushed_commits, 0);
+        assert_eq!(state.remote_operations, vec!["push"]);
+    });
+
+    // Nothing is staged, so there is nothing to commit.
+    let result = project
+        .update(cx, |project, cx| {
+            project.git_commit(work_directory.clone(), "Change b".to_string(), false, cx)
+        })
+        .await;
+    assert!(result.is_err());
+}
+
+#[gpui::test]
+async fn test_git_revision_text(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
 
-    let buffer = project
-        .update(cx, |p, cx| p.open_local_buffer("/dir/a.ts", cx))
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            ".git": {},
+            "a.txt": "a\nb\n",
+            "b.txt": "new",
+        }),
+    )
+    .await;
+    fs.with_git_state(Path::new("/dir/.git"), true, |state| {
+        state.revision_contents.insert(
+            "HEAD".to_string(),
+            [(PathBuf::from("a.txt"), "a\n".to_string())]
+                .into_iter()
+                .collect(),
+        );
+    });
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    cx.executor().run_until_parked();
+    let buffer_a = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/a.txt", cx)
+        })
         .await
         .unwrap();
-
-    let fake_server = fake_language_servers.next().await.unwrap();
-
-    // Language server returns code actions that contain commands, and not edits.
-    let actions = project.update(cx, |project, cx| project.code_actions(&buffer, 0..0, cx));
-    fake_server
-        .handle_request::<lsp::request::CodeActionRequest, _, _>(|_, _| async move {
-            Ok(Some(vec![
-                lsp::CodeActionOrCommand::CodeAction(lsp::CodeAction {
-                    title: "The code action".into(),
-                    data: Some(serde_json::json!({
-                        "command": "_the/command",
-                    })),
-                    ..lsp::CodeAction::default()
-                }),
-                lsp::CodeActionOrCommand::CodeAction(lsp::CodeAction {
-                    title: "two".into(),
-                    ..lsp::CodeAction::default()
-                }),
-            ]))
+    let buffer_b = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/b.txt", cx)
         })
-        .next()
-        .await;
+        .await
+        .unwrap();
 
-    let action = actions.await[0].clone();
-    let apply = project.update(cx, |project, cx| {
-        project.apply_code_action(buffer.clone(), action, true, cx)
-    });
+    let text = project
+        .update(cx, |project, cx| {
+            project.git_revision_text(&buffer_a, "HEAD".to_string(), cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(text.as_deref(), Some("a\n"));
 
-    // Resolving the code action does not populate its edits. In absence of
-    // edits, we must execute the given command.
-    fake_server.handle_request::<lsp::request::CodeActionResolveRequest, _, _>(
-        |mut action, _| async move {
-            if action.data.is_some() {
-                action.command = Some(lsp::Command {
-                    title: "The command".into(),
-                    command: "_the/command".into(),
-                    arguments: Some(vec![json!("the-argument")]),
-                });
-            }
-            Ok(action)
-        },
-    );
+    // The file was added after the revision.
+    let text = project
+        .update(cx, |project, cx| {
+            project.git_revision_text(&buffer_b, "HEAD".to_string(), cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(text, None);
 
-    // While executing the command, the language server sends the editor
-    // a `workspaceEdit` request.
-    fake_server
-        .handle_request::<lsp::request::ExecuteCommand, _, _>({
-            let fake = fake_server.clone();
-            move |params, _| {
-                assert_eq!(params.command, "_the/command");
-                let fake = fake.clone();
-                async move {
-                    fake.server
-                        .request::<lsp::request::ApplyWorkspaceEdit>(
-                            lsp::ApplyWorkspaceEditParams {
-                                label: None,
-                                edit: lsp::WorkspaceEdit {
-                                    changes: Some(
-                                        [(
-                                            lsp::Url::from_file_path("/dir/a.ts").unwrap(),
-                                            vec![lsp::TextEdit {
-                                                range: lsp::Range::new(
-                                                    lsp::Position::new(0, 0),
-                                                    lsp::Position::new(0, 0),
-                                                ),
-                                                new_text: "X".into(),
-                                            }],
-                                        )]
-                                        .into_iter()
-                                        .collect(),
-                                    ),
-                                    ..Default::default()
-                                },
-                            },
-                        )
-                        .await
-                        .unwrap();
-                    Ok(Some(json!(null)))
-                }
-            }
+    let result = project
+        .update(cx, |project, cx| {
+            project.git_revision_text(&buffer_a, "unknown".to_string(), cx)
         })
-        .next()
         .await;
-
-    // Applying the code action returns a project transaction containing the edits
-    // sent by the language server in its `workspaceEdit` request.
-    let transaction = apply.await.unwrap();
-    assert!(transaction.0.contains_key(&buffer));
-    buffer.update(cx, |buffer, cx| {
-        assert_eq!(buffer.text(), "Xa");
-        buffer.undo(cx);
-        assert_eq!(buffer.text(), "a");
-    });
+    assert!(result.is_err());
 }
 
-#[gpui::test(iterations = 10)]
-async fn test_save_file(cx: &mut gpui::TestAppContext) {
+#[gpui::test]
+async fn test_git_file_history(cx: &mut gpui::TestAppContext) {
     init_test(cx);
 
     let fs = FakeFs::new(cx.executor());
     fs.insert_tree(
         "/dir",
         json!({
-            "file1": "the old contents",
+            ".git": {},
+            "src": {
+                "a.txt": "a\nb\n",
+            },
+            "b.txt": "new",
         }),
     )
     .await;
+    let entry = git::history::HistoryEntry {
+        sha: "a060a80a19491ef24816656f02efd6a3abd33540".parse().unwrap(),
+        author: "Joe Schmoe".to_string(),
+        author_mail: "joe.schmoe@example.com".to_string(),
+        author_time: 1709741400,
+        author_tz: "+0100".to_string(),
+        message: "Add a.txt".to_string(),
+    };
+    fs.with_git_state(Path::new("/dir/.git"), true, |state| {
+        state.file_histories.insert(
+            PathBuf::from("src/a.txt"),
+            git::history::FileHistory {
+                entries: vec![entry.clone()],
+                remote_url: Some("git@github.com:zed-industries/zed.git".to_string()),
+            },
+        );
+    });
 
     let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
-    let buffer = project
-        .update(cx, |p, cx| p.open_local_buffer("/dir/file1", cx))
+    cx.executor().run_until_parked();
+    let buffer_a = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/src/a.txt", cx)
+        })
+        .await
+        .unwrap();
+    let buffer_b = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/b.txt", cx)
+        })
         .await
         .unwrap();
-    buffer.update(cx, |buffer, cx| {
-        assert_eq!(buffer.text(), "the old contents");
-        buffer.edit([(0..0, "a line of text.\n".repeat(10 * 1024))], None, cx);
-    });
 
-    project
-        .update(cx, |project, cx| project.save_buffer(buffer.clone(), cx))
+    let history = project
+        .update(cx, |project, cx| {
+            project.git_file_history(&buffer_a, Some(0..1), cx)
+        })
         .await
         .unwrap();
+    assert_eq!(history.entries, vec![entry]);
+    assert_eq!(
+        history.remote_url.as_deref(),
+        Some("git@github.com:zed-industries/zed.git")
+    );
 
-    let new_text = fs.load(Path::new("/dir/file1")).await.unwrap();
-    assert_eq!(new_text, buffer.update(cx, |buffer, _| buffer.text()));
+    let result = project
+        .update(cx, |project, cx| {
+            project.git_file_history(&buffer_b, None, cx)
+        })
+        .await;
+    assert!(result.is_err());
 }
 
 #[gpui::test(iterations = 30)]
@@ -2778,6 +3826,7 @@ async fn test_file_changes_multiple_times_on_disk(cx: &mut gpui::TestAppContext)
         "/dir/file1".as_ref(),
         &"the first contents".into(),
         Default::default(),
+        Default::default(),
     )
     .await
     .unwrap();
@@ -2789,6 +3838,7 @@ async fn test_file_changes_multiple_times_on_disk(cx: &mut gpui::TestAppContext)
         "/dir/file1".as_ref(),
         &"the second contents".into(),
         Default::default(),
+        Default::default(),
     )
     .await
     .unwrap();
@@ -2833,6 +3883,7 @@ async fn test_edit_buffer_while_it_reloads(cx: &mut gpui::TestAppContext) {
         "/dir/file1".as_ref(),
         &"the first contents".into(),
         Default::default(),
+        Default::default(),
     )
     .await
     .unwrap();
@@ -3371,6 +4422,7 @@ async fn test_buffer_file_changes_on_disk(cx: &mut gpui::TestAppContext) {
         "/dir/the-file".as_ref(),
         &new_contents.into(),
         LineEnding::Unix,
+        Default::default(),
     )
     .await
     .unwrap();
@@ -3406,6 +4458,7 @@ async fn test_buffer_file_changes_on_disk(cx: &mut gpui::TestAppContext) {
         "/dir/the-file".as_ref(),
         &"\n\n\nAAAA\naaa\nBB\nbbbbb\n".into(),
         LineEnding::Unix,
+        Default::default(),
     )
     .await
     .unwrap();
@@ -3457,6 +4510,7 @@ async fn test_buffer_line_endings(cx: &mut gpui::TestAppContext) {
         "/dir/file1".as_ref(),
         &"aaa\nb\nc\n".into(),
         LineEnding::Windows,
+        Default::default(),
     )
     .await
     .unwrap();
@@ -3912,6 +4966,136 @@ async fn test_search(cx: &mut gpui::TestAppContext) {
     );
 }
 
+#[gpui::test]
+async fn test_search_multiline_and_fancy_regex(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "one.rs": "fn one() {\n    let a = 1;\n}",
+            "two.rs": "let b = 2;\nlet b = 3;",
+        }),
+    )
+    .await;
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+
+    let regex = |query: &str, multiline: bool, fancy_regex: bool| {
+        SearchQuery::regex(
+            query,
+            false,
+            true,
+            false,
+            multiline,
+            fancy_regex,
+            Vec::new(),
+            Vec::new(),
+        )
+        .unwrap()
+    };
+    assert!(search(&project, regex(r"\{\s+let", false, false), cx)
+        .await
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        search(&project, regex(r"\{\s+let", true, false), cx)
+            .await
+            .unwrap(),
+        HashMap::from_iter([("dir/one.rs".to_string(), vec![9..18])])
+    );
+    assert_eq!(
+        search(&project, regex(r"(?<=let )\w+(?= = 2)", false, true), cx)
+            .await
+            .unwrap(),
+        HashMap::from_iter([("dir/two.rs".to_string(), vec![4..5])])
+    );
+    assert_eq!(
+        search(&project, regex(r"^let (\w+) = \d;\nlet \1", true, true), cx)
+            .await
+            .unwrap(),
+        HashMap::from_iter([("dir/two.rs".to_string(), vec![0..16])])
+    );
+
+    // The lookaround of the replaced match sees the text around it.
+    let query = regex(r"(?<=let )\w+(?= = 2)", false, true).with_replacement("renamed_$0".into());
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/two.rs", cx)
+        })
+        .await
+        .unwrap();
+    buffer.update(cx, |buffer, _| {
+        let snapshot = buffer.snapshot();
+        assert_eq!(
+            query.replacement_for_match(&snapshot, 4..5).as_deref(),
+            Some("renamed_b")
+        );
+        assert_eq!(query.replacement_for_match(&snapshot, 15..16), None);
+    });
+}
+
+#[gpui::test]
+async fn test_structural_search(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "one.rs": "fn one() { foo(1); bar(1); }",
+            "two.rs": "fn two() { foo(bar(2), 2); }",
+            "three.txt": "foo(3)",
+        }),
+    )
+    .await;
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    project.read_with(cx, |project, _| project.languages().add(rust_lang()));
+
+    assert_eq!(
+        search(
+            &project,
+            SearchQuery::structural("foo($A)", false, Vec::new(), Vec::new()).unwrap(),
+            cx
+        )
+        .await
+        .unwrap(),
+        HashMap::from_iter([("dir/one.rs".to_string(), vec![11..17])])
+    );
+    assert_eq!(
+        search(
+            &project,
+            SearchQuery::structural("bar($_)", false, Vec::new(), Vec::new()).unwrap(),
+            cx
+        )
+        .await
+        .unwrap(),
+        HashMap::from_iter([
+            ("dir/one.rs".to_string(), vec![19..25]),
+            ("dir/two.rs".to_string(), vec![15..21])
+        ])
+    );
+    assert_eq!(
+        search(
+            &project,
+            SearchQuery::structural(
+                "(call_expression function: (identifier) @name (#eq? @name \"foo\")) @match",
+                false,
+                Vec::new(),
+                Vec::new()
+            )
+            .unwrap(),
+            cx
+        )
+        .await
+        .unwrap(),
+        HashMap::from_iter([
+            ("dir/one.rs".to_string(), vec![11..17]),
+            ("dir/two.rs".to_string(), vec![11..25])
+        ])
+    );
+}
+
 #[gpui::test]
 async fn test_search_with_inclusions(cx: &mut gpui::TestAppContext) {
     init_test(cx);
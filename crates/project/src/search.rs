@@ -2,7 +2,7 @@ use aho_corasick::{AhoCorasick, AhoCorasickBuilder};
 use anyhow::{Context, Result};
 use client::proto;
 use itertools::Itertools;
-use language::{char_kind, BufferSnapshot};
+use language::{char_kind, BufferSnapshot, Point, StructuralPattern};
 use regex::{Captures, Regex, RegexBuilder};
 use smol::future::yield_now;
 use std::{
@@ -16,6 +16,53 @@ use util::paths::PathMatcher;
 
 static TEXT_REPLACEMENT_SPECIAL_CHARACTERS_REGEX: OnceLock<Regex> = OnceLock::new();
 
+/// A regular expression, compiled either with the `regex` crate, or with `fancy-regex`, which
+/// additionally supports lookaround and backreferences by backtracking.
+#[derive(Clone, Debug)]
+pub enum SearchRegex {
+    Standard(Regex),
+    Fancy(fancy_regex::Regex),
+}
+
+impl SearchRegex {
+    pub fn is_match(&self, text: &str) -> bool {
+        match self {
+            Self::Standard(regex) => regex.is_match(text),
+            Self::Fancy(regex) => regex.is_match(text).unwrap_or(false),
+        }
+    }
+
+    /// Returns the ranges of the matches in the text. The matching stops at the first error of
+    /// the fancy engine, which happens when it exceeds its backtracking limit.
+    fn find_iter<'a>(&'a self, text: &'a str) -> Box<dyn Iterator<Item = Range<usize>> + 'a> {
+        match self {
+            Self::Standard(regex) => Box::new(regex.find_iter(text).map(|mat| mat.range())),
+            Self::Fancy(regex) => Box::new(
+                regex
+                    .find_iter(text)
+                    .map_while(|mat| mat.ok())
+                    .map(|mat| mat.range()),
+            ),
+        }
+    }
+
+    /// Replaces the first match in the text, expanding the `$name` and `${name}` groups of the replacement.
+    pub fn replace<'a>(&self, text: &'a str, replacement: &str) -> Cow<'a, str> {
+        match self {
+            Self::Standard(regex) => regex.replace(text, replacement),
+            Self::Fancy(regex) => regex.replace(text, replacement),
+        }
+    }
+
+    /// Replaces all the matches in the text, like [`Self::replace`] does for the first one.
+    pub fn replace_all<'a>(&self, text: &'a str, replacement: &str) -> Cow<'a, str> {
+        match self {
+            Self::Standard(regex) => regex.replace_all(text, replacement),
+            Self::Fancy(regex) => regex.replace_all(text, replacement),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct SearchInputs {
     query: Arc<str>,
@@ -46,14 +93,24 @@ pub enum SearchQuery {
     },
 
     Regex {
-        regex: Regex,
+        regex: SearchRegex,
         replacement: Option<String>,
+        /// Whether the regex is matched against the whole text of a file, rather than each line.
         multiline: bool,
+        fancy_regex: bool,
         whole_word: bool,
         case_sensitive: bool,
         include_ignored: bool,
         inner: SearchInputs,
     },
+
+    /// A search for syntax nodes, with a Tree-sitter query or a code template with metavariables.
+    Structural {
+        pattern: Arc<StructuralPattern>,
+        replacement: Option<String>,
+        include_ignored: bool,
+        inner: SearchInputs,
+    },
 }
 
 impl SearchQuery {
@@ -84,11 +141,17 @@ impl SearchQuery {
         })
     }
 
+    /// Creates a regex query, which matches across lines, when `multiline` is set or the query
+    /// contains a newline, and uses the `fancy-regex` engine for lookaround and backreferences,
+    /// when `fancy_regex` is set.
+    #[allow(clippy::too_many_arguments)]
     pub fn regex(
         query: impl ToString,
         whole_word: bool,
         case_sensitive: bool,
         include_ignored: bool,
+        multiline: bool,
+        fancy_regex: bool,
         files_to_include: Vec<PathMatcher>,
         files_to_exclude: Vec<PathMatcher>,
     ) -> Result<Self> {
@@ -102,11 +165,27 @@ impl SearchQuery {
             query = word_query
         }
 
-        let multiline = query.contains('\n') || query.contains("\\n");
-        let regex = RegexBuilder::new(&query)
-            .case_insensitive(!case_sensitive)
-            .multi_line(multiline)
-            .build()?;
+        let multiline = multiline || query.contains('\n') || query.contains("\\n");
+        let regex = if fancy_regex {
+            let mut flags = String::new();
+            if !case_sensitive {
+                flags.push('i');
+            }
+            if multiline {
+                flags.push('m');
+            }
+            if !flags.is_empty() {
+                query = format!("(?{flags}){query}");
+            }
+            SearchRegex::Fancy(fancy_regex::Regex::new(&query)?)
+        } else {
+            SearchRegex::Standard(
+                RegexBuilder::new(&query)
+                    .case_insensitive(!case_sensitive)
+                    .multi_line(multiline)
+                    .build()?,
+            )
+        };
         let inner = SearchInputs {
             query: initial_query,
             files_to_exclude,
@@ -116,6 +195,7 @@ impl SearchQuery {
             regex,
             replacement: None,
             multiline,
+            fancy_regex,
             whole_word,
             case_sensitive,
             include_ignored,
@@ -123,13 +203,43 @@ impl SearchQuery {
         })
     }
 
+    pub fn structural(
+        query: impl ToString,
+        include_ignored: bool,
+        files_to_include: Vec<PathMatcher>,
+        files_to_exclude: Vec<PathMatcher>,
+    ) -> Result<Self> {
+        let query = query.to_string();
+        let pattern = StructuralPattern::new(&query)?;
+        let inner = SearchInputs {
+            query: query.into(),
+            files_to_exclude,
+            files_to_include,
+        };
+        Ok(Self::Structural {
+            pattern: Arc::new(pattern),
+            replacement: None,
+            include_ignored,
+            inner,
+        })
+    }
+
     pub fn from_proto(message: proto::SearchProject) -> Result<Self> {
-        if message.regex {
+        if message.structural {
+            Self::structural(
+                message.query,
+                message.include_ignored,
+                deserialize_path_matches(&message.files_to_include)?,
+                deserialize_path_matches(&message.files_to_exclude)?,
+            )
+        } else if message.regex {
             Self::regex(
                 message.query,
                 message.whole_word,
                 message.case_sensitive,
                 message.include_ignored,
+                message.multiline,
+                message.fancy_regex,
                 deserialize_path_matches(&message.files_to_include)?,
                 deserialize_path_matches(&message.files_to_exclude)?,
             )
@@ -153,6 +263,10 @@ impl SearchQuery {
             | Self::Regex {
                 ref mut replacement,
                 ..
+            }
+            | Self::Structural {
+                ref mut replacement,
+                ..
             } => {
                 *replacement = Some(new_replacement);
                 self
@@ -164,6 +278,9 @@ impl SearchQuery {
             project_id,
             query: self.as_str().to_string(),
             regex: self.is_regex(),
+            structural: self.is_structural(),
+            multiline: self.multiline(),
+            fancy_regex: self.fancy_regex(),
             whole_word: self.whole_word(),
             case_sensitive: self.case_sensitive(),
             include_ignored: self.include_ignored(),
@@ -203,26 +320,31 @@ impl SearchQuery {
                     if let Err(err) = reader.read_to_string(&mut text) {
                         Err(err.into())
                     } else {
-                        Ok(regex.find(&text).is_some())
+                        Ok(regex.is_match(&text))
                     }
                 } else {
                     for line in reader.lines() {
                         let line = line?;
-                        if regex.find(&line).is_some() {
+                        if regex.is_match(&line) {
                             return Ok(true);
                         }
                     }
                     Ok(false)
                 }
             }
+            Self::Structural { pattern, .. } => {
+                let mut text = String::new();
+                BufReader::new(stream).read_to_string(&mut text)?;
+                Ok(pattern.may_match(&text))
+            }
         }
     }
     /// Returns the replacement text for this `SearchQuery`.
     pub fn replacement(&self) -> Option<&str> {
         match self {
-            SearchQuery::Text { replacement, .. } | SearchQuery::Regex { replacement, .. } => {
-                replacement.as_deref()
-            }
+            SearchQuery::Text { replacement, .. }
+            | SearchQuery::Regex { replacement, .. }
+            | SearchQuery::Structural { replacement, .. } => replacement.as_deref(),
         }
     }
     /// Replaces search hits if replacement is set. `text` is assumed to be a string that matches this `SearchQuery` exactly, without any leftovers on either side.
@@ -231,22 +353,70 @@ impl SearchQuery {
             SearchQuery::Text { replacement, .. } => replacement.clone().map(Cow::from),
             SearchQuery::Regex {
                 regex, replacement, ..
+            } => replacement
+                .as_ref()
+                .map(|replacement| regex.replace(text, &unescape_replacement(replacement))),
+            // The replacement of a structural match depends on its syntax tree, rather than its text.
+            SearchQuery::Structural { .. } => None,
+        }
+    }
+
+    /// Replaces the search hit at the range of the buffer if replacement is set. Unlike
+    /// [`Self::replacement_for`], this takes the surroundings of the hit into account, which the
+    /// syntax tree of a structural match and the lookaround of a fancy regex depend on.
+    pub fn replacement_for_match(
+        &self,
+        buffer: &BufferSnapshot,
+        range: Range<usize>,
+    ) -> Option<String> {
+        match self {
+            SearchQuery::Structural {
+                pattern,
+                replacement,
+                ..
             } => {
-                if let Some(replacement) = replacement {
-                    let replacement = TEXT_REPLACEMENT_SPECIAL_CHARACTERS_REGEX
-                        .get_or_init(|| Regex::new(r"\\\\|\\n|\\t").unwrap())
-                        .replace_all(replacement, |c: &Captures| {
-                            match c.get(0).unwrap().as_str() {
-                                r"\\" => "\\",
-                                r"\n" => "\n",
-                                r"\t" => "\t",
-                                x => unreachable!("Unexpected escape sequence: {}", x),
-                            }
-                        });
-                    Some(regex.replace(text, replacement))
+                let replacement = replacement.as_ref()?;
+                pattern
+                    .matches(buffer, range.clone())
+                    .into_iter()
+                    .find(|mat| mat.range == range)
+                    .map(|mat| mat.replacement(replacement))
+            }
+            SearchQuery::Regex {
+                regex: SearchRegex::Fancy(regex),
+                replacement,
+                multiline,
+                ..
+            } => {
+                let replacement = replacement.as_ref()?;
+                // Match again within the hit's lines, or the whole buffer, for the lookaround to
+                // see the same text as the search did.
+                let context_range = if *multiline {
+                    0..buffer.len()
                 } else {
-                    None
+                    let start_row = buffer.offset_to_point(range.start).row;
+                    let end_row = buffer.offset_to_point(range.end).row;
+                    buffer.point_to_offset(Point::new(start_row, 0))
+                        ..buffer.point_to_offset(Point::new(end_row, buffer.line_len(end_row)))
+                };
+                let context = buffer
+                    .text_for_range(context_range.clone())
+                    .collect::<String>();
+                let match_range =
+                    range.start - context_range.start..range.end - context_range.start;
+                let captures = regex
+                    .captures_from_pos(&context, match_range.start)
+                    .ok()??;
+                if captures.get(0)?.range() != match_range {
+                    return None;
                 }
+                let mut result = String::new();
+                captures.expand(&unescape_replacement(replacement), &mut result);
+                Some(result)
+            }
+            SearchQuery::Text { .. } | SearchQuery::Regex { .. } => {
+                let text = buffer.text_for_range(range).collect::<String>();
+                self.replacement_for(&text).map(Cow::into_owned)
             }
         }
     }
@@ -309,7 +479,7 @@ impl SearchQuery {
                             yield_now().await;
                         }
 
-                        matches.push(mat.start()..mat.end());
+                        matches.push(mat);
                     }
                 } else {
                     let mut line = String::new();
@@ -322,8 +492,8 @@ impl SearchQuery {
                         for (newline_ix, text) in chunk.split('\n').enumerate() {
                             if newline_ix > 0 {
                                 for mat in regex.find_iter(&line) {
-                                    let start = line_offset + mat.start();
-                                    let end = line_offset + mat.end();
+                                    let start = line_offset + mat.start;
+                                    let end = line_offset + mat.end;
                                     matches.push(start..end);
                                 }
 
@@ -335,6 +505,16 @@ impl SearchQuery {
                     }
                 }
             }
+
+            Self::Structural { pattern, .. } => {
+                let range = range_offset..range_offset + rope.len();
+                matches.extend(
+                    pattern
+                        .matches(buffer, range)
+                        .into_iter()
+                        .map(|mat| mat.range.start - range_offset..mat.range.end - range_offset),
+                );
+            }
         }
 
         matches
@@ -352,6 +532,7 @@ impl SearchQuery {
         match self {
             Self::Text { whole_word, .. } => *whole_word,
             Self::Regex { whole_word, .. } => *whole_word,
+            Self::Structural { .. } => false,
         }
     }
 
@@ -359,6 +540,7 @@ impl SearchQuery {
         match self {
             Self::Text { case_sensitive, .. } => *case_sensitive,
             Self::Regex { case_sensitive, .. } => *case_sensitive,
+            Self::Structural { .. } => false,
         }
     }
 
@@ -370,6 +552,9 @@ impl SearchQuery {
             Self::Regex {
                 include_ignored, ..
             } => *include_ignored,
+            Self::Structural {
+                include_ignored, ..
+            } => *include_ignored,
         }
     }
 
@@ -377,6 +562,32 @@ impl SearchQuery {
         matches!(self, Self::Regex { .. })
     }
 
+    pub fn multiline(&self) -> bool {
+        match self {
+            Self::Regex { multiline, .. } => *multiline,
+            Self::Text { .. } | Self::Structural { .. } => false,
+        }
+    }
+
+    /// The compiled regular expression of a regex query.
+    pub fn search_regex(&self) -> Option<&SearchRegex> {
+        match self {
+            Self::Regex { regex, .. } => Some(regex),
+            Self::Text { .. } | Self::Structural { .. } => None,
+        }
+    }
+
+    pub fn fancy_regex(&self) -> bool {
+        match self {
+            Self::Regex { fancy_regex, .. } => *fancy_regex,
+            Self::Text { .. } | Self::Structural { .. } => false,
+        }
+    }
+
+    pub fn is_structural(&self) -> bool {
+        matches!(self, Self::Structural { .. })
+    }
+
     pub fn files_to_include(&self) -> &[PathMatcher] {
         self.as_inner().files_to_include()
     }
@@ -413,11 +624,27 @@ impl SearchQuery {
     }
     pub fn as_inner(&self) -> &SearchInputs {
         match self {
-            Self::Regex { inner, .. } | Self::Text { inner, .. } => inner,
+            Self::Regex { inner, .. }
+            | Self::Text { inner, .. }
+            | Self::Structural { inner, .. } => inner,
         }
     }
 }
 
+/// Replaces the escape sequences for a backslash, a newline and a tab in a regex replacement.
+fn unescape_replacement(replacement: &str) -> Cow<str> {
+    TEXT_REPLACEMENT_SPECIAL_CHARACTERS_REGEX
+        .get_or_init(|| Regex::new(r"\\\\|\\n|\\t").unwrap())
+        .replace_all(replacement, |c: &Captures| {
+            match c.get(0).unwrap().as_str() {
+                r"\\" => "\\",
+                r"\n" => "\n",
+                r"\t" => "\t",
+                x => unreachable!("Unexpected escape sequence: {}", x),
+            }
+        })
+}
+
 fn deserialize_path_matches(glob_set: &str) -> anyhow::Result<Vec<PathMatcher>> {
     glob_set
         .split(',')
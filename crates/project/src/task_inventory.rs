@@ -6,14 +6,16 @@ use std::{
     sync::Arc,
 };
 
+use anyhow::{bail, Context as _};
 use collections::{btree_map, BTreeMap, VecDeque};
 use gpui::{AppContext, Context, Model, ModelContext};
 use itertools::{Either, Itertools};
 use language::Language;
 use task::{
-    static_source::StaticSource, ResolvedTask, TaskContext, TaskId, TaskTemplate, VariableName,
+    static_source::StaticSource, ResolvedTask, SpawnInTerminal, TaskContext, TaskId, TaskTemplate,
+    TaskVariables, VariableName,
 };
-use util::{post_inc, NumericPrefixWithSuffix};
+use util::{post_inc, NumericPrefixWithSuffix, ResultExt};
 use worktree::WorktreeId;
 
 /// Inventory tracks available tasks for a given project.
@@ -177,11 +179,7 @@ impl Inventory {
         let task_source_kind = language.as_ref().map(|language| TaskSourceKind::Language {
             name: language.name(),
         });
-        let language_tasks = language
-            .and_then(|language| language.context_provider()?.associated_tasks())
-            .into_iter()
-            .flat_map(|tasks| tasks.0.into_iter())
-            .flat_map(|task| Some((task_source_kind.as_ref()?, task)));
+        let available_tasks = self.list_tasks(language, worktree);
 
         let mut lru_score = 0_u32;
         let mut task_usage = self
@@ -206,25 +204,14 @@ impl Inventory {
                 },
             );
         let not_used_score = post_inc(&mut lru_score);
-        let currently_resolved_tasks = self
-            .sources
+        let currently_resolved_tasks = available_tasks
             .iter()
-            .filter(|source| {
-                let source_worktree = source.kind.worktree();
-                worktree.is_none() || source_worktree.is_none() || source_worktree == worktree
-            })
-            .flat_map(|source| {
-                source
-                    .source
-                    .tasks_to_schedule()
-                    .0
-                    .into_iter()
-                    .map(|task| (&source.kind, task))
-            })
-            .chain(language_tasks)
             .filter_map(|(kind, task)| {
-                let id_base = kind.to_id_base();
-                Some((kind, task.resolve_task(&id_base, task_context)?))
+                let task_context = self.with_input_answers(kind, task, task_context);
+                Some((
+                    kind,
+                    resolve_task_with_dependencies(&available_tasks, kind, task, &task_context)?,
+                ))
             })
             .map(|(kind, task)| {
                 let lru_score = task_usage
@@ -290,6 +277,73 @@ impl Inventory {
             })
     }
 
+    /// Resolves the task template given with the [`TaskContext`], along with all tasks it depends on.
+    /// Dependencies are looked up by their labels among the tasks relevant to the worktree and the language given.
+    pub fn resolve_task(
+        &self,
+        task_source_kind: &TaskSourceKind,
+        task: &TaskTemplate,
+        language: Option<Arc<Language>>,
+        worktree: Option<WorktreeId>,
+        task_context: &TaskContext,
+    ) -> Option<ResolvedTask> {
+        let available_tasks = if task.depends_on.is_empty() {
+            Vec::new()
+        } else {
+            self.list_tasks(language, worktree)
+        };
+        let task_context = self.with_input_answers(task_source_kind, task, task_context);
+        resolve_task_with_dependencies(&available_tasks, task_source_kind, task, &task_context)
+    }
+
+    /// Returns the answers to the task template's inputs, that the last scheduled task with the same label
+    /// from the same source got resolved with, if all of the inputs were answered then.
+    pub fn last_input_answers(
+        &self,
+        task_source_kind: &TaskSourceKind,
+        task: &TaskTemplate,
+    ) -> Option<TaskVariables> {
+        if task.inputs.is_empty() {
+            return None;
+        }
+        let (_, last_task) = self
+            .last_scheduled_tasks
+            .iter()
+            .rev()
+            .find(|(kind, resolved)| {
+                kind == task_source_kind && resolved.original_task().label == task.label
+            })?;
+        let answers = last_task.input_answers();
+        task.inputs
+            .iter()
+            .all(|input| answers.get(&input.variable_name()).is_some())
+            .then(|| answers.clone())
+    }
+
+    /// Adds the answers to the task template's inputs, that are missing in the context given:
+    /// the ones remembered from the last time the task was scheduled, or the inputs' defaults.
+    fn with_input_answers(
+        &self,
+        task_source_kind: &TaskSourceKind,
+        task: &TaskTemplate,
+        task_context: &TaskContext,
+    ) -> TaskContext {
+        let mut task_context = task_context.clone();
+        if let Some(answers) = self.last_input_answers(task_source_kind, task) {
+            for input in &task.inputs {
+                let variable = input.variable_name();
+                if task_context.task_variables.get(&variable).is_none() {
+                    if let Some(answer) = answers.get(&variable) {
+                        task_context
+                            .task_variables
+                            .insert(variable, answer.to_owned());
+                    }
+                }
+            }
+        }
+        with_default_input_answers(task, task_context)
+    }
+
     /// Returns the last scheduled task, if any of the sources contains one with the matching id.
     pub fn last_scheduled_task(&self) -> Option<(TaskSourceKind, ResolvedTask)> {
         self.last_scheduled_tasks.back().cloned()
@@ -315,6 +369,82 @@ impl Inventory {
     }
 }
 
+fn resolve_task_with_dependencies(
+    available_tasks: &[(TaskSourceKind, TaskTemplate)],
+    task_source_kind: &TaskSourceKind,
+    task: &TaskTemplate,
+    task_context: &TaskContext,
+) -> Option<ResolvedTask> {
+    let mut resolved_task = task.resolve_task(&task_source_kind.to_id_base(), task_context)?;
+    if !task.depends_on.is_empty() {
+        let dependencies = resolve_dependencies(
+            available_tasks,
+            task_source_kind,
+            task,
+            task_context,
+            &mut vec![task.label.as_str()],
+        )
+        .with_context(|| format!("resolving dependencies of task `{}`", task.label))
+        .log_err()?;
+        resolved_task.resolved.as_mut()?.dependencies = dependencies;
+    }
+    Some(resolved_task)
+}
+
+/// Resolves the tasks the given one depends on, recursively.
+/// A dependency with the same label from the same source as its dependent task is preferred to the others.
+fn resolve_dependencies<'a>(
+    available_tasks: &'a [(TaskSourceKind, TaskTemplate)],
+    task_source_kind: &TaskSourceKind,
+    task: &TaskTemplate,
+    task_context: &TaskContext,
+    dependent_labels: &mut Vec<&'a str>,
+) -> anyhow::Result<Vec<SpawnInTerminal>> {
+    let mut dependencies = Vec::with_capacity(task.depends_on.len());
+    for label in &task.depends_on {
+        let (dependency_source_kind, dependency) = available_tasks
+            .iter()
+            .filter(|(_, dependency)| &dependency.label == label)
+            .min_by_key(|(kind, _)| (kind != task_source_kind, task_source_kind_preference(kind)))
+            .with_context(|| format!("unknown task `{label}`"))?;
+        if dependent_labels.contains(&dependency.label.as_str()) {
+            bail!(
+                "dependency cycle: {} -> {label}",
+                dependent_labels.join(" -> ")
+            );
+        }
+        let dependency_context = with_default_input_answers(dependency, task_context.clone());
+        let mut resolved = dependency
+            .resolve_task(&dependency_source_kind.to_id_base(), &dependency_context)
+            .and_then(|dependency| dependency.resolved)
+            .with_context(|| format!("cannot resolve task `{label}`"))?;
+        dependent_labels.push(&dependency.label);
+        resolved.dependencies = resolve_dependencies(
+            available_tasks,
+            dependency_source_kind,
+            dependency,
+            task_context,
+            dependent_labels,
+        )?;
+        dependent_labels.pop();
+        dependencies.push(resolved);
+    }
+    Ok(dependencies)
+}
+
+/// Answers the task template's inputs, that are not answered in the context given, with their defaults.
+fn with_default_input_answers(task: &TaskTemplate, mut task_context: TaskContext) -> TaskContext {
+    for input in &task.inputs {
+        let variable = input.variable_name();
+        if task_context.task_variables.get(&variable).is_none() {
+            task_context
+                .task_variables
+                .insert(variable, input.default_answer());
+        }
+    }
+    task_context
+}
+
 fn task_lru_comparator(
     (kind_a, task_a, lru_score_a): &(TaskSourceKind, ResolvedTask, u32),
     (kind_b, task_b, lru_score_b): &(TaskSourceKind, ResolvedTask, u32),
@@ -388,6 +518,13 @@ mod test_inventory {
                 })
                 .collect(),
         );
+        static_template_source(tasks, cx)
+    }
+
+    pub(super) fn static_template_source(
+        tasks: TaskTemplates,
+        cx: &mut AppContext,
+    ) -> StaticSource {
         let (tx, rx) = futures::channel::mpsc::unbounded();
         let file = TrackedFile::new(rx, cx);
         tx.unbounded_send(serde_json::to_string(&tasks).unwrap())
@@ -466,6 +603,7 @@ mod test_inventory {
 #[cfg(test)]
 mod tests {
     use gpui::TestAppContext;
+    use task::TaskTemplates;
 
     use super::test_inventory::*;
     use super::*;
@@ -762,4 +900,84 @@ mod tests {
                 .collect::<Vec<_>>(),
         );
     }
+
+    #[gpui::test]
+    fn test_resolving_task_dependencies(cx: &mut TestAppContext) {
+        let inventory = cx.update(Inventory::new);
+        let task = |label: &str, command: &str, depends_on: &[&str]| TaskTemplate {
+            label: label.to_string(),
+            command: command.to_string(),
+            depends_on: depends_on.iter().map(|label| label.to_string()).collect(),
+            ..TaskTemplate::default()
+        };
+        inventory.update(cx, |inventory, cx| {
+            inventory.add_source(
+                TaskSourceKind::UserInput,
+                static_template_source(
+                    TaskTemplates(vec![
+                        task("build", "cargo build", &[]),
+                        task("lint", "cargo clippy", &[]),
+                        task("test", "cargo test", &["build"]),
+                        task("check", "", &["lint", "test"]),
+                        task("missing", "", &["deploy"]),
+                        task("ping", "echo ping", &["pong"]),
+                        task("pong", "echo pong", &["ping"]),
+                    ]),
+                    cx,
+                ),
+                cx,
+            );
+        });
+        cx.run_until_parked();
+
+        inventory.update(cx, |inventory, _| {
+            let (_, current) =
+                inventory.used_and_current_resolved_tasks(None, None, &TaskContext::default());
+            let check = current
+                .iter()
+                .find(|(_, task)| task.resolved_label == "check")
+                .and_then(|(_, task)| task.resolved.clone())
+                .expect("compound task should be resolved");
+            assert!(check.is_compound());
+            assert_eq!(
+                check
+                    .dependencies
+                    .iter()
+                    .map(|dependency| (
+                        dependency.label.as_str(),
+                        dependency
+                            .dependencies
+                            .iter()
+                            .map(|dependency| dependency.label.as_str())
+                            .collect::<Vec<_>>()
+                    ))
+                    .collect::<Vec<_>>(),
+                vec![("lint", vec![]), ("test", vec!["build"])],
+            );
+
+            let current_labels = current
+                .iter()
+                .map(|(_, task)| task.resolved_label.as_str())
+                .sorted()
+                .collect::<Vec<_>>();
+            assert_eq!(
+                current_labels,
+                vec!["build", "check", "lint", "test"],
+                "Tasks with unknown or cyclic dependencies should not be resolved"
+            );
+
+            let test = inventory
+                .resolve_task(
+                    &TaskSourceKind::UserInput,
+                    &task("test", "cargo test", &["build"]),
+                    None,
+                    None,
+                    &TaskContext::default(),
+                )
+                .and_then(|task| task.resolved)
+                .unwrap();
+            assert_eq!(test.dependencies.len(), 1);
+            assert_eq!(test.dependencies[0].command, "cargo build");
+        });
+    }
 }
@@ -1,21 +1,32 @@
 use crate::Project;
-use collections::HashMap;
-use gpui::{AnyWindowHandle, Context, Entity, Model, ModelContext, WeakModel};
+use collections::{HashMap, HashSet};
+use fs::normalize_path;
+use gpui::{AnyWindowHandle, AppContext, Context, Entity, Model, ModelContext, WeakModel};
+use language::{Diagnostic, DiagnosticEntry, PointUtf16, Unclipped};
+use lsp::{DiagnosticSeverity, LanguageServerId};
 use settings::{Settings, SettingsLocation};
 use smol::channel::bounded;
 use std::path::{Path, PathBuf};
-use task::SpawnInTerminal;
+use task::{Problem, ProblemSeverity, SpawnInTerminal};
 use terminal::{
     terminal_settings::{self, Shell, TerminalSettings, VenvSettingsContent},
-    TaskState, TaskStatus, Terminal, TerminalBuilder,
+    BackgroundTaskState, TaskOutputScanner, TaskState, TaskStatus, Terminal, TerminalBuilder,
 };
-use util::ResultExt;
+use util::{post_inc, ResultExt};
 
 // #[cfg(target_os = "macos")]
 // use std::os::unix::ffi::OsStrExt;
 
 pub struct Terminals {
     pub(crate) local_handles: Vec<WeakModel<terminal::Terminal>>,
+    /// Diagnostics, reported by the problem matchers of the tasks, by the task full labels.
+    pub(crate) task_diagnostics: HashMap<String, TaskDiagnostics>,
+}
+
+pub(crate) struct TaskDiagnostics {
+    /// Task diagnostics are stored the same way the language server ones are, under an id that no server uses.
+    server_id: LanguageServerId,
+    entries: HashMap<PathBuf, Vec<DiagnosticEntry<Unclipped<PointUtf16>>>>,
 }
 
 impl Project {
@@ -23,6 +34,7 @@ impl Project {
         &mut self,
         working_directory: Option<PathBuf>,
         spawn_task: Option<SpawnInTerminal>,
+        restored_scrollback: Option<String>,
         window: AnyWindowHandle,
         cx: &mut ModelContext<Self>,
     ) -> anyhow::Result<Model<Terminal>> {
@@ -61,6 +73,16 @@ impl Project {
             .as_deref()
             .unwrap_or_else(|| Path::new(""));
 
+        let task_problems_source = spawn_task.as_ref().map(|spawn_task| {
+            (
+                spawn_task.full_label.clone(),
+                spawn_task.cwd.clone().or_else(|| working_directory.clone()),
+            )
+        });
+        if let Some((full_label, _)) = &task_problems_source {
+            self.clear_task_diagnostics(full_label, cx);
+        }
+
         let (spawn_task, shell) = if let Some(spawn_task) = spawn_task {
             log::debug!("Spawning task: {spawn_task:?}");
             env.extend(spawn_task.env);
@@ -76,6 +98,13 @@ impl Project {
                     command_label: spawn_task.command_label,
                     status: TaskStatus::Running,
                     completion_rx,
+                    output_scanner: match &spawn_task.background {
+                        Some(_) => None,
+                        None => TaskOutputScanner::new(&spawn_task.problem_matchers),
+                    },
+                    background: spawn_task.background.as_ref().map(|background| {
+                        BackgroundTaskState::new(background, &spawn_task.problem_matchers)
+                    }),
                 }),
                 Shell::WithArguments {
                     program: spawn_task.command,
@@ -94,6 +123,8 @@ impl Project {
             Some(settings.blinking.clone()),
             settings.alternate_scroll,
             settings.max_scroll_history_lines,
+            settings.shell_integration && is_terminal,
+            restored_scrollback.as_deref(),
             window,
             completion_tx,
         )
@@ -103,6 +134,7 @@ impl Project {
             self.terminals
                 .local_handles
                 .push(terminal_handle.downgrade());
+            cx.notify();
 
             let id = terminal_handle.entity_id();
             cx.observe_release(&terminal_handle, move |project, _terminal, cx| {
@@ -118,6 +150,20 @@ impl Project {
             })
             .detach();
 
+            if let Some((full_label, cwd)) = task_problems_source {
+                cx.subscribe(&terminal_handle, move |project, _, event, cx| match event {
+                    terminal::Event::ProblemsFound(problems) => {
+                        project.report_task_problems(&full_label, cwd.as_deref(), problems, cx);
+                    }
+                    terminal::Event::ProblemsOutdated => {
+                        project.clear_task_diagnostics(&full_label, cx);
+                    }
+                    terminal::Event::TaskStatusChanged => cx.notify(),
+                    _ => {}
+                })
+                .detach();
+            }
+
             // if the terminal is not a task, activate full Python virtual environment
             if is_terminal {
                 if let Some(python_settings) = &python_settings.as_option() {
@@ -229,6 +275,143 @@ impl Project {
     pub fn local_terminal_handles(&self) -> &Vec<WeakModel<terminal::Terminal>> {
         &self.terminals.local_handles
     }
+
+    /// Removes all diagnostics, reported by the previous runs of the task with the given label.
+    fn clear_task_diagnostics(&mut self, full_label: &str, cx: &mut ModelContext<Self>) {
+        let Some(task_diagnostics) = self.terminals.task_diagnostics.get_mut(full_label) else {
+            return;
+        };
+        let server_id = task_diagnostics.server_id;
+        let paths = task_diagnostics
+            .entries
+            .drain()
+            .map(|(path, _)| path)
+            .collect::<Vec<_>>();
+        for path in paths {
+            self.update_diagnostic_entries(server_id, path, None, Vec::new(), cx)
+                .log_err();
+        }
+    }
+
+    fn report_task_problems(
+        &mut self,
+        full_label: &str,
+        cwd: Option<&Path>,
+        problems: &[Problem],
+        cx: &mut ModelContext<Self>,
+    ) {
+        let mut new_entries = Vec::new();
+        for problem in problems {
+            let Some(abs_path) = self.resolve_problem_path(&problem.path, cwd, cx) else {
+                log::debug!(
+                    "Skipping a problem of task `{full_label}` outside of the project: {:?}",
+                    problem.path
+                );
+                continue;
+            };
+            let row = problem.line.saturating_sub(1);
+            let start = PointUtf16::new(
+                row,
+                problem.column.map_or(0, |column| column.saturating_sub(1)),
+            );
+            let end = match problem.end_column {
+                Some(end_column) => PointUtf16::new(
+                    problem.end_line.map_or(row, |line| line.saturating_sub(1)),
+                    end_column.saturating_sub(1),
+                ),
+                None => start,
+            };
+            new_entries.push((
+                abs_path,
+                DiagnosticEntry {
+                    range: Unclipped(start)..Unclipped(end.max(start)),
+                    diagnostic: Diagnostic {
+                        source: Some(
+                            problem
+                                .source
+                                .clone()
+                                .unwrap_or_else(|| full_label.to_string()),
+                        ),
+                        code: problem.code.clone(),
+                        severity: match problem.severity {
+                            ProblemSeverity::Error => DiagnosticSeverity::ERROR,
+                            ProblemSeverity::Warning => DiagnosticSeverity::WARNING,
+                            ProblemSeverity::Info => DiagnosticSeverity::INFORMATION,
+                            ProblemSeverity::Hint => DiagnosticSeverity::HINT,
+                        },
+                        message: problem.message.clone(),
+                        group_id: post_inc(&mut self.next_diagnostic_group_id),
+                        is_primary: true,
+                        is_disk_based: true,
+                        is_unnecessary: false,
+                    },
+                },
+            ));
+        }
+        if new_entries.is_empty() {
+            return;
+        }
+
+        let languages = self.languages.clone();
+        let task_diagnostics = self
+            .terminals
+            .task_diagnostics
+            .entry(full_label.to_string())
+            .or_insert_with(|| TaskDiagnostics {
+                server_id: languages.next_language_server_id(),
+                entries: HashMap::default(),
+            });
+        let server_id = task_diagnostics.server_id;
+        let mut updated_paths = HashSet::default();
+        for (abs_path, entry) in new_entries {
+            task_diagnostics
+                .entries
+                .entry(abs_path.clone())
+                .or_default()
+                .push(entry);
+            updated_paths.insert(abs_path);
+        }
+        let updates = updated_paths
+            .into_iter()
+            .filter_map(|abs_path| {
+                let entries = task_diagnostics.entries.get(&abs_path)?.clone();
+                Some((abs_path, entries))
+            })
+            .collect::<Vec<_>>();
+        for (abs_path, entries) in updates {
+            self.update_diagnostic_entries(server_id, abs_path, None, entries, cx)
+                .log_err();
+        }
+    }
+
+    /// Finds a project file for the path, printed by a task: relative paths are looked up in the task's
+    /// working directory first, and in the visible worktrees' roots after that.
+    fn resolve_problem_path(
+        &self,
+        path: &Path,
+        cwd: Option<&Path>,
+        cx: &AppContext,
+    ) -> Option<PathBuf> {
+        let candidates = if path.is_absolute() {
+            vec![path.to_path_buf()]
+        } else {
+            cwd.map(|cwd| cwd.join(path))
+                .into_iter()
+                .chain(self.visible_worktrees(cx).filter_map(|worktree| {
+                    Some(worktree.read(cx).as_local()?.abs_path().join(path))
+                }))
+                .collect()
+        };
+        candidates
+            .into_iter()
+            .map(|candidate| normalize_path(&candidate))
+            .find(|abs_path| {
+                self.find_local_worktree(abs_path, cx)
+                    .map_or(false, |(worktree, path)| {
+                        worktree.read(cx).entry_for_path(path).is_some()
+                    })
+            })
+    }
 }
 
 // TODO: Add a few tests for adding and removing terminal tabs
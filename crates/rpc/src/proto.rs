@@ -185,27 +185,44 @@ messages!(
     (GetChannelMessagesResponse, Background),
     (GetCodeActions, Background),
     (GetCodeActionsResponse, Background),
+    (GetCodeLens, Background),
+    (GetCodeLensResponse, Background),
     (GetCompletions, Background),
     (GetCompletionsResponse, Background),
     (GetDefinition, Background),
     (GetDefinitionResponse, Background),
     (GetDocumentHighlights, Background),
     (GetDocumentHighlightsResponse, Background),
+    (GetDocumentLinks, Background),
+    (GetDocumentLinksResponse, Background),
+    (GetFoldingRanges, Background),
+    (GetFoldingRangesResponse, Background),
+    (GetHierarchyItemsResponse, Background),
     (GetHover, Background),
     (GetHoverResponse, Background),
+    (GetLinkedEditingRanges, Background),
+    (GetLinkedEditingRangesResponse, Background),
     (GetNotifications, Foreground),
     (GetNotificationsResponse, Foreground),
+    (GetOutgoingCalls, Background),
     (GetPrivateUserInfo, Foreground),
     (GetPrivateUserInfoResponse, Foreground),
     (GetProjectSymbols, Background),
     (GetProjectSymbolsResponse, Background),
     (GetReferences, Background),
     (GetReferencesResponse, Background),
+    (GetSelectionRanges, Background),
+    (GetSelectionRangesResponse, Background),
+    (GetSemanticTokens, Background),
+    (GetSemanticTokensResponse, Background),
+    (GetSubtypes, Background),
     (GetSupermavenApiKey, Background),
     (GetSupermavenApiKeyResponse, Background),
+    (GetSupertypes, Background),
     (GetTypeDefinition, Background),
     (GetTypeDefinitionResponse, Background),
     (GetImplementation, Background),
+    (GetIncomingCalls, Background),
     (GetImplementationResponse, Background),
     (GetUsers, Foreground),
     (Hello, Foreground),
@@ -240,10 +257,14 @@ messages!(
     (PerformRename, Background),
     (PerformRenameResponse, Background),
     (Ping, Foreground),
+    (PrepareCallHierarchy, Background),
     (PrepareRename, Background),
     (PrepareRenameResponse, Background),
+    (PrepareTypeHierarchy, Background),
     (ProjectEntryResponse, Foreground),
+    (RefreshCodeLens, Foreground),
     (RefreshInlayHints, Foreground),
+    (RefreshSemanticTokens, Foreground),
     (RejoinChannelBuffers, Foreground),
     (RejoinChannelBuffersResponse, Foreground),
     (RejoinRoom, Foreground),
@@ -261,6 +282,8 @@ messages!(
     (RequestContact, Foreground),
     (ResolveCompletionDocumentation, Background),
     (ResolveCompletionDocumentationResponse, Background),
+    (ResolveDocumentLink, Background),
+    (ResolveDocumentLinkResponse, Background),
     (ResolveInlayHint, Background),
     (ResolveInlayHintResponse, Background),
     (RespondToChannelInvite, Foreground),
@@ -305,6 +328,17 @@ messages!(
     (SetRoomParticipantRole, Foreground),
     (BlameBuffer, Foreground),
     (BlameBufferResponse, Foreground),
+    (GitChangedFiles, Background),
+    (GitChangedFilesResponse, Background),
+    (GitStage, Background),
+    (GitUnstage, Background),
+    (GitSetIndexText, Background),
+    (GitCommit, Background),
+    (GitRemoteOperation, Background),
+    (GitRevisionText, Background),
+    (GitRevisionTextResponse, Background),
+    (GitFileHistory, Background),
+    (GitFileHistoryResponse, Background),
     (CreateDevServerProject, Background),
     (CreateDevServerProjectResponse, Foreground),
     (CreateDevServer, Foreground),
@@ -356,16 +390,26 @@ request_messages!(
     (GetChannelMessages, GetChannelMessagesResponse),
     (GetChannelMessagesById, GetChannelMessagesResponse),
     (GetCodeActions, GetCodeActionsResponse),
+    (GetCodeLens, GetCodeLensResponse),
     (GetCompletions, GetCompletionsResponse),
     (GetDefinition, GetDefinitionResponse),
     (GetImplementation, GetImplementationResponse),
+    (GetIncomingCalls, GetHierarchyItemsResponse),
+    (GetLinkedEditingRanges, GetLinkedEditingRangesResponse),
     (GetDocumentHighlights, GetDocumentHighlightsResponse),
+    (GetDocumentLinks, GetDocumentLinksResponse),
+    (GetFoldingRanges, GetFoldingRangesResponse),
     (GetHover, GetHoverResponse),
     (GetNotifications, GetNotificationsResponse),
+    (GetOutgoingCalls, GetHierarchyItemsResponse),
     (GetPrivateUserInfo, GetPrivateUserInfoResponse),
     (GetProjectSymbols, GetProjectSymbolsResponse),
     (GetReferences, GetReferencesResponse),
+    (GetSelectionRanges, GetSelectionRangesResponse),
+    (GetSemanticTokens, GetSemanticTokensResponse),
+    (GetSubtypes, GetHierarchyItemsResponse),
     (GetSupermavenApiKey, GetSupermavenApiKeyResponse),
+    (GetSupertypes, GetHierarchyItemsResponse),
     (GetTypeDefinition, GetTypeDefinitionResponse),
     (GetUsers, UsersResponse),
     (IncomingCall, Ack),
@@ -388,8 +432,12 @@ request_messages!(
     (OpenNewBuffer, OpenBufferResponse),
     (PerformRename, PerformRenameResponse),
     (Ping, Ack),
+    (PrepareCallHierarchy, GetHierarchyItemsResponse),
     (PrepareRename, PrepareRenameResponse),
+    (PrepareTypeHierarchy, GetHierarchyItemsResponse),
+    (RefreshCodeLens, Ack),
     (RefreshInlayHints, Ack),
+    (RefreshSemanticTokens, Ack),
     (RejoinChannelBuffers, RejoinChannelBuffersResponse),
     (RejoinRoom, RejoinRoomResponse),
     (ReloadBuffers, ReloadBuffersResponse),
@@ -404,6 +452,7 @@ request_messages!(
         ResolveCompletionDocumentation,
         ResolveCompletionDocumentationResponse
     ),
+    (ResolveDocumentLink, ResolveDocumentLinkResponse),
     (ResolveInlayHint, ResolveInlayHintResponse),
     (RespondToChannelInvite, Ack),
     (RespondToContactRequest, Ack),
@@ -422,6 +471,14 @@ request_messages!(
     (LspExtExpandMacro, LspExtExpandMacroResponse),
     (SetRoomParticipantRole, Ack),
     (BlameBuffer, BlameBufferResponse),
+    (GitChangedFiles, GitChangedFilesResponse),
+    (GitStage, Ack),
+    (GitUnstage, Ack),
+    (GitSetIndexText, Ack),
+    (GitCommit, Ack),
+    (GitRemoteOperation, Ack),
+    (GitRevisionText, GitRevisionTextResponse),
+    (GitFileHistory, GitFileHistoryResponse),
     (CreateDevServerProject, CreateDevServerProjectResponse),
     (CreateDevServer, CreateDevServerResponse),
     (ShutdownDevServer, Ack),
@@ -452,14 +509,32 @@ entity_messages!(
     ExpandProjectEntry,
     FormatBuffers,
     GetCodeActions,
+    GetCodeLens,
     GetCompletions,
     GetDefinition,
     GetImplementation,
     GetDocumentHighlights,
+    GetDocumentLinks,
+    GetFoldingRanges,
     GetHover,
+    GetIncomingCalls,
+    GetLinkedEditingRanges,
+    GetOutgoingCalls,
     GetProjectSymbols,
     GetReferences,
+    GetSelectionRanges,
+    GetSemanticTokens,
+    GetSubtypes,
+    GetSupertypes,
     GetTypeDefinition,
+    GitChangedFiles,
+    GitCommit,
+    GitFileHistory,
+    GitRemoteOperation,
+    GitRevisionText,
+    GitSetIndexText,
+    GitStage,
+    GitUnstage,
     InlayHints,
     JoinProject,
     LeaveProject,
@@ -470,12 +545,17 @@ entity_messages!(
     OpenBufferByPath,
     OpenBufferForSymbol,
     PerformRename,
+    PrepareCallHierarchy,
     PrepareRename,
+    PrepareTypeHierarchy,
+    RefreshCodeLens,
     RefreshInlayHints,
+    RefreshSemanticTokens,
     ReloadBuffers,
     RemoveProjectCollaborator,
     RenameProjectEntry,
     ResolveCompletionDocumentation,
+    ResolveDocumentLink,
     ResolveInlayHint,
     SaveBuffer,
     SearchProject,
@@ -3,7 +3,8 @@ mod registrar;
 use crate::{
     search_bar::render_nav_button, FocusSearch, NextHistoryQuery, PreviousHistoryQuery, ReplaceAll,
     ReplaceNext, SearchOptions, SelectAllMatches, SelectNextMatch, SelectPrevMatch,
-    ToggleCaseSensitive, ToggleRegex, ToggleReplace, ToggleWholeWord,
+    ToggleCaseSensitive, ToggleFancyRegex, ToggleMultiline, ToggleRegex, ToggleReplace,
+    ToggleStructural, ToggleWholeWord,
 };
 use any_vec::AnyVec;
 use collections::HashMap;
@@ -238,6 +239,30 @@ impl Render for BufferSearchBar {
                                 cx.listener(|this, _, cx| this.toggle_regex(&ToggleRegex, cx)),
                             )
                         }))
+                        .children(supported_options.multiline.then(|| {
+                            self.render_search_option_button(
+                                SearchOptions::MULTILINE,
+                                cx.listener(|this, _, cx| {
+                                    this.toggle_multiline(&ToggleMultiline, cx)
+                                }),
+                            )
+                        }))
+                        .children(supported_options.fancy_regex.then(|| {
+                            self.render_search_option_button(
+                                SearchOptions::FANCY_REGEX,
+                                cx.listener(|this, _, cx| {
+                                    this.toggle_fancy_regex(&ToggleFancyRegex, cx)
+                                }),
+                            )
+                        }))
+                        .children(supported_options.structural.then(|| {
+                            self.render_search_option_button(
+                                SearchOptions::STRUCTURAL,
+                                cx.listener(|this, _, cx| {
+                                    this.toggle_structural(&ToggleStructural, cx)
+                                }),
+                            )
+                        }))
                     }),
             )
             .when(supported_options.replacement, |this| {
@@ -363,6 +388,15 @@ impl Render for BufferSearchBar {
             .when(self.supported_options().regex, |this| {
                 this.on_action(cx.listener(Self::toggle_regex))
             })
+            .when(self.supported_options().multiline, |this| {
+                this.on_action(cx.listener(Self::toggle_multiline))
+            })
+            .when(self.supported_options().fancy_regex, |this| {
+                this.on_action(cx.listener(Self::toggle_fancy_regex))
+            })
+            .when(self.supported_options().structural, |this| {
+                this.on_action(cx.listener(Self::toggle_structural))
+            })
             .gap_2()
             .child(
                 h_flex()
@@ -684,12 +718,16 @@ impl BufferSearchBar {
     }
 
     fn toggle_search_option(&mut self, search_option: SearchOptions, cx: &mut ViewContext<Self>) {
-        self.search_options.toggle(search_option);
+        self.search_options.toggle_option(search_option);
         self.default_options = self.search_options;
         let _ = self.update_matches(cx);
         cx.notify();
     }
 
+    pub fn search_options(&self) -> SearchOptions {
+        self.search_options
+    }
+
     pub fn enable_search_option(
         &mut self,
         search_option: SearchOptions,
@@ -831,6 +869,18 @@ impl BufferSearchBar {
         self.toggle_search_option(SearchOptions::REGEX, cx)
     }
 
+    fn toggle_multiline(&mut self, _: &ToggleMultiline, cx: &mut ViewContext<Self>) {
+        self.toggle_search_option(SearchOptions::MULTILINE, cx)
+    }
+
+    fn toggle_fancy_regex(&mut self, _: &ToggleFancyRegex, cx: &mut ViewContext<Self>) {
+        self.toggle_search_option(SearchOptions::FANCY_REGEX, cx)
+    }
+
+    fn toggle_structural(&mut self, _: &ToggleStructural, cx: &mut ViewContext<Self>) {
+        self.toggle_search_option(SearchOptions::STRUCTURAL, cx)
+    }
+
     fn clear_active_searchable_item_matches(&mut self, cx: &mut WindowContext) {
         if let Some(active_searchable_item) = self.active_searchable_item.as_ref() {
             self.active_match_index = None;
@@ -870,12 +920,24 @@ impl BufferSearchBar {
                 let _ = done_tx.send(());
                 cx.notify();
             } else {
-                let query: Arc<_> = if self.search_options.contains(SearchOptions::REGEX) {
+                let query: Arc<_> = if self.search_options.contains(SearchOptions::STRUCTURAL) {
+                    match SearchQuery::structural(query, false, Vec::new(), Vec::new()) {
+                        Ok(query) => query.with_replacement(self.replacement(cx)),
+                        Err(_) => {
+                            self.query_contains_error = true;
+                            self.clear_active_searchable_item_matches(cx);
+                            cx.notify();
+                            return done_rx;
+                        }
+                    }
+                } else if self.search_options.contains(SearchOptions::REGEX) {
                     match SearchQuery::regex(
                         query,
                         self.search_options.contains(SearchOptions::WHOLE_WORD),
                         self.search_options.contains(SearchOptions::CASE_SENSITIVE),
                         false,
+                        self.search_options.contains(SearchOptions::MULTILINE),
+                        self.search_options.contains(SearchOptions::FANCY_REGEX),
                         Vec::new(),
                         Vec::new(),
                     ) {
@@ -0,0 +1,110 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use db::{
+    define_connection, query,
+    sqlez::{
+        bindable::{Bind, Column, StaticColumnCount},
+        statement::Statement,
+    },
+    sqlez_macros::sql,
+};
+use workspace::{ItemId, WorkspaceDb, WorkspaceId};
+
+use crate::{search_file::SavedSearch, SearchOptions};
+
+define_connection! {
+    pub static ref SEARCH_DB: SearchDb<WorkspaceDb> =
+        &[sql!(
+            CREATE TABLE project_searches (
+                workspace_id INTEGER,
+                item_id INTEGER,
+                query TEXT NOT NULL,
+                options INTEGER NOT NULL,
+                included_files TEXT NOT NULL,
+                excluded_files TEXT NOT NULL,
+                context_lines INTEGER NOT NULL,
+                search_file_path BLOB,
+                PRIMARY KEY(workspace_id, item_id),
+                FOREIGN KEY(workspace_id) REFERENCES workspaces(workspace_id)
+                ON DELETE CASCADE
+            ) STRICT;
+        )];
+}
+
+impl StaticColumnCount for SavedSearch {
+    fn column_count() -> usize {
+        5
+    }
+}
+
+impl Bind for SavedSearch {
+    fn bind(&self, statement: &Statement, start_index: i32) -> Result<i32> {
+        let next_index = statement.bind(&self.query, start_index)?;
+        let next_index = statement.bind(&u32::from(self.options.bits()), next_index)?;
+        let next_index = statement.bind(&self.included_files, next_index)?;
+        let next_index = statement.bind(&self.excluded_files, next_index)?;
+        statement.bind(&self.context_lines, next_index)
+    }
+}
+
+impl Column for SavedSearch {
+    fn column(statement: &mut Statement, start_index: i32) -> Result<(Self, i32)> {
+        let (query, next_index) = String::column(statement, start_index)?;
+        let (options, next_index) = u32::column(statement, next_index)?;
+        let (included_files, next_index) = String::column(statement, next_index)?;
+        let (excluded_files, next_index) = String::column(statement, next_index)?;
+        let (context_lines, next_index) = u32::column(statement, next_index)?;
+        Ok((
+            SavedSearch {
+                query,
+                options: SearchOptions::from_bits_truncate(options as u8),
+                included_files,
+                excluded_files,
+                context_lines,
+            },
+            next_index,
+        ))
+    }
+}
+
+impl SearchDb {
+    query! {
+        pub async fn save_search(
+            item_id: ItemId,
+            workspace_id: WorkspaceId,
+            search: SavedSearch,
+            search_file_path: Option<PathBuf>
+        ) -> Result<()> {
+            INSERT INTO project_searches(
+                item_id,
+                workspace_id,
+                query,
+                options,
+                included_files,
+                excluded_files,
+                context_lines,
+                search_file_path
+            )
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            ON CONFLICT DO UPDATE SET
+                query = ?3,
+                options = ?4,
+                included_files = ?5,
+                excluded_files = ?6,
+                context_lines = ?7,
+                search_file_path = ?8
+        }
+    }
+
+    query! {
+        pub fn get_search(
+            item_id: ItemId,
+            workspace_id: WorkspaceId
+        ) -> Result<Option<(SavedSearch, Option<PathBuf>)>> {
+            SELECT query, options, included_files, excluded_files, context_lines, search_file_path
+            FROM project_searches
+            WHERE item_id = ? AND workspace_id = ?
+        }
+    }
+}
@@ -1,7 +1,9 @@
 use crate::{
+    persistence::SEARCH_DB,
+    search_file::{self, SavedSearch, SearchFile},
     FocusSearch, NextHistoryQuery, PreviousHistoryQuery, ReplaceAll, ReplaceNext, SearchOptions,
-    SelectNextMatch, SelectPrevMatch, ToggleCaseSensitive, ToggleIncludeIgnored, ToggleRegex,
-    ToggleReplace, ToggleWholeWord,
+    SelectNextMatch, SelectPrevMatch, ToggleCaseSensitive, ToggleFancyRegex, ToggleIncludeIgnored,
+    ToggleMultiline, ToggleRegex, ToggleReplace, ToggleStructural, ToggleWholeWord,
 };
 use anyhow::Context as _;
 use collections::{HashMap, HashSet};
@@ -26,16 +28,16 @@ use std::{
     any::{Any, TypeId},
     mem,
     ops::{Not, Range},
-    path::Path,
+    path::{Path, PathBuf},
 };
 use theme::ThemeSettings;
 use ui::{
     h_flex, prelude::*, v_flex, Icon, IconButton, IconName, Label, LabelCommon, LabelSize,
     Selectable, Tooltip,
 };
-use util::paths::PathMatcher;
+use util::{paths::PathMatcher, ResultExt as _};
 use workspace::{
-    item::{BreadcrumbText, Item, ItemEvent, ItemHandle, TabContentParams},
+    item::{BreadcrumbText, Item, ItemEvent, ItemHandle, ProjectItem, TabContentParams},
     searchable::{Direction, SearchableItem, SearchableItemHandle},
     DeploySearch, ItemNavHistory, NewSearch, Pane, ToolbarItemEvent, ToolbarItemLocation,
     ToolbarItemView, Workspace, WorkspaceId,
@@ -43,10 +45,19 @@ use workspace::{
 
 const MIN_INPUT_WIDTH_REMS: f32 = 15.;
 const MAX_INPUT_WIDTH_REMS: f32 = 30.;
+const MAX_CONTEXT_LINES: u32 = 20;
 
 actions!(
     project_search,
-    [SearchInNew, ToggleFocus, NextField, ToggleFilters]
+    [
+        SearchInNew,
+        ToggleFocus,
+        NextField,
+        ToggleFilters,
+        SaveSearch,
+        IncreaseContextLines,
+        DecreaseContextLines
+    ]
 );
 
 #[derive(Default)]
@@ -56,6 +67,8 @@ impl Global for ActiveSettings {}
 
 pub fn init(cx: &mut AppContext) {
     cx.set_global(ActiveSettings::default());
+    workspace::register_project_item::<ProjectSearchView>(cx);
+    workspace::register_deserializable_item::<ProjectSearchView>(cx);
     cx.observe_new_views(|workspace: &mut Workspace, _cx| {
         register_workspace_action(workspace, move |search_bar, _: &FocusSearch, cx| {
             search_bar.focus_search(cx);
@@ -72,9 +85,26 @@ pub fn init(cx: &mut AppContext) {
         register_workspace_action(workspace, move |search_bar, _: &ToggleRegex, cx| {
             search_bar.toggle_search_option(SearchOptions::REGEX, cx);
         });
+        register_workspace_action(workspace, move |search_bar, _: &ToggleStructural, cx| {
+            search_bar.toggle_search_option(SearchOptions::STRUCTURAL, cx);
+        });
+        register_workspace_action(workspace, move |search_bar, _: &ToggleMultiline, cx| {
+            search_bar.toggle_search_option(SearchOptions::MULTILINE, cx);
+        });
+        register_workspace_action(workspace, move |search_bar, _: &ToggleFancyRegex, cx| {
+            search_bar.toggle_search_option(SearchOptions::FANCY_REGEX, cx);
+        });
         register_workspace_action(workspace, move |search_bar, action: &ToggleReplace, cx| {
             search_bar.toggle_replace(action, cx)
         });
+        register_workspace_action(
+            workspace,
+            move |search_bar, _: &IncreaseContextLines, cx| search_bar.change_context_lines(1, cx),
+        );
+        register_workspace_action(
+            workspace,
+            move |search_bar, _: &DecreaseContextLines, cx| search_bar.change_context_lines(-1, cx),
+        );
         register_workspace_action(
             workspace,
             move |search_bar, action: &SelectPrevMatch, cx| {
@@ -92,6 +122,9 @@ pub fn init(cx: &mut AppContext) {
         register_workspace_action_for_present_search(workspace, |workspace, action, cx| {
             ProjectSearchView::search_in_new(workspace, action, cx)
         });
+        register_workspace_action_for_present_search(workspace, |workspace, action, cx| {
+            ProjectSearchView::save_search(workspace, action, cx)
+        });
 
         // Both on present and dismissed search, we need to unconditionally handle those actions to focus from the editor.
         workspace.register_action(move |workspace, action: &DeploySearch, cx| {
@@ -125,6 +158,8 @@ struct ProjectSearch {
     no_results: Option<bool>,
     limit_reached: bool,
     search_history_cursor: SearchHistoryCursor,
+    /// The number of lines shown around each match.
+    context_lines: u32,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -149,6 +184,9 @@ pub struct ProjectSearchView {
     excluded_files_editor: View<Editor>,
     filters_enabled: bool,
     replace_enabled: bool,
+    /// The search file this search was opened from or last saved to.
+    search_file: Option<PathBuf>,
+    workspace_id: Option<WorkspaceId>,
     _subscriptions: Vec<Subscription>,
 }
 
@@ -179,6 +217,7 @@ impl ProjectSearch {
             no_results: None,
             limit_reached: false,
             search_history_cursor: Default::default(),
+            context_lines: editor::DEFAULT_MULTIBUFFER_CONTEXT,
         }
     }
 
@@ -196,6 +235,7 @@ impl ProjectSearch {
             no_results: self.no_results,
             limit_reached: self.limit_reached,
             search_history_cursor: self.search_history_cursor.clone(),
+            context_lines: self.context_lines,
         })
     }
 
@@ -210,6 +250,7 @@ impl ProjectSearch {
         self.search_id += 1;
         self.active_query = Some(query);
         self.match_ranges.clear();
+        let context_lines = self.context_lines;
         self.pending_search = Some(cx.spawn(|this, mut cx| async move {
             let mut matches = search;
             let this = this.upgrade()?;
@@ -232,7 +273,7 @@ impl ProjectSearch {
                                     excerpts.stream_excerpts_with_context_lines(
                                         buffer,
                                         ranges,
-                                        editor::DEFAULT_MULTIBUFFER_CONTEXT,
+                                        context_lines,
                                         cx,
                                     )
                                 })
@@ -338,6 +379,9 @@ impl FocusableView for ProjectSearchView {
 impl Item for ProjectSearchView {
     type Event = ViewEvent;
     fn tab_tooltip_text(&self, cx: &AppContext) -> Option<SharedString> {
+        if let Some(search_file) = &self.search_file {
+            return Some(search_file.to_string_lossy().into_owned().into());
+        }
         let query_text = self.query_editor.read(cx).text(cx);
 
         query_text
@@ -467,6 +511,8 @@ impl Item for ProjectSearchView {
     }
 
     fn added_to_workspace(&mut self, workspace: &mut Workspace, cx: &mut ViewContext<Self>) {
+        self.workspace_id = Some(workspace.database_id());
+        self.serialize(cx);
         self.results_editor
             .update(cx, |editor, cx| editor.added_to_workspace(workspace, cx));
     }
@@ -509,17 +555,51 @@ impl Item for ProjectSearchView {
     }
 
     fn serialized_item_kind() -> Option<&'static str> {
-        None
+        Some("ProjectSearchView")
     }
 
     fn deserialize(
-        _project: Model<Project>,
+        project: Model<Project>,
         _workspace: WeakView<Workspace>,
-        _workspace_id: workspace::WorkspaceId,
-        _item_id: workspace::ItemId,
-        _cx: &mut ViewContext<Pane>,
+        workspace_id: workspace::WorkspaceId,
+        item_id: workspace::ItemId,
+        cx: &mut ViewContext<Pane>,
     ) -> Task<anyhow::Result<View<Self>>> {
-        unimplemented!()
+        cx.spawn(|pane, mut cx| async move {
+            let (search, search_file) = SEARCH_DB
+                .get_search(item_id, workspace_id)?
+                .context("no project search to deserialize")?;
+            pane.update(&mut cx, |_, cx| {
+                let model = cx.new_model(|cx| ProjectSearch::new(project, cx));
+                cx.new_view(|cx| {
+                    let mut view = Self::new(model, cx, None);
+                    view.search_file = search_file;
+                    view.apply_saved_search(search, cx);
+                    view
+                })
+            })
+        })
+    }
+}
+
+impl ProjectItem for ProjectSearchView {
+    type Item = SearchFile;
+
+    fn for_project_item(
+        project: Model<Project>,
+        item: Model<Self::Item>,
+        cx: &mut ViewContext<Self>,
+    ) -> Self
+    where
+        Self: Sized,
+    {
+        let model = cx.new_model(|cx| ProjectSearch::new(project, cx));
+        let mut view = Self::new(model, cx, None);
+        let search_file = item.read(cx);
+        let search = search_file.search.clone();
+        view.search_file = Some(search_file.abs_path.clone());
+        view.apply_saved_search(search, cx);
+        view
     }
 }
 
@@ -540,8 +620,119 @@ impl ProjectSearchView {
             filters_enabled: self.filters_enabled,
         }
     }
+
+    fn saved_search(&self, cx: &AppContext) -> SavedSearch {
+        SavedSearch {
+            query: self.query_editor.read(cx).text(cx),
+            options: self.search_options,
+            included_files: self.included_files_editor.read(cx).text(cx),
+            excluded_files: self.excluded_files_editor.read(cx).text(cx),
+            context_lines: self.model.read(cx).context_lines,
+        }
+    }
+
+    /// Fills out the search's fields from a saved search and runs it again.
+    fn apply_saved_search(&mut self, search: SavedSearch, cx: &mut ViewContext<Self>) {
+        self.set_query(&search.query, cx);
+        self.included_files_editor
+            .update(cx, |editor, cx| editor.set_text(search.included_files, cx));
+        self.excluded_files_editor
+            .update(cx, |editor, cx| editor.set_text(search.excluded_files, cx));
+        self.filters_enabled = !self.included_files_editor.read(cx).text(cx).is_empty()
+            || !self.excluded_files_editor.read(cx).text(cx).is_empty();
+        self.search_options = search.options;
+        self.model.update(cx, |model, _| {
+            model.context_lines = search.context_lines.min(MAX_CONTEXT_LINES)
+        });
+        self.search(cx);
+    }
+
+    fn serialize(&self, cx: &mut ViewContext<Self>) {
+        let Some(workspace_id) = self.workspace_id else {
+            return;
+        };
+        let item_id = cx.entity_id().as_u64();
+        let search = self.saved_search(cx);
+        let search_file = self.search_file.clone();
+        cx.background_executor()
+            .spawn(async move {
+                SEARCH_DB
+                    .save_search(item_id, workspace_id, search, search_file)
+                    .await
+                    .log_err();
+            })
+            .detach();
+    }
+
+    fn change_context_lines(&mut self, delta: i32, cx: &mut ViewContext<Self>) {
+        self.model.update(cx, |model, cx| {
+            let context_lines = model
+                .context_lines
+                .saturating_add_signed(delta)
+                .min(MAX_CONTEXT_LINES);
+            if context_lines != model.context_lines {
+                model.context_lines = context_lines;
+                if let Some(query) = model.active_query.clone() {
+                    model.search(query, cx);
+                }
+            }
+        });
+        self.serialize(cx);
+    }
+
+    /// Returns the contents of a search file for this search and its current results.
+    fn search_file_contents(&self, cx: &AppContext) -> String {
+        let model = self.model.read(cx);
+        search_file::search_file_contents(
+            &self.saved_search(cx),
+            &model.excerpts.read(cx).snapshot(cx),
+            &model.match_ranges,
+            cx,
+        )
+    }
+
+    /// Writes the active search to its search file, prompting for a path if it was never saved.
+    fn save_search(workspace: &mut Workspace, _: &SaveSearch, cx: &mut ViewContext<Workspace>) {
+        let Some(search_view) = workspace.active_item_as::<ProjectSearchView>(cx) else {
+            return;
+        };
+        let project = workspace.project().clone();
+        let fs = workspace.app_state().fs.clone();
+        let search_file = search_view.read(cx).search_file.clone();
+        let new_path = search_file
+            .is_none()
+            .then(|| workspace.prompt_for_new_path(cx));
+        cx.spawn(|_, mut cx| async move {
+            let abs_path = match (search_file, new_path) {
+                (Some(search_file), _) => search_file,
+                (None, Some(new_path)) => {
+                    let Some(project_path) = new_path.await? else {
+                        return Ok(());
+                    };
+                    let abs_path = project
+                        .update(&mut cx, |project, cx| {
+                            project.absolute_path(&project_path, cx)
+                        })?
+                        .context("failed to find the absolute path of the search file")?;
+                    search_file::with_search_file_extension(abs_path)
+                }
+                (None, None) => return Ok(()),
+            };
+            let contents = search_view.update(&mut cx, |search_view, cx| {
+                search_view.search_file_contents(cx)
+            })?;
+            fs.atomic_write(abs_path.clone(), contents).await?;
+            search_view.update(&mut cx, |search_view, cx| {
+                search_view.search_file = Some(abs_path);
+                search_view.serialize(cx);
+                cx.emit(ViewEvent::UpdateTab);
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
     fn toggle_search_option(&mut self, option: SearchOptions, cx: &mut ViewContext<Self>) {
-        self.search_options.toggle(option);
+        self.search_options.toggle_option(option);
         cx.update_global(|state: &mut ActiveSettings, cx| {
             state.0.insert(
                 self.model.read(cx).project.downgrade(),
@@ -721,6 +912,8 @@ impl ProjectSearchView {
             excluded_files_editor,
             filters_enabled,
             replace_enabled: false,
+            search_file: None,
+            workspace_id: None,
             _subscriptions: subscriptions,
         };
         this.model_changed(cx);
@@ -855,6 +1048,7 @@ impl ProjectSearchView {
     fn search(&mut self, cx: &mut ViewContext<Self>) {
         if let Some(query) = self.build_search_query(cx) {
             self.model.update(cx, |model, cx| model.search(query, cx));
+            self.serialize(cx);
         }
     }
 
@@ -897,12 +1091,38 @@ impl ProjectSearchView {
                 }
             };
 
-        let query = if self.search_options.contains(SearchOptions::REGEX) {
+        let query = if self.search_options.contains(SearchOptions::STRUCTURAL) {
+            match SearchQuery::structural(
+                text,
+                self.search_options.contains(SearchOptions::INCLUDE_IGNORED),
+                included_files,
+                excluded_files,
+            ) {
+                Ok(query) => {
+                    let should_unmark_error = self.panels_with_errors.remove(&InputPanel::Query);
+                    if should_unmark_error {
+                        cx.notify();
+                    }
+
+                    Some(query)
+                }
+                Err(_e) => {
+                    let should_mark_error = self.panels_with_errors.insert(InputPanel::Query);
+                    if should_mark_error {
+                        cx.notify();
+                    }
+
+                    None
+                }
+            }
+        } else if self.search_options.contains(SearchOptions::REGEX) {
             match SearchQuery::regex(
                 text,
                 self.search_options.contains(SearchOptions::WHOLE_WORD),
                 self.search_options.contains(SearchOptions::CASE_SENSITIVE),
                 self.search_options.contains(SearchOptions::INCLUDE_IGNORED),
+                self.search_options.contains(SearchOptions::MULTILINE),
+                self.search_options.contains(SearchOptions::FANCY_REGEX),
                 included_files,
                 excluded_files,
             ) {
@@ -1193,6 +1413,14 @@ impl ProjectSearchBar {
         }
     }
 
+    fn change_context_lines(&mut self, delta: i32, cx: &mut ViewContext<Self>) {
+        if let Some(search_view) = self.active_project_search.as_ref() {
+            search_view.update(cx, |search_view, cx| {
+                search_view.change_context_lines(delta, cx);
+            });
+        }
+    }
+
     fn toggle_filters(&mut self, cx: &mut ViewContext<Self>) -> bool {
         if let Some(search_view) = self.active_project_search.as_ref() {
             search_view.update(cx, |search_view, cx| {
@@ -1372,6 +1600,24 @@ impl Render for ProjectSearchBar {
                         cx.listener(|this, _, cx| {
                             this.toggle_search_option(SearchOptions::REGEX, cx);
                         }),
+                    ))
+                    .child(SearchOptions::MULTILINE.as_button(
+                        self.is_option_enabled(SearchOptions::MULTILINE, cx),
+                        cx.listener(|this, _, cx| {
+                            this.toggle_search_option(SearchOptions::MULTILINE, cx);
+                        }),
+                    ))
+                    .child(SearchOptions::FANCY_REGEX.as_button(
+                        self.is_option_enabled(SearchOptions::FANCY_REGEX, cx),
+                        cx.listener(|this, _, cx| {
+                            this.toggle_search_option(SearchOptions::FANCY_REGEX, cx);
+                        }),
+                    ))
+                    .child(SearchOptions::STRUCTURAL.as_button(
+                        self.is_option_enabled(SearchOptions::STRUCTURAL, cx),
+                        cx.listener(|this, _, cx| {
+                            this.toggle_search_option(SearchOptions::STRUCTURAL, cx);
+                        }),
                     )),
             );
 
@@ -1402,6 +1648,34 @@ impl Render for ProjectSearchBar {
                                 .unwrap_or_default(),
                         )
                         .tooltip(|cx| Tooltip::for_action("Toggle replace", &ToggleReplace, cx)),
+                )
+                .child(
+                    IconButton::new("project-search-fewer-context-lines", IconName::Dash)
+                        .disabled(search.model.read(cx).context_lines == 0)
+                        .on_click(cx.listener(|this, _, cx| {
+                            this.change_context_lines(-1, cx);
+                        }))
+                        .tooltip(|cx| {
+                            Tooltip::for_action(
+                                "Show fewer context lines",
+                                &DecreaseContextLines,
+                                cx,
+                            )
+                        }),
+                )
+                .child(
+                    IconButton::new("project-search-more-context-lines", IconName::Plus)
+                        .disabled(search.model.read(cx).context_lines >= MAX_CONTEXT_LINES)
+                        .on_click(cx.listener(|this, _, cx| {
+                            this.change_context_lines(1, cx);
+                        }))
+                        .tooltip(|cx| {
+                            Tooltip::for_action(
+                                "Show more context lines",
+                                &IncreaseContextLines,
+                                cx,
+                            )
+                        }),
                 ),
         );
 
@@ -3224,6 +3498,100 @@ pub mod tests {
             .expect("unable to update search view");
     }
 
+    #[gpui::test]
+    async fn test_search_file_and_context_lines(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.background_executor.clone());
+        fs.insert_tree(
+            "/dir",
+            json!({
+                "one.rs": "fn one() {\n    let a = 1;\n    let b = 2;\n    let c = 3;\n}\n",
+            }),
+        )
+        .await;
+        let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+        let worktree_id = project.read_with(cx, |project, cx| {
+            project.worktrees().next().unwrap().read(cx).id()
+        });
+        let search = cx.new_model(|cx| ProjectSearch::new(project.clone(), cx));
+        let search_view = cx.add_window(|cx| ProjectSearchView::new(search.clone(), cx, None));
+
+        search_view
+            .update(cx, |search_view, cx| {
+                search_view.change_context_lines(-2, cx)
+            })
+            .unwrap();
+        perform_search(search_view, "let b", cx);
+        search_view
+            .update(cx, |search_view, cx| {
+                assert_eq!(
+                    search_view
+                        .results_editor
+                        .update(cx, |editor, cx| editor.display_text(cx)),
+                    "\n\n    let b = 2;"
+                );
+                search_view.change_context_lines(1, cx);
+            })
+            .unwrap();
+        cx.background_executor.run_until_parked();
+
+        let contents = search_view
+            .update(cx, |search_view, cx| {
+                assert_eq!(
+                    search_view
+                        .results_editor
+                        .update(cx, |editor, cx| editor.display_text(cx)),
+                    "\n\n    let a = 1;\n    let b = 2;\n    let c = 3;"
+                );
+                let contents = search_view.search_file_contents(cx);
+                assert_eq!(
+                    SavedSearch::parse(&contents).unwrap(),
+                    search_view.saved_search(cx)
+                );
+                contents
+            })
+            .unwrap();
+        assert_eq!(
+            contents,
+            "# Query: let b\n\
+             # Context lines: 1\n\
+             \n\
+             dir/one.rs:\n  \
+             2      let a = 1;\n  \
+             3:     let b = 2;\n  \
+             4      let c = 3;\n"
+        );
+
+        // Opening the search file reruns its search.
+        fs.insert_file("/dir/let.zed-search", contents.into_bytes())
+            .await;
+        let window = cx.add_window(|cx| Workspace::test_new(project, cx));
+        let item = window
+            .update(cx, |workspace, cx| {
+                workspace.open_path((worktree_id, "let.zed-search"), None, true, cx)
+            })
+            .unwrap()
+            .await
+            .unwrap();
+        cx.background_executor.run_until_parked();
+        let reopened_view = item.downcast::<ProjectSearchView>().unwrap();
+        window
+            .update(cx, |_, cx| {
+                reopened_view.update(cx, |search_view, cx| {
+                    assert_eq!(search_view.query_editor.read(cx).text(cx), "let b");
+                    assert_eq!(search_view.model.read(cx).context_lines, 1);
+                    assert_eq!(
+                        search_view
+                            .results_editor
+                            .update(cx, |editor, cx| editor.display_text(cx)),
+                        "\n\n    let a = 1;\n    let b = 2;\n    let c = 3;"
+                    );
+                });
+            })
+            .unwrap();
+    }
+
     fn init_test(cx: &mut TestAppContext) {
         cx.update(|cx| {
             let settings = SettingsStore::test(cx);
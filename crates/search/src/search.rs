@@ -7,8 +7,10 @@ use ui::{prelude::*, Tooltip};
 use ui::{ButtonStyle, IconButton};
 
 pub mod buffer_search;
+mod persistence;
 pub mod project_search;
 pub(crate) mod search_bar;
+mod search_file;
 
 pub fn init(cx: &mut AppContext) {
     menu::init();
@@ -24,6 +26,9 @@ actions!(
         ToggleCaseSensitive,
         ToggleIncludeIgnored,
         ToggleRegex,
+        ToggleStructural,
+        ToggleMultiline,
+        ToggleFancyRegex,
         ToggleReplace,
         SelectNextMatch,
         SelectPrevMatch,
@@ -43,6 +48,9 @@ bitflags! {
         const CASE_SENSITIVE = 0b010;
         const INCLUDE_IGNORED = 0b100;
         const REGEX = 0b1000;
+        const STRUCTURAL = 0b10000;
+        const MULTILINE = 0b100000;
+        const FANCY_REGEX = 0b1000000;
     }
 }
 
@@ -53,6 +61,9 @@ impl SearchOptions {
             SearchOptions::CASE_SENSITIVE => "match case",
             SearchOptions::INCLUDE_IGNORED => "include Ignored",
             SearchOptions::REGEX => "regular expression",
+            SearchOptions::STRUCTURAL => "structural search",
+            SearchOptions::MULTILINE => "multiline regular expression",
+            SearchOptions::FANCY_REGEX => "lookaround and backreferences",
             _ => panic!("{:?} is not a named SearchOption", self),
         }
     }
@@ -63,6 +74,9 @@ impl SearchOptions {
             SearchOptions::CASE_SENSITIVE => ui::IconName::CaseSensitive,
             SearchOptions::INCLUDE_IGNORED => ui::IconName::FileGit,
             SearchOptions::REGEX => ui::IconName::Regex,
+            SearchOptions::STRUCTURAL => ui::IconName::Code,
+            SearchOptions::MULTILINE => ui::IconName::Return,
+            SearchOptions::FANCY_REGEX => ui::IconName::MagicWand,
             _ => panic!("{:?} is not a named SearchOption", self),
         }
     }
@@ -73,6 +87,9 @@ impl SearchOptions {
             SearchOptions::CASE_SENSITIVE => Box::new(ToggleCaseSensitive),
             SearchOptions::INCLUDE_IGNORED => Box::new(ToggleIncludeIgnored),
             SearchOptions::REGEX => Box::new(ToggleRegex),
+            SearchOptions::STRUCTURAL => Box::new(ToggleStructural),
+            SearchOptions::MULTILINE => Box::new(ToggleMultiline),
+            SearchOptions::FANCY_REGEX => Box::new(ToggleFancyRegex),
             _ => panic!("{:?} is not a named SearchOption", self),
         }
     }
@@ -87,9 +104,30 @@ impl SearchOptions {
         options.set(SearchOptions::CASE_SENSITIVE, query.case_sensitive());
         options.set(SearchOptions::INCLUDE_IGNORED, query.include_ignored());
         options.set(SearchOptions::REGEX, query.is_regex());
+        options.set(SearchOptions::STRUCTURAL, query.is_structural());
+        options.set(SearchOptions::MULTILINE, query.multiline());
+        options.set(SearchOptions::FANCY_REGEX, query.fancy_regex());
         options
     }
 
+    /// Toggles the option, keeping the options consistent: a query is either a regular expression
+    /// or a structural pattern, and the multiline and fancy regex options only apply to regular
+    /// expressions.
+    pub fn toggle_option(&mut self, option: SearchOptions) {
+        let regex_options = SearchOptions::MULTILINE | SearchOptions::FANCY_REGEX;
+        self.toggle(option);
+        if !self.contains(option) {
+            if option == SearchOptions::REGEX {
+                self.remove(regex_options);
+            }
+        } else if option == SearchOptions::STRUCTURAL {
+            self.remove(SearchOptions::REGEX | regex_options);
+        } else if regex_options.contains(option) || option == SearchOptions::REGEX {
+            self.insert(SearchOptions::REGEX);
+            self.remove(SearchOptions::STRUCTURAL);
+        }
+    }
+
     pub fn as_button(
         &self,
         active: bool,
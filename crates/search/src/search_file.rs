@@ -0,0 +1,264 @@
+//! Saved project searches: the `.zed-search` files, which store a search's parameters in a
+//! header followed by a plain-text copy of its results, and which rerun the search when opened.
+use std::{
+    ffi::OsStr,
+    fmt::Write as _,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Context as _, Result};
+use collections::HashMap;
+use editor::{Anchor, MultiBufferSnapshot};
+use gpui::{AppContext, Context as _, Model, Task};
+use language::{Point, ToPoint as _};
+use project::{Project, ProjectEntryId, ProjectPath};
+
+use crate::SearchOptions;
+
+pub(crate) const SEARCH_FILE_EXTENSION: &str = "zed-search";
+
+const QUERY_KEY: &str = "Query";
+const OPTIONS_KEY: &str = "Options";
+const INCLUDE_KEY: &str = "Include";
+const EXCLUDE_KEY: &str = "Exclude";
+const CONTEXT_LINES_KEY: &str = "Context lines";
+
+/// The names of the search options in a search file's header.
+const OPTION_NAMES: [(SearchOptions, &str); 7] = [
+    (SearchOptions::WHOLE_WORD, "whole_word"),
+    (SearchOptions::CASE_SENSITIVE, "case_sensitive"),
+    (SearchOptions::INCLUDE_IGNORED, "include_ignored"),
+    (SearchOptions::REGEX, "regex"),
+    (SearchOptions::STRUCTURAL, "structural"),
+    (SearchOptions::MULTILINE, "multiline"),
+    (SearchOptions::FANCY_REGEX, "fancy_regex"),
+];
+
+/// The parameters of a project search, from which it can be run again.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct SavedSearch {
+    pub query: String,
+    pub options: SearchOptions,
+    pub included_files: String,
+    pub excluded_files: String,
+    pub context_lines: u32,
+}
+
+impl SavedSearch {
+    /// Parses the header of a search file, ignoring the results that follow it.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut query = None;
+        let mut options = SearchOptions::NONE;
+        let mut included_files = String::new();
+        let mut excluded_files = String::new();
+        let mut context_lines = editor::DEFAULT_MULTIBUFFER_CONTEXT;
+
+        for line in text.lines() {
+            let Some(line) = line.strip_prefix("# ") else {
+                break;
+            };
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            match key {
+                QUERY_KEY => query = Some(unescape(value)),
+                OPTIONS_KEY => {
+                    for name in value.split(',').map(str::trim) {
+                        if let Some((option, _)) = OPTION_NAMES
+                            .iter()
+                            .find(|(_, option_name)| *option_name == name)
+                        {
+                            options.insert(*option);
+                        }
+                    }
+                }
+                INCLUDE_KEY => included_files = value.to_string(),
+                EXCLUDE_KEY => excluded_files = value.to_string(),
+                CONTEXT_LINES_KEY => {
+                    context_lines = value
+                        .trim()
+                        .parse()
+                        .with_context(|| format!("parsing {value:?} as a number of lines"))?;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            query: query.ok_or_else(|| anyhow!("search file has no query"))?,
+            options,
+            included_files,
+            excluded_files,
+            context_lines,
+        })
+    }
+
+    /// Returns the header of a search file, which [`SavedSearch::parse`] reads back.
+    pub fn header(&self) -> String {
+        let mut header = format!("# {QUERY_KEY}: {}\n", escape(&self.query));
+        let option_names = OPTION_NAMES
+            .iter()
+            .filter(|(option, _)| self.options.contains(*option))
+            .map(|(_, name)| *name)
+            .collect::<Vec<_>>();
+        if !option_names.is_empty() {
+            writeln!(header, "# {OPTIONS_KEY}: {}", option_names.join(", ")).ok();
+        }
+        if !self.included_files.is_empty() {
+            writeln!(header, "# {INCLUDE_KEY}: {}", self.included_files).ok();
+        }
+        if !self.excluded_files.is_empty() {
+            writeln!(header, "# {EXCLUDE_KEY}: {}", self.excluded_files).ok();
+        }
+        writeln!(header, "# {CONTEXT_LINES_KEY}: {}", self.context_lines).ok();
+        header
+    }
+}
+
+/// Keeps the query on a single line of the header.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') => result.push('\n'),
+                Some(c) => result.push(c),
+                None => result.push('\\'),
+            }
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+/// Returns the contents of a search file: the search's header, followed by the lines of each
+/// excerpt of its results, grouped by file. The lines containing a match are marked by a colon
+/// after their line number.
+pub(crate) fn search_file_contents(
+    search: &SavedSearch,
+    results: &MultiBufferSnapshot,
+    match_ranges: &[Range<Anchor>],
+    cx: &AppContext,
+) -> String {
+    let mut matches_by_excerpt = HashMap::<_, Vec<Range<Anchor>>>::default();
+    for range in match_ranges {
+        matches_by_excerpt
+            .entry(range.start.excerpt_id)
+            .or_default()
+            .push(range.clone());
+    }
+
+    let mut contents = search.header();
+    let mut last_path = None::<PathBuf>;
+    for (excerpt_id, buffer, excerpt_range) in results.excerpts() {
+        let path = buffer
+            .file()
+            .map(|file| file.full_path(cx))
+            .unwrap_or_else(|| PathBuf::from("untitled"));
+        if last_path.as_ref() == Some(&path) {
+            contents.push_str("  ...\n");
+        } else {
+            write!(contents, "\n{}:\n", path.display()).ok();
+            last_path = Some(path);
+        }
+
+        let rows = excerpt_range.context.start.to_point(buffer).row
+            ..=excerpt_range.context.end.to_point(buffer).row;
+        let number_width = (rows.end() + 1).to_string().len();
+        let matched_rows = matches_by_excerpt
+            .get(&excerpt_id)
+            .map(|ranges| {
+                ranges
+                    .iter()
+                    .map(|range| {
+                        range.start.text_anchor.to_point(buffer).row
+                            ..range.end.text_anchor.to_point(buffer).row + 1
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        for row in rows {
+            let separator = if matched_rows.iter().any(|rows| rows.contains(&row)) {
+                ':'
+            } else {
+                ' '
+            };
+            let line = buffer
+                .text_for_range(Point::new(row, 0)..Point::new(row, buffer.line_len(row)))
+                .collect::<String>();
+            writeln!(contents, "  {:>number_width$}{separator} {line}", row + 1).ok();
+        }
+    }
+    contents
+}
+
+/// Returns the path with the extension of search files, unless it already has it.
+pub(crate) fn with_search_file_extension(path: PathBuf) -> PathBuf {
+    if path.extension() == Some(OsStr::new(SEARCH_FILE_EXTENSION)) {
+        path
+    } else {
+        let mut path = path.into_os_string();
+        path.push(".");
+        path.push(SEARCH_FILE_EXTENSION);
+        path.into()
+    }
+}
+
+pub(crate) fn is_search_file(path: &Path) -> bool {
+    path.extension() == Some(OsStr::new(SEARCH_FILE_EXTENSION))
+}
+
+/// A `.zed-search` file of a local project, which opens as a project search.
+pub struct SearchFile {
+    pub(crate) abs_path: PathBuf,
+    pub(crate) project_path: ProjectPath,
+    pub(crate) search: SavedSearch,
+}
+
+impl project::Item for SearchFile {
+    fn try_open(
+        project: &Model<Project>,
+        path: &ProjectPath,
+        cx: &mut AppContext,
+    ) -> Option<Task<gpui::Result<Model<Self>>>> {
+        if !is_search_file(&path.path) || !project.read(cx).is_local() {
+            return None;
+        }
+
+        let project_path = path.clone();
+        let project = project.clone();
+        Some(cx.spawn(|mut cx| async move {
+            let (abs_path, fs) = project.read_with(&cx, |project, cx| {
+                (
+                    project.absolute_path(&project_path, cx),
+                    project.fs().clone(),
+                )
+            })?;
+            let abs_path = abs_path.context("failed to find the absolute path")?;
+            let text = fs.load(&abs_path).await?;
+            let search = SavedSearch::parse(&text)
+                .with_context(|| format!("parsing search file {abs_path:?}"))?;
+            cx.new_model(|_| SearchFile {
+                abs_path,
+                project_path,
+                search,
+            })
+        }))
+    }
+
+    fn entry_id(&self, _: &AppContext) -> Option<ProjectEntryId> {
+        None
+    }
+
+    fn project_path(&self, _: &AppContext) -> Option<ProjectPath> {
+        Some(self.project_path.clone())
+    }
+}
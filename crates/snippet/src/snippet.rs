@@ -1,6 +1,11 @@
 use anyhow::{anyhow, Context, Result};
+use regex::{Captures, Regex, RegexBuilder};
 use smallvec::SmallVec;
-use std::{collections::BTreeMap, ops::Range};
+use std::{
+    collections::{BTreeMap, HashMap},
+    mem,
+    ops::Range,
+};
 
 #[derive(Default)]
 pub struct Snippet {
@@ -8,15 +13,86 @@ pub struct Snippet {
     pub tabstops: Vec<TabStop>,
 }
 
-type TabStop = SmallVec<[Range<isize>; 2]>;
+#[derive(Clone, Debug, Default)]
+pub struct TabStop {
+    /// The ranges, which are selected together when moving to the tabstop.
+    pub ranges: SmallVec<[Range<isize>; 2]>,
+    /// The values to choose from for the tabstop, the first of which is inserted.
+    pub choices: Vec<String>,
+    /// The ranges showing a transformation of the tabstop's text, updated when moving out of it.
+    pub transformed_ranges: Vec<(Range<isize>, Transform)>,
+}
+
+/// A regex replacement, like `${1/(.*)/${1:/upcase}/}`, applied to a tabstop's or a variable's text.
+#[derive(Clone, Debug)]
+pub struct Transform {
+    regex: Regex,
+    format: Vec<FormatItem>,
+    global: bool,
+}
+
+#[derive(Clone, Debug)]
+enum FormatItem {
+    Text(String),
+    Group {
+        index: usize,
+        case_change: Option<CaseChange>,
+    },
+    /// `${1:+if}`, `${1:?if:else}` and `${1:-else}`, the group's text is inserted when `if_matched` is `None`.
+    Conditional {
+        index: usize,
+        if_matched: Option<String>,
+        otherwise: String,
+    },
+}
+
+#[derive(Clone, Copy, Debug)]
+enum CaseChange {
+    Upcase,
+    Downcase,
+    Capitalize,
+    CamelCase,
+    PascalCase,
+}
+
+enum Node {
+    Text(String),
+    TabStop {
+        index: usize,
+        placeholder: Option<Vec<Node>>,
+        choices: Vec<String>,
+        transform: Option<Transform>,
+    },
+    Variable {
+        name: String,
+        default: Option<Vec<Node>>,
+        transform: Option<Transform>,
+    },
+}
 
 impl Snippet {
     pub fn parse(source: &str) -> Result<Self> {
-        let mut text = String::with_capacity(source.len());
-        let mut tabstops = BTreeMap::new();
-        parse_snippet(source, false, &mut text, &mut tabstops)
-            .context("failed to parse snippet")?;
+        Self::parse_with_variables(source, &|_| None)
+    }
 
+    /// Parses the snippet, inserting the values of its variables, like `$TM_FILENAME`.
+    ///
+    /// Variables, which `resolve_variable` does not know, become placeholders with their name.
+    pub fn parse_with_variables(
+        source: &str,
+        resolve_variable: &dyn Fn(&str) -> Option<String>,
+    ) -> Result<Self> {
+        let mut nodes = Vec::new();
+        parse_snippet(source, false, &mut nodes).context("failed to parse snippet")?;
+
+        let mut renderer = Renderer::new(&nodes, resolve_variable);
+        renderer.render(&nodes);
+        let Renderer {
+            text, mut tabstops, ..
+        } = renderer;
+
+        // Tabstops, which only have transformations, cannot be moved to.
+        tabstops.retain(|_, tabstop| !tabstop.ranges.is_empty());
         let len = text.len() as isize;
         let final_tabstop = tabstops.remove(&0);
         let mut tabstops = tabstops.into_values().collect::<Vec<_>>();
@@ -25,8 +101,11 @@ impl Snippet {
             tabstops.push(final_tabstop);
         } else {
             let end_tabstop = [len..len].into_iter().collect();
-            if !tabstops.last().map_or(false, |t| *t == end_tabstop) {
-                tabstops.push(end_tabstop);
+            if !tabstops.last().map_or(false, |t| t.ranges == end_tabstop) {
+                tabstops.push(TabStop {
+                    ranges: end_tabstop,
+                    ..Default::default()
+                });
             }
         }
 
@@ -34,22 +113,280 @@ impl Snippet {
     }
 }
 
-fn parse_snippet<'a>(
-    mut source: &'a str,
-    nested: bool,
-    text: &mut String,
-    tabstops: &mut BTreeMap<usize, TabStop>,
-) -> Result<&'a str> {
+impl Transform {
+    fn new(pattern: &str, format: Vec<FormatItem>, options: &str) -> Result<Self> {
+        let regex = RegexBuilder::new(pattern)
+            .case_insensitive(options.contains('i'))
+            .multi_line(options.contains('m'))
+            .build()
+            .with_context(|| format!("invalid transform regex {pattern:?}"))?;
+        Ok(Self {
+            regex,
+            format,
+            global: options.contains('g'),
+        })
+    }
+
+    /// Replaces the first match of the regex in the text, or all of them with the `g` option.
+    pub fn apply(&self, text: &str) -> String {
+        let mut result = String::with_capacity(text.len());
+        let mut last_match_end = 0;
+        for captures in self.regex.captures_iter(text) {
+            let Some(whole_match) = captures.get(0) else {
+                continue;
+            };
+            result.push_str(&text[last_match_end..whole_match.start()]);
+            self.format(&captures, &mut result);
+            last_match_end = whole_match.end();
+            if !self.global {
+                break;
+            }
+        }
+        result.push_str(&text[last_match_end..]);
+        result
+    }
+
+    fn format(&self, captures: &Captures, result: &mut String) {
+        for item in &self.format {
+            match item {
+                FormatItem::Text(text) => result.push_str(text),
+                FormatItem::Group { index, case_change } => {
+                    if let Some(group) = captures.get(*index) {
+                        match case_change {
+                            Some(case_change) => {
+                                result.push_str(&case_change.apply(group.as_str()))
+                            }
+                            None => result.push_str(group.as_str()),
+                        }
+                    }
+                }
+                FormatItem::Conditional {
+                    index,
+                    if_matched,
+                    otherwise,
+                } => match captures.get(*index).filter(|group| !group.is_empty()) {
+                    Some(group) => result.push_str(if_matched.as_deref().unwrap_or(group.as_str())),
+                    None => result.push_str(otherwise),
+                },
+            }
+        }
+    }
+}
+
+impl CaseChange {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "upcase" => Some(Self::Upcase),
+            "downcase" => Some(Self::Downcase),
+            "capitalize" => Some(Self::Capitalize),
+            "camelcase" => Some(Self::CamelCase),
+            "pascalcase" => Some(Self::PascalCase),
+            _ => None,
+        }
+    }
+
+    fn apply(self, text: &str) -> String {
+        fn capitalize(word: &str) -> String {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect())
+                .unwrap_or_default()
+        }
+
+        let words = || {
+            text.split(|c: char| !c.is_alphanumeric())
+                .filter(|word| !word.is_empty())
+        };
+        match self {
+            Self::Upcase => text.to_uppercase(),
+            Self::Downcase => text.to_lowercase(),
+            Self::Capitalize => capitalize(text),
+            Self::PascalCase => words().map(capitalize).collect(),
+            Self::CamelCase => words()
+                .enumerate()
+                .map(|(ix, word)| {
+                    if ix == 0 {
+                        let mut chars = word.chars();
+                        chars
+                            .next()
+                            .map(|first| first.to_lowercase().chain(chars).collect())
+                            .unwrap_or_default()
+                    } else {
+                        capitalize(word)
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Renders the parsed snippet into its text and tabstops.
+struct Renderer<'a> {
+    text: String,
+    tabstops: BTreeMap<usize, TabStop>,
+    /// The texts of the tabstops' first placeholders, which their mirrors repeat.
+    placeholder_texts: HashMap<usize, String>,
+    resolve_variable: &'a dyn Fn(&str) -> Option<String>,
+    /// The tabstops, that unknown variables became, after all of the snippet's ones.
+    variable_tabstops: HashMap<String, usize>,
+    next_variable_tabstop: usize,
+}
+
+impl<'a> Renderer<'a> {
+    fn new(nodes: &[Node], resolve_variable: &'a dyn Fn(&str) -> Option<String>) -> Self {
+        let mut this = Self {
+            text: String::new(),
+            tabstops: BTreeMap::new(),
+            placeholder_texts: HashMap::new(),
+            resolve_variable,
+            variable_tabstops: HashMap::new(),
+            next_variable_tabstop: max_tabstop_index(nodes) + 1,
+        };
+        let mut placeholder_texts = HashMap::new();
+        this.collect_placeholder_texts(nodes, &mut placeholder_texts);
+        this.placeholder_texts = placeholder_texts;
+        this
+    }
+
+    fn collect_placeholder_texts(&self, nodes: &[Node], texts: &mut HashMap<usize, String>) {
+        for node in nodes {
+            match node {
+                Node::Text(_) => {}
+                Node::TabStop {
+                    index,
+                    placeholder,
+                    choices,
+                    ..
+                } => {
+                    if let Some(placeholder) = placeholder {
+                        if !texts.contains_key(index) {
+                            let mut renderer = Renderer::new(placeholder, self.resolve_variable);
+                            renderer.render(placeholder);
+                            texts.insert(*index, renderer.text);
+                        }
+                        self.collect_placeholder_texts(placeholder, texts);
+                    } else if let Some(choice) = choices.first() {
+                        texts.entry(*index).or_insert_with(|| choice.clone());
+                    }
+                }
+                Node::Variable { default, .. } => {
+                    if let Some(default) = default {
+                        self.collect_placeholder_texts(default, texts);
+                    }
+                }
+            }
+        }
+    }
+
+    fn render(&mut self, nodes: &[Node]) {
+        for node in nodes {
+            match node {
+                Node::Text(text) => self.text.push_str(text),
+                Node::TabStop {
+                    index,
+                    placeholder,
+                    choices,
+                    transform,
+                } => {
+                    let start = self.text.len();
+                    if let Some(transform) = transform {
+                        let text = self
+                            .placeholder_texts
+                            .get(index)
+                            .map_or(String::new(), |text| transform.apply(text));
+                        self.text.push_str(&text);
+                        self.tabstops
+                            .entry(*index)
+                            .or_default()
+                            .transformed_ranges
+                            .push((start as isize..self.text.len() as isize, transform.clone()));
+                        continue;
+                    }
+
+                    match placeholder {
+                        Some(placeholder) => self.render(placeholder),
+                        None => {
+                            if let Some(text) = self.placeholder_texts.get(index) {
+                                self.text.push_str(text);
+                            }
+                        }
+                    }
+                    let tabstop = self.tabstops.entry(*index).or_default();
+                    tabstop
+                        .ranges
+                        .push(start as isize..self.text.len() as isize);
+                    if tabstop.choices.is_empty() {
+                        tabstop.choices.clone_from(choices);
+                    }
+                }
+                Node::Variable {
+                    name,
+                    default,
+                    transform,
+                } => match ((self.resolve_variable)(name), default) {
+                    (Some(value), None) => self.push_variable_value(&value, transform.as_ref()),
+                    (Some(value), Some(_)) if !value.is_empty() => {
+                        self.push_variable_value(&value, transform.as_ref())
+                    }
+                    (_, Some(default)) => self.render(default),
+                    (None, None) => {
+                        // Unknown variables become placeholders with their name, so that they are easy to replace.
+                        let start = self.text.len();
+                        self.text.push_str(name);
+                        let index = match self.variable_tabstops.get(name) {
+                            Some(index) => *index,
+                            None => {
+                                let index = self.next_variable_tabstop;
+                                self.next_variable_tabstop += 1;
+                                self.variable_tabstops.insert(name.clone(), index);
+                                index
+                            }
+                        };
+                        self.tabstops
+                            .entry(index)
+                            .or_default()
+                            .ranges
+                            .push(start as isize..self.text.len() as isize);
+                    }
+                },
+            }
+        }
+    }
+
+    fn push_variable_value(&mut self, value: &str, transform: Option<&Transform>) {
+        match transform {
+            Some(transform) => self.text.push_str(&transform.apply(value)),
+            None => self.text.push_str(value),
+        }
+    }
+}
+
+fn max_tabstop_index(nodes: &[Node]) -> usize {
+    nodes
+        .iter()
+        .map(|node| match node {
+            Node::Text(_) => 0,
+            Node::TabStop {
+                index, placeholder, ..
+            } => (*index).max(placeholder.as_deref().map_or(0, max_tabstop_index)),
+            Node::Variable { default, .. } => default.as_deref().map_or(0, max_tabstop_index),
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+fn parse_snippet<'a>(mut source: &'a str, nested: bool, nodes: &mut Vec<Node>) -> Result<&'a str> {
     loop {
         match source.chars().next() {
             None => return Ok(""),
             Some('$') => {
-                source = parse_tabstop(&source[1..], text, tabstops)?;
+                source = parse_tabstop_or_variable(&source[1..], nodes)?;
             }
             Some('\\') => {
                 source = &source[1..];
                 if let Some(c) = source.chars().next() {
-                    text.push(c);
+                    push_text(nodes, &source[..c.len_utf8()]);
                     source = &source[c.len_utf8()..];
                 }
             }
@@ -57,54 +394,288 @@ fn parse_snippet<'a>(
                 if nested {
                     return Ok(source);
                 } else {
-                    text.push('}');
+                    push_text(nodes, "}");
                     source = &source[1..];
                 }
             }
             Some(_) => {
                 let chunk_end = source.find(['}', '$', '\\']).unwrap_or(source.len());
                 let (chunk, rest) = source.split_at(chunk_end);
-                text.push_str(chunk);
+                push_text(nodes, chunk);
                 source = rest;
             }
         }
     }
 }
 
-fn parse_tabstop<'a>(
-    mut source: &'a str,
-    text: &mut String,
-    tabstops: &mut BTreeMap<usize, TabStop>,
-) -> Result<&'a str> {
-    let tabstop_start = text.len();
-    let tabstop_index;
-    if source.starts_with('{') {
-        let (index, rest) = parse_int(&source[1..])?;
-        tabstop_index = index;
+fn push_text(nodes: &mut Vec<Node>, text: &str) {
+    if let Some(Node::Text(last_text)) = nodes.last_mut() {
+        last_text.push_str(text);
+    } else {
+        nodes.push(Node::Text(text.to_string()));
+    }
+}
+
+fn parse_tabstop_or_variable<'a>(source: &'a str, nodes: &mut Vec<Node>) -> Result<&'a str> {
+    if let Some(source) = source.strip_prefix('{') {
+        if source.starts_with(|c: char| c.is_ascii_digit()) {
+            let (index, source) = parse_int(source)?;
+            parse_tabstop(index, source, nodes)
+        } else if let Some((name, source)) = parse_variable_name(source) {
+            parse_variable(name, source, nodes)
+        } else {
+            Err(anyhow!("expected a tabstop index or a variable name"))
+        }
+    } else if source.starts_with(|c: char| c.is_ascii_digit()) {
+        let (index, source) = parse_int(source)?;
+        nodes.push(Node::TabStop {
+            index,
+            placeholder: None,
+            choices: Vec::new(),
+            transform: None,
+        });
+        Ok(source)
+    } else if let Some((name, source)) = parse_variable_name(source) {
+        nodes.push(Node::Variable {
+            name: name.to_string(),
+            default: None,
+            transform: None,
+        });
+        Ok(source)
+    } else {
+        push_text(nodes, "$");
+        Ok(source)
+    }
+}
+
+/// Parses the rest of a `${...}` tabstop, after its index.
+fn parse_tabstop<'a>(index: usize, mut source: &'a str, nodes: &mut Vec<Node>) -> Result<&'a str> {
+    let mut placeholder = None;
+    let mut choices = Vec::new();
+    let mut transform = None;
+    if let Some(rest) = source.strip_prefix(':') {
+        let mut placeholder_nodes = Vec::new();
+        source = parse_snippet(rest, true, &mut placeholder_nodes)?;
+        placeholder = Some(placeholder_nodes);
+    } else if let Some(rest) = source.strip_prefix('|') {
+        (choices, source) = parse_choices(rest)?;
+    } else if let Some(rest) = source.strip_prefix('/') {
+        let (parsed_transform, rest) = parse_transform(rest)?;
+        transform = Some(parsed_transform);
         source = rest;
+    }
+
+    let source = source
+        .strip_prefix('}')
+        .ok_or_else(|| anyhow!("expected a closing brace"))?;
+    nodes.push(Node::TabStop {
+        index,
+        placeholder,
+        choices,
+        transform,
+    });
+    Ok(source)
+}
+
+/// Parses the rest of a `${...}` variable, after its name.
+fn parse_variable<'a>(name: &str, mut source: &'a str, nodes: &mut Vec<Node>) -> Result<&'a str> {
+    let mut default = None;
+    let mut transform = None;
+    if let Some(rest) = source.strip_prefix(':') {
+        let mut default_nodes = Vec::new();
+        source = parse_snippet(rest, true, &mut default_nodes)?;
+        default = Some(default_nodes);
+    } else if let Some(rest) = source.strip_prefix('/') {
+        let (parsed_transform, rest) = parse_transform(rest)?;
+        transform = Some(parsed_transform);
+        source = rest;
+    }
+
+    let source = source
+        .strip_prefix('}')
+        .ok_or_else(|| anyhow!("expected a closing brace"))?;
+    nodes.push(Node::Variable {
+        name: name.to_string(),
+        default,
+        transform,
+    });
+    Ok(source)
+}
+
+/// Parses the choices of a `${1|one,two|}` tabstop, leaving its closing brace.
+fn parse_choices(mut source: &str) -> Result<(Vec<String>, &str)> {
+    let mut choices = Vec::new();
+    let mut choice = String::new();
+    loop {
+        let mut chars = source.chars();
+        match chars.next() {
+            None => return Err(anyhow!("expected the end of the choices")),
+            Some('\\') => {
+                if let Some(c) = chars.next() {
+                    choice.push(c);
+                }
+            }
+            Some(',') => choices.push(mem::take(&mut choice)),
+            Some('|') if chars.as_str().starts_with('}') => {
+                choices.push(choice);
+                return Ok((choices, chars.as_str()));
+            }
+            Some(c) => choice.push(c),
+        }
+        source = chars.as_str();
+    }
+}
 
-        if source.starts_with(':') {
-            source = parse_snippet(&source[1..], true, text, tabstops)?;
+/// Parses the `regex/format/options` of a transform, leaving its closing brace.
+fn parse_transform(source: &str) -> Result<(Transform, &str)> {
+    let mut pattern = String::new();
+    let mut chars = source.chars();
+    loop {
+        match chars.next() {
+            None => return Err(anyhow!("expected the end of the transform regex")),
+            Some('/') => break,
+            Some('\\') => match chars.next() {
+                Some('/') => pattern.push('/'),
+                Some(c) => {
+                    pattern.push('\\');
+                    pattern.push(c);
+                }
+                None => pattern.push('\\'),
+            },
+            Some(c) => pattern.push(c),
         }
+    }
+
+    let (format, source) = parse_format(chars.as_str())?;
+    let options_len = source
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(source.len());
+    let (options, source) = source.split_at(options_len);
+    Ok((Transform::new(&pattern, format, options)?, source))
+}
 
-        if source.starts_with('}') {
-            source = &source[1..];
+/// Parses the format of a transform, until the slash before its options.
+fn parse_format(mut source: &str) -> Result<(Vec<FormatItem>, &str)> {
+    let mut items = Vec::new();
+    loop {
+        if let Some(rest) = source.strip_prefix('/') {
+            return Ok((items, rest));
+        } else if let Some(rest) = source.strip_prefix('$') {
+            source = parse_format_group(rest, &mut items)?;
+        } else if source.is_empty() {
+            return Err(anyhow!("expected the end of the transform format"));
         } else {
-            return Err(anyhow!("expected a closing brace"));
+            let (text, rest) = parse_format_text(source, &['/', '$']);
+            match items.last_mut() {
+                Some(FormatItem::Text(last_text)) => last_text.push_str(&text),
+                _ => items.push(FormatItem::Text(text)),
+            }
+            source = rest;
         }
-    } else {
-        let (index, rest) = parse_int(source)?;
-        tabstop_index = index;
-        source = rest;
     }
+}
+
+/// Parses a `$1` or `${1...}` format group, after its dollar sign.
+fn parse_format_group<'a>(source: &'a str, items: &mut Vec<FormatItem>) -> Result<&'a str> {
+    if source.starts_with(|c: char| c.is_ascii_digit()) {
+        let (index, source) = parse_int(source)?;
+        items.push(FormatItem::Group {
+            index,
+            case_change: None,
+        });
+        return Ok(source);
+    }
+    let Some(source) = source.strip_prefix('{') else {
+        items.push(FormatItem::Text("$".to_string()));
+        return Ok(source);
+    };
+
+    let (index, source) = parse_int(source)?;
+    let (item, source) = if let Some(source) = source.strip_prefix(":/") {
+        let name_len = source.find('}').unwrap_or(source.len());
+        let (name, source) = source.split_at(name_len);
+        let case_change =
+            CaseChange::parse(name).ok_or_else(|| anyhow!("unknown case change {name:?}"))?;
+        let item = FormatItem::Group {
+            index,
+            case_change: Some(case_change),
+        };
+        (item, source)
+    } else if let Some(source) = source.strip_prefix(":+") {
+        let (if_matched, source) = parse_format_text(source, &['}']);
+        let item = FormatItem::Conditional {
+            index,
+            if_matched: Some(if_matched),
+            otherwise: String::new(),
+        };
+        (item, source)
+    } else if let Some(source) = source.strip_prefix(":?") {
+        let (if_matched, source) = parse_format_text(source, &[':', '}']);
+        let source = source
+            .strip_prefix(':')
+            .ok_or_else(|| anyhow!("expected the else branch of the condition"))?;
+        let (otherwise, source) = parse_format_text(source, &['}']);
+        let item = FormatItem::Conditional {
+            index,
+            if_matched: Some(if_matched),
+            otherwise,
+        };
+        (item, source)
+    } else if let Some(source) = source
+        .strip_prefix(":-")
+        .or_else(|| source.strip_prefix(':'))
+    {
+        let (otherwise, source) = parse_format_text(source, &['}']);
+        let item = FormatItem::Conditional {
+            index,
+            if_matched: None,
+            otherwise,
+        };
+        (item, source)
+    } else {
+        let item = FormatItem::Group {
+            index,
+            case_change: None,
+        };
+        (item, source)
+    };
 
-    tabstops
-        .entry(tabstop_index)
-        .or_default()
-        .push(tabstop_start as isize..text.len() as isize);
+    let source = source
+        .strip_prefix('}')
+        .ok_or_else(|| anyhow!("expected a closing brace"))?;
+    items.push(item);
     Ok(source)
 }
 
+/// Parses the text until one of the unescaped terminators, which it leaves.
+fn parse_format_text<'a>(mut source: &'a str, terminators: &[char]) -> (String, &'a str) {
+    let mut text = String::new();
+    loop {
+        let mut chars = source.chars();
+        match chars.next() {
+            None => return (text, source),
+            Some(c) if terminators.contains(&c) => return (text, source),
+            Some('\\') => {
+                if let Some(c) = chars.next() {
+                    text.push(c);
+                }
+            }
+            Some(c) => text.push(c),
+        }
+        source = chars.as_str();
+    }
+}
+
+fn parse_variable_name(source: &str) -> Option<(&str, &str)> {
+    if !source.starts_with(|c: char| c == '_' || c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let len = source
+        .find(|c: char| c != '_' && !c.is_ascii_alphanumeric())
+        .unwrap_or(source.len());
+    Some(source.split_at(len))
+}
+
 fn parse_int(source: &str) -> Result<(usize, &str)> {
     let len = source
         .find(|c: char| !c.is_ascii_digit())
@@ -188,6 +759,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_snippet_with_mirrors() {
+        // Mirrors repeat the placeholder of their tabstop, wherever it is.
+        let snippet = Snippet::parse("<$1>${1:div}</$1>").unwrap();
+        assert_eq!(snippet.text, "<div>div</div>");
+        assert_eq!(
+            tabstops(&snippet),
+            &[vec![1..4, 5..8, 10..13], vec![14..14]]
+        );
+    }
+
     #[test]
     fn test_snippet_parsing_with_escaped_chars() {
         let snippet = Snippet::parse("\"\\$schema\": $1").unwrap();
@@ -197,9 +779,118 @@ mod tests {
         let snippet = Snippet::parse("{a\\}").unwrap();
         assert_eq!(snippet.text, "{a}");
         assert_eq!(tabstops(&snippet), &[vec![3..3]]);
+
+        // A dollar sign, which starts neither a tabstop nor a variable, is kept.
+        let snippet = Snippet::parse("cost: $ 5").unwrap();
+        assert_eq!(snippet.text, "cost: $ 5");
+    }
+
+    #[test]
+    fn test_snippet_with_choices() {
+        let snippet = Snippet::parse("let x = ${1|one,two\\,three,four|};$0").unwrap();
+        assert_eq!(snippet.text, "let x = one;");
+        assert_eq!(tabstops(&snippet), &[vec![8..11], vec![12..12]]);
+        assert_eq!(snippet.tabstops[0].choices, ["one", "two,three", "four"]);
+        assert!(snippet.tabstops[1].choices.is_empty());
+
+        assert!(Snippet::parse("${1|one,two}").is_err());
+    }
+
+    #[test]
+    fn test_snippet_with_variables() {
+        let resolve_variable = |name: &str| match name {
+            "TM_FILENAME" => Some("main.rs".to_string()),
+            "TM_SELECTED_TEXT" => Some(String::new()),
+            _ => None,
+        };
+
+        let snippet = Snippet::parse_with_variables(
+            "// $TM_FILENAME: ${TM_SELECTED_TEXT}$1",
+            &resolve_variable,
+        )
+        .unwrap();
+        assert_eq!(snippet.text, "// main.rs: ");
+        assert_eq!(tabstops(&snippet), &[vec![12..12]]);
+
+        // Empty and unknown variables use their default.
+        let snippet = Snippet::parse_with_variables(
+            "${TM_SELECTED_TEXT:${1:value}} ${UNKNOWN:default}",
+            &resolve_variable,
+        )
+        .unwrap();
+        assert_eq!(snippet.text, "value default");
+        assert_eq!(tabstops(&snippet), &[vec![0..5], vec![13..13]]);
+
+        // Unknown variables without a default become placeholders after the other tabstops.
+        let snippet =
+            Snippet::parse_with_variables("$UNKNOWN ${2:two} $1 $UNKNOWN", &resolve_variable)
+                .unwrap();
+        assert_eq!(snippet.text, "UNKNOWN two  UNKNOWN");
+        assert_eq!(
+            tabstops(&snippet),
+            &[vec![12..12], vec![8..11], vec![0..7, 13..20], vec![20..20]]
+        );
+    }
+
+    #[test]
+    fn test_snippet_with_transforms() {
+        let resolve_variable = |name: &str| match name {
+            "TM_FILENAME" => Some("user_settings.rs".to_string()),
+            _ => None,
+        };
+
+        let snippet = Snippet::parse_with_variables(
+            "struct ${TM_FILENAME/(.*)\\..+$/${1:/pascalcase}/};",
+            &resolve_variable,
+        )
+        .unwrap();
+        assert_eq!(snippet.text, "struct UserSettings;");
+
+        // Transformed tabstops show their placeholder transformed and are updated when moving
+        // out of the tabstop, but cannot be moved to.
+        let snippet = Snippet::parse("${1:name} ${1/(.)/${1:/upcase}/}$0").unwrap();
+        assert_eq!(snippet.text, "name Name");
+        assert_eq!(tabstops(&snippet), &[vec![0..4], vec![9..9]]);
+        let (range, transform) = &snippet.tabstops[0].transformed_ranges[0];
+        assert_eq!(range, &(5..9));
+        assert_eq!(transform.apply("other"), "Other");
+
+        let snippet = Snippet::parse("${1/a/b/}").unwrap();
+        assert_eq!(snippet.text, "");
+        assert_eq!(tabstops(&snippet), &[vec![0..0]]);
+    }
+
+    #[test]
+    fn test_transform_formats() {
+        let transform = |source: &str| parse_transform(source).unwrap().0;
+
+        assert_eq!(transform("a/b/").apply("aaa"), "baa");
+        assert_eq!(transform("a/b/g").apply("aaa"), "bbb");
+        assert_eq!(transform("A/b/gi").apply("aAa"), "bbb");
+        assert_eq!(transform("\\//-/g").apply("a/b/c"), "a-b-c");
+        assert_eq!(transform("(\\w+)/$1-$1/").apply("ab cd"), "ab-ab cd");
+        assert_eq!(
+            transform("(.*)/${1:/upcase} ${1:/downcase} ${1:/capitalize}/").apply("hELLO"),
+            "HELLO hello HELLO"
+        );
+        assert_eq!(
+            transform("(.*)/${1:/camelcase} ${1:/pascalcase}/").apply("foo-bar_baz"),
+            "fooBarBaz FooBarBaz"
+        );
+        assert_eq!(
+            transform("(a)?b/${1:+yes}${1:?A:B}${1:-none}${1:fallback}/").apply("ab"),
+            "yesAaa"
+        );
+        assert_eq!(
+            transform("(a)?b/${1:+yes}${1:?A:B}${1:-none}${1:fallback}/").apply("b"),
+            "Bnonefallback"
+        );
+        assert_eq!(transform("x/\\$1 \\/ $ \\}/").apply("x"), "$1 / $ }");
+        assert!(parse_transform("(/x/").is_err());
+        assert!(parse_transform("x/${1:/unknown}/").is_err());
     }
 
     fn tabstops(snippet: &Snippet) -> Vec<Vec<Range<isize>>> {
-        snippet.tabstops.iter().map(|t| t.to_vec()).collect()
+        snippet.tabstops.iter().map(|t| t.ranges.to_vec()).collect()
     }
 }
@@ -0,0 +1,311 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use collections::BTreeMap;
+use fs::Fs;
+use futures::StreamExt;
+use gpui::{AppContext, Global};
+use parking_lot::RwLock;
+use serde::Deserialize;
+use util::{paths, ResultExt};
+
+/// A snippet, defined by the user in a snippets file, in the format used by VS Code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserSnippet {
+    pub name: String,
+    /// The words, which, when typed, offer the snippet in the completions.
+    pub prefixes: Vec<String>,
+    /// The body of the snippet, in the LSP snippet grammar.
+    pub body: String,
+    pub description: Option<String>,
+    /// The ids of the languages the snippet is for, or `None`, if it is for all of them.
+    scope: Option<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+struct SnippetContent {
+    prefix: Option<StringOrList>,
+    body: StringOrList,
+    description: Option<StringOrList>,
+    scope: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StringOrList {
+    String(String),
+    List(Vec<String>),
+}
+
+impl StringOrList {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            Self::String(string) => vec![string],
+            Self::List(list) => list,
+        }
+    }
+}
+
+struct SnippetFile {
+    /// The language of a `<language>.json` file, `None` for a global `*.code-snippets` file.
+    language: Option<String>,
+    snippets: Vec<Arc<UserSnippet>>,
+}
+
+#[derive(Default)]
+struct GlobalSnippetRegistry(Arc<SnippetRegistry>);
+
+impl Global for GlobalSnippetRegistry {}
+
+/// The user snippets, loaded from the snippets directory.
+#[derive(Default)]
+pub struct SnippetRegistry {
+    files: RwLock<BTreeMap<PathBuf, SnippetFile>>,
+}
+
+impl SnippetRegistry {
+    /// Returns the global [`SnippetRegistry`], if it was initialized.
+    pub fn try_global(cx: &AppContext) -> Option<Arc<Self>> {
+        cx.try_global::<GlobalSnippetRegistry>()
+            .map(|registry| registry.0.clone())
+    }
+
+    /// Returns the global [`SnippetRegistry`].
+    ///
+    /// Inserts a default [`SnippetRegistry`] if one does not yet exist.
+    pub fn default_global(cx: &mut AppContext) -> Arc<Self> {
+        cx.default_global::<GlobalSnippetRegistry>().0.clone()
+    }
+
+    /// Returns the snippets for the language with the given name, or for plain text.
+    pub fn snippets_for_language(&self, language_name: Option<&str>) -> Vec<Arc<UserSnippet>> {
+        let files = self.files.read();
+        let mut snippets = Vec::new();
+        for file in files.values() {
+            match (&file.language, language_name) {
+                (Some(file_language), Some(language_name)) => {
+                    if language_matches(file_language, language_name) {
+                        snippets.extend(file.snippets.iter().cloned());
+                    }
+                }
+                (Some(_), None) => {}
+                (None, _) => snippets.extend(file.snippets.iter().cloned().filter(|snippet| {
+                    snippet.scope.as_ref().map_or(true, |scope| {
+                        language_name.map_or(false, |language_name| {
+                            scope.iter().any(|id| language_matches(id, language_name))
+                        })
+                    })
+                })),
+            }
+        }
+        snippets
+    }
+
+    /// Parses the snippets file at the given path and adds its snippets, replacing the ones it
+    /// had before.
+    pub fn insert_file(&self, path: &Path, content: &str) -> Result<()> {
+        let file = parse_snippet_file(path, content)
+            .with_context(|| format!("failed to parse snippets file {path:?}"))?;
+        if let Some(file) = file {
+            self.files.write().insert(path.to_path_buf(), file);
+        }
+        Ok(())
+    }
+
+    /// Removes the snippets of the snippets file at the given path.
+    pub fn remove_file(&self, path: &Path) {
+        self.files.write().remove(path);
+    }
+
+    /// Loads the snippets file at the given path, or removes its snippets, if it does not exist
+    /// anymore.
+    pub async fn reload_file(&self, path: &Path, fs: Arc<dyn Fs>) -> Result<()> {
+        if fs.is_file(path).await {
+            let content = fs.load(path).await?;
+            self.insert_file(path, &content)
+        } else {
+            self.remove_file(path);
+            Ok(())
+        }
+    }
+
+    /// Loads all of the snippets files in the given directory.
+    pub async fn load_dir(&self, snippets_dir: &Path, fs: Arc<dyn Fs>) -> Result<()> {
+        let mut paths = fs
+            .read_dir(snippets_dir)
+            .await
+            .with_context(|| format!("reading snippets from {snippets_dir:?}"))?;
+        while let Some(path) = paths.next().await {
+            let Some(path) = path.log_err() else {
+                continue;
+            };
+            self.reload_file(&path, fs.clone()).await.log_err();
+        }
+        Ok(())
+    }
+}
+
+/// Loads the user snippets from the snippets directory and reloads them, when they change.
+pub fn init(fs: Arc<dyn Fs>, cx: &mut AppContext) {
+    let registry = SnippetRegistry::default_global(cx);
+    cx.background_executor()
+        .spawn(async move {
+            let snippets_dir = paths::SNIPPETS_DIR.as_path();
+            match fs
+                .metadata(snippets_dir)
+                .await?
+                .map(|metadata| metadata.is_dir)
+            {
+                Some(is_dir) => {
+                    anyhow::ensure!(is_dir, "Snippets path {snippets_dir:?} is not a directory")
+                }
+                None => fs.create_dir(snippets_dir).await.with_context(|| {
+                    format!("Failed to create snippets dir at path {snippets_dir:?}")
+                })?,
+            }
+            registry.load_dir(snippets_dir, fs.clone()).await?;
+
+            let mut events = fs.watch(snippets_dir, Duration::from_millis(100)).await;
+            while let Some(paths) = events.next().await {
+                for path in paths {
+                    registry.reload_file(&path, fs.clone()).await.log_err();
+                }
+            }
+            anyhow::Ok(())
+        })
+        .detach_and_log_err(cx);
+}
+
+/// Parses a `<language>.json` or a global `*.code-snippets` file, returning `None` for other files.
+fn parse_snippet_file(path: &Path, content: &str) -> Result<Option<SnippetFile>> {
+    let language = match path.extension().and_then(|extension| extension.to_str()) {
+        Some("json") => path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .map(|stem| stem.to_string()),
+        Some("code-snippets") => None,
+        _ => return Ok(None),
+    };
+    if content.trim().is_empty() {
+        return Ok(Some(SnippetFile {
+            language,
+            snippets: Vec::new(),
+        }));
+    }
+
+    let contents: BTreeMap<String, SnippetContent> = serde_json_lenient::from_str(content)?;
+    let snippets = contents
+        .into_iter()
+        .filter_map(|(name, snippet)| {
+            // Snippets without a prefix could never be offered in the completions.
+            let prefixes = snippet.prefix?.into_vec();
+            let scope = snippet.scope.filter(|_| language.is_none()).map(|scope| {
+                scope
+                    .split(',')
+                    .map(|id| id.trim().to_string())
+                    .filter(|id| !id.is_empty())
+                    .collect()
+            });
+            Some(Arc::new(UserSnippet {
+                name,
+                prefixes,
+                body: snippet.body.into_vec().join("\n"),
+                description: snippet
+                    .description
+                    .map(|description| description.into_vec().join("\n")),
+                scope,
+            }))
+        })
+        .collect();
+
+    Ok(Some(SnippetFile { language, snippets }))
+}
+
+/// Returns whether the language id of a snippets file, or of a snippet's scope, is the language
+/// with the given name, either by its lowercase name or by the language id VS Code uses for it.
+fn language_matches(id: &str, language_name: &str) -> bool {
+    let id = id.to_lowercase();
+    id == language_name.to_lowercase() || id == vscode_language_id(language_name)
+}
+
+fn vscode_language_id(language_name: &str) -> String {
+    match language_name {
+        "C++" => "cpp".to_string(),
+        "C#" => "csharp".to_string(),
+        "TSX" => "typescriptreact".to_string(),
+        "JSX" => "javascriptreact".to_string(),
+        "Shell Script" => "shellscript".to_string(),
+        _ => language_name.to_lowercase().replace(' ', ""),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snippets_for_language() {
+        let registry = SnippetRegistry::default();
+        registry
+            .insert_file(
+                Path::new("/snippets/rust.json"),
+                r#"{
+                    // Comments are allowed.
+                    "Print": {
+                        "prefix": ["pr", "print"],
+                        "body": ["println!(\"$1\");", "$0"],
+                        "description": "Print a line"
+                    },
+                    "Without a prefix": {
+                        "body": "unused"
+                    }
+                }"#,
+            )
+            .unwrap();
+        registry
+            .insert_file(
+                Path::new("/snippets/cpp.json"),
+                r##"{ "Include": { "prefix": "inc", "body": "#include <$1>" } }"##,
+            )
+            .unwrap();
+        registry
+            .insert_file(
+                Path::new("/snippets/global.code-snippets"),
+                r#"{
+                    "Date": { "prefix": "date", "body": "$CURRENT_YEAR" },
+                    "Todo": { "prefix": "todo", "body": "TODO: $1", "scope": "rust, shellscript" }
+                }"#,
+            )
+            .unwrap();
+        registry
+            .insert_file(Path::new("/snippets/notes.txt"), "not snippets")
+            .unwrap();
+        assert!(registry
+            .insert_file(Path::new("/snippets/go.json"), "{ invalid")
+            .is_err());
+
+        let names = |language_name| {
+            registry
+                .snippets_for_language(language_name)
+                .iter()
+                .map(|snippet| snippet.name.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(Some("Rust")), ["Date", "Todo", "Print"]);
+        assert_eq!(names(Some("C++")), ["Include", "Date"]);
+        assert_eq!(names(Some("Shell Script")), ["Date", "Todo"]);
+        assert_eq!(names(None), ["Date"]);
+
+        let print = &registry.snippets_for_language(Some("Rust"))[2];
+        assert_eq!(print.prefixes, ["pr", "print"]);
+        assert_eq!(print.body, "println!(\"$1\");\n$0");
+        assert_eq!(print.description.as_deref(), Some("Print a line"));
+
+        registry.remove_file(Path::new("/snippets/rust.json"));
+        assert_eq!(names(Some("Rust")), ["Date", "Todo"]);
+    }
+}
@@ -62,7 +62,7 @@ impl TabSwitcher {
             terminal
                 .focus_handle(cx)
                 .contains_focused(cx)
-                .then(|| terminal.read(cx).pane())
+                .then(|| terminal.read(cx).active_pane())
         });
         let weak_pane = terminal_pane
             .unwrap_or_else(|| workspace.active_pane())
@@ -1,6 +1,7 @@
 //! Baseline interface of Tasks in Zed: all tasks in Zed are intended to use those for implementing their own logic.
 #![deny(missing_docs)]
 
+mod problem_matcher;
 pub mod static_source;
 mod task_template;
 mod vscode_format;
@@ -11,7 +12,14 @@ use serde::Serialize;
 use std::borrow::Cow;
 use std::path::PathBuf;
 
-pub use task_template::{RevealStrategy, TaskTemplate, TaskTemplates};
+pub use problem_matcher::{
+    Problem, ProblemMatcher, ProblemMatcherDefinition, ProblemPattern, ProblemScanner,
+    ProblemSeverity,
+};
+pub use task_template::{
+    BackgroundTask, DependsOrder, RevealStrategy, TaskInput, TaskInputKind, TaskTemplate,
+    TaskTemplates,
+};
 pub use vscode_format::VsCodeTaskFile;
 
 /// Task identifier, unique within the application.
@@ -45,6 +53,21 @@ pub struct SpawnInTerminal {
     pub allow_concurrent_runs: bool,
     /// What to do with the terminal pane and tab, after the command was started.
     pub reveal: RevealStrategy,
+    /// How to run the `dependencies`.
+    pub depends_order: DependsOrder,
+    /// Tasks that have to succeed before this task is started, resolved from the template's `depends_on` labels.
+    pub dependencies: Vec<SpawnInTerminal>,
+    /// Problem matchers to turn the task output into project diagnostics with.
+    pub problem_matchers: Vec<ProblemMatcher>,
+    /// Readiness and restart settings, if the task is a background one.
+    pub background: Option<BackgroundTask>,
+}
+
+impl SpawnInTerminal {
+    /// Whether the task has no command of its own and only runs its dependencies.
+    pub fn is_compound(&self) -> bool {
+        self.command.trim().is_empty()
+    }
 }
 
 /// A final form of the [`TaskTemplate`], that got resolved with a particualar [`TaskContext`] and now is ready to spawn the actual task.
@@ -62,6 +85,8 @@ pub struct ResolvedTask {
     pub resolved_label: String,
     /// Variables that were substituted during the task template resolution.
     substituted_variables: HashSet<VariableName>,
+    /// Answers to the template's inputs, that the task got resolved with.
+    input_answers: TaskVariables,
     /// Further actions that need to take place after the resolved task is spawned,
     /// with all task variables resolved.
     pub resolved: Option<SpawnInTerminal>,
@@ -78,6 +103,11 @@ impl ResolvedTask {
         &self.substituted_variables
     }
 
+    /// Answers to the template's inputs, that the task got resolved with.
+    pub fn input_answers(&self) -> &TaskVariables {
+        &self.input_answers
+    }
+
     /// A human-readable label to display in the UI.
     pub fn display_label(&self) -> &str {
         self.resolved
@@ -107,13 +137,15 @@ pub enum VariableName {
     /// Custom variable, provided by the plugin or other external source.
     /// Will be printed with `ZED_` prefix to avoid potential conflicts with other variables.
     Custom(Cow<'static, str>),
+    /// An answer to the [`TaskInput`] with the given id, asked from the user before the task is spawned.
+    Input(Cow<'static, str>),
 }
 
 impl VariableName {
     /// Generates a `$VARIABLE`-like string value to be used in templates.
-    /// Custom variables are wrapped in `${}` to avoid substitution issues with whitespaces.
+    /// Custom and input variables are wrapped in `${}` to avoid substitution issues with whitespaces.
     pub fn template_value(&self) -> String {
-        if matches!(self, Self::Custom(_)) {
+        if matches!(self, Self::Custom(_) | Self::Input(_)) {
             format!("${{{self}}}")
         } else {
             format!("${self}")
@@ -135,6 +167,7 @@ impl std::fmt::Display for VariableName {
             Self::SelectedText => write!(f, "{ZED_VARIABLE_NAME_PREFIX}SELECTED_TEXT"),
             Self::RunnableSymbol => write!(f, "{ZED_VARIABLE_NAME_PREFIX}RUNNABLE_SYMBOL"),
             Self::Custom(s) => write!(f, "{ZED_VARIABLE_NAME_PREFIX}CUSTOM_{s}"),
+            Self::Input(s) => write!(f, "{ZED_VARIABLE_NAME_PREFIX}INPUT_{s}"),
         }
     }
 }
@@ -153,6 +186,11 @@ impl TaskVariables {
     pub fn extend(&mut self, other: Self) {
         self.0.extend(other.0);
     }
+
+    /// Returns the value of the variable, if it is present in the container.
+    pub fn get(&self, variable: &VariableName) -> Option<&str> {
+        self.0.get(variable).map(String::as_str)
+    }
 }
 
 impl FromIterator<(VariableName, String)> for TaskVariables {
@@ -0,0 +1,609 @@
+//! Problem matchers scan the output of a task and turn it into a list of [`Problem`]s,
+//! that are displayed as diagnostics in the project.
+
+use std::{borrow::Cow, path::PathBuf};
+
+use anyhow::{anyhow, Context};
+use regex::{Captures, Regex};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use util::ResultExt;
+
+/// A problem matcher to apply to the task output: either a name of a built-in one, or a custom definition.
+///
+/// Built-in matchers are: `$rustc`, `$tsc`, `$tsc-watch`, `$gcc` and `$eslint-stylish`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum ProblemMatcher {
+    /// A name of a built-in problem matcher, e.g. `$rustc`.
+    BuiltIn(String),
+    /// A custom, regex-based problem matcher.
+    Custom(ProblemMatcherDefinition),
+}
+
+/// A custom problem matcher, that matches task output lines against a sequence of regex patterns.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ProblemMatcherDefinition {
+    /// A name of the tool that reports the problems, displayed as the diagnostic source.
+    /// Defaults to the task label.
+    #[serde(default)]
+    pub source: Option<String>,
+    /// A severity to use for problems, whose patterns do not capture one.
+    #[serde(default)]
+    pub severity: ProblemSeverity,
+    /// Patterns to match consecutive output lines against, a problem is reported after the last of them matches.
+    pub pattern: Vec<ProblemPattern>,
+}
+
+/// A single line pattern of a problem matcher.
+/// All capture group indices are 1-based, as in the regex itself.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ProblemPattern {
+    /// A regular expression to match the output line against.
+    pub regexp: String,
+    /// A capture group with the file path, relative to the task's working directory or absolute.
+    #[serde(default)]
+    pub file: Option<usize>,
+    /// A capture group with the 1-based line of the problem.
+    #[serde(default)]
+    pub line: Option<usize>,
+    /// A capture group with the 1-based column of the problem.
+    #[serde(default)]
+    pub column: Option<usize>,
+    /// A capture group with the 1-based line the problem ends at.
+    #[serde(default)]
+    pub end_line: Option<usize>,
+    /// A capture group with the 1-based column the problem ends at.
+    #[serde(default)]
+    pub end_column: Option<usize>,
+    /// A capture group with the problem severity: `error`, `warning`, `info` or `hint`.
+    #[serde(default)]
+    pub severity: Option<usize>,
+    /// A capture group with the problem code.
+    #[serde(default)]
+    pub code: Option<usize>,
+    /// A capture group with the problem message.
+    #[serde(default)]
+    pub message: Option<usize>,
+    /// Whether the pattern may match multiple consecutive lines, reporting a problem for each of them.
+    /// Only allowed for the last pattern of a matcher.
+    #[serde(default, rename = "loop")]
+    pub is_loop: bool,
+}
+
+/// Severity of a [`Problem`].
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProblemSeverity {
+    /// An error, the default severity.
+    #[default]
+    Error,
+    /// A warning.
+    Warning,
+    /// An informational message.
+    Info,
+    /// A hint.
+    Hint,
+}
+
+impl ProblemSeverity {
+    fn parse(text: &str) -> Option<Self> {
+        match text.trim().to_lowercase().as_str() {
+            "error" | "fatal" | "fatal error" | "e" => Some(Self::Error),
+            "warning" | "warn" | "w" => Some(Self::Warning),
+            "info" | "information" | "note" | "i" => Some(Self::Info),
+            "hint" | "help" | "h" => Some(Self::Hint),
+            _ => None,
+        }
+    }
+}
+
+/// A problem, found in the task output.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Problem {
+    /// Path to the file with the problem, as printed by the task.
+    pub path: PathBuf,
+    /// 1-based line of the problem.
+    pub line: u32,
+    /// 1-based column of the problem, if known.
+    pub column: Option<u32>,
+    /// 1-based line the problem ends at, if known.
+    pub end_line: Option<u32>,
+    /// 1-based column the problem ends at, if known.
+    pub end_column: Option<u32>,
+    /// Severity of the problem.
+    pub severity: ProblemSeverity,
+    /// Problem code, if any.
+    pub code: Option<String>,
+    /// Problem description.
+    pub message: String,
+    /// A name of the tool that reported the problem, if the matcher specifies one.
+    pub source: Option<String>,
+}
+
+impl ProblemMatcher {
+    /// Returns the matcher definition, looking up the built-in matchers by their names.
+    pub fn definition(&self) -> anyhow::Result<Cow<'_, ProblemMatcherDefinition>> {
+        match self {
+            Self::BuiltIn(name) => built_in_matcher(name)
+                .map(Cow::Owned)
+                .ok_or_else(|| anyhow!("unknown problem matcher `{name}`")),
+            Self::Custom(definition) => Ok(Cow::Borrowed(definition)),
+        }
+    }
+}
+
+fn pattern(regexp: &str) -> ProblemPattern {
+    ProblemPattern {
+        regexp: regexp.to_owned(),
+        ..ProblemPattern::default()
+    }
+}
+
+fn built_in_matcher(name: &str) -> Option<ProblemMatcherDefinition> {
+    let (source, pattern) = match name {
+        "$rustc" => (
+            "rustc",
+            vec![
+                ProblemPattern {
+                    severity: Some(1),
+                    code: Some(2),
+                    message: Some(3),
+                    ..pattern(r"^(warning|error)(?:\[(\S+)\])?: (.*)$")
+                },
+                ProblemPattern {
+                    file: Some(1),
+                    line: Some(2),
+                    column: Some(3),
+                    ..pattern(r"^\s*-->\s+(.+?):(\d+):(\d+)$")
+                },
+            ],
+        ),
+        "$tsc" | "$tsc-watch" => (
+            "tsc",
+            vec![ProblemPattern {
+                file: Some(1),
+                line: Some(2),
+                column: Some(3),
+                severity: Some(4),
+                code: Some(5),
+                message: Some(6),
+                ..pattern(
+                    r"^([^\s].*)[\(:](\d+)[,:](\d+)(?:\):\s+|\s+-\s+)(error|warning|info)\s+(TS\d+)\s*:\s*(.*)$",
+                )
+            }],
+        ),
+        "$gcc" => (
+            "gcc",
+            vec![ProblemPattern {
+                file: Some(1),
+                line: Some(2),
+                column: Some(3),
+                severity: Some(4),
+                message: Some(5),
+                ..pattern(r"^(.*?):(\d+):(\d*):?\s+(?:fatal\s+)?(warning|error):\s+(.*)$")
+            }],
+        ),
+        "$eslint-stylish" => (
+            "eslint",
+            vec![
+                ProblemPattern {
+                    file: Some(1),
+                    ..pattern(r"^([^\s].*)$")
+                },
+                ProblemPattern {
+                    line: Some(1),
+                    column: Some(2),
+                    severity: Some(3),
+                    message: Some(4),
+                    code: Some(5),
+                    is_loop: true,
+                    ..pattern(r"^\s+(\d+):(\d+)\s+(error|warning|info)\s+(.*?)(?:\s\s+(.*))?$")
+                },
+            ],
+        ),
+        _ => return None,
+    };
+    Some(ProblemMatcherDefinition {
+        source: Some(source.to_owned()),
+        severity: ProblemSeverity::Error,
+        pattern,
+    })
+}
+
+/// Feeds task output lines into a set of problem matchers, collecting the problems found.
+#[derive(Debug, Default)]
+pub struct ProblemScanner {
+    matchers: Vec<MatcherState>,
+    problems: Vec<Problem>,
+}
+
+#[derive(Debug)]
+struct MatcherState {
+    source: Option<String>,
+    severity: ProblemSeverity,
+    patterns: Vec<(Regex, ProblemPattern)>,
+    next_pattern: usize,
+    pending: PendingProblem,
+}
+
+#[derive(Debug, Default, Clone)]
+struct PendingProblem {
+    path: Option<String>,
+    line: Option<u32>,
+    column: Option<u32>,
+    end_line: Option<u32>,
+    end_column: Option<u32>,
+    severity: Option<ProblemSeverity>,
+    code: Option<String>,
+    message: Option<String>,
+}
+
+impl ProblemScanner {
+    /// Prepares the matchers for scanning, skipping (and logging) the ones that are unknown or invalid.
+    pub fn new(matchers: &[ProblemMatcher]) -> Self {
+        let matchers = matchers
+            .iter()
+            .filter_map(|matcher| MatcherState::new(matcher).log_err())
+            .collect();
+        Self {
+            matchers,
+            problems: Vec::new(),
+        }
+    }
+
+    /// Whether there are no matchers to scan the output with.
+    pub fn is_empty(&self) -> bool {
+        self.matchers.is_empty()
+    }
+
+    /// Matches a single line of the output, without its line terminator.
+    pub fn scan_line(&mut self, line: &str) {
+        let line = line.trim_end();
+        for matcher in &mut self.matchers {
+            matcher.scan_line(line, &mut self.problems);
+        }
+    }
+
+    /// Matches every line of the output given.
+    pub fn scan(&mut self, output: &str) {
+        for line in output.lines() {
+            self.scan_line(line);
+        }
+    }
+
+    /// Returns the problems found since the last call.
+    pub fn take_problems(&mut self) -> Vec<Problem> {
+        std::mem::take(&mut self.problems)
+    }
+}
+
+impl MatcherState {
+    fn new(matcher: &ProblemMatcher) -> anyhow::Result<Self> {
+        let definition = matcher.definition()?;
+        anyhow::ensure!(
+            !definition.pattern.is_empty(),
+            "problem matcher has no patterns"
+        );
+        let last_pattern = definition.pattern.len() - 1;
+        let patterns = definition
+            .pattern
+            .iter()
+            .enumerate()
+            .map(|(ix, pattern)| {
+                anyhow::ensure!(
+                    !pattern.is_loop || ix == last_pattern,
+                    "only the last problem matcher pattern may loop"
+                );
+                let regex = Regex::new(&pattern.regexp).with_context(|| {
+                    format!("invalid problem matcher pattern `{}`", pattern.regexp)
+                })?;
+                Ok((regex, pattern.clone()))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self {
+            source: definition.source.clone(),
+            severity: definition.severity,
+            patterns,
+            next_pattern: 0,
+            pending: PendingProblem::default(),
+        })
+    }
+
+    fn scan_line(&mut self, line: &str, problems: &mut Vec<Problem>) {
+        if self.next_pattern > 0 && self.match_pattern(self.next_pattern, line, problems) {
+            return;
+        }
+        self.next_pattern = 0;
+        self.pending = PendingProblem::default();
+        self.match_pattern(0, line, problems);
+    }
+
+    fn match_pattern(&mut self, ix: usize, line: &str, problems: &mut Vec<Problem>) -> bool {
+        let (regex, pattern) = &self.patterns[ix];
+        let Some(captures) = regex.captures(line) else {
+            return false;
+        };
+
+        let is_last = ix + 1 == self.patterns.len();
+        let mut pending = if is_last && pattern.is_loop {
+            // Every looped line reports its own problem, based on the lines matched before the loop.
+            self.pending.clone()
+        } else {
+            std::mem::take(&mut self.pending)
+        };
+        pending.fill(pattern, &captures);
+
+        if is_last {
+            if let Some(problem) = pending.into_problem(self.severity, self.source.clone()) {
+                problems.push(problem);
+            }
+            self.next_pattern = if pattern.is_loop { ix } else { 0 };
+        } else {
+            self.pending = pending;
+            self.next_pattern = ix + 1;
+        }
+        true
+    }
+}
+
+impl PendingProblem {
+    fn fill(&mut self, pattern: &ProblemPattern, captures: &Captures) {
+        let text = |group: Option<usize>| {
+            group
+                .and_then(|group| captures.get(group))
+                .map(|capture| capture.as_str().trim())
+                .filter(|text| !text.is_empty())
+        };
+        let number = |group: Option<usize>| text(group).and_then(|text| text.parse::<u32>().ok());
+
+        if let Some(path) = text(pattern.file) {
+            self.path = Some(path.to_owned());
+        }
+        if let Some(line) = number(pattern.line) {
+            self.line = Some(line);
+        }
+        if let Some(column) = number(pattern.column) {
+            self.column = Some(column);
+        }
+        if let Some(end_line) = number(pattern.end_line) {
+            self.end_line = Some(end_line);
+        }
+        if let Some(end_column) = number(pattern.end_column) {
+            self.end_column = Some(end_column);
+        }
+        if let Some(severity) = text(pattern.severity).and_then(ProblemSeverity::parse) {
+            self.severity = Some(severity);
+        }
+        if let Some(code) = text(pattern.code) {
+            self.code = Some(code.to_owned());
+        }
+        if let Some(message) = text(pattern.message) {
+            self.message = Some(message.to_owned());
+        }
+    }
+
+    fn into_problem(
+        self,
+        default_severity: ProblemSeverity,
+        source: Option<String>,
+    ) -> Option<Problem> {
+        Some(Problem {
+            path: PathBuf::from(self.path?),
+            line: self.line.unwrap_or(1).max(1),
+            column: self.column,
+            end_line: self.end_line,
+            end_column: self.end_column,
+            severity: self.severity.unwrap_or(default_severity),
+            code: self.code,
+            message: self.message?,
+            source,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scan(matcher: ProblemMatcher, output: &str) -> Vec<Problem> {
+        let mut scanner = ProblemScanner::new(&[matcher]);
+        assert!(!scanner.is_empty());
+        scanner.scan(output);
+        scanner.take_problems()
+    }
+
+    fn problem(path: &str, line: u32, column: Option<u32>, message: &str) -> Problem {
+        Problem {
+            path: PathBuf::from(path),
+            line,
+            column,
+            end_line: None,
+            end_column: None,
+            severity: ProblemSeverity::Error,
+            code: None,
+            message: message.to_owned(),
+            source: None,
+        }
+    }
+
+    #[test]
+    fn test_rustc_matcher() {
+        let output = r#"   Compiling foo v0.1.0 (/tmp/foo)
+warning: unused variable: `x`
+ --> src/main.rs:2:9
+  |
+2 |     let x = 5;
+  |         ^ help: if this is intentional, prefix it with an underscore: `_x`
+  |
+error[E0425]: cannot find value `y` in this scope
+  --> src/lib.rs:10:5
+   |
+10 |     y
+   |     ^ not found in this scope
+
+error: aborting due to 1 previous error; 1 warning emitted
+"#;
+        assert_eq!(
+            scan(ProblemMatcher::BuiltIn("$rustc".to_owned()), output),
+            vec![
+                Problem {
+                    severity: ProblemSeverity::Warning,
+                    source: Some("rustc".to_owned()),
+                    ..problem("src/main.rs", 2, Some(9), "unused variable: `x`")
+                },
+                Problem {
+                    code: Some("E0425".to_owned()),
+                    source: Some("rustc".to_owned()),
+                    ..problem(
+                        "src/lib.rs",
+                        10,
+                        Some(5),
+                        "cannot find value `y` in this scope"
+                    )
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tsc_and_gcc_matchers() {
+        let tsc_output =
+            "src/index.ts(3,7): error TS2322: Type 'string' is not assignable to type 'number'.\n\
+            src/util.ts:12:1 - warning TS6133: 'foo' is declared but its value is never read.\n";
+        assert_eq!(
+            scan(ProblemMatcher::BuiltIn("$tsc".to_owned()), tsc_output),
+            vec![
+                Problem {
+                    code: Some("TS2322".to_owned()),
+                    source: Some("tsc".to_owned()),
+                    ..problem(
+                        "src/index.ts",
+                        3,
+                        Some(7),
+                        "Type 'string' is not assignable to type 'number'."
+                    )
+                },
+                Problem {
+                    code: Some("TS6133".to_owned()),
+                    severity: ProblemSeverity::Warning,
+                    source: Some("tsc".to_owned()),
+                    ..problem(
+                        "src/util.ts",
+                        12,
+                        Some(1),
+                        "'foo' is declared but its value is never read."
+                    )
+                },
+            ]
+        );
+
+        let gcc_output = "main.c: In function 'main':\n\
+            main.c:4:5: warning: implicit declaration of function 'foo'\n\
+            main.c:7:: fatal error: bar.h: No such file or directory\n";
+        assert_eq!(
+            scan(ProblemMatcher::BuiltIn("$gcc".to_owned()), gcc_output),
+            vec![
+                Problem {
+                    severity: ProblemSeverity::Warning,
+                    source: Some("gcc".to_owned()),
+                    ..problem(
+                        "main.c",
+                        4,
+                        Some(5),
+                        "implicit declaration of function 'foo'"
+                    )
+                },
+                Problem {
+                    source: Some("gcc".to_owned()),
+                    ..problem("main.c", 7, None, "bar.h: No such file or directory")
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_looping_matcher() {
+        let output = "/project/src/a.js\n  \
+            1:10  error    'x' is not defined      no-undef\n  \
+            3:1   warning  Unexpected console statement  no-console\n\
+            \n\
+            /project/src/b.js\n  \
+            7:2  error  Missing semicolon  semi\n\
+            \n\
+            ✖ 3 problems (2 errors, 1 warning)\n";
+        let problems = scan(
+            ProblemMatcher::BuiltIn("$eslint-stylish".to_owned()),
+            output,
+        );
+        assert_eq!(
+            problems
+                .iter()
+                .map(|problem| (
+                    problem.path.to_string_lossy().to_string(),
+                    problem.line,
+                    problem.severity,
+                    problem.message.as_str(),
+                    problem.code.as_deref(),
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "/project/src/a.js".to_owned(),
+                    1,
+                    ProblemSeverity::Error,
+                    "'x' is not defined",
+                    Some("no-undef"),
+                ),
+                (
+                    "/project/src/a.js".to_owned(),
+                    3,
+                    ProblemSeverity::Warning,
+                    "Unexpected console statement",
+                    Some("no-console"),
+                ),
+                (
+                    "/project/src/b.js".to_owned(),
+                    7,
+                    ProblemSeverity::Error,
+                    "Missing semicolon",
+                    Some("semi"),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_custom_matcher() {
+        let matcher: ProblemMatcher = serde_json_lenient::from_str(
+            r#"{
+                "severity": "warning",
+                "pattern": [
+                    { "regexp": "^lint: (\\S+) line (\\d+): (.*)$", "file": 1, "line": 2, "message": 3 }
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            scan(
+                matcher,
+                "lint: foo.py line 4: trailing whitespace\nall done\n"
+            ),
+            vec![Problem {
+                severity: ProblemSeverity::Warning,
+                ..problem("foo.py", 4, None, "trailing whitespace")
+            }]
+        );
+
+        assert!(ProblemScanner::new(&[ProblemMatcher::BuiltIn("$unknown".to_owned())]).is_empty());
+        assert!(
+            ProblemScanner::new(&[ProblemMatcher::Custom(ProblemMatcherDefinition {
+                pattern: vec![pattern("(unclosed")],
+                ..ProblemMatcherDefinition::default()
+            })])
+            .is_empty()
+        );
+    }
+}
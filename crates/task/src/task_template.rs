@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::{borrow::Cow, path::PathBuf};
 
 use anyhow::{bail, Context};
 use collections::{HashMap, HashSet};
@@ -8,7 +8,8 @@ use sha2::{Digest, Sha256};
 use util::{truncate_and_remove_front, ResultExt};
 
 use crate::{
-    ResolvedTask, SpawnInTerminal, TaskContext, TaskId, VariableName, ZED_VARIABLE_NAME_PREFIX,
+    ProblemMatcher, ResolvedTask, SpawnInTerminal, TaskContext, TaskId, VariableName,
+    ZED_VARIABLE_NAME_PREFIX,
 };
 
 /// A template definition of a Zed task to run.
@@ -62,6 +63,114 @@ pub struct TaskTemplate {
     /// Represents the tags which this template attaches to. Adding this removes this task from other UI.
     #[serde(default)]
     pub tags: Vec<String>,
+
+    /// Labels of the tasks that have to succeed before this task is started.
+    /// A task without a `command` but with dependencies is a compound task, that only runs its dependencies.
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    /// How to run the tasks from `depends_on`:
+    /// * `sequence` — one after another, in the order they are listed, stopping at the first failure (default)
+    /// * `parallel` — all at once
+    #[serde(default)]
+    pub depends_order: DependsOrder,
+
+    /// Problem matchers that scan the task output and report the problems found as project diagnostics,
+    /// replacing the ones reported by the previous run of the same task.
+    /// Either names of the built-in matchers (`$rustc`, `$tsc`, `$gcc`, `$eslint-stylish`) or custom regex-based definitions.
+    #[serde(default)]
+    pub problem_matchers: Vec<ProblemMatcher>,
+
+    /// Makes the task a long-running background one, e.g. a dev server or a file watcher,
+    /// that is never waited for to finish, but reports whether it is ready or rebuilding instead.
+    #[serde(default)]
+    pub background: Option<BackgroundTask>,
+
+    /// Values to ask the user for before the task is spawned, available in the other fields as `$ZED_INPUT_<id>` variables.
+    #[serde(default)]
+    pub inputs: Vec<TaskInput>,
+}
+
+/// A value to ask the user for, before the task is spawned.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct TaskInput {
+    /// An identifier of the input, its answer is substituted into the `$ZED_INPUT_<id>` task variable.
+    pub id: String,
+    /// A text to show in the prompt.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// An answer to propose by default.
+    #[serde(default)]
+    pub default: Option<String>,
+    /// How to get the answer from the user.
+    #[serde(flatten)]
+    pub kind: TaskInputKind,
+}
+
+/// How to get the answer to a [`TaskInput`] from the user.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TaskInputKind {
+    /// Any text typed in by the user.
+    Text,
+    /// One of the options listed.
+    PickString {
+        /// Options to pick from.
+        options: Vec<String>,
+    },
+    /// An absolute path of a file from the project.
+    PickFile {
+        /// A glob to filter the files with, matched against their worktree-relative paths.
+        #[serde(default)]
+        glob: Option<String>,
+    },
+}
+
+impl TaskInput {
+    /// A task variable to substitute the answer into.
+    pub fn variable_name(&self) -> VariableName {
+        VariableName::Input(Cow::Owned(self.id.clone()))
+    }
+
+    /// An answer to use when the user was not asked: the default one, or the first option to pick from.
+    pub fn default_answer(&self) -> String {
+        match (&self.default, &self.kind) {
+            (Some(default), _) => default.clone(),
+            (None, TaskInputKind::PickString { options }) => {
+                options.first().cloned().unwrap_or_default()
+            }
+            (None, TaskInputKind::Text | TaskInputKind::PickFile { .. }) => String::new(),
+        }
+    }
+}
+
+/// Readiness and restart settings of a background task.
+#[derive(Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct BackgroundTask {
+    /// A regex matching the output line that starts a (re)build: the task is marked as rebuilding
+    /// and its previously reported problems are cleared.
+    #[serde(default)]
+    pub begin_pattern: Option<String>,
+    /// A regex matching the output line that ends a (re)build: the task is marked as ready
+    /// and the problems found during the build are reported.
+    /// Without it, the task is ready as soon as it is started.
+    #[serde(default)]
+    pub end_pattern: Option<String>,
+    /// Globs of the worktree files that cause the task to be restarted when they change.
+    #[serde(default)]
+    pub restart_on_change: Vec<String>,
+}
+
+/// How to run the dependencies of a task.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DependsOrder {
+    /// Run the dependencies one after another, in the order they are listed, stopping at the first failure.
+    #[default]
+    Sequence,
+    /// Run all dependencies at once.
+    Parallel,
 }
 
 /// What to do with the terminal pane and tab, after the command was started.
@@ -99,7 +208,9 @@ impl TaskTemplate {
     /// Every [`ResolvedTask`] gets a [`TaskId`], based on the `id_base` (to avoid collision with various task sources),
     /// and hashes of its template and [`TaskContext`], see [`ResolvedTask`] fields' documentation for more details.
     pub fn resolve_task(&self, id_base: &str, cx: &TaskContext) -> Option<ResolvedTask> {
-        if self.label.trim().is_empty() || self.command.trim().is_empty() {
+        if self.label.trim().is_empty()
+            || (self.command.trim().is_empty() && self.depends_on.is_empty())
+        {
             return None;
         }
 
@@ -181,9 +292,19 @@ impl TaskTemplate {
             &mut substituted_variables,
         )?;
         env.extend(task_variables.into_iter().map(|(k, v)| (k, v.to_owned())));
+        let input_answers = self
+            .inputs
+            .iter()
+            .filter_map(|input| {
+                let variable = input.variable_name();
+                let answer = cx.task_variables.get(&variable)?.to_owned();
+                Some((variable, answer))
+            })
+            .collect();
         Some(ResolvedTask {
             id: id.clone(),
             substituted_variables,
+            input_answers,
             original_task: self.clone(),
             resolved_label: full_label.clone(),
             resolved: Some(SpawnInTerminal {
@@ -205,6 +326,10 @@ impl TaskTemplate {
                 use_new_terminal: self.use_new_terminal,
                 allow_concurrent_runs: self.allow_concurrent_runs,
                 reveal: self.reveal,
+                depends_order: self.depends_order,
+                dependencies: Vec::new(),
+                problem_matchers: self.problem_matchers.clone(),
+                background: self.background.clone(),
             }),
         })
     }
@@ -355,6 +480,38 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_resolving_compound_templates() {
+        let compound_task = TaskTemplate {
+            label: "build and test".to_string(),
+            depends_on: vec!["build".to_string(), "test".to_string()],
+            depends_order: DependsOrder::Parallel,
+            ..TaskTemplate::default()
+        };
+        let resolved = compound_task
+            .resolve_task(TEST_ID_BASE, &TaskContext::default())
+            .unwrap()
+            .resolved
+            .unwrap();
+        assert!(resolved.is_compound());
+        assert_eq!(resolved.depends_order, DependsOrder::Parallel);
+        assert!(
+            resolved.dependencies.is_empty(),
+            "dependencies are resolved by the task inventory, not by the template"
+        );
+
+        let task_with_dependencies = TaskTemplate {
+            command: "cargo test".to_string(),
+            ..compound_task
+        };
+        let resolved = task_with_dependencies
+            .resolve_task(TEST_ID_BASE, &TaskContext::default())
+            .unwrap()
+            .resolved
+            .unwrap();
+        assert!(!resolved.is_compound());
+    }
+
     #[test]
     fn test_template_cwd_resolution() {
         let task_without_cwd = TaskTemplate {
@@ -615,6 +772,55 @@ mod tests {
             .is_none());
     }
 
+    #[test]
+    fn test_resolving_task_inputs() {
+        let task = TaskTemplate {
+            label: "test ${ZED_INPUT_target}".into(),
+            command: "cargo".into(),
+            args: vec!["test".into(), "-p".into(), "${ZED_INPUT_target}".into()],
+            inputs: serde_json_lenient::from_str(
+                r#"[{ "id": "target", "type": "pick_string", "options": ["client", "server"] }]"#,
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+        assert_eq!(
+            task.inputs,
+            vec![TaskInput {
+                id: "target".into(),
+                description: None,
+                default: None,
+                kind: TaskInputKind::PickString {
+                    options: vec!["client".into(), "server".into()],
+                },
+            }]
+        );
+        assert_eq!(task.inputs[0].default_answer(), "client");
+        assert!(
+            task.resolve_task(TEST_ID_BASE, &TaskContext::default())
+                .is_none(),
+            "Should not resolve a task without the answers to its inputs"
+        );
+
+        let input_answers = TaskVariables::from_iter([(
+            VariableName::Input(Cow::Borrowed("target")),
+            "server".to_string(),
+        )]);
+        let resolved_task = task
+            .resolve_task(
+                TEST_ID_BASE,
+                &TaskContext {
+                    cwd: None,
+                    task_variables: input_answers.clone(),
+                },
+            )
+            .unwrap();
+        assert_eq!(resolved_task.input_answers(), &input_answers);
+        let resolved = resolved_task.resolved.unwrap();
+        assert_eq!(resolved.label, "test server");
+        assert_eq!(resolved.args, vec!["test", "-p", "server"]);
+    }
+
     #[test]
     fn test_symbol_dependent_tasks() {
         let task_with_all_properties = TaskTemplate {
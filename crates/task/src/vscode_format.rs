@@ -1,9 +1,12 @@
-use anyhow::bail;
+use anyhow::{anyhow, bail};
 use collections::HashMap;
 use serde::Deserialize;
 use util::ResultExt;
 
-use crate::{TaskTemplate, TaskTemplates, VariableName};
+use crate::{
+    BackgroundTask, DependsOrder, ProblemMatcher, ProblemMatcherDefinition, ProblemPattern,
+    ProblemSeverity, TaskInput, TaskInputKind, TaskTemplate, TaskTemplates, VariableName,
+};
 
 #[derive(Clone, Debug, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -63,6 +66,13 @@ impl EnvVariableReplacer {
                     ret.push_str(default);
                 }
             };
+            if let Some(input_id) = default
+                .strip_prefix(':')
+                .filter(|_| variable_name == "input")
+            {
+                // `${input:id}` refers to an answer to the input with the given id.
+                return Some(VariableName::Input(input_id.to_owned().into()).template_value());
+            }
             if let Some(substitution) = self.variables.get(variable_name) {
                 // Got a VSCode->Zed hit, perform a substitution
                 let mut name = format!("${{{substitution}");
@@ -85,21 +95,49 @@ impl EnvVariableReplacer {
 
 impl VsCodeTaskDefinition {
     fn to_zed_format(self, replacer: &EnvVariableReplacer) -> anyhow::Result<TaskTemplate> {
-        if self.other_attributes.contains_key("dependsOn") {
-            bail!("Encountered unsupported `dependsOn` key during deserialization");
-        }
-        // `type` might not be set in e.g. tasks that use `dependsOn`; we still want to deserialize the whole object though (hence command is an Option),
-        // as that way we can provide more specific description of why deserialization failed.
-        // E.g. if the command is missing due to `dependsOn` presence, we can check other_attributes first before doing this (and provide nice error message)
-        // catch-all if on value.command presence.
-        let Some(command) = self.command else {
-            bail!("Missing `type` field in task");
+        let depends_on = match self.other_attributes.get("dependsOn") {
+            None => Vec::new(),
+            Some(serde_json_lenient::Value::String(label)) => vec![label.clone()],
+            Some(serde_json_lenient::Value::Array(labels)) => labels
+                .iter()
+                .map(|label| match label {
+                    serde_json_lenient::Value::String(label) => Ok(label.clone()),
+                    _ => Err(anyhow!("Unsupported `dependsOn` entry {label}")),
+                })
+                .collect::<anyhow::Result<_>>()?,
+            Some(other) => bail!("Unsupported `dependsOn` value {other}"),
         };
-
-        let (command, args) = match command {
-            Command::Npm { script } => ("npm".to_owned(), vec!["run".to_string(), script]),
-            Command::Shell { command, args } => (command, args),
-            Command::Gulp { task } => ("gulp".to_owned(), vec![task]),
+        let depends_order = match self.other_attributes.get("dependsOrder") {
+            Some(serde_json_lenient::Value::String(order)) if order == "parallel" => {
+                DependsOrder::Parallel
+            }
+            _ => DependsOrder::Sequence,
+        };
+        let problem_matchers = match self.other_attributes.get("problemMatcher") {
+            None => Vec::new(),
+            Some(serde_json_lenient::Value::Array(matchers)) => matchers
+                .iter()
+                .filter_map(|matcher| to_zed_problem_matcher(matcher).log_err())
+                .collect(),
+            Some(matcher) => to_zed_problem_matcher(matcher)
+                .log_err()
+                .into_iter()
+                .collect(),
+        };
+        let background = match self.other_attributes.get("isBackground") {
+            Some(serde_json_lenient::Value::Bool(true)) => Some(to_zed_background_task(
+                self.other_attributes.get("problemMatcher"),
+            )),
+            _ => None,
+        };
+        // `type` might not be set in tasks that use `dependsOn`; we still want to deserialize the whole object though (hence command is an Option),
+        // as such tasks are converted into compound ones.
+        let (command, args) = match self.command {
+            Some(Command::Npm { script }) => ("npm".to_owned(), vec!["run".to_string(), script]),
+            Some(Command::Shell { command, args }) => (command, args),
+            Some(Command::Gulp { task }) => ("gulp".to_owned(), vec![task]),
+            None if !depends_on.is_empty() => (String::new(), Vec::new()),
+            None => bail!("Missing `type` field in task"),
         };
         // Per VSC docs, only `command`, `args` and `options` support variable substitution.
         let command = replacer.replace(&command);
@@ -108,6 +146,10 @@ impl VsCodeTaskDefinition {
             label: self.label,
             command,
             args,
+            depends_on,
+            depends_order,
+            problem_matchers,
+            background,
             ..Default::default()
         };
         if let Some(options) = self.options {
@@ -118,10 +160,221 @@ impl VsCodeTaskDefinition {
     }
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VsCodeProblemMatcher {
+    base: Option<String>,
+    owner: Option<String>,
+    source: Option<String>,
+    severity: Option<String>,
+    pattern: Option<VsCodeProblemPatterns>,
+    background: Option<VsCodeBackgroundMatcher>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VsCodeBackgroundMatcher {
+    begins_pattern: Option<VsCodeBackgroundPattern>,
+    ends_pattern: Option<VsCodeBackgroundPattern>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum VsCodeBackgroundPattern {
+    Regexp(String),
+    Object { regexp: String },
+}
+
+impl VsCodeBackgroundPattern {
+    fn into_regexp(self) -> String {
+        match self {
+            Self::Regexp(regexp) | Self::Object { regexp } => regexp,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum VsCodeProblemPatterns {
+    Single(VsCodeProblemPattern),
+    Multiple(Vec<VsCodeProblemPattern>),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VsCodeProblemPattern {
+    regexp: String,
+    file: Option<usize>,
+    line: Option<usize>,
+    column: Option<usize>,
+    end_line: Option<usize>,
+    end_column: Option<usize>,
+    severity: Option<usize>,
+    code: Option<usize>,
+    message: Option<usize>,
+    #[serde(default, rename = "loop")]
+    is_loop: bool,
+}
+
+/// Converts a single `problemMatcher` entry: either a built-in matcher name, a matcher, extending a built-in one
+/// (only its name is kept, as Zed does not support overriding built-in matchers' properties) or a custom matcher.
+fn to_zed_problem_matcher(value: &serde_json_lenient::Value) -> anyhow::Result<ProblemMatcher> {
+    if let serde_json_lenient::Value::String(name) = value {
+        return Ok(ProblemMatcher::BuiltIn(name.clone()));
+    }
+    let matcher = VsCodeProblemMatcher::deserialize(value)?;
+    let patterns = match matcher.pattern {
+        Some(VsCodeProblemPatterns::Single(pattern)) => vec![pattern],
+        Some(VsCodeProblemPatterns::Multiple(patterns)) => patterns,
+        None => {
+            return matcher
+                .base
+                .map(ProblemMatcher::BuiltIn)
+                .ok_or_else(|| anyhow!("Problem matcher has neither `base` nor `pattern`"));
+        }
+    };
+    Ok(ProblemMatcher::Custom(ProblemMatcherDefinition {
+        source: matcher.source.or(matcher.owner),
+        severity: match matcher.severity.as_deref() {
+            Some("warning") => ProblemSeverity::Warning,
+            Some("info") => ProblemSeverity::Info,
+            _ => ProblemSeverity::Error,
+        },
+        pattern: patterns
+            .into_iter()
+            .map(|pattern| ProblemPattern {
+                regexp: pattern.regexp,
+                file: pattern.file,
+                line: pattern.line,
+                column: pattern.column,
+                end_line: pattern.end_line,
+                end_column: pattern.end_column,
+                severity: pattern.severity,
+                code: pattern.code,
+                message: pattern.message,
+                is_loop: pattern.is_loop,
+            })
+            .collect(),
+    }))
+}
+
+const TSC_WATCH_BEGIN_PATTERN: &str = r"(?:Starting compilation in watch mode|File change detected\. Starting incremental compilation)\.\.\.";
+const TSC_WATCH_END_PATTERN: &str =
+    r"(?:Compilation complete\.|Found \d+ errors?\.) Watching for file changes\.";
+
+/// Takes the readiness patterns of a background task from its problem matchers: either from their
+/// `background` property, or from the built-in `$tsc-watch` matcher, that is commonly used for such tasks.
+fn to_zed_background_task(problem_matcher: Option<&serde_json_lenient::Value>) -> BackgroundTask {
+    let matchers = match problem_matcher {
+        Some(serde_json_lenient::Value::Array(matchers)) => matchers.iter().collect(),
+        Some(matcher) => vec![matcher],
+        None => Vec::new(),
+    };
+    let mut background_task = BackgroundTask::default();
+    for matcher in matchers {
+        let (base, background) = match matcher {
+            serde_json_lenient::Value::String(name) => (Some(name.clone()), None),
+            matcher => match VsCodeProblemMatcher::deserialize(matcher) {
+                Ok(matcher) => (matcher.base, matcher.background),
+                Err(_) => continue,
+            },
+        };
+        if let Some(background) = background {
+            background_task.begin_pattern = background
+                .begins_pattern
+                .map(VsCodeBackgroundPattern::into_regexp);
+            background_task.end_pattern = background
+                .ends_pattern
+                .map(VsCodeBackgroundPattern::into_regexp);
+            break;
+        } else if base.as_deref() == Some("$tsc-watch") {
+            background_task.begin_pattern = Some(TSC_WATCH_BEGIN_PATTERN.to_owned());
+            background_task.end_pattern = Some(TSC_WATCH_END_PATTERN.to_owned());
+            break;
+        }
+    }
+    background_task
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum VsCodeInput {
+    PromptString {
+        id: String,
+        description: Option<String>,
+        default: Option<String>,
+    },
+    PickString {
+        id: String,
+        description: Option<String>,
+        options: Vec<VsCodePickStringOption>,
+        default: Option<String>,
+    },
+    #[serde(other)]
+    Unsupported,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+enum VsCodePickStringOption {
+    Value(String),
+    Labeled { value: String },
+}
+
+impl VsCodeInput {
+    fn to_zed_format(self) -> anyhow::Result<TaskInput> {
+        match self {
+            Self::PromptString {
+                id,
+                description,
+                default,
+            } => Ok(TaskInput {
+                id,
+                description,
+                default,
+                kind: TaskInputKind::Text,
+            }),
+            Self::PickString {
+                id,
+                description,
+                options,
+                default,
+            } => Ok(TaskInput {
+                id,
+                description,
+                default,
+                kind: TaskInputKind::PickString {
+                    options: options
+                        .into_iter()
+                        .map(|option| match option {
+                            VsCodePickStringOption::Value(value)
+                            | VsCodePickStringOption::Labeled { value } => value,
+                        })
+                        .collect(),
+                },
+            }),
+            Self::Unsupported => bail!("Unsupported input type"),
+        }
+    }
+}
+
+/// Whether any of the task fields, that support variable substitution, refer to the input given.
+fn uses_input(task: &TaskTemplate, input: &TaskInput) -> bool {
+    let variable = input.variable_name().template_value();
+    task.command.contains(&variable)
+        || task.args.iter().any(|arg| arg.contains(&variable))
+        || task
+            .cwd
+            .as_ref()
+            .map_or(false, |cwd| cwd.contains(&variable))
+}
+
 /// [`VsCodeTaskFile`] is a superset of Code's task definition format.
 #[derive(Debug, Deserialize, PartialEq)]
 pub struct VsCodeTaskFile {
     tasks: Vec<VsCodeTaskDefinition>,
+    #[serde(default)]
+    inputs: Vec<VsCodeInput>,
 }
 
 impl TryFrom<VsCodeTaskFile> for TaskTemplates {
@@ -140,10 +393,23 @@ impl TryFrom<VsCodeTaskFile> for TaskTemplates {
                 VariableName::SelectedText.to_string(),
             ),
         ]));
+        let inputs = value
+            .inputs
+            .into_iter()
+            .filter_map(|input| input.to_zed_format().log_err())
+            .collect::<Vec<_>>();
         let templates = value
             .tasks
             .into_iter()
             .filter_map(|vscode_definition| vscode_definition.to_zed_format(&replacer).log_err())
+            .map(|mut template| {
+                template.inputs = inputs
+                    .iter()
+                    .filter(|input| uses_input(&template, input))
+                    .cloned()
+                    .collect();
+                template
+            })
             .collect();
         Ok(Self(templates))
     }
@@ -155,10 +421,21 @@ mod tests {
 
     use crate::{
         vscode_format::{Command, VsCodeTaskDefinition},
-        TaskTemplate, TaskTemplates, VsCodeTaskFile,
+        BackgroundTask, ProblemMatcher, ProblemMatcherDefinition, ProblemPattern, ProblemSeverity,
+        TaskInput, TaskInputKind, TaskTemplate, TaskTemplates, VsCodeTaskFile,
+    };
+
+    use super::{
+        to_zed_problem_matcher, EnvVariableReplacer, TSC_WATCH_BEGIN_PATTERN, TSC_WATCH_END_PATTERN,
     };
 
-    use super::EnvVariableReplacer;
+    fn tsc_watch_background() -> BackgroundTask {
+        BackgroundTask {
+            begin_pattern: Some(TSC_WATCH_BEGIN_PATTERN.to_string()),
+            end_pattern: Some(TSC_WATCH_END_PATTERN.to_string()),
+            restart_on_change: Vec::new(),
+        }
+    }
 
     fn compare_without_other_attributes(lhs: VsCodeTaskDefinition, rhs: VsCodeTaskDefinition) {
         assert_eq!(
@@ -184,6 +461,7 @@ mod tests {
         );
         assert_eq!(replacer.replace("${PATH}"), "${PATH}");
         assert_eq!(replacer.replace("${PATH:food}"), "${PATH:food}");
+        assert_eq!(replacer.replace("${input:target}"), "${ZED_INPUT_target}");
         // And now, the actual replacing
         let replacer = EnvVariableReplacer::new(HashMap::from_iter([(
             "PATH".to_owned(),
@@ -257,6 +535,7 @@ mod tests {
                 label: "gulp: tests".to_string(),
                 command: "npm".to_string(),
                 args: vec!["run".to_string(), "build:tests:notypecheck".to_string()],
+                problem_matchers: vec![ProblemMatcher::BuiltIn("$tsc".to_string())],
                 ..Default::default()
             },
             TaskTemplate {
@@ -268,18 +547,22 @@ mod tests {
                     "${ZED_WORKTREE_ROOT}/src".to_string(),
                     "--watch".to_string(),
                 ],
+                problem_matchers: vec![ProblemMatcher::BuiltIn("$tsc-watch".to_string())],
+                background: Some(tsc_watch_background()),
                 ..Default::default()
             },
             TaskTemplate {
                 label: "npm: build:compiler".to_string(),
                 command: "npm".to_string(),
                 args: vec!["run".to_string(), "build:compiler".to_string()],
+                problem_matchers: vec![ProblemMatcher::BuiltIn("$tsc".to_string())],
                 ..Default::default()
             },
             TaskTemplate {
                 label: "npm: build:tests".to_string(),
                 command: "npm".to_string(),
                 args: vec!["run".to_string(), "build:tests:notypecheck".to_string()],
+                problem_matchers: vec![ProblemMatcher::BuiltIn("$tsc".to_string())],
                 ..Default::default()
             },
         ];
@@ -360,32 +643,146 @@ mod tests {
                 label: "Build Extension in Background".to_string(),
                 command: "npm".to_string(),
                 args: vec!["run".to_string(), "watch".to_string()],
+                problem_matchers: vec![ProblemMatcher::BuiltIn("$tsc-watch".to_string())],
+                background: Some(tsc_watch_background()),
                 ..Default::default()
             },
             TaskTemplate {
                 label: "Build Extension".to_string(),
                 command: "npm".to_string(),
                 args: vec!["run".to_string(), "build".to_string()],
+                problem_matchers: vec![ProblemMatcher::BuiltIn("$tsc".to_string())],
                 ..Default::default()
             },
             TaskTemplate {
                 label: "Build Server".to_string(),
                 command: "cargo build --package rust-analyzer".to_string(),
+                problem_matchers: vec![ProblemMatcher::BuiltIn("$rustc".to_string())],
                 ..Default::default()
             },
             TaskTemplate {
                 label: "Build Server (Release)".to_string(),
                 command: "cargo build --release --package rust-analyzer".to_string(),
+                problem_matchers: vec![ProblemMatcher::BuiltIn("$rustc".to_string())],
                 ..Default::default()
             },
             TaskTemplate {
                 label: "Pretest".to_string(),
                 command: "npm".to_string(),
                 args: vec!["run".to_string(), "pretest".to_string()],
+                problem_matchers: vec![ProblemMatcher::BuiltIn("$tsc".to_string())],
+                ..Default::default()
+            },
+            TaskTemplate {
+                label: "Build Server and Extension".to_string(),
+                depends_on: vec!["Build Server".to_string(), "Build Extension".to_string()],
+                problem_matchers: vec![ProblemMatcher::BuiltIn("$rustc".to_string())],
+                ..Default::default()
+            },
+            TaskTemplate {
+                label: "Build Server (Release) and Extension".to_string(),
+                depends_on: vec![
+                    "Build Server (Release)".to_string(),
+                    "Build Extension".to_string(),
+                ],
+                problem_matchers: vec![ProblemMatcher::BuiltIn("$rustc".to_string())],
                 ..Default::default()
             },
         ];
         let tasks: TaskTemplates = vscode_definitions.try_into().unwrap();
         assert_eq!(tasks.0, expected);
     }
+
+    #[test]
+    fn can_convert_problem_matchers() {
+        let matcher = serde_json_lenient::from_str(
+            r#"{
+                "owner": "lint",
+                "severity": "warning",
+                "pattern": {
+                    "regexp": "^(.*):(\\d+):(\\d+):\\s+(.*)$",
+                    "file": 1,
+                    "line": 2,
+                    "column": 3,
+                    "message": 4,
+                },
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            to_zed_problem_matcher(&matcher).unwrap(),
+            ProblemMatcher::Custom(ProblemMatcherDefinition {
+                source: Some("lint".to_string()),
+                severity: ProblemSeverity::Warning,
+                pattern: vec![ProblemPattern {
+                    regexp: "^(.*):(\\d+):(\\d+):\\s+(.*)$".to_string(),
+                    file: Some(1),
+                    line: Some(2),
+                    column: Some(3),
+                    message: Some(4),
+                    ..ProblemPattern::default()
+                }],
+            })
+        );
+        let matcher = serde_json_lenient::from_str(r#"{ "owner": "lint" }"#).unwrap();
+        assert!(to_zed_problem_matcher(&matcher).is_err());
+    }
+
+    #[test]
+    fn can_convert_inputs() {
+        let vscode_definitions: VsCodeTaskFile = serde_json_lenient::from_str(
+            r#"{
+                "version": "2.0.0",
+                "tasks": [
+                    {
+                        "label": "test",
+                        "type": "shell",
+                        "command": "cargo",
+                        "args": ["test", "-p", "${input:package}"],
+                    },
+                    {
+                        "label": "build",
+                        "type": "shell",
+                        "command": "cargo build",
+                    },
+                ],
+                "inputs": [
+                    {
+                        "id": "package",
+                        "type": "pickString",
+                        "description": "Package to test",
+                        "options": ["client", { "label": "Server", "value": "server" }],
+                        "default": "server",
+                    },
+                    {
+                        "id": "name",
+                        "type": "promptString",
+                    },
+                    {
+                        "id": "pick",
+                        "type": "command",
+                        "command": "extension.pick",
+                    },
+                ],
+            }"#,
+        )
+        .unwrap();
+        let templates = TaskTemplates::try_from(vscode_definitions).unwrap();
+        assert_eq!(
+            templates.0[0].args,
+            vec!["test", "-p", "${ZED_INPUT_package}"]
+        );
+        assert_eq!(
+            templates.0[0].inputs,
+            vec![TaskInput {
+                id: "package".to_string(),
+                description: Some("Package to test".to_string()),
+                default: Some("server".to_string()),
+                kind: TaskInputKind::PickString {
+                    options: vec!["client".to_string(), "server".to_string()],
+                },
+            }]
+        );
+        assert_eq!(templates.0[1].inputs, Vec::new());
+    }
 }
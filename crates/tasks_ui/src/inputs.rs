@@ -0,0 +1,313 @@
+use std::{path::Path, sync::Arc};
+
+use fuzzy::{match_strings, StringMatch, StringMatchCandidate};
+use gpui::{
+    AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, Model, ParentElement,
+    Render, Styled, View, ViewContext, VisualContext, WeakView,
+};
+use picker::{Picker, PickerDelegate};
+use project::Project;
+use task::{TaskInput, TaskInputKind, TaskVariables};
+use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
+use util::{paths::PathMatcher, ResultExt};
+use workspace::ModalView;
+
+type OnAnswered = Box<dyn FnOnce(TaskVariables, &mut WindowContext)>;
+
+/// A modal that asks for the answers to the task inputs, one after another.
+pub(crate) struct TaskInputsModal {
+    pub(crate) picker: View<Picker<TaskInputsModalDelegate>>,
+}
+
+impl TaskInputsModal {
+    pub(crate) fn new(
+        task_label: String,
+        inputs: Vec<TaskInput>,
+        project: Model<Project>,
+        on_answered: impl FnOnce(TaskVariables, &mut WindowContext) + 'static,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let modal = cx.view().downgrade();
+        let picker = cx.new_view(|cx| {
+            let mut delegate = TaskInputsModalDelegate {
+                modal,
+                project,
+                task_label,
+                inputs,
+                current_input: 0,
+                answers: TaskVariables::default(),
+                candidates: Vec::new(),
+                candidate_answers: Vec::new(),
+                matches: Vec::new(),
+                selected_index: 0,
+                query: String::new(),
+                on_answered: Some(Box::new(on_answered)),
+            };
+            let query = delegate.load_current_input(cx);
+            let picker = Picker::uniform_list(delegate, cx);
+            picker.set_query(query, cx);
+            picker
+        });
+        Self { picker }
+    }
+}
+
+impl Render for TaskInputsModal {
+    fn render(&mut self, _: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex()
+            .key_context("TaskInputsModal")
+            .w(rems(34.))
+            .child(self.picker.clone())
+    }
+}
+
+impl FocusableView for TaskInputsModal {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<DismissEvent> for TaskInputsModal {}
+impl ModalView for TaskInputsModal {}
+
+pub(crate) struct TaskInputsModalDelegate {
+    modal: WeakView<TaskInputsModal>,
+    project: Model<Project>,
+    task_label: String,
+    inputs: Vec<TaskInput>,
+    current_input: usize,
+    answers: TaskVariables,
+    /// Labels of the answers to pick from for the current input, empty for the text inputs.
+    candidates: Vec<StringMatchCandidate>,
+    candidate_answers: Vec<String>,
+    matches: Vec<StringMatch>,
+    selected_index: usize,
+    query: String,
+    on_answered: Option<OnAnswered>,
+}
+
+impl TaskInputsModalDelegate {
+    fn input(&self) -> &TaskInput {
+        &self.inputs[self.current_input]
+    }
+
+    /// Prepares the answers to pick from for the current input, returns the query to start with.
+    fn load_current_input(&mut self, cx: &AppContext) -> String {
+        let input = &self.inputs[self.current_input];
+        let default_answer = input.default_answer();
+        let (labels, answers): (Vec<String>, Vec<String>) = match &input.kind {
+            TaskInputKind::Text => {
+                self.candidates.clear();
+                self.candidate_answers.clear();
+                self.selected_index = 0;
+                return default_answer;
+            }
+            TaskInputKind::PickString { options } => (options.clone(), options.clone()),
+            TaskInputKind::PickFile { glob } => project_files(&self.project, glob.as_deref(), cx)
+                .into_iter()
+                .unzip(),
+        };
+        self.candidates = labels
+            .into_iter()
+            .enumerate()
+            .map(|(id, label)| StringMatchCandidate::new(id, label))
+            .collect();
+        self.selected_index = answers
+            .iter()
+            .position(|answer| answer == &default_answer)
+            .unwrap_or(0);
+        self.candidate_answers = answers;
+        String::new()
+    }
+}
+
+/// Lists the files of the visible worktrees, matching the glob given, as pairs of their labels and absolute paths.
+fn project_files(
+    project: &Model<Project>,
+    glob: Option<&str>,
+    cx: &AppContext,
+) -> Vec<(String, String)> {
+    let glob = glob.and_then(|glob| PathMatcher::new(glob).log_err());
+    let project = project.read(cx);
+    let include_root_name = project.visible_worktrees(cx).count() > 1;
+    project
+        .visible_worktrees(cx)
+        .flat_map(|worktree| {
+            let snapshot = worktree.read(cx).snapshot();
+            snapshot
+                .files(false, 0)
+                .filter(|entry| {
+                    glob.as_ref()
+                        .map_or(true, |glob| glob.is_match(&entry.path))
+                })
+                .map(|entry| {
+                    let label = if include_root_name {
+                        Path::new(snapshot.root_name()).join(&entry.path)
+                    } else {
+                        entry.path.to_path_buf()
+                    };
+                    (
+                        label.to_string_lossy().into_owned(),
+                        snapshot
+                            .abs_path()
+                            .join(&entry.path)
+                            .to_string_lossy()
+                            .into_owned(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+impl PickerDelegate for TaskInputsModalDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self, _: &mut WindowContext) -> Arc<str> {
+        let input = self.input();
+        match &input.description {
+            Some(description) => description.as_str().into(),
+            None => format!("{} for {}...", input.id, self.task_label).into(),
+        }
+    }
+
+    fn no_matches_text(&self, _: &mut WindowContext) -> SharedString {
+        match self.input().kind {
+            TaskInputKind::PickFile { .. } => "No matching files".into(),
+            TaskInputKind::Text | TaskInputKind::PickString { .. } => "No matching options".into(),
+        }
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(
+        &mut self,
+        query: String,
+        cx: &mut ViewContext<Picker<Self>>,
+    ) -> gpui::Task<()> {
+        if matches!(self.input().kind, TaskInputKind::Text) {
+            // The only answer to a text input is the query itself.
+            self.matches = vec![StringMatch {
+                candidate_id: 0,
+                string: query.clone(),
+                positions: Vec::new(),
+                score: 0.0,
+            }];
+            self.selected_index = 0;
+            self.query = query;
+            return gpui::Task::ready(());
+        }
+
+        let background = cx.background_executor().clone();
+        let candidates = self.candidates.clone();
+        cx.spawn(|this, mut cx| async move {
+            let matches = if query.is_empty() {
+                candidates
+                    .into_iter()
+                    .map(|candidate| StringMatch {
+                        candidate_id: candidate.id,
+                        string: candidate.string,
+                        positions: Vec::new(),
+                        score: 0.0,
+                    })
+                    .collect()
+            } else {
+                match_strings(
+                    &candidates,
+                    &query,
+                    false,
+                    1000,
+                    &Default::default(),
+                    background,
+                )
+                .await
+            };
+
+            this.update(&mut cx, |this, cx| {
+                let delegate = &mut this.delegate;
+                // Keep the default answer selected, until the user starts typing.
+                if !query.is_empty() {
+                    delegate.selected_index = 0;
+                }
+                delegate.matches = matches;
+                delegate.selected_index = delegate
+                    .selected_index
+                    .min(delegate.matches.len().saturating_sub(1));
+                delegate.query = query;
+                cx.notify();
+            })
+            .log_err();
+        })
+    }
+
+    fn confirm(&mut self, _: bool, cx: &mut ViewContext<Picker<Self>>) {
+        let answer = match self.input().kind {
+            TaskInputKind::Text => self.query.clone(),
+            TaskInputKind::PickString { .. } | TaskInputKind::PickFile { .. } => {
+                let Some(answer) = self
+                    .matches
+                    .get(self.selected_index)
+                    .and_then(|mat| self.candidate_answers.get(mat.candidate_id))
+                else {
+                    return;
+                };
+                answer.clone()
+            }
+        };
+        self.answers.insert(self.input().variable_name(), answer);
+
+        self.current_input += 1;
+        if self.current_input < self.inputs.len() {
+            let query = self.load_current_input(cx);
+            cx.defer(move |picker, cx| {
+                picker.set_query(query, cx);
+                picker.refresh(cx);
+            });
+            return;
+        }
+
+        if let Some(on_answered) = self.on_answered.take() {
+            on_answered(std::mem::take(&mut self.answers), cx);
+        }
+        self.dismissed(cx);
+    }
+
+    fn dismissed(&mut self, cx: &mut ViewContext<Picker<Self>>) {
+        self.modal
+            .update(cx, |_, cx| cx.emit(DismissEvent))
+            .log_err();
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _: &mut ViewContext<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let mat = self.matches.get(ix)?;
+        let label = if mat.string.is_empty() {
+            Label::new("Empty value")
+                .color(Color::Muted)
+                .into_any_element()
+        } else {
+            HighlightedLabel::new(mat.string.clone(), mat.positions.clone()).into_any_element()
+        };
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .selected(selected)
+                .child(label),
+        )
+    }
+}
@@ -3,12 +3,14 @@ use std::sync::Arc;
 use ::settings::Settings;
 use editor::{tasks::task_context, Editor};
 use gpui::{AppContext, ViewContext, WindowContext};
+use inputs::TaskInputsModal;
 use language::Language;
 use modal::TasksModal;
-use project::WorktreeId;
-use workspace::tasks::schedule_task;
+use project::{TaskSourceKind, WorktreeId};
+use task::{ResolvedTask, TaskContext};
 use workspace::{tasks::schedule_resolved_task, Workspace};
 
+mod inputs;
 mod modal;
 mod settings;
 
@@ -35,14 +37,29 @@ pub fn init(cx: &mut AppContext) {
                                 original_task.use_new_terminal = use_new_terminal;
                             }
                             let task_context = task_context(workspace, cx);
-                            schedule_task(
-                                workspace,
-                                task_source_kind,
-                                &original_task,
-                                &task_context,
-                                false,
-                                cx,
-                            )
+                            let (worktree, language) =
+                                active_item_selection_properties(workspace, cx);
+                            if let Some(resolved_task) = workspace
+                                .project()
+                                .read(cx)
+                                .task_inventory()
+                                .read(cx)
+                                .resolve_task(
+                                    &task_source_kind,
+                                    &original_task,
+                                    language,
+                                    worktree,
+                                    &task_context,
+                                )
+                            {
+                                schedule_resolved_task(
+                                    workspace,
+                                    task_source_kind,
+                                    resolved_task,
+                                    false,
+                                    cx,
+                                );
+                            }
                         } else {
                             if let Some(resolved) = last_scheduled_task.resolved.as_mut() {
                                 if let Some(allow_concurrent_runs) = action.allow_concurrent_runs {
@@ -91,19 +108,25 @@ fn spawn_task_with_name(name: String, cx: &mut ViewContext<Workspace>) {
         let did_spawn = workspace
             .update(&mut cx, |workspace, cx| {
                 let (worktree, language) = active_item_selection_properties(workspace, cx);
-                let tasks = workspace.project().update(cx, |project, cx| {
-                    project
-                        .task_inventory()
-                        .update(cx, |inventory, _| inventory.list_tasks(language, worktree))
-                });
-                let (task_source_kind, target_task) =
-                    tasks.into_iter().find(|(_, task)| task.label == name)?;
+                let inventory = workspace.project().read(cx).task_inventory().clone();
+                let (task_source_kind, target_task) = inventory
+                    .read(cx)
+                    .list_tasks(language.clone(), worktree)
+                    .into_iter()
+                    .find(|(_, task)| task.label == name)?;
                 let task_context = task_context(workspace, cx);
+                let resolved_task = inventory.read(cx).resolve_task(
+                    &task_source_kind,
+                    &target_task,
+                    language,
+                    worktree,
+                    &task_context,
+                )?;
                 schedule_task(
                     workspace,
                     task_source_kind,
-                    &target_task,
-                    &task_context,
+                    resolved_task,
+                    task_context,
                     false,
                     cx,
                 );
@@ -123,6 +146,64 @@ fn spawn_task_with_name(name: String, cx: &mut ViewContext<Workspace>) {
     .detach();
 }
 
+/// Schedules the task given, asking for the answers to its inputs first,
+/// unless the task was scheduled before and its previous answers are remembered.
+fn schedule_task(
+    workspace: &mut Workspace,
+    task_source_kind: TaskSourceKind,
+    resolved_task: ResolvedTask,
+    task_context: TaskContext,
+    omit_history: bool,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let template = resolved_task.original_task().clone();
+    let inventory = workspace.project().read(cx).task_inventory().clone();
+    if template.inputs.is_empty()
+        || inventory
+            .read(cx)
+            .last_input_answers(&task_source_kind, &template)
+            .is_some()
+    {
+        schedule_resolved_task(workspace, task_source_kind, resolved_task, omit_history, cx);
+        return;
+    }
+
+    let project = workspace.project().clone();
+    let workspace_handle = workspace.weak_handle();
+    workspace.toggle_modal(cx, move |cx| {
+        TaskInputsModal::new(
+            resolved_task.display_label().to_owned(),
+            template.inputs.clone(),
+            project,
+            move |answers, cx| {
+                workspace_handle
+                    .update(cx, |workspace, cx| {
+                        let mut task_context = task_context;
+                        task_context.task_variables.extend(answers);
+                        let (worktree, language) = active_item_selection_properties(workspace, cx);
+                        let resolved_task = inventory.read(cx).resolve_task(
+                            &task_source_kind,
+                            &template,
+                            language,
+                            worktree,
+                            &task_context,
+                        )?;
+                        schedule_resolved_task(
+                            workspace,
+                            task_source_kind,
+                            resolved_task,
+                            omit_history,
+                            cx,
+                        );
+                        Some(())
+                    })
+                    .ok();
+            },
+            cx,
+        )
+    });
+}
+
 fn active_item_selection_properties(
     workspace: &Workspace,
     cx: &mut WindowContext,
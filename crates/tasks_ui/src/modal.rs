@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use crate::active_item_selection_properties;
+use crate::{active_item_selection_properties, schedule_task};
 use fuzzy::{StringMatch, StringMatchCandidate};
 use gpui::{
     impl_actions, rems, AppContext, DismissEvent, EventEmitter, FocusableView, InteractiveElement,
@@ -9,7 +9,7 @@ use gpui::{
 };
 use picker::{highlighted_match_with_paths::HighlightedText, Picker, PickerDelegate};
 use project::{Inventory, TaskSourceKind};
-use task::{ResolvedTask, TaskContext, TaskTemplate};
+use task::{DependsOrder, ResolvedTask, TaskContext, TaskTemplate};
 use ui::{
     div, v_flex, ButtonCommon, ButtonSize, Clickable, Color, FluentBuilder as _, Icon, IconButton,
     IconButtonShape, IconName, IconSize, ListItem, ListItemSpacing, RenderOnce, Selectable,
@@ -294,12 +294,20 @@ impl PickerDelegate for TasksModalDelegate {
             return;
         };
 
+        let task_context = self.task_context.clone();
+        cx.emit(DismissEvent);
         self.workspace
             .update(cx, |workspace, cx| {
-                schedule_resolved_task(workspace, task_source_kind, task, omit_history_entry, cx);
+                schedule_task(
+                    workspace,
+                    task_source_kind,
+                    task,
+                    task_context,
+                    omit_history_entry,
+                    cx,
+                );
             })
             .ok();
-        cx.emit(DismissEvent);
     }
 
     fn dismissed(&mut self, cx: &mut ViewContext<picker::Picker<Self>>) {
@@ -332,6 +340,24 @@ impl PickerDelegate for TasksModalDelegate {
                 }
                 tooltip_label_text.push_str(&resolved.command_label);
             }
+            if !resolved.dependencies.is_empty() {
+                if !tooltip_label_text.trim().is_empty() {
+                    tooltip_label_text.push('\n');
+                }
+                let separator = match resolved.depends_order {
+                    DependsOrder::Sequence => " → ",
+                    DependsOrder::Parallel => ", ",
+                };
+                tooltip_label_text.push_str("Depends on: ");
+                tooltip_label_text.push_str(
+                    &resolved
+                        .dependencies
+                        .iter()
+                        .map(|dependency| dependency.label.as_str())
+                        .collect::<Vec<_>>()
+                        .join(separator),
+                );
+            }
         }
         let tooltip_label = if tooltip_label_text.trim().is_empty() {
             None
@@ -443,7 +469,7 @@ mod tests {
     use task::TaskTemplates;
     use workspace::CloseInactiveTabsAndPanes;
 
-    use crate::{modal::Spawn, tests::init_test};
+    use crate::{inputs::TaskInputsModal, modal::Spawn, tests::init_test};
 
     use super::*;
 
@@ -689,6 +715,98 @@ mod tests {
         cx.executor().run_until_parked();
     }
 
+    #[gpui::test]
+    async fn test_task_inputs(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            "/dir",
+            json!({
+                ".zed": {
+                    "tasks.json": r#"[
+                        {
+                            "label": "test ${ZED_INPUT_package}",
+                            "command": "cargo",
+                            "args": ["test", "-p", "${ZED_INPUT_package}"],
+                            "inputs": [
+                                {
+                                    "id": "package",
+                                    "type": "pick_string",
+                                    "options": ["client", "server"],
+                                    "default": "server"
+                                }
+                            ]
+                        }
+                    ]"#,
+                },
+                "a.ts": "a"
+            }),
+        )
+        .await;
+
+        let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
+
+        let tasks_picker = open_spawn_tasks(&workspace, cx);
+        assert_eq!(
+            task_names(&tasks_picker, cx),
+            vec!["test server"],
+            "Tasks that were never spawned should be listed with the default answers to their inputs"
+        );
+        tasks_picker.update(cx, |picker, cx| picker.delegate.confirm(false, cx));
+        cx.executor().run_until_parked();
+
+        let inputs_picker = workspace.update(cx, |workspace, cx| {
+            workspace
+                .active_modal::<TaskInputsModal>(cx)
+                .expect("no task inputs modal after spawning a task with inputs")
+                .read(cx)
+                .picker
+                .clone()
+        });
+        inputs_picker.update(cx, |picker, _| {
+            assert_eq!(picker.delegate.match_count(), 2);
+            assert_eq!(
+                picker.delegate.selected_index(),
+                1,
+                "The default answer should be selected"
+            );
+        });
+        cx.simulate_input("cli");
+        inputs_picker.update(cx, |picker, _| assert_eq!(picker.delegate.match_count(), 1));
+        inputs_picker.update(cx, |picker, cx| picker.delegate.confirm(false, cx));
+        cx.executor().run_until_parked();
+        assert_eq!(
+            last_scheduled_task_label(&project, cx),
+            Some("test client".to_string())
+        );
+
+        let tasks_picker = open_spawn_tasks(&workspace, cx);
+        assert_eq!(task_names(&tasks_picker, cx), vec!["test client"]);
+        tasks_picker.update(cx, |picker, cx| picker.delegate.confirm(false, cx));
+        cx.executor().run_until_parked();
+        workspace.update(cx, |workspace, cx| {
+            assert!(
+                workspace.active_modal::<TaskInputsModal>(cx).is_none(),
+                "Should not ask for the inputs again, when the task is rerun"
+            );
+        });
+        assert_eq!(
+            last_scheduled_task_label(&project, cx),
+            Some("test client".to_string())
+        );
+    }
+
+    fn last_scheduled_task_label(
+        project: &Model<Project>,
+        cx: &mut VisualTestContext,
+    ) -> Option<String> {
+        project.update(cx, |project, cx| {
+            let (_, task) = project.task_inventory().read(cx).last_scheduled_task()?;
+            Some(task.resolved_label)
+        })
+    }
+
     #[gpui::test]
     async fn test_language_task_filtering(cx: &mut TestAppContext) {
         init_test(cx);
@@ -0,0 +1,385 @@
+use alacritty_terminal::{
+    grid::Dimensions,
+    index::{Column, Line, Point as AlacPoint},
+    term::cell::Flags,
+    Term,
+};
+use anyhow::Context;
+use futures::channel::oneshot;
+use regex::Regex;
+use task::{BackgroundTask, Problem, ProblemMatcher, ProblemScanner};
+use util::ResultExt;
+
+/// How many of the last read lines are remembered to find the place to continue reading from,
+/// after the terminal history got truncated.
+const LINES_TO_RESYNC_WITH: usize = 4;
+
+/// A status of a running background task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackgroundTaskStatus {
+    /// The task was started, but did not report being ready yet.
+    Starting,
+    /// The task is rebuilding, after it was ready before.
+    Rebuilding,
+    /// The task is ready.
+    Ready,
+}
+
+/// A change in the background task state, found in its output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum BackgroundTaskEvent {
+    StatusChanged,
+    ProblemsOutdated,
+    ProblemsFound(Vec<Problem>),
+}
+
+pub struct BackgroundTaskState {
+    pub status: BackgroundTaskStatus,
+    begin_pattern: Option<Regex>,
+    end_pattern: Option<Regex>,
+    problem_scanner: ProblemScanner,
+    output: OutputLines,
+    ready_listeners: Vec<oneshot::Sender<()>>,
+}
+
+impl BackgroundTaskState {
+    pub fn new(background_task: &BackgroundTask, problem_matchers: &[ProblemMatcher]) -> Self {
+        let compile = |pattern: &Option<String>| {
+            let pattern = pattern.as_ref()?;
+            Regex::new(pattern)
+                .with_context(|| format!("invalid background task pattern `{pattern}`"))
+                .log_err()
+        };
+        let end_pattern = compile(&background_task.end_pattern);
+        Self {
+            status: if end_pattern.is_some() {
+                BackgroundTaskStatus::Starting
+            } else {
+                BackgroundTaskStatus::Ready
+            },
+            begin_pattern: compile(&background_task.begin_pattern),
+            end_pattern,
+            problem_scanner: ProblemScanner::new(problem_matchers),
+            output: OutputLines::default(),
+            ready_listeners: Vec::new(),
+        }
+    }
+
+    /// Returns a receiver, that resolves once the task is ready, or gets cancelled when the task finishes before that.
+    pub(crate) fn wait_until_ready(&mut self) -> oneshot::Receiver<()> {
+        let (tx, rx) = oneshot::channel();
+        if self.status == BackgroundTaskStatus::Ready {
+            tx.send(()).ok();
+        } else {
+            self.ready_listeners.push(tx);
+        }
+        rx
+    }
+
+    pub(crate) fn finish(&mut self) {
+        self.ready_listeners.clear();
+    }
+
+    /// Reads the task output, that appeared since the last call, and reacts on its begin and end patterns.
+    pub(crate) fn process_output<T>(&mut self, term: &Term<T>) -> Vec<BackgroundTaskEvent> {
+        let mut events = Vec::new();
+        for line in self.output.read_new_lines(term, false) {
+            if self
+                .begin_pattern
+                .as_ref()
+                .map_or(false, |pattern| pattern.is_match(&line))
+            {
+                // Whatever was found before the rebuild is not relevant anymore.
+                self.problem_scanner.take_problems();
+                events.push(BackgroundTaskEvent::ProblemsOutdated);
+                if self.status == BackgroundTaskStatus::Ready {
+                    self.status = BackgroundTaskStatus::Rebuilding;
+                    events.push(BackgroundTaskEvent::StatusChanged);
+                }
+            }
+
+            self.problem_scanner.scan_line(&line);
+
+            if self
+                .end_pattern
+                .as_ref()
+                .map_or(false, |pattern| pattern.is_match(&line))
+            {
+                events.extend(self.take_problems());
+                if self.status != BackgroundTaskStatus::Ready {
+                    self.status = BackgroundTaskStatus::Ready;
+                    events.push(BackgroundTaskEvent::StatusChanged);
+                    for listener in self.ready_listeners.drain(..) {
+                        listener.send(()).ok();
+                    }
+                }
+            }
+        }
+
+        if self.end_pattern.is_none() {
+            events.extend(self.take_problems());
+        }
+        events
+    }
+
+    pub(crate) fn take_problems(&mut self) -> Option<BackgroundTaskEvent> {
+        let problems = self.problem_scanner.take_problems();
+        if problems.is_empty() {
+            None
+        } else {
+            Some(BackgroundTaskEvent::ProblemsFound(problems))
+        }
+    }
+}
+
+/// Scans the output of a task, that is not a background one, for problems as it gets printed.
+pub struct TaskOutputScanner {
+    problem_scanner: ProblemScanner,
+    output: OutputLines,
+}
+
+impl TaskOutputScanner {
+    /// Returns `None` if none of the problem matchers can be used.
+    pub fn new(problem_matchers: &[ProblemMatcher]) -> Option<Self> {
+        let problem_scanner = ProblemScanner::new(problem_matchers);
+        if problem_scanner.is_empty() {
+            return None;
+        }
+        Some(Self {
+            problem_scanner,
+            output: OutputLines::default(),
+        })
+    }
+
+    /// Returns the problems found in the output lines, that appeared since the last call.
+    pub(crate) fn process_output<T>(&mut self, term: &Term<T>) -> Vec<Problem> {
+        self.scan(term, false)
+    }
+
+    /// Returns the problems found in the rest of the output, after the task has finished,
+    /// including its last line that might be not terminated.
+    pub(crate) fn finish<T>(&mut self, term: &Term<T>) -> Vec<Problem> {
+        self.scan(term, true)
+    }
+
+    fn scan<T>(&mut self, term: &Term<T>, finished: bool) -> Vec<Problem> {
+        for line in self.output.read_new_lines(term, finished) {
+            self.problem_scanner.scan_line(&line);
+        }
+        self.problem_scanner.take_problems()
+    }
+}
+
+/// Reads the terminal output line by line, remembering which lines were read already.
+#[derive(Default)]
+struct OutputLines {
+    /// An index of the first unread line, counting from the topmost line of the terminal history.
+    next_line: usize,
+    /// Contents of the last read lines, used to find the place to continue reading from,
+    /// if the lines were shifted after the terminal history got full and started to drop its topmost lines.
+    last_lines: Vec<String>,
+}
+
+impl OutputLines {
+    /// Reads the lines printed since the last call. Unless `include_cursor_line` is set,
+    /// the line with the cursor is left unread, as it's still being printed.
+    fn read_new_lines<T>(&mut self, term: &Term<T>, include_cursor_line: bool) -> Vec<String> {
+        let grid = term.grid();
+        let history_size = grid.history_size();
+        let to_line = |index: usize| Line(index as i32 - history_size as i32);
+        let row_text = |index: usize| {
+            let line = to_line(index);
+            term.bounds_to_string(
+                AlacPoint::new(line, Column(0)),
+                AlacPoint::new(line, grid.last_column()),
+            )
+        };
+
+        // The line with the cursor is still being printed, and so are the lines wrapped into it.
+        let mut end = history_size + grid.cursor.point.line.0.max(0) as usize;
+        if include_cursor_line {
+            end += 1;
+        } else {
+            while end > 0
+                && grid[to_line(end - 1)][grid.last_column()]
+                    .flags
+                    .contains(Flags::WRAPLINE)
+            {
+                end -= 1;
+            }
+        }
+
+        let is_in_sync = |next_line: usize| {
+            next_line >= self.last_lines.len()
+                && next_line <= end
+                && self
+                    .last_lines
+                    .iter()
+                    .enumerate()
+                    .all(|(ix, text)| row_text(next_line - self.last_lines.len() + ix) == *text)
+        };
+        if !self.last_lines.is_empty() && !is_in_sync(self.next_line) {
+            self.next_line = (self.last_lines.len()..self.next_line.min(end))
+                .rev()
+                .find(|&next_line| is_in_sync(next_line))
+                .unwrap_or_else(|| end.saturating_sub(grid.screen_lines()));
+        }
+        if self.next_line >= end {
+            return Vec::new();
+        }
+
+        let text = term.bounds_to_string(
+            AlacPoint::new(to_line(self.next_line), Column(0)),
+            AlacPoint::new(to_line(end - 1), grid.last_column()),
+        );
+        self.last_lines = (end.saturating_sub(LINES_TO_RESYNC_WITH)..end)
+            .map(row_text)
+            .collect();
+        self.next_line = end;
+        text.lines().map(ToOwned::to_owned).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alacritty_terminal::{
+        event::VoidListener,
+        term::{Config, Term},
+        vte::ansi::Handler,
+    };
+    use gpui::{px, size};
+    use task::{BackgroundTask, Problem, ProblemMatcher, ProblemSeverity};
+
+    use crate::TerminalSize;
+
+    use super::{
+        BackgroundTaskEvent, BackgroundTaskState, BackgroundTaskStatus, TaskOutputScanner,
+    };
+
+    fn print_lines(term: &mut Term<VoidListener>, lines: &[&str]) {
+        for line in lines {
+            for c in line.chars() {
+                term.input(c);
+            }
+            term.carriage_return();
+            term.linefeed();
+        }
+    }
+
+    fn gcc_problem(line: u32, severity: ProblemSeverity, message: &str) -> Problem {
+        Problem {
+            path: "main.c".into(),
+            line,
+            column: Some(2),
+            end_line: None,
+            end_column: None,
+            severity,
+            code: None,
+            message: message.to_string(),
+            source: Some("gcc".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_background_task_output_processing() {
+        let mut term = Term::new(
+            Config {
+                scrolling_history: 3,
+                ..Config::default()
+            },
+            &TerminalSize::new(px(1.), px(1.), size(px(40.), px(4.))),
+            VoidListener,
+        );
+        let mut state = BackgroundTaskState::new(
+            &BackgroundTask {
+                begin_pattern: Some("^build started".to_string()),
+                end_pattern: Some("^build finished".to_string()),
+                restart_on_change: Vec::new(),
+            },
+            &[ProblemMatcher::BuiltIn("$gcc".to_string())],
+        );
+        assert_eq!(state.status, BackgroundTaskStatus::Starting);
+
+        print_lines(
+            &mut term,
+            &["build started", "main.c:1:2: error: oops", "build finished"],
+        );
+        assert_eq!(
+            state.process_output(&term),
+            vec![
+                BackgroundTaskEvent::ProblemsOutdated,
+                BackgroundTaskEvent::ProblemsFound(vec![gcc_problem(
+                    1,
+                    ProblemSeverity::Error,
+                    "oops"
+                )]),
+                BackgroundTaskEvent::StatusChanged,
+            ]
+        );
+        assert_eq!(state.status, BackgroundTaskStatus::Ready);
+        assert_eq!(state.process_output(&term), Vec::new());
+
+        print_lines(&mut term, &["build started"]);
+        assert_eq!(
+            state.process_output(&term),
+            vec![
+                BackgroundTaskEvent::ProblemsOutdated,
+                BackgroundTaskEvent::StatusChanged,
+            ]
+        );
+        assert_eq!(state.status, BackgroundTaskStatus::Rebuilding);
+
+        print_lines(&mut term, &["main.c:3:2: warning: hmm", "build finished"]);
+        assert_eq!(
+            state.process_output(&term),
+            vec![
+                BackgroundTaskEvent::ProblemsFound(vec![gcc_problem(
+                    3,
+                    ProblemSeverity::Warning,
+                    "hmm"
+                )]),
+                BackgroundTaskEvent::StatusChanged,
+            ]
+        );
+
+        // The history is full now, so the new lines push the oldest ones out of it:
+        // the lines that were read already should not be read again.
+        print_lines(&mut term, &["watching", "build started"]);
+        assert_eq!(
+            state.process_output(&term),
+            vec![
+                BackgroundTaskEvent::ProblemsOutdated,
+                BackgroundTaskEvent::StatusChanged,
+            ]
+        );
+        assert_eq!(state.status, BackgroundTaskStatus::Rebuilding);
+    }
+
+    #[test]
+    fn test_task_output_scanning() {
+        let mut term = Term::new(
+            Config::default(),
+            &TerminalSize::new(px(1.), px(1.), size(px(40.), px(4.))),
+            VoidListener,
+        );
+        let mut scanner =
+            TaskOutputScanner::new(&[ProblemMatcher::BuiltIn("$gcc".to_string())]).unwrap();
+
+        print_lines(&mut term, &["main.c:1:2: error: oops"]);
+        for c in "main.c:3:2: warning: hmm".chars() {
+            term.input(c);
+        }
+        assert_eq!(
+            scanner.process_output(&term),
+            vec![gcc_problem(1, ProblemSeverity::Error, "oops")]
+        );
+        assert_eq!(scanner.process_output(&term), Vec::new());
+
+        // The last line is not terminated, so it's only read once the task is finished.
+        assert_eq!(
+            scanner.finish(&term),
+            vec![gcc_problem(3, ProblemSeverity::Warning, "hmm")]
+        );
+        assert!(TaskOutputScanner::new(&[]).is_none());
+    }
+}
@@ -0,0 +1,532 @@
+//! The values, anchored to the terminal grid lines: the images and the command marks.
+//!
+//! Alacritty has no way to attach the data to its grid and does not report the lines it scrolls or clears, so
+//! [`GridTracker`] follows the pty output along with the terminal and tells the changes of the grid lines, which
+//! are applied to [`GridAnchors`] the same way the terminal applies them to its grid.
+
+use std::{
+    collections::BTreeMap,
+    ops::{Range, RangeInclusive},
+};
+
+use alacritty_terminal::{
+    grid::{Dimensions, GridCell},
+    index::{Column, Line, Point as AlacPoint},
+    term::TermMode,
+    vte::{Params, Parser, Perform},
+    Term,
+};
+
+/// A change of the terminal grid lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum GridChange {
+    /// The lines of the region moved up. If the region starts at the top of the screen, the lines above it
+    /// are moved into the history, otherwise they are dropped.
+    ScrollUp { region: Range<Line>, lines: usize },
+    /// The lines of the region moved down, the lines below it are dropped.
+    ScrollDown { region: Range<Line>, lines: usize },
+    /// The cells between the points, inclusive, were cleared.
+    Clear { start: AlacPoint, end: AlacPoint },
+    /// All the lines moved by the number of lines given, e.g. to keep their place relative to the cursor,
+    /// when the terminal gets resized.
+    Shift(i32),
+    /// The terminal switched to the alternate screen, or back to the normal one.
+    SwapScreens { alternate: bool },
+    /// The terminal was reset, with its history.
+    Reset,
+}
+
+/// The values, anchored to the cells of one terminal screen.
+struct Anchors<T> {
+    /// The values by their lines, counted from the same line as `top`, and columns.
+    values: BTreeMap<(i64, Column), T>,
+    /// The line of the top of the screen, grows as the lines are scrolled into the history.
+    top: i64,
+}
+
+impl<T> Default for Anchors<T> {
+    fn default() -> Self {
+        Self {
+            values: BTreeMap::new(),
+            top: 0,
+        }
+    }
+}
+
+impl<T> Anchors<T> {
+    fn key(&self, line: Line, column: Column) -> (i64, Column) {
+        (self.top + line.0 as i64, column)
+    }
+
+    fn point(&self, (line, column): (i64, Column)) -> AlacPoint {
+        AlacPoint::new(Line((line - self.top) as i32), column)
+    }
+
+    /// Removes the values from the lines given and returns them.
+    fn take(&mut self, lines: Range<Line>) -> Vec<(AlacPoint, T)> {
+        if lines.is_empty() {
+            return Vec::new();
+        }
+        let keys = self
+            .values
+            .range(self.key(lines.start, Column(0))..self.key(lines.end, Column(0)))
+            .map(|(key, _)| *key)
+            .collect::<Vec<_>>();
+        let mut taken = Vec::with_capacity(keys.len());
+        for key in keys {
+            if let Some(value) = self.values.remove(&key) {
+                taken.push((self.point(key), value));
+            }
+        }
+        taken
+    }
+
+    fn insert(&mut self, point: AlacPoint, value: T) {
+        self.values
+            .insert(self.key(point.line, point.column), value);
+    }
+
+    fn apply(&mut self, change: &GridChange) {
+        match change {
+            GridChange::ScrollUp { region, lines } if region.start == Line(0) => {
+                // The whole grid moves up, except for the lines below the region.
+                let below = self.take(region.end..Line(i32::MAX));
+                self.top += *lines as i64;
+                for (point, value) in below {
+                    self.insert(point, value);
+                }
+            }
+            GridChange::ScrollUp { region, lines } => {
+                for (point, value) in self.take(region.clone()) {
+                    let line = point.line - *lines;
+                    if line >= region.start {
+                        self.insert(AlacPoint::new(line, point.column), value);
+                    }
+                }
+            }
+            GridChange::ScrollDown { region, lines } => {
+                for (point, value) in self.take(region.clone()) {
+                    let line = point.line + *lines;
+                    if line < region.end {
+                        self.insert(AlacPoint::new(line, point.column), value);
+                    }
+                }
+            }
+            GridChange::Clear { start, end } if start <= end => {
+                let keys = self
+                    .values
+                    .range(self.key(start.line, start.column)..=self.key(end.line, end.column))
+                    .map(|(key, _)| *key)
+                    .collect::<Vec<_>>();
+                for key in keys {
+                    self.values.remove(&key);
+                }
+            }
+            GridChange::Clear { .. } => {}
+            GridChange::Shift(lines) => self.top -= *lines as i64,
+            GridChange::SwapScreens { .. } | GridChange::Reset => self.values.clear(),
+        }
+    }
+
+    /// Drops the values, which lines are not in the grid anymore.
+    fn truncate(&mut self, lines: &RangeInclusive<Line>) {
+        let first = self.key(*lines.start(), Column(0));
+        let last = self.key(*lines.end(), Column(usize::MAX));
+        while let Some(entry) = self.values.first_entry() {
+            if *entry.key() >= first {
+                break;
+            }
+            entry.remove();
+        }
+        while let Some(entry) = self.values.last_entry() {
+            if *entry.key() <= last {
+                break;
+            }
+            entry.remove();
+        }
+    }
+}
+
+/// The values, anchored to the cells of the terminal grid, which move along with their lines and are dropped
+/// when their lines are cleared or leave the grid.
+pub(crate) struct GridAnchors<T> {
+    anchors: Anchors<T>,
+    /// The anchors of the screen, which is not shown: the normal screen, when the alternate one is shown.
+    inactive: Anchors<T>,
+}
+
+impl<T> Default for GridAnchors<T> {
+    fn default() -> Self {
+        Self {
+            anchors: Anchors::default(),
+            inactive: Anchors::default(),
+        }
+    }
+}
+
+impl<T> GridAnchors<T> {
+    pub(crate) fn insert(&mut self, point: AlacPoint, value: T) {
+        self.anchors.insert(point, value);
+    }
+
+    /// Returns the values, anchored to the lines given, in the grid order.
+    pub(crate) fn range(
+        &self,
+        lines: RangeInclusive<Line>,
+    ) -> impl DoubleEndedIterator<Item = (AlacPoint, &T)> + '_ {
+        let start = self.anchors.key(*lines.start(), Column(0));
+        let end = self.anchors.key(*lines.end(), Column(usize::MAX));
+        self.anchors
+            .values
+            .range(start..=end)
+            .map(|(key, value)| (self.anchors.point(*key), value))
+    }
+
+    /// Keeps only the values, the predicate returns `true` for, on both screens.
+    pub(crate) fn retain(&mut self, mut f: impl FnMut(&T) -> bool) {
+        self.anchors.values.retain(|_, value| f(value));
+        self.inactive.values.retain(|_, value| f(value));
+    }
+
+    /// Applies the changes of the grid, which now has the lines given.
+    pub(crate) fn apply(&mut self, changes: &[GridChange], lines: RangeInclusive<Line>) {
+        for change in changes {
+            match change {
+                GridChange::SwapScreens { alternate } => {
+                    std::mem::swap(&mut self.anchors, &mut self.inactive);
+                    // The alternate screen is cleared every time it is shown.
+                    if *alternate {
+                        self.anchors.apply(change);
+                    }
+                }
+                GridChange::Reset => {
+                    self.anchors.apply(change);
+                    self.inactive.apply(change);
+                }
+                change => self.anchors.apply(change),
+            }
+        }
+        self.anchors.truncate(&lines);
+    }
+}
+
+/// Follows the pty output, to tell the changes each byte makes to the terminal grid lines.
+pub(crate) struct GridTracker {
+    parser: Parser,
+    /// The scrolling region, set by the program, if it is not the whole screen.
+    scroll_region: Option<Range<Line>>,
+    changes: Vec<GridChange>,
+}
+
+impl GridTracker {
+    pub(crate) fn new() -> Self {
+        Self {
+            parser: Parser::new(),
+            scroll_region: None,
+            changes: Vec::new(),
+        }
+    }
+
+    /// Handles the byte, before the terminal processes it.
+    pub(crate) fn advance<T>(&mut self, term: &Term<T>, byte: u8) {
+        let mut performer = ChangesPerformer {
+            term,
+            scroll_region: &mut self.scroll_region,
+            changes: &mut self.changes,
+        };
+        self.parser.advance(&mut performer, byte);
+    }
+
+    /// Takes the changes, the bytes handled so far make.
+    pub(crate) fn take_changes(&mut self) -> Vec<GridChange> {
+        std::mem::take(&mut self.changes)
+    }
+
+    /// Forgets the scrolling region, which the terminal resets on resizing.
+    pub(crate) fn reset_scroll_region(&mut self) {
+        self.scroll_region = None;
+    }
+}
+
+/// Tells the grid changes of the terminal actions, from the terminal state before they are performed.
+struct ChangesPerformer<'a, T> {
+    term: &'a Term<T>,
+    scroll_region: &'a mut Option<Range<Line>>,
+    changes: &'a mut Vec<GridChange>,
+}
+
+impl<T> ChangesPerformer<'_, T> {
+    fn scroll_region(&self) -> Range<Line> {
+        self.scroll_region
+            .clone()
+            .unwrap_or(Line(0)..Line(self.term.screen_lines() as i32))
+    }
+
+    fn linefeed(&mut self) {
+        let region = self.scroll_region();
+        if self.term.grid().cursor.point.line + 1 == region.end {
+            self.changes.push(GridChange::ScrollUp { region, lines: 1 });
+        }
+    }
+
+    fn screen_end(&self) -> AlacPoint {
+        AlacPoint::new(self.term.bottommost_line(), self.term.last_column())
+    }
+}
+
+impl<T> Perform for ChangesPerformer<'_, T> {
+    fn print(&mut self, _: char) {
+        let cursor = &self.term.grid().cursor;
+        if cursor.input_needs_wrap && self.term.mode().contains(TermMode::LINE_WRAP) {
+            self.linefeed();
+        }
+    }
+
+    fn execute(&mut self, byte: u8) {
+        // Line feed, vertical tab and form feed.
+        if matches!(byte, 0x0a..=0x0c) {
+            self.linefeed();
+        }
+    }
+
+    fn esc_dispatch(&mut self, intermediates: &[u8], ignore: bool, byte: u8) {
+        if ignore || !intermediates.is_empty() {
+            return;
+        }
+        match byte {
+            b'D' | b'E' => self.linefeed(),
+            b'M' => {
+                let region = self.scroll_region();
+                if self.term.grid().cursor.point.line == region.start {
+                    self.changes
+                        .push(GridChange::ScrollDown { region, lines: 1 });
+                }
+            }
+            b'c' => {
+                *self.scroll_region = None;
+                self.changes.push(GridChange::Reset);
+            }
+            _ => {}
+        }
+    }
+
+    fn csi_dispatch(&mut self, params: &Params, intermediates: &[u8], ignore: bool, action: char) {
+        if ignore || !intermediates.is_empty() {
+            return;
+        }
+        let mut params = params.iter().map(|param| param[0] as usize);
+        let mut count = || params.next().filter(|count| *count != 0).unwrap_or(1);
+        let cursor = self.term.grid().cursor.point;
+        let region = self.scroll_region();
+        let screen_lines = self.term.screen_lines();
+        let region_lines = (region.end.0 - region.start.0) as usize;
+        match action {
+            'S' => self.changes.push(GridChange::ScrollUp {
+                lines: count().min(region_lines),
+                region,
+            }),
+            'T' => self.changes.push(GridChange::ScrollDown {
+                lines: count().min(region_lines),
+                region,
+            }),
+            'L' | 'M' if region.contains(&cursor.line) => {
+                let lines = count().min(screen_lines - cursor.line.0 as usize);
+                let region = cursor.line..region.end;
+                self.changes.push(if action == 'L' {
+                    GridChange::ScrollDown { region, lines }
+                } else {
+                    GridChange::ScrollUp { region, lines }
+                });
+            }
+            'r' => {
+                let top = count();
+                let bottom = params
+                    .next()
+                    .filter(|bottom| *bottom != 0)
+                    .map_or(screen_lines, |bottom| bottom.min(screen_lines));
+                if top < bottom {
+                    *self.scroll_region = Some(Line(top as i32 - 1)..Line(bottom as i32));
+                }
+            }
+            'J' => {
+                let screen_start = AlacPoint::new(Line(0), Column(0));
+                match params.next().unwrap_or(0) {
+                    0 => self.changes.push(GridChange::Clear {
+                        start: cursor,
+                        end: self.screen_end(),
+                    }),
+                    1 => self.changes.push(GridChange::Clear {
+                        start: screen_start,
+                        end: cursor,
+                    }),
+                    2 => {
+                        // The normal screen is cleared by scrolling its used lines into the history.
+                        if !self.term.mode().contains(TermMode::ALT_SCREEN) {
+                            if let Some(last_used_line) = last_used_line(self.term) {
+                                self.changes.push(GridChange::ScrollUp {
+                                    region: Line(0)..Line(screen_lines as i32),
+                                    lines: last_used_line.0 as usize + 1,
+                                });
+                            }
+                        }
+                        self.changes.push(GridChange::Clear {
+                            start: screen_start,
+                            end: self.screen_end(),
+                        });
+                    }
+                    3 if self.term.topmost_line() < Line(0) => {
+                        self.changes.push(GridChange::Clear {
+                            start: AlacPoint::new(self.term.topmost_line(), Column(0)),
+                            end: AlacPoint::new(Line(-1), self.term.last_column()),
+                        })
+                    }
+                    _ => {}
+                }
+            }
+            'K' => {
+                let line_start = AlacPoint::new(cursor.line, Column(0));
+                let line_end = AlacPoint::new(cursor.line, self.term.last_column());
+                let (start, end) = match params.next().unwrap_or(0) {
+                    0 => (cursor, line_end),
+                    1 => (line_start, cursor),
+                    2 => (line_start, line_end),
+                    _ => return,
+                };
+                self.changes.push(GridChange::Clear { start, end });
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Returns the last screen line, which has anything printed on it.
+fn last_used_line<T>(term: &Term<T>) -> Option<Line> {
+    let grid = term.grid();
+    (0..term.screen_lines() as i32)
+        .rev()
+        .map(Line)
+        .find(|line| (0..term.columns()).any(|column| !grid[*line][Column(column)].is_empty()))
+}
+
+#[cfg(test)]
+mod tests {
+    use alacritty_terminal::{
+        event::VoidListener,
+        index::{Column, Line, Point as AlacPoint},
+        term::{Config, Term},
+        vte::ansi::Processor,
+    };
+    use gpui::{px, size};
+
+    use crate::TerminalSize;
+
+    use super::{GridAnchors, GridChange, GridTracker};
+
+    fn advance(
+        term: &mut Term<VoidListener>,
+        processor: &mut Processor,
+        tracker: &mut GridTracker,
+        anchors: &mut GridAnchors<char>,
+        output: &[u8],
+    ) {
+        for &byte in output {
+            tracker.advance(term, byte);
+            processor.advance(term, byte);
+            anchors.apply(
+                &tracker.take_changes(),
+                term.topmost_line()..=term.bottommost_line(),
+            );
+        }
+    }
+
+    fn anchored(term: &Term<VoidListener>, anchors: &GridAnchors<char>) -> Vec<(i32, char)> {
+        anchors
+            .range(term.topmost_line()..=term.bottommost_line())
+            .map(|(point, value)| (point.line.0, *value))
+            .collect()
+    }
+
+    #[test]
+    fn test_anchors_follow_scrolling_and_clearing() {
+        // 5 lines of 10 columns.
+        let terminal_size = TerminalSize::new(px(10.), px(10.), size(px(100.), px(50.)));
+        let mut term = Term::new(Config::default(), &terminal_size, VoidListener);
+        let mut processor = Processor::new();
+        let mut tracker = GridTracker::new();
+        let mut anchors = GridAnchors::default();
+
+        advance(
+            &mut term,
+            &mut processor,
+            &mut tracker,
+            &mut anchors,
+            b"a\r\nb",
+        );
+        anchors.insert(AlacPoint::new(Line(0), Column(0)), 'a');
+        anchors.insert(AlacPoint::new(Line(1), Column(0)), 'b');
+
+        // The lines scroll into the history with the new lines and with the lines wrapped.
+        advance(
+            &mut term,
+            &mut processor,
+            &mut tracker,
+            &mut anchors,
+            b"\r\n\r\n\r\n\r\n0123456789x",
+        );
+        assert_eq!(anchored(&term, &anchors), vec![(-2, 'a'), (-1, 'b')]);
+
+        // The lines within the scrolling region only move inside of it.
+        anchors.insert(AlacPoint::new(Line(2), Column(0)), 'c');
+        anchors.insert(AlacPoint::new(Line(3), Column(0)), 'd');
+        advance(
+            &mut term,
+            &mut processor,
+            &mut tracker,
+            &mut anchors,
+            b"\x1b[3;4r\x1b[1S\x1b[r",
+        );
+        assert_eq!(
+            anchored(&term, &anchors),
+            vec![(-2, 'a'), (-1, 'b'), (2, 'd')]
+        );
+
+        // The cleared lines lose their anchors.
+        advance(
+            &mut term,
+            &mut processor,
+            &mut tracker,
+            &mut anchors,
+            b"\x1b[3;1H\x1b[2K\x1b[3J",
+        );
+        assert_eq!(anchored(&term, &anchors), vec![]);
+    }
+
+    #[test]
+    fn test_anchors_of_alternate_screen() {
+        let mut anchors = GridAnchors::default();
+        let lines = Line(-10)..=Line(4);
+        anchors.insert(AlacPoint::new(Line(1), Column(0)), 'a');
+
+        anchors.apply(
+            &[GridChange::SwapScreens { alternate: true }],
+            lines.clone(),
+        );
+        assert_eq!(anchors.range(lines.clone()).count(), 0);
+        anchors.insert(AlacPoint::new(Line(2), Column(0)), 'b');
+
+        anchors.apply(
+            &[GridChange::SwapScreens { alternate: false }],
+            lines.clone(),
+        );
+        assert_eq!(
+            anchors.range(lines.clone()).collect::<Vec<_>>(),
+            vec![(AlacPoint::new(Line(1), Column(0)), &'a')]
+        );
+
+        anchors.apply(
+            &[GridChange::SwapScreens { alternate: true }],
+            lines.clone(),
+        );
+        assert_eq!(anchors.range(lines).count(), 0);
+    }
+}
@@ -0,0 +1,791 @@
+//! Inline images, sent to the terminal with the sixel (DCS) or the kitty graphics protocol (APC) sequences.
+//!
+//! Alacritty ignores both, so the images are decoded from the pty output by [`ImageParser`] and placed at the
+//! cursor: the image is anchored to the cell at its top left corner, so it moves along with its line, and
+//! disappears when the line is cleared.
+
+use std::{collections::VecDeque, io::Read, ops::RangeInclusive, sync::Arc};
+
+use alacritty_terminal::{
+    event::EventListener,
+    grid::Dimensions,
+    index::{Line, Point as AlacPoint},
+    vte::ansi::Handler,
+    Term,
+};
+use anyhow::{anyhow, bail, Context, Result};
+use collections::HashMap;
+use gpui::{px, size, ImageData, Pixels, Size};
+use image::{Bgra, ImageBuffer};
+
+use crate::grid_anchors::{GridAnchors, GridChange};
+
+/// Longer image sequences are dropped.
+const MAX_SEQUENCE_LENGTH: usize = 64 * 1024 * 1024;
+/// Bigger images are dropped.
+const MAX_IMAGE_SIDE: usize = 10_000;
+/// How much of the decoded image data the terminal keeps, older images are dropped after that.
+const MAX_IMAGES_BYTES: usize = 256 * 1024 * 1024;
+/// How many images, transmitted with the kitty protocol to be displayed later, the terminal keeps.
+const MAX_TRANSMITTED_IMAGES: usize = 64;
+
+/// The VT340 default sixel palette, in percents.
+const SIXEL_PALETTE: [[u8; 3]; 16] = [
+    [0, 0, 0],
+    [20, 20, 80],
+    [80, 13, 13],
+    [20, 80, 20],
+    [80, 20, 80],
+    [20, 80, 80],
+    [80, 80, 20],
+    [53, 53, 53],
+    [26, 26, 26],
+    [33, 33, 60],
+    [60, 26, 26],
+    [33, 60, 33],
+    [60, 33, 60],
+    [33, 60, 60],
+    [60, 60, 33],
+    [80, 80, 80],
+];
+
+/// An image, displayed in the terminal.
+#[derive(Clone)]
+pub struct TerminalImage {
+    pub data: Arc<ImageData>,
+    /// The size the image is displayed with.
+    pub size: Size<Pixels>,
+    /// How many terminal lines the image covers.
+    pub lines: usize,
+    /// The id, the image was transmitted with the kitty protocol with.
+    kitty_id: Option<u32>,
+}
+
+/// The images displayed in the terminal, by their ids.
+#[derive(Default)]
+struct TerminalImages {
+    images: HashMap<usize, TerminalImage>,
+    /// The ids of the images, anchored to their top left corners.
+    anchors: GridAnchors<usize>,
+    order: VecDeque<usize>,
+    next_id: usize,
+    total_bytes: usize,
+}
+
+impl TerminalImages {
+    fn insert(&mut self, image: TerminalImage) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.total_bytes += image.data.as_bytes().len();
+        self.images.insert(id, image);
+        self.order.push_back(id);
+        while self.total_bytes > MAX_IMAGES_BYTES && self.order.len() > 1 {
+            if let Some(oldest) = self.order.front().copied() {
+                self.remove(oldest);
+            }
+        }
+        id
+    }
+
+    fn remove(&mut self, id: usize) {
+        if let Some(image) = self.images.remove(&id) {
+            self.total_bytes -= image.data.as_bytes().len();
+            self.order.retain(|other_id| *other_id != id);
+            self.anchors.retain(|other_id| *other_id != id);
+        }
+    }
+
+    fn retain(&mut self, mut f: impl FnMut(&TerminalImage) -> bool) {
+        let removed = self
+            .images
+            .iter()
+            .filter(|(_, image)| !f(image))
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        for id in removed {
+            self.remove(id);
+        }
+    }
+
+    fn visible_images<T>(&self, term: &Term<T>) -> Vec<(AlacPoint, TerminalImage)> {
+        let Some(max_lines) = self.images.values().map(|image| image.lines).max() else {
+            return Vec::new();
+        };
+        let top_line = Line(-(term.grid().display_offset() as i32));
+        let bottom_line = Line(top_line.0 + term.screen_lines() as i32 - 1);
+        // The images, anchored above the viewport, may still cover its first lines.
+        let first_line = Line(top_line.0 - max_lines as i32).max(term.topmost_line());
+        self.anchors
+            .range(first_line..=bottom_line)
+            .filter_map(|(point, id)| {
+                let image = self.images.get(id)?;
+                (point.line.0 + image.lines as i32 > top_line.0).then(|| (point, image.clone()))
+            })
+            .collect()
+    }
+}
+
+/// Finds the DCS and APC sequences in the pty output, byte by byte.
+#[derive(Debug, Default)]
+struct SequenceScanner {
+    state: ScannerState,
+    payload: Vec<u8>,
+    truncated: bool,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum ScannerState {
+    #[default]
+    Ground,
+    Escape,
+    Dcs,
+    DcsEscape,
+    Apc,
+    ApcEscape,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Sequence<'a> {
+    Dcs(&'a [u8]),
+    Apc(&'a [u8]),
+}
+
+impl SequenceScanner {
+    fn advance(&mut self, byte: u8) -> Option<Sequence<'_>> {
+        match (self.state, byte) {
+            (ScannerState::Ground, 0x1b) => self.state = ScannerState::Escape,
+            (ScannerState::Ground, _) => {}
+            (ScannerState::Escape | ScannerState::DcsEscape | ScannerState::ApcEscape, b'P') => {
+                self.start(ScannerState::Dcs)
+            }
+            (ScannerState::Escape | ScannerState::DcsEscape | ScannerState::ApcEscape, b'_') => {
+                self.start(ScannerState::Apc)
+            }
+            (ScannerState::Escape, 0x1b) => {}
+            (ScannerState::Escape, _) => self.state = ScannerState::Ground,
+            (ScannerState::DcsEscape, b'\\') => {
+                self.state = ScannerState::Ground;
+                return (!self.truncated).then(|| Sequence::Dcs(&self.payload));
+            }
+            (ScannerState::ApcEscape, b'\\') => {
+                self.state = ScannerState::Ground;
+                return (!self.truncated).then(|| Sequence::Apc(&self.payload));
+            }
+            (ScannerState::DcsEscape | ScannerState::ApcEscape, 0x1b) => {
+                self.state = ScannerState::Escape
+            }
+            (ScannerState::DcsEscape | ScannerState::ApcEscape, _) => {
+                self.state = ScannerState::Ground
+            }
+            (ScannerState::Dcs | ScannerState::Apc, 0x18 | 0x1a) => {
+                self.state = ScannerState::Ground
+            }
+            (ScannerState::Dcs, 0x1b) => self.state = ScannerState::DcsEscape,
+            (ScannerState::Apc, 0x1b) => self.state = ScannerState::ApcEscape,
+            (ScannerState::Dcs | ScannerState::Apc, _) => {
+                if self.payload.len() < MAX_SEQUENCE_LENGTH {
+                    self.payload.push(byte);
+                } else {
+                    self.truncated = true;
+                }
+            }
+        }
+        None
+    }
+
+    fn start(&mut self, state: ScannerState) {
+        self.state = state;
+        self.payload.clear();
+        self.truncated = false;
+    }
+}
+
+/// Decodes the images from the pty output and places them into the terminal.
+pub(crate) struct ImageParser {
+    scanner: SequenceScanner,
+    images: TerminalImages,
+    cell_size: Size<Pixels>,
+    /// The kitty image, being transmitted in chunks.
+    kitty_chunks: Option<(KittyCommand, Vec<u8>)>,
+    /// The kitty images, transmitted to be displayed later.
+    kitty_images: VecDeque<(u32, Arc<ImageData>)>,
+    /// The replies to the kitty protocol commands, to write to the pty.
+    responses: Vec<String>,
+}
+
+impl ImageParser {
+    pub(crate) fn new(cell_size: Size<Pixels>) -> Self {
+        Self {
+            scanner: SequenceScanner::default(),
+            images: TerminalImages::default(),
+            cell_size,
+            kitty_chunks: None,
+            kitty_images: VecDeque::new(),
+            responses: Vec::new(),
+        }
+    }
+
+    pub(crate) fn set_cell_size(&mut self, cell_size: Size<Pixels>) {
+        self.cell_size = cell_size;
+    }
+
+    /// Returns the images, visible in the terminal viewport, with the points of their top left corners.
+    pub(crate) fn visible_images<T>(&self, term: &Term<T>) -> Vec<(AlacPoint, TerminalImage)> {
+        self.images.visible_images(term)
+    }
+
+    /// Moves the images along with the terminal grid lines.
+    pub(crate) fn apply_grid_changes(
+        &mut self,
+        changes: &[GridChange],
+        lines: RangeInclusive<Line>,
+    ) {
+        self.images.anchors.apply(changes, lines);
+    }
+
+    /// Takes the replies to write to the pty.
+    pub(crate) fn take_responses(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.responses)
+    }
+
+    /// Whether the byte has to be passed to [`Self::advance`]. Outside of the DCS and APC sequences,
+    /// only the escape bytes, which may introduce them, have to be.
+    pub(crate) fn accepts(&self, byte: u8) -> bool {
+        byte == 0x1b || self.scanner.state != ScannerState::Ground
+    }
+
+    /// Handles the byte, after the terminal processed it.
+    pub(crate) fn advance<T: EventListener>(&mut self, term: &mut Term<T>, byte: u8) {
+        match self.scanner.advance(byte) {
+            Some(Sequence::Dcs(payload)) => {
+                if let Some(image) = decode_sixel(payload) {
+                    let size = image_size(&image);
+                    let lines =
+                        self.place(term, Arc::new(ImageData::new(image)), size, None, false);
+                    // The cursor is moved to the first column under the image.
+                    for _ in 0..lines {
+                        term.linefeed();
+                    }
+                    term.carriage_return();
+                }
+            }
+            Some(Sequence::Apc(payload)) => {
+                if let Some(control) = payload.strip_prefix(b"G") {
+                    let control = control.to_vec();
+                    self.handle_kitty_command(term, &control);
+                }
+            }
+            None => {}
+        }
+    }
+
+    fn handle_kitty_command<T: EventListener>(&mut self, term: &mut Term<T>, sequence: &[u8]) {
+        let Ok(sequence) = std::str::from_utf8(sequence) else {
+            return;
+        };
+        let (control, payload) = sequence.split_once(';').unwrap_or((sequence, ""));
+        let command = KittyCommand::parse(control);
+        let payload = match base64::decode(payload) {
+            Ok(payload) => payload,
+            Err(_) => {
+                self.kitty_chunks = None;
+                self.respond(&command, Err(anyhow!("EINVAL:invalid base64 data")));
+                return;
+            }
+        };
+
+        // The chunks after the first one only have the `m` and the `q` keys.
+        let (command, payload) = match self.kitty_chunks.take() {
+            Some((first_command, mut data)) => {
+                data.extend(payload);
+                (
+                    KittyCommand {
+                        more: command.more,
+                        ..first_command
+                    },
+                    data,
+                )
+            }
+            None => (command, payload),
+        };
+        if command.more {
+            if payload.len() <= MAX_SEQUENCE_LENGTH {
+                self.kitty_chunks = Some((command, payload));
+            }
+            return;
+        }
+
+        let result = match command.action {
+            'q' => command.decode(payload).map(|_| ()),
+            't' | 'T' => command.decode(payload).map(|image| {
+                let image = Arc::new(ImageData::new(image));
+                if command.action == 'T' {
+                    let size = command.display_size(&image, self.cell_size);
+                    self.place(term, image, size, command.id, command.move_cursor);
+                } else if let Some(id) = command.id {
+                    self.kitty_images.retain(|(other_id, _)| *other_id != id);
+                    if self.kitty_images.len() >= MAX_TRANSMITTED_IMAGES {
+                        self.kitty_images.pop_front();
+                    }
+                    self.kitty_images.push_back((id, image));
+                }
+            }),
+            'p' => {
+                let image = self
+                    .kitty_images
+                    .iter()
+                    .find(|(id, _)| Some(*id) == command.id)
+                    .map(|(_, image)| image.clone());
+                match image {
+                    Some(image) => {
+                        let size = command.display_size(&image, self.cell_size);
+                        self.place(term, image, size, command.id, command.move_cursor);
+                        Ok(())
+                    }
+                    None => Err(anyhow!("ENOENT:image not found")),
+                }
+            }
+            'd' => {
+                match command.delete {
+                    'i' | 'I' => self.images.retain(|image| image.kitty_id != command.id),
+                    _ => self.images.retain(|_| false),
+                }
+                if command.delete.is_ascii_uppercase() {
+                    self.kitty_images.clear();
+                }
+                return;
+            }
+            _ => Err(anyhow!("EINVAL:unsupported action")),
+        };
+        self.respond(&command, result);
+    }
+
+    fn respond(&mut self, command: &KittyCommand, result: Result<()>) {
+        // The clients expect replies only to the commands with the image ids.
+        let Some(id) = command.id else {
+            return;
+        };
+        let message = match result {
+            Ok(()) if command.quiet == 0 => "OK".to_string(),
+            Err(error) if command.quiet < 2 => format!("{error:#}"),
+            _ => return,
+        };
+        self.responses.push(format!("\x1b_Gi={id};{message}\x1b\\"));
+    }
+
+    /// Places the image at the cursor, returns how many lines it covers.
+    fn place<T: EventListener>(
+        &mut self,
+        term: &mut Term<T>,
+        data: Arc<ImageData>,
+        size: Size<Pixels>,
+        kitty_id: Option<u32>,
+        move_cursor: bool,
+    ) -> usize {
+        let columns = (size.width / self.cell_size.width).ceil().max(1.) as usize;
+        let lines = (size.height / self.cell_size.height).ceil().max(1.) as usize;
+        let id = self.images.insert(TerminalImage {
+            data,
+            size,
+            lines,
+            kitty_id,
+        });
+
+        let cursor = term.grid().cursor.point;
+        self.images.anchors.insert(cursor, id);
+        if move_cursor {
+            // The cursor is moved to the first column after the image, on its last line.
+            for _ in 1..lines {
+                term.linefeed();
+            }
+            let column = cursor.column + columns;
+            term.grid_mut().cursor.point.column = column.min(term.last_column());
+        }
+        lines
+    }
+}
+
+fn image_size(image: &ImageBuffer<Bgra<u8>, Vec<u8>>) -> Size<Pixels> {
+    size(px(image.width() as f32), px(image.height() as f32))
+}
+
+/// A command of the kitty graphics protocol: https://sw.kovidgoyal.net/kitty/graphics-protocol/
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct KittyCommand {
+    action: char,
+    format: u32,
+    medium: char,
+    width: usize,
+    height: usize,
+    id: Option<u32>,
+    more: bool,
+    compressed: bool,
+    quiet: u32,
+    columns: Option<u32>,
+    rows: Option<u32>,
+    move_cursor: bool,
+    delete: char,
+}
+
+impl KittyCommand {
+    fn parse(control: &str) -> Self {
+        let mut command = Self {
+            action: 't',
+            format: 32,
+            medium: 'd',
+            width: 0,
+            height: 0,
+            id: None,
+            more: false,
+            compressed: false,
+            quiet: 0,
+            columns: None,
+            rows: None,
+            move_cursor: true,
+            delete: 'a',
+        };
+        for (key, value) in control.split(',').filter_map(|pair| pair.split_once('=')) {
+            let char_value = value.chars().next().unwrap_or_default();
+            let number = value.parse::<u32>().ok();
+            match key {
+                "a" => command.action = char_value,
+                "f" => command.format = number.unwrap_or(32),
+                "t" => command.medium = char_value,
+                "s" => command.width = number.unwrap_or(0) as usize,
+                "v" => command.height = number.unwrap_or(0) as usize,
+                "i" => command.id = number,
+                "m" => command.more = number == Some(1),
+                "o" => command.compressed = char_value == 'z',
+                "q" => command.quiet = number.unwrap_or(0),
+                "c" => command.columns = number,
+                "r" => command.rows = number,
+                "C" => command.move_cursor = number != Some(1),
+                "d" => command.delete = char_value,
+                _ => {}
+            }
+        }
+        command
+    }
+
+    fn decode(&self, payload: Vec<u8>) -> Result<ImageBuffer<Bgra<u8>, Vec<u8>>> {
+        if self.medium != 'd' {
+            bail!("EINVAL:only the direct transmission is supported");
+        }
+        let payload = if self.compressed {
+            let mut decompressed = Vec::new();
+            flate2::read::ZlibDecoder::new(payload.as_slice())
+                .take(MAX_SEQUENCE_LENGTH as u64)
+                .read_to_end(&mut decompressed)
+                .context("EINVAL:invalid compressed data")?;
+            decompressed
+        } else {
+            payload
+        };
+
+        let bytes_per_pixel = match self.format {
+            100 => {
+                return Ok(
+                    image::load_from_memory_with_format(&payload, image::ImageFormat::Png)
+                        .context("EBADPNG:invalid PNG data")?
+                        .into_bgra8(),
+                )
+            }
+            24 => 3,
+            32 => 4,
+            _ => bail!("EINVAL:unsupported format"),
+        };
+        if self.width == 0
+            || self.height == 0
+            || self.width > MAX_IMAGE_SIDE
+            || self.height > MAX_IMAGE_SIDE
+            || payload.len() < self.width * self.height * bytes_per_pixel
+        {
+            bail!("ENODATA:insufficient image data");
+        }
+        let bgra = payload
+            .chunks_exact(bytes_per_pixel)
+            .take(self.width * self.height)
+            .flat_map(|pixel| {
+                [
+                    pixel[2],
+                    pixel[1],
+                    pixel[0],
+                    pixel.get(3).copied().unwrap_or(255),
+                ]
+            })
+            .collect();
+        ImageBuffer::from_raw(self.width as u32, self.height as u32, bgra)
+            .context("ENODATA:insufficient image data")
+    }
+
+    /// The size to display the image with: the columns and the rows requested, or its own size.
+    fn display_size(&self, image: &ImageData, cell_size: Size<Pixels>) -> Size<Pixels> {
+        let image_size = image.size();
+        let width = px(u32::from(image_size.width) as f32);
+        let height = px(u32::from(image_size.height) as f32);
+        match (self.columns, self.rows) {
+            (Some(columns), Some(rows)) => size(
+                cell_size.width * columns as f32,
+                cell_size.height * rows as f32,
+            ),
+            (Some(columns), None) => {
+                let width_to_fit = cell_size.width * columns as f32;
+                size(width_to_fit, height * (width_to_fit / width))
+            }
+            (None, Some(rows)) => {
+                let height_to_fit = cell_size.height * rows as f32;
+                size(width * (height_to_fit / height), height_to_fit)
+            }
+            (None, None) => size(width, height),
+        }
+    }
+}
+
+/// Decodes the sixel image from the DCS sequence payload: https://vt100.net/docs/vt3xx-gp/chapter14.html
+fn decode_sixel(payload: &[u8]) -> Option<ImageBuffer<Bgra<u8>, Vec<u8>>> {
+    let params_end = payload
+        .iter()
+        .position(|byte| !matches!(byte, b'0'..=b'9' | b';'))?;
+    if payload[params_end] != b'q' {
+        return None;
+    }
+
+    let mut palette = [[0, 0, 0]; 256];
+    for (color, percents) in palette.iter_mut().zip(SIXEL_PALETTE) {
+        *color = percents.map(from_percent);
+    }
+    let mut canvas = SixelCanvas::default();
+    let mut color = palette[0];
+    let mut index = params_end + 1;
+    while index < payload.len() {
+        match payload[index] {
+            b'"' => {
+                let (params, next_index) = parse_sixel_params(payload, index + 1);
+                index = next_index;
+                if let [_, _, width, height] = params[..] {
+                    canvas.reserve(width, height)?;
+                }
+                continue;
+            }
+            b'#' => {
+                let (params, next_index) = parse_sixel_params(payload, index + 1);
+                index = next_index;
+                match params[..] {
+                    [register] => color = palette[register.min(255)],
+                    [register, space, x, y, z, ..] => {
+                        let register = register.min(255);
+                        palette[register] = match space {
+                            1 => hls_to_rgb(x, y, z),
+                            2 => [from_percent(x), from_percent(y), from_percent(z)],
+                            _ => palette[register],
+                        };
+                        color = palette[register];
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            b'!' => {
+                let (params, next_index) = parse_sixel_params(payload, index + 1);
+                index = next_index;
+                let repeat = params.first().copied().unwrap_or(1).max(1);
+                if let Some(&byte @ b'?'..=b'~') = payload.get(index) {
+                    canvas.draw(byte - b'?', repeat, color)?;
+                    index += 1;
+                }
+                continue;
+            }
+            byte @ b'?'..=b'~' => canvas.draw(byte - b'?', 1, color)?,
+            b'$' => canvas.x = 0,
+            b'-' => {
+                canvas.x = 0;
+                canvas.y += 6;
+            }
+            _ => {}
+        }
+        index += 1;
+    }
+    canvas.into_image()
+}
+
+fn parse_sixel_params(payload: &[u8], start: usize) -> (Vec<usize>, usize) {
+    let end = payload[start..]
+        .iter()
+        .position(|byte| !matches!(byte, b'0'..=b'9' | b';'))
+        .map_or(payload.len(), |position| start + position);
+    let params = std::str::from_utf8(&payload[start..end])
+        .unwrap_or_default()
+        .split(';')
+        .map(|param| param.parse().unwrap_or(0))
+        .collect();
+    (params, end)
+}
+
+fn from_percent(percent: usize) -> u8 {
+    (percent.min(100) * 255 / 100) as u8
+}
+
+/// Converts the sixel HLS color, where the blue hue is at 0 degrees, to RGB.
+fn hls_to_rgb(hue: usize, lightness: usize, saturation: usize) -> [u8; 3] {
+    let hue = ((hue + 240) % 360) as f32 / 360.;
+    let lightness = lightness.min(100) as f32 / 100.;
+    let saturation = saturation.min(100) as f32 / 100.;
+    let rgb = gpui::hsla(hue, saturation, lightness, 1.).to_rgb();
+    [rgb.r, rgb.g, rgb.b].map(|component| (component * 255.).round() as u8)
+}
+
+/// The pixels of the sixel image being decoded, growing as the image is drawn.
+#[derive(Default)]
+struct SixelCanvas {
+    pixels: Vec<u8>,
+    width: usize,
+    height: usize,
+    x: usize,
+    y: usize,
+}
+
+impl SixelCanvas {
+    fn reserve(&mut self, width: usize, height: usize) -> Option<()> {
+        if width <= self.width && height <= self.height {
+            return Some(());
+        }
+        if width > MAX_IMAGE_SIDE || height > MAX_IMAGE_SIDE {
+            return None;
+        }
+        let new_width = width.max(self.width);
+        let new_height = height.max(self.height);
+        let mut pixels = vec![0; new_width * new_height * 4];
+        for row in 0..self.height {
+            let old_row = &self.pixels[row * self.width * 4..(row + 1) * self.width * 4];
+            pixels[row * new_width * 4..][..old_row.len()].copy_from_slice(old_row);
+        }
+        self.pixels = pixels;
+        self.width = new_width;
+        self.height = new_height;
+        Some(())
+    }
+
+    fn draw(&mut self, sixel: u8, repeat: usize, [r, g, b]: [u8; 3]) -> Option<()> {
+        if sixel != 0 {
+            // Grows by the whole sixel rows, doubling the width, to not reallocate on every sixel.
+            let width = if self.x + repeat > self.width {
+                (self.x + repeat).max(self.width * 2).min(MAX_IMAGE_SIDE)
+            } else {
+                self.width
+            };
+            self.reserve(width.max(self.x + repeat), self.height.max(self.y + 6))?;
+            for x in self.x..self.x + repeat {
+                for bit in 0..6 {
+                    if sixel & (1 << bit) != 0 {
+                        let offset = ((self.y + bit) * self.width + x) * 4;
+                        self.pixels[offset..offset + 4].copy_from_slice(&[b, g, r, 255]);
+                    }
+                }
+            }
+        }
+        self.x += repeat;
+        Some(())
+    }
+
+    fn into_image(self) -> Option<ImageBuffer<Bgra<u8>, Vec<u8>>> {
+        // Trims the transparent columns and rows, the canvas grew by in advance.
+        let is_drawn = |x: usize, y: usize| self.pixels[(y * self.width + x) * 4 + 3] != 0;
+        let width = (0..self.width)
+            .rev()
+            .find(|x| (0..self.height).any(|y| is_drawn(*x, y)))?
+            + 1;
+        let height = (0..self.height)
+            .rev()
+            .find(|y| (0..width).any(|x| is_drawn(x, *y)))?
+            + 1;
+        let pixels = (0..height)
+            .flat_map(|y| &self.pixels[y * self.width * 4..(y * self.width + width) * 4])
+            .copied()
+            .collect();
+        ImageBuffer::from_raw(width as u32, height as u32, pixels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gpui::{px, size};
+
+    use alacritty_terminal::{
+        event::VoidListener,
+        term::{Config, Term},
+    };
+
+    use crate::TerminalSize;
+
+    use super::{decode_sixel, ImageParser, KittyCommand, Sequence, SequenceScanner};
+
+    #[test]
+    fn test_scanning_sequences() {
+        let mut scanner = SequenceScanner::default();
+        let mut sequences = Vec::new();
+        for byte in b"text\x1bP0;1q#1~\x1b\\\x1b]0;title\x07\x1b_Ga=q;AAAA\x1b\\" {
+            match scanner.advance(*byte) {
+                Some(Sequence::Dcs(payload)) => sequences.push(("dcs", payload.to_vec())),
+                Some(Sequence::Apc(payload)) => sequences.push(("apc", payload.to_vec())),
+                None => {}
+            }
+        }
+        assert_eq!(
+            sequences,
+            vec![("dcs", b"0;1q#1~".to_vec()), ("apc", b"Ga=q;AAAA".to_vec())]
+        );
+    }
+
+    #[test]
+    fn test_accepting_bytes() {
+        let terminal_size = TerminalSize::new(px(10.), px(10.), size(px(100.), px(100.)));
+        let mut term = Term::new(Config::default(), &terminal_size, VoidListener);
+        let mut parser = ImageParser::new(size(px(10.), px(10.)));
+        let mut accepted = Vec::new();
+        for &byte in b"a\x1b[0m\x1b_x\x1b\\b" {
+            if parser.accepts(byte) {
+                accepted.push(byte);
+                parser.advance(&mut term, byte);
+            }
+        }
+        assert_eq!(accepted, b"\x1b[\x1b_x\x1b\\");
+    }
+
+    #[test]
+    fn test_decoding_sixel() {
+        // A red 3x6 rectangle with a green 2x1 line under it, the second line starts with the transparent pixel.
+        let image = decode_sixel(b"0;1;0q\"1;1;3;12#1;2;100;0;0!3~-#2;2;0;100;0?@@").unwrap();
+        assert_eq!(image.dimensions(), (3, 7));
+        assert_eq!(image.get_pixel(2, 5).0, [0, 0, 255, 255]);
+        assert_eq!(image.get_pixel(0, 6).0, [0, 0, 0, 0]);
+        assert_eq!(image.get_pixel(1, 6).0, [0, 255, 0, 255]);
+
+        assert!(decode_sixel(b"0;1;0p#1~").is_none());
+        assert!(decode_sixel(b"q").is_none());
+    }
+
+    #[test]
+    fn test_kitty_commands() {
+        let command = KittyCommand::parse("a=T,f=24,s=2,v=1,i=7,q=1,C=1");
+        assert_eq!(command.action, 'T');
+        assert_eq!(command.id, Some(7));
+        assert!(!command.move_cursor);
+
+        let image = command.decode(vec![255, 0, 0, 0, 0, 255]).unwrap();
+        assert_eq!(image.dimensions(), (2, 1));
+        assert_eq!(image.get_pixel(0, 0).0, [0, 0, 255, 255]);
+        assert_eq!(image.get_pixel(1, 0).0, [255, 0, 0, 255]);
+        assert!(command.decode(vec![255, 0, 0]).is_err());
+
+        let display_size = command.display_size(&gpui::ImageData::new(image), size(px(1.), px(2.)));
+        assert_eq!(display_size, size(px(2.), px(1.)));
+        let command = KittyCommand::parse("a=p,i=7,c=4");
+        assert_eq!(
+            command.display_size(
+                &gpui::ImageData::new(image::ImageBuffer::new(2, 1)),
+                size(px(1.), px(2.))
+            ),
+            size(px(4.), px(2.))
+        );
+    }
+}
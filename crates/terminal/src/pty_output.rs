@@ -0,0 +1,467 @@
+//! Alacritty's event loop parses the pty output with a parser of its own, which leaves the sequences it does
+//! not support out of the terminal. [`PtyEventLoop`] follows the same loop, but parses the output with
+//! [`OutputParser`], which also passes it to the shell integration and the image parsers.
+
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    io::{self, ErrorKind, Read, Write},
+    num::NonZeroUsize,
+    ops::RangeInclusive,
+    path::Path,
+    sync::{mpsc, Arc},
+    thread,
+    time::Instant,
+};
+
+use alacritty_terminal::{
+    event::{Event as AlacTermEvent, EventListener, Notify, OnResize},
+    event_loop::Msg,
+    grid::Dimensions,
+    index::{Line, Point as AlacPoint},
+    sync::FairMutex,
+    term::TermMode,
+    tty::{self, ChildEvent, EventedPty, EventedReadWrite},
+    vte::ansi::Processor,
+    Term,
+};
+use gpui::{px, size};
+use parking_lot::Mutex;
+use polling::{Event, Events, PollMode, Poller};
+
+use crate::{
+    grid_anchors::{GridChange, GridTracker},
+    images::{ImageParser, TerminalImage},
+    shell_integration::{CommandMark, ShellIntegrationParser},
+    TerminalSize,
+};
+
+/// How much of the pty output is read at once.
+const READ_BUFFER_SIZE: usize = 0x10_0000;
+
+/// How much of the pty output is parsed before the terminal is unlocked, to not block its rendering for too long.
+const MAX_LOCKED_READ: usize = u16::MAX as usize;
+
+/// Parses the pty output into the terminal.
+///
+/// The parser is shared between the pty event loop and the terminal model, and it is always locked before the terminal,
+/// to not deadlock.
+pub(crate) struct OutputParser {
+    processor: Processor,
+    grid_tracker: GridTracker,
+    shell_integration: Option<ShellIntegrationParser>,
+    images: ImageParser,
+}
+
+impl OutputParser {
+    pub(crate) fn new(
+        shell_integration: Option<ShellIntegrationParser>,
+        images: ImageParser,
+    ) -> Self {
+        Self {
+            processor: Processor::new(),
+            grid_tracker: GridTracker::new(),
+            shell_integration,
+            images,
+        }
+    }
+
+    pub(crate) fn advance<T: EventListener>(&mut self, term: &mut Term<T>, bytes: &[u8]) {
+        self.flush_expired_sync(term, Instant::now());
+        for &byte in bytes {
+            let alternate_screen = term.mode().contains(TermMode::ALT_SCREEN);
+            self.grid_tracker.advance(term, byte);
+            self.processor.advance(term, byte);
+            let mut changes = self.grid_tracker.take_changes();
+            if term.mode().contains(TermMode::ALT_SCREEN) != alternate_screen {
+                changes.push(GridChange::SwapScreens {
+                    alternate: !alternate_screen,
+                });
+            }
+            if !changes.is_empty() {
+                self.apply_grid_changes(term, &changes);
+            }
+            if let Some(shell_integration) = &mut self.shell_integration {
+                shell_integration.advance(term, byte);
+            }
+            if self.images.accepts(byte) {
+                self.images.advance(term, byte);
+            }
+        }
+    }
+
+    /// How many bytes of the synchronized update in progress are not shown yet.
+    fn sync_bytes_count(&self) -> usize {
+        self.processor.sync_bytes_count()
+    }
+
+    /// When the synchronized update in progress, if any, has to be shown, even if its end is not received.
+    fn sync_deadline(&self) -> Option<Instant> {
+        self.processor.sync_timeout().sync_timeout()
+    }
+
+    /// Shows the synchronized update in progress, if its deadline has passed. Returns whether it was shown.
+    fn flush_expired_sync<T: EventListener>(&mut self, term: &mut Term<T>, now: Instant) -> bool {
+        if self.sync_deadline().is_some_and(|deadline| deadline <= now) {
+            self.processor.stop_sync(term);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Resizes the terminal, keeping the images and the command marks at the same distance from the cursor.
+    pub(crate) fn resize<T: EventListener>(&mut self, term: &mut Term<T>, size: TerminalSize) {
+        let cursor_line = term.grid().cursor.point.line;
+        term.resize(size);
+        self.grid_tracker.reset_scroll_region();
+        let shift = term.grid().cursor.point.line.0 - cursor_line.0;
+        self.apply_grid_changes(term, &[GridChange::Shift(shift)]);
+    }
+
+    /// Moves the images and the command marks along with the terminal grid lines, after the terminal changed them.
+    pub(crate) fn apply_grid_changes<T>(&mut self, term: &Term<T>, changes: &[GridChange]) {
+        let lines = term.topmost_line()..=term.bottommost_line();
+        if let Some(shell_integration) = &mut self.shell_integration {
+            shell_integration.apply_grid_changes(changes, lines.clone());
+        }
+        self.images.apply_grid_changes(changes, lines);
+    }
+
+    /// Returns the marks of the commands on the lines given, from the oldest to the newest.
+    pub(crate) fn command_marks(&self, lines: RangeInclusive<Line>) -> Vec<(Line, CommandMark)> {
+        self.shell_integration
+            .as_ref()
+            .map_or_else(Vec::new, |shell_integration| {
+                shell_integration.command_marks(lines)
+            })
+    }
+
+    /// The working directory, reported by the shell integration.
+    pub(crate) fn shell_cwd(&self) -> Option<&Path> {
+        self.shell_integration.as_ref()?.cwd()
+    }
+
+    /// Returns the images, visible in the terminal viewport, with the points of their top left corners.
+    pub(crate) fn visible_images<T>(&self, term: &Term<T>) -> Vec<(AlacPoint, TerminalImage)> {
+        self.images.visible_images(term)
+    }
+}
+
+/// Sends the input and the other messages to a [`PtyEventLoop`].
+#[derive(Clone)]
+pub(crate) struct PtySender {
+    sender: mpsc::Sender<Msg>,
+    poller: Arc<Poller>,
+}
+
+impl PtySender {
+    pub(crate) fn send(&self, msg: Msg) -> io::Result<()> {
+        self.sender
+            .send(msg)
+            .map_err(|_| io::Error::from(ErrorKind::BrokenPipe))?;
+        self.poller.notify()
+    }
+}
+
+impl Notify for PtySender {
+    fn notify<B: Into<Cow<'static, [u8]>>>(&self, bytes: B) {
+        let bytes = bytes.into();
+        // The pty hangs if empty input is written to it.
+        if !bytes.is_empty() {
+            self.send(Msg::Input(bytes)).ok();
+        }
+    }
+}
+
+/// Reads the pty output on a thread of its own, parsing it into the terminal with [`OutputParser`],
+/// and writes the input into the pty.
+pub(crate) struct PtyEventLoop<P, L> {
+    poller: Arc<Poller>,
+    pty: P,
+    receiver: mpsc::Receiver<Msg>,
+    sender: PtySender,
+    term: Arc<FairMutex<Term<L>>>,
+    listener: L,
+    parser: Arc<Mutex<OutputParser>>,
+    /// Whether the output, that is left in the pty after the child process exits, is read before stopping.
+    drain_on_exit: bool,
+    write_list: VecDeque<Cow<'static, [u8]>>,
+    /// The input being written, with the length of its part written already.
+    writing: Option<(Cow<'static, [u8]>, usize)>,
+}
+
+impl<P, L> PtyEventLoop<P, L>
+where
+    P: EventedPty + OnResize + Send + 'static,
+    L: EventListener + Send + 'static,
+{
+    pub(crate) fn new(
+        pty: P,
+        term: Arc<FairMutex<Term<L>>>,
+        listener: L,
+        parser: Arc<Mutex<OutputParser>>,
+        drain_on_exit: bool,
+    ) -> io::Result<Self> {
+        let poller = Arc::new(Poller::new()?);
+        let (sender, receiver) = mpsc::channel();
+        Ok(Self {
+            sender: PtySender {
+                sender,
+                poller: poller.clone(),
+            },
+            poller,
+            pty,
+            receiver,
+            term,
+            listener,
+            parser,
+            drain_on_exit,
+            write_list: VecDeque::new(),
+            writing: None,
+        })
+    }
+
+    pub(crate) fn sender(&self) -> PtySender {
+        self.sender.clone()
+    }
+
+    pub(crate) fn spawn(mut self) -> io::Result<thread::JoinHandle<()>> {
+        thread::Builder::new()
+            .name("PTY reader".to_string())
+            .spawn(move || self.run())
+    }
+
+    fn run(&mut self) {
+        let mut buffer = vec![0; READ_BUFFER_SIZE].into_boxed_slice();
+        let mut interest = Event::readable(tty::PTY_READ_WRITE_TOKEN);
+        let poll_mode = PollMode::Level;
+        // SAFETY: the pty is deregistered below, before it gets dropped.
+        if let Err(error) = unsafe { self.pty.register(&self.poller, interest, poll_mode) } {
+            log::error!("failed to register the pty in the event loop: {error}");
+            return;
+        }
+
+        let mut events = Events::with_capacity(NonZeroUsize::new(1024).unwrap());
+        'event_loop: loop {
+            // Wake up when the synchronized update in progress has to be shown.
+            let timeout = self
+                .parser
+                .lock()
+                .sync_deadline()
+                .map(|deadline| deadline.saturating_duration_since(Instant::now()));
+            events.clear();
+            if let Err(error) = self.poller.wait(&mut events, timeout) {
+                if error.kind() == ErrorKind::Interrupted {
+                    continue;
+                }
+                log::error!("failed to poll the pty: {error}");
+                break;
+            }
+
+            if events.is_empty() {
+                let mut parser = self.parser.lock();
+                if parser.flush_expired_sync(&mut *self.term.lock(), Instant::now()) {
+                    self.listener.send_event(AlacTermEvent::Wakeup);
+                }
+            }
+
+            if !self.drain_receiver() {
+                break;
+            }
+
+            for event in events.iter() {
+                match event.key {
+                    tty::PTY_CHILD_EVENT_TOKEN => {
+                        if let Some(ChildEvent::Exited(code)) = self.pty.next_child_event() {
+                            if let Some(code) = code {
+                                self.listener.send_event(AlacTermEvent::ChildExit(code));
+                            }
+                            if self.drain_on_exit {
+                                self.read_pty(&mut buffer).ok();
+                            }
+                            self.listener.send_event(AlacTermEvent::Exit);
+                            self.listener.send_event(AlacTermEvent::Wakeup);
+                            break 'event_loop;
+                        }
+                    }
+                    tty::PTY_READ_WRITE_TOKEN => {
+                        if event.is_interrupt() {
+                            continue;
+                        }
+                        if event.readable {
+                            if let Err(error) = self.read_pty(&mut buffer) {
+                                // Reading fails with `EIO` on Linux, once the child process hangs up:
+                                // its exit event is going to come next.
+                                #[cfg(target_os = "linux")]
+                                if error.raw_os_error() == Some(libc::EIO) {
+                                    continue;
+                                }
+                                log::error!("failed to read from the pty: {error}");
+                                break 'event_loop;
+                            }
+                        }
+                        if event.writable {
+                            if let Err(error) = self.write_pty() {
+                                log::error!("failed to write to the pty: {error}");
+                                break 'event_loop;
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            let needs_write = self.writing.is_some() || !self.write_list.is_empty();
+            if needs_write != interest.writable {
+                interest.writable = needs_write;
+                if let Err(error) = self.pty.reregister(&self.poller, interest, poll_mode) {
+                    log::error!("failed to register the pty in the event loop: {error}");
+                    break;
+                }
+            }
+        }
+
+        self.pty.deregister(&self.poller).ok();
+    }
+
+    /// Handles the messages received, returns `false` if the loop has to shut down.
+    fn drain_receiver(&mut self) -> bool {
+        while let Ok(msg) = self.receiver.try_recv() {
+            match msg {
+                Msg::Input(input) => self.write_list.push_back(input),
+                Msg::Resize(window_size) => {
+                    self.parser.lock().images.set_cell_size(size(
+                        px(window_size.cell_width as f32),
+                        px(window_size.cell_height as f32),
+                    ));
+                    self.pty.on_resize(window_size);
+                }
+                Msg::Shutdown => return false,
+            }
+        }
+        true
+    }
+
+    /// Reads the pty output until it would block, parsing it into the terminal.
+    ///
+    /// Like in Alacritty, the reader leases the terminal, so that the renderer cannot lock it in between,
+    /// but unlocks it every [`MAX_LOCKED_READ`] bytes parsed.
+    fn read_pty(&mut self, buffer: &mut [u8]) -> io::Result<()> {
+        let mut unprocessed = 0;
+        let mut processed = 0;
+        // The parser is locked before the terminal, as everywhere else.
+        let mut parser = self.parser.lock();
+        let _term_lease = self.term.lease();
+        let mut term = None;
+        loop {
+            match self.pty.reader().read(&mut buffer[unprocessed..]) {
+                Ok(0) if unprocessed == 0 => break,
+                Ok(read) => unprocessed += read,
+                Err(error) => match error.kind() {
+                    ErrorKind::Interrupted | ErrorKind::WouldBlock => {
+                        if unprocessed == 0 {
+                            break;
+                        }
+                    }
+                    _ => return Err(error),
+                },
+            }
+
+            let term = match &mut term {
+                Some(term) => term,
+                None => term.insert(match self.term.try_lock_unfair() {
+                    // Waits for the terminal, once there is no more room for the output to read.
+                    None if unprocessed >= buffer.len() => self.term.lock_unfair(),
+                    None => continue,
+                    Some(term) => term,
+                }),
+            };
+            parser.advance(&mut **term, &buffer[..unprocessed]);
+            processed += unprocessed;
+            unprocessed = 0;
+            if processed >= MAX_LOCKED_READ {
+                break;
+            }
+        }
+        drop(term);
+
+        self.write_list.extend(
+            parser
+                .images
+                .take_responses()
+                .into_iter()
+                .map(|response| Cow::Owned(response.into_bytes())),
+        );
+        // Nothing changed on the screen, if all of the output belongs to a synchronized update.
+        if processed > 0 && parser.sync_bytes_count() < processed {
+            self.listener.send_event(AlacTermEvent::Wakeup);
+        }
+        Ok(())
+    }
+
+    /// Writes as much of the pending input into the pty, as it accepts without blocking.
+    fn write_pty(&mut self) -> io::Result<()> {
+        loop {
+            let (input, written) = match self.writing.take() {
+                Some(writing) => writing,
+                None => match self.write_list.pop_front() {
+                    Some(input) => (input, 0),
+                    None => return Ok(()),
+                },
+            };
+            match self.pty.writer().write(&input[written..]) {
+                Ok(0) => {
+                    self.writing = Some((input, written));
+                    return Ok(());
+                }
+                Ok(len) if written + len < input.len() => {
+                    self.writing = Some((input, written + len));
+                }
+                Ok(_) => {}
+                Err(error) => {
+                    self.writing = Some((input, written));
+                    return match error.kind() {
+                        ErrorKind::Interrupted | ErrorKind::WouldBlock => Ok(()),
+                        _ => Err(error),
+                    };
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use alacritty_terminal::{
+        event::VoidListener,
+        index::{Column, Line},
+        term::{Config, Term},
+    };
+    use gpui::{px, size};
+
+    use crate::{images::ImageParser, TerminalSize};
+
+    use super::OutputParser;
+
+    #[test]
+    fn test_flushing_expired_sync() {
+        let terminal_size = TerminalSize::new(px(10.), px(10.), size(px(100.), px(100.)));
+        let mut term = Term::new(Config::default(), &terminal_size, VoidListener);
+        let mut parser = OutputParser::new(None, ImageParser::new(size(px(10.), px(10.))));
+
+        // The update is not shown until its end, which never comes here.
+        parser.advance(&mut term, b"\x1b[?2026hab");
+        assert_eq!(term.grid()[Line(0)][Column(0)].c, ' ');
+        let deadline = parser.sync_deadline().unwrap();
+        assert!(!parser.flush_expired_sync(&mut term, deadline - Duration::from_millis(1)));
+        assert_eq!(term.grid()[Line(0)][Column(0)].c, ' ');
+
+        assert!(parser.flush_expired_sync(&mut term, deadline));
+        assert_eq!(parser.sync_deadline(), None);
+        assert_eq!(term.grid()[Line(0)][Column(0)].c, 'a');
+        assert_eq!(term.grid()[Line(0)][Column(1)].c, 'b');
+    }
+}
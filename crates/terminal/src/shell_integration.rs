@@ -0,0 +1,552 @@
+//! Shell integration: the shell marks its prompts and commands with OSC 133 (or VS Code's OSC 633) sequences
+//! and reports its working directory with OSC 7, using the scripts Zed starts the supported shells with.
+//!
+//! Alacritty ignores these sequences, so [`ShellIntegrationParser`] finds them in the pty output, right after the
+//! terminal processed each byte, and notes the marks at the exact terminal position, where the shell printed them.
+
+use std::{
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use alacritty_terminal::{
+    grid::Dimensions,
+    index::{Column, Line, Point as AlacPoint},
+    Term,
+};
+use collections::HashMap;
+use url::Url;
+use util::{paths::SHELL_INTEGRATION_DIR, ResultExt};
+
+use crate::{
+    grid_anchors::{GridAnchors, GridChange},
+    terminal_settings::Shell,
+};
+
+/// The scripts, which are written into the shell integration directory, with their relative paths.
+const SCRIPTS: &[(&str, &str)] = &[
+    ("zed.bash", include_str!("./shell_integration/zed.bash")),
+    ("zed.fish", include_str!("./shell_integration/zed.fish")),
+    (
+        "zsh/zed.zsh",
+        include_str!("./shell_integration/zsh/zed.zsh"),
+    ),
+    (
+        "zsh/.zshenv",
+        include_str!("./shell_integration/zsh/.zshenv"),
+    ),
+    (
+        "zsh/.zprofile",
+        include_str!("./shell_integration/zsh/.zprofile"),
+    ),
+    ("zsh/.zshrc", include_str!("./shell_integration/zsh/.zshrc")),
+];
+
+/// Longer OSC sequences are not shell integration ones, so they are not collected.
+const MAX_OSC_LENGTH: usize = 4096;
+
+/// Changes the way the shell is started, so that it loads Zed's shell integration script after the user's
+/// configuration. Returns `None` for the shells, which have no integration script, or are started with the
+/// user's arguments.
+pub(crate) fn integrate(
+    shell: &Shell,
+    env: &mut HashMap<String, String>,
+) -> Option<alacritty_terminal::tty::Shell> {
+    // The scripts are written for the Unix shells only, for now.
+    if cfg!(windows) {
+        return None;
+    }
+    let program = match shell {
+        Shell::System => std::env::var("SHELL").ok()?,
+        Shell::Program(program) => program.clone(),
+        Shell::WithArguments { .. } => return None,
+    };
+    // Alacritty starts the system shell as a login shell on macOS, keep it this way.
+    let login = cfg!(target_os = "macos") && matches!(shell, Shell::System);
+    let dir = scripts_dir()?;
+
+    let args = match Path::new(&program).file_name()?.to_str()? {
+        "bash" => {
+            // Bash ignores `--init-file` in login shells, so the script loads the profile instead.
+            if login {
+                env.insert("ZED_SHELL_LOGIN".to_string(), "1".to_string());
+            }
+            vec![
+                "--init-file".to_string(),
+                dir.join("zed.bash").to_string_lossy().into_owned(),
+            ]
+        }
+        "zsh" => {
+            if let Some(user_zdotdir) = env
+                .get("ZDOTDIR")
+                .cloned()
+                .or_else(|| std::env::var("ZDOTDIR").ok())
+            {
+                env.insert("ZED_USER_ZDOTDIR".to_string(), user_zdotdir);
+            }
+            env.insert(
+                "ZDOTDIR".to_string(),
+                dir.join("zsh").to_string_lossy().into_owned(),
+            );
+            if login {
+                vec!["-l".to_string()]
+            } else {
+                Vec::new()
+            }
+        }
+        "fish" => {
+            let script = dir.join("zed.fish").to_string_lossy().replace('\'', "\\'");
+            let mut args = vec!["--init-command".to_string(), format!("source '{script}'")];
+            if login {
+                args.insert(0, "-l".to_string());
+            }
+            args
+        }
+        _ => return None,
+    };
+    Some(alacritty_terminal::tty::Shell::new(program, args))
+}
+
+/// Returns the directory with the shell integration scripts, writing them there the first time.
+fn scripts_dir() -> Option<&'static Path> {
+    static INSTALLED: OnceLock<bool> = OnceLock::new();
+    let installed = *INSTALLED.get_or_init(|| {
+        SCRIPTS
+            .iter()
+            .try_for_each(|(relative_path, contents)| {
+                let path = SHELL_INTEGRATION_DIR.join(relative_path);
+                if std::fs::read_to_string(&path).ok().as_deref() == Some(*contents) {
+                    return Ok(());
+                }
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(&path, contents)
+            })
+            .log_err()
+            .is_some()
+    });
+    installed.then(|| SHELL_INTEGRATION_DIR.as_path())
+}
+
+/// A sequence the shell integration reports the shell state with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ShellSequence {
+    /// The shell started printing its prompt (`133;A`).
+    PromptStart,
+    /// The shell finished printing its prompt, the command is typed after it (`133;B`).
+    CommandStart,
+    /// The command was submitted and started running (`133;C`).
+    CommandExecuted,
+    /// The command finished, with the exit code given, if any (`133;D[;exit code]`).
+    CommandFinished(Option<i32>),
+    /// The shell changed its working directory (`7;file://host/path` or `633;P;Cwd=path`).
+    WorkingDirectory { host: Option<String>, path: PathBuf },
+}
+
+/// Finds the OSC sequences in the pty output, byte by byte.
+#[derive(Debug, Default)]
+struct OscScanner {
+    state: ScannerState,
+    payload: Vec<u8>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum ScannerState {
+    #[default]
+    Ground,
+    Escape,
+    Osc,
+    OscEscape,
+}
+
+impl OscScanner {
+    /// Returns the shell integration sequence, which the byte given completes, if any.
+    fn advance(&mut self, byte: u8) -> Option<ShellSequence> {
+        match (self.state, byte) {
+            (ScannerState::Ground, 0x1b) => self.state = ScannerState::Escape,
+            (ScannerState::Ground, _) => {}
+            (ScannerState::Escape | ScannerState::OscEscape, b']') => {
+                self.payload.clear();
+                self.state = ScannerState::Osc;
+            }
+            (ScannerState::Escape, 0x1b) => {}
+            (ScannerState::Escape, _) => self.state = ScannerState::Ground,
+            (ScannerState::Osc, 0x07) | (ScannerState::OscEscape, b'\\') => {
+                self.state = ScannerState::Ground;
+                return parse_osc(&self.payload);
+            }
+            (ScannerState::Osc, 0x1b) => self.state = ScannerState::OscEscape,
+            (ScannerState::Osc, 0x18 | 0x1a) => self.state = ScannerState::Ground,
+            (ScannerState::Osc, _) => {
+                if self.payload.len() < MAX_OSC_LENGTH {
+                    self.payload.push(byte);
+                }
+            }
+            (ScannerState::OscEscape, 0x1b) => self.state = ScannerState::Escape,
+            (ScannerState::OscEscape, _) => self.state = ScannerState::Ground,
+        }
+        None
+    }
+}
+
+fn parse_osc(payload: &[u8]) -> Option<ShellSequence> {
+    let payload = std::str::from_utf8(payload).ok()?;
+    let (command, params) = payload.split_once(';')?;
+    match command {
+        "133" | "633" => {
+            let mut params = params.split(';');
+            match params.next()? {
+                "A" => Some(ShellSequence::PromptStart),
+                "B" => Some(ShellSequence::CommandStart),
+                "C" => Some(ShellSequence::CommandExecuted),
+                "D" => Some(ShellSequence::CommandFinished(
+                    params.next().and_then(|code| code.parse().ok()),
+                )),
+                "P" if command == "633" => {
+                    let cwd = params.next()?.strip_prefix("Cwd=")?;
+                    Some(ShellSequence::WorkingDirectory {
+                        host: None,
+                        path: PathBuf::from(unescape_vscode_value(cwd)),
+                    })
+                }
+                _ => None,
+            }
+        }
+        "7" => {
+            let url = Url::parse(params).ok()?;
+            if url.scheme() != "file" {
+                return None;
+            }
+            let host = url
+                .host_str()
+                .filter(|host| !host.is_empty())
+                .map(|host| host.to_string());
+            let mut local_url = url.clone();
+            local_url.set_host(None).ok()?;
+            Some(ShellSequence::WorkingDirectory {
+                host,
+                path: local_url.to_file_path().ok()?,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// VS Code's shell integration escapes backslashes and semicolons in the values it reports.
+fn unescape_vscode_value(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(index) = rest.find('\\') {
+        unescaped.push_str(&rest[..index]);
+        rest = &rest[index + 1..];
+        if let Some(after_backslash) = rest.strip_prefix('\\') {
+            unescaped.push('\\');
+            rest = after_backslash;
+        } else if let Some(character) = rest
+            .strip_prefix('x')
+            .and_then(|hex| hex.get(..2))
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            unescaped.push(character as char);
+            rest = &rest[3..];
+        } else {
+            unescaped.push('\\');
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+/// A state of the shell command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandState {
+    /// The shell shows its prompt, the command is being typed.
+    Prompted,
+    /// The command was submitted and it is running.
+    Running,
+    /// The command finished with a zero exit code, or without reporting it.
+    Succeeded,
+    /// The command finished with a non-zero exit code.
+    Failed,
+}
+
+/// A mark the shell integration leaves on the line the command was typed on.
+///
+/// The mark is anchored to the first cell of the line, so it moves along with the line on scrolling, resizing
+/// and history truncation, and gets removed when the line is cleared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommandMark {
+    pub state: CommandState,
+    /// How many lines above the command line the prompt starts.
+    pub prompt_lines: u8,
+    /// How many lines below the command line the command output starts.
+    pub output_offset: u8,
+}
+
+impl CommandMark {
+    /// The line the command prompt starts at, given the line of the mark.
+    pub fn prompt_start(&self, line: Line) -> Line {
+        line - self.prompt_lines as usize
+    }
+}
+
+/// Marks the prompts and the commands, reported by the shell, in the terminal.
+pub(crate) struct ShellIntegrationParser {
+    scanner: OscScanner,
+    /// How many lines the current prompt has printed so far, if the shell is printing one.
+    prompt_lines: Option<u8>,
+    hostname: Option<String>,
+    /// The marks, anchored to the first cells of the command lines.
+    marks: GridAnchors<CommandMark>,
+    cwd: Option<PathBuf>,
+}
+
+impl ShellIntegrationParser {
+    pub(crate) fn new() -> Self {
+        Self {
+            scanner: OscScanner::default(),
+            prompt_lines: None,
+            hostname: sysinfo::System::host_name(),
+            marks: GridAnchors::default(),
+            cwd: None,
+        }
+    }
+
+    /// The working directory, the shell reported last.
+    pub(crate) fn cwd(&self) -> Option<&Path> {
+        self.cwd.as_deref()
+    }
+
+    /// Returns the marks of the commands on the lines given, from the oldest to the newest. The prompts, that
+    /// were redrawn elsewhere, are skipped: only the last command may not have been submitted yet.
+    pub(crate) fn command_marks(&self, lines: RangeInclusive<Line>) -> Vec<(Line, CommandMark)> {
+        let mut marks: Vec<(Line, CommandMark)> = Vec::new();
+        for (point, mark) in self.marks.range(lines) {
+            if marks
+                .last()
+                .is_some_and(|(_, last)| last.state == CommandState::Prompted)
+            {
+                marks.pop();
+            }
+            marks.push((point.line, *mark));
+        }
+        marks
+    }
+
+    /// Moves the marks along with the terminal grid lines.
+    pub(crate) fn apply_grid_changes(
+        &mut self,
+        changes: &[GridChange],
+        lines: RangeInclusive<Line>,
+    ) {
+        self.marks.apply(changes, lines);
+    }
+
+    fn set_mark(&mut self, line: Line, mark: CommandMark) {
+        self.marks.insert(AlacPoint::new(line, Column(0)), mark);
+    }
+
+    /// Returns the closest command mark at or above the line given.
+    fn last_mark_above<T>(&self, term: &Term<T>, line: Line) -> Option<(Line, CommandMark)> {
+        self.marks
+            .range(term.topmost_line()..=line)
+            .next_back()
+            .map(|(point, mark)| (point.line, *mark))
+    }
+
+    /// Handles the byte, after the terminal processed it.
+    pub(crate) fn advance<T>(&mut self, term: &Term<T>, byte: u8) {
+        if byte == b'\n' {
+            if let Some(prompt_lines) = &mut self.prompt_lines {
+                *prompt_lines = prompt_lines.saturating_add(1);
+            }
+        }
+        if let Some(sequence) = self.scanner.advance(byte) {
+            self.handle_sequence(term, sequence);
+        }
+    }
+
+    fn handle_sequence<T>(&mut self, term: &Term<T>, sequence: ShellSequence) {
+        let cursor_line = term.grid().cursor.point.line;
+        match sequence {
+            ShellSequence::PromptStart => self.prompt_lines = Some(0),
+            ShellSequence::CommandStart => {
+                let mark = CommandMark {
+                    state: CommandState::Prompted,
+                    prompt_lines: self.prompt_lines.take().unwrap_or(0),
+                    output_offset: 0,
+                };
+                self.set_mark(cursor_line, mark);
+            }
+            ShellSequence::CommandExecuted => {
+                self.prompt_lines = None;
+                if let Some((line, mut mark)) = self.last_mark_above(term, cursor_line) {
+                    if mark.state == CommandState::Prompted {
+                        mark.state = CommandState::Running;
+                        mark.output_offset =
+                            (cursor_line.0 - line.0).clamp(0, u8::MAX.into()) as u8;
+                        self.set_mark(line, mark);
+                    }
+                }
+            }
+            ShellSequence::CommandFinished(exit_code) => {
+                // The shells may report the finish after every prompt, even if no command was submitted.
+                if let Some((line, mut mark)) = self.last_mark_above(term, cursor_line) {
+                    if mark.state == CommandState::Running {
+                        mark.state = match exit_code {
+                            Some(code) if code != 0 => CommandState::Failed,
+                            _ => CommandState::Succeeded,
+                        };
+                        self.set_mark(line, mark);
+                    }
+                }
+            }
+            ShellSequence::WorkingDirectory { host, path } => {
+                if host.map_or(true, |host| self.is_local_host(&host)) {
+                    self.cwd = Some(path);
+                }
+            }
+        }
+    }
+
+    /// Whether the host, the shell reported its directory on, is this machine, and not a remote one,
+    /// connected to over ssh.
+    fn is_local_host(&self, host: &str) -> bool {
+        let short_name = |name: &str| name.split('.').next().unwrap_or(name).to_lowercase();
+        host == "localhost"
+            || self
+                .hostname
+                .as_deref()
+                .map_or(false, |hostname| short_name(hostname) == short_name(host))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use alacritty_terminal::{
+        event::VoidListener,
+        grid::Dimensions,
+        index::Line,
+        term::{Config, Term},
+    };
+    use gpui::{px, size};
+
+    use crate::{images::ImageParser, pty_output::OutputParser, TerminalSize};
+
+    use super::{
+        unescape_vscode_value, CommandMark, CommandState, OscScanner, ShellIntegrationParser,
+        ShellSequence,
+    };
+
+    fn scan(scanner: &mut OscScanner, output: &str) -> Vec<ShellSequence> {
+        output
+            .bytes()
+            .filter_map(|byte| scanner.advance(byte))
+            .collect()
+    }
+
+    #[test]
+    fn test_scanning_sequences() {
+        let mut scanner = OscScanner::default();
+        assert_eq!(
+            scan(
+                &mut scanner,
+                "\x1b]133;A\x07$ \x1b]133;B\x1b\\ls\r\n\x1b]133;C\x07Cargo.toml\r\n\x1b]133;D;2\x07"
+            ),
+            vec![
+                ShellSequence::PromptStart,
+                ShellSequence::CommandStart,
+                ShellSequence::CommandExecuted,
+                ShellSequence::CommandFinished(Some(2)),
+            ]
+        );
+
+        // Sequences may be split between the reads, other sequences are ignored.
+        assert_eq!(scan(&mut scanner, "\x1b]0;title\x07\x1b[1m\x1b]63"), vec![]);
+        assert_eq!(
+            scan(&mut scanner, "3;D\x07"),
+            vec![ShellSequence::CommandFinished(None)]
+        );
+        assert_eq!(scan(&mut scanner, "\x1b]133;X\x07\x1b]133\x07"), vec![]);
+    }
+
+    #[test]
+    fn test_scanning_working_directory() {
+        let mut scanner = OscScanner::default();
+        assert_eq!(
+            scan(&mut scanner, "\x1b]7;file://host/tmp/my%20dir/100%25\x07"),
+            vec![ShellSequence::WorkingDirectory {
+                host: Some("host".to_string()),
+                path: PathBuf::from("/tmp/my dir/100%"),
+            }]
+        );
+        assert_eq!(
+            scan(&mut scanner, "\x1b]7;file:///tmp\x07"),
+            vec![ShellSequence::WorkingDirectory {
+                host: None,
+                path: PathBuf::from("/tmp"),
+            }]
+        );
+        assert_eq!(
+            scan(&mut scanner, "\x1b]633;P;Cwd=/tmp/a\\x3bb\x07"),
+            vec![ShellSequence::WorkingDirectory {
+                host: None,
+                path: PathBuf::from("/tmp/a;b"),
+            }]
+        );
+        assert_eq!(scan(&mut scanner, "\x1b]7;https://zed.dev\x07"), vec![]);
+
+        assert_eq!(unescape_vscode_value(r"a\\b\x3bc\"), r"a\b;c\");
+    }
+
+    #[test]
+    fn test_marking_commands() {
+        let size = TerminalSize::new(px(10.), px(10.), size(px(100.), px(100.)));
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+        let mut parser = OutputParser::new(
+            Some(ShellIntegrationParser::new()),
+            ImageParser::new(size(px(10.), px(10.))),
+        );
+
+        parser.advance(
+            &mut term,
+            b"\x1b]133;A\x07~\r\n$ \x1b]133;B\x07false\r\n\x1b]133;C\x07\x1b]133;D;1\x07",
+        );
+        parser.advance(
+            &mut term,
+            b"\x1b]7;file:///tmp\x07\x1b]133;A\x07~\r\n$ \x1b]133;B\x07echo\r\n\x1b]133;C\x07\r\n\x1b]133;D;0\x07",
+        );
+        parser.advance(&mut term, b"\x1b]133;A\x07~\r\n$ \x1b]133;B\x07");
+
+        let mark = |state, output_offset| CommandMark {
+            state,
+            prompt_lines: 1,
+            output_offset,
+        };
+        assert_eq!(
+            parser.command_marks(term.topmost_line()..=term.bottommost_line()),
+            vec![
+                (Line(1), mark(CommandState::Failed, 1)),
+                (Line(3), mark(CommandState::Succeeded, 1)),
+                (Line(6), mark(CommandState::Prompted, 0)),
+            ]
+        );
+        assert_eq!(mark(CommandState::Failed, 1).prompt_start(Line(1)), Line(0));
+        assert_eq!(parser.shell_cwd(), Some(Path::new("/tmp")));
+
+        // The marks move along with their lines, when the output scrolls them into the history.
+        parser.advance(&mut term, b"ls\r\n\x1b]133;C\x07\r\n\r\n\r\n\r\n\r\n");
+        assert_eq!(
+            parser.command_marks(term.topmost_line()..=term.bottommost_line()),
+            vec![
+                (Line(-2), mark(CommandState::Failed, 1)),
+                (Line(0), mark(CommandState::Succeeded, 1)),
+                (Line(3), mark(CommandState::Running, 1)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,46 @@
+# Zed's shell integration for bash, started with `--init-file` in place of `~/.bashrc`.
+# Marks the prompts and the commands with OSC 133 sequences and reports the working directory with OSC 7.
+
+if [[ -n "$ZED_SHELL_LOGIN" ]]; then
+    unset ZED_SHELL_LOGIN
+    [[ -r /etc/profile ]] && . /etc/profile
+    for __zed_profile in ~/.bash_profile ~/.bash_login ~/.profile; do
+        if [[ -r "$__zed_profile" ]]; then
+            . "$__zed_profile"
+            break
+        fi
+    done
+    unset __zed_profile
+elif [[ -r ~/.bashrc ]]; then
+    . ~/.bashrc
+fi
+
+if [[ $- == *i* && -z "$__zed_shell_integration" ]]; then
+    __zed_shell_integration=1
+
+    __zed_prompt_start() {
+        local status=$?
+        # The shell reports every prompt, so the prompts without a command before them are skipped by the terminal.
+        printf '\e]133;D;%s\a' "$status"
+        printf '\e]7;file://%s%s\a' "$HOSTNAME" "${PWD//\%/%25}"
+        return $status
+    }
+
+    __zed_prompt_end() {
+        local status=$?
+        # Prompt themes may set the prompt on every command, so it is marked after them.
+        if [[ "$PS1" != *'133;B'* ]]; then
+            PS1="\[\e]133;A\a\]$PS1\[\e]133;B\a\]"
+        fi
+        return $status
+    }
+
+    if [[ "$(declare -p PROMPT_COMMAND 2>/dev/null)" == "declare -a"* ]]; then
+        PROMPT_COMMAND=(__zed_prompt_start "${PROMPT_COMMAND[@]}" __zed_prompt_end)
+    else
+        PROMPT_COMMAND="__zed_prompt_start${PROMPT_COMMAND:+; $PROMPT_COMMAND}; __zed_prompt_end"
+    fi
+
+    # `PS0` is printed before the command runs, it is supported since bash 4.4.
+    PS0="${PS0}\e]133;C\a"
+fi
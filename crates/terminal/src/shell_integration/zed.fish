@@ -0,0 +1,34 @@
+# Zed's shell integration for fish, sourced with `--init-command` after the user configuration.
+# Marks the prompts and the commands with OSC 133 sequences and reports the working directory with OSC 7.
+
+status is-interactive; or exit
+set -q __zed_shell_integration; and exit
+set -g __zed_shell_integration 1
+
+function __zed_command_executed --on-event fish_preexec
+    printf '\e]133;C\a'
+end
+
+function __zed_command_finished --on-event fish_postexec
+    printf '\e]133;D;%s\a' $status
+end
+
+function __zed_report_cwd --on-event fish_prompt
+    printf '\e]7;file://%s%s\a' $hostname (string replace --all '%' '%25' -- $PWD)
+end
+
+function __zed_restore_status
+    return $argv[1]
+end
+
+# The prompt is wrapped, so that it gets marked on every repaint.
+functions --query fish_prompt; and functions --copy fish_prompt __zed_user_fish_prompt
+function fish_prompt
+    set -l last_status $status
+    printf '\e]133;A\a'
+    if functions --query __zed_user_fish_prompt
+        __zed_restore_status $last_status
+        __zed_user_fish_prompt
+    end
+    printf '\e]133;B\a'
+end
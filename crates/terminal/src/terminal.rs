@@ -1,13 +1,21 @@
+mod background_task;
 pub mod mappings;
 
 pub use alacritty_terminal;
+pub use background_task::{BackgroundTaskState, BackgroundTaskStatus, TaskOutputScanner};
+pub use images::TerminalImage;
+pub use shell_integration::{CommandMark, CommandState};
 
+mod grid_anchors;
+mod images;
 mod pty_info;
+mod pty_output;
+mod shell_integration;
 pub mod terminal_settings;
 
 use alacritty_terminal::{
     event::{Event as AlacTermEvent, EventListener, Notify, WindowSize},
-    event_loop::{EventLoop, Msg, Notifier},
+    event_loop::Msg,
     grid::{Dimensions, Scroll as AlacScroll},
     index::{Boundary, Column, Direction as AlacDirection, Line, Point as AlacPoint},
     selection::{Selection, SelectionRange, SelectionType},
@@ -18,7 +26,7 @@ use alacritty_terminal::{
         Config, RenderableCursor, TermMode,
     },
     tty::{self, setup_env},
-    vte::ansi::{ClearMode, Handler, NamedPrivateMode, PrivateMode, Rgb},
+    vte::ansi::{ClearMode, Handler, NamedPrivateMode, PrivateMode, Processor, Rgb},
     Term,
 };
 use anyhow::{bail, Result};
@@ -33,13 +41,19 @@ use mappings::mouse::{
     scroll_report,
 };
 
+use background_task::BackgroundTaskEvent;
 use collections::{HashMap, VecDeque};
 use futures::StreamExt;
+use grid_anchors::GridChange;
+use images::ImageParser;
+use parking_lot::Mutex;
 use pty_info::PtyProcessInfo;
+use pty_output::{OutputParser, PtyEventLoop, PtySender};
 use serde::{Deserialize, Serialize};
 use settings::Settings;
+use shell_integration::ShellIntegrationParser;
 use smol::channel::{Receiver, Sender};
-use task::TaskId;
+use task::{Problem, TaskId};
 use terminal_settings::{AlternateScroll, Shell, TerminalBlink, TerminalSettings};
 use theme::{ActiveTheme, Theme};
 use util::truncate_and_trailoff;
@@ -64,7 +78,16 @@ use crate::mappings::{colors::to_alac_rgb, keys::to_esc_str};
 
 actions!(
     terminal,
-    [Clear, Copy, Paste, ShowCharacterPalette, SearchTest,]
+    [
+        Clear,
+        Copy,
+        Paste,
+        ShowCharacterPalette,
+        SearchTest,
+        ScrollToPreviousPrompt,
+        ScrollToNextPrompt,
+        CopyLastCommandOutput,
+    ]
 );
 
 ///Scrolling is unbearably sluggish by default. Alacritty supports a configurable
@@ -92,6 +115,12 @@ pub enum Event {
     SelectionsChanged,
     NewNavigationTarget(Option<MaybeNavigationTarget>),
     Open(MaybeNavigationTarget),
+    /// Problems, found in the task output by the task's problem matchers.
+    ProblemsFound(Vec<Problem>),
+    /// The background task started rebuilding, so the problems it reported before are outdated.
+    ProblemsOutdated,
+    /// The task finished, or its background status changed.
+    TaskStatusChanged,
 }
 
 #[derive(Clone, Debug)]
@@ -126,6 +155,9 @@ enum InternalEvent {
     // Adjusted mouse position, should open
     FindHyperlink(Point<Pixels>, bool),
     Copy,
+    ScrollToPreviousPrompt,
+    ScrollToNextPrompt,
+    CopyLastCommandOutput,
 }
 
 ///A translation struct for Alacritty to communicate with us from their event loop
@@ -290,6 +322,31 @@ impl Display for TerminalError {
 const DEFAULT_SCROLL_HISTORY_LINES: usize = 10_000;
 const MAX_SCROLL_HISTORY_LINES: usize = 100_000;
 
+fn scrollback_text<T>(term: &Term<T>, max_lines: usize) -> String {
+    let cursor_line = term.grid().cursor.point.line;
+    if cursor_line <= term.topmost_line() {
+        return String::new();
+    }
+    let start_line = cmp::max(term.topmost_line(), cursor_line - max_lines);
+    let start = AlacPoint::new(start_line, Column(0));
+    let end = AlacPoint::new(cursor_line - 1, term.last_column());
+    let text = term.bounds_to_string(start, end);
+    text.trim_end().to_string()
+}
+
+/// Writes the text given into the terminal, as if it was output before the shell started.
+fn restore_scrollback<T: EventListener>(term: &mut Term<T>, text: &str) {
+    let mut processor: Processor = Processor::new();
+    for line in text.lines() {
+        for byte in line.bytes() {
+            processor.advance(term, byte);
+        }
+        for byte in b"\r\n" {
+            processor.advance(term, *byte);
+        }
+    }
+}
+
 pub struct TerminalBuilder {
     terminal: Terminal,
     events_rx: UnboundedReceiver<AlacTermEvent>,
@@ -305,6 +362,8 @@ impl TerminalBuilder {
         blink_settings: Option<TerminalBlink>,
         alternate_scroll: AlternateScroll,
         max_scroll_history_lines: Option<usize>,
+        shell_integration: bool,
+        restored_scrollback: Option<&str>,
         window: AnyWindowHandle,
         completion_tx: Sender<()>,
     ) -> Result<TerminalBuilder> {
@@ -315,7 +374,11 @@ impl TerminalBuilder {
         env.insert("ZED_TERM".to_string(), "true".to_string());
 
         let pty_options = {
+            let integrated_shell = shell_integration
+                .then(|| shell_integration::integrate(&shell, &mut env))
+                .flatten();
             let alac_shell = match shell.clone() {
+                _ if integrated_shell.is_some() => integrated_shell,
                 Shell::System => None,
                 Shell::Program(program) => {
                     Some(alacritty_terminal::tty::Shell::new(program, Vec::new()))
@@ -371,6 +434,11 @@ impl TerminalBuilder {
             term.unset_private_mode(PrivateMode::Named(NamedPrivateMode::AlternateScroll));
         }
 
+        // Write the restored lines before the shell starts, so that its first prompt gets printed below them.
+        if let Some(scrollback) = restored_scrollback {
+            restore_scrollback(&mut term, scrollback);
+        }
+
         let term = Arc::new(FairMutex::new(term));
 
         //Setup the pty...
@@ -392,24 +460,31 @@ impl TerminalBuilder {
         let pty_info = PtyProcessInfo::new(&pty);
 
         //And connect them together
-        let event_loop = EventLoop::new(
+        let parser = Arc::new(Mutex::new(OutputParser::new(
+            shell_integration.then(ShellIntegrationParser::new),
+            ImageParser::new(Size {
+                width: TerminalSize::default().cell_width,
+                height: TerminalSize::default().line_height,
+            }),
+        )));
+        let event_loop = PtyEventLoop::new(
+            pty,
             term.clone(),
             ZedListener(events_tx.clone()),
-            pty,
+            parser.clone(),
             pty_options.hold,
-            false,
         )?;
 
         //Kick things off
-        let pty_tx = event_loop.channel();
-        let _io_thread = event_loop.spawn(); // DANGER
+        let pty_tx = event_loop.sender();
+        let _io_thread = event_loop.spawn()?; // DANGER
 
         let url_regex = RegexSearch::new(r#"(ipfs:|ipns:|magnet:|mailto:|gemini://|gopher://|https://|http://|news:|file://|git://|ssh:|ftp://)[^\u{0000}-\u{001F}\u{007F}-\u{009F}<>"\s{-}\^⟨⟩`]+"#).unwrap();
         let word_regex = RegexSearch::new(r#"[\$\+\w.\[\]:/@\-~]+"#).unwrap();
 
         let terminal = Terminal {
             task,
-            pty_tx: Notifier(pty_tx),
+            pty_tx,
             completion_tx,
             term,
             events: VecDeque::with_capacity(10), //Should never get this high.
@@ -418,6 +493,7 @@ impl TerminalBuilder {
             matches: Vec::new(),
             selection_head: None,
             pty_info,
+            parser,
             breadcrumb_text: String::new(),
             scroll_px: px(0.),
             last_mouse_position: None,
@@ -525,6 +601,10 @@ pub struct TerminalContent {
     pub cursor_char: char,
     pub size: TerminalSize,
     pub last_hovered_word: Option<HoveredWord>,
+    /// The images, visible in the terminal, with the points of their top left corners.
+    pub images: Vec<(AlacPoint, TerminalImage)>,
+    /// The marks of the commands, typed on the lines visible in the terminal.
+    pub command_marks: Vec<(Line, CommandMark)>,
 }
 
 #[derive(Clone)]
@@ -549,6 +629,8 @@ impl Default for TerminalContent {
             cursor_char: Default::default(),
             size: Default::default(),
             last_hovered_word: None,
+            images: Vec::new(),
+            command_marks: Vec::new(),
         }
     }
 }
@@ -560,7 +642,7 @@ pub enum SelectionPhase {
 }
 
 pub struct Terminal {
-    pty_tx: Notifier,
+    pty_tx: PtySender,
     completion_tx: Sender<()>,
     term: Arc<FairMutex<Term<ZedListener>>>,
     events: VecDeque<InternalEvent>,
@@ -573,6 +655,9 @@ pub struct Terminal {
     pub selection_head: Option<AlacPoint>,
     pub breadcrumb_text: String,
     pub pty_info: PtyProcessInfo,
+    /// The parser of the pty output, with the images and the shell integration state.
+    /// It is locked before the terminal, the same way the pty event loop does it.
+    parser: Arc<Mutex<OutputParser>>,
     scroll_px: Pixels,
     next_link_id: usize,
     selection_phase: SelectionPhase,
@@ -590,6 +675,9 @@ pub struct TaskState {
     pub command_label: String,
     pub status: TaskStatus,
     pub completion_rx: Receiver<()>,
+    pub background: Option<BackgroundTaskState>,
+    /// Finds problems in the output of a task that is not a background one.
+    pub output_scanner: Option<TaskOutputScanner>,
 }
 
 /// A status of the current terminal tab's task.
@@ -653,6 +741,7 @@ impl Terminal {
             }
             AlacTermEvent::Wakeup => {
                 cx.emit(Event::Wakeup);
+                self.process_task_output(cx);
 
                 if self.pty_info.has_changed() {
                     cx.emit(Event::TitleChanged);
@@ -673,13 +762,18 @@ impl Terminal {
     }
 
     pub fn get_cwd(&self) -> Option<PathBuf> {
-        self.pty_info.current.as_ref().map(|info| info.cwd.clone())
+        self.parser
+            .lock()
+            .shell_cwd()
+            .map(|cwd| cwd.to_path_buf())
+            .or_else(|| self.pty_info.current.as_ref().map(|info| info.cwd.clone()))
     }
 
     ///Takes events from Alacritty and translates them to behavior on this view
     fn process_terminal_event(
         &mut self,
         event: &InternalEvent,
+        parser: &mut OutputParser,
         term: &mut Term<ZedListener>,
         cx: &mut ModelContext<Self>,
     ) {
@@ -696,9 +790,9 @@ impl Terminal {
 
                 self.last_content.size = new_size;
 
-                self.pty_tx.0.send(Msg::Resize(new_size.into())).ok();
+                self.pty_tx.send(Msg::Resize(new_size.into())).ok();
 
-                term.resize(new_size);
+                parser.resize(term, new_size);
             }
             InternalEvent::Clear => {
                 // Clear back buffer
@@ -730,6 +824,21 @@ impl Terminal {
                     term.grid_mut().reset_region((new_cursor.line + 1)..);
                 }
 
+                // The images and the command marks of the current line move up along with it.
+                parser.apply_grid_changes(
+                    term,
+                    &[
+                        GridChange::ScrollUp {
+                            region: Line(0)..Line(term.screen_lines() as i32),
+                            lines: cursor.line.0 as usize,
+                        },
+                        GridChange::Clear {
+                            start: AlacPoint::new(Line(1), Column(0)),
+                            end: AlacPoint::new(term.bottommost_line(), term.last_column()),
+                        },
+                    ],
+                );
+
                 cx.emit(Event::Wakeup);
             }
             InternalEvent::Scroll(scroll) => {
@@ -779,6 +888,55 @@ impl Terminal {
                 term.scroll_to_point(*point);
                 self.refresh_hovered_word();
             }
+            InternalEvent::ScrollToPreviousPrompt | InternalEvent::ScrollToNextPrompt => {
+                if term.mode().contains(TermMode::ALT_SCREEN) {
+                    return;
+                }
+                let display_offset = term.grid().display_offset() as i32;
+                let top_line = Line(-display_offset);
+                let mut prompt_lines = parser
+                    .command_marks(term.topmost_line()..=term.bottommost_line())
+                    .into_iter()
+                    .map(|(line, mark)| mark.prompt_start(line).max(term.topmost_line()));
+                let prompt_line = if matches!(event, InternalEvent::ScrollToPreviousPrompt) {
+                    prompt_lines.filter(|line| *line < top_line).last()
+                } else {
+                    prompt_lines.find(|line| *line > top_line)
+                };
+                // The scroll that puts the prompt line at the top, or scrolls to the bottom.
+                let scroll = match prompt_line {
+                    Some(line) => AlacScroll::Delta(-line.0 - display_offset),
+                    None if matches!(event, InternalEvent::ScrollToNextPrompt) => {
+                        AlacScroll::Bottom
+                    }
+                    None => return,
+                };
+                term.scroll_display(scroll);
+                self.refresh_hovered_word();
+            }
+            InternalEvent::CopyLastCommandOutput => {
+                let marks = parser.command_marks(term.topmost_line()..=term.bottommost_line());
+                let Some(index) = marks.iter().rposition(|(_, mark)| {
+                    matches!(mark.state, CommandState::Succeeded | CommandState::Failed)
+                }) else {
+                    return;
+                };
+                let (line, mark) = marks[index];
+                let start = line + mark.output_offset as usize;
+                let end = match marks.get(index + 1) {
+                    Some((next_line, next_mark)) => next_mark.prompt_start(*next_line),
+                    None => term.grid().cursor.point.line,
+                } - 1;
+                let output = if start <= end {
+                    term.bounds_to_string(
+                        AlacPoint::new(start, Column(0)),
+                        AlacPoint::new(end, term.last_column()),
+                    )
+                } else {
+                    String::new()
+                };
+                cx.write_to_clipboard(ClipboardItem::new(output.trim_end().to_string()))
+            }
             InternalEvent::FindHyperlink(position, open) => {
                 let prev_hovered_word = self.last_content.last_hovered_word.take();
 
@@ -982,6 +1140,28 @@ impl Terminal {
         self.events.push_back(InternalEvent::Clear)
     }
 
+    /// Scrolls the terminal to the prompt of the command above the top visible line,
+    /// as marked by the shell integration.
+    pub fn scroll_to_previous_prompt(&mut self) {
+        self.events.push_back(InternalEvent::ScrollToPreviousPrompt)
+    }
+
+    /// Scrolls the terminal to the prompt of the command below the top visible line,
+    /// or to the bottom, if there is no such command.
+    pub fn scroll_to_next_prompt(&mut self) {
+        self.events.push_back(InternalEvent::ScrollToNextPrompt)
+    }
+
+    /// Copies the output of the last finished command, as marked by the shell integration.
+    pub fn copy_last_command_output(&mut self) {
+        self.events.push_back(InternalEvent::CopyLastCommandOutput)
+    }
+
+    /// Returns the text of the last terminal lines, up to the line with the cursor (usually, the shell prompt).
+    pub fn scrollback_text(&self, max_lines: usize) -> String {
+        scrollback_text(&self.term.lock(), max_lines)
+    }
+
     ///Resize the terminal and the PTY.
     pub fn set_size(&mut self, new_size: TerminalSize) {
         if self.last_content.size != new_size {
@@ -1045,18 +1225,26 @@ impl Terminal {
     }
 
     pub fn sync(&mut self, cx: &mut ModelContext<Self>) {
+        let parser = self.parser.clone();
+        let mut parser = parser.lock();
         let term = self.term.clone();
         let mut terminal = term.lock_unfair();
         //Note that the ordering of events matters for event processing
         while let Some(e) = self.events.pop_front() {
-            self.process_terminal_event(&e, &mut terminal, cx)
+            self.process_terminal_event(&e, &mut parser, &mut terminal, cx)
         }
 
-        self.last_content = Self::make_content(&terminal, &self.last_content);
+        self.last_content = Self::make_content(&terminal, &parser, &self.last_content);
     }
 
-    fn make_content(term: &Term<ZedListener>, last_content: &TerminalContent) -> TerminalContent {
+    fn make_content(
+        term: &Term<ZedListener>,
+        parser: &OutputParser,
+        last_content: &TerminalContent,
+    ) -> TerminalContent {
         let content = term.renderable_content();
+        let top_line = Line(-(content.display_offset as i32));
+        let bottom_line = top_line + (term.screen_lines() - 1);
         TerminalContent {
             cells: content
                 .display_iter
@@ -1080,6 +1268,8 @@ impl Terminal {
             cursor_char: term.grid()[content.cursor.point].c,
             size: last_content.size,
             last_hovered_word: last_content.last_hovered_word.clone(),
+            images: parser.visible_images(term),
+            command_marks: parser.command_marks(top_line..=bottom_line),
         }
     }
 
@@ -1427,6 +1617,48 @@ impl Terminal {
         Task::ready(())
     }
 
+    /// Waits until the background task reports it is ready, or stops running.
+    /// Resolves immediately for other terminals.
+    pub fn wait_for_background_task_ready(&mut self, cx: &mut AppContext) -> Task<()> {
+        if let Some(task) = &mut self.task {
+            if task.status == TaskStatus::Running {
+                if let Some(background) = &mut task.background {
+                    let ready_rx = background.wait_until_ready();
+                    return cx.spawn(|_| async move {
+                        ready_rx.await.ok();
+                    });
+                }
+            }
+        }
+        Task::ready(())
+    }
+
+    fn process_task_output(&mut self, cx: &mut ModelContext<Self>) {
+        let Some(task) = &mut self.task else {
+            return;
+        };
+        if task.status != TaskStatus::Running {
+            return;
+        }
+        if let Some(output_scanner) = &mut task.output_scanner {
+            let problems = output_scanner.process_output(&*self.term.lock());
+            if !problems.is_empty() {
+                cx.emit(Event::ProblemsFound(problems));
+            }
+        }
+        let Some(background) = &mut task.background else {
+            return;
+        };
+        let events = background.process_output(&*self.term.lock());
+        for event in events {
+            cx.emit(match event {
+                BackgroundTaskEvent::StatusChanged => Event::TaskStatusChanged,
+                BackgroundTaskEvent::ProblemsOutdated => Event::ProblemsOutdated,
+                BackgroundTaskEvent::ProblemsFound(problems) => Event::ProblemsFound(problems),
+            });
+        }
+    }
+
     fn register_task_finished(
         &mut self,
         error_code: Option<i32>,
@@ -1454,12 +1686,35 @@ impl Terminal {
             }
         };
 
+        if let Some(background) = &mut task.background {
+            background.finish();
+            if let Some(BackgroundTaskEvent::ProblemsFound(problems)) = background.take_problems() {
+                cx.emit(Event::ProblemsFound(problems));
+            }
+        } else if let Some(output_scanner) = &mut task.output_scanner {
+            let problems = output_scanner.finish(&*self.term.lock());
+            if !problems.is_empty() {
+                cx.emit(Event::ProblemsFound(problems));
+            }
+        }
+        cx.emit(Event::TaskStatusChanged);
+
         let (task_line, command_line) = task_summary(task, error_code);
         // SAFETY: the invocation happens on non `TaskStatus::Running` tasks, once,
         // after either `AlacTermEvent::Exit` or `AlacTermEvent::ChildExit` events that are spawned
         // when Zed task finishes and no more output is made.
         // After the task summary is output once, no more text is appended to the terminal.
-        unsafe { append_text_to_term(&mut self.term.lock(), &[&task_line, &command_line]) };
+        let mut parser = self.parser.lock();
+        let mut term = self.term.lock_unfair();
+        let scrolled_lines =
+            unsafe { append_text_to_term(&mut term, &[&task_line, &command_line]) };
+        parser.apply_grid_changes(
+            &*term,
+            &[GridChange::ScrollUp {
+                region: Line(0)..Line(term.screen_lines() as i32),
+                lines: scrolled_lines,
+            }],
+        );
     }
 }
 
@@ -1507,21 +1762,35 @@ fn task_summary(task: &TaskState, error_code: Option<i32>) -> (String, String) {
 /// do not properly set the scrolling state and display odd text after appending; also those manipulations are more tedious and error-prone.
 /// The function achieves proper display and scrolling capabilities, at a cost of grid state not properly synchronized.
 /// This is enough for printing moderately-sized texts like task summaries, but might break or perform poorly for larger texts.
-unsafe fn append_text_to_term(term: &mut Term<ZedListener>, text_lines: &[&str]) {
-    term.newline();
-    term.grid_mut().cursor.point.column = Column(0);
+///
+/// Returns how many lines the text scrolled the terminal by.
+unsafe fn append_text_to_term(term: &mut Term<ZedListener>, text_lines: &[&str]) -> usize {
+    fn at_bottom(term: &Term<ZedListener>) -> bool {
+        term.grid().cursor.point.line.0 as usize + 1 == term.screen_lines()
+    }
+    fn newline(term: &mut Term<ZedListener>) -> usize {
+        let scrolled_lines = at_bottom(term) as usize;
+        term.newline();
+        term.grid_mut().cursor.point.column = Column(0);
+        scrolled_lines
+    }
+
+    let mut scrolled_lines = newline(term);
     for line in text_lines {
         for c in line.chars() {
+            if term.grid().cursor.input_needs_wrap && at_bottom(term) {
+                scrolled_lines += 1;
+            }
             term.input(c);
         }
-        term.newline();
-        term.grid_mut().cursor.point.column = Column(0);
+        scrolled_lines += newline(term);
     }
+    scrolled_lines
 }
 
 impl Drop for Terminal {
     fn drop(&mut self) {
-        self.pty_tx.0.send(Msg::Shutdown).ok();
+        self.pty_tx.send(Msg::Shutdown).ok();
     }
 }
 
@@ -1661,16 +1930,31 @@ pub fn rgba_color(r: u8, g: u8, b: u8) -> Hsla {
 #[cfg(test)]
 mod tests {
     use alacritty_terminal::{
+        event::VoidListener,
         index::{Column, Line, Point as AlacPoint},
-        term::cell::Cell,
+        term::{cell::Cell, Config, Term},
     };
-    use gpui::{point, size, Pixels};
+    use gpui::{point, px, size, Pixels};
     use rand::{distributions::Alphanumeric, rngs::ThreadRng, thread_rng, Rng};
 
     use crate::{
-        content_index_for_mouse, rgb_for_index, IndexedCell, TerminalContent, TerminalSize,
+        content_index_for_mouse, restore_scrollback, rgb_for_index, scrollback_text, IndexedCell,
+        TerminalContent, TerminalSize,
     };
 
+    #[test]
+    fn test_restore_scrollback() {
+        let size = TerminalSize::new(px(10.), px(10.), size(px(200.), px(50.)));
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+        let lines = (1..=8).map(|ix| format!("line {ix}")).collect::<Vec<_>>();
+        restore_scrollback(&mut term, &lines.join("\n"));
+
+        // The lines that do not fit the 5 visible rows go to the history, the cursor waits for the prompt below them.
+        assert_eq!(term.grid().cursor.point, AlacPoint::new(Line(4), Column(0)));
+        assert_eq!(scrollback_text(&term, 100), lines.join("\n"));
+        assert_eq!(scrollback_text(&term, 2), "line 7\nline 8");
+    }
+
     #[test]
     fn test_rgb_for_index() {
         // Test every possible value in the color cube.
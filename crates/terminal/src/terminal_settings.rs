@@ -42,6 +42,7 @@ pub struct TerminalSettings {
     pub default_height: Pixels,
     pub detect_venv: VenvSettings,
     pub max_scroll_history_lines: Option<usize>,
+    pub shell_integration: bool,
     pub toolbar: Toolbar,
 }
 
@@ -166,6 +167,13 @@ pub struct TerminalSettingsContent {
     ///
     /// Default: 10_000
     pub max_scroll_history_lines: Option<usize>,
+    /// Whether to start bash, zsh and fish with Zed's shell integration, which marks
+    /// the prompts and the commands in the terminal output and reports the working directory.
+    /// Shells with their own integration are tracked as well, when this is enabled.
+    /// Existing terminals will not pick up this change until they are recreated.
+    ///
+    /// Default: true
+    pub shell_integration: Option<bool>,
     /// Toolbar related settings
     pub toolbar: Option<ToolbarContent>,
 }
@@ -0,0 +1,115 @@
+use std::time::Duration;
+
+use gpui::{
+    percentage, Animation, AnimationExt, AppContext, IntoElement, Model, ParentElement, Render,
+    Subscription, Transformation, ViewContext, WeakView,
+};
+use project::Project;
+use terminal::{BackgroundTaskStatus, TaskStatus, Terminal};
+use ui::{h_flex, prelude::*, ButtonLike, Tooltip};
+use workspace::{item::ItemHandle, StatusItemView, Workspace};
+
+use crate::terminal_panel::TerminalPanel;
+
+/// Shows every running background task in the status bar, along with its readiness.
+pub struct BackgroundTasksIndicator {
+    project: Model<Project>,
+    workspace: WeakView<Workspace>,
+    _observe_project: Subscription,
+}
+
+impl BackgroundTasksIndicator {
+    pub fn new(workspace: &Workspace, cx: &mut ViewContext<Self>) -> Self {
+        let project = workspace.project().clone();
+        Self {
+            _observe_project: cx.observe(&project, |_, _, cx| cx.notify()),
+            project,
+            workspace: workspace.weak_handle(),
+        }
+    }
+
+    fn background_tasks(&self, cx: &AppContext) -> Vec<(Model<Terminal>, BackgroundTaskStatus)> {
+        self.project
+            .read(cx)
+            .local_terminal_handles()
+            .iter()
+            .filter_map(|terminal| {
+                let terminal = terminal.upgrade()?;
+                let task = terminal.read(cx).task()?;
+                if task.status != TaskStatus::Running {
+                    return None;
+                }
+                let status = task.background.as_ref()?.status;
+                Some((terminal, status))
+            })
+            .collect()
+    }
+
+    fn reveal_terminal(&self, terminal: &Model<Terminal>, cx: &mut ViewContext<Self>) {
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        workspace.update(cx, |workspace, cx| {
+            if let Some(terminal_panel) = workspace.panel::<TerminalPanel>(cx) {
+                workspace.focus_panel::<TerminalPanel>(cx);
+                terminal_panel.update(cx, |terminal_panel, cx| {
+                    terminal_panel.activate_terminal(terminal, cx)
+                });
+            }
+        });
+    }
+}
+
+impl Render for BackgroundTasksIndicator {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        h_flex()
+            .gap_2()
+            .children(self.background_tasks(cx).into_iter().enumerate().map(
+                |(ix, (terminal, status))| {
+                    let label = terminal
+                        .read(cx)
+                        .task()
+                        .map(|task| task.label.clone())
+                        .unwrap_or_default();
+                    let icon = match status {
+                        BackgroundTaskStatus::Ready => Icon::new(IconName::Check)
+                            .size(IconSize::Small)
+                            .color(Color::Success)
+                            .into_any_element(),
+                        BackgroundTaskStatus::Starting | BackgroundTaskStatus::Rebuilding => {
+                            Icon::new(IconName::ArrowCircle)
+                                .size(IconSize::Small)
+                                .with_animation(
+                                    ("background-task-progress", ix),
+                                    Animation::new(Duration::from_secs(2)).repeat(),
+                                    |icon, delta| {
+                                        icon.transform(Transformation::rotate(percentage(delta)))
+                                    },
+                                )
+                                .into_any_element()
+                        }
+                    };
+                    let tooltip = match status {
+                        BackgroundTaskStatus::Starting => "Starting",
+                        BackgroundTaskStatus::Rebuilding => "Rebuilding",
+                        BackgroundTaskStatus::Ready => "Ready",
+                    };
+                    ButtonLike::new(("background-task", ix))
+                        .child(
+                            h_flex()
+                                .gap_1()
+                                .child(icon)
+                                .child(Label::new(label).size(LabelSize::Small)),
+                        )
+                        .tooltip(move |cx| Tooltip::text(tooltip, cx))
+                        .on_click(cx.listener(move |this, _, cx| {
+                            this.reveal_terminal(&terminal, cx);
+                        }))
+                },
+            ))
+    }
+}
+
+impl StatusItemView for BackgroundTasksIndicator {
+    fn set_active_pane_item(&mut self, _: Option<&dyn ItemHandle>, _: &mut ViewContext<Self>) {}
+}
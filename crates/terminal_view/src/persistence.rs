@@ -34,6 +34,10 @@ define_connection! {
             DROP TABLE terminals;
 
             ALTER TABLE terminals2 RENAME TO terminals;
+        ),
+        sql!(
+            ALTER TABLE terminals ADD COLUMN title TEXT;
+            ALTER TABLE terminals ADD COLUMN scrollback TEXT;
         )];
 }
 
@@ -56,8 +60,25 @@ impl TerminalDb {
             workspace_id: WorkspaceId,
             working_directory: PathBuf
         ) -> Result<()> {
-            INSERT OR REPLACE INTO terminals(item_id, workspace_id, working_directory)
-            VALUES (?, ?, ?)
+            INSERT INTO terminals(item_id, workspace_id, working_directory)
+            VALUES (?1, ?2, ?3)
+            ON CONFLICT DO UPDATE SET
+                working_directory = ?3
+        }
+    }
+
+    query! {
+        pub async fn save_contents(
+            item_id: ItemId,
+            workspace_id: WorkspaceId,
+            title: String,
+            scrollback: String
+        ) -> Result<()> {
+            INSERT INTO terminals(item_id, workspace_id, title, scrollback)
+            VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT DO UPDATE SET
+                title = ?3,
+                scrollback = ?4
         }
     }
 
@@ -68,4 +89,58 @@ impl TerminalDb {
             WHERE item_id = ? AND workspace_id = ?
         }
     }
+
+    query! {
+        pub fn get_contents(item_id: ItemId, workspace_id: WorkspaceId) -> Result<Option<(Option<String>, Option<String>)>> {
+            SELECT title, scrollback
+            FROM terminals
+            WHERE item_id = ? AND workspace_id = ?
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use db::open_test_db;
+
+    use super::*;
+
+    #[gpui::test]
+    async fn test_terminal_contents() {
+        let db = TerminalDb(open_test_db("test_terminal_contents").await);
+        let workspace_id = db
+            .write(|conn| {
+                conn.select_row::<WorkspaceId>(sql!(
+                    INSERT INTO workspaces DEFAULT VALUES RETURNING workspace_id
+                ))
+                .unwrap()()
+                .unwrap()
+                .unwrap()
+            })
+            .await;
+        assert_eq!(db.get_contents(1, workspace_id).unwrap(), None);
+
+        db.save_working_directory(1, workspace_id, "/dir".into())
+            .await
+            .unwrap();
+        db.save_contents(1, workspace_id, "~/dir".into(), "$ ls\nfile".into())
+            .await
+            .unwrap();
+        db.save_contents(
+            1,
+            workspace_id,
+            "~/dir".into(),
+            "$ ls\nfile\n$ pwd\n/dir".into(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            db.get_contents(1, workspace_id).unwrap(),
+            Some((Some("~/dir".into()), Some("$ ls\nfile\n$ pwd\n/dir".into())))
+        );
+        assert_eq!(
+            db.get_working_directory(1, workspace_id).unwrap(),
+            Some("/dir".into())
+        );
+    }
 }
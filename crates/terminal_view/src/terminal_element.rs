@@ -1,9 +1,9 @@
 use editor::{CursorLayout, HighlightedRange, HighlightedRangeLine};
 use gpui::{
-    div, fill, point, px, relative, AnyElement, Bounds, DispatchPhase, Element, ElementId,
-    FocusHandle, Font, FontStyle, FontWeight, GlobalElementId, HighlightStyle, Hitbox, Hsla,
-    InputHandler, InteractiveElement, Interactivity, IntoElement, LayoutId, Model, ModelContext,
-    ModifiersChangedEvent, MouseButton, MouseMoveEvent, Pixels, Point, ShapedLine,
+    div, fill, point, px, relative, size, AnyElement, Bounds, ContentMask, Corners, DispatchPhase,
+    Element, ElementId, FocusHandle, Font, FontStyle, FontWeight, GlobalElementId, HighlightStyle,
+    Hitbox, Hsla, InputHandler, InteractiveElement, Interactivity, IntoElement, LayoutId, Model,
+    ModelContext, ModifiersChangedEvent, MouseButton, MouseMoveEvent, Pixels, Point, ShapedLine,
     StatefulInteractiveElement, StrikethroughStyle, Styled, TextRun, TextStyle, UnderlineStyle,
     WeakView, WhiteSpace, WindowContext, WindowTextSystem,
 };
@@ -21,10 +21,11 @@ use terminal::{
         },
     },
     terminal_settings::TerminalSettings,
-    HoveredWord, IndexedCell, Terminal, TerminalContent, TerminalSize,
+    CommandState, HoveredWord, IndexedCell, Terminal, TerminalContent, TerminalImage, TerminalSize,
 };
 use theme::{ActiveTheme, Theme, ThemeSettings};
 use ui::Tooltip;
+use util::ResultExt;
 use workspace::Workspace;
 
 use std::mem;
@@ -44,6 +45,10 @@ pub struct LayoutState {
     hyperlink_tooltip: Option<AnyElement>,
     gutter: Pixels,
     last_hovered_word: Option<HoveredWord>,
+    /// The displayed lines, where the commands that failed were typed, marked in the gutter.
+    failed_command_lines: Vec<i32>,
+    failed_command_color: Hsla,
+    images: Vec<(AlacPoint, TerminalImage)>,
 }
 
 /// Helper struct for converting data between Alacritty's cursor points, and displayed cursor points.
@@ -692,9 +697,17 @@ impl Element for TerminalElement {
                     cursor_char,
                     selection,
                     cursor,
+                    images,
+                    command_marks,
                     ..
                 } = &self.terminal.read(cx).last_content;
 
+                let failed_command_lines = command_marks
+                    .iter()
+                    .filter(|(_, mark)| mark.state == CommandState::Failed)
+                    .map(|(line, _)| line.0 + *display_offset as i32)
+                    .collect();
+
                 // searches, highlights to a single range representations
                 let mut relative_highlighted_ranges = Vec::new();
                 for search_match in search_matches {
@@ -780,6 +793,9 @@ impl Element for TerminalElement {
                     hyperlink_tooltip,
                     gutter,
                     last_hovered_word,
+                    failed_command_lines,
+                    failed_command_color: theme.status().error,
+                    images: images.clone(),
                 }
             })
     }
@@ -837,6 +853,35 @@ impl Element for TerminalElement {
                     rect.paint(origin, &layout, cx);
                 }
 
+                for line in &layout.failed_command_lines {
+                    let marker_origin = point(
+                        bounds.origin.x,
+                        origin.y + *line as f32 * layout.dimensions.line_height,
+                    );
+                    let marker_size = size(layout.gutter / 4., layout.dimensions.line_height);
+                    cx.paint_quad(fill(
+                        Bounds::new(marker_origin, marker_size),
+                        layout.failed_command_color,
+                    ));
+                }
+
+                cx.with_content_mask(Some(ContentMask { bounds }), |cx| {
+                    for (image_point, image) in &layout.images {
+                        let line = image_point.line.0 + layout.display_offset as i32;
+                        let image_origin = point(
+                            origin.x + image_point.column.0 as f32 * layout.dimensions.cell_width,
+                            origin.y + line as f32 * layout.dimensions.line_height,
+                        );
+                        cx.paint_image(
+                            Bounds::new(image_origin, image.size),
+                            Corners::default(),
+                            image.data.clone(),
+                            false,
+                        )
+                        .log_err();
+                    }
+                });
+
                 for (relative_highlighted_range, color) in layout.relative_highlighted_ranges.iter()
                 {
                     if let Some((start_y, highlighted_range_lines)) =
@@ -1,37 +1,47 @@
-use std::{ops::ControlFlow, path::PathBuf, sync::Arc};
+use std::{cell::RefCell, mem, ops::ControlFlow, path::PathBuf, rc::Rc, sync::Arc, time::Duration};
 
 use crate::TerminalView;
 use collections::{HashMap, HashSet};
 use db::kvp::KEY_VALUE_STORE;
-use futures::future::join_all;
+use futures::{
+    channel::oneshot,
+    future::{join_all, try_join_all, LocalBoxFuture, Shared},
+    FutureExt as _,
+};
 use gpui::{
-    actions, Action, AppContext, AsyncWindowContext, DismissEvent, Entity, EventEmitter,
-    ExternalPaths, FocusHandle, FocusableView, IntoElement, ParentElement, Pixels, Render, Styled,
-    Subscription, Task, View, ViewContext, VisualContext, WeakView, WindowContext,
+    actions, Action, AppContext, AsyncWindowContext, Axis, DismissEvent, Entity, EntityId,
+    EventEmitter, ExternalPaths, FocusHandle, FocusableView, IntoElement, Model, ParentElement,
+    Pixels, Render, Styled, Subscription, Task, View, ViewContext, VisualContext, WeakView,
+    WindowContext,
 };
 use itertools::Itertools;
-use project::{Fs, ProjectEntryId};
+use project::{Fs, Project, ProjectEntryId};
 use search::{buffer_search::DivRegistrar, BufferSearchBar};
 use serde::{Deserialize, Serialize};
 use settings::Settings;
-use task::{RevealStrategy, SpawnInTerminal, TaskId};
-use terminal::terminal_settings::{Shell, TerminalDockPosition, TerminalSettings};
+use task::{DependsOrder, RevealStrategy, SpawnInTerminal, TaskId};
+use terminal::{
+    terminal_settings::{Shell, TerminalDockPosition, TerminalSettings},
+    BackgroundTaskStatus, TaskStatus, Terminal,
+};
 use ui::{
     h_flex, ButtonCommon, Clickable, ContextMenu, FluentBuilder, IconButton, IconSize, Selectable,
     Tooltip,
 };
-use util::{ResultExt, TryFutureExt};
+use util::{paths::PathMatcher, ResultExt, TryFutureExt};
 use workspace::{
     dock::{DockPosition, Panel, PanelEvent},
     item::Item,
     pane,
     ui::IconName,
-    DraggedTab, NewTerminal, Pane, ToggleZoom, Workspace,
+    DraggedTab, NewTerminal, Pane, PaneGroup, PaneGroupLayout, SplitDirection, ToggleZoom,
+    Workspace,
 };
 
-use anyhow::Result;
+use anyhow::{anyhow, Context as _, Result};
 
 const TERMINAL_PANEL_KEY: &str = "TerminalPanel";
+const RESTART_DEBOUNCE: Duration = Duration::from_millis(300);
 
 actions!(terminal_panel, [ToggleFocus]);
 
@@ -49,7 +59,8 @@ pub fn init(cx: &mut AppContext) {
 }
 
 pub struct TerminalPanel {
-    pane: View<Pane>,
+    active_pane: View<Pane>,
+    center: PaneGroup,
     fs: Arc<dyn Fs>,
     workspace: WeakView<Workspace>,
     width: Option<Pixels>,
@@ -58,137 +69,29 @@ pub struct TerminalPanel {
     pending_terminals_to_add: usize,
     _subscriptions: Vec<Subscription>,
     deferred_tasks: HashMap<TaskId, Task<()>>,
+    tasks_waiting_for_dependencies: HashMap<TaskId, Task<()>>,
+    task_terminal_listeners: HashMap<TaskId, Vec<oneshot::Sender<Model<Terminal>>>>,
+    restartable_tasks: HashMap<String, RestartableTask>,
+    pending_restarts: HashMap<String, Task<()>>,
 }
 
-impl TerminalPanel {
-    fn new(workspace: &Workspace, cx: &mut ViewContext<Self>) -> Self {
-        let pane = cx.new_view(|cx| {
-            let mut pane = Pane::new(
-                workspace.weak_handle(),
-                workspace.project().clone(),
-                Default::default(),
-                None,
-                NewTerminal.boxed_clone(),
-                cx,
-            );
-            pane.set_can_split(false, cx);
-            pane.set_can_navigate(false, cx);
-            pane.display_nav_history_buttons(None);
-            pane.set_should_display_tab_bar(|_| true);
-            pane.set_render_tab_bar_buttons(cx, move |pane, cx| {
-                h_flex()
-                    .gap_2()
-                    .child(
-                        IconButton::new("plus", IconName::Plus)
-                            .icon_size(IconSize::Small)
-                            .on_click(cx.listener(|pane, _, cx| {
-                                let focus_handle = pane.focus_handle(cx);
-                                let menu = ContextMenu::build(cx, |menu, _| {
-                                    menu.action(
-                                        "New Terminal",
-                                        workspace::NewTerminal.boxed_clone(),
-                                    )
-                                    .entry(
-                                        "Spawn task",
-                                        Some(tasks_ui::Spawn::modal().boxed_clone()),
-                                        move |cx| {
-                                            // We want the focus to go back to terminal panel once task modal is dismissed,
-                                            // hence we focus that first. Otherwise, we'd end up without a focused element, as
-                                            // context menu will be gone the moment we spawn the modal.
-                                            cx.focus(&focus_handle);
-                                            cx.dispatch_action(
-                                                tasks_ui::Spawn::modal().boxed_clone(),
-                                            );
-                                        },
-                                    )
-                                });
-                                cx.subscribe(&menu, |pane, _, _: &DismissEvent, _| {
-                                    pane.new_item_menu = None;
-                                })
-                                .detach();
-                                pane.new_item_menu = Some(menu);
-                            }))
-                            .tooltip(|cx| Tooltip::text("New...", cx)),
-                    )
-                    .when_some(pane.new_item_menu.as_ref(), |el, new_item_menu| {
-                        el.child(Pane::render_menu_overlay(new_item_menu))
-                    })
-                    .child({
-                        let zoomed = pane.is_zoomed();
-                        IconButton::new("toggle_zoom", IconName::Maximize)
-                            .icon_size(IconSize::Small)
-                            .selected(zoomed)
-                            .selected_icon(IconName::Minimize)
-                            .on_click(cx.listener(|pane, _, cx| {
-                                pane.toggle_zoom(&workspace::ToggleZoom, cx);
-                            }))
-                            .tooltip(move |cx| {
-                                Tooltip::for_action(
-                                    if zoomed { "Zoom Out" } else { "Zoom In" },
-                                    &ToggleZoom,
-                                    cx,
-                                )
-                            })
-                    })
-                    .into_any_element()
-            });
+/// The runs of the dependencies of a task, shared by all of their dependents, so that every dependency runs once.
+type DependencyRuns = Rc<RefCell<HashMap<TaskId, DependencyRun>>>;
+type DependencyRun = Shared<LocalBoxFuture<'static, Result<(), Arc<anyhow::Error>>>>;
 
-            let workspace = workspace.weak_handle();
-            pane.set_custom_drop_handle(cx, move |pane, dropped_item, cx| {
-                if let Some(tab) = dropped_item.downcast_ref::<DraggedTab>() {
-                    let item = if &tab.pane == cx.view() {
-                        pane.item_for_index(tab.ix)
-                    } else {
-                        tab.pane.read(cx).item_for_index(tab.ix)
-                    };
-                    if let Some(item) = item {
-                        if item.downcast::<TerminalView>().is_some() {
-                            return ControlFlow::Continue(());
-                        } else if let Some(project_path) = item.project_path(cx) {
-                            if let Some(entry_path) = workspace
-                                .update(cx, |workspace, cx| {
-                                    workspace
-                                        .project()
-                                        .read(cx)
-                                        .absolute_path(&project_path, cx)
-                                })
-                                .log_err()
-                                .flatten()
-                            {
-                                add_paths_to_terminal(pane, &[entry_path], cx);
-                            }
-                        }
-                    }
-                } else if let Some(&entry_id) = dropped_item.downcast_ref::<ProjectEntryId>() {
-                    if let Some(entry_path) = workspace
-                        .update(cx, |workspace, cx| {
-                            let project = workspace.project().read(cx);
-                            project
-                                .path_for_entry(entry_id, cx)
-                                .and_then(|project_path| project.absolute_path(&project_path, cx))
-                        })
-                        .log_err()
-                        .flatten()
-                    {
-                        add_paths_to_terminal(pane, &[entry_path], cx);
-                    }
-                } else if let Some(paths) = dropped_item.downcast_ref::<ExternalPaths>() {
-                    add_paths_to_terminal(pane, paths.paths(), cx);
-                }
+/// A background task, that gets restarted when the files matching its globs change.
+struct RestartableTask {
+    spawn_in_terminal: SpawnInTerminal,
+    restart_on_change: Vec<PathMatcher>,
+}
 
-                ControlFlow::Break(())
-            });
-            let buffer_search_bar = cx.new_view(search::BufferSearchBar::new);
-            pane.toolbar()
-                .update(cx, |toolbar, cx| toolbar.add_item(buffer_search_bar, cx));
-            pane
-        });
-        let subscriptions = vec![
-            cx.observe(&pane, |_, _, cx| cx.notify()),
-            cx.subscribe(&pane, Self::handle_pane_event),
-        ];
+impl TerminalPanel {
+    fn new(workspace: &Workspace, cx: &mut ViewContext<Self>) -> Self {
+        let pane = new_terminal_pane(workspace.weak_handle(), workspace.project().clone(), cx);
+        let subscriptions = vec![cx.subscribe(workspace.project(), Self::handle_project_event)];
         let this = Self {
-            pane,
+            center: PaneGroup::new(pane.clone()),
+            active_pane: pane,
             fs: workspace.app_state().fs.clone(),
             workspace: workspace.weak_handle(),
             pending_serialization: Task::ready(None),
@@ -196,6 +99,10 @@ impl TerminalPanel {
             height: None,
             pending_terminals_to_add: 0,
             deferred_tasks: HashMap::default(),
+            tasks_waiting_for_dependencies: HashMap::default(),
+            task_terminal_listeners: HashMap::default(),
+            restartable_tasks: HashMap::default(),
+            pending_restarts: HashMap::default(),
             _subscriptions: subscriptions,
         };
         this
@@ -216,34 +123,27 @@ impl TerminalPanel {
             .log_err()
             .flatten();
 
-        let (panel, pane, items) = workspace.update(&mut cx, |workspace, cx| {
+        let (panel, layout, panes) = workspace.update(&mut cx, |workspace, cx| {
             let panel = cx.new_view(|cx| TerminalPanel::new(workspace, cx));
-            let items = if let Some(serialized_panel) = serialized_panel.as_ref() {
+            let mut panes = Vec::new();
+            let layout = if let Some(serialized_panel) = serialized_panel {
                 panel.update(cx, |panel, cx| {
                     cx.notify();
                     panel.height = serialized_panel.height.map(|h| h.round());
                     panel.width = serialized_panel.width.map(|w| w.round());
-                    panel.pane.update(cx, |_, cx| {
-                        serialized_panel
-                            .items
-                            .iter()
-                            .map(|item_id| {
-                                TerminalView::deserialize(
-                                    workspace.project().clone(),
-                                    workspace.weak_handle(),
-                                    workspace.database_id(),
-                                    *item_id,
-                                    cx,
-                                )
-                            })
-                            .collect::<Vec<_>>()
-                    })
+                    let pane_group = serialized_panel.pane_group.unwrap_or_else(|| {
+                        SerializedPaneGroup::Pane(SerializedPane {
+                            items: serialized_panel.items,
+                            active_item_id: serialized_panel.active_item_id,
+                            active: true,
+                        })
+                    });
+                    Some(panel.deserialize_pane_group(pane_group, workspace, &mut panes, cx))
                 })
             } else {
-                Vec::new()
+                None
             };
-            let pane = panel.read(cx).pane.clone();
-            (panel, pane, items)
+            (panel, layout, panes)
         })?;
 
         if let Some(workspace) = workspace.upgrade() {
@@ -261,47 +161,137 @@ impl TerminalPanel {
                 .ok();
         }
 
-        let pane = pane.downgrade();
-        let items = futures::future::join_all(items).await;
-        pane.update(&mut cx, |pane, cx| {
-            let active_item_id = serialized_panel
-                .as_ref()
-                .and_then(|panel| panel.active_item_id);
-            let mut active_ix = None;
-            for item in items {
-                if let Some(item) = item.log_err() {
-                    let item_id = item.entity_id().as_u64();
-                    pane.add_item(Box::new(item), false, false, None, cx);
-                    if Some(item_id) == active_item_id {
-                        active_ix = Some(pane.items_len() - 1);
+        let mut active_pane = None;
+        for deserialized_pane in panes {
+            let items = futures::future::join_all(deserialized_pane.items).await;
+            let pane = deserialized_pane.pane;
+            let has_items = pane.update(&mut cx, |pane, cx| {
+                let mut active_ix = None;
+                for item in items {
+                    if let Some(item) = item.log_err() {
+                        let item_id = item.entity_id().as_u64();
+                        pane.add_item(Box::new(item), false, false, None, cx);
+                        if Some(item_id) == deserialized_pane.active_item_id {
+                            active_ix = Some(pane.items_len() - 1);
+                        }
                     }
                 }
-            }
 
-            if let Some(active_ix) = active_ix {
-                pane.activate_item(active_ix, false, false, cx)
+                if let Some(active_ix) = active_ix {
+                    pane.activate_item(active_ix, false, false, cx)
+                }
+                pane.items_len() > 0
+            })?;
+            if has_items && deserialized_pane.active {
+                active_pane = Some(pane);
             }
-        })?;
+        }
+
+        if let Some(layout) = layout {
+            panel.update(&mut cx, |panel, cx| {
+                // Terminals of the tasks are not restored, drop the splits that had nothing else.
+                if let Some(layout) = retain_panes_with_items(layout, cx) {
+                    panel.center = PaneGroup::from_layout(layout);
+                    panel.active_pane = active_pane
+                        .filter(|pane| panel.center.panes().contains(&pane))
+                        .unwrap_or_else(|| panel.center.panes()[0].clone());
+                }
+                cx.notify();
+            })?;
+        }
 
         Ok(panel)
     }
 
+    fn deserialize_pane_group(
+        &mut self,
+        serialized: SerializedPaneGroup,
+        workspace: &Workspace,
+        panes: &mut Vec<DeserializedPane>,
+        cx: &mut ViewContext<Self>,
+    ) -> PaneGroupLayout {
+        match serialized {
+            SerializedPaneGroup::Group {
+                axis,
+                flexes,
+                children,
+            } => PaneGroupLayout::Axis {
+                axis: axis.into(),
+                flexes,
+                members: children
+                    .into_iter()
+                    .map(|child| self.deserialize_pane_group(child, workspace, panes, cx))
+                    .collect(),
+            },
+            SerializedPaneGroup::Pane(serialized_pane) => {
+                let pane = if panes.is_empty() {
+                    self.active_pane.clone()
+                } else {
+                    new_terminal_pane(workspace.weak_handle(), workspace.project().clone(), cx)
+                };
+                let items = pane.update(cx, |_, cx| {
+                    serialized_pane
+                        .items
+                        .iter()
+                        .map(|item_id| {
+                            TerminalView::deserialize(
+                                workspace.project().clone(),
+                                workspace.weak_handle(),
+                                workspace.database_id(),
+                                *item_id,
+                                cx,
+                            )
+                        })
+                        .collect()
+                });
+                panes.push(DeserializedPane {
+                    pane: pane.clone(),
+                    items,
+                    active_item_id: serialized_pane.active_item_id,
+                    active: serialized_pane.active,
+                });
+                PaneGroupLayout::Pane(pane)
+            }
+        }
+    }
+
     fn handle_pane_event(
         &mut self,
-        _pane: View<Pane>,
+        pane: View<Pane>,
         event: &pane::Event,
         cx: &mut ViewContext<Self>,
     ) {
         match event {
             pane::Event::ActivateItem { .. } => self.serialize(cx),
             pane::Event::RemoveItem { .. } => self.serialize(cx),
-            pane::Event::Remove => cx.emit(PanelEvent::Close),
+            pane::Event::Remove => match self.center.remove(&pane) {
+                Ok(true) => {
+                    if self.active_pane == pane {
+                        self.active_pane = self.center.panes()[0].clone();
+                        cx.focus_view(&self.active_pane);
+                    }
+                    self.serialize(cx);
+                    cx.notify();
+                }
+                Ok(false) => cx.emit(PanelEvent::Close),
+                Err(_) => {}
+            },
             pane::Event::ZoomIn => cx.emit(PanelEvent::ZoomIn),
             pane::Event::ZoomOut => cx.emit(PanelEvent::ZoomOut),
+            pane::Event::Focus => self.active_pane = pane,
+            pane::Event::Split(direction) => {
+                let working_directory = pane
+                    .read(cx)
+                    .active_item()
+                    .and_then(|item| item.downcast::<TerminalView>())
+                    .and_then(|terminal_view| terminal_view.read(cx).terminal().read(cx).get_cwd());
+                if self.split_pane(&pane, *direction, cx).is_some() {
+                    self.add_terminal(working_directory, None, RevealStrategy::Always, cx);
+                }
+            }
 
             pane::Event::AddItem { item } => {
                 if let Some(workspace) = self.workspace.upgrade() {
-                    let pane = self.pane.clone();
                     workspace.update(cx, |workspace, cx| item.added_to_pane(workspace, pane, cx))
                 }
             }
@@ -310,6 +300,102 @@ impl TerminalPanel {
         }
     }
 
+    /// Splits the pane given with a new, empty pane, which becomes the active one.
+    fn split_pane(
+        &mut self,
+        pane: &View<Pane>,
+        direction: SplitDirection,
+        cx: &mut ViewContext<Self>,
+    ) -> Option<View<Pane>> {
+        let project = self.workspace.upgrade()?.read(cx).project().clone();
+        let new_pane = new_terminal_pane(self.workspace.clone(), project, cx);
+        self.center.split(pane, &new_pane, direction).log_err()?;
+        self.active_pane = new_pane.clone();
+        cx.focus_view(&new_pane);
+        cx.notify();
+        Some(new_pane)
+    }
+
+    fn split_with_item(
+        &mut self,
+        from_pane: &View<Pane>,
+        to_pane: &View<Pane>,
+        item_id: EntityId,
+        direction: SplitDirection,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let Some((item_ix, item)) = from_pane
+            .read(cx)
+            .items()
+            .enumerate()
+            .find(|(_, item)| item.item_id() == item_id)
+            .map(|(ix, item)| (ix, item.boxed_clone()))
+        else {
+            // The tab was closed during the drag.
+            return;
+        };
+        let Some(new_pane) = self.split_pane(to_pane, direction, cx) else {
+            return;
+        };
+        from_pane.update(cx, |pane, cx| pane.remove_item(item_ix, false, true, cx));
+        new_pane.update(cx, |pane, cx| pane.add_item(item, true, true, None, cx));
+    }
+
+    fn handle_project_event(
+        &mut self,
+        _project: Model<Project>,
+        event: &project::Event,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let project::Event::WorktreeUpdatedEntries(_, changes) = event else {
+            return;
+        };
+        let labels_to_restart = self
+            .restartable_tasks
+            .iter()
+            .filter(|(label, task)| {
+                !self.pending_restarts.contains_key(*label)
+                    && changes.iter().any(|(path, _, _)| {
+                        task.restart_on_change
+                            .iter()
+                            .any(|matcher| matcher.is_match(path))
+                    })
+            })
+            .map(|(label, _)| label.clone())
+            .collect::<Vec<_>>();
+        for label in labels_to_restart {
+            let is_running = self.terminals_for_task(&label, cx).iter().any(|view| {
+                view.read(cx)
+                    .terminal()
+                    .read(cx)
+                    .task()
+                    .map_or(false, |task| task.status == TaskStatus::Running)
+            });
+            if !is_running {
+                self.restartable_tasks.remove(&label);
+                continue;
+            }
+
+            self.pending_restarts.insert(
+                label.clone(),
+                cx.spawn(|terminal_panel, mut cx| async move {
+                    // Files usually change in batches, restart once for all of them.
+                    cx.background_executor().timer(RESTART_DEBOUNCE).await;
+                    terminal_panel
+                        .update(&mut cx, |terminal_panel, cx| {
+                            terminal_panel.pending_restarts.remove(&label);
+                            if let Some(task) = terminal_panel.restartable_tasks.get(&label) {
+                                let mut spawn_in_terminal = task.spawn_in_terminal.clone();
+                                spawn_in_terminal.reveal = RevealStrategy::Never;
+                                terminal_panel.spawn_task_in_terminal(&spawn_in_terminal, cx);
+                            }
+                        })
+                        .ok();
+                }),
+            );
+        }
+    }
+
     pub fn open_terminal(
         workspace: &mut Workspace,
         action: &workspace::OpenTerminal,
@@ -330,6 +416,217 @@ impl TerminalPanel {
     }
 
     fn spawn_task(&mut self, spawn_in_terminal: &SpawnInTerminal, cx: &mut ViewContext<Self>) {
+        if spawn_in_terminal.dependencies.is_empty() {
+            self.spawn_task_in_terminal(spawn_in_terminal, cx);
+            return;
+        }
+
+        let mut spawn_in_terminal = spawn_in_terminal.clone();
+        let dependencies = self.run_tasks(
+            mem::take(&mut spawn_in_terminal.dependencies),
+            spawn_in_terminal.depends_order,
+            &DependencyRuns::default(),
+            cx,
+        );
+        self.tasks_waiting_for_dependencies.insert(
+            spawn_in_terminal.id.clone(),
+            cx.spawn(|terminal_panel, mut cx| async move {
+                let result = dependencies.await;
+                terminal_panel
+                    .update(&mut cx, |terminal_panel, cx| {
+                        match result {
+                            Ok(()) => {
+                                if !spawn_in_terminal.is_compound() {
+                                    terminal_panel.spawn_task_in_terminal(&spawn_in_terminal, cx);
+                                }
+                            }
+                            Err(error) => {
+                                let error = error.context(format!(
+                                    "Task `{}` was not started",
+                                    spawn_in_terminal.label
+                                ));
+                                terminal_panel
+                                    .workspace
+                                    .update(cx, |workspace, cx| workspace.show_error(&error, cx))
+                                    .ok();
+                            }
+                        }
+                        terminal_panel
+                            .tasks_waiting_for_dependencies
+                            .remove(&spawn_in_terminal.id);
+                    })
+                    .ok();
+            }),
+        );
+    }
+
+    /// Runs the tasks given to completion, failing if any of them did not succeed.
+    ///
+    /// Tasks that already ran as a dependency of another task of the same run are not run again.
+    fn run_tasks(
+        &mut self,
+        tasks: Vec<SpawnInTerminal>,
+        order: DependsOrder,
+        runs: &DependencyRuns,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Result<()>> {
+        match order {
+            DependsOrder::Parallel => {
+                let runs = tasks
+                    .into_iter()
+                    .map(|task| self.run_task(task, runs, cx))
+                    .collect::<Vec<_>>();
+                cx.spawn(|_, _| async move {
+                    try_join_all(runs)
+                        .await
+                        .map_err(|error| anyhow!("{error:#}"))?;
+                    Ok(())
+                })
+            }
+            DependsOrder::Sequence => {
+                let runs = runs.clone();
+                cx.spawn(|terminal_panel, mut cx| async move {
+                    for task in tasks {
+                        terminal_panel
+                            .update(&mut cx, |terminal_panel, cx| {
+                                terminal_panel.run_task(task, &runs, cx)
+                            })?
+                            .await
+                            .map_err(|error| anyhow!("{error:#}"))?;
+                    }
+                    Ok(())
+                })
+            }
+        }
+    }
+
+    /// Runs the task after its dependencies and waits for it to finish, failing if it did not succeed.
+    fn run_task(
+        &mut self,
+        mut task: SpawnInTerminal,
+        runs: &DependencyRuns,
+        cx: &mut ViewContext<Self>,
+    ) -> DependencyRun {
+        if let Some(run) = runs.borrow().get(&task.id) {
+            return run.clone();
+        }
+
+        let dependencies = self.run_tasks(
+            mem::take(&mut task.dependencies),
+            task.depends_order,
+            runs,
+            cx,
+        );
+        let task_id = task.id.clone();
+        let run = cx.spawn(|terminal_panel, mut cx| async move {
+            dependencies.await?;
+            if task.is_compound() {
+                return Ok(());
+            }
+
+            let terminal = terminal_panel
+                .update(&mut cx, |terminal_panel, cx| {
+                    let (terminal_tx, terminal_rx) = oneshot::channel();
+                    terminal_panel
+                        .task_terminal_listeners
+                        .entry(task.id.clone())
+                        .or_default()
+                        .push(terminal_tx);
+                    if terminal_panel.spawn_task_in_terminal(&task, cx).is_none() {
+                        terminal_panel.task_terminal_created(&task.id, None);
+                    }
+                    terminal_rx
+                })?
+                .await
+                .with_context(|| format!("Task `{}` could not be started", task.label))?;
+            if task.background.is_some() {
+                // Background tasks do not finish, tasks depending on them wait for them to get ready instead.
+                terminal
+                    .update(&mut cx, |terminal, cx| {
+                        terminal.wait_for_background_task_ready(cx)
+                    })?
+                    .await;
+                let status = terminal.update(&mut cx, |terminal, _| {
+                    terminal.task().and_then(|task_state| {
+                        Some((task_state.status, task_state.background.as_ref()?.status))
+                    })
+                })?;
+                return match status {
+                    Some((TaskStatus::Running, BackgroundTaskStatus::Ready)) => Ok(()),
+                    _ => Err(anyhow!(
+                        "Task `{}` stopped before getting ready",
+                        task.label
+                    )),
+                };
+            }
+            terminal
+                .update(&mut cx, |terminal, cx| terminal.wait_for_completed_task(cx))?
+                .await;
+            let status = terminal.update(&mut cx, |terminal, _| {
+                terminal.task().map(|task_state| task_state.status)
+            })?;
+            match status {
+                Some(TaskStatus::Completed { success: true }) => Ok(()),
+                _ => Err(anyhow!("Task `{}` failed", task.label)),
+            }
+        });
+        let run = run
+            .map(|result| result.map_err(Arc::new))
+            .boxed_local()
+            .shared();
+        runs.borrow_mut().insert(task_id, run.clone());
+        run
+    }
+
+    /// Hands the terminal of the task to the ones waiting for it, `None` tells them that the task could not be started.
+    fn task_terminal_created(&mut self, task_id: &TaskId, terminal: Option<&Model<Terminal>>) {
+        for listener in self
+            .task_terminal_listeners
+            .remove(task_id)
+            .into_iter()
+            .flatten()
+        {
+            if let Some(terminal) = terminal {
+                listener.send(terminal.clone()).ok();
+            }
+        }
+    }
+
+    /// Spawns the task in a terminal, returning `None` if it could not be started.
+    /// Terminals that get created later report to the task's terminal listeners themselves.
+    fn spawn_task_in_terminal(
+        &mut self,
+        spawn_in_terminal: &SpawnInTerminal,
+        cx: &mut ViewContext<Self>,
+    ) -> Option<()> {
+        match spawn_in_terminal
+            .background
+            .as_ref()
+            .filter(|background| !background.restart_on_change.is_empty())
+        {
+            Some(background) => {
+                let restart_on_change = background
+                    .restart_on_change
+                    .iter()
+                    .filter_map(|glob| {
+                        PathMatcher::new(glob)
+                            .with_context(|| format!("invalid restart glob `{glob}`"))
+                            .log_err()
+                    })
+                    .collect();
+                self.restartable_tasks.insert(
+                    spawn_in_terminal.full_label.clone(),
+                    RestartableTask {
+                        spawn_in_terminal: spawn_in_terminal.clone(),
+                        restart_on_change,
+                    },
+                );
+            }
+            None => {
+                self.restartable_tasks.remove(&spawn_in_terminal.full_label);
+            }
+        }
+
         let mut spawn_task = spawn_in_terminal.clone();
         // Set up shell args unconditionally, as tasks are always spawned inside of a shell.
         let Some((shell, mut user_args)) = (match TerminalSettings::get_global(cx).shell.clone() {
@@ -337,7 +634,7 @@ impl TerminalPanel {
             Shell::Program(shell) => Some((shell, Vec::new())),
             Shell::WithArguments { program, args } => Some((program, args)),
         }) else {
-            return;
+            return None;
         };
 
         spawn_task.command_label = format!("{shell} -i -c `{}`", spawn_task.command_label);
@@ -358,33 +655,29 @@ impl TerminalPanel {
         let working_directory = spawn_in_terminal.cwd.clone();
         let allow_concurrent_runs = spawn_in_terminal.allow_concurrent_runs;
         let use_new_terminal = spawn_in_terminal.use_new_terminal;
+        let is_background = spawn_in_terminal.background.is_some();
 
         if allow_concurrent_runs && use_new_terminal {
             self.spawn_in_new_terminal(spawn_task, working_directory, cx);
-            return;
+            return Some(());
         }
 
         let terminals_for_task = self.terminals_for_task(&spawn_in_terminal.full_label, cx);
         if terminals_for_task.is_empty() {
             self.spawn_in_new_terminal(spawn_task, working_directory, cx);
-            return;
+            return Some(());
         }
-        let (existing_item_index, existing_terminal) = terminals_for_task
+        let existing_terminal = terminals_for_task
             .last()
             .expect("covered no terminals case above")
             .clone();
-        if allow_concurrent_runs {
+        // Background tasks never finish on their own, so instead of waiting for the previous run, replace it.
+        if allow_concurrent_runs || is_background {
             debug_assert!(
-                !use_new_terminal,
+                !(allow_concurrent_runs && use_new_terminal),
                 "Should have handled 'allow_concurrent_runs && use_new_terminal' case above"
             );
-            self.replace_terminal(
-                working_directory,
-                spawn_task,
-                existing_item_index,
-                existing_terminal,
-                cx,
-            );
+            self.replace_terminal(working_directory, spawn_task, existing_terminal, cx)?;
         } else {
             self.deferred_tasks.insert(
                 spawn_in_terminal.id.clone(),
@@ -399,13 +692,18 @@ impl TerminalPanel {
                                     cx,
                                 );
                             } else {
-                                terminal_panel.replace_terminal(
-                                    working_directory,
-                                    spawn_task,
-                                    existing_item_index,
-                                    existing_terminal,
-                                    cx,
-                                );
+                                let task_id = spawn_task.id.clone();
+                                if terminal_panel
+                                    .replace_terminal(
+                                        working_directory,
+                                        spawn_task,
+                                        existing_terminal,
+                                        cx,
+                                    )
+                                    .is_none()
+                                {
+                                    terminal_panel.task_terminal_created(&task_id, None);
+                                }
                             }
                         })
                         .ok();
@@ -414,7 +712,7 @@ impl TerminalPanel {
 
             match reveal {
                 RevealStrategy::Always => {
-                    self.activate_terminal_view(existing_item_index, cx);
+                    self.activate_terminal_view(&existing_terminal, cx);
                     let task_workspace = self.workspace.clone();
                     cx.spawn(|_, mut cx| async move {
                         task_workspace
@@ -426,6 +724,7 @@ impl TerminalPanel {
                 RevealStrategy::Never => {}
             }
         }
+        Some(())
     }
 
     fn spawn_in_new_terminal(
@@ -453,31 +752,53 @@ impl TerminalPanel {
         });
     }
 
-    fn terminals_for_task(
-        &self,
-        label: &str,
-        cx: &mut AppContext,
-    ) -> Vec<(usize, View<TerminalView>)> {
-        self.pane
-            .read(cx)
-            .items()
-            .enumerate()
-            .filter_map(|(index, item)| Some((index, item.act_as::<TerminalView>(cx)?)))
-            .filter_map(|(index, terminal_view)| {
-                let task_state = terminal_view.read(cx).terminal().read(cx).task()?;
-                if &task_state.full_label == label {
-                    Some((index, terminal_view))
-                } else {
-                    None
-                }
+    fn terminal_views(&self, cx: &AppContext) -> Vec<View<TerminalView>> {
+        self.center
+            .panes()
+            .into_iter()
+            .flat_map(|pane| {
+                pane.read(cx)
+                    .items()
+                    .filter_map(|item| item.act_as::<TerminalView>(cx))
+                    .collect::<Vec<_>>()
             })
             .collect()
     }
 
-    fn activate_terminal_view(&self, item_index: usize, cx: &mut WindowContext) {
-        self.pane.update(cx, |pane, cx| {
-            pane.activate_item(item_index, true, true, cx)
-        })
+    fn terminals_for_task(&self, label: &str, cx: &mut AppContext) -> Vec<View<TerminalView>> {
+        self.terminal_views(cx)
+            .into_iter()
+            .filter(|terminal_view| {
+                terminal_view
+                    .read(cx)
+                    .terminal()
+                    .read(cx)
+                    .task()
+                    .map_or(false, |task_state| task_state.full_label == label)
+            })
+            .collect()
+    }
+
+    /// Activates the tab of the terminal given, if the panel has it.
+    pub(crate) fn activate_terminal(&self, terminal: &Model<Terminal>, cx: &mut WindowContext) {
+        let terminal_view = self
+            .terminal_views(cx)
+            .into_iter()
+            .find(|view| view.read(cx).terminal() == terminal);
+        if let Some(terminal_view) = terminal_view {
+            self.activate_terminal_view(&terminal_view, cx);
+        }
+    }
+
+    fn activate_terminal_view(&self, terminal_view: &View<TerminalView>, cx: &mut WindowContext) {
+        for pane in self.center.panes() {
+            if let Some(item_index) = pane.read(cx).index_for_item(terminal_view) {
+                pane.update(cx, |pane, cx| {
+                    pane.activate_item(item_index, true, true, cx)
+                });
+                return;
+            }
+        }
     }
 
     fn add_terminal(
@@ -488,17 +809,18 @@ impl TerminalPanel {
         cx: &mut ViewContext<Self>,
     ) {
         let workspace = self.workspace.clone();
+        let task_id = spawn_task.as_ref().map(|spawn_task| spawn_task.id.clone());
         self.pending_terminals_to_add += 1;
 
         cx.spawn(|terminal_panel, mut cx| async move {
-            let pane = terminal_panel.update(&mut cx, |this, _| this.pane.clone())?;
-            workspace.update(&mut cx, |workspace, cx| {
+            let pane = terminal_panel.update(&mut cx, |this, _| this.active_pane.clone())?;
+            let terminal = workspace.update(&mut cx, |workspace, cx| {
                 if workspace.project().read(cx).is_remote() {
                     workspace.show_error(
                         &anyhow::anyhow!("Cannot open terminals on remote projects (yet!)"),
                         cx,
                     );
-                    return;
+                    return None;
                 };
 
                 let working_directory = if let Some(working_directory) = working_directory {
@@ -510,11 +832,12 @@ impl TerminalPanel {
                 };
 
                 let window = cx.window_handle();
-                if let Some(terminal) = workspace.project().update(cx, |project, cx| {
+                let terminal = workspace.project().update(cx, |project, cx| {
                     project
-                        .create_terminal(working_directory, spawn_task, window, cx)
+                        .create_terminal(working_directory, spawn_task, None, window, cx)
                         .log_err()
-                }) {
+                });
+                if let Some(terminal) = terminal.clone() {
                     let terminal = Box::new(cx.new_view(|cx| {
                         TerminalView::new(
                             terminal,
@@ -531,8 +854,14 @@ impl TerminalPanel {
                 if reveal_strategy == RevealStrategy::Always {
                     workspace.focus_panel::<Self>(cx);
                 }
-            })?;
+                terminal
+            });
+            // Let the ones waiting for the task's terminal know even if the workspace is gone.
+            let terminal = terminal.log_err().flatten();
             terminal_panel.update(&mut cx, |this, cx| {
+                if let Some(task_id) = &task_id {
+                    this.task_terminal_created(task_id, terminal.as_ref());
+                }
                 this.pending_terminals_to_add = this.pending_terminals_to_add.saturating_sub(1);
                 this.serialize(cx)
             })?;
@@ -542,28 +871,7 @@ impl TerminalPanel {
     }
 
     fn serialize(&mut self, cx: &mut ViewContext<Self>) {
-        let mut items_to_serialize = HashSet::default();
-        let items = self
-            .pane
-            .read(cx)
-            .items()
-            .filter_map(|item| {
-                let terminal_view = item.act_as::<TerminalView>(cx)?;
-                if terminal_view.read(cx).terminal().read(cx).task().is_some() {
-                    None
-                } else {
-                    let id = item.item_id().as_u64();
-                    items_to_serialize.insert(id);
-                    Some(id)
-                }
-            })
-            .collect::<Vec<_>>();
-        let active_item_id = self
-            .pane
-            .read(cx)
-            .active_item()
-            .map(|item| item.item_id().as_u64())
-            .filter(|active_id| items_to_serialize.contains(active_id));
+        let pane_group = serialize_pane_group(self.center.layout(), &self.active_pane, cx);
         let height = self.height;
         let width = self.width;
         self.pending_serialization = cx.background_executor().spawn(
@@ -572,8 +880,9 @@ impl TerminalPanel {
                     .write_kvp(
                         TERMINAL_PANEL_KEY.into(),
                         serde_json::to_string(&SerializedTerminalPanel {
-                            items,
-                            active_item_id,
+                            items: Vec::new(),
+                            active_item_id: None,
+                            pane_group: Some(pane_group),
                             height,
                             width,
                         })?,
@@ -586,10 +895,9 @@ impl TerminalPanel {
     }
 
     fn replace_terminal(
-        &self,
+        &mut self,
         working_directory: Option<PathBuf>,
         spawn_task: SpawnInTerminal,
-        terminal_item_index: usize,
         terminal_to_replace: View<TerminalView>,
         cx: &mut ViewContext<'_, Self>,
     ) -> Option<()> {
@@ -599,19 +907,22 @@ impl TerminalPanel {
             .ok()?;
 
         let reveal = spawn_task.reveal;
+        let task_id = spawn_task.id.clone();
         let window = cx.window_handle();
         let new_terminal = project.update(cx, |project, cx| {
             project
-                .create_terminal(working_directory, Some(spawn_task), window, cx)
+                .create_terminal(working_directory, Some(spawn_task), None, window, cx)
                 .log_err()
-        })?;
+        });
+        self.task_terminal_created(&task_id, new_terminal.as_ref());
+        let new_terminal = new_terminal?;
         terminal_to_replace.update(cx, |terminal_to_replace, cx| {
             terminal_to_replace.set_terminal(new_terminal, cx);
         });
 
         match reveal {
             RevealStrategy::Always => {
-                self.activate_terminal_view(terminal_item_index, cx);
+                self.activate_terminal_view(&terminal_to_replace, cx);
                 let task_workspace = self.workspace.clone();
                 cx.spawn(|_, mut cx| async move {
                     task_workspace
@@ -626,20 +937,268 @@ impl TerminalPanel {
         Some(())
     }
 
-    pub fn pane(&self) -> &View<Pane> {
-        &self.pane
+    pub fn active_pane(&self) -> &View<Pane> {
+        &self.active_pane
+    }
+
+    fn terminals_count(&self, cx: &AppContext) -> usize {
+        self.center
+            .panes()
+            .into_iter()
+            .map(|pane| pane.read(cx).items_len())
+            .sum()
     }
 
     fn has_no_terminals(&mut self, cx: &mut ViewContext<'_, Self>) -> bool {
-        self.pane.read(cx).items_len() == 0 && self.pending_terminals_to_add == 0
+        self.terminals_count(cx) == 0 && self.pending_terminals_to_add == 0
+    }
+}
+
+fn new_terminal_pane(
+    workspace: WeakView<Workspace>,
+    project: Model<Project>,
+    cx: &mut ViewContext<TerminalPanel>,
+) -> View<Pane> {
+    let terminal_panel = cx.view().downgrade();
+    let pane = cx.new_view(|cx| {
+        let mut pane = Pane::new(
+            workspace.clone(),
+            project,
+            Default::default(),
+            None,
+            NewTerminal.boxed_clone(),
+            cx,
+        );
+        pane.set_can_split(true, cx);
+        pane.set_can_navigate(false, cx);
+        pane.display_nav_history_buttons(None);
+        pane.set_should_display_tab_bar(|_| true);
+        pane.set_render_tab_bar_buttons(cx, move |pane, cx| {
+            h_flex()
+                .gap_2()
+                .child(
+                    IconButton::new("plus", IconName::Plus)
+                        .icon_size(IconSize::Small)
+                        .on_click(cx.listener(|pane, _, cx| {
+                            let focus_handle = pane.focus_handle(cx);
+                            let this = cx.view().downgrade();
+                            let menu = ContextMenu::build(cx, |menu, _| {
+                                let split = |direction| {
+                                    let this = this.clone();
+                                    move |cx: &mut WindowContext| {
+                                        this.update(cx, |pane, cx| pane.split(direction, cx))
+                                            .log_err();
+                                    }
+                                };
+                                menu.action("New Terminal", workspace::NewTerminal.boxed_clone())
+                                    .entry(
+                                        "Split Right",
+                                        Some(pane::SplitRight.boxed_clone()),
+                                        split(SplitDirection::Right),
+                                    )
+                                    .entry(
+                                        "Split Down",
+                                        Some(pane::SplitDown.boxed_clone()),
+                                        split(SplitDirection::Down),
+                                    )
+                                    .separator()
+                                    .entry(
+                                        "Spawn task",
+                                        Some(tasks_ui::Spawn::modal().boxed_clone()),
+                                        move |cx| {
+                                            // We want the focus to go back to terminal panel once task modal is dismissed,
+                                            // hence we focus that first. Otherwise, we'd end up without a focused element, as
+                                            // context menu will be gone the moment we spawn the modal.
+                                            cx.focus(&focus_handle);
+                                            cx.dispatch_action(
+                                                tasks_ui::Spawn::modal().boxed_clone(),
+                                            );
+                                        },
+                                    )
+                            });
+                            cx.subscribe(&menu, |pane, _, _: &DismissEvent, _| {
+                                pane.new_item_menu = None;
+                            })
+                            .detach();
+                            pane.new_item_menu = Some(menu);
+                        }))
+                        .tooltip(|cx| Tooltip::text("New...", cx)),
+                )
+                .when_some(pane.new_item_menu.as_ref(), |el, new_item_menu| {
+                    el.child(Pane::render_menu_overlay(new_item_menu))
+                })
+                .child({
+                    let zoomed = pane.is_zoomed();
+                    IconButton::new("toggle_zoom", IconName::Maximize)
+                        .icon_size(IconSize::Small)
+                        .selected(zoomed)
+                        .selected_icon(IconName::Minimize)
+                        .on_click(cx.listener(|pane, _, cx| {
+                            pane.toggle_zoom(&workspace::ToggleZoom, cx);
+                        }))
+                        .tooltip(move |cx| {
+                            Tooltip::for_action(
+                                if zoomed { "Zoom Out" } else { "Zoom In" },
+                                &ToggleZoom,
+                                cx,
+                            )
+                        })
+                })
+                .into_any_element()
+        });
+
+        pane.set_custom_drop_handle(cx, move |pane, dropped_item, cx| {
+            if let Some(tab) = dropped_item.downcast_ref::<DraggedTab>() {
+                let item = if &tab.pane == cx.view() {
+                    pane.item_for_index(tab.ix)
+                } else {
+                    tab.pane.read(cx).item_for_index(tab.ix)
+                };
+                if let Some(item) = item {
+                    if item.downcast::<TerminalView>().is_some() {
+                        // Splits of the terminal panel are its own, not the workspace center ones.
+                        let Some(split_direction) = pane.drag_split_direction() else {
+                            return ControlFlow::Continue(());
+                        };
+                        let from_pane = tab.pane.clone();
+                        let to_pane = cx.view().clone();
+                        let item_id = item.item_id();
+                        terminal_panel
+                            .update(cx, |_, cx| {
+                                cx.defer(move |terminal_panel, cx| {
+                                    terminal_panel.split_with_item(
+                                        &from_pane,
+                                        &to_pane,
+                                        item_id,
+                                        split_direction,
+                                        cx,
+                                    );
+                                })
+                            })
+                            .log_err();
+                    } else if let Some(project_path) = item.project_path(cx) {
+                        if let Some(entry_path) = workspace
+                            .update(cx, |workspace, cx| {
+                                workspace
+                                    .project()
+                                    .read(cx)
+                                    .absolute_path(&project_path, cx)
+                            })
+                            .log_err()
+                            .flatten()
+                        {
+                            add_paths_to_terminal(pane, &[entry_path], cx);
+                        }
+                    }
+                }
+            } else if let Some(&entry_id) = dropped_item.downcast_ref::<ProjectEntryId>() {
+                if let Some(entry_path) = workspace
+                    .update(cx, |workspace, cx| {
+                        let project = workspace.project().read(cx);
+                        project
+                            .path_for_entry(entry_id, cx)
+                            .and_then(|project_path| project.absolute_path(&project_path, cx))
+                    })
+                    .log_err()
+                    .flatten()
+                {
+                    add_paths_to_terminal(pane, &[entry_path], cx);
+                }
+            } else if let Some(paths) = dropped_item.downcast_ref::<ExternalPaths>() {
+                add_paths_to_terminal(pane, paths.paths(), cx);
+            }
+
+            ControlFlow::Break(())
+        });
+        let buffer_search_bar = cx.new_view(search::BufferSearchBar::new);
+        pane.toolbar()
+            .update(cx, |toolbar, cx| toolbar.add_item(buffer_search_bar, cx));
+        pane
+    });
+    cx.observe(&pane, |_, _, cx| cx.notify()).detach();
+    cx.subscribe(&pane, TerminalPanel::handle_pane_event)
+        .detach();
+    pane
+}
+
+fn serialize_pane_group(
+    layout: PaneGroupLayout,
+    active_pane: &View<Pane>,
+    cx: &AppContext,
+) -> SerializedPaneGroup {
+    match layout {
+        PaneGroupLayout::Axis {
+            axis,
+            flexes,
+            members,
+        } => SerializedPaneGroup::Group {
+            axis: axis.into(),
+            flexes,
+            children: members
+                .into_iter()
+                .map(|member| serialize_pane_group(member, active_pane, cx))
+                .collect(),
+        },
+        PaneGroupLayout::Pane(pane) => {
+            let mut items_to_serialize = HashSet::default();
+            let items = pane
+                .read(cx)
+                .items()
+                .filter_map(|item| {
+                    let terminal_view = item.act_as::<TerminalView>(cx)?;
+                    if terminal_view.read(cx).terminal().read(cx).task().is_some() {
+                        None
+                    } else {
+                        let id = item.item_id().as_u64();
+                        items_to_serialize.insert(id);
+                        Some(id)
+                    }
+                })
+                .collect::<Vec<_>>();
+            let active_item_id = pane
+                .read(cx)
+                .active_item()
+                .map(|item| item.item_id().as_u64())
+                .filter(|active_id| items_to_serialize.contains(active_id));
+            SerializedPaneGroup::Pane(SerializedPane {
+                items,
+                active_item_id,
+                active: &pane == active_pane,
+            })
+        }
+    }
+}
+
+/// Drops the panes without any items from the layout, returns `None` if none of the panes has items.
+fn retain_panes_with_items(layout: PaneGroupLayout, cx: &AppContext) -> Option<PaneGroupLayout> {
+    match layout {
+        PaneGroupLayout::Pane(pane) => {
+            (pane.read(cx).items_len() > 0).then_some(PaneGroupLayout::Pane(pane))
+        }
+        PaneGroupLayout::Axis {
+            axis,
+            flexes,
+            members,
+        } => {
+            let (flexes, members): (Vec<_>, Vec<_>) = flexes
+                .into_iter()
+                .zip(members)
+                .filter_map(|(flex, member)| Some((flex, retain_panes_with_items(member, cx)?)))
+                .unzip();
+            (!members.is_empty()).then_some(PaneGroupLayout::Axis {
+                axis,
+                flexes,
+                members,
+            })
+        }
     }
 }
 
 async fn wait_for_terminals_tasks(
-    terminals_for_task: Vec<(usize, View<TerminalView>)>,
+    terminals_for_task: Vec<View<TerminalView>>,
     cx: &mut AsyncWindowContext,
 ) {
-    let pending_tasks = terminals_for_task.iter().filter_map(|(_, terminal)| {
+    let pending_tasks = terminals_for_task.iter().filter_map(|terminal| {
         terminal
             .update(cx, |terminal_view, cx| {
                 terminal_view
@@ -674,7 +1233,7 @@ impl Render for TerminalPanel {
         let mut registrar = DivRegistrar::new(
             |panel, cx| {
                 panel
-                    .pane
+                    .active_pane
                     .read(cx)
                     .toolbar()
                     .read(cx)
@@ -683,13 +1242,32 @@ impl Render for TerminalPanel {
             cx,
         );
         BufferSearchBar::register(&mut registrar);
-        registrar.into_div().size_full().child(self.pane.clone())
+        let panes = if self.active_pane.read(cx).is_zoomed() {
+            None
+        } else {
+            self.workspace
+                .update(cx, |workspace, cx| {
+                    self.center
+                        .render_in_dock(
+                            workspace.project(),
+                            &self.active_pane,
+                            workspace.app_state(),
+                            cx,
+                        )
+                        .into_any_element()
+                })
+                .ok()
+        };
+        registrar
+            .into_div()
+            .size_full()
+            .child(panes.unwrap_or_else(|| self.active_pane.clone().into_any_element()))
     }
 }
 
 impl FocusableView for TerminalPanel {
     fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
-        self.pane.focus_handle(cx)
+        self.active_pane.focus_handle(cx)
     }
 }
 
@@ -737,11 +1315,14 @@ impl Panel for TerminalPanel {
     }
 
     fn is_zoomed(&self, cx: &WindowContext) -> bool {
-        self.pane.read(cx).is_zoomed()
+        self.active_pane.read(cx).is_zoomed()
     }
 
     fn set_zoomed(&mut self, zoomed: bool, cx: &mut ViewContext<Self>) {
-        self.pane.update(cx, |pane, cx| pane.set_zoomed(zoomed, cx));
+        for pane in self.center.panes() {
+            let zoomed = zoomed && pane == &self.active_pane;
+            pane.update(cx, |pane, cx| pane.set_zoomed(zoomed, cx));
+        }
     }
 
     fn set_active(&mut self, active: bool, cx: &mut ViewContext<Self>) {
@@ -751,7 +1332,7 @@ impl Panel for TerminalPanel {
     }
 
     fn icon_label(&self, cx: &WindowContext) -> Option<String> {
-        let count = self.pane.read(cx).items_len();
+        let count = self.terminals_count(cx);
         if count == 0 {
             None
         } else {
@@ -780,8 +1361,198 @@ impl Panel for TerminalPanel {
 
 #[derive(Serialize, Deserialize)]
 struct SerializedTerminalPanel {
+    /// Terminals of the panel, serialized before it could be split.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     items: Vec<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     active_item_id: Option<u64>,
+    #[serde(default)]
+    pane_group: Option<SerializedPaneGroup>,
     width: Option<Pixels>,
     height: Option<Pixels>,
 }
+
+#[derive(Serialize, Deserialize)]
+enum SerializedPaneGroup {
+    Group {
+        axis: SerializedAxis,
+        flexes: Vec<f32>,
+        children: Vec<SerializedPaneGroup>,
+    },
+    Pane(SerializedPane),
+}
+
+#[derive(Serialize, Deserialize)]
+struct SerializedPane {
+    items: Vec<u64>,
+    active_item_id: Option<u64>,
+    active: bool,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+enum SerializedAxis {
+    Horizontal,
+    Vertical,
+}
+
+impl From<Axis> for SerializedAxis {
+    fn from(axis: Axis) -> Self {
+        match axis {
+            Axis::Horizontal => Self::Horizontal,
+            Axis::Vertical => Self::Vertical,
+        }
+    }
+}
+
+impl From<SerializedAxis> for Axis {
+    fn from(axis: SerializedAxis) -> Self {
+        match axis {
+            SerializedAxis::Horizontal => Self::Horizontal,
+            SerializedAxis::Vertical => Self::Vertical,
+        }
+    }
+}
+
+/// A pane of the restored panel, with its terminals still being deserialized.
+struct DeserializedPane {
+    pane: View<Pane>,
+    items: Vec<Task<Result<View<TerminalView>>>>,
+    active_item_id: Option<u64>,
+    active: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use gpui::{TestAppContext, VisualTestContext};
+    use project::FakeFs;
+    use settings::SettingsStore;
+    use workspace::item::test::TestItem;
+
+    use super::*;
+
+    #[gpui::test]
+    async fn test_split_and_close_panes(cx: &mut TestAppContext) {
+        let (panel, cx) = init_test(cx).await;
+        let (first_pane, second_pane, third_pane) = split_panes(&panel, cx);
+
+        panel.update(cx, |panel, _| {
+            assert_eq!(
+                describe_layout(&panel.center.layout()),
+                "Horizontal[pane, Vertical[pane, pane]]"
+            );
+            assert_eq!(panel.active_pane, third_pane);
+        });
+
+        second_pane.update(cx, |pane, cx| pane.remove_item(0, false, true, cx));
+        panel.update(cx, |panel, _| {
+            assert_eq!(
+                describe_layout(&panel.center.layout()),
+                "Horizontal[pane, pane]"
+            );
+            assert_eq!(panel.active_pane, third_pane);
+        });
+
+        third_pane.update(cx, |pane, cx| pane.remove_item(0, false, true, cx));
+        panel.update(cx, |panel, _| {
+            assert_eq!(describe_layout(&panel.center.layout()), "pane");
+            assert_eq!(panel.active_pane, first_pane);
+        });
+    }
+
+    #[gpui::test]
+    async fn test_pane_group_layout_round_trip(cx: &mut TestAppContext) {
+        let (panel, cx) = init_test(cx).await;
+        split_panes(&panel, cx);
+
+        let (layout, serialized) = panel.update(cx, |panel, cx| {
+            let layout = panel.center.layout();
+            let serialized = serialize_pane_group(layout.clone(), &panel.active_pane, cx);
+            (layout, serde_json::to_string(&serialized).unwrap())
+        });
+        let serialized = serde_json::from_str::<SerializedPaneGroup>(&serialized).unwrap();
+        let SerializedPaneGroup::Group { children, .. } = &serialized else {
+            panic!("expected a group, got a single pane");
+        };
+        let SerializedPaneGroup::Group {
+            children: nested_children,
+            ..
+        } = &children[1]
+        else {
+            panic!("expected a nested group");
+        };
+        assert!(matches!(&nested_children[1], SerializedPaneGroup::Pane(pane) if pane.active));
+
+        let workspace = panel.update(cx, |panel, _| panel.workspace.upgrade().unwrap());
+        let restored = workspace.update(cx, |workspace, cx| {
+            panel.update(cx, |panel, cx| {
+                let mut panes = Vec::new();
+                let restored = panel.deserialize_pane_group(serialized, workspace, &mut panes, cx);
+                assert_eq!(panes.len(), 3);
+                assert!(panes[2].active);
+                restored
+            })
+        });
+        assert_eq!(describe_layout(&restored), describe_layout(&layout));
+        assert_eq!(
+            describe_layout(&PaneGroup::from_layout(restored).layout()),
+            describe_layout(&layout)
+        );
+    }
+
+    async fn init_test(cx: &mut TestAppContext) -> (View<TerminalPanel>, &mut VisualTestContext) {
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            theme::init(theme::LoadThemes::JustBase, cx);
+            workspace::init_settings(cx);
+            Project::init_settings(cx);
+            language::init(cx);
+            terminal::init(cx);
+        });
+
+        let fs = FakeFs::new(cx.executor());
+        let project = Project::test(fs, [], cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
+        let panel = workspace.update(cx, |workspace, cx| {
+            cx.new_view(|cx| TerminalPanel::new(workspace, cx))
+        });
+        (panel, cx)
+    }
+
+    /// Splits the panel into a pane on the left and two panes on the right, each with an item.
+    fn split_panes(
+        panel: &View<TerminalPanel>,
+        cx: &mut VisualTestContext,
+    ) -> (View<Pane>, View<Pane>, View<Pane>) {
+        panel.update(cx, |panel, cx| {
+            let first_pane = panel.active_pane.clone();
+            add_item(&first_pane, cx);
+            let second_pane = panel
+                .split_pane(&first_pane, SplitDirection::Right, cx)
+                .unwrap();
+            add_item(&second_pane, cx);
+            let third_pane = panel
+                .split_pane(&second_pane, SplitDirection::Down, cx)
+                .unwrap();
+            add_item(&third_pane, cx);
+            (first_pane, second_pane, third_pane)
+        })
+    }
+
+    fn add_item(pane: &View<Pane>, cx: &mut ViewContext<TerminalPanel>) {
+        let item = cx.new_view(TestItem::new);
+        pane.update(cx, |pane, cx| {
+            pane.add_item(Box::new(item), true, true, None, cx)
+        });
+    }
+
+    fn describe_layout(layout: &PaneGroupLayout) -> String {
+        match layout {
+            PaneGroupLayout::Pane(_) => "pane".to_string(),
+            PaneGroupLayout::Axis { axis, members, .. } => format!(
+                "{axis:?}[{}]",
+                members.iter().map(describe_layout).join(", ")
+            ),
+        }
+    }
+}
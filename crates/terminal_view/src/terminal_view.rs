@@ -1,14 +1,18 @@
+mod background_tasks_indicator;
 mod persistence;
 pub mod terminal_element;
 pub mod terminal_panel;
 
+pub use background_tasks_indicator::BackgroundTasksIndicator;
+
 use collections::HashSet;
 use editor::{scroll::Autoscroll, Editor};
 use futures::{stream::FuturesUnordered, StreamExt};
 use gpui::{
-    anchored, deferred, div, impl_actions, AnyElement, AppContext, DismissEvent, EventEmitter,
-    FocusHandle, FocusableView, KeyContext, KeyDownEvent, Keystroke, Model, MouseButton,
-    MouseDownEvent, Pixels, Render, Styled, Subscription, Task, View, VisualContext, WeakView,
+    anchored, deferred, div, impl_actions, AnyElement, AppContext, DismissEvent, EntityId,
+    EventEmitter, FocusHandle, FocusableView, KeyContext, KeyDownEvent, Keystroke, Model,
+    MouseButton, MouseDownEvent, Pixels, Render, Styled, Subscription, Task, View, VisualContext,
+    WeakView,
 };
 use language::Bias;
 use persistence::TERMINAL_DB;
@@ -20,7 +24,8 @@ use terminal::{
         term::{search::RegexSearch, TermMode},
     },
     terminal_settings::{TerminalBlink, TerminalSettings, WorkingDirectory},
-    Clear, Copy, Event, MaybeNavigationTarget, Paste, ShowCharacterPalette, TaskStatus, Terminal,
+    BackgroundTaskStatus, Clear, Copy, CopyLastCommandOutput, Event, MaybeNavigationTarget, Paste,
+    ScrollToNextPrompt, ScrollToPreviousPrompt, ShowCharacterPalette, TaskStatus, Terminal,
 };
 use terminal_element::TerminalElement;
 use ui::{h_flex, prelude::*, ContextMenu, Icon, IconName, Label};
@@ -52,6 +57,11 @@ const REGEX_SPECIAL_CHARS: &[char] = &[
 ];
 
 const CURSOR_BLINK_INTERVAL: Duration = Duration::from_millis(500);
+/// How many of the last terminal lines are persisted, to be restored with the workspace.
+const MAX_SERIALIZED_SCROLLBACK_LINES: usize = 2_000;
+/// How long the title has to stay the same before the terminal contents get stored,
+/// so that every command run does not write the scrollback to the database.
+const SERIALIZE_CONTENTS_DEBOUNCE: Duration = Duration::from_secs(5);
 
 ///Event to transmit the scroll from the element to the view
 #[derive(Clone, Debug, PartialEq)]
@@ -92,6 +102,7 @@ pub struct TerminalView {
     can_navigate_to_selected_word: bool,
     workspace_id: WorkspaceId,
     show_title: bool,
+    pending_contents_serialization: Task<()>,
     _subscriptions: Vec<Subscription>,
     _terminal_subscriptions: Vec<Subscription>,
 }
@@ -121,7 +132,7 @@ impl TerminalView {
         let terminal = workspace
             .project()
             .update(cx, |project, cx| {
-                project.create_terminal(working_directory, None, window, cx)
+                project.create_terminal(working_directory, None, None, window, cx)
             })
             .notify_err(workspace, cx);
 
@@ -154,6 +165,17 @@ impl TerminalView {
         let focus_out = cx.on_focus_out(&focus_handle, |terminal_view, cx| {
             terminal_view.focus_out(cx);
         });
+        let this = cx.view().downgrade();
+        let app_quit = cx.on_app_quit(move |cx| {
+            let serialize_contents = this
+                .upgrade()
+                .map(|this| this.read(cx).serialize_contents(this.entity_id(), cx));
+            async move {
+                if let Some(serialize_contents) = serialize_contents {
+                    serialize_contents.await;
+                }
+            }
+        });
 
         Self {
             terminal,
@@ -168,9 +190,11 @@ impl TerminalView {
             can_navigate_to_selected_word: false,
             workspace_id,
             show_title: TerminalSettings::get_global(cx).toolbar.title,
+            pending_contents_serialization: Task::ready(()),
             _subscriptions: vec![
                 focus_in,
                 focus_out,
+                app_quit,
                 cx.observe_global::<SettingsStore>(Self::settings_changed),
             ],
             _terminal_subscriptions: terminal_subscriptions,
@@ -197,6 +221,7 @@ impl TerminalView {
     ) {
         let context_menu = ContextMenu::build(cx, |menu, _| {
             menu.action("Clear", Box::new(Clear))
+                .action("Copy Last Command Output", Box::new(CopyLastCommandOutput))
                 .action("Close", Box::new(CloseActiveItem { save_intent: None }))
         });
 
@@ -250,6 +275,28 @@ impl TerminalView {
         cx.notify();
     }
 
+    fn scroll_to_previous_prompt(
+        &mut self,
+        _: &ScrollToPreviousPrompt,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.terminal
+            .update(cx, |term, _| term.scroll_to_previous_prompt());
+        cx.notify();
+    }
+
+    fn scroll_to_next_prompt(&mut self, _: &ScrollToNextPrompt, cx: &mut ViewContext<Self>) {
+        self.terminal
+            .update(cx, |term, _| term.scroll_to_next_prompt());
+        cx.notify();
+    }
+
+    fn copy_last_command_output(&mut self, _: &CopyLastCommandOutput, cx: &mut ViewContext<Self>) {
+        self.terminal
+            .update(cx, |term, _| term.copy_last_command_output());
+        cx.notify();
+    }
+
     pub fn should_show_cursor(&self, focused: bool, cx: &mut gpui::ViewContext<Self>) -> bool {
         //Don't blink the cursor when not focused, blinking is disabled, or paused
         if !focused
@@ -305,6 +352,23 @@ impl TerminalView {
         &self.terminal
     }
 
+    /// Stores the terminal title and its last lines, to show them again when the terminal is restored.
+    fn serialize_contents(&self, item_id: EntityId, cx: &AppContext) -> Task<()> {
+        let terminal = self.terminal.read(cx);
+        if terminal.task().is_some() {
+            return Task::ready(());
+        }
+        let title = terminal.breadcrumb_text.clone();
+        let scrollback = terminal.scrollback_text(MAX_SERIALIZED_SCROLLBACK_LINES);
+        let workspace_id = self.workspace_id;
+        cx.background_executor().spawn(async move {
+            TERMINAL_DB
+                .save_contents(item_id.as_u64(), workspace_id, title, scrollback)
+                .await
+                .log_err();
+        })
+    }
+
     fn next_blink_epoch(&mut self) -> usize {
         self.blink_epoch += 1;
         self.blink_epoch
@@ -467,6 +531,17 @@ fn subscribe_for_terminal_events(
                             })
                             .detach();
                     }
+                    this.pending_contents_serialization = cx.spawn(|this, mut cx| async move {
+                        cx.background_executor()
+                            .timer(SERIALIZE_CONTENTS_DEBOUNCE)
+                            .await;
+                        let Ok(serialize_contents) = this.update(&mut cx, |this, cx| {
+                            this.serialize_contents(cx.entity_id(), cx)
+                        }) else {
+                            return;
+                        };
+                        serialize_contents.await;
+                    });
                 }
             }
 
@@ -589,6 +664,8 @@ fn subscribe_for_terminal_events(
             Event::BreadcrumbsChanged => cx.emit(ItemEvent::UpdateBreadcrumbs),
             Event::CloseTerminal => cx.emit(ItemEvent::CloseItem),
             Event::SelectionsChanged => cx.emit(SearchEvent::ActiveMatchChanged),
+            Event::TaskStatusChanged => cx.emit(ItemEvent::UpdateTab),
+            Event::ProblemsFound(_) | Event::ProblemsOutdated => {}
         });
     vec![terminal_subscription, terminal_events_subscription]
 }
@@ -741,6 +818,9 @@ impl Render for TerminalView {
             .on_action(cx.listener(TerminalView::copy))
             .on_action(cx.listener(TerminalView::paste))
             .on_action(cx.listener(TerminalView::clear))
+            .on_action(cx.listener(TerminalView::scroll_to_previous_prompt))
+            .on_action(cx.listener(TerminalView::scroll_to_next_prompt))
+            .on_action(cx.listener(TerminalView::copy_last_command_output))
             .on_action(cx.listener(TerminalView::show_character_palette))
             .on_action(cx.listener(TerminalView::select_all))
             .on_key_down(cx.listener(Self::key_down))
@@ -789,7 +869,16 @@ impl Item for TerminalView {
         let icon = match terminal.task() {
             Some(terminal_task) => match &terminal_task.status {
                 TaskStatus::Unknown => IconName::ExclamationTriangle,
-                TaskStatus::Running => IconName::Play,
+                TaskStatus::Running => match terminal_task
+                    .background
+                    .as_ref()
+                    .map(|background| background.status)
+                {
+                    Some(BackgroundTaskStatus::Starting | BackgroundTaskStatus::Rebuilding) => {
+                        IconName::ArrowCircle
+                    }
+                    Some(BackgroundTaskStatus::Ready) | None => IconName::Play,
+                },
                 TaskStatus::Completed { success } => {
                     if *success {
                         IconName::Check
@@ -894,9 +983,18 @@ impl Item for TerminalView {
                 })
                 .filter(|cwd| !cwd.as_os_str().is_empty());
 
+            let (title, scrollback) = TERMINAL_DB
+                .get_contents(item_id, workspace_id)
+                .log_err()
+                .flatten()
+                .unwrap_or_default();
+
             let terminal = project.update(&mut cx, |project, cx| {
-                project.create_terminal(cwd, None, window, cx)
+                project.create_terminal(cwd, None, scrollback, window, cx)
             })??;
+            if let Some(title) = title {
+                terminal.update(&mut cx, |terminal, _| terminal.breadcrumb_text = title)?;
+            }
             pane.update(&mut cx, |_, cx| {
                 cx.new_view(|cx| TerminalView::new(terminal, workspace, workspace_id, cx))
             })
@@ -929,6 +1027,9 @@ impl SearchableItem for TerminalView {
             case: false,
             word: false,
             regex: true,
+            multiline: false,
+            fancy_regex: false,
+            structural: false,
             replacement: false,
         }
     }
@@ -987,6 +1088,8 @@ impl SearchableItem for TerminalView {
                 .unwrap()),
             ),
             SearchQuery::Regex { .. } => regex_search_for_query(&query),
+            // The terminal has no syntax tree to search.
+            SearchQuery::Structural { .. } => None,
         };
 
         if let Some(s) = searcher {
@@ -0,0 +1,339 @@
+use anyhow::{anyhow, bail, Result};
+use std::{borrow::Cow, fmt};
+
+/// How many bytes are inspected when guessing the encoding of a file without a byte order mark.
+const DETECTION_SAMPLE_LEN: usize = 64 * 1024;
+
+/// The encodings that can be picked to reopen a file with, in the order they are listed.
+static SUPPORTED_ENCODINGS: &[&encoding_rs::Encoding] = &[
+    &encoding_rs::UTF_8_INIT,
+    &encoding_rs::UTF_16LE_INIT,
+    &encoding_rs::UTF_16BE_INIT,
+    &encoding_rs::WINDOWS_1252_INIT,
+    &encoding_rs::ISO_8859_15_INIT,
+    &encoding_rs::WINDOWS_1250_INIT,
+    &encoding_rs::ISO_8859_2_INIT,
+    &encoding_rs::WINDOWS_1251_INIT,
+    &encoding_rs::KOI8_R_INIT,
+    &encoding_rs::IBM866_INIT,
+    &encoding_rs::WINDOWS_1253_INIT,
+    &encoding_rs::WINDOWS_1254_INIT,
+    &encoding_rs::WINDOWS_1255_INIT,
+    &encoding_rs::WINDOWS_1256_INIT,
+    &encoding_rs::WINDOWS_1257_INIT,
+    &encoding_rs::WINDOWS_1258_INIT,
+    &encoding_rs::WINDOWS_874_INIT,
+    &encoding_rs::MACINTOSH_INIT,
+    &encoding_rs::SHIFT_JIS_INIT,
+    &encoding_rs::EUC_JP_INIT,
+    &encoding_rs::GBK_INIT,
+    &encoding_rs::GB18030_INIT,
+    &encoding_rs::BIG5_INIT,
+    &encoding_rs::EUC_KR_INIT,
+];
+
+/// The character encoding of a file on disk.
+///
+/// Buffers always hold UTF-8 text, files are decoded when loaded and encoded back when saved.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Encoding {
+    encoding: &'static encoding_rs::Encoding,
+    has_bom: bool,
+}
+
+impl Default for Encoding {
+    fn default() -> Self {
+        Self::utf8()
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.has_bom && self.encoding == encoding_rs::UTF_8 {
+            write!(f, "{} with BOM", self.encoding.name())
+        } else {
+            write!(f, "{}", self.encoding.name())
+        }
+    }
+}
+
+impl Encoding {
+    pub fn utf8() -> Self {
+        Self {
+            encoding: encoding_rs::UTF_8,
+            has_bom: false,
+        }
+    }
+
+    /// The encoding that text contents which are not valid in any other encoding fall back to, as it can decode any byte sequence.
+    pub fn windows_1252() -> Self {
+        Self {
+            encoding: encoding_rs::WINDOWS_1252,
+            has_bom: false,
+        }
+    }
+
+    /// Returns the encodings files can be reopened with.
+    pub fn all() -> Vec<Self> {
+        let mut encodings = Vec::with_capacity(SUPPORTED_ENCODINGS.len() + 1);
+        for &encoding in SUPPORTED_ENCODINGS {
+            let is_unicode = encoding == encoding_rs::UTF_8
+                || encoding == encoding_rs::UTF_16LE
+                || encoding == encoding_rs::UTF_16BE;
+            encodings.push(Self {
+                encoding,
+                has_bom: encoding != encoding_rs::UTF_8 && is_unicode,
+            });
+            if encoding == encoding_rs::UTF_8 {
+                encodings.push(Self {
+                    encoding,
+                    has_bom: true,
+                });
+            }
+        }
+        encodings
+    }
+
+    /// Parses an encoding from its name, as displayed, or from any of its [WHATWG labels](https://encoding.spec.whatwg.org/#names-and-labels).
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim();
+        let (name, has_bom) = match name.strip_suffix(" with BOM") {
+            Some(name) => (name, true),
+            None => (name, false),
+        };
+        let encoding = encoding_rs::Encoding::for_label(name.as_bytes())?;
+        if !SUPPORTED_ENCODINGS.contains(&encoding) {
+            return None;
+        }
+        Some(Self {
+            encoding,
+            has_bom: has_bom
+                || encoding == encoding_rs::UTF_16LE
+                || encoding == encoding_rs::UTF_16BE,
+        })
+    }
+
+    /// Guesses the encoding of the given file contents.
+    ///
+    /// A byte order mark always wins. Otherwise, contents with many NUL bytes at every other position are considered UTF-16,
+    /// valid UTF-8 is kept as such, Japanese text is recognized as Shift_JIS and anything else falls back to Windows-1252,
+    /// which can decode any byte sequence, unless the contents look binary, which fails.
+    pub fn detect(bytes: &[u8]) -> Result<Self> {
+        Self::detect_internal(bytes, true)
+    }
+
+    /// Guesses the encoding of a file from its first bytes, for files that are too large to be inspected as a whole.
+    pub fn detect_from_start(bytes: &[u8]) -> Result<Self> {
+        Self::detect_internal(bytes, false)
+    }
+
+    fn detect_internal(bytes: &[u8], is_complete: bool) -> Result<Self> {
+        if let Some((encoding, _)) = encoding_rs::Encoding::for_bom(bytes) {
+            return Ok(Self {
+                encoding,
+                has_bom: true,
+            });
+        }
+
+        let sample = &bytes[..bytes.len().min(DETECTION_SAMPLE_LEN)];
+        if let Some(encoding) = detect_utf16(sample) {
+            return Ok(Self {
+                encoding,
+                has_bom: false,
+            });
+        }
+
+        let is_utf8 = match std::str::from_utf8(bytes) {
+            Ok(_) => true,
+            // Incomplete contents may end in the middle of a character.
+            Err(error) => !is_complete && error.error_len().is_none(),
+        };
+        let encoding = if is_utf8 {
+            encoding_rs::UTF_8
+        } else if is_shift_jis(sample, is_complete && sample.len() == bytes.len()) {
+            encoding_rs::SHIFT_JIS
+        } else {
+            Self::reject_binary(sample)?;
+            encoding_rs::WINDOWS_1252
+        };
+        Ok(Self {
+            encoding,
+            has_bom: false,
+        })
+    }
+
+    /// Fails for contents that look like binary data rather than text, so that they are not opened as Windows-1252:
+    /// those with a NUL byte, or with control characters other than whitespace in more than one byte out of twenty.
+    pub fn reject_binary(bytes: &[u8]) -> Result<()> {
+        let mut control_characters = 0;
+        for &byte in bytes {
+            match byte {
+                0 => bail!("binary contents cannot be opened as text"),
+                b'\t' | b'\n' | b'\r' | b'\x0C' | b'\x1B' => {}
+                0x01..=0x1F | 0x7F => control_characters += 1,
+                _ => {}
+            }
+        }
+        if control_characters * 20 > bytes.len() {
+            bail!("binary contents cannot be opened as text");
+        }
+        Ok(())
+    }
+
+    /// Returns the canonical name of the encoding, without mentioning its byte order mark.
+    pub fn name(&self) -> &'static str {
+        self.encoding.name()
+    }
+
+    pub fn has_bom(&self) -> bool {
+        self.has_bom
+    }
+
+    pub fn is_utf8(&self) -> bool {
+        self.encoding == encoding_rs::UTF_8
+    }
+
+    /// Returns the byte order mark written at the start of the file, if any.
+    pub fn bom(&self) -> &'static [u8] {
+        if !self.has_bom {
+            &[]
+        } else if self.encoding == encoding_rs::UTF_8 {
+            b"\xEF\xBB\xBF"
+        } else if self.encoding == encoding_rs::UTF_16LE {
+            b"\xFF\xFE"
+        } else if self.encoding == encoding_rs::UTF_16BE {
+            b"\xFE\xFF"
+        } else {
+            &[]
+        }
+    }
+
+    /// Decodes file contents, failing if they are not valid in this encoding.
+    pub fn decode(&self, mut bytes: Vec<u8>) -> Result<String> {
+        let bom = self.bom();
+        if !bom.is_empty() && bytes.starts_with(bom) {
+            bytes.drain(..bom.len());
+        }
+
+        if self.is_utf8() {
+            Ok(String::from_utf8(bytes)?)
+        } else {
+            self.encoding
+                .decode_without_bom_handling_and_without_replacement(&bytes)
+                .map(Cow::into_owned)
+                .ok_or_else(|| anyhow!("contents are not valid {}", self.name()))
+        }
+    }
+
+    /// Returns a decoder for contents that are read piece by piece.
+    pub fn decoder(&self) -> Decoder {
+        Decoder {
+            encoding: *self,
+            decoder: self.encoding.new_decoder_with_bom_removal(),
+        }
+    }
+
+    /// Encodes a piece of text, failing if it contains characters that this encoding cannot represent.
+    /// The byte order mark is not included.
+    pub fn encode<'a>(&self, text: &'a str) -> Result<Cow<'a, [u8]>> {
+        if self.is_utf8() {
+            Ok(Cow::Borrowed(text.as_bytes()))
+        } else if self.encoding == encoding_rs::UTF_16LE {
+            Ok(Cow::Owned(
+                text.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+            ))
+        } else if self.encoding == encoding_rs::UTF_16BE {
+            Ok(Cow::Owned(
+                text.encode_utf16().flat_map(u16::to_be_bytes).collect(),
+            ))
+        } else {
+            let (bytes, _, has_unmappable_characters) = self.encoding.encode(text);
+            if has_unmappable_characters {
+                let character = text
+                    .chars()
+                    .find(|c| {
+                        let mut buf = [0; 4];
+                        self.encoding.encode(c.encode_utf8(&mut buf)).2
+                    })
+                    .unwrap_or(char::REPLACEMENT_CHARACTER);
+                Err(anyhow!(
+                    "{character:?} cannot be saved as {}, reopen the file with another encoding",
+                    self.name()
+                ))
+            } else {
+                Ok(bytes)
+            }
+        }
+    }
+}
+
+/// Decodes file contents piece by piece, keeping the incomplete characters at the end of a piece for the next one.
+pub struct Decoder {
+    encoding: Encoding,
+    decoder: encoding_rs::Decoder,
+}
+
+impl Decoder {
+    /// Decodes the next piece of the contents, `is_last` tells whether no more contents follow.
+    pub fn decode(&mut self, bytes: &[u8], is_last: bool) -> Result<String> {
+        let capacity = self
+            .decoder
+            .max_utf8_buffer_length_without_replacement(bytes.len())
+            .ok_or_else(|| anyhow!("contents are too large"))?;
+        let mut text = String::with_capacity(capacity);
+        let (result, _) = self
+            .decoder
+            .decode_to_string_without_replacement(bytes, &mut text, is_last);
+        match result {
+            encoding_rs::DecoderResult::InputEmpty => Ok(text),
+            encoding_rs::DecoderResult::OutputFull
+            | encoding_rs::DecoderResult::Malformed(_, _) => {
+                Err(anyhow!("contents are not valid {}", self.encoding.name()))
+            }
+        }
+    }
+}
+
+fn detect_utf16(sample: &[u8]) -> Option<&'static encoding_rs::Encoding> {
+    let pairs = sample.len() / 2;
+    if pairs == 0 {
+        return None;
+    }
+    let (mut even_nuls, mut odd_nuls) = (0, 0);
+    for pair in sample.chunks_exact(2) {
+        if pair[0] == 0 {
+            even_nuls += 1;
+        }
+        if pair[1] == 0 {
+            odd_nuls += 1;
+        }
+    }
+
+    // Text with mostly ASCII characters has a NUL byte in most of its code units, always on the same side.
+    let is_mostly_nul = |count: usize| count * 10 >= pairs * 3;
+    let is_almost_never_nul = |count: usize| count * 20 <= pairs;
+    if is_mostly_nul(odd_nuls) && is_almost_never_nul(even_nuls) {
+        Some(encoding_rs::UTF_16LE)
+    } else if is_mostly_nul(even_nuls) && is_almost_never_nul(odd_nuls) {
+        Some(encoding_rs::UTF_16BE)
+    } else {
+        None
+    }
+}
+
+fn is_shift_jis(sample: &[u8], is_complete: bool) -> bool {
+    let mut decoder = encoding_rs::SHIFT_JIS.new_decoder_without_bom_handling();
+    let Some(capacity) = decoder.max_utf8_buffer_length_without_replacement(sample.len()) else {
+        return false;
+    };
+    let mut text = String::with_capacity(capacity);
+    // An incomplete sample may end in the middle of a two byte character.
+    let (result, _) = decoder.decode_to_string_without_replacement(sample, &mut text, is_complete);
+    if matches!(result, encoding_rs::DecoderResult::Malformed(_, _)) {
+        return false;
+    }
+
+    // Single byte katakana overlap with accented latin letters, but any Japanese text has some two byte kana.
+    text.chars()
+        .any(|c| matches!(c, '\u{3041}'..='\u{3096}' | '\u{30A1}'..='\u{30FA}'))
+}
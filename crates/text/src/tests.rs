@@ -181,6 +181,57 @@ fn test_line_endings() {
     buffer.check_invariants();
 }
 
+#[test]
+fn test_encodings() {
+    fn detect_and_decode(bytes: &[u8]) -> (String, String) {
+        let encoding = Encoding::detect(bytes).unwrap();
+        let text = encoding.decode(bytes.to_vec()).unwrap();
+        let mut encoded = encoding.bom().to_vec();
+        encoded.extend_from_slice(&encoding.encode(&text).unwrap());
+        assert_eq!(encoded, bytes, "{encoding} does not round-trip");
+        (encoding.to_string(), text)
+    }
+
+    assert_eq!(
+        detect_and_decode("🍐 café".as_bytes()),
+        ("UTF-8".into(), "🍐 café".into())
+    );
+    assert_eq!(
+        detect_and_decode(b"\xEF\xBB\xBFcaf\xC3\xA9"),
+        ("UTF-8 with BOM".into(), "café".into())
+    );
+    assert_eq!(
+        detect_and_decode(b"caf\xE9 \x80"),
+        ("windows-1252".into(), "café €".into())
+    );
+    assert_eq!(
+        detect_and_decode(b"\x82\xB1\x82\xF1\x82\xC9\x82\xBF\x82\xCD"),
+        ("Shift_JIS".into(), "こんにちは".into())
+    );
+    assert_eq!(
+        detect_and_decode(b"\xFF\xFEa\x00\xE9\x00"),
+        ("UTF-16LE".into(), "aé".into())
+    );
+    assert_eq!(
+        detect_and_decode(b"\x00a\x00b\x00\n"),
+        ("UTF-16BE".into(), "ab\n".into())
+    );
+
+    // Binary contents are not valid UTF-8 either, but are not opened as Windows-1252 text.
+    assert!(Encoding::detect(b"\x7FELF\x02\x01\x01\x00\x00\xE9").is_err());
+    assert!(Encoding::detect(b"\x89PNG\r\n\x1A\n\x01\x02\x03\xFF\x10\x11").is_err());
+    assert!(Encoding::detect(b"caf\xE9\x1B[0m\r\n").is_ok());
+
+    let windows_1252 = Encoding::from_name("latin1").unwrap();
+    assert_eq!(windows_1252.to_string(), "windows-1252");
+    assert!(windows_1252.encode("naïve 🍐").is_err());
+    assert!(Encoding::utf8().decode(b"caf\xE9".to_vec()).is_err());
+    assert_eq!(
+        Encoding::from_name("UTF-8 with BOM"),
+        Encoding::all().get(1).copied()
+    );
+}
+
 #[test]
 fn test_line_len() {
     let mut buffer = Buffer::new(0, BufferId::new(1).unwrap(), "".into());
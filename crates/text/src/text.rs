@@ -1,4 +1,5 @@
 mod anchor;
+mod encoding;
 pub mod locator;
 #[cfg(any(test, feature = "test-support"))]
 pub mod network;
@@ -14,6 +15,7 @@ pub use anchor::*;
 use anyhow::{anyhow, Context as _, Result};
 pub use clock::ReplicaId;
 use collections::{HashMap, HashSet};
+pub use encoding::{Decoder, Encoding};
 use locator::Locator;
 use operation_queue::OperationQueue;
 pub use patch::Patch;
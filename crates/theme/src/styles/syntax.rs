@@ -1,4 +1,4 @@
-use gpui::{HighlightStyle, Hsla};
+use gpui::{HighlightStyle, Hsla, StrikethroughStyle};
 
 use crate::{
     blue, cyan, gold, indigo, iris, jade, lime, mint, neutral, orange, plum, purple, red, sky,
@@ -155,4 +155,127 @@ impl SyntaxTheme {
     pub fn color(&self, name: &str) -> Hsla {
         self.get(name).color.unwrap_or_default()
     }
+
+    /// Returns the style of the most specific highlight whose dot-separated components
+    /// all appear in `name`, the same way tree-sitter capture names are resolved.
+    pub fn style_for_name(&self, name: &str) -> Option<HighlightStyle> {
+        self.highlights
+            .iter()
+            .filter_map(|(key, style)| {
+                let mut len = 0;
+                for key_part in key.split('.') {
+                    if name.split('.').any(|part| part == key_part) {
+                        len += 1;
+                    } else {
+                        return None;
+                    }
+                }
+                Some((style, len))
+            })
+            .max_by_key(|(_, len)| *len)
+            .map(|(style, _)| *style)
+    }
+
+    /// Returns the style for a language server's semantic token, or `None` if the
+    /// token should leave the tree-sitter highlighting untouched.
+    ///
+    /// LSP token types and modifiers are mapped onto the highlight names used
+    /// by `highlights.scm` queries, so themes don't need dedicated entries for them.
+    pub fn semantic_token_style(
+        &self,
+        token_type: &str,
+        token_modifiers: &[impl AsRef<str>],
+    ) -> Option<HighlightStyle> {
+        let has_modifier = |modifier: &str| token_modifiers.iter().any(|m| m.as_ref() == modifier);
+
+        let name = match token_type {
+            "namespace" => Some("namespace"),
+            "type" | "class" | "struct" | "typeParameter" => Some("type"),
+            "builtinType" => Some("type.builtin"),
+            "enum" => Some("enum"),
+            "interface" => Some("type.interface"),
+            "parameter" => Some("variable.parameter"),
+            "variable" if has_modifier("readonly") && has_modifier("static") => Some("constant"),
+            "variable" => Some("variable"),
+            "selfKeyword" | "selfTypeKeyword" => Some("variable.special"),
+            "property" | "event" => Some("property"),
+            "enumMember" => Some("variant"),
+            "function" => Some("function"),
+            "method" => Some("function.method"),
+            "macro" => Some("function.special"),
+            "keyword" | "modifier" => Some("keyword"),
+            "comment" if has_modifier("documentation") => Some("comment.doc"),
+            "comment" => Some("comment"),
+            "string" => Some("string"),
+            "number" => Some("number"),
+            "regexp" => Some("string.regex"),
+            "operator" => Some("operator"),
+            "decorator" | "attribute" => Some("attribute"),
+            "lifetime" => Some("lifetime"),
+            "label" => Some("label"),
+            _ => None,
+        };
+
+        let mut style = name.and_then(|name| {
+            if has_modifier("defaultLibrary") {
+                self.style_for_name(&format!("{name}.builtin"))
+            } else {
+                self.style_for_name(name)
+            }
+        });
+
+        if has_modifier("deprecated") {
+            style
+                .get_or_insert_with(HighlightStyle::default)
+                .strikethrough = Some(StrikethroughStyle {
+                thickness: 1.0.into(),
+                ..Default::default()
+            });
+        }
+
+        style
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gpui::rgba;
+
+    use super::*;
+
+    #[test]
+    fn test_semantic_token_style() {
+        let type_color: Hsla = rgba(0xaaaaaaff).into();
+        let builtin_type_color: Hsla = rgba(0xbbbbbbff).into();
+        let function_color: Hsla = rgba(0xccccccff).into();
+        let macro_color: Hsla = rgba(0xddddddff).into();
+        let theme = SyntaxTheme::new_test([
+            ("type", type_color),
+            ("type.builtin", builtin_type_color),
+            ("function", function_color),
+            ("function.special", macro_color),
+        ]);
+        let no_modifiers: &[&str] = &[];
+        let color = |token_type: &str, token_modifiers: &[&str]| {
+            theme
+                .semantic_token_style(token_type, token_modifiers)
+                .and_then(|style| style.color)
+        };
+
+        assert_eq!(color("struct", no_modifiers), Some(type_color));
+        assert_eq!(color("type", &["defaultLibrary"]), Some(builtin_type_color));
+        assert_eq!(
+            color("method", no_modifiers),
+            Some(function_color),
+            "falls back to the less specific highlight"
+        );
+        assert_eq!(color("macro", no_modifiers), Some(macro_color));
+        assert_eq!(theme.semantic_token_style("unknown", no_modifiers), None);
+
+        let deprecated = theme
+            .semantic_token_style("variable", &["deprecated"])
+            .unwrap();
+        assert_eq!(deprecated.color, None);
+        assert!(deprecated.strikethrough.is_some());
+    }
 }
@@ -90,6 +90,7 @@ pub enum IconName {
     BellRing,
     BellDot,
     Bolt,
+    Bookmark,
     CaseSensitive,
     Check,
     ChevronDown,
@@ -203,6 +204,7 @@ impl IconName {
             IconName::BellRing => "icons/bell_ring.svg",
             IconName::BellDot => "icons/bell_dot.svg",
             IconName::Bolt => "icons/bolt.svg",
+            IconName::Bookmark => "icons/bookmark.svg",
             IconName::CaseSensitive => "icons/case_insensitive.svg",
             IconName::Check => "icons/check.svg",
             IconName::ChevronDown => "icons/chevron_down.svg",
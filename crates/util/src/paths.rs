@@ -30,6 +30,7 @@ lazy_static::lazy_static! {
         SUPPORT_DIR.join("embeddings")
     };
     pub static ref THEMES_DIR: PathBuf = CONFIG_DIR.join("themes");
+    pub static ref SNIPPETS_DIR: PathBuf = CONFIG_DIR.join("snippets");
 
     pub static ref SUPPORT_DIR: PathBuf = if cfg!(target_os = "macos") {
         HOME.join("Library/Application Support/Zed")
@@ -54,6 +55,7 @@ lazy_static::lazy_static! {
     pub static ref COPILOT_DIR: PathBuf = SUPPORT_DIR.join("copilot");
     pub static ref SUPERMAVEN_DIR: PathBuf = SUPPORT_DIR.join("supermaven");
     pub static ref DEFAULT_PRETTIER_DIR: PathBuf = SUPPORT_DIR.join("prettier");
+    pub static ref SHELL_INTEGRATION_DIR: PathBuf = SUPPORT_DIR.join("shell_integration");
     pub static ref DB_DIR: PathBuf = SUPPORT_DIR.join("db");
     pub static ref CRASHES_DIR: Option<PathBuf> = cfg!(target_os = "macos")
         .then_some(HOME.join("Library/Logs/DiagnosticReports"));
@@ -1,16 +1,24 @@
+use std::{borrow::Cow, ops::RangeInclusive, path::PathBuf};
+
+use anyhow::{anyhow, bail, Result};
 use command_palette_hooks::CommandInterceptResult;
-use editor::actions::{SortLinesCaseInsensitive, SortLinesCaseSensitive};
-use gpui::{impl_actions, Action, AppContext, ViewContext};
+use editor::{
+    actions::{SortLinesCaseInsensitive, SortLinesCaseSensitive},
+    scroll::Autoscroll,
+    Anchor, Editor,
+};
+use gpui::{impl_actions, Action, AppContext, Keystroke, Modifiers, ViewContext, WindowContext};
+use language::Point;
+use multi_buffer::{MultiBufferRow, MultiBufferSnapshot, ToPoint};
+use project::search::{SearchQuery, SearchRegex};
+use search::{BufferSearchBar, SearchOptions};
 use serde_derive::Deserialize;
+use util::{paths::HOME, ResultExt};
 use workspace::{SaveIntent, Workspace};
 
 use crate::{
-    motion::{EndOfDocument, Motion, StartOfDocument},
-    normal::{
-        move_cursor,
-        search::{range_regex, FindCommand, ReplaceCommand},
-        JoinLines,
-    },
+    motion::Motion,
+    normal::{delete::delete_motion, move_cursor, search::FindCommand, JoinLines},
     state::Mode,
     Vim,
 };
@@ -20,7 +28,80 @@ pub struct GoToLine {
     pub line: u32,
 }
 
-impl_actions!(vim, [GoToLine]);
+/// Runs an ex command on a range of lines, like `:3,5d` or `:%s/a/b/g`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RangeCommand {
+    pub range: Option<CommandRange>,
+    pub command: LineCommand,
+}
+
+/// Opens the file at the given path, relative to the worktree of the active item.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct EditFile {
+    pub path: String,
+}
+
+impl_actions!(vim, [GoToLine, RangeCommand, EditFile]);
+
+/// A range of lines, as in `:'<,'>` or `:.,+3`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct CommandRange {
+    pub start: Position,
+    pub end: Option<Position>,
+}
+
+/// A line address, followed by an offset (`$-1`, `/foo/+2`).
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Position {
+    pub address: Address,
+    pub offset: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub enum Address {
+    /// A line number, starting at 1. Line 0 is the (virtual) line above the first one.
+    Line(u32),
+    CurrentLine,
+    LastLine,
+    Mark(char),
+    Search {
+        pattern: String,
+        backwards: bool,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub enum LineCommand {
+    GoTo,
+    Delete {
+        register: Option<char>,
+        count: Option<u32>,
+    },
+    Move {
+        destination: Position,
+    },
+    Copy {
+        destination: Position,
+    },
+    Substitute(Substitution),
+    Global {
+        pattern: String,
+        invert: bool,
+        command: Box<RangeCommand>,
+    },
+    Normal {
+        keystrokes: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Substitution {
+    pub pattern: String,
+    pub replacement: String,
+    /// Replace all matches of each line, rather than only the first one.
+    pub global: bool,
+    pub case_sensitive: bool,
+}
 
 pub fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
     workspace.register_action(|_: &mut Workspace, action: &GoToLine, cx| {
@@ -29,339 +110,477 @@ pub fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
             move_cursor(vim, Motion::StartOfDocument, Some(action.line as usize), cx);
         });
     });
+
+    workspace.register_action(|workspace: &mut Workspace, action: &RangeCommand, cx| {
+        let last_search = workspace
+            .active_pane()
+            .read(cx)
+            .toolbar()
+            .read(cx)
+            .item_of_type::<BufferSearchBar>()
+            .map(|search_bar| {
+                let search_bar = search_bar.read(cx);
+                LastSearch {
+                    query: search_bar.query(cx),
+                    fancy_regex: search_bar
+                        .search_options()
+                        .contains(SearchOptions::FANCY_REGEX),
+                }
+            })
+            .unwrap_or_default();
+        let action = action.clone();
+        // `:normal` dispatches keystrokes, which cannot be done while the workspace is being updated.
+        cx.window_context().defer(move |cx| {
+            action.run(&last_search, cx).log_err();
+        });
+    });
+
+    workspace.register_action(|workspace: &mut Workspace, action: &EditFile, cx| {
+        let path = match action.path.strip_prefix("~/") {
+            Some(path) => HOME.join(path),
+            None => PathBuf::from(&action.path),
+        };
+        let abs_path = if path.is_absolute() {
+            path
+        } else {
+            let project = workspace.project().read(cx);
+            let Some(worktree) = workspace
+                .active_item(cx)
+                .and_then(|item| item.project_path(cx))
+                .and_then(|project_path| project.worktree_for_id(project_path.worktree_id, cx))
+                .or_else(|| project.worktrees().next())
+            else {
+                return;
+            };
+            worktree.read(cx).abs_path().join(path)
+        };
+        workspace
+            .open_abs_path(abs_path, false, cx)
+            .detach_and_log_err(cx);
+    });
+}
+
+/// A command of the vim command line, like `:w[rite][!]`.
+struct VimCommand {
+    prefix: &'static str,
+    suffix: &'static str,
+    action: Option<Box<dyn Action>>,
+    bang_action: Option<Box<dyn Action>>,
+    /// Builds the action for commands that take a range or arguments.
+    args: Option<fn(Option<CommandRange>, bool, &str, &AppContext) -> Option<Box<dyn Action>>>,
 }
 
-pub fn command_interceptor(mut query: &str, cx: &AppContext) -> Option<CommandInterceptResult> {
-    // Note: this is a very poor simulation of vim's command palette.
-    // In the future we should adjust it to handle parsing range syntax,
-    // and then calling the appropriate commands with/without ranges.
-    //
-    // We also need to support passing arguments to commands like :w
-    // (ideally with filename autocompletion).
-    //
-    // For now, you can only do a replace on the % range, and you can
-    // only use a specific line number range to "go to line"
-    while query.starts_with(':') {
-        query = &query[1..];
+impl VimCommand {
+    fn new(pattern: (&'static str, &'static str), action: impl Action) -> Self {
+        Self {
+            prefix: pattern.0,
+            suffix: pattern.1,
+            action: Some(action.boxed_clone()),
+            bang_action: None,
+            args: None,
+        }
+    }
+
+    /// A command running an action that might not be registered, like those of the panels.
+    fn str(pattern: (&'static str, &'static str), action_name: &str, cx: &AppContext) -> Self {
+        Self {
+            prefix: pattern.0,
+            suffix: pattern.1,
+            action: cx.build_action(action_name, None).ok(),
+            bang_action: None,
+            args: None,
+        }
+    }
+
+    fn args(
+        pattern: (&'static str, &'static str),
+        args: fn(Option<CommandRange>, bool, &str, &AppContext) -> Option<Box<dyn Action>>,
+    ) -> Self {
+        Self {
+            prefix: pattern.0,
+            suffix: pattern.1,
+            action: None,
+            bang_action: None,
+            args: Some(args),
+        }
+    }
+
+    fn bang(mut self, bang_action: impl Action) -> Self {
+        self.bang_action = Some(bang_action.boxed_clone());
+        self
+    }
+
+    fn name(&self) -> String {
+        format!("{}{}", self.prefix, self.suffix)
+    }
+
+    /// Whether `name` is a valid abbreviation of this command.
+    fn matches(&self, name: &str) -> bool {
+        name.strip_prefix(self.prefix)
+            .map_or(false, |rest| self.suffix.starts_with(rest))
+    }
+
+    fn action(
+        &self,
+        range: Option<CommandRange>,
+        bang: bool,
+        args: &str,
+        cx: &AppContext,
+    ) -> Option<Box<dyn Action>> {
+        if let Some(parse_args) = self.args {
+            return parse_args(range, bang, args, cx);
+        }
+        if range.is_some() || !args.is_empty() {
+            return None;
+        }
+        if bang {
+            self.bang_action.as_ref().map(|action| action.boxed_clone())
+        } else {
+            self.action.as_ref().map(|action| action.boxed_clone())
+        }
     }
+}
 
-    let (name, action) = match query {
+fn commands(cx: &AppContext) -> Vec<VimCommand> {
+    vec![
         // save and quit
-        "w" | "wr" | "wri" | "writ" | "write" => (
-            "write",
+        VimCommand::new(
+            ("w", "rite"),
             workspace::Save {
                 save_intent: Some(SaveIntent::Save),
-            }
-            .boxed_clone(),
-        ),
-        "w!" | "wr!" | "wri!" | "writ!" | "write!" => (
-            "write!",
-            workspace::Save {
-                save_intent: Some(SaveIntent::Overwrite),
-            }
-            .boxed_clone(),
-        ),
-        "q" | "qu" | "qui" | "quit" => (
-            "quit",
+            },
+        )
+        .bang(workspace::Save {
+            save_intent: Some(SaveIntent::Overwrite),
+        }),
+        VimCommand::new(
+            ("q", "uit"),
             workspace::CloseActiveItem {
                 save_intent: Some(SaveIntent::Close),
-            }
-            .boxed_clone(),
-        ),
-        "q!" | "qu!" | "qui!" | "quit!" => (
-            "quit!",
-            workspace::CloseActiveItem {
-                save_intent: Some(SaveIntent::Skip),
-            }
-            .boxed_clone(),
-        ),
-        "wq" => (
-            "wq",
+            },
+        )
+        .bang(workspace::CloseActiveItem {
+            save_intent: Some(SaveIntent::Skip),
+        }),
+        VimCommand::new(
+            ("wq", ""),
             workspace::CloseActiveItem {
                 save_intent: Some(SaveIntent::Save),
-            }
-            .boxed_clone(),
-        ),
-        "wq!" => (
-            "wq!",
-            workspace::CloseActiveItem {
-                save_intent: Some(SaveIntent::Overwrite),
-            }
-            .boxed_clone(),
-        ),
-        "x" | "xi" | "xit" | "exi" | "exit" => (
-            "exit",
+            },
+        )
+        .bang(workspace::CloseActiveItem {
+            save_intent: Some(SaveIntent::Overwrite),
+        }),
+        VimCommand::new(
+            ("x", "it"),
             workspace::CloseActiveItem {
                 save_intent: Some(SaveIntent::SaveAll),
-            }
-            .boxed_clone(),
-        ),
-        "x!" | "xi!" | "xit!" | "exi!" | "exit!" => (
-            "exit!",
+            },
+        )
+        .bang(workspace::CloseActiveItem {
+            save_intent: Some(SaveIntent::Overwrite),
+        }),
+        VimCommand::new(
+            ("exi", "t"),
             workspace::CloseActiveItem {
-                save_intent: Some(SaveIntent::Overwrite),
-            }
-            .boxed_clone(),
-        ),
-        "up" | "upd" | "upda" | "updat" | "update" => (
-            "update",
+                save_intent: Some(SaveIntent::SaveAll),
+            },
+        )
+        .bang(workspace::CloseActiveItem {
+            save_intent: Some(SaveIntent::Overwrite),
+        }),
+        VimCommand::new(
+            ("up", "date"),
             workspace::Save {
                 save_intent: Some(SaveIntent::SaveAll),
-            }
-            .boxed_clone(),
+            },
         ),
-        "wa" | "wal" | "wall" => (
-            "wall",
+        VimCommand::new(
+            ("wa", "ll"),
             workspace::SaveAll {
                 save_intent: Some(SaveIntent::SaveAll),
-            }
-            .boxed_clone(),
-        ),
-        "wa!" | "wal!" | "wall!" => (
-            "wall!",
-            workspace::SaveAll {
-                save_intent: Some(SaveIntent::Overwrite),
-            }
-            .boxed_clone(),
-        ),
-        "qa" | "qal" | "qall" | "quita" | "quital" | "quitall" => (
-            "quitall",
+            },
+        )
+        .bang(workspace::SaveAll {
+            save_intent: Some(SaveIntent::Overwrite),
+        }),
+        VimCommand::new(
+            ("qa", "ll"),
             workspace::CloseAllItemsAndPanes {
                 save_intent: Some(SaveIntent::Close),
-            }
-            .boxed_clone(),
-        ),
-        "qa!" | "qal!" | "qall!" | "quita!" | "quital!" | "quitall!" => (
-            "quitall!",
+            },
+        )
+        .bang(workspace::CloseAllItemsAndPanes {
+            save_intent: Some(SaveIntent::Skip),
+        }),
+        VimCommand::new(
+            ("quita", "ll"),
             workspace::CloseAllItemsAndPanes {
-                save_intent: Some(SaveIntent::Skip),
-            }
-            .boxed_clone(),
-        ),
-        "xa" | "xal" | "xall" => (
-            "xall",
+                save_intent: Some(SaveIntent::Close),
+            },
+        )
+        .bang(workspace::CloseAllItemsAndPanes {
+            save_intent: Some(SaveIntent::Skip),
+        }),
+        VimCommand::new(
+            ("xa", "ll"),
             workspace::CloseAllItemsAndPanes {
                 save_intent: Some(SaveIntent::SaveAll),
-            }
-            .boxed_clone(),
-        ),
-        "xa!" | "xal!" | "xall!" => (
-            "xall!",
-            workspace::CloseAllItemsAndPanes {
-                save_intent: Some(SaveIntent::Overwrite),
-            }
-            .boxed_clone(),
-        ),
-        "wqa" | "wqal" | "wqall" => (
-            "wqall",
+            },
+        )
+        .bang(workspace::CloseAllItemsAndPanes {
+            save_intent: Some(SaveIntent::Overwrite),
+        }),
+        VimCommand::new(
+            ("wqa", "ll"),
             workspace::CloseAllItemsAndPanes {
                 save_intent: Some(SaveIntent::SaveAll),
+            },
+        )
+        .bang(workspace::CloseAllItemsAndPanes {
+            save_intent: Some(SaveIntent::Overwrite),
+        }),
+        VimCommand::new(("cq", "uit"), zed_actions::Quit).bang(zed_actions::Quit),
+        VimCommand::args(("e", "dit"), |range, bang, args, _| {
+            if range.is_some() || bang || args.is_empty() {
+                return None;
             }
-            .boxed_clone(),
-        ),
-        "wqa!" | "wqal!" | "wqall!" => (
-            "wqall!",
-            workspace::CloseAllItemsAndPanes {
-                save_intent: Some(SaveIntent::Overwrite),
-            }
-            .boxed_clone(),
-        ),
-        "cq" | "cqu" | "cqui" | "cquit" | "cq!" | "cqu!" | "cqui!" | "cquit!" => {
-            ("cquit!", zed_actions::Quit.boxed_clone())
-        }
-
+            Some(
+                EditFile {
+                    path: args.trim_end().to_string(),
+                }
+                .boxed_clone(),
+            )
+        }),
         // pane management
-        "sp" | "spl" | "spli" | "split" => ("split", workspace::SplitUp.boxed_clone()),
-        "vs" | "vsp" | "vspl" | "vspli" | "vsplit" => {
-            ("vsplit", workspace::SplitLeft.boxed_clone())
-        }
-        "new" => (
-            "new",
-            workspace::NewFileInDirection(workspace::SplitDirection::Up).boxed_clone(),
+        VimCommand::new(("sp", "lit"), workspace::SplitUp),
+        VimCommand::new(("vs", "plit"), workspace::SplitLeft),
+        VimCommand::new(
+            ("new", ""),
+            workspace::NewFileInDirection(workspace::SplitDirection::Up),
         ),
-        "vne" | "vnew" => (
-            "vnew",
-            workspace::NewFileInDirection(workspace::SplitDirection::Left).boxed_clone(),
+        VimCommand::new(
+            ("vne", "w"),
+            workspace::NewFileInDirection(workspace::SplitDirection::Left),
         ),
-        "tabe" | "tabed" | "tabedi" | "tabedit" => ("tabedit", workspace::NewFile.boxed_clone()),
-        "tabnew" => ("tabnew", workspace::NewFile.boxed_clone()),
-
-        "tabn" | "tabne" | "tabnex" | "tabnext" => {
-            ("tabnext", workspace::ActivateNextItem.boxed_clone())
-        }
-        "tabp" | "tabpr" | "tabpre" | "tabprev" | "tabprevi" | "tabprevio" | "tabpreviou"
-        | "tabprevious" => ("tabprevious", workspace::ActivatePrevItem.boxed_clone()),
-        "tabN" | "tabNe" | "tabNex" | "tabNext" => {
-            ("tabNext", workspace::ActivatePrevItem.boxed_clone())
-        }
-        "tabc" | "tabcl" | "tabclo" | "tabclos" | "tabclose" => (
-            "tabclose",
+        VimCommand::new(("tabe", "dit"), workspace::NewFile),
+        VimCommand::new(("tabnew", ""), workspace::NewFile),
+        VimCommand::new(("tabn", "ext"), workspace::ActivateNextItem),
+        VimCommand::new(("tabp", "revious"), workspace::ActivatePrevItem),
+        VimCommand::new(("tabN", "ext"), workspace::ActivatePrevItem),
+        VimCommand::new(
+            ("tabc", "lose"),
             workspace::CloseActiveItem {
                 save_intent: Some(SaveIntent::Close),
-            }
-            .boxed_clone(),
+            },
         ),
-        "tabo" | "tabon" | "tabonl" | "tabonly" => (
-            "tabonly",
+        VimCommand::new(
+            ("tabo", "nly"),
             workspace::CloseInactiveItems {
                 save_intent: Some(SaveIntent::Close),
-            }
-            .boxed_clone(),
-        ),
-        "tabo!" | "tabon!" | "tabonl!" | "tabonly!" => (
-            "tabonly!",
-            workspace::CloseInactiveItems {
-                save_intent: Some(SaveIntent::Skip),
-            }
-            .boxed_clone(),
-        ),
-        "on" | "onl" | "only" => (
-            "only",
+            },
+        )
+        .bang(workspace::CloseInactiveItems {
+            save_intent: Some(SaveIntent::Skip),
+        }),
+        VimCommand::new(
+            ("on", "ly"),
             workspace::CloseInactiveTabsAndPanes {
                 save_intent: Some(SaveIntent::Close),
+            },
+        )
+        .bang(workspace::CloseInactiveTabsAndPanes {
+            save_intent: Some(SaveIntent::Skip),
+        }),
+        // quickfix / loclist (merged together for now)
+        VimCommand::str(("cl", "ist"), "diagnostics::Deploy", cx),
+        VimCommand::new(("cc", ""), editor::actions::Hover),
+        VimCommand::new(("ll", ""), editor::actions::Hover),
+        VimCommand::new(("cn", "ext"), editor::actions::GoToDiagnostic),
+        VimCommand::new(("lne", "xt"), editor::actions::GoToDiagnostic),
+        VimCommand::new(("cp", "revious"), editor::actions::GoToPrevDiagnostic),
+        VimCommand::new(("cN", "ext"), editor::actions::GoToPrevDiagnostic),
+        VimCommand::new(("lp", "revious"), editor::actions::GoToPrevDiagnostic),
+        VimCommand::new(("lN", "ext"), editor::actions::GoToPrevDiagnostic),
+        // modify the buffer
+        VimCommand::new(("j", "oin"), JoinLines),
+        VimCommand::args(("d", "elete"), |range, bang, args, _| {
+            if bang {
+                return None;
             }
-            .boxed_clone(),
-        ),
-        "on!" | "onl!" | "only!" => (
-            "only!",
-            workspace::CloseInactiveTabsAndPanes {
-                save_intent: Some(SaveIntent::Skip),
+            let (register, count) = parse_register_and_count(args)?;
+            Some(
+                RangeCommand {
+                    range,
+                    command: LineCommand::Delete { register, count },
+                }
+                .boxed_clone(),
+            )
+        }),
+        VimCommand::args(("m", "ove"), |range, bang, args, _| {
+            let destination = parse_destination(args).filter(|_| !bang)?;
+            Some(
+                RangeCommand {
+                    range,
+                    command: LineCommand::Move { destination },
+                }
+                .boxed_clone(),
+            )
+        }),
+        VimCommand::args(("t", ""), |range, bang, args, _| {
+            let destination = parse_destination(args).filter(|_| !bang)?;
+            Some(
+                RangeCommand {
+                    range,
+                    command: LineCommand::Copy { destination },
+                }
+                .boxed_clone(),
+            )
+        }),
+        VimCommand::args(("co", "py"), |range, bang, args, _| {
+            let destination = parse_destination(args).filter(|_| !bang)?;
+            Some(
+                RangeCommand {
+                    range,
+                    command: LineCommand::Copy { destination },
+                }
+                .boxed_clone(),
+            )
+        }),
+        VimCommand::args(("s", "ubstitute"), |range, bang, args, _| {
+            let substitution = Substitution::parse(args).filter(|_| !bang)?;
+            Some(
+                RangeCommand {
+                    range,
+                    command: LineCommand::Substitute(substitution),
+                }
+                .boxed_clone(),
+            )
+        }),
+        VimCommand::args(("g", "lobal"), |range, bang, args, cx| {
+            parse_global(range, bang, args, cx)
+        }),
+        VimCommand::args(("v", "global"), |range, bang, args, cx| {
+            parse_global(range, !bang, args, cx)
+        }),
+        VimCommand::args(("norm", "al"), |range, _, args, _| {
+            if args.is_empty() {
+                return None;
             }
-            .boxed_clone(),
-        ),
-
-        // quickfix / loclist (merged together for now)
-        "cl" | "cli" | "clis" | "clist" => (
-            "clist",
-            cx.build_action("diagnostics::Deploy", None).unwrap(),
-        ),
-        "cc" => ("cc", editor::actions::Hover.boxed_clone()),
-        "ll" => ("ll", editor::actions::Hover.boxed_clone()),
-        "cn" | "cne" | "cnex" | "cnext" => ("cnext", editor::actions::GoToDiagnostic.boxed_clone()),
-        "lne" | "lnex" | "lnext" => ("cnext", editor::actions::GoToDiagnostic.boxed_clone()),
-
-        "cpr" | "cpre" | "cprev" | "cprevi" | "cprevio" | "cpreviou" | "cprevious" => (
-            "cprevious",
-            editor::actions::GoToPrevDiagnostic.boxed_clone(),
-        ),
-        "cN" | "cNe" | "cNex" | "cNext" => {
-            ("cNext", editor::actions::GoToPrevDiagnostic.boxed_clone())
-        }
-        "lp" | "lpr" | "lpre" | "lprev" | "lprevi" | "lprevio" | "lpreviou" | "lprevious" => (
-            "lprevious",
-            editor::actions::GoToPrevDiagnostic.boxed_clone(),
-        ),
-        "lN" | "lNe" | "lNex" | "lNext" => {
-            ("lNext", editor::actions::GoToPrevDiagnostic.boxed_clone())
-        }
-
-        // modify the buffer (should accept [range])
-        "j" | "jo" | "joi" | "join" => ("join", JoinLines.boxed_clone()),
-        "d" | "de" | "del" | "dele" | "delet" | "delete" | "dl" | "dell" | "delel" | "deletl"
-        | "deletel" | "dp" | "dep" | "delp" | "delep" | "deletp" | "deletep" => {
-            ("delete", editor::actions::DeleteLine.boxed_clone())
-        }
-        "sor" | "sor " | "sort" | "sort " => ("sort", SortLinesCaseSensitive.boxed_clone()),
-        "sor i" | "sort i" => ("sort i", SortLinesCaseInsensitive.boxed_clone()),
-
+            Some(
+                RangeCommand {
+                    range,
+                    command: LineCommand::Normal {
+                        keystrokes: args.to_string(),
+                    },
+                }
+                .boxed_clone(),
+            )
+        }),
+        VimCommand::args(("sor", "t"), |range, _, args, _| {
+            if range.is_some() {
+                return None;
+            }
+            match args.trim_end() {
+                "" => Some(SortLinesCaseSensitive.boxed_clone()),
+                "i" => Some(SortLinesCaseInsensitive.boxed_clone()),
+                _ => None,
+            }
+        }),
         // Explore, etc.
-        "E" | "Ex" | "Exp" | "Expl" | "Explo" | "Explor" | "Explore" => (
-            "Explore",
-            cx.build_action("project_panel::ToggleFocus", None).unwrap(),
-        ),
-        "H" | "He" | "Hex" | "Hexp" | "Hexpl" | "Hexplo" | "Hexplor" | "Hexplore" => (
-            "Hexplore",
-            cx.build_action("project_panel::ToggleFocus", None).unwrap(),
-        ),
-        "L" | "Le" | "Lex" | "Lexp" | "Lexpl" | "Lexplo" | "Lexplor" | "Lexplore" => (
-            "Lexplore",
-            cx.build_action("project_panel::ToggleFocus", None).unwrap(),
-        ),
-        "S" | "Se" | "Sex" | "Sexp" | "Sexpl" | "Sexplo" | "Sexplor" | "Sexplore" => (
-            "Sexplore",
-            cx.build_action("project_panel::ToggleFocus", None).unwrap(),
-        ),
-        "Ve" | "Vex" | "Vexp" | "Vexpl" | "Vexplo" | "Vexplor" | "Vexplore" => (
-            "Vexplore",
-            cx.build_action("project_panel::ToggleFocus", None).unwrap(),
-        ),
-        "te" | "ter" | "term" => (
-            "term",
-            cx.build_action("terminal_panel::ToggleFocus", None)
-                .unwrap(),
-        ),
+        VimCommand::str(("E", "xplore"), "project_panel::ToggleFocus", cx),
+        VimCommand::str(("H", "explore"), "project_panel::ToggleFocus", cx),
+        VimCommand::str(("L", "explore"), "project_panel::ToggleFocus", cx),
+        VimCommand::str(("S", "explore"), "project_panel::ToggleFocus", cx),
+        VimCommand::str(("Ve", "xplore"), "project_panel::ToggleFocus", cx),
+        VimCommand::str(("te", "rm"), "terminal_panel::ToggleFocus", cx),
         // Zed panes
-        "T" | "Te" | "Ter" | "Term" => (
-            "Term",
-            cx.build_action("terminal_panel::ToggleFocus", None)
-                .unwrap(),
-        ),
-        "C" | "Co" | "Col" | "Coll" | "Colla" | "Collab" => (
-            "Collab",
-            cx.build_action("collab_panel::ToggleFocus", None).unwrap(),
-        ),
-        "Ch" | "Cha" | "Chat" => (
-            "Chat",
-            cx.build_action("chat_panel::ToggleFocus", None).unwrap(),
-        ),
-        "No" | "Not" | "Noti" | "Notif" | "Notifi" | "Notific" | "Notifica" | "Notificat"
-        | "Notificati" | "Notificatio" | "Notification" => (
-            "Notifications",
-            cx.build_action("notification_panel::ToggleFocus", None)
-                .unwrap(),
-        ),
-        "A" | "AI" | "Ai" => (
-            "AI",
-            cx.build_action("assistant::ToggleFocus", None).unwrap(),
-        ),
+        VimCommand::str(("T", "erm"), "terminal_panel::ToggleFocus", cx),
+        VimCommand::str(("C", "ollab"), "collab_panel::ToggleFocus", cx),
+        VimCommand::str(("Ch", "at"), "chat_panel::ToggleFocus", cx),
+        VimCommand::str(("No", "tifications"), "notification_panel::ToggleFocus", cx),
+        VimCommand::str(("A", "I"), "assistant::ToggleFocus", cx),
+    ]
+}
 
-        // goto (other ranges handled under _ => )
-        "$" => ("$", EndOfDocument.boxed_clone()),
-        "%" => ("%", EndOfDocument.boxed_clone()),
-        "0" => ("0", StartOfDocument.boxed_clone()),
-
-        _ => {
-            if query.starts_with('/') || query.starts_with('?') {
-                (
-                    query,
-                    FindCommand {
-                        query: query[1..].to_string(),
-                        backwards: query.starts_with('?'),
-                    }
-                    .boxed_clone(),
-                )
-            } else if query.starts_with('%') {
-                (
-                    query,
-                    ReplaceCommand {
-                        query: query.to_string(),
-                    }
-                    .boxed_clone(),
-                )
-            } else if let Ok(line) = query.parse::<u32>() {
-                (query, GoToLine { line }.boxed_clone())
-            } else if range_regex().is_match(query) {
-                (
-                    query,
-                    ReplaceCommand {
-                        query: query.to_string(),
-                    }
-                    .boxed_clone(),
-                )
-            } else {
-                return None;
+pub fn command_interceptor(mut input: &str, cx: &AppContext) -> Vec<CommandInterceptResult> {
+    while input.starts_with(':') {
+        input = &input[1..];
+    }
+
+    let (range, query) = parse_range(input);
+    let range_text = &input[..input.len() - query.len()];
+    if query.is_empty() {
+        let Some(range) = range else {
+            return Vec::new();
+        };
+        let action = match range {
+            CommandRange {
+                start:
+                    Position {
+                        address: Address::Search { pattern, backwards },
+                        offset: 0,
+                    },
+                end: None,
+            } => FindCommand {
+                query: pattern,
+                backwards,
             }
-        }
-    };
+            .boxed_clone(),
+            range => RangeCommand {
+                range: Some(range),
+                command: LineCommand::GoTo,
+            }
+            .boxed_clone(),
+        };
+        let string = format!(":{input}");
+        let positions = generate_positions(&string, input);
+        return vec![CommandInterceptResult {
+            action,
+            string,
+            positions,
+        }];
+    }
 
-    let string = ":".to_owned() + name;
-    let positions = generate_positions(&string, query);
+    let name_len = query
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(query.len());
+    let (name, rest) = query.split_at(name_len);
+    let (bang, args) = match rest.strip_prefix('!') {
+        Some(args) => (true, args.trim_start()),
+        None => (false, rest.trim_start()),
+    };
 
-    Some(CommandInterceptResult {
-        action,
-        string,
-        positions,
-    })
+    let mut results = Vec::new();
+    for command in commands(cx) {
+        let command_name = command.name();
+        if command.matches(name) {
+            if let Some(action) = command.action(range.clone(), bang, args, cx) {
+                let string = format!(":{range_text}{command_name}{rest}");
+                let positions = generate_positions(&string, input);
+                results.insert(
+                    0,
+                    CommandInterceptResult {
+                        action,
+                        string,
+                        positions,
+                    },
+                );
+            }
+        } else if name.len() > 1 && rest.is_empty() && command_name.starts_with(name) {
+            // Complete the commands that can run without arguments.
+            if let Some(action) = command.action(range.clone(), false, "", cx) {
+                let string = format!(":{range_text}{command_name}");
+                let positions = generate_positions(&string, input);
+                results.push(CommandInterceptResult {
+                    action,
+                    string,
+                    positions,
+                });
+            }
+        }
+    }
+    results
 }
 
 fn generate_positions(string: &str, query: &str) -> Vec<usize> {
@@ -386,12 +605,788 @@ fn generate_positions(string: &str, query: &str) -> Vec<usize> {
     positions
 }
 
+/// Parses the range at the start of the query, returning it along with the rest of the query.
+fn parse_range(query: &str) -> (Option<CommandRange>, &str) {
+    if let Some(rest) = query.strip_prefix('%') {
+        let range = CommandRange {
+            start: Position {
+                address: Address::Line(1),
+                offset: 0,
+            },
+            end: Some(Position {
+                address: Address::LastLine,
+                offset: 0,
+            }),
+        };
+        return (Some(range), rest);
+    }
+
+    let (start, rest) = match parse_position(query) {
+        Some((start, rest)) => (start, rest),
+        None if query.starts_with([',', ';']) => (Position::current_line(), query),
+        None => return (None, query),
+    };
+    let Some(rest) = rest.strip_prefix(',').or_else(|| rest.strip_prefix(';')) else {
+        return (Some(CommandRange { start, end: None }), rest);
+    };
+    let (end, rest) = parse_position(rest).unwrap_or((Position::current_line(), rest));
+    (
+        Some(CommandRange {
+            start,
+            end: Some(end),
+        }),
+        rest,
+    )
+}
+
+fn parse_position(query: &str) -> Option<(Position, &str)> {
+    let mut chars = query.chars();
+    let (address, rest) = match chars.next() {
+        Some('.') => (Some(Address::CurrentLine), &query[1..]),
+        Some('$') => (Some(Address::LastLine), &query[1..]),
+        Some('\'') => match chars.next() {
+            Some(mark) => (Some(Address::Mark(mark)), chars.as_str()),
+            None => return None,
+        },
+        Some(delimiter @ ('/' | '?')) => {
+            let (pattern, rest) = split_at_delimiter(chars.as_str(), delimiter);
+            let address = Address::Search {
+                pattern,
+                backwards: delimiter == '?',
+            };
+            (Some(address), rest)
+        }
+        Some(c) if c.is_ascii_digit() => {
+            let len = query
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(query.len());
+            (
+                Some(Address::Line(query[..len].parse().ok()?)),
+                &query[len..],
+            )
+        }
+        _ => (None, query),
+    };
+
+    let mut offset = 0i32;
+    let mut rest = rest;
+    while let Some(sign) = rest.chars().next().filter(|c| *c == '+' || *c == '-') {
+        rest = &rest[1..];
+        let len = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let amount = if len == 0 {
+            1
+        } else {
+            rest[..len].parse::<i32>().ok()?
+        };
+        rest = &rest[len..];
+        offset = if sign == '+' {
+            offset.checked_add(amount)?
+        } else {
+            offset.checked_sub(amount)?
+        };
+    }
+
+    if address.is_none() && rest.len() == query.len() {
+        return None;
+    }
+    let address = address.unwrap_or(Address::CurrentLine);
+    Some((Position { address, offset }, rest))
+}
+
+/// Parses the destination of `:m` and `:t`, which must be a single line.
+fn parse_destination(args: &str) -> Option<Position> {
+    let (position, rest) = parse_position(args)?;
+    rest.trim().is_empty().then_some(position)
+}
+
+/// Parses the arguments of `:d[elete] [x] [count]`.
+fn parse_register_and_count(args: &str) -> Option<(Option<char>, Option<u32>)> {
+    let mut args = args.trim_end();
+    let mut register = None;
+    if let Some(c) = args.chars().next().filter(|c| !c.is_ascii_digit()) {
+        if !(c.is_ascii_alphabetic() || "\"-_+*".contains(c)) {
+            return None;
+        }
+        register = Some(c).filter(|c| *c != '"');
+        args = args[1..].trim_start();
+    }
+    let count = if args.is_empty() {
+        None
+    } else {
+        Some(args.parse::<u32>().ok().filter(|count| *count > 0)?)
+    };
+    Some((register, count))
+}
+
+fn parse_global(
+    range: Option<CommandRange>,
+    invert: bool,
+    args: &str,
+    cx: &AppContext,
+) -> Option<Box<dyn Action>> {
+    let mut chars = args.chars();
+    let delimiter = chars
+        .next()
+        .filter(|c| !c.is_alphanumeric() && !"\\\"|".contains(*c))?;
+    let (pattern, query) = split_at_delimiter(chars.as_str(), delimiter);
+    let action = command_interceptor(query.trim_start(), cx)
+        .into_iter()
+        .next()?
+        .action;
+    let command = action.as_any().downcast_ref::<RangeCommand>()?.clone();
+    if matches!(command.command, LineCommand::Global { .. }) {
+        return None;
+    }
+    Some(
+        RangeCommand {
+            range,
+            command: LineCommand::Global {
+                pattern,
+                invert,
+                command: Box::new(command),
+            },
+        }
+        .boxed_clone(),
+    )
+}
+
+/// Splits the text at the first unescaped delimiter, unescaping the delimiters before it.
+fn split_at_delimiter(text: &str, delimiter: char) -> (String, &str) {
+    let mut result = String::new();
+    let mut escaped = false;
+    for (ix, c) in text.char_indices() {
+        if escaped {
+            escaped = false;
+            if c != delimiter {
+                result.push('\\');
+            }
+            result.push(c);
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == delimiter {
+            return (result, &text[ix + c.len_utf8()..]);
+        } else {
+            result.push(c);
+        }
+    }
+    if escaped {
+        result.push('\\');
+    }
+    (result, "")
+}
+
+impl Substitution {
+    /// Parses the arguments of `:s/pattern/replacement/flags`.
+    fn parse(args: &str) -> Option<Self> {
+        let mut chars = args.chars();
+        let delimiter = chars
+            .next()
+            .filter(|c| !c.is_alphanumeric() && !"\\\"|".contains(*c))?;
+        let (pattern, rest) = split_at_delimiter(chars.as_str(), delimiter);
+        let (replacement, flags) = split_at_delimiter(rest, delimiter);
+        let mut substitution = Substitution {
+            pattern,
+            replacement,
+            global: false,
+            case_sensitive: true,
+        };
+        for flag in flags.trim().chars() {
+            match flag {
+                'g' => substitution.global = true,
+                'i' => substitution.case_sensitive = false,
+                'I' => substitution.case_sensitive = true,
+                // Confirming each substitution is not supported, substitute them all.
+                'c' | 'e' => {}
+                _ => return None,
+            }
+        }
+        Some(substitution)
+    }
+}
+
+impl Position {
+    fn current_line() -> Self {
+        Self {
+            address: Address::CurrentLine,
+            offset: 0,
+        }
+    }
+
+    /// Returns the line number this position refers to, starting at 1.
+    fn line_number(
+        &self,
+        vim: &Vim,
+        editor: &Editor,
+        last_search: &LastSearch,
+        cx: &AppContext,
+    ) -> Result<u32> {
+        let snapshot = editor.buffer().read(cx).snapshot(cx);
+        let current_row = editor
+            .selections
+            .newest_anchor()
+            .head()
+            .to_point(&snapshot)
+            .row;
+        let last_line = snapshot.max_point().row + 1;
+        let line = match &self.address {
+            Address::Line(line) => *line,
+            Address::CurrentLine => current_row + 1,
+            Address::LastLine => last_line,
+            Address::Mark(name) => {
+                let anchor = vim
+                    .state()
+                    .marks
+                    .get(&name.to_string())
+                    .and_then(|anchors| anchors.first())
+                    .ok_or_else(|| anyhow!("mark not set: {name}"))?;
+                anchor.to_point(&snapshot).row + 1
+            }
+            Address::Search { pattern, backwards } => {
+                let regex = last_search.regex(pattern, true)?;
+                search_row(&snapshot, &regex, *backwards, current_row)
+                    .ok_or_else(|| anyhow!("pattern not found: {pattern}"))?
+                    + 1
+            }
+        };
+        let line = line as i64 + self.offset as i64;
+        if line < 0 || line > last_line as i64 {
+            bail!("invalid range");
+        }
+        Ok(line as u32)
+    }
+}
+
+impl CommandRange {
+    /// The range of the current visual selection, `'<,'>`.
+    fn visual() -> Self {
+        Self {
+            start: Position {
+                address: Address::Mark('<'),
+                offset: 0,
+            },
+            end: Some(Position {
+                address: Address::Mark('>'),
+                offset: 0,
+            }),
+        }
+    }
+
+    /// Returns the rows of the buffer in the range.
+    fn buffer_rows(
+        &self,
+        vim: &Vim,
+        editor: &Editor,
+        last_search: &LastSearch,
+        cx: &AppContext,
+    ) -> Result<RangeInclusive<u32>> {
+        let start = self.start.line_number(vim, editor, last_search, cx)?;
+        let end = match &self.end {
+            Some(end) => end.line_number(vim, editor, last_search, cx)?,
+            None => start,
+        };
+        let (start, end) = if start <= end {
+            (start, end)
+        } else {
+            (end, start)
+        };
+        Ok(start.saturating_sub(1)..=end.saturating_sub(1))
+    }
+}
+
+impl RangeCommand {
+    fn run(&self, last_search: &LastSearch, cx: &mut WindowContext) -> Result<()> {
+        match &self.command {
+            LineCommand::Global {
+                pattern,
+                invert,
+                command,
+            } => self.run_global(pattern, *invert, command, last_search, cx),
+            LineCommand::Normal { keystrokes } => self.run_normal(keystrokes, last_search, cx),
+            command => Vim::update(cx, |vim, cx| {
+                let rows = self
+                    .buffer_rows(vim, last_search, cx)?
+                    .ok_or_else(|| anyhow!("no active editor"))?;
+                vim.switch_mode(Mode::Normal, false, cx);
+                command.run(rows, last_search, vim, cx)
+            }),
+        }
+    }
+
+    /// Returns the rows the command applies to, or `None` if it applies to the cursor only.
+    fn buffer_rows(
+        &self,
+        vim: &mut Vim,
+        last_search: &LastSearch,
+        cx: &mut WindowContext,
+    ) -> Result<Option<RangeInclusive<u32>>> {
+        let range = match &self.range {
+            Some(range) => range.clone(),
+            None if vim.state().mode.is_visual() => CommandRange::visual(),
+            None => match self.command {
+                LineCommand::Global { .. } => CommandRange {
+                    start: Position {
+                        address: Address::Line(1),
+                        offset: 0,
+                    },
+                    end: Some(Position {
+                        address: Address::LastLine,
+                        offset: 0,
+                    }),
+                },
+                LineCommand::Normal { .. } => return Ok(None),
+                _ => CommandRange {
+                    start: Position::current_line(),
+                    end: None,
+                },
+            },
+        };
+        let Some(editor) = vim
+            .active_editor
+            .clone()
+            .and_then(|editor| editor.upgrade())
+        else {
+            bail!("no active editor");
+        };
+        range
+            .buffer_rows(vim, editor.read(cx), last_search, cx)
+            .map(Some)
+    }
+
+    /// Runs the command on each line matching the pattern, as in `:g/pattern/command`.
+    fn run_global(
+        &self,
+        pattern: &str,
+        invert: bool,
+        command: &RangeCommand,
+        last_search: &LastSearch,
+        cx: &mut WindowContext,
+    ) -> Result<()> {
+        let regex = last_search.regex(pattern, true)?;
+        let anchors = Vim::update(cx, |vim, cx| {
+            let rows = self
+                .buffer_rows(vim, last_search, cx)?
+                .ok_or_else(|| anyhow!("no active editor"))?;
+            vim.switch_mode(Mode::Normal, false, cx);
+            vim.update_active_editor(cx, |_, editor, cx| {
+                let snapshot = editor.buffer().read(cx).snapshot(cx);
+                rows.filter(|row| regex.is_match(&line_text(&snapshot, *row)) != invert)
+                    .map(|row| snapshot.anchor_after(Point::new(row, 0)))
+                    .collect::<Vec<_>>()
+            })
+            .ok_or_else(|| anyhow!("no active editor"))
+        })?;
+        for_each_line(anchors, cx, |cx| command.run(last_search, cx))
+    }
+
+    /// Types the keystrokes in normal mode on each line of the range, as in `:normal`.
+    fn run_normal(
+        &self,
+        keystrokes: &str,
+        last_search: &LastSearch,
+        cx: &mut WindowContext,
+    ) -> Result<()> {
+        let keystrokes = keystrokes.chars().map(keystroke_for).collect::<Vec<_>>();
+        let anchors = Vim::update(cx, |vim, cx| {
+            let rows = self.buffer_rows(vim, last_search, cx)?;
+            vim.switch_mode(Mode::Normal, false, cx);
+            anyhow::Ok(rows.and_then(|rows| {
+                vim.update_active_editor(cx, |_, editor, cx| {
+                    let snapshot = editor.buffer().read(cx).snapshot(cx);
+                    rows.map(|row| snapshot.anchor_after(Point::new(row, 0)))
+                        .collect::<Vec<_>>()
+                })
+            }))
+        })?;
+
+        // The focus just moved back from the command palette to the editor,
+        // draw so that the text typed in insert mode is sent to the editor.
+        cx.draw();
+        match anchors {
+            Some(anchors) => for_each_line(anchors, cx, |cx| {
+                dispatch_normal_keystrokes(&keystrokes, cx);
+                Ok(())
+            }),
+            None => {
+                dispatch_normal_keystrokes(&keystrokes, cx);
+                Ok(())
+            }
+        }
+    }
+}
+
+impl LineCommand {
+    fn run(
+        &self,
+        rows: RangeInclusive<u32>,
+        last_search: &LastSearch,
+        vim: &mut Vim,
+        cx: &mut WindowContext,
+    ) -> Result<()> {
+        match self {
+            LineCommand::GoTo => {
+                vim.update_active_editor(cx, |_, editor, cx| {
+                    move_to_first_non_whitespace(editor, *rows.end(), cx)
+                });
+            }
+            LineCommand::Delete { register, count } => {
+                let (start, end) = match count {
+                    Some(count) => (*rows.end(), rows.end() + count - 1),
+                    None => (*rows.start(), *rows.end()),
+                };
+                vim.update_active_editor(cx, |_, editor, cx| {
+                    move_to_first_non_whitespace(editor, start, cx)
+                });
+                vim.update_state(|state| state.selected_register = *register);
+                delete_motion(
+                    vim,
+                    Motion::CurrentLine,
+                    Some((end - start + 1) as usize),
+                    cx,
+                );
+                vim.update_state(|state| state.selected_register.take());
+                vim.update_active_editor(cx, |_, editor, cx| {
+                    move_to_first_non_whitespace(editor, start, cx)
+                });
+            }
+            LineCommand::Move { destination } | LineCommand::Copy { destination } => {
+                let copy = matches!(self, LineCommand::Copy { .. });
+                vim.update_active_editor(cx, |vim, editor, cx| {
+                    let destination = destination.line_number(vim, editor, last_search, cx)?;
+                    move_lines(editor, rows, destination, copy, cx)
+                })
+                .transpose()?;
+            }
+            LineCommand::Substitute(substitution) => {
+                vim.update_active_editor(cx, |_, editor, cx| {
+                    substitution.run(editor, rows, last_search, cx)
+                })
+                .transpose()?;
+            }
+            LineCommand::Global { .. } | LineCommand::Normal { .. } => {
+                bail!("{self:?} cannot be run on its own")
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Substitution {
+    fn run(
+        &self,
+        editor: &mut Editor,
+        rows: RangeInclusive<u32>,
+        last_search: &LastSearch,
+        cx: &mut ViewContext<Editor>,
+    ) -> Result<()> {
+        let regex = last_search.regex(&self.pattern, self.case_sensitive)?;
+        let replacement = vim_replacement(&self.replacement);
+        let snapshot = editor.buffer().read(cx).snapshot(cx);
+        let mut edits = Vec::new();
+        for row in rows {
+            let line = line_text(&snapshot, row);
+            let new_line = if self.global {
+                regex.replace_all(&line, replacement.as_str())
+            } else {
+                regex.replace(&line, replacement.as_str())
+            };
+            if let Cow::Owned(new_line) = new_line {
+                let end = Point::new(row, snapshot.line_len(MultiBufferRow(row)));
+                edits.push((Point::new(row, 0)..end, new_line));
+            }
+        }
+        let Some((last_range, _)) = edits.last() else {
+            bail!("pattern not found: {}", self.pattern);
+        };
+
+        let last_line = snapshot.anchor_before(last_range.start);
+        editor.transact(cx, |editor, cx| editor.edit(edits, cx));
+        let snapshot = editor.buffer().read(cx).snapshot(cx);
+        move_to_first_non_whitespace(editor, last_line.to_point(&snapshot).row, cx);
+        Ok(())
+    }
+}
+
+/// Moves the cursor to each line that still exists and runs the function there,
+/// grouping all edits into a single transaction.
+fn for_each_line(
+    anchors: Vec<Anchor>,
+    cx: &mut WindowContext,
+    mut f: impl FnMut(&mut WindowContext) -> Result<()>,
+) -> Result<()> {
+    let Some(editor) = Vim::read(cx)
+        .active_editor
+        .clone()
+        .and_then(|editor| editor.upgrade())
+    else {
+        bail!("no active editor");
+    };
+    editor.update(cx, |editor, cx| {
+        editor
+            .buffer()
+            .update(cx, |buffer, cx| buffer.start_transaction(cx))
+    });
+    let mut result = Ok(());
+    for anchor in anchors {
+        let moved = editor.update(cx, |editor, cx| {
+            let snapshot = editor.buffer().read(cx).snapshot(cx);
+            if !anchor.is_valid(&snapshot) {
+                return false;
+            }
+            let point = anchor.to_point(&snapshot);
+            editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                s.select_ranges([point..point])
+            });
+            true
+        });
+        if moved {
+            result = f(cx);
+            if result.is_err() {
+                break;
+            }
+        }
+    }
+    editor.update(cx, |editor, cx| {
+        editor
+            .buffer()
+            .update(cx, |buffer, cx| buffer.end_transaction(cx))
+    });
+    result
+}
+
+fn dispatch_normal_keystrokes(keystrokes: &[Keystroke], cx: &mut WindowContext) {
+    for keystroke in keystrokes {
+        let focused = cx.focused();
+        cx.dispatch_keystroke(keystroke.clone());
+        if cx.focused() != focused {
+            cx.draw();
+        }
+    }
+    // Incomplete commands are aborted, and insert or visual mode are left, as if escape was typed.
+    let vim = Vim::read(cx);
+    let finished = vim.state().mode == Mode::Normal && vim.active_operator().is_none();
+    if !finished {
+        cx.dispatch_keystroke(Keystroke::parse("escape").unwrap());
+    }
+}
+
+fn keystroke_for(c: char) -> Keystroke {
+    let key = if c == ' ' {
+        "space".to_string()
+    } else {
+        c.to_lowercase().to_string()
+    };
+    Keystroke {
+        modifiers: Modifiers {
+            shift: c.is_uppercase(),
+            ..Default::default()
+        },
+        key,
+        ime_key: Some(c.to_string()),
+    }
+}
+
+fn move_to_first_non_whitespace(editor: &mut Editor, row: u32, cx: &mut ViewContext<Editor>) {
+    let snapshot = editor.buffer().read(cx).snapshot(cx);
+    let row = row.min(snapshot.max_point().row);
+    let column = snapshot
+        .indent_size_for_line(MultiBufferRow(row))
+        .len
+        .min(snapshot.line_len(MultiBufferRow(row)).saturating_sub(1));
+    let point = Point::new(row, column);
+    editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+        s.select_ranges([point..point])
+    });
+}
+
+/// Moves or copies the lines below the given line number, as in `:m` and `:t`.
+fn move_lines(
+    editor: &mut Editor,
+    rows: RangeInclusive<u32>,
+    destination: u32,
+    copy: bool,
+    cx: &mut ViewContext<Editor>,
+) -> Result<()> {
+    let (start, end) = (*rows.start(), *rows.end());
+    let line_count = end - start + 1;
+    if !copy && destination >= start && destination <= end + 1 {
+        if destination != start && destination != end + 1 {
+            bail!("cannot move a range of lines into itself");
+        }
+        move_to_first_non_whitespace(editor, end, cx);
+        return Ok(());
+    }
+
+    let snapshot = editor.buffer().read(cx).snapshot(cx);
+    let max_row = snapshot.max_point().row;
+    let line_end = |row: u32| Point::new(row, snapshot.line_len(MultiBufferRow(row)));
+    let text = snapshot
+        .text_for_range(Point::new(start, 0)..line_end(end))
+        .collect::<String>();
+
+    let mut edits = Vec::new();
+    if destination == 0 {
+        edits.push((Point::zero()..Point::zero(), format!("{text}\n")));
+    } else {
+        let point = line_end(destination - 1);
+        edits.push((point..point, format!("\n{text}")));
+    }
+    if !copy {
+        let range = if end < max_row {
+            Point::new(start, 0)..Point::new(end + 1, 0)
+        } else if start > 0 {
+            line_end(start - 1)..line_end(end)
+        } else {
+            Point::zero()..line_end(end)
+        };
+        edits.push((range, String::new()));
+    }
+    edits.sort_by_key(|(range, _)| range.start);
+    editor.transact(cx, |editor, cx| editor.edit(edits, cx));
+
+    let last_row = if copy || destination < start {
+        destination + line_count - 1
+    } else {
+        destination - 1
+    };
+    move_to_first_non_whitespace(editor, last_row, cx);
+    Ok(())
+}
+
+fn line_text(snapshot: &MultiBufferSnapshot, row: u32) -> String {
+    let end = Point::new(row, snapshot.line_len(MultiBufferRow(row)));
+    snapshot.text_for_range(Point::new(row, 0)..end).collect()
+}
+
+/// Finds the first row after (or before) the given one matching the regex, wrapping around the buffer.
+fn search_row(
+    snapshot: &MultiBufferSnapshot,
+    regex: &SearchRegex,
+    backwards: bool,
+    from_row: u32,
+) -> Option<u32> {
+    let max_row = snapshot.max_point().row;
+    let mut rows: Box<dyn Iterator<Item = u32>> = if backwards {
+        Box::new((0..from_row).rev().chain((from_row..=max_row).rev()))
+    } else {
+        Box::new((from_row + 1..=max_row).chain(0..=from_row))
+    };
+    rows.find(|row| regex.is_match(&line_text(snapshot, *row)))
+}
+
+/// The query and the options of the search bar, which the patterns of the commands default to and are compiled with.
+#[derive(Debug, Default)]
+struct LastSearch {
+    query: String,
+    fancy_regex: bool,
+}
+
+impl LastSearch {
+    /// Compiles the pattern like the search bar would, an empty pattern repeats the last search.
+    fn regex(&self, pattern: &str, case_sensitive: bool) -> Result<SearchRegex> {
+        let pattern = match (pattern, self.query.as_str()) {
+            ("", "") => bail!("no previous regular expression"),
+            ("", last_search) => last_search,
+            (pattern, _) => pattern,
+        };
+        vim_regex(pattern, case_sensitive, self.fancy_regex)
+    }
+}
+
+// convert a vim pattern into something more usable by zed.
+// we don't attempt to fully convert between the two regex syntaxes,
+// but we do flip \( \) \| \{ \+ and \? to ( ) | { + and ? (and vice-versa),
+// and convert \< and \> to word boundaries so that common idioms work.
+fn vim_regex(pattern: &str, case_sensitive: bool, fancy_regex: bool) -> Result<SearchRegex> {
+    let mut regex = String::new();
+    let mut case_sensitive = case_sensitive;
+    let mut in_count = false;
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(c @ ('(' | ')' | '|' | '+' | '?')) => regex.push(c),
+                Some('{') => {
+                    in_count = true;
+                    regex.push('{');
+                }
+                Some('=') => regex.push('?'),
+                Some('<' | '>') => regex.push_str("\\b"),
+                Some('c') => case_sensitive = false,
+                Some('C') => case_sensitive = true,
+                Some(c) => {
+                    regex.push('\\');
+                    regex.push(c);
+                }
+                None => regex.push_str("\\\\"),
+            },
+            '}' if in_count => {
+                in_count = false;
+                regex.push('}');
+            }
+            '(' | ')' | '|' | '{' | '}' | '+' | '?' => {
+                regex.push('\\');
+                regex.push(c);
+            }
+            c => regex.push(c),
+        }
+    }
+    let query = SearchQuery::regex(
+        regex,
+        false,
+        case_sensitive,
+        false,
+        false,
+        fancy_regex,
+        Vec::new(),
+        Vec::new(),
+    )?;
+    query
+        .search_regex()
+        .cloned()
+        .ok_or_else(|| anyhow!("invalid regular expression: {pattern}"))
+}
+
+// convert \0..\9 and & to groups of the match, and escape the $ that
+// zed's regex engine would otherwise interpret.
+fn vim_replacement(replacement: &str) -> String {
+    let mut result = String::new();
+    let mut chars = replacement.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(digit @ '0'..='9') => {
+                    result.push_str("${");
+                    result.push(digit);
+                    result.push('}');
+                }
+                Some('n' | 'r') => result.push('\n'),
+                Some('t') => result.push('\t'),
+                Some('$') => result.push_str("$$"),
+                Some(c) => result.push(c),
+                None => result.push('\\'),
+            },
+            '&' => result.push_str("${0}"),
+            '$' => result.push_str("$$"),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
 #[cfg(test)]
 mod test {
     use std::path::Path;
 
-    use crate::test::{NeovimBackedTestContext, VimTestContext};
+    use crate::{
+        state::Mode,
+        test::{NeovimBackedTestContext, VimTestContext},
+    };
     use gpui::TestAppContext;
+    use search::{BufferSearchBar, SearchOptions};
+
+    use super::command_interceptor;
     use indoc::indoc;
 
     #[gpui::test]
@@ -476,6 +1471,233 @@ mod test {
                 c"});
     }
 
+    #[gpui::test]
+    async fn test_command_ranges(cx: &mut TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state(indoc! {"
+            ˇa
+            b
+            c
+            d
+            e
+            f"})
+            .await;
+        cx.simulate_shared_keystrokes(": 2 , 3 d enter").await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            a
+            ˇd
+            e
+            f"});
+        cx.simulate_shared_keystrokes(": . , $ - 1 d enter").await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            a
+            ˇf"});
+
+        cx.set_shared_state(indoc! {"
+            ˇa
+            b
+            c
+            d"})
+            .await;
+        cx.simulate_shared_keystrokes("j shift-v j : d enter").await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            a
+            ˇd"});
+
+        cx.set_shared_state(indoc! {"
+            ˇa
+            b
+            c
+            d"})
+            .await;
+        cx.simulate_shared_keystrokes(": / c / d enter").await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            a
+            b
+            ˇd"});
+    }
+
+    #[gpui::test]
+    async fn test_command_substitute(cx: &mut TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state(indoc! {"
+            ˇa a
+            b a a
+            c"})
+            .await;
+        cx.simulate_shared_keystrokes(": % s / a / x enter").await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            x a
+            ˇb x a
+            c"});
+        cx.simulate_shared_keystrokes(": % s / a / y / g enter")
+            .await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            x y
+            ˇb x y
+            c"});
+        cx.simulate_shared_keystrokes(
+            ": 1 s / \\ ( x \\ ) space \\ ( y \\ ) / \\ 2 space \\ 1 enter",
+        )
+        .await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            ˇy x
+            b x y
+            c"});
+    }
+
+    #[gpui::test]
+    async fn test_command_substitute_fancy_regex(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        let search_bar = cx.workspace(|workspace, cx| {
+            workspace
+                .active_pane()
+                .read(cx)
+                .toolbar()
+                .read(cx)
+                .item_of_type::<BufferSearchBar>()
+                .expect("Buffer search bar should be deployed")
+        });
+        cx.update_view(search_bar, |bar, cx| {
+            bar.enable_search_option(SearchOptions::FANCY_REGEX, cx);
+        });
+
+        // backreferences are only supported by the fancy regex engine, enabled in the search bar
+        cx.set_state("ˇfoo bar", Mode::Normal);
+        cx.simulate_keystrokes(": s / \\ ( o \\ ) \\ 1 / 0 enter");
+        cx.assert_state("ˇf0 bar", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_command_global(cx: &mut TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state(indoc! {"
+            ˇa1
+            b
+            a2
+            c
+            a3"})
+            .await;
+        cx.simulate_shared_keystrokes(": g / a / d enter").await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            b
+            ˇc"});
+        cx.simulate_shared_keystrokes(": v / b / d enter").await;
+        cx.shared_state().await.assert_eq("ˇb");
+
+        cx.set_shared_state(indoc! {"
+            ˇa
+            b
+            c"})
+            .await;
+        cx.simulate_shared_keystrokes(": g / ^ / m 0 enter").await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            ˇc
+            b
+            a"});
+    }
+
+    #[gpui::test]
+    async fn test_command_normal(cx: &mut TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state(indoc! {"
+            ˇa
+            b
+            c"})
+            .await;
+        cx.simulate_shared_keystrokes(": % n o r m space shift-a x enter")
+            .await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            ax
+            bx
+            cˇx"});
+        cx.simulate_shared_keystrokes(": g / b / n o r m space d d enter")
+            .await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            ax
+            ˇcx"});
+    }
+
+    #[gpui::test]
+    async fn test_command_move_copy(cx: &mut TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state(indoc! {"
+            ˇa
+            b
+            c
+            d"})
+            .await;
+        cx.simulate_shared_keystrokes(": m $ enter").await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            b
+            c
+            d
+            ˇa"});
+        cx.simulate_shared_keystrokes(": 1 , 2 t 0 enter").await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            b
+            ˇc
+            b
+            c
+            d
+            a"});
+    }
+
+    #[gpui::test]
+    async fn test_command_completions(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        let completions = cx.update(|cx| {
+            command_interceptor("tab", cx)
+                .into_iter()
+                .map(|result| result.string)
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(
+            completions,
+            [
+                ":tabedit",
+                ":tabnew",
+                ":tabnext",
+                ":tabprevious",
+                ":tabNext",
+                ":tabclose",
+                ":tabonly"
+            ]
+        );
+
+        // the command typed comes first
+        let completions = cx.update(|cx| {
+            command_interceptor("tabn", cx)
+                .into_iter()
+                .map(|result| result.string)
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(completions, [":tabnext", ":tabnew"]);
+    }
+
+    #[gpui::test]
+    async fn test_command_edit(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let fs = cx.workspace(|workspace, cx| workspace.project().read(cx).fs().clone());
+        fs.as_fake()
+            .insert_file("/root/dir/other.rs", b"other\n".to_vec())
+            .await;
+
+        cx.simulate_keystrokes(": e space d i r / o t h e r . r s enter");
+        cx.run_until_parked();
+        cx.workspace(|workspace, cx| {
+            let project_path = workspace.active_item(cx).unwrap().project_path(cx).unwrap();
+            assert_eq!(project_path.path.as_ref(), Path::new("dir/other.rs"));
+        });
+    }
+
     #[gpui::test]
     async fn test_command_write(cx: &mut TestAppContext) {
         let mut cx = VimTestContext::new(cx, true).await;
@@ -7,6 +7,7 @@ use crate::{state::Mode, Vim};
 pub struct ModeIndicator {
     pub(crate) mode: Option<Mode>,
     pub(crate) operators: String,
+    pub(crate) recording_register: Option<char>,
     _subscription: Subscription,
 }
 
@@ -17,6 +18,7 @@ impl ModeIndicator {
         let mut this = Self {
             mode: None,
             operators: "".to_string(),
+            recording_register: None,
             _subscription,
         };
         this.update_mode(cx);
@@ -32,8 +34,10 @@ impl ModeIndicator {
         if vim.enabled {
             self.mode = Some(vim.state().mode);
             self.operators = self.current_operators_description(&vim);
+            self.recording_register = vim.workspace_state.recording_register;
         } else {
             self.mode = None;
+            self.recording_register = None;
         }
     }
 
@@ -53,7 +57,11 @@ impl Render for ModeIndicator {
             return div().into_any();
         };
 
-        Label::new(format!("{} -- {} --", self.operators, mode))
+        let recording = self
+            .recording_register
+            .map(|register| format!("recording @{register} "))
+            .unwrap_or_default();
+        Label::new(format!("{}{} -- {} --", recording, self.operators, mode))
             .size(LabelSize::Small)
             .line_height_style(LineHeightStyle::UiLabel)
             .into_any_element()
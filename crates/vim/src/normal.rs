@@ -1,6 +1,6 @@
 mod case;
 mod change;
-mod delete;
+pub(crate) mod delete;
 mod increment;
 pub(crate) mod mark;
 mod paste;
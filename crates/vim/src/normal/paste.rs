@@ -30,7 +30,7 @@ pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>
 fn system_clipboard_is_newer(vim: &Vim, cx: &mut AppContext) -> bool {
     cx.read_from_clipboard().is_some_and(|item| {
         if let Some(last_state) = vim.workspace_state.registers.get(".system.") {
-            last_state != item.text()
+            &last_state.text != item.text()
         } else {
             true
         }
@@ -41,22 +41,36 @@ fn paste(_: &mut Workspace, action: &Paste, cx: &mut ViewContext<Workspace>) {
     Vim::update(cx, |vim, cx| {
         vim.record_current_action(cx);
         let count = vim.take_count(cx).unwrap_or(1);
+        let selected_register = vim.update_state(|state| state.selected_register.take());
         vim.update_active_editor(cx, |vim, editor, cx| {
             let text_layout_details = editor.text_layout_details(cx);
             editor.transact(cx, |editor, cx| {
                 editor.set_clip_at_line_ends(false, cx);
 
                 let (clipboard_text, clipboard_selections): (String, Option<_>) =
-                    if VimSettings::get_global(cx).use_system_clipboard == UseSystemClipboard::Never
-                        || VimSettings::get_global(cx).use_system_clipboard
-                            == UseSystemClipboard::OnYank
-                            && !system_clipboard_is_newer(vim, cx)
+                    if let Some(register) =
+                        selected_register.filter(|register| !matches!(register, '+' | '*'))
+                    {
+                        (
+                            vim.workspace_state
+                                .registers
+                                .get(&register.to_ascii_lowercase().to_string())
+                                .map(|register| register.text.clone())
+                                .unwrap_or_default(),
+                            None,
+                        )
+                    } else if selected_register.is_none()
+                        && (VimSettings::get_global(cx).use_system_clipboard
+                            == UseSystemClipboard::Never
+                            || VimSettings::get_global(cx).use_system_clipboard
+                                == UseSystemClipboard::OnYank
+                                && !system_clipboard_is_newer(vim, cx))
                     {
                         (
                             vim.workspace_state
                                 .registers
                                 .get("\"")
-                                .cloned()
+                                .map(|register| register.text.clone())
                                 .unwrap_or_else(|| "".to_string()),
                             None,
                         )
@@ -606,4 +620,124 @@ mod test {
             three
         "});
     }
+
+    #[gpui::test]
+    async fn test_named_registers(cx: &mut gpui::TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state(indoc! {"
+            The quick ˇbrown
+            fox jumps over
+            the lazy dog"})
+            .await;
+        cx.simulate_shared_keystrokes("\" a y i w j \" b d d").await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            The quick brown
+            the lazy dˇog"});
+        cx.simulate_shared_keystrokes("\" a p").await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            The quick brown
+            the lazy dobrowˇng"});
+        cx.simulate_shared_keystrokes("\" b shift-p").await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            The quick brown
+            ˇfox jumps over
+            the lazy dobrowng"});
+
+        // uppercase registers append
+        cx.simulate_shared_keystrokes("\" shift-a y i w \" a p")
+            .await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            The quick brown
+            fbrownfoˇxox jumps over
+            the lazy dobrowng"});
+    }
+
+    #[gpui::test]
+    async fn test_numbered_registers(cx: &mut gpui::TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state(indoc! {"
+            ˇone
+            two
+            three
+            four"})
+            .await;
+        cx.simulate_shared_keystrokes("d d d d y y \" _ d d").await;
+        cx.shared_state().await.assert_eq("ˇfour");
+        cx.simulate_shared_keystrokes("\" 2 p").await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            four
+            ˇone"});
+        cx.simulate_shared_keystrokes("\" 1 p").await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            four
+            one
+            ˇtwo"});
+        // the black hole register leaves the last yank in place
+        cx.simulate_shared_keystrokes("p").await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            four
+            one
+            two
+            ˇthree"});
+        cx.simulate_shared_keystrokes("\" 0 shift-p").await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            four
+            one
+            two
+            ˇthree
+            three"});
+
+        // small deletions go to "-
+        cx.simulate_shared_keystrokes("x \" - p").await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            four
+            one
+            two
+            hˇtree
+            three"});
+    }
+
+    #[gpui::test]
+    async fn test_system_clipboard_register(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| {
+                s.use_system_clipboard = Some(UseSystemClipboard::Never)
+            });
+        });
+
+        cx.set_state(
+            indoc! {"
+                The quick brown
+                fox jˇumps over
+                the lazy dog"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes("\" + y i w");
+        assert_eq!(
+            cx.read_from_clipboard().map(|item| item.text().clone()),
+            Some("jumps".into())
+        );
+
+        cx.write_to_clipboard(ClipboardItem::new("clipboard".into()));
+        cx.simulate_keystrokes("y i w \" + p");
+        cx.assert_state(
+            indoc! {"
+                The quick brown
+                fox jclipboarˇdumps over
+                the lazy dog"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes("p");
+        cx.assert_state(
+            indoc! {"
+                The quick brown
+                fox jclipboardjumpˇsumps over
+                the lazy dog"},
+            Mode::Normal,
+        );
+    }
 }
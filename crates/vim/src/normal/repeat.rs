@@ -1,14 +1,16 @@
+use std::{mem, sync::Arc};
+
 use crate::{
     insert::NormalBefore,
     motion::Motion,
-    state::{Mode, RecordedSelection, ReplayableAction},
+    state::{Mode, Operator, RecordedSelection, Register, ReplayableAction},
     visual::visual_motion,
     Vim,
 };
-use gpui::{actions, Action, ViewContext, WindowContext};
+use gpui::{actions, Action, Keystroke, Modifiers, ViewContext, WindowContext};
 use workspace::Workspace;
 
-actions!(vim, [Repeat, EndRepeat]);
+actions!(vim, [Repeat, EndRepeat, ToggleRecord]);
 
 fn should_replay(action: &Box<dyn Action>) -> bool {
     // skip so that we don't leave the character palette open
@@ -35,7 +37,7 @@ fn repeatable_insert(action: &ReplayableAction) -> Option<Box<dyn Action>> {
                 None
             }
         }
-        ReplayableAction::Insertion { .. } => None,
+        ReplayableAction::Insertion { .. } | ReplayableAction::Keystroke(_) => None,
     }
 }
 
@@ -48,18 +50,212 @@ pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>
     });
 
     workspace.register_action(|_: &mut Workspace, _: &Repeat, cx| repeat(cx, false));
+
+    workspace.register_action(|_: &mut Workspace, _: &ToggleRecord, cx| {
+        Vim::update(cx, |vim, cx| {
+            if vim.workspace_state.recording_register.take().is_none() {
+                vim.push_operator(Operator::RecordRegister, cx);
+            }
+        })
+    });
+}
+
+/// Starts recording a macro into the given register, appending to its
+/// current content for an uppercase register name.
+pub(crate) fn record_register(register: Arc<str>, cx: &mut WindowContext) {
+    Vim::update(cx, |vim, cx| {
+        vim.clear_operator(cx);
+        let Some(register) = register
+            .chars()
+            .next()
+            .filter(|register| register.is_ascii_alphanumeric() || *register == '"')
+        else {
+            return;
+        };
+
+        let recording_register = register.to_ascii_lowercase();
+        let new_macro = || Register {
+            text: String::new(),
+            recorded_actions: Some(Vec::new()),
+        };
+        let registers = &mut vim.workspace_state.registers;
+        if register.is_ascii_uppercase() {
+            registers
+                .entry(recording_register.to_string())
+                .or_insert_with(new_macro);
+        } else {
+            registers.insert(recording_register.to_string(), new_macro());
+        }
+        vim.workspace_state.recording_register = Some(recording_register);
+    });
+}
+
+/// Replays the macro recorded into the given register, `count` times.
+/// `@` replays the register that was replayed last.
+///
+/// Registers without recorded actions, such as yanked text, get their text replayed as typed keystrokes.
+pub(crate) fn replay_register(register: Arc<str>, cx: &mut WindowContext) {
+    let Some(actions) = Vim::update(cx, |vim, cx| {
+        let count = vim.take_count(cx).unwrap_or(1);
+        vim.clear_operator(cx);
+        let register = match register.chars().next()? {
+            '@' => vim.workspace_state.last_replayed_register?,
+            register => register.to_ascii_lowercase(),
+        };
+        vim.workspace_state.last_replayed_register = Some(register);
+
+        let register = vim.workspace_state.registers.get(&register.to_string())?;
+        let recorded_actions = match &register.recorded_actions {
+            Some(recorded_actions) => recorded_actions.clone(),
+            None => text_keystrokes(&register.text)
+                .map(ReplayableAction::Keystroke)
+                .collect(),
+        };
+        let mut actions = Vec::with_capacity(recorded_actions.len() * count);
+        for _ in 0..count {
+            actions.extend(recorded_actions.iter().cloned());
+        }
+        Some(actions)
+    }) else {
+        return;
+    };
+
+    replay(actions, cx);
+}
+
+/// The text of the keystroke in the register a macro is recorded into: the character it types,
+/// a control character for the keys that have one, like vim does, or the keystroke within `<>` otherwise.
+pub(crate) fn keystroke_text(keystroke: &Keystroke) -> String {
+    let Modifiers {
+        control,
+        alt,
+        shift,
+        platform,
+        function,
+    } = keystroke.modifiers;
+    if !control && !alt && !platform && !function {
+        if let Some(ime_key) = &keystroke.ime_key {
+            return ime_key.clone();
+        }
+        match keystroke.key.as_str() {
+            "escape" => return "\x1b".to_string(),
+            "enter" => return "\r".to_string(),
+            "tab" => return "\t".to_string(),
+            "space" => return " ".to_string(),
+            key if key.chars().count() == 1 => {
+                return if shift {
+                    key.to_uppercase()
+                } else {
+                    key.to_string()
+                };
+            }
+            _ => {}
+        }
+    }
+    if control && !alt && !shift && !platform && !function {
+        if let [key @ b'a'..=b'z'] = keystroke.key.as_bytes() {
+            return char::from(key - b'a' + 1).to_string();
+        }
+    }
+
+    let mut text = "<".to_string();
+    for (pressed, modifier) in [
+        (control, "ctrl-"),
+        (alt, "alt-"),
+        (shift, "shift-"),
+        (platform, "cmd-"),
+        (function, "fn-"),
+    ] {
+        if pressed {
+            text.push_str(modifier);
+        }
+    }
+    text.push_str(&keystroke.key);
+    text.push('>');
+    text
+}
+
+/// The keys [`keystroke_text`] writes within `<>`, when pressed without modifiers.
+const NAMED_KEYS: &[&str] = &[
+    "backspace",
+    "delete",
+    "up",
+    "down",
+    "left",
+    "right",
+    "home",
+    "end",
+    "pageup",
+    "pagedown",
+    "f1",
+    "f2",
+    "f3",
+    "f4",
+    "f5",
+    "f6",
+    "f7",
+    "f8",
+    "f9",
+    "f10",
+    "f11",
+    "f12",
+];
+
+/// The keystrokes typing the text of a register, the inverse of [`keystroke_text`].
+fn text_keystrokes(text: &str) -> impl Iterator<Item = Keystroke> + '_ {
+    let mut text = text;
+    std::iter::from_fn(move || {
+        if let Some((keystroke, rest)) = text
+            .strip_prefix('<')
+            .and_then(|rest| rest.split_once('>'))
+            .and_then(|(keystroke, rest)| Some((Keystroke::parse(keystroke).ok()?, rest)))
+            .filter(|(keystroke, _)| {
+                keystroke.modifiers.modified() || NAMED_KEYS.contains(&keystroke.key.as_str())
+            })
+        {
+            text = rest;
+            return Some(keystroke);
+        }
+        let c = text.chars().next()?;
+        text = &text[c.len_utf8()..];
+
+        let key = |key: &str| Keystroke {
+            key: key.to_string(),
+            ..Default::default()
+        };
+        Some(match c {
+            '\x1b' => key("escape"),
+            '\r' | '\n' => key("enter"),
+            '\t' => key("tab"),
+            ' ' => key("space"),
+            '\x01'..='\x1a' => Keystroke {
+                modifiers: Modifiers {
+                    control: true,
+                    ..Default::default()
+                },
+                key: char::from(c as u8 - 1 + b'a').to_string(),
+                ime_key: None,
+            },
+            c if c.is_uppercase() => Keystroke {
+                modifiers: Modifiers {
+                    shift: true,
+                    ..Default::default()
+                },
+                key: c.to_lowercase().to_string(),
+                ime_key: None,
+            },
+            c => key(&c.to_string()),
+        })
+    })
 }
 
 pub(crate) fn repeat(cx: &mut WindowContext, from_insert_mode: bool) {
-    let Some((mut actions, editor, selection)) = Vim::update(cx, |vim, cx| {
+    let Some((mut actions, selection)) = Vim::update(cx, |vim, cx| {
         let actions = vim.workspace_state.recorded_actions.clone();
-        if actions.is_empty() {
+        if actions.is_empty() || vim.active_editor.is_none() {
             return None;
         }
 
-        let Some(editor) = vim.active_editor.clone() else {
-            return None;
-        };
         let count = vim.take_count(cx);
 
         let selection = vim.workspace_state.recorded_selection.clone();
@@ -83,7 +279,7 @@ pub(crate) fn repeat(cx: &mut WindowContext, from_insert_mode: bool) {
             }
         }
 
-        Some((actions, editor, selection))
+        Some((actions, selection))
     }) else {
         return;
     };
@@ -165,40 +361,77 @@ pub(crate) fn repeat(cx: &mut WindowContext, from_insert_mode: bool) {
         actions = new_actions;
     }
 
+    actions.push(ReplayableAction::Action(EndRepeat.boxed_clone()));
     Vim::update(cx, |vim, _| vim.workspace_state.replaying = true);
+    replay(actions, cx);
+}
+
+/// Replays the given actions in the active editor.
+///
+/// Actions replayed while another replay is running, like a `.` or an `@` within a macro,
+/// are queued ahead of the rest of that replay so that everything runs in order.
+fn replay(actions: Vec<ReplayableAction>, cx: &mut WindowContext) {
+    let Some(editor) = Vim::update(cx, |vim, _| {
+        let editor = vim.active_editor.clone()?;
+        let state = &mut vim.workspace_state;
+        state.replay_queue.splice(0..0, actions);
+        if mem::replace(&mut state.replay_running, true) {
+            return None;
+        }
+        Some(editor)
+    }) else {
+        return;
+    };
+
     let window = cx.window_handle();
     cx.spawn(move |mut cx| async move {
-        editor.update(&mut cx, |editor, _| {
-            editor.show_local_selections = false;
-        })?;
-        for action in actions {
-            if !matches!(
-                cx.update(|cx| Vim::read(cx).workspace_state.replaying),
-                Ok(true)
-            ) {
-                break;
-            }
-
-            match action {
-                ReplayableAction::Action(action) => {
-                    if should_replay(&action) {
-                        window.update(&mut cx, |_, cx| cx.dispatch_action(action))
-                    } else {
-                        Ok(())
+        let result = async {
+            editor.update(&mut cx, |editor, _| {
+                editor.show_local_selections = false;
+            })?;
+            while let Some(action) = cx.update(|cx| {
+                Vim::update(cx, |vim, _| {
+                    let queue = &mut vim.workspace_state.replay_queue;
+                    (!queue.is_empty()).then(|| queue.remove(0))
+                })
+            })? {
+                match action {
+                    ReplayableAction::Action(action) => {
+                        if should_replay(&action) {
+                            window.update(&mut cx, |_, cx| cx.dispatch_action(action))
+                        } else {
+                            Ok(())
+                        }
                     }
-                }
-                ReplayableAction::Insertion {
-                    text,
-                    utf16_range_to_replace,
-                } => editor.update(&mut cx, |editor, cx| {
-                    editor.replay_insert_event(&text, utf16_range_to_replace.clone(), cx)
-                }),
-            }?
+                    ReplayableAction::Insertion {
+                        text,
+                        utf16_range_to_replace,
+                    } => editor.update(&mut cx, |editor, cx| {
+                        editor.replay_insert_event(&text, utf16_range_to_replace.clone(), cx)
+                    }),
+                    ReplayableAction::Keystroke(keystroke) => window.update(&mut cx, |_, cx| {
+                        cx.dispatch_keystroke(keystroke);
+                    }),
+                }?
+            }
+            editor.update(&mut cx, |editor, _| {
+                editor.show_local_selections = true;
+            })
         }
-        editor.update(&mut cx, |editor, _| {
-            editor.show_local_selections = true;
+        .await;
+
+        let interrupted_repeat = cx.update(|cx| {
+            Vim::update(cx, |vim, _| {
+                vim.workspace_state.replay_queue.clear();
+                vim.workspace_state.replay_running = false;
+                vim.workspace_state.replaying
+            })
         })?;
-        window.update(&mut cx, |_, cx| cx.dispatch_action(EndRepeat.boxed_clone()))
+        // A `.` whose replay got stopped still has to end.
+        if interrupted_repeat {
+            window.update(&mut cx, |_, cx| cx.dispatch_action(EndRepeat.boxed_clone()))?;
+        }
+        result
     })
     .detach_and_log_err(cx);
 }
@@ -497,4 +730,95 @@ mod test {
         cx.simulate_shared_keystrokes(".").await;
         cx.shared_state().await.assert_eq("ˇx hello\n");
     }
+
+    #[gpui::test]
+    async fn test_record_replay(cx: &mut gpui::TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state(indoc! {"
+            ˇhello world
+            hello world
+            hello world
+            hello world"})
+            .await;
+        cx.simulate_shared_keystrokes("q a d w j q").await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            world
+            ˇhello world
+            hello world
+            hello world"});
+        cx.simulate_shared_keystrokes("@ a").await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            world
+            world
+            ˇhello world
+            hello world"});
+        cx.simulate_shared_keystrokes("@ @").await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            world
+            world
+            world
+            ˇhello world"});
+    }
+
+    #[gpui::test]
+    async fn test_record_replay_count(cx: &mut gpui::TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state(indoc! {"
+            ˇone
+            two
+            three
+            four
+            five"})
+            .await;
+        cx.simulate_shared_keystrokes("q c shift-a ! escape j q")
+            .await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            one!
+            twˇo
+            three
+            four
+            five"});
+        cx.simulate_shared_keystrokes("2 @ c").await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            one!
+            two!
+            three!
+            fouˇr
+            five"});
+        cx.simulate_shared_keystrokes("@ @").await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            one!
+            two!
+            three!
+            four!
+            fivˇe"});
+    }
+
+    #[gpui::test]
+    async fn test_record_replay_dot(cx: &mut gpui::TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        // `.` within a macro repeats the change made within the macro
+        cx.set_shared_state("ˇa b c d e f").await;
+        cx.simulate_shared_keystrokes("q b x l . l q").await;
+        cx.shared_state().await.assert_eq("  ˇc d e f");
+        cx.simulate_shared_keystrokes("@ b").await;
+        cx.shared_state().await.assert_eq("    ˇe f");
+    }
+
+    #[gpui::test]
+    async fn test_record_replay_registers(cx: &mut gpui::TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        // macros are pasted as text, and yanked text gets replayed as keystrokes
+        cx.set_shared_state("ˇabc def").await;
+        cx.simulate_shared_keystrokes("q a x q").await;
+        cx.shared_state().await.assert_eq("ˇbc def");
+        cx.simulate_shared_keystrokes("\" a p").await;
+        cx.shared_state().await.assert_eq("bˇxc def");
+        cx.simulate_shared_keystrokes("\" b y l w @ b").await;
+        cx.shared_state().await.assert_eq("bxc ˇef");
+    }
 }
@@ -1,15 +1,10 @@
-use std::{ops::Range, sync::OnceLock, time::Duration};
-
 use gpui::{actions, impl_actions, ViewContext};
-use language::Point;
-use regex::Regex;
 use search::{buffer_search, BufferSearchBar, SearchOptions};
 use serde_derive::Deserialize;
 use workspace::{searchable::Direction, Workspace};
 
 use crate::{
     motion::{search_motion, Motion},
-    normal::move_cursor,
     state::{Mode, SearchState},
     Vim,
 };
@@ -40,30 +35,8 @@ pub struct FindCommand {
     pub backwards: bool,
 }
 
-#[derive(Debug, Clone, PartialEq, Deserialize)]
-pub struct ReplaceCommand {
-    pub query: String,
-}
-
-#[derive(Debug, Default)]
-struct Replacement {
-    search: String,
-    replacement: String,
-    should_replace_all: bool,
-    is_case_sensitive: bool,
-    range: Option<Range<usize>>,
-}
-
 actions!(vim, [SearchSubmit, MoveToNextMatch, MoveToPrevMatch]);
-impl_actions!(
-    vim,
-    [FindCommand, ReplaceCommand, Search, MoveToPrev, MoveToNext]
-);
-
-static RANGE_REGEX: OnceLock<Regex> = OnceLock::new();
-pub(crate) fn range_regex() -> &'static Regex {
-    RANGE_REGEX.get_or_init(|| Regex::new(r"^(\d+),(\d+)s(.*)").unwrap())
-}
+impl_actions!(vim, [FindCommand, Search, MoveToPrev, MoveToNext]);
 
 pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
     workspace.register_action(move_to_next);
@@ -75,7 +48,6 @@ pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>
     workspace.register_action(search_deploy);
 
     workspace.register_action(find_command);
-    workspace.register_action(replace_command);
 }
 
 fn move_to_next(workspace: &mut Workspace, action: &MoveToNext, cx: &mut ViewContext<Workspace>) {
@@ -332,181 +304,6 @@ fn find_command(workspace: &mut Workspace, action: &FindCommand, cx: &mut ViewCo
     })
 }
 
-fn replace_command(
-    workspace: &mut Workspace,
-    action: &ReplaceCommand,
-    cx: &mut ViewContext<Workspace>,
-) {
-    let replacement = parse_replace_all(&action.query);
-    let pane = workspace.active_pane().clone();
-    let mut editor = Vim::read(cx)
-        .active_editor
-        .as_ref()
-        .and_then(|editor| editor.upgrade());
-    if let Some(range) = &replacement.range {
-        if let Some(editor) = editor.as_mut() {
-            editor.update(cx, |editor, cx| {
-                let snapshot = &editor.snapshot(cx).buffer_snapshot;
-                let range = snapshot
-                    .anchor_before(Point::new(range.start.saturating_sub(1) as u32, 0))
-                    ..snapshot.anchor_before(Point::new(range.end as u32, 0));
-
-                editor.set_search_within_ranges(&[range], cx)
-            })
-        }
-    }
-    pane.update(cx, |pane, cx| {
-        let Some(search_bar) = pane.toolbar().read(cx).item_of_type::<BufferSearchBar>() else {
-            return;
-        };
-        let search = search_bar.update(cx, |search_bar, cx| {
-            if !search_bar.show(cx) {
-                return None;
-            }
-
-            let mut options = SearchOptions::REGEX;
-            if replacement.is_case_sensitive {
-                options.set(SearchOptions::CASE_SENSITIVE, true)
-            }
-            let search = if replacement.search == "" {
-                search_bar.query(cx)
-            } else {
-                replacement.search
-            };
-
-            search_bar.set_replacement(Some(&replacement.replacement), cx);
-            Some(search_bar.search(&search, Some(options), cx))
-        });
-        let Some(search) = search else { return };
-        let search_bar = search_bar.downgrade();
-        cx.spawn(|_, mut cx| async move {
-            search.await?;
-            search_bar.update(&mut cx, |search_bar, cx| {
-                if replacement.should_replace_all {
-                    search_bar.select_last_match(cx);
-                    search_bar.replace_all(&Default::default(), cx);
-                    if let Some(editor) = editor {
-                        cx.spawn(|_, mut cx| async move {
-                            cx.background_executor()
-                                .timer(Duration::from_millis(200))
-                                .await;
-                            editor
-                                .update(&mut cx, |editor, cx| {
-                                    editor.set_search_within_ranges(&[], cx)
-                                })
-                                .ok();
-                        })
-                        .detach();
-                    }
-                    Vim::update(cx, |vim, cx| {
-                        move_cursor(
-                            vim,
-                            Motion::StartOfLine {
-                                display_lines: false,
-                            },
-                            None,
-                            cx,
-                        )
-                    })
-                }
-            })?;
-            anyhow::Ok(())
-        })
-        .detach_and_log_err(cx);
-    })
-}
-
-// convert a vim query into something more usable by zed.
-// we don't attempt to fully convert between the two regex syntaxes,
-// but we do flip \( and \) to ( and ) (and vice-versa) in the pattern,
-// and convert \0..\9 to $0..$9 in the replacement so that common idioms work.
-fn parse_replace_all(query: &str) -> Replacement {
-    let mut chars = query.chars();
-    let mut range = None;
-    let maybe_line_range_and_rest: Option<(Range<usize>, &str)> =
-        range_regex().captures(query).map(|captures| {
-            (
-                captures.get(1).unwrap().as_str().parse().unwrap()
-                    ..captures.get(2).unwrap().as_str().parse().unwrap(),
-                captures.get(3).unwrap().as_str(),
-            )
-        });
-    if maybe_line_range_and_rest.is_some() {
-        let (line_range, rest) = maybe_line_range_and_rest.unwrap();
-        range = Some(line_range);
-        chars = rest.chars();
-    } else if Some('%') != chars.next() || Some('s') != chars.next() {
-        return Replacement::default();
-    }
-
-    let Some(delimiter) = chars.next() else {
-        return Replacement::default();
-    };
-
-    let mut search = String::new();
-    let mut replacement = String::new();
-    let mut flags = String::new();
-
-    let mut buffer = &mut search;
-
-    let mut escaped = false;
-    // 0 - parsing search
-    // 1 - parsing replacement
-    // 2 - parsing flags
-    let mut phase = 0;
-
-    for c in chars {
-        if escaped {
-            escaped = false;
-            if phase == 1 && c.is_digit(10) {
-                buffer.push('$')
-            // unescape escaped parens
-            } else if phase == 0 && c == '(' || c == ')' {
-            } else if c != delimiter {
-                buffer.push('\\')
-            }
-            buffer.push(c)
-        } else if c == '\\' {
-            escaped = true;
-        } else if c == delimiter {
-            if phase == 0 {
-                buffer = &mut replacement;
-                phase = 1;
-            } else if phase == 1 {
-                buffer = &mut flags;
-                phase = 2;
-            } else {
-                break;
-            }
-        } else {
-            // escape unescaped parens
-            if phase == 0 && c == '(' || c == ')' {
-                buffer.push('\\')
-            }
-            buffer.push(c)
-        }
-    }
-
-    let mut replacement = Replacement {
-        search,
-        replacement,
-        should_replace_all: true,
-        is_case_sensitive: true,
-        range,
-    };
-
-    for c in flags.chars() {
-        match c {
-            'g' | 'I' => {}
-            'c' | 'n' => replacement.should_replace_all = false,
-            'i' => replacement.is_case_sensitive = false,
-            _ => {}
-        }
-    }
-
-    replacement
-}
-
 #[cfg(test)]
 mod test {
     use editor::{display_map::DisplayRow, DisplayPoint};
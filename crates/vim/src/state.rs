@@ -4,7 +4,7 @@ use crate::surrounds::SurroundsType;
 use crate::{motion::Motion, object::Object};
 use collections::HashMap;
 use editor::Anchor;
-use gpui::{Action, KeyContext};
+use gpui::{Action, KeyContext, Keystroke};
 use language::{CursorShape, Selection, TransactionId};
 use serde::{Deserialize, Serialize};
 use workspace::searchable::Direction;
@@ -61,6 +61,9 @@ pub enum Operator {
     DeleteSurrounds,
     Mark,
     Jump { line: bool },
+    Register,
+    RecordRegister,
+    ReplayRegister,
 }
 
 #[derive(Default, Clone)]
@@ -74,6 +77,8 @@ pub struct EditorState {
     pub post_count: Option<usize>,
 
     pub operator_stack: Vec<Operator>,
+    /// The register selected with `"` for the next yank, delete or paste
+    pub selected_register: Option<char>,
     pub replacements: Vec<(Range<editor::Anchor>, String)>,
 
     pub marks: HashMap<String, Vec<Anchor>>,
@@ -117,7 +122,42 @@ pub struct WorkspaceState {
     pub recorded_actions: Vec<ReplayableAction>,
     pub recorded_selection: RecordedSelection,
 
-    pub registers: HashMap<String, String>,
+    /// Actions queued to be replayed by `.` or `@`, in order
+    pub replay_queue: Vec<ReplayableAction>,
+    pub replay_running: bool,
+
+    /// The register a macro is being recorded into with `q`
+    pub recording_register: Option<char>,
+    pub last_replayed_register: Option<char>,
+
+    pub registers: HashMap<String, Register>,
+}
+
+/// The content of a register, yanked or deleted into it, or recorded into it as a macro with `q`.
+#[derive(Clone, Debug, Default)]
+pub struct Register {
+    /// The text pasted from the register, the keystrokes of a recorded macro.
+    pub text: String,
+    /// The actions of the macro recorded into the register, replayed by `@` instead of its text.
+    /// They are dropped when the text changes otherwise, and the text gets replayed as keystrokes.
+    pub recorded_actions: Option<Vec<ReplayableAction>>,
+}
+
+impl From<String> for Register {
+    fn from(text: String) -> Self {
+        Self {
+            text,
+            recorded_actions: None,
+        }
+    }
+}
+
+impl WorkspaceState {
+    /// The register a macro is being recorded into with `q`, if any.
+    pub fn recording_register_mut(&mut self) -> Option<&mut Register> {
+        let register = self.recording_register?;
+        self.registers.get_mut(&register.to_string())
+    }
 }
 
 #[derive(Debug)]
@@ -127,6 +167,8 @@ pub enum ReplayableAction {
         text: Arc<str>,
         utf16_range_to_replace: Option<Range<isize>>,
     },
+    /// A keystroke of a register's text, replayed by `@` as if it was typed.
+    Keystroke(Keystroke),
 }
 
 impl Clone for ReplayableAction {
@@ -140,6 +182,7 @@ impl Clone for ReplayableAction {
                 text: text.clone(),
                 utf16_range_to_replace: utf16_range_to_replace.clone(),
             },
+            Self::Keystroke(keystroke) => Self::Keystroke(keystroke.clone()),
         }
     }
 }
@@ -266,6 +309,9 @@ impl Operator {
             Operator::Mark => "m",
             Operator::Jump { line: true } => "'",
             Operator::Jump { line: false } => "`",
+            Operator::Register => "\"",
+            Operator::RecordRegister => "q",
+            Operator::ReplayRegister => "@",
         }
     }
 
@@ -279,7 +325,10 @@ impl Operator {
             | Operator::Replace
             | Operator::AddSurrounds { target: Some(_) }
             | Operator::ChangeSurrounds { .. }
-            | Operator::DeleteSurrounds => &["VimWaiting"],
+            | Operator::DeleteSurrounds
+            | Operator::Register
+            | Operator::RecordRegister
+            | Operator::ReplayRegister => &["VimWaiting"],
             _ => &[],
         }
     }
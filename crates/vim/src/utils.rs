@@ -102,21 +102,66 @@ fn copy_selections_content_internal(
         }
     }
 
+    let selected_register = vim.update_state(|state| state.selected_register.take());
+    if selected_register == Some('_') {
+        return;
+    }
+
     let setting = VimSettings::get_global(cx).use_system_clipboard;
-    if setting == UseSystemClipboard::Always || setting == UseSystemClipboard::OnYank && is_yank {
+    if matches!(selected_register, Some('+' | '*'))
+        || selected_register.is_none()
+            && (setting == UseSystemClipboard::Always
+                || setting == UseSystemClipboard::OnYank && is_yank)
+    {
         cx.write_to_clipboard(ClipboardItem::new(text.clone()).with_metadata(clipboard_selections));
         vim.workspace_state
             .registers
-            .insert(".system.".to_string(), text.clone());
+            .insert(".system.".to_string(), text.clone().into());
     } else {
         vim.workspace_state.registers.insert(
             ".system.".to_string(),
             cx.read_from_clipboard()
                 .map(|item| item.text().clone())
-                .unwrap_or_default(),
+                .unwrap_or_default()
+                .into(),
         );
     }
-    vim.workspace_state.registers.insert("\"".to_string(), text);
+
+    let registers = &mut vim.workspace_state.registers;
+    match selected_register {
+        Some('+' | '*') => {}
+        // Uppercase register names append to the lowercase register.
+        Some(register) if register.is_ascii_uppercase() => {
+            let register = registers
+                .entry(register.to_ascii_lowercase().to_string())
+                .or_default();
+            if linewise && !register.text.is_empty() && !register.text.ends_with('\n') {
+                register.text.push('\n');
+            }
+            register.text.push_str(&text);
+            register.recorded_actions = None;
+            text = register.text.clone();
+        }
+        Some(register) => {
+            registers.insert(register.to_string(), text.clone().into());
+        }
+        None if is_yank => {
+            registers.insert("0".to_string(), text.clone().into());
+        }
+        // Deletions spanning lines shift the numbered registers, smaller ones go to "-.
+        None if text.contains('\n') => {
+            for register in (1..9).rev() {
+                if let Some(deleted_text) = registers.remove(&register.to_string()) {
+                    registers.insert((register + 1).to_string(), deleted_text);
+                }
+            }
+            registers.insert("1".to_string(), text.clone().into());
+        }
+        None => {
+            registers.insert("-".to_string(), text.clone().into());
+        }
+    }
+    registers.insert("\"".to_string(), text.into());
     if !is_yank || vim.state().mode == Mode::Visual {
         return;
     }
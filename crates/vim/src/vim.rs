@@ -35,6 +35,7 @@ use motion::Motion;
 use normal::{
     mark::{create_mark, create_mark_after, create_mark_before},
     normal_replace,
+    repeat::{keystroke_text, record_register, replay_register},
 };
 use replace::multi_replace;
 use schemars::JsonSchema;
@@ -183,6 +184,15 @@ fn observe_keystrokes(keystroke_event: &KeystrokeEvent, cx: &mut WindowContext)
                     vim.workspace_state.stop_recording_after_next_action = false;
                 }
             }
+
+            if let Some(register) = vim.workspace_state.recording_register_mut() {
+                register
+                    .text
+                    .push_str(&keystroke_text(&keystroke_event.keystroke));
+                if let Some(recorded_actions) = &mut register.recorded_actions {
+                    recorded_actions.push(ReplayableAction::Action(action.boxed_clone()));
+                }
+            }
         });
 
         // Keystroke is handled by the vim system, so continue forward
@@ -202,7 +212,10 @@ fn observe_keystrokes(keystroke_event: &KeystrokeEvent, cx: &mut WindowContext)
             | Operator::ChangeSurrounds { .. }
             | Operator::DeleteSurrounds
             | Operator::Mark
-            | Operator::Jump { .. },
+            | Operator::Jump { .. }
+            | Operator::Register
+            | Operator::RecordRegister
+            | Operator::ReplayRegister,
         ) => {}
         Some(_) => {
             vim.clear_operator(cx);
@@ -254,13 +267,19 @@ impl Vim {
                 }
             }
             EditorEvent::InputIgnored { text } => {
+                // Recorded before being handled so that the name of the register
+                // a macro starts being recorded into is not part of the macro.
+                Vim::record_macro_insertion(text, None, cx);
                 Vim::active_editor_input_ignored(text.clone(), cx);
                 Vim::record_insertion(text, None, cx)
             }
             EditorEvent::InputHandled {
                 text,
                 utf16_range_to_replace: range_to_replace,
-            } => Vim::record_insertion(text, range_to_replace.clone(), cx),
+            } => {
+                Vim::record_insertion(text, range_to_replace.clone(), cx);
+                Vim::record_macro_insertion(text, range_to_replace.clone(), cx)
+            }
             EditorEvent::TransactionBegun { transaction_id } => Vim::update(cx, |vim, cx| {
                 vim.transaction_begun(*transaction_id, cx);
             }),
@@ -308,6 +327,28 @@ impl Vim {
         });
     }
 
+    fn record_macro_insertion(
+        text: &Arc<str>,
+        range_to_replace: Option<Range<isize>>,
+        cx: &mut WindowContext,
+    ) {
+        Vim::update(cx, |vim, _| {
+            // Replayed insertions are already recorded as the actions replaying them.
+            if vim.workspace_state.replay_running {
+                return;
+            }
+            if let Some(register) = vim.workspace_state.recording_register_mut() {
+                register.text.push_str(text);
+                if let Some(recorded_actions) = &mut register.recorded_actions {
+                    recorded_actions.push(ReplayableAction::Insertion {
+                        text: text.clone(),
+                        utf16_range_to_replace: range_to_replace,
+                    });
+                }
+            }
+        });
+    }
+
     fn update_active_editor<S>(
         &mut self,
         cx: &mut WindowContext,
@@ -375,8 +416,9 @@ impl Vim {
         }
     }
 
+    /// Drops the actions left to replay, including the rest of any macro being replayed.
     pub fn stop_replaying(&mut self) {
-        self.workspace_state.replaying = false;
+        self.workspace_state.replay_queue.clear();
     }
 
     /// When finishing an action that modifies the buffer, stop recording.
@@ -566,7 +608,10 @@ impl Vim {
 
     fn clear_operator(&mut self, cx: &mut WindowContext) {
         self.take_count(cx);
-        self.update_state(|state| state.operator_stack.clear());
+        self.update_state(|state| {
+            state.operator_stack.clear();
+            state.selected_register.take();
+        });
         self.sync_vim_settings(cx);
     }
 
@@ -574,6 +619,15 @@ impl Vim {
         self.state().operator_stack.last().cloned()
     }
 
+    fn select_register(&mut self, register: Arc<str>, cx: &mut WindowContext) {
+        self.pop_operator(cx);
+        // `""` selects the unnamed register, which is used when no register is selected.
+        let register = register.chars().next().filter(|register| {
+            register.is_ascii_alphanumeric() || matches!(register, '-' | '_' | '+' | '*')
+        });
+        self.update_state(|state| state.selected_register = register);
+    }
+
     fn transaction_begun(&mut self, transaction_id: TransactionId, _: &mut WindowContext) {
         self.update_state(|state| {
             let mode = if (state.mode == Mode::Insert
@@ -735,6 +789,9 @@ impl Vim {
                 normal::mark::create_mark(vim, text, false, cx)
             }),
             Some(Operator::Jump { line }) => normal::mark::jump(text, line, cx),
+            Some(Operator::Register) => Vim::update(cx, |vim, cx| vim.select_register(text, cx)),
+            Some(Operator::RecordRegister) => record_register(text, cx),
+            Some(Operator::ReplayRegister) => replay_register(text, cx),
             _ => match Vim::read(cx).state().mode {
                 Mode::Replace => multi_replace(text, cx),
                 _ => {}
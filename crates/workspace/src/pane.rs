@@ -517,6 +517,11 @@ impl Pane {
         self.should_display_tab_bar = Rc::new(should_display_tab_bar);
     }
 
+    /// The side of the pane, where the item dragged over it would be dropped into a new split.
+    pub fn drag_split_direction(&self) -> Option<SplitDirection> {
+        self.drag_split_direction
+    }
+
     pub fn set_can_split(&mut self, can_split: bool, cx: &mut ViewContext<Self>) {
         self.can_split = can_split;
         cx.notify();
@@ -114,7 +114,36 @@ impl PaneGroup {
         )
     }
 
-    pub(crate) fn panes(&self) -> Vec<&View<Pane>> {
+    /// Renders a group that lives outside of the workspace center, in a dock panel:
+    /// such panes are never followed, and the panel takes care of their zooming.
+    pub fn render_in_dock(
+        &self,
+        project: &Model<Project>,
+        active_pane: &View<Pane>,
+        app_state: &Arc<AppState>,
+        cx: &mut ViewContext<Workspace>,
+    ) -> impl IntoElement {
+        self.render(
+            project,
+            &HashMap::default(),
+            None,
+            active_pane,
+            None,
+            app_state,
+            cx,
+        )
+    }
+
+    pub fn layout(&self) -> PaneGroupLayout {
+        self.root.layout()
+    }
+
+    /// Restores a group from its layout, every axis in it has to have at least one member.
+    pub fn from_layout(layout: PaneGroupLayout) -> Self {
+        Self::with_root(Member::from_layout(layout))
+    }
+
+    pub fn panes(&self) -> Vec<&View<Pane>> {
         let mut panes = Vec::new();
         self.root.collect_panes(&mut panes);
         panes
@@ -125,6 +154,17 @@ impl PaneGroup {
     }
 }
 
+/// A snapshot of the panes' arrangement in a [`PaneGroup`], for the groups persisted outside of the workspace.
+#[derive(Clone, Debug)]
+pub enum PaneGroupLayout {
+    Pane(View<Pane>),
+    Axis {
+        axis: Axis,
+        flexes: Vec<f32>,
+        members: Vec<PaneGroupLayout>,
+    },
+}
+
 #[derive(Clone)]
 pub(crate) enum Member {
     Axis(PaneAxis),
@@ -163,6 +203,35 @@ impl Member {
         }
     }
 
+    fn layout(&self) -> PaneGroupLayout {
+        match self {
+            Member::Axis(axis) => PaneGroupLayout::Axis {
+                axis: axis.axis,
+                flexes: axis.flexes.lock().clone(),
+                members: axis.members.iter().map(Member::layout).collect(),
+            },
+            Member::Pane(pane) => PaneGroupLayout::Pane(pane.clone()),
+        }
+    }
+
+    fn from_layout(layout: PaneGroupLayout) -> Self {
+        match layout {
+            PaneGroupLayout::Pane(pane) => Member::Pane(pane),
+            PaneGroupLayout::Axis {
+                axis,
+                flexes,
+                mut members,
+            } => {
+                if members.len() == 1 {
+                    return Member::from_layout(members.remove(0));
+                }
+                let flexes = Some(flexes).filter(|flexes| flexes.len() == members.len());
+                let members = members.into_iter().map(Member::from_layout).collect();
+                Member::Axis(PaneAxis::load(axis, members, flexes))
+            }
+        }
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn render(
         &self,
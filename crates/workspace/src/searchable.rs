@@ -39,6 +39,11 @@ pub struct SearchOptions {
     pub case: bool,
     pub word: bool,
     pub regex: bool,
+    /// Specifies whether the item supports regexes, which match across lines or use lookaround.
+    pub multiline: bool,
+    pub fancy_regex: bool,
+    /// Specifies whether the item supports structural search, which requires a syntax tree.
+    pub structural: bool,
     /// Specifies whether the item supports search & replace.
     pub replacement: bool,
 }
@@ -51,6 +56,9 @@ pub trait SearchableItem: Item + EventEmitter<SearchEvent> {
             case: true,
             word: true,
             regex: true,
+            multiline: true,
+            fancy_regex: true,
+            structural: true,
             replacement: true,
         }
     }
@@ -1,7 +1,7 @@
 use std::path::PathBuf;
 
 use project::TaskSourceKind;
-use task::{ResolvedTask, TaskContext, TaskTemplate};
+use task::ResolvedTask;
 use ui::{ViewContext, WindowContext};
 
 use crate::Workspace;
@@ -41,27 +41,6 @@ pub fn task_cwd(workspace: &Workspace, cx: &mut WindowContext) -> anyhow::Result
     Ok(cwd.map(|path| path.to_path_buf()))
 }
 
-pub fn schedule_task(
-    workspace: &Workspace,
-    task_source_kind: TaskSourceKind,
-    task_to_resolve: &TaskTemplate,
-    task_cx: &TaskContext,
-    omit_history: bool,
-    cx: &mut ViewContext<'_, Workspace>,
-) {
-    if let Some(spawn_in_terminal) =
-        task_to_resolve.resolve_task(&task_source_kind.to_id_base(), task_cx)
-    {
-        schedule_resolved_task(
-            workspace,
-            task_source_kind,
-            spawn_in_terminal,
-            omit_history,
-            cx,
-        );
-    }
-}
-
 pub fn schedule_resolved_task(
     workspace: &Workspace,
     task_source_kind: TaskSourceKind,
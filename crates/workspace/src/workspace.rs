@@ -4809,8 +4809,13 @@ pub fn create_and_open_local_file(
         let fs = workspace.update(&mut cx, |workspace, _| workspace.app_state().fs.clone())?;
         if !fs.is_file(path).await {
             fs.create_file(path, Default::default()).await?;
-            fs.save(path, &default_content(), Default::default())
-                .await?;
+            fs.save(
+                path,
+                &default_content(),
+                Default::default(),
+                Default::default(),
+            )
+            .await?;
         }
 
         let mut items = workspace
@@ -32,7 +32,8 @@ use ignore::IgnoreStack;
 use itertools::Itertools;
 use language::{
     proto::{deserialize_version, serialize_line_ending, serialize_version},
-    Buffer, Capability, DiagnosticEntry, File as _, LineEnding, PointUtf16, Rope, Unclipped,
+    Buffer, Capability, DiagnosticEntry, Encoding, File as _, LineEnding, LoadedContents,
+    LoadedFile, PointUtf16, Rope, Unclipped,
 };
 use lsp::{DiagnosticSeverity, LanguageServerId};
 use parking_lot::Mutex;
@@ -51,7 +52,7 @@ use std::{
     ffi::OsStr,
     fmt,
     future::Future,
-    mem,
+    io, mem,
     ops::{AddAssign, Deref, DerefMut, Sub},
     path::{Path, PathBuf},
     pin::Pin,
@@ -747,27 +748,143 @@ fn path_matchers(values: Option<&[String]>, context: &'static str) -> Vec<PathMa
         .collect()
 }
 
+/// How many bytes of a large file are read and decoded at once.
+const LARGE_FILE_CHUNK_LEN: usize = 4 * 1024 * 1024;
+
+/// Loads a file that exceeds the large file threshold chunk by chunk, appending every chunk to
+/// the buffer's rope as soon as it's decoded, so that the whole file never has to be held in
+/// memory as bytes and as a string at the same time.
+///
+/// The encoding is guessed from the first chunk only. When a later chunk turns out not to be
+/// valid in it, the file is loaded again as Windows-1252, like smaller files whose contents
+/// are not valid in any other encoding.
+async fn load_large_file(
+    fs: Arc<dyn Fs>,
+    abs_path: PathBuf,
+    len: u64,
+    buffer_id: BufferId,
+    mut progress: watch::Sender<Option<f32>>,
+) -> Result<(text::Buffer, Encoding)> {
+    let mut reader = fs.open_sync(&abs_path).await?;
+    let (text, line_ending, encoding) = match decode_large_file(
+        reader.as_mut(),
+        len,
+        None,
+        &mut progress,
+    )? {
+        Some(decoded) => decoded,
+        None => {
+            log::info!("{abs_path:?} is not valid in the encoding detected from its start, loading it as Windows-1252");
+            let mut reader = fs.open_sync(&abs_path).await?;
+            let encoding = Encoding::windows_1252();
+            decode_large_file(reader.as_mut(), len, Some(encoding), &mut progress)?
+                .ok_or_else(|| anyhow!("contents are not valid {}", encoding.name()))?
+        }
+    };
+    Ok((
+        text::Buffer::new_normalized(0, buffer_id, line_ending, text),
+        encoding,
+    ))
+}
+
+/// Decodes a large file in the given encoding, or in the one detected from its first chunk.
+/// Returns `None` if some chunk is not valid in that encoding, and fails if the file looks binary.
+fn decode_large_file(
+    reader: &mut dyn io::Read,
+    len: u64,
+    mut encoding: Option<Encoding>,
+    progress: &mut watch::Sender<Option<f32>>,
+) -> Result<Option<(Rope, LineEnding, Encoding)>> {
+    let mut chunk = vec![0; LARGE_FILE_CHUNK_LEN];
+    let mut decoder = None;
+    let mut line_ending = None;
+    let mut text = Rope::new();
+    let mut loaded_len = 0;
+    let mut pending_carriage_return = false;
+    *progress.borrow_mut() = Some(0.);
+    loop {
+        let chunk_len = read_chunk(reader, &mut chunk)?;
+        let is_last = chunk_len < chunk.len();
+        let bytes = &chunk[..chunk_len];
+        let encoding = match encoding {
+            Some(encoding) => encoding,
+            None => *encoding.insert(Encoding::detect_from_start(bytes)?),
+        };
+        // Windows-1252 decodes anything, so each chunk is checked not to be binary on its own.
+        if encoding == Encoding::windows_1252() {
+            Encoding::reject_binary(bytes)?;
+        }
+        let Ok(decoded) = decoder
+            .get_or_insert_with(|| encoding.decoder())
+            .decode(bytes, is_last)
+        else {
+            return Ok(None);
+        };
+        let mut chunk_text = String::new();
+        // A "\r\n" line ending may be split across two chunks.
+        if pending_carriage_return {
+            chunk_text.push('\r');
+        }
+        chunk_text.push_str(&decoded);
+        pending_carriage_return = !is_last && chunk_text.ends_with('\r');
+        if pending_carriage_return {
+            chunk_text.pop();
+        }
+        line_ending.get_or_insert_with(|| LineEnding::detect(&chunk_text));
+        LineEnding::normalize(&mut chunk_text);
+        text.push(&chunk_text);
+
+        loaded_len += chunk_len as u64;
+        *progress.borrow_mut() = Some(loaded_len as f32 / len.max(1) as f32);
+        if is_last {
+            break;
+        }
+    }
+
+    Ok(Some((
+        text,
+        line_ending.unwrap_or_default(),
+        encoding.unwrap_or_default(),
+    )))
+}
+
+fn read_chunk(reader: &mut dyn io::Read, chunk: &mut [u8]) -> io::Result<usize> {
+    let mut len = 0;
+    while len < chunk.len() {
+        match reader.read(&mut chunk[len..]) {
+            Ok(0) => break,
+            Ok(read_len) => len += read_len,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(len)
+}
+
 impl LocalWorktree {
     pub fn contains_abs_path(&self, path: &Path) -> bool {
         path.starts_with(&self.abs_path)
     }
 
+    /// Loads the file at the given path into a new buffer.
+    ///
+    /// Files over the `large_file_threshold_mb` setting are loaded in chunks, the share of
+    /// the file loaded so far is reported through `progress`.
     pub fn load_buffer(
         &mut self,
         path: &Path,
+        progress: watch::Sender<Option<f32>>,
         cx: &mut ModelContext<Worktree>,
     ) -> Task<Result<Model<Buffer>>> {
         let path = Arc::from(path);
         let reservation = cx.reserve_model();
         let buffer_id = BufferId::from(reservation.entity_id().as_non_zero_u64());
         cx.spawn(move |this, mut cx| async move {
-            let (file, contents, diff_base) = this
-                .update(&mut cx, |t, cx| t.as_local().unwrap().load(&path, cx))?
+            let (file, text_buffer, diff_base) = this
+                .update(&mut cx, |t, cx| {
+                    t.as_local().unwrap().load(&path, buffer_id, progress, cx)
+                })?
                 .await?;
-            let text_buffer = cx
-                .background_executor()
-                .spawn(async move { text::Buffer::new(0, buffer_id, contents) })
-                .await;
             cx.insert_model(reservation, |_| {
                 Buffer::build(
                     text_buffer,
@@ -799,6 +916,8 @@ impl LocalWorktree {
                     is_local: true,
                     is_deleted: false,
                     is_private: false,
+                    encoding: Encoding::default(),
+                    is_large: false,
                 })),
                 Capability::ReadWrite,
             )
@@ -1068,19 +1187,58 @@ impl LocalWorktree {
     fn load(
         &self,
         path: &Path,
+        buffer_id: BufferId,
+        progress: watch::Sender<Option<f32>>,
         cx: &mut ModelContext<Worktree>,
-    ) -> Task<Result<(File, String, Option<String>)>> {
+    ) -> Task<Result<(File, text::Buffer, Option<String>)>> {
         let path = Arc::from(path);
         let abs_path = self.absolutize(&path);
         let fs = self.fs.clone();
         let entry = self.refresh_entry(path.clone(), None, cx);
+        let large_file_threshold = WorktreeSettings::get(
+            Some(SettingsLocation {
+                worktree_id: cx.handle().entity_id().as_u64() as usize,
+                path: &path,
+            }),
+            cx,
+        )
+        .large_file_threshold();
 
         cx.spawn(|this, mut cx| async move {
             let abs_path = abs_path?;
-            let text = fs.load(&abs_path).await?;
+            let len = fs
+                .metadata(&abs_path)
+                .await
+                .ok()
+                .flatten()
+                .map_or(0, |metadata| metadata.len);
+            let is_large = len > large_file_threshold;
+            let (text_buffer, encoding) = if is_large {
+                cx.background_executor()
+                    .spawn(load_large_file(
+                        fs.clone(),
+                        abs_path.clone(),
+                        len,
+                        buffer_id,
+                        progress,
+                    ))
+                    .await
+            } else {
+                let bytes = fs.load_bytes(&abs_path).await?;
+                cx.background_executor()
+                    .spawn(async move {
+                        let encoding = Encoding::detect(&bytes)?;
+                        let text = encoding.decode(bytes)?;
+                        anyhow::Ok((text::Buffer::new(0, buffer_id, text), encoding))
+                    })
+                    .await
+            }
+            .with_context(|| format!("decoding {abs_path:?}"))?;
             let mut index_task = None;
             let snapshot = this.update(&mut cx, |this, _| this.as_local().unwrap().snapshot())?;
-            if let Some(repo) = snapshot.repository_for_path(&path) {
+            // Large files are not diffed against the index, so there's no need to load it.
+            let repo = snapshot.repository_for_path(&path).filter(|_| !is_large);
+            if let Some(repo) = repo {
                 if let Some(repo_path) = repo.work_directory.relativize(&snapshot, &path).log_err()
                 {
                     if let Some(git_repo) = snapshot.git_repositories.get(&*repo.work_directory) {
@@ -1127,8 +1285,10 @@ impl LocalWorktree {
                         is_local: true,
                         is_deleted: false,
                         is_private: entry.is_private,
+                        encoding,
+                        is_large,
                     },
-                    text,
+                    text_buffer,
                     diff_base,
                 )),
                 None => {
@@ -1151,8 +1311,10 @@ impl LocalWorktree {
                             is_local: true,
                             is_deleted: false,
                             is_private,
+                            encoding,
+                            is_large,
                         },
-                        text,
+                        text_buffer,
                         diff_base,
                     ))
                 }
@@ -1179,7 +1341,12 @@ impl LocalWorktree {
 
         let text = buffer.as_rope().clone();
         let version = buffer.version();
-        let save = self.write_file(path.as_ref(), text, buffer.line_ending(), cx);
+        let encoding = buffer
+            .file()
+            .map(|file| file.encoding())
+            .unwrap_or_default();
+        let buffer_is_large = buffer.file().map_or(false, |file| file.is_large());
+        let save = self.write_file(path.as_ref(), text, buffer.line_ending(), encoding, cx);
         let fs = Arc::clone(&self.fs);
         let abs_path = self.absolutize(&path);
         let is_private = self.snapshot.is_path_private(&path);
@@ -1216,6 +1383,8 @@ impl LocalWorktree {
                     is_local: true,
                     is_deleted: false,
                     is_private: is_dotenv,
+                    encoding,
+                    is_large: buffer_is_large,
                 });
 
                 if let Some(project_id) = project_id {
@@ -1278,8 +1447,13 @@ impl LocalWorktree {
             if is_dir {
                 fs.create_dir(&abs_path?).await
             } else {
-                fs.save(&abs_path?, &Default::default(), Default::default())
-                    .await
+                fs.save(
+                    &abs_path?,
+                    &Default::default(),
+                    Default::default(),
+                    Default::default(),
+                )
+                .await
             }
         });
 
@@ -1318,6 +1492,7 @@ impl LocalWorktree {
         path: impl Into<Arc<Path>>,
         text: Rope,
         line_ending: LineEnding,
+        encoding: Encoding,
         cx: &mut ModelContext<Worktree>,
     ) -> Task<Result<Option<Entry>>> {
         let path: Arc<Path> = path.into();
@@ -1325,7 +1500,7 @@ impl LocalWorktree {
         let fs = self.fs.clone();
         let write = cx
             .background_executor()
-            .spawn(async move { fs.save(&abs_path?, &text, line_ending).await });
+            .spawn(async move { fs.save(&abs_path?, &text, line_ending, encoding).await });
 
         cx.spawn(|this, mut cx| async move {
             write.await?;
@@ -2959,6 +3134,8 @@ pub struct File {
     pub is_local: bool,
     pub is_deleted: bool,
     pub is_private: bool,
+    pub encoding: Encoding,
+    pub is_large: bool,
 }
 
 impl language::File for File {
@@ -3029,12 +3206,22 @@ impl language::File for File {
             path: self.path.to_string_lossy().into(),
             mtime: self.mtime.map(|time| time.into()),
             is_deleted: self.is_deleted,
+            encoding: Some(self.encoding.to_string()),
+            is_large: self.is_large,
         }
     }
 
     fn is_private(&self) -> bool {
         self.is_private
     }
+
+    fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    fn is_large(&self) -> bool {
+        self.is_large
+    }
 }
 
 impl language::LocalFile for File {
@@ -3047,12 +3234,50 @@ impl language::LocalFile for File {
         }
     }
 
-    fn load(&self, cx: &AppContext) -> Task<Result<String>> {
+    fn load(&self, cx: &AppContext) -> Task<Result<LoadedFile>> {
         let worktree = self.worktree.read(cx).as_local().unwrap();
         let abs_path = worktree.absolutize(&self.path);
         let fs = worktree.fs.clone();
-        cx.background_executor()
-            .spawn(async move { fs.load(&abs_path?).await })
+        let large_file_threshold = WorktreeSettings::get(
+            Some(SettingsLocation {
+                worktree_id: self.worktree.entity_id().as_u64() as usize,
+                path: &self.path,
+            }),
+            cx,
+        )
+        .large_file_threshold();
+        let file = self.clone();
+        cx.background_executor().spawn(async move {
+            let abs_path = abs_path?;
+            let len = fs
+                .metadata(&abs_path)
+                .await
+                .ok()
+                .flatten()
+                .map_or(0, |metadata| metadata.len);
+            let is_large = len > large_file_threshold;
+            let contents = if is_large {
+                // The file is decoded in the encoding it was opened with, like smaller files.
+                let mut reader = fs.open_sync(&abs_path).await?;
+                let (mut progress, _) = watch::channel();
+                let (text, line_ending, _) =
+                    decode_large_file(reader.as_mut(), len, Some(file.encoding), &mut progress)?
+                        .ok_or_else(|| anyhow!("contents are not valid {}", file.encoding.name()))
+                        .with_context(|| format!("decoding {abs_path:?}"))?;
+                LoadedContents::Large { text, line_ending }
+            } else {
+                let bytes = fs.load_bytes(&abs_path).await?;
+                LoadedContents::Text(
+                    file.encoding
+                        .decode(bytes)
+                        .with_context(|| format!("decoding {abs_path:?}"))?,
+                )
+            };
+            Ok(LoadedFile {
+                file: Arc::new(File { is_large, ..file }),
+                contents,
+            })
+        })
     }
 
     fn buffer_reloaded(
@@ -3089,6 +3314,8 @@ impl File {
             is_local: true,
             is_deleted: false,
             is_private: entry.is_private,
+            encoding: Encoding::default(),
+            is_large: false,
         })
     }
 
@@ -3115,6 +3342,12 @@ impl File {
             is_local: false,
             is_deleted: proto.is_deleted,
             is_private: false,
+            encoding: proto
+                .encoding
+                .as_deref()
+                .and_then(Encoding::from_name)
+                .unwrap_or_default(),
+            is_large: proto.is_large,
         })
     }
 
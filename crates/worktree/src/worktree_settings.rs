@@ -23,6 +23,18 @@ pub struct WorktreeSettings {
     /// Treat the files matching these globs as `.env` files.
     /// Default: [ "**/.env*" ]
     pub private_files: Option<Vec<String>>,
+
+    /// Open the files larger than this many megabytes in large file mode:
+    /// read-only by default, without syntax highlighting, language servers and git diffs.
+    /// Default: 20
+    pub large_file_threshold_mb: Option<u64>,
+}
+
+impl WorktreeSettings {
+    /// Returns the size in bytes from which files are opened in large file mode.
+    pub fn large_file_threshold(&self) -> u64 {
+        self.large_file_threshold_mb.unwrap_or(20) * 1024 * 1024
+    }
 }
 
 impl Settings for WorktreeSettings {
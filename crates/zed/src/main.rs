@@ -133,6 +133,8 @@ fn init_ui(app_state: Arc<AppState>, cx: &mut AppContext) -> Result<()> {
     editor::init(cx);
     image_viewer::init(cx);
     diagnostics::init(cx);
+    diff_view::init(cx);
+    git_history::init(cx);
 
     audio::init(Assets, cx);
     workspace::init(app_state.clone(), cx);
@@ -142,8 +144,11 @@ fn init_ui(app_state: Arc<AppState>, cx: &mut AppContext) -> Result<()> {
     file_finder::init(cx);
     tab_switcher::init(cx);
     outline::init(cx);
+    bookmarks::init(cx);
     project_symbols::init(cx);
     project_panel::init(Assets, cx);
+    hierarchy_panel::init(cx);
+    git_panel::init(cx);
     tasks_ui::init(cx);
     channel::init(&app_state.client.clone(), app_state.user_store.clone(), cx);
     search::init(cx);
@@ -152,6 +157,7 @@ fn init_ui(app_state: Arc<AppState>, cx: &mut AppContext) -> Result<()> {
 
     journal::init(app_state.clone(), cx);
     language_selector::init(cx);
+    encoding_selector::init(cx);
     theme_selector::init(cx);
     language_tools::init(cx);
     call::init(app_state.client.clone(), app_state.user_store.clone(), cx);
@@ -221,6 +227,7 @@ fn init_ui(app_state: Arc<AppState>, cx: &mut AppContext) -> Result<()> {
     let fs = app_state.fs.clone();
     load_user_themes_in_background(fs.clone(), cx);
     watch_themes(fs.clone(), cx);
+    snippet_provider::init(fs.clone(), cx);
     watch_languages(fs.clone(), app_state.languages.clone(), cx);
     watch_file_types(fs.clone(), cx);
 
@@ -18,6 +18,8 @@ pub use open_listener::*;
 use anyhow::Context as _;
 use assets::Assets;
 use futures::{channel::mpsc, select_biased, StreamExt};
+use git_panel::GitPanel;
+use hierarchy_panel::HierarchyPanel;
 use project::TaskSourceKind;
 use project_panel::ProjectPanel;
 use quick_action_bar::QuickActionBar;
@@ -135,15 +137,21 @@ pub fn initialize_workspace(app_state: Arc<AppState>, cx: &mut AppContext) {
             cx.new_view(|cx| diagnostics::items::DiagnosticIndicator::new(workspace, cx));
         let activity_indicator =
             activity_indicator::ActivityIndicator::new(workspace, app_state.languages.clone(), cx);
+        let background_tasks_indicator =
+            cx.new_view(|cx| terminal_view::BackgroundTasksIndicator::new(workspace, cx));
         let active_buffer_language =
             cx.new_view(|_| language_selector::ActiveBufferLanguage::new(workspace));
+        let active_buffer_encoding =
+            cx.new_view(|_| encoding_selector::ActiveBufferEncoding::new(workspace));
         let vim_mode_indicator = cx.new_view(|cx| vim::ModeIndicator::new(cx));
         let cursor_position =
             cx.new_view(|_| go_to_line::cursor_position::CursorPosition::new(workspace));
         workspace.status_bar().update(cx, |status_bar, cx| {
             status_bar.add_left_item(diagnostic_summary, cx);
             status_bar.add_left_item(activity_indicator, cx);
+            status_bar.add_left_item(background_tasks_indicator, cx);
             status_bar.add_right_item(inline_completion_button, cx);
+            status_bar.add_right_item(active_buffer_encoding, cx);
             status_bar.add_right_item(active_buffer_language, cx);
             status_bar.add_right_item(vim_mode_indicator, cx);
             status_bar.add_right_item(cursor_position, cx);
@@ -185,6 +193,8 @@ pub fn initialize_workspace(app_state: Arc<AppState>, cx: &mut AppContext) {
             let assistant_panel =
                 assistant::AssistantPanel::load(workspace_handle.clone(), cx.clone());
             let project_panel = ProjectPanel::load(workspace_handle.clone(), cx.clone());
+            let hierarchy_panel = HierarchyPanel::load(workspace_handle.clone(), cx.clone());
+            let git_panel = GitPanel::load(workspace_handle.clone(), cx.clone());
             let terminal_panel = TerminalPanel::load(workspace_handle.clone(), cx.clone());
             let channels_panel =
                 collab_ui::collab_panel::CollabPanel::load(workspace_handle.clone(), cx.clone());
@@ -197,6 +207,8 @@ pub fn initialize_workspace(app_state: Arc<AppState>, cx: &mut AppContext) {
 
             let (
                 project_panel,
+                hierarchy_panel,
+                git_panel,
                 terminal_panel,
                 assistant_panel,
                 channels_panel,
@@ -204,6 +216,8 @@ pub fn initialize_workspace(app_state: Arc<AppState>, cx: &mut AppContext) {
                 notification_panel,
             ) = futures::try_join!(
                 project_panel,
+                hierarchy_panel,
+                git_panel,
                 terminal_panel,
                 assistant_panel,
                 channels_panel,
@@ -214,6 +228,8 @@ pub fn initialize_workspace(app_state: Arc<AppState>, cx: &mut AppContext) {
             workspace_handle.update(&mut cx, |workspace, cx| {
                 workspace.add_panel(assistant_panel, cx);
                 workspace.add_panel(project_panel, cx);
+                workspace.add_panel(hierarchy_panel, cx);
+                workspace.add_panel(git_panel, cx);
                 if !workspace.project().read(cx).is_remote() {
                     workspace.add_panel(terminal_panel, cx);
                 }
@@ -2798,6 +2814,7 @@ mod tests {
                 "#
                 .into(),
                 Default::default(),
+                Default::default(),
             )
             .await
             .unwrap();
@@ -2817,6 +2834,7 @@ mod tests {
                 "#
                 .into(),
                 Default::default(),
+                Default::default(),
             )
             .await
             .unwrap();
@@ -2869,6 +2887,7 @@ mod tests {
                 "#
                 .into(),
                 Default::default(),
+                Default::default(),
             )
             .await
             .unwrap();
@@ -2894,6 +2913,7 @@ mod tests {
                 "#
                 .into(),
                 Default::default(),
+                Default::default(),
             )
             .await
             .unwrap();
@@ -2938,6 +2958,7 @@ mod tests {
                 "#
                 .into(),
                 Default::default(),
+                Default::default(),
             )
             .await
             .unwrap();
@@ -2956,6 +2977,7 @@ mod tests {
                 "#
                 .into(),
                 Default::default(),
+                Default::default(),
             )
             .await
             .unwrap();
@@ -3003,6 +3025,7 @@ mod tests {
                 "#
                 .into(),
                 Default::default(),
+                Default::default(),
             )
             .await
             .unwrap();
@@ -3028,6 +3051,7 @@ mod tests {
                 "#
                 .into(),
                 Default::default(),
+                Default::default(),
             )
             .await
             .unwrap();
@@ -3107,6 +3131,10 @@ mod tests {
             project_panel::init_settings(cx);
             collab_ui::init(&app_state, cx);
             project_panel::init((), cx);
+            hierarchy_panel::init(cx);
+            git_panel::init(cx);
+            diff_view::init(cx);
+            git_history::init(cx);
             terminal_view::init(cx);
             assistant::init(app_state.client.clone(), cx);
             tasks_ui::init(cx);